@@ -0,0 +1,133 @@
+//! Register, renew, and resolve on a wired Registry, Resolver, and Registrar.
+
+use registrar::{Registrar, RegistrarClient};
+use registry::{Registry, RegistryClient};
+use resolver::{Resolver, ResolverClient};
+use soroban_sdk::testutils::{Address as _, Ledger as _};
+use soroban_sdk::token::StellarAssetClient;
+use soroban_sdk::{vec, Address, Bytes, Env};
+
+use crate::{Ceiling, Report};
+
+const YEAR: u64 = 365 * 86_400;
+
+#[test]
+fn register_renew_resolve_stay_within_ceilings() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000_000);
+    let admin = Address::generate(&env);
+    let registry = RegistryClient::new(&env, &env.register(Registry, (&admin, &admin)));
+    let resolver = ResolverClient::new(&env, &env.register(Resolver, ()));
+    let registrar = RegistrarClient::new(&env, &env.register(Registrar, ()));
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    let tld = Bytes::from_slice(&env, b"stellar");
+    resolver.init(&registry.address);
+    registrar.init(&registry.address, &tld, &admin, &token);
+    registry.create_tld(&admin, &tld, &registrar.address);
+    registry.set_controller(&registrar.address, &true);
+    let alice = Address::generate(&env);
+    StellarAssetClient::new(&env, &token).mint(&alice, &1_000_000_000_000_000);
+
+    let mut report = Report::default();
+    let label = Bytes::from_slice(&env, b"alice");
+    let secret = Bytes::from_slice(&env, b"secret");
+    let resolver_id = Some(resolver.address.clone());
+    let commitment =
+        registrar.make_commitment(&tld, &label, &alice, &secret, &alice, &resolver_id, &YEAR);
+
+    registrar.commit(&alice, &commitment, &label.len());
+    report.record(
+        &env,
+        "commit",
+        Ceiling {
+            instructions: 690_000,
+            mem_bytes: 100_000,
+            read_entries: 21,
+            write_entries: 4,
+            write_bytes: 560,
+        },
+    );
+
+    env.ledger().set_timestamp(env.ledger().timestamp() + 60);
+    let node = registrar.register(
+        &alice,
+        &tld,
+        &label,
+        &alice,
+        &secret,
+        &resolver_id,
+        &YEAR,
+        &None,
+        &None,
+        &None,
+    );
+    report.record(
+        &env,
+        "register",
+        Ceiling {
+            instructions: 3_450_000,
+            mem_bytes: 650_000,
+            read_entries: 51,
+            write_entries: 26,
+            write_bytes: 2_960,
+        },
+    );
+
+    registrar.renew(&alice, &tld, &label);
+    report.record(
+        &env,
+        "renew",
+        Ceiling {
+            instructions: 1_200_000,
+            mem_bytes: 156_000,
+            read_entries: 25,
+            write_entries: 6,
+            write_bytes: 890,
+        },
+    );
+
+    resolver.set_addr(&alice, &node, &alice);
+    report.record(
+        &env,
+        "set_addr",
+        Ceiling {
+            instructions: 910_000,
+            mem_bytes: 206_000,
+            read_entries: 20,
+            write_entries: 5,
+            write_bytes: 760,
+        },
+    );
+
+    let resolved = resolver.resolve(&vec![&env, tld.clone(), label.clone()]);
+    report.record(
+        &env,
+        "resolve",
+        Ceiling {
+            instructions: 258_000,
+            mem_bytes: 43_000,
+            read_entries: 10,
+            write_entries: 0,
+            write_bytes: 0,
+        },
+    );
+    assert_eq!(resolved, Some(alice.clone()));
+
+    resolver.addr(&node);
+    report.record(
+        &env,
+        "addr",
+        Ceiling {
+            instructions: 119_000,
+            mem_bytes: 21_300,
+            read_entries: 8,
+            write_entries: 0,
+            write_bytes: 0,
+        },
+    );
+
+    report.finish();
+}
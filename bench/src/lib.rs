@@ -0,0 +1,134 @@
+//! Resource benchmarks for the main user flows, with regression ceilings.
+//!
+//! Each benchmark runs a flow on natively registered contracts and reads the host's
+//! invocation metering for it: modelled CPU instructions, memory, ledger entries read and
+//! written, bytes written, event size, and the fee those resources would cost. A flow that
+//! rises above its [`Ceiling`] fails the test, so a storage or layout change that makes
+//! registration or resolution more expensive shows up in `cargo test`.
+//!
+//! Native contracts skip Wasm instantiation and execution, so absolute numbers are lower
+//! than on the network; they are for comparing revisions, not for setting fees. Run with
+//! `--nocapture` to print the table.
+
+use soroban_sdk::Env;
+
+/// Resources metered for the last top-level invocation in an `Env`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Cost {
+    pub instructions: i64,
+    pub mem_bytes: i64,
+    /// Entries read, from memory and disk.
+    pub read_entries: u32,
+    pub write_entries: u32,
+    pub write_bytes: u32,
+    pub events_bytes: u32,
+    /// Fee estimate in stroops at the SDK's snapshot of pubnet rates.
+    pub fee: i64,
+}
+
+impl Cost {
+    pub fn last(env: &Env) -> Self {
+        let estimate = env.cost_estimate();
+        let resources = estimate.resources();
+        Cost {
+            instructions: resources.instructions,
+            mem_bytes: resources.mem_bytes,
+            read_entries: resources.disk_read_entries + resources.memory_read_entries,
+            write_entries: resources.write_entries,
+            write_bytes: resources.write_bytes,
+            events_bytes: resources.contract_events_size_bytes,
+            fee: estimate.fee().total,
+        }
+    }
+}
+
+/// Upper bounds a flow must stay within. Set them about a quarter above the measured cost,
+/// so noise passes and real regressions do not; lower them when a change makes a flow
+/// cheaper.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ceiling {
+    pub instructions: i64,
+    pub mem_bytes: i64,
+    pub read_entries: u32,
+    pub write_entries: u32,
+    pub write_bytes: u32,
+}
+
+impl Ceiling {
+    /// The resources by which `cost` exceeds this ceiling, as `name: cost > ceiling`.
+    pub fn overruns(&self, cost: &Cost) -> Vec<String> {
+        let checks = [
+            ("instructions", cost.instructions, self.instructions),
+            ("mem_bytes", cost.mem_bytes, self.mem_bytes),
+            (
+                "read_entries",
+                cost.read_entries.into(),
+                self.read_entries.into(),
+            ),
+            (
+                "write_entries",
+                cost.write_entries.into(),
+                self.write_entries.into(),
+            ),
+            (
+                "write_bytes",
+                cost.write_bytes.into(),
+                self.write_bytes.into(),
+            ),
+        ];
+        checks
+            .into_iter()
+            .filter(|(_, cost, ceiling)| cost > ceiling)
+            .map(|(name, cost, ceiling)| format!("{name}: {cost} > {ceiling}"))
+            .collect()
+    }
+}
+
+/// Measured flows, printed as a table and checked against their ceilings together, so one
+/// run reports every regression.
+#[derive(Default)]
+pub struct Report {
+    rows: Vec<(&'static str, Cost, Ceiling)>,
+}
+
+impl Report {
+    /// Records the cost of the invocation that just ran in `env` as `flow`.
+    pub fn record(&mut self, env: &Env, flow: &'static str, ceiling: Ceiling) -> Cost {
+        let cost = Cost::last(env);
+        self.rows.push((flow, cost, ceiling));
+        cost
+    }
+
+    /// Prints the table and panics listing every flow over its ceiling.
+    pub fn finish(self) {
+        println!(
+            "{:<16} {:>12} {:>10} {:>6} {:>6} {:>8} {:>8} {:>9}",
+            "flow", "instructions", "mem_bytes", "reads", "writes", "w_bytes", "events", "fee"
+        );
+        let mut failures = Vec::new();
+        for (flow, cost, ceiling) in &self.rows {
+            println!(
+                "{:<16} {:>12} {:>10} {:>6} {:>6} {:>8} {:>8} {:>9}",
+                flow,
+                cost.instructions,
+                cost.mem_bytes,
+                cost.read_entries,
+                cost.write_entries,
+                cost.write_bytes,
+                cost.events_bytes,
+                cost.fee
+            );
+            for overrun in ceiling.overruns(cost) {
+                failures.push(format!("{flow}: {overrun}"));
+            }
+        }
+        assert!(
+            failures.is_empty(),
+            "resource regressions:\n{}",
+            failures.join("\n")
+        );
+    }
+}
+
+#[cfg(test)]
+mod flows;
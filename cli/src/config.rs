@@ -0,0 +1,190 @@
+//! Network, identity, and deployment settings, looked up where `stellar-cli` keeps them so
+//! identities and networks created with `stellar keys` and `stellar network` work unchanged.
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+use ed25519_dalek::SigningKey;
+use stellar_name_service_sdk::xdr::ScAddress;
+use stellar_name_service_sdk::Contracts;
+
+/// RPC endpoint and passphrase of one network.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Network {
+    pub rpc_url: String,
+    pub passphrase: String,
+}
+
+/// Networks known without any configuration, as `(name, rpc_url, passphrase)`. Mainnet has
+/// no public default RPC, so its URL must be configured.
+const BUILTIN_NETWORKS: [(&str, &str, &str); 4] = [
+    (
+        "testnet",
+        "https://soroban-testnet.stellar.org",
+        "Test SDF Network ; September 2015",
+    ),
+    (
+        "futurenet",
+        "https://rpc-futurenet.stellar.org",
+        "Test SDF Future Network ; October 2022",
+    ),
+    (
+        "local",
+        "http://localhost:8000/rpc",
+        "Standalone Network ; February 2017",
+    ),
+    (
+        "mainnet",
+        "",
+        "Public Global Stellar Network ; September 2015",
+    ),
+];
+
+/// Directories holding `stellar-cli` configuration, most specific first: `.stellar` in the
+/// working directory, then `$STELLAR_CONFIG_HOME` or `~/.config/stellar`.
+fn config_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".stellar")];
+    if let Ok(home) = env::var("STELLAR_CONFIG_HOME") {
+        dirs.push(PathBuf::from(home));
+    } else if let Ok(xdg) = env::var("XDG_CONFIG_HOME") {
+        dirs.push(PathBuf::from(xdg).join("stellar"));
+    } else if let Ok(home) = env::var("HOME") {
+        dirs.push(PathBuf::from(home).join(".config").join("stellar"));
+    }
+    dirs
+}
+
+/// Contents of `<kind>/<name>.toml` from the first config directory that has it.
+fn read_config(kind: &str, name: &str) -> Option<String> {
+    config_dirs()
+        .into_iter()
+        .find_map(|dir| fs::read_to_string(dir.join(kind).join(format!("{name}.toml"))).ok())
+}
+
+/// Value of a top-level `key = "value"` line. The files `stellar-cli` writes are flat, so
+/// this is all of TOML they need.
+pub fn toml_string(text: &str, key: &str) -> Option<String> {
+    text.lines().find_map(|line| {
+        let (k, v) = line.split_once('=')?;
+        if k.trim() != key {
+            return None;
+        }
+        Some(v.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Resolves the network from `--rpc-url` and `--network-passphrase` (or `STELLAR_RPC_URL`
+/// and `STELLAR_NETWORK_PASSPHRASE`), falling back to the named network (`--network`,
+/// `STELLAR_NETWORK`, or `testnet`) from the config directories or the built-in list.
+pub fn network(
+    name: Option<&str>,
+    rpc_url: Option<&str>,
+    passphrase: Option<&str>,
+) -> Result<Network, String> {
+    let rpc_url = rpc_url
+        .map(str::to_string)
+        .or_else(|| env::var("STELLAR_RPC_URL").ok());
+    let passphrase = passphrase
+        .map(str::to_string)
+        .or_else(|| env::var("STELLAR_NETWORK_PASSPHRASE").ok());
+    if let (Some(rpc_url), Some(passphrase)) = (&rpc_url, &passphrase) {
+        return Ok(Network {
+            rpc_url: rpc_url.clone(),
+            passphrase: passphrase.clone(),
+        });
+    }
+    let name = name
+        .map(str::to_string)
+        .or_else(|| env::var("STELLAR_NETWORK").ok())
+        .unwrap_or_else(|| "testnet".to_string());
+    let (default_url, default_passphrase) = match read_config("network", &name) {
+        Some(text) => (
+            toml_string(&text, "rpc_url"),
+            toml_string(&text, "network_passphrase"),
+        ),
+        None => {
+            let (_, url, passphrase) = BUILTIN_NETWORKS
+                .iter()
+                .find(|(builtin, _, _)| *builtin == name)
+                .ok_or_else(|| format!("unknown network `{name}`"))?;
+            (
+                Some(url.to_string()).filter(|url| !url.is_empty()),
+                Some(passphrase.to_string()),
+            )
+        }
+    };
+    Ok(Network {
+        rpc_url: rpc_url
+            .or(default_url)
+            .ok_or_else(|| format!("network `{name}` needs --rpc-url"))?,
+        passphrase: passphrase
+            .or(default_passphrase)
+            .ok_or_else(|| format!("network `{name}` needs --network-passphrase"))?,
+    })
+}
+
+/// Signing key for `--source` (or `STELLAR_ACCOUNT`): an `S...` secret seed, or the alias
+/// of a `stellar keys` identity. Identities stored as seed phrases are unlocked with
+/// `stellar keys secret`.
+pub fn signing_key(source: &str) -> Result<SigningKey, String> {
+    if let Ok(key) = stellar_strkey::ed25519::PrivateKey::from_string(source) {
+        return Ok(SigningKey::from_bytes(&key.0));
+    }
+    let text = read_config("identity", source)
+        .ok_or_else(|| format!("no identity `{source}`; create it with `stellar keys generate`"))?;
+    let secret = match toml_string(&text, "secret_key") {
+        Some(secret) => secret,
+        None => {
+            let output = Command::new("stellar")
+                .args(["keys", "secret", source])
+                .output()
+                .map_err(|err| format!("running `stellar keys secret`: {err}"))?;
+            if !output.status.success() {
+                return Err(format!("`stellar keys secret {source}` failed"));
+            }
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+    };
+    stellar_strkey::ed25519::PrivateKey::from_string(&secret)
+        .map(|key| SigningKey::from_bytes(&key.0))
+        .map_err(|_| format!("identity `{source}` holds no valid secret key"))
+}
+
+/// Account address of `key`.
+pub fn account(key: &SigningKey) -> ScAddress {
+    let public = stellar_strkey::ed25519::PublicKey(key.verifying_key().to_bytes());
+    public
+        .to_string()
+        .parse()
+        .expect("strkey of an ed25519 key is a valid account")
+}
+
+/// Parses a `G...` or `C...` strkey.
+pub fn address(strkey: &str) -> Result<ScAddress, String> {
+    strkey
+        .parse()
+        .map_err(|_| format!("`{strkey}` is not a G... or C... address"))
+}
+
+/// Contract ids from `--registry`, `--resolver`, and `--registrar`, or the `REGISTRY_ID`,
+/// `RESOLVER_ID`, and `REGISTRAR_ID` variables the deploy scripts use.
+pub fn contracts(
+    registry: Option<&str>,
+    resolver: Option<&str>,
+    registrar: Option<&str>,
+) -> Result<Contracts, String> {
+    let pick = |flag: Option<&str>, var: &str| -> Result<ScAddress, String> {
+        let id = flag
+            .map(str::to_string)
+            .or_else(|| env::var(var).ok())
+            .ok_or_else(|| format!("missing contract id; pass it or set {var}"))?;
+        address(&id)
+    };
+    Ok(Contracts {
+        registry: pick(registry, "REGISTRY_ID")?,
+        resolver: pick(resolver, "RESOLVER_ID")?,
+        registrar: pick(registrar, "REGISTRAR_ID")?,
+    })
+}
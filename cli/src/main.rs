@@ -0,0 +1,320 @@
+//! `sns`: manage Stellar Name Service names from the command line.
+
+mod config;
+mod rpc;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::process::ExitCode;
+
+use stellar_name_service_sdk::{NameService, Registration};
+
+use crate::rpc::RpcTransport;
+
+const USAGE: &str = "\
+Usage: sns [options] <command> [arguments]
+
+Commands:
+  register <name>               Commit, wait, and register `label.tld`
+      [--owner <address>] [--duration-secs <secs>] [--resolver <C...>] [--secret <hex>]
+  renew <name>                  Extend a registration by the renewal period
+  resolve <name>                Print the address a name resolves to
+  reverse <address>             Print an address's primary name
+  set-text <name> <key> <value> Set a text record on the bundled Resolver
+  namehash <name>               Print a name's namehash (offline)
+
+Options:
+  --network <name>              testnet, futurenet, local, mainnet, or a `stellar network`
+                                alias [env: STELLAR_NETWORK, default: testnet]
+  --rpc-url <url>               [env: STELLAR_RPC_URL]
+  --network-passphrase <text>   [env: STELLAR_NETWORK_PASSPHRASE]
+  --source <identity|S...>      Signing identity from `stellar keys` [env: STELLAR_ACCOUNT]
+  --registry-id <C...>          [env: REGISTRY_ID]
+  --resolver-id <C...>          [env: RESOLVER_ID]
+  --registrar-id <C...>         [env: REGISTRAR_ID]
+  --json                        Print results as one JSON object
+  -h, --help                    Print this help
+";
+
+/// Options every command accepts.
+const GLOBAL_OPTIONS: [&str; 7] = [
+    "network",
+    "rpc-url",
+    "network-passphrase",
+    "source",
+    "registry-id",
+    "resolver-id",
+    "registrar-id",
+];
+
+/// Registration term used when `--duration-secs` is not given: one year.
+const DEFAULT_DURATION_SECS: u64 = 365 * 24 * 60 * 60;
+
+/// Command line split into positional arguments and `--option value` pairs.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Args {
+    positional: Vec<String>,
+    options: BTreeMap<String, String>,
+    json: bool,
+    help: bool,
+}
+
+impl Args {
+    fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Args::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => parsed.json = true,
+                "-h" | "--help" => parsed.help = true,
+                _ => match arg.strip_prefix("--") {
+                    Some(option) => {
+                        let (name, value) = match option.split_once('=') {
+                            Some((name, value)) => (name.to_string(), value.to_string()),
+                            None => (
+                                option.to_string(),
+                                args.next()
+                                    .ok_or_else(|| format!("--{option} needs a value"))?,
+                            ),
+                        };
+                        parsed.options.insert(name, value);
+                    }
+                    None => parsed.positional.push(arg),
+                },
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    /// Rejects options that neither `command` nor the global list knows.
+    fn check_options(&self, command: &[&str]) -> Result<(), String> {
+        match self.options.keys().find(|name| {
+            !GLOBAL_OPTIONS.contains(&name.as_str()) && !command.contains(&name.as_str())
+        }) {
+            Some(name) => Err(format!("unknown option --{name}")),
+            None => Ok(()),
+        }
+    }
+
+    /// The `count` arguments after the command name.
+    fn operands(&self, count: usize) -> Result<&[String], String> {
+        let operands = &self.positional[1..];
+        if operands.len() != count {
+            return Err(format!(
+                "`{}` takes {count} argument(s); see --help",
+                self.positional[0]
+            ));
+        }
+        Ok(operands)
+    }
+}
+
+/// Labelled values a command prints, in order.
+type Output = Vec<(&'static str, String)>;
+
+fn run(args: &Args) -> Result<Output, String> {
+    let command = args.positional[0].as_str();
+    let command_options: &[&str] = match command {
+        "register" => &["owner", "duration-secs", "resolver", "secret"],
+        "renew" | "resolve" | "reverse" | "set-text" | "namehash" => &[],
+        _ => return Err(format!("unknown command `{command}`; see --help")),
+    };
+    args.check_options(command_options)?;
+    if command == "namehash" {
+        let name = &args.operands(1)?[0];
+        let namehash = stellar_name_service_sdk::namehash(name).ok_or("invalid name")?;
+        return Ok(vec![("namehash", encode_hex(&namehash))]);
+    }
+
+    let source = args
+        .option("source")
+        .map(str::to_string)
+        .or_else(|| std::env::var("STELLAR_ACCOUNT").ok());
+    let signer = source.as_deref().map(config::signing_key).transpose()?;
+    let caller = signer.as_ref().map(config::account);
+    let network = config::network(
+        args.option("network"),
+        args.option("rpc-url"),
+        args.option("network-passphrase"),
+    )?;
+    let contracts = config::contracts(
+        args.option("registry-id"),
+        args.option("resolver-id"),
+        args.option("registrar-id"),
+    )?;
+    let sns = NameService::new(RpcTransport::new(&network, signer), contracts);
+    let need_caller = || caller.clone().ok_or("this command needs --source");
+
+    match command {
+        "register" => {
+            let name = &args.operands(1)?[0];
+            let caller = need_caller()?;
+            let owner = match args.option("owner") {
+                Some(owner) => config::address(owner)?,
+                None => caller.clone(),
+            };
+            let duration_secs = match args.option("duration-secs") {
+                Some(secs) => secs
+                    .parse()
+                    .map_err(|_| "--duration-secs must be a number of seconds")?,
+                None => DEFAULT_DURATION_SECS,
+            };
+            let resolver = match args.option("resolver") {
+                Some(resolver) => config::address(resolver)?,
+                None => sns.contracts().resolver.clone(),
+            };
+            let secret = match args.option("secret") {
+                Some(secret) => decode_hex(secret)?,
+                None => random_secret()?,
+            };
+            let namehash = sns
+                .register_flow(&Registration {
+                    caller,
+                    name: name.clone(),
+                    owner: owner.clone(),
+                    secret,
+                    resolver: Some(resolver),
+                    duration_secs,
+                    referrer: None,
+                    voucher: None,
+                    sponsor: None,
+                })
+                .map_err(|err| err.to_string())?;
+            Ok(vec![
+                ("name", name.to_ascii_lowercase()),
+                ("namehash", encode_hex(&namehash)),
+                ("owner", owner.to_string()),
+            ])
+        }
+        "renew" => {
+            let name = &args.operands(1)?[0];
+            sns.renew(&need_caller()?, name)
+                .map_err(|err| err.to_string())?;
+            Ok(vec![("name", name.to_ascii_lowercase())])
+        }
+        "resolve" => {
+            let name = &args.operands(1)?[0];
+            let address = sns.resolve(name).map_err(|err| err.to_string())?;
+            Ok(vec![("address", address.to_string())])
+        }
+        "reverse" => {
+            let address = config::address(&args.operands(1)?[0])?;
+            let name = sns.reverse(&address).map_err(|err| err.to_string())?;
+            Ok(vec![("name", name)])
+        }
+        "set-text" => {
+            let operands = args.operands(3)?;
+            let (name, key, value) = (&operands[0], &operands[1], &operands[2]);
+            sns.set_text(&need_caller()?, name, key, value)
+                .map_err(|err| err.to_string())?;
+            Ok(vec![
+                ("name", name.to_ascii_lowercase()),
+                ("key", key.clone()),
+                ("value", value.clone()),
+            ])
+        }
+        _ => unreachable!("checked above"),
+    }
+}
+
+/// Prints `output`: the bare value when there is one, else `label: value` lines, or a JSON
+/// object with `--json`.
+fn print(output: &Output, json: bool) {
+    if json {
+        let object: serde_json::Map<_, _> = output
+            .iter()
+            .map(|(label, value)| (label.to_string(), value.clone().into()))
+            .collect();
+        println!("{}", serde_json::Value::Object(object));
+    } else if let [(_, value)] = output.as_slice() {
+        println!("{value}");
+    } else {
+        for (label, value) in output {
+            println!("{label}: {value}");
+        }
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let hex = hex.strip_prefix("0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return Err("--secret must be hex".into());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| "--secret must be hex".into()))
+        .collect()
+}
+
+/// 32 bytes from the operating system's random source.
+fn random_secret() -> Result<Vec<u8>, String> {
+    let mut secret = vec![0u8; 32];
+    File::open("/dev/urandom")
+        .and_then(|mut random| random.read_exact(&mut secret))
+        .map_err(|err| format!("reading /dev/urandom: {err}; pass --secret"))?;
+    Ok(secret)
+}
+
+fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+    if args.help || args.positional.is_empty() {
+        print!("{USAGE}");
+        return if args.help {
+            ExitCode::SUCCESS
+        } else {
+            ExitCode::FAILURE
+        };
+    }
+    match run(&args) {
+        Ok(output) => {
+            print(&output, args.json);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            if args.json {
+                println!("{}", serde_json::json!({ "error": err }));
+            } else {
+                eprintln!("error: {err}");
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Args {
+        Args::parse(line.split_whitespace().map(str::to_string)).unwrap()
+    }
+
+    #[test]
+    fn parses_commands_options_and_flags() {
+        let parsed = args("register alice.stellar --owner GABC --duration-secs=60 --json");
+        assert_eq!(parsed.positional, ["register", "alice.stellar"]);
+        assert_eq!(parsed.option("owner"), Some("GABC"));
+        assert_eq!(parsed.option("duration-secs"), Some("60"));
+        assert!(parsed.json);
+        assert!(parsed.check_options(&["owner", "duration-secs"]).is_ok());
+        assert!(parsed.check_options(&[]).is_err());
+        assert!(Args::parse(["--network".to_string()]).is_err());
+        assert_eq!(decode_hex("0x0aff").unwrap(), [0x0a, 0xff]);
+        assert!(decode_hex("abc").is_err());
+    }
+}
@@ -0,0 +1,360 @@
+//! [`Transport`] over Soroban RPC. Requests are JSON-RPC posted through `curl`, and writes
+//! are signed locally with the `--source` key.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signer, SigningKey};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use stellar_name_service_sdk::xdr::{
+    AccountId, DecoratedSignature, Hash, HostFunction, InvokeContractArgs, InvokeHostFunctionOp,
+    LedgerEntryData, LedgerKey, LedgerKeyAccount, Limits, Memo, MuxedAccount, Operation,
+    OperationBody, Preconditions, PublicKey, ReadXdr, ScAddress, ScVal, SequenceNumber,
+    SorobanAuthorizationEntry, SorobanCredentials, SorobanTransactionData, Transaction,
+    TransactionEnvelope, TransactionExt, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, TransactionV1Envelope, Uint256, WriteXdr,
+};
+use stellar_name_service_sdk::Transport;
+
+use crate::config::Network;
+
+/// Inclusion fee offered per transaction, in stroops.
+const BASE_FEE: u32 = 100;
+/// How often and how many times to poll `getTransaction` after submitting.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const POLL_ATTEMPTS: u32 = 30;
+
+pub struct RpcTransport {
+    rpc_url: String,
+    network_id: [u8; 32],
+    signer: Option<SigningKey>,
+}
+
+/// What `simulateTransaction` reports for one invocation.
+struct Simulation {
+    result: ScVal,
+    auth: Vec<SorobanAuthorizationEntry>,
+    data: SorobanTransactionData,
+    min_resource_fee: u32,
+}
+
+impl RpcTransport {
+    pub fn new(network: &Network, signer: Option<SigningKey>) -> Self {
+        Self {
+            rpc_url: network.rpc_url.clone(),
+            network_id: Sha256::digest(network.passphrase.as_bytes()).into(),
+            signer,
+        }
+    }
+
+    fn request(&self, method: &str, params: Value) -> Result<Value, String> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut child = Command::new("curl")
+            .args(["-sS", "-X", "POST", "-H", "Content-Type: application/json"])
+            .args(["--data-binary", "@-", &self.rpc_url])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|err| format!("running curl: {err}"))?;
+        child
+            .stdin
+            .take()
+            .expect("stdin is piped")
+            .write_all(body.to_string().as_bytes())
+            .map_err(|err| format!("writing request: {err}"))?;
+        let output = child
+            .wait_with_output()
+            .map_err(|err| format!("running curl: {err}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "{method}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        let mut response: Value = serde_json::from_slice(&output.stdout)
+            .map_err(|err| format!("{method}: invalid response: {err}"))?;
+        if let Some(error) = response.get("error") {
+            return Err(format!(
+                "{method}: {}",
+                error["message"].as_str().unwrap_or("failed")
+            ));
+        }
+        Ok(response["result"].take())
+    }
+
+    fn account_sequence(&self, account: &[u8; 32]) -> Result<i64, String> {
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: account_id(account),
+        });
+        let result = self.request("getLedgerEntries", json!({ "keys": [to_base64(&key)?] }))?;
+        let entry = result["entries"][0]["xdr"]
+            .as_str()
+            .ok_or("source account not found; fund it first")?;
+        match from_base64::<LedgerEntryData>(entry)? {
+            LedgerEntryData::Account(account) => Ok(account.seq_num.0),
+            _ => Err("getLedgerEntries: not an account entry".into()),
+        }
+    }
+
+    fn simulate_tx(&self, tx: &Transaction) -> Result<Simulation, String> {
+        let envelope = TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx: tx.clone(),
+            signatures: Default::default(),
+        });
+        let result = self.request(
+            "simulateTransaction",
+            json!({ "transaction": to_base64(&envelope)? }),
+        )?;
+        if let Some(error) = result["error"].as_str() {
+            return Err(format!("simulation failed: {error}"));
+        }
+        let invocation = &result["results"][0];
+        let auth = invocation["auth"]
+            .as_array()
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| from_base64(entry.as_str().unwrap_or_default()))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Simulation {
+            result: from_base64(invocation["xdr"].as_str().ok_or("simulation: no result")?)?,
+            auth,
+            data: from_base64(result["transactionData"].as_str().unwrap_or_default())?,
+            min_resource_fee: result["minResourceFee"]
+                .as_str()
+                .and_then(|fee| fee.parse().ok())
+                .ok_or("simulation: no resource fee")?,
+        })
+    }
+
+    fn sign(&self, key: &SigningKey, tx: Transaction) -> Result<TransactionEnvelope, String> {
+        let payload = TransactionSignaturePayload {
+            network_id: Hash(self.network_id),
+            tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(tx.clone()),
+        };
+        let payload = payload
+            .to_xdr(Limits::none())
+            .map_err(|err| format!("encoding transaction: {err}"))?;
+        let signature = key.sign(&Sha256::digest(payload));
+        let public = key.verifying_key().to_bytes();
+        let decorated = DecoratedSignature {
+            hint: public[28..]
+                .try_into()
+                .map(stellar_name_service_sdk::xdr::SignatureHint)
+                .expect("4-byte hint"),
+            signature: stellar_name_service_sdk::xdr::Signature(
+                signature
+                    .to_bytes()
+                    .to_vec()
+                    .try_into()
+                    .expect("64-byte signature"),
+            ),
+        };
+        Ok(TransactionEnvelope::Tx(TransactionV1Envelope {
+            tx,
+            signatures: vec![decorated].try_into().expect("one signature"),
+        }))
+    }
+
+    fn wait_for(&self, hash: &str) -> Result<(), String> {
+        for _ in 0..POLL_ATTEMPTS {
+            let result = self.request("getTransaction", json!({ "hash": hash }))?;
+            match result["status"].as_str() {
+                Some("SUCCESS") => return Ok(()),
+                Some("FAILED") => return Err(format!("transaction {hash} failed")),
+                _ => thread::sleep(POLL_INTERVAL),
+            }
+        }
+        Err(format!("transaction {hash} not confirmed in time"))
+    }
+}
+
+impl Transport for RpcTransport {
+    type Error = String;
+
+    fn simulate(
+        &self,
+        contract: &ScAddress,
+        function: &str,
+        args: &[ScVal],
+    ) -> Result<ScVal, String> {
+        let source = self
+            .signer
+            .as_ref()
+            .map(|key| key.verifying_key().to_bytes())
+            .unwrap_or_default();
+        let tx = transaction(&source, 0, BASE_FEE, contract, function, args)?;
+        Ok(self.simulate_tx(&tx)?.result)
+    }
+
+    /// Simulates, attaches the footprint and source-account authorizations, signs, submits,
+    /// and waits. The result is the simulated one, which the ledger reproduces when the
+    /// transaction succeeds.
+    fn invoke(
+        &self,
+        source: &ScAddress,
+        contract: &ScAddress,
+        function: &str,
+        args: &[ScVal],
+    ) -> Result<ScVal, String> {
+        let key = self.signer.as_ref().ok_or("this command needs --source")?;
+        let account = key.verifying_key().to_bytes();
+        if *source != crate::config::account(key) {
+            return Err("--source does not match the acting account".into());
+        }
+        let seq = self.account_sequence(&account)? + 1;
+        let mut tx = transaction(&account, seq, BASE_FEE, contract, function, args)?;
+        let simulation = self.simulate_tx(&tx)?;
+        if simulation
+            .auth
+            .iter()
+            .any(|entry| !matches!(entry.credentials, SorobanCredentials::SourceAccount))
+        {
+            return Err("the call needs authorization from another account".into());
+        }
+        let mut operations = tx.operations.to_vec();
+        if let OperationBody::InvokeHostFunction(op) = &mut operations[0].body {
+            op.auth = simulation
+                .auth
+                .try_into()
+                .map_err(|_| "too many authorizations")?;
+        }
+        tx.operations = operations.try_into().expect("one operation");
+        tx.fee = BASE_FEE.saturating_add(simulation.min_resource_fee);
+        tx.ext = TransactionExt::V1(simulation.data);
+        let envelope = self.sign(key, tx)?;
+        let result = self.request(
+            "sendTransaction",
+            json!({ "transaction": to_base64(&envelope)? }),
+        )?;
+        match result["status"].as_str() {
+            Some("PENDING") | Some("DUPLICATE") => {}
+            status => {
+                return Err(format!(
+                    "sendTransaction: {}",
+                    status.unwrap_or("no status")
+                ))
+            }
+        }
+        self.wait_for(result["hash"].as_str().ok_or("sendTransaction: no hash")?)?;
+        Ok(simulation.result)
+    }
+
+    /// Sleeps until the wall clock passes `timestamp`. Ledger close times track it closely,
+    /// and callers re-check on-chain state afterwards.
+    fn wait_until(&self, timestamp: u64) -> Result<(), String> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|err| err.to_string())?
+            .as_secs();
+        thread::sleep(Duration::from_secs(timestamp.saturating_sub(now) + 1));
+        Ok(())
+    }
+}
+
+fn account_id(key: &[u8; 32]) -> AccountId {
+    AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(*key)))
+}
+
+/// Unsigned transaction of `source` holding one `InvokeContract` operation.
+fn transaction(
+    source: &[u8; 32],
+    seq: i64,
+    fee: u32,
+    contract: &ScAddress,
+    function: &str,
+    args: &[ScVal],
+) -> Result<Transaction, String> {
+    let invoke = InvokeContractArgs {
+        contract_address: contract.clone(),
+        function_name: function
+            .try_into()
+            .map_err(|_| format!("invalid function name `{function}`"))?,
+        args: args.to_vec().try_into().map_err(|_| "too many arguments")?,
+    };
+    let op = Operation {
+        source_account: None,
+        body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+            host_function: HostFunction::InvokeContract(invoke),
+            auth: Default::default(),
+        }),
+    };
+    Ok(Transaction {
+        source_account: MuxedAccount::Ed25519(Uint256(*source)),
+        fee,
+        seq_num: SequenceNumber(seq),
+        cond: Preconditions::None,
+        memo: Memo::None,
+        operations: vec![op].try_into().expect("one operation"),
+        ext: TransactionExt::V0,
+    })
+}
+
+fn to_base64<T: WriteXdr>(value: &T) -> Result<String, String> {
+    value
+        .to_xdr_base64(Limits::none())
+        .map_err(|err| format!("encoding XDR: {err}"))
+}
+
+fn from_base64<T: ReadXdr>(b64: &str) -> Result<T, String> {
+    T::from_xdr_base64(b64, Limits::none()).map_err(|err| format!("decoding XDR: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transactions_round_trip_through_base64() {
+        let contract: ScAddress = "CA7QYNF7SOWQ3GLR2BGMZEHXAVIRZA4KVWLTJJFC7MGXUA74P7UJUWDA"
+            .parse()
+            .unwrap();
+        let tx = transaction(
+            &[7; 32],
+            42,
+            BASE_FEE,
+            &contract,
+            "resolve",
+            &[ScVal::U64(1)],
+        )
+        .unwrap();
+        let envelope = RpcTransport::new(
+            &Network {
+                rpc_url: String::new(),
+                passphrase: "Test SDF Network ; September 2015".into(),
+            },
+            None,
+        )
+        .sign(&SigningKey::from_bytes(&[1; 32]), tx.clone())
+        .unwrap();
+        let decoded: TransactionEnvelope = from_base64(&to_base64(&envelope).unwrap()).unwrap();
+        let TransactionEnvelope::Tx(decoded) = decoded else {
+            panic!("expected a v1 envelope");
+        };
+        assert_eq!(decoded.tx, tx);
+        let payload = TransactionSignaturePayload {
+            network_id: Hash(Sha256::digest(b"Test SDF Network ; September 2015").into()),
+            tagged_transaction: TransactionSignaturePayloadTaggedTransaction::Tx(tx),
+        };
+        let digest = Sha256::digest(payload.to_xdr(Limits::none()).unwrap());
+        let signature: [u8; 64] = decoded.signatures[0]
+            .signature
+            .0
+            .to_vec()
+            .try_into()
+            .unwrap();
+        SigningKey::from_bytes(&[1; 32])
+            .verifying_key()
+            .verify_strict(&digest, &ed25519_dalek::Signature::from_bytes(&signature))
+            .unwrap();
+        let overlong = "f".repeat(33);
+        assert!(transaction(&[7; 32], 42, BASE_FEE, &contract, &overlong, &[]).is_err());
+    }
+}
@@ -0,0 +1,789 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, IntoVal, Symbol, Val,
+};
+
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Config,
+    Auction(Bytes),
+    Bid(Bytes, Address),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuctionParams {
+    /// Seconds after `start_auction` during which sealed bids are accepted.
+    pub bidding_secs: u64,
+    /// Seconds after bidding closes during which bids are revealed.
+    pub reveal_secs: u64,
+    /// Reserve price: the floor for a valid bid, and the price a sole bidder pays.
+    pub min_bid: i128,
+    /// Registration term the winner receives.
+    pub registration_secs: u64,
+    /// `start_auction` is rejected after this timestamp, ending the launch phase.
+    pub launch_ends: u64,
+}
+
+#[contracttype]
+#[derive(Clone)]
+struct Config {
+    registrar: Address,
+    /// TLD under which auctioned labels are held and issued.
+    tld: Bytes,
+    token: Address,
+    treasury: Address,
+    params: AuctionParams,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuctionInfo {
+    pub bidding_ends: u64,
+    pub reveal_ends: u64,
+    /// Highest valid revealed bid so far; its deposit stays locked until `finalize`.
+    pub highest_bidder: Option<Address>,
+    pub highest_bid: i128,
+    /// Second-highest valid revealed bid, which sets the winner's price.
+    pub second_bid: i128,
+    /// Deposits behind bids not yet revealed; forfeited to the treasury at `finalize`.
+    pub sealed_deposits: i128,
+    pub finalized: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SealedBid {
+    pub commitment: BytesN<32>,
+    pub deposit: i128,
+    pub revealed: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["auction_started"])]
+pub struct EvtAuctionStarted {
+    #[topic]
+    pub label: Bytes,
+    pub bidding_ends: u64,
+    pub reveal_ends: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["bid_placed"])]
+pub struct EvtBidPlaced {
+    #[topic]
+    pub label: Bytes,
+    #[topic]
+    pub bidder: Address,
+    pub deposit: i128,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["bid_revealed"])]
+pub struct EvtBidRevealed {
+    #[topic]
+    pub label: Bytes,
+    #[topic]
+    pub bidder: Address,
+    pub amount: i128,
+    /// `false` when the bid was below the reserve or above its deposit; it is refunded.
+    pub valid: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["auction_finalized"])]
+pub struct EvtAuctionFinalized {
+    #[topic]
+    pub label: Bytes,
+    pub winner: Option<Address>,
+    pub price: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum AuctionError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    InvalidParams = 3,
+    LaunchOver = 4,
+    AuctionExists = 5,
+    AuctionNotFound = 6,
+    BiddingClosed = 7,
+    NotRevealPhase = 8,
+    BidExists = 9,
+    BidNotFound = 10,
+    /// The revealed amount and salt do not match the sealed commitment.
+    CommitmentMismatch = 11,
+    InvalidAmount = 12,
+    RevealNotOver = 13,
+    AlreadyFinalized = 14,
+}
+
+/// Sealed-bid, second-price auctions for contested names during the launch phase.
+///
+/// Bidders lock a deposit with a commitment to their real bid, then reveal it. The highest
+/// valid bid wins and pays the second-highest valid bid (or the reserve if it was the only
+/// one). Outbid and invalid bids are refunded when revealed, the winner's change is
+/// refunded at `finalize`, and unrevealed deposits are forfeited to the treasury. The
+/// Registrar must list this contract as its auction house.
+#[contract]
+pub struct Auction;
+
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn read_config(env: &Env) -> Config {
+    let storage = env.storage().persistent();
+    let config = storage
+        .get(&DataKey::Config)
+        .unwrap_or_else(|| panic_with_error!(env, AuctionError::NotInitialized));
+    storage.extend_ttl(&DataKey::Config, TTL_THRESHOLD, TTL_EXTEND_TO);
+    config
+}
+
+fn read_auction(env: &Env, label: &Bytes) -> AuctionInfo {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Auction(label.clone()))
+        .unwrap_or_else(|| panic_with_error!(env, AuctionError::AuctionNotFound))
+}
+
+fn validate_params(env: &Env, params: &AuctionParams) {
+    if params.bidding_secs == 0
+        || params.reveal_secs == 0
+        || params.min_bid <= 0
+        || params.registration_secs == 0
+    {
+        panic_with_error!(env, AuctionError::InvalidParams);
+    }
+}
+
+/// `sha256(label || bidder xdr || amount (i128 BE) || salt)`. Binding the bidder stops
+/// others from replaying a commitment they saw on-chain.
+fn bid_hash(env: &Env, label: &Bytes, bidder: &Address, amount: i128, salt: &Bytes) -> BytesN<32> {
+    let mut data = label.clone();
+    data.append(&bidder.clone().to_xdr(env));
+    data.extend_from_array(&amount.to_be_bytes());
+    data.append(salt);
+    env.crypto().sha256(&data).to_bytes()
+}
+
+fn pay(env: &Env, config: &Config, to: &Address, amount: i128) {
+    if amount > 0 {
+        TokenClient::new(env, &config.token).transfer(&env.current_contract_address(), to, &amount);
+    }
+}
+
+mod registrar_api {
+    use super::*;
+
+    pub fn hold(env: &Env, registrar: &Address, tld: &Bytes, label: &Bytes) {
+        env.invoke_contract::<()>(
+            registrar,
+            &Symbol::new(env, "hold_for_auction"),
+            (env.current_contract_address(), tld.clone(), label.clone()).into_val(env),
+        );
+    }
+
+    pub fn release(env: &Env, registrar: &Address, tld: &Bytes, label: &Bytes) {
+        env.invoke_contract::<()>(
+            registrar,
+            &Symbol::new(env, "release_auction_hold"),
+            (env.current_contract_address(), tld.clone(), label.clone()).into_val(env),
+        );
+    }
+
+    pub fn allocate(
+        env: &Env,
+        registrar: &Address,
+        tld: &Bytes,
+        label: &Bytes,
+        owner: &Address,
+        duration_secs: u64,
+        price: i128,
+    ) -> BytesN<32> {
+        env.invoke_contract(
+            registrar,
+            &Symbol::new(env, "allocate_auctioned"),
+            (
+                env.current_contract_address(),
+                tld.clone(),
+                label.clone(),
+                owner.clone(),
+                duration_secs,
+                price,
+            )
+                .into_val(env),
+        )
+    }
+}
+
+#[contractimpl]
+impl Auction {
+    /// Runs once, at deployment, so nobody can configure the auction before its deployer.
+    /// Proceeds (winning prices and forfeited deposits) are paid to `treasury` in `token`,
+    /// which should be the Registrar's fee token. Labels are auctioned under `tld`.
+    pub fn __constructor(
+        env: Env,
+        registrar: Address,
+        tld: Bytes,
+        token: Address,
+        treasury: Address,
+        params: AuctionParams,
+    ) {
+        validate_params(&env, &params);
+        let config = Config {
+            registrar,
+            tld,
+            token,
+            treasury,
+            params,
+        };
+        write_entry(&env, &DataKey::Config, &config);
+    }
+
+    pub fn params(env: Env) -> AuctionParams {
+        read_config(&env).params
+    }
+
+    /// Opens bidding on an available `label` and withholds it from commit–reveal
+    /// registration until `finalize`. Permissionless until `launch_ends`.
+    pub fn start_auction(env: Env, label: Bytes) {
+        let config = read_config(&env);
+        let now = env.ledger().timestamp();
+        if now > config.params.launch_ends {
+            panic_with_error!(&env, AuctionError::LaunchOver);
+        }
+        let key = DataKey::Auction(label.clone());
+        if env.storage().persistent().has(&key) {
+            panic_with_error!(&env, AuctionError::AuctionExists);
+        }
+        registrar_api::hold(&env, &config.registrar, &config.tld, &label);
+        let bidding_ends = now.saturating_add(config.params.bidding_secs);
+        let reveal_ends = bidding_ends.saturating_add(config.params.reveal_secs);
+        let auction = AuctionInfo {
+            bidding_ends,
+            reveal_ends,
+            highest_bidder: None,
+            highest_bid: 0,
+            second_bid: 0,
+            sealed_deposits: 0,
+            finalized: false,
+        };
+        write_entry(&env, &key, &auction);
+        EvtAuctionStarted {
+            label,
+            bidding_ends,
+            reveal_ends,
+        }
+        .publish(&env);
+    }
+
+    /// Places one sealed bid per bidder, locking `deposit`. The deposit must cover the
+    /// real bid and may exceed it to hide the amount.
+    pub fn bid(env: Env, bidder: Address, label: Bytes, commitment: BytesN<32>, deposit: i128) {
+        bidder.require_auth();
+        let config = read_config(&env);
+        let mut auction = read_auction(&env, &label);
+        if env.ledger().timestamp() >= auction.bidding_ends {
+            panic_with_error!(&env, AuctionError::BiddingClosed);
+        }
+        if deposit < config.params.min_bid {
+            panic_with_error!(&env, AuctionError::InvalidAmount);
+        }
+        let bid_key = DataKey::Bid(label.clone(), bidder.clone());
+        if env.storage().persistent().has(&bid_key) {
+            panic_with_error!(&env, AuctionError::BidExists);
+        }
+        TokenClient::new(&env, &config.token).transfer(
+            &bidder,
+            env.current_contract_address(),
+            &deposit,
+        );
+        let sealed = SealedBid {
+            commitment,
+            deposit,
+            revealed: false,
+        };
+        write_entry(&env, &bid_key, &sealed);
+        auction.sealed_deposits += deposit;
+        write_entry(&env, &DataKey::Auction(label.clone()), &auction);
+        EvtBidPlaced {
+            label,
+            bidder,
+            deposit,
+        }
+        .publish(&env);
+    }
+
+    /// Opens a sealed bid during the reveal window. A bid that does not lead is refunded
+    /// immediately; a new leader refunds the previous one.
+    pub fn reveal(env: Env, bidder: Address, label: Bytes, amount: i128, salt: Bytes) {
+        bidder.require_auth();
+        let config = read_config(&env);
+        let mut auction = read_auction(&env, &label);
+        let now = env.ledger().timestamp();
+        if now < auction.bidding_ends || now >= auction.reveal_ends {
+            panic_with_error!(&env, AuctionError::NotRevealPhase);
+        }
+        let bid_key = DataKey::Bid(label.clone(), bidder.clone());
+        let mut sealed: SealedBid = env
+            .storage()
+            .persistent()
+            .get(&bid_key)
+            .unwrap_or_else(|| panic_with_error!(&env, AuctionError::BidNotFound));
+        if sealed.revealed {
+            panic_with_error!(&env, AuctionError::BidNotFound);
+        }
+        if bid_hash(&env, &label, &bidder, amount, &salt) != sealed.commitment {
+            panic_with_error!(&env, AuctionError::CommitmentMismatch);
+        }
+        sealed.revealed = true;
+        write_entry(&env, &bid_key, &sealed);
+        auction.sealed_deposits -= sealed.deposit;
+
+        let valid = amount >= config.params.min_bid && amount <= sealed.deposit;
+        if valid && amount > auction.highest_bid {
+            if let Some(previous) = auction.highest_bidder.clone() {
+                let previous_bid: SealedBid = env
+                    .storage()
+                    .persistent()
+                    .get(&DataKey::Bid(label.clone(), previous.clone()))
+                    .unwrap_or_else(|| panic_with_error!(&env, AuctionError::BidNotFound));
+                pay(&env, &config, &previous, previous_bid.deposit);
+            }
+            auction.second_bid = auction.highest_bid;
+            auction.highest_bid = amount;
+            auction.highest_bidder = Some(bidder.clone());
+        } else {
+            if valid && amount > auction.second_bid {
+                auction.second_bid = amount;
+            }
+            pay(&env, &config, &bidder, sealed.deposit);
+        }
+        write_entry(&env, &DataKey::Auction(label.clone()), &auction);
+        EvtBidRevealed {
+            label,
+            bidder,
+            amount,
+            valid,
+        }
+        .publish(&env);
+    }
+
+    /// Settles after the reveal window: registers the name to the winner at the second
+    /// price, refunds the winner's change, and pays proceeds to the treasury. With no valid
+    /// bid the label returns to open registration. Permissionless; returns the winner.
+    pub fn finalize(env: Env, label: Bytes) -> Option<Address> {
+        let config = read_config(&env);
+        let mut auction = read_auction(&env, &label);
+        if auction.finalized {
+            panic_with_error!(&env, AuctionError::AlreadyFinalized);
+        }
+        if env.ledger().timestamp() < auction.reveal_ends {
+            panic_with_error!(&env, AuctionError::RevealNotOver);
+        }
+        auction.finalized = true;
+        write_entry(&env, &DataKey::Auction(label.clone()), &auction);
+
+        let winner = auction.highest_bidder.clone();
+        let price = match winner.as_ref() {
+            Some(winner) => {
+                let price = auction.second_bid.max(config.params.min_bid);
+                let deposit = env
+                    .storage()
+                    .persistent()
+                    .get::<_, SealedBid>(&DataKey::Bid(label.clone(), winner.clone()))
+                    .unwrap_or_else(|| panic_with_error!(&env, AuctionError::BidNotFound))
+                    .deposit;
+                pay(&env, &config, winner, deposit - price);
+                registrar_api::allocate(
+                    &env,
+                    &config.registrar,
+                    &config.tld,
+                    &label,
+                    winner,
+                    config.params.registration_secs,
+                    price,
+                );
+                price
+            }
+            None => {
+                registrar_api::release(&env, &config.registrar, &config.tld, &label);
+                0
+            }
+        };
+        pay(
+            &env,
+            &config,
+            &config.treasury,
+            price + auction.sealed_deposits,
+        );
+        EvtAuctionFinalized {
+            label,
+            winner: winner.clone(),
+            price,
+        }
+        .publish(&env);
+        winner
+    }
+
+    pub fn auction(env: Env, label: Bytes) -> Option<AuctionInfo> {
+        env.storage().persistent().get(&DataKey::Auction(label))
+    }
+
+    pub fn sealed_bid(env: Env, label: Bytes, bidder: Address) -> Option<SealedBid> {
+        env.storage().persistent().get(&DataKey::Bid(label, bidder))
+    }
+
+    /// The commitment `bid` expects for a real bid of `amount` hidden by `salt`.
+    pub fn bid_commitment(
+        env: Env,
+        label: Bytes,
+        bidder: Address,
+        amount: i128,
+        salt: Bytes,
+    ) -> BytesN<32> {
+        bid_hash(&env, &label, &bidder, amount, &salt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::token::StellarAssetClient;
+
+    #[derive(Clone)]
+    #[contracttype]
+    enum MockRegistrarKey {
+        Held(Bytes, Bytes),
+        Owner(Bytes),
+        Price(Bytes),
+    }
+
+    /// Registrar stand-in recording holds and allocations.
+    #[contract]
+    pub struct MockRegistrar;
+
+    #[contractimpl]
+    impl MockRegistrar {
+        pub fn hold_for_auction(env: Env, auction_house: Address, tld: Bytes, label: Bytes) {
+            auction_house.require_auth();
+            env.storage()
+                .persistent()
+                .set(&MockRegistrarKey::Held(tld, label), &true);
+        }
+
+        pub fn release_auction_hold(env: Env, auction_house: Address, tld: Bytes, label: Bytes) {
+            auction_house.require_auth();
+            env.storage()
+                .persistent()
+                .remove(&MockRegistrarKey::Held(tld, label));
+        }
+
+        pub fn allocate_auctioned(
+            env: Env,
+            auction_house: Address,
+            tld: Bytes,
+            label: Bytes,
+            owner: Address,
+            _duration_secs: u64,
+            price: i128,
+        ) -> BytesN<32> {
+            auction_house.require_auth();
+            let storage = env.storage().persistent();
+            storage.remove(&MockRegistrarKey::Held(tld, label.clone()));
+            storage.set(&MockRegistrarKey::Owner(label.clone()), &owner);
+            storage.set(&MockRegistrarKey::Price(label.clone()), &price);
+            env.crypto().sha256(&label).to_bytes()
+        }
+
+        pub fn held(env: Env, tld: Bytes, label: Bytes) -> bool {
+            env.storage()
+                .persistent()
+                .has(&MockRegistrarKey::Held(tld, label))
+        }
+
+        pub fn owner(env: Env, label: Bytes) -> Option<Address> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistrarKey::Owner(label))
+        }
+
+        pub fn price(env: Env, label: Bytes) -> Option<i128> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistrarKey::Price(label))
+        }
+    }
+
+    const BIDDING: u64 = 100;
+    const REVEAL: u64 = 50;
+    const TLD: &[u8] = b"stellar";
+
+    struct Setup {
+        env: Env,
+        auction: AuctionClient<'static>,
+        registrar: MockRegistrarClient<'static>,
+        token: TokenClient<'static>,
+        treasury: Address,
+    }
+
+    fn setup() -> Setup {
+        let env = Env::default();
+        env.mock_all_auths();
+        let registrar_id = env.register(MockRegistrar, ());
+        let token_admin = Address::generate(&env);
+        let token_id = env
+            .register_stellar_asset_contract_v2(token_admin)
+            .address();
+        let treasury = Address::generate(&env);
+        let auction_id = env.register(
+            Auction,
+            (
+                &registrar_id,
+                Bytes::from_slice(&env, TLD),
+                &token_id,
+                &treasury,
+                AuctionParams {
+                    bidding_secs: BIDDING,
+                    reveal_secs: REVEAL,
+                    min_bid: 10,
+                    registration_secs: 31_536_000,
+                    launch_ends: 1_000,
+                },
+            ),
+        );
+        let auction = AuctionClient::new(&env, &auction_id);
+        Setup {
+            registrar: MockRegistrarClient::new(&env, &registrar_id),
+            token: TokenClient::new(&env, &token_id),
+            auction,
+            treasury,
+            env,
+        }
+    }
+
+    fn funded_bidder(s: &Setup, amount: i128) -> Address {
+        let bidder = Address::generate(&s.env);
+        StellarAssetClient::new(&s.env, &s.token.address).mint(&bidder, &amount);
+        bidder
+    }
+
+    fn place_bid(s: &Setup, bidder: &Address, label: &Bytes, amount: i128, deposit: i128) -> Bytes {
+        let salt = Bytes::from_slice(&s.env, &amount.to_be_bytes());
+        let commitment = s.auction.bid_commitment(label, bidder, &amount, &salt);
+        s.auction.bid(bidder, label, &commitment, &deposit);
+        salt
+    }
+
+    #[test]
+    fn winner_pays_second_price_and_losers_are_refunded() {
+        let s = setup();
+        let label = Bytes::from_slice(&s.env, b"vault");
+        s.auction.start_auction(&label);
+        assert!(s.registrar.held(&Bytes::from_slice(&s.env, TLD), &label));
+        assert_eq!(
+            s.auction.try_start_auction(&label),
+            Err(Ok(AuctionError::AuctionExists.into()))
+        );
+
+        let high = funded_bidder(&s, 150);
+        let low = funded_bidder(&s, 80);
+        let silent = funded_bidder(&s, 50);
+        // The high bidder over-deposits to hide their real bid.
+        let high_salt = place_bid(&s, &high, &label, 100, 150);
+        let low_salt = place_bid(&s, &low, &label, 80, 80);
+        place_bid(&s, &silent, &label, 50, 50);
+        assert_eq!(
+            s.auction.try_reveal(&high, &label, &100, &high_salt),
+            Err(Ok(AuctionError::NotRevealPhase.into()))
+        );
+
+        s.env.ledger().set_timestamp(BIDDING);
+        assert_eq!(
+            s.auction
+                .try_bid(&low, &label, &BytesN::from_array(&s.env, &[0; 32]), &10),
+            Err(Ok(AuctionError::BiddingClosed.into()))
+        );
+        assert_eq!(
+            s.auction.try_reveal(&high, &label, &99, &high_salt),
+            Err(Ok(AuctionError::CommitmentMismatch.into()))
+        );
+        s.auction.reveal(&low, &label, &80, &low_salt);
+        s.auction.reveal(&high, &label, &100, &high_salt);
+        // Outbid: `low` got the full deposit back as soon as `high` took the lead.
+        assert_eq!(s.token.balance(&low), 80);
+
+        assert_eq!(
+            s.auction.try_finalize(&label),
+            Err(Ok(AuctionError::RevealNotOver.into()))
+        );
+        s.env.ledger().set_timestamp(BIDDING + REVEAL);
+        assert_eq!(s.auction.finalize(&label), Some(high.clone()));
+        assert_eq!(s.registrar.owner(&label), Some(high.clone()));
+        assert_eq!(s.registrar.price(&label), Some(80));
+        assert_eq!(s.token.balance(&high), 70);
+        // Second price plus the unrevealed deposit.
+        assert_eq!(s.token.balance(&s.treasury), 130);
+        assert_eq!(s.token.balance(&s.auction.address), 0);
+        assert_eq!(
+            s.auction.try_finalize(&label),
+            Err(Ok(AuctionError::AlreadyFinalized.into()))
+        );
+    }
+
+    #[test]
+    fn sole_bidder_pays_reserve_and_empty_auctions_release_the_label() {
+        let s = setup();
+        let solo_label = Bytes::from_slice(&s.env, b"solo");
+        let empty_label = Bytes::from_slice(&s.env, b"empty");
+        s.auction.start_auction(&solo_label);
+        s.auction.start_auction(&empty_label);
+
+        let solo = funded_bidder(&s, 40);
+        let invalid = funded_bidder(&s, 30);
+        let salt = place_bid(&s, &solo, &solo_label, 40, 40);
+        // Bidding above the deposit makes the bid invalid; it is refunded on reveal.
+        let invalid_salt = place_bid(&s, &invalid, &solo_label, 60, 30);
+        s.env.ledger().set_timestamp(BIDDING);
+        s.auction.reveal(&solo, &solo_label, &40, &salt);
+        s.auction.reveal(&invalid, &solo_label, &60, &invalid_salt);
+        assert_eq!(s.token.balance(&invalid), 30);
+        assert_eq!(
+            s.auction.try_reveal(&solo, &solo_label, &40, &salt),
+            Err(Ok(AuctionError::BidNotFound.into()))
+        );
+
+        s.env.ledger().set_timestamp(BIDDING + REVEAL);
+        s.auction.finalize(&solo_label);
+        assert_eq!(s.registrar.price(&solo_label), Some(10));
+        assert_eq!(s.token.balance(&solo), 30);
+
+        assert_eq!(s.auction.finalize(&empty_label), None);
+        assert!(!s
+            .registrar
+            .held(&Bytes::from_slice(&s.env, TLD), &empty_label));
+        assert_eq!(s.registrar.owner(&empty_label), None);
+
+        s.env.ledger().set_timestamp(1_001);
+        assert_eq!(
+            s.auction
+                .try_start_auction(&Bytes::from_slice(&s.env, b"late")),
+            Err(Ok(AuctionError::LaunchOver.into()))
+        );
+    }
+
+    #[test]
+    fn ties_keep_the_first_reveal_and_bad_bids_are_refunded() {
+        let s = setup();
+        let label = Bytes::from_slice(&s.env, b"tied");
+        assert_eq!(
+            s.auction.try_bid(
+                &s.treasury,
+                &label,
+                &BytesN::from_array(&s.env, &[0; 32]),
+                &10
+            ),
+            Err(Ok(AuctionError::AuctionNotFound.into()))
+        );
+        s.auction.start_auction(&label);
+
+        let first = funded_bidder(&s, 60);
+        let second = funded_bidder(&s, 60);
+        let cheap = funded_bidder(&s, 20);
+        let copycat = funded_bidder(&s, 60);
+        assert_eq!(
+            s.auction
+                .try_bid(&cheap, &label, &BytesN::from_array(&s.env, &[0; 32]), &9),
+            Err(Ok(AuctionError::InvalidAmount.into()))
+        );
+        let first_salt = place_bid(&s, &first, &label, 50, 60);
+        let second_salt = place_bid(&s, &second, &label, 50, 60);
+        // Below the reserve, though the deposit covers it.
+        let cheap_salt = place_bid(&s, &cheap, &label, 5, 20);
+        assert_eq!(
+            s.auction
+                .try_bid(&first, &label, &BytesN::from_array(&s.env, &[0; 32]), &10),
+            Err(Ok(AuctionError::BidExists.into()))
+        );
+        // A commitment is bound to its bidder, so copying one does not help.
+        let copied = s.auction.sealed_bid(&label, &first).unwrap().commitment;
+        s.auction.bid(&copycat, &label, &copied, &60);
+
+        s.env.ledger().set_timestamp(BIDDING);
+        assert_eq!(
+            s.auction.try_reveal(&copycat, &label, &50, &first_salt),
+            Err(Ok(AuctionError::CommitmentMismatch.into()))
+        );
+        assert_eq!(
+            s.auction.try_reveal(&s.treasury, &label, &50, &first_salt),
+            Err(Ok(AuctionError::BidNotFound.into()))
+        );
+        s.auction.reveal(&first, &label, &50, &first_salt);
+        s.auction.reveal(&second, &label, &50, &second_salt);
+        s.auction.reveal(&cheap, &label, &5, &cheap_salt);
+        assert_eq!(s.token.balance(&second), 60);
+        assert_eq!(s.token.balance(&cheap), 20);
+        let info = s.auction.auction(&label).unwrap();
+        assert_eq!(info.highest_bidder, Some(first.clone()));
+        assert_eq!((info.highest_bid, info.second_bid), (50, 50));
+
+        s.env.ledger().set_timestamp(BIDDING + REVEAL);
+        assert_eq!(
+            s.auction.try_reveal(&copycat, &label, &50, &first_salt),
+            Err(Ok(AuctionError::NotRevealPhase.into()))
+        );
+        // The tie sets the price, and the unrevealed copy is forfeited.
+        assert_eq!(s.auction.finalize(&label), Some(first.clone()));
+        assert_eq!(s.registrar.price(&label), Some(50));
+        assert_eq!(s.token.balance(&first), 10);
+        assert_eq!(s.token.balance(&copycat), 0);
+        assert_eq!(s.token.balance(&s.treasury), 110);
+        assert_eq!(s.token.balance(&s.auction.address), 0);
+    }
+
+    #[test]
+    fn unrevealed_auctions_forfeit_deposits_and_release_the_label() {
+        let s = setup();
+        let label = Bytes::from_slice(&s.env, b"quiet");
+        assert_eq!(
+            s.auction.try_finalize(&label),
+            Err(Ok(AuctionError::AuctionNotFound.into()))
+        );
+        s.auction.start_auction(&label);
+        let bidders = [funded_bidder(&s, 30), funded_bidder(&s, 40)];
+        place_bid(&s, &bidders[0], &label, 30, 30);
+        place_bid(&s, &bidders[1], &label, 40, 40);
+        assert_eq!(s.auction.auction(&label).unwrap().sealed_deposits, 70);
+
+        s.env.ledger().set_timestamp(BIDDING + REVEAL);
+        assert_eq!(s.auction.finalize(&label), None);
+        assert!(!s.registrar.held(&Bytes::from_slice(&s.env, TLD), &label));
+        assert_eq!(s.registrar.owner(&label), None);
+        assert_eq!(s.token.balance(&s.treasury), 70);
+        assert!(s.auction.auction(&label).unwrap().finalized);
+        assert_eq!(
+            s.auction.try_start_auction(&label),
+            Err(Ok(AuctionError::AuctionExists.into()))
+        );
+    }
+}
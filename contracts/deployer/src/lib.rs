@@ -0,0 +1,277 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{
+    contract, contractevent, contractimpl, contracttype, Address, Bytes, BytesN, ConstructorArgs,
+    Env, IntoVal, Symbol, Val, Vec,
+};
+
+/// Already-uploaded Wasm for each contract `deploy` instantiates.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WasmHashes {
+    pub registry: BytesN<32>,
+    pub resolver: BytesN<32>,
+    pub registrar: BytesN<32>,
+}
+
+/// Addresses of one wired deployment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Deployment {
+    pub registry: Address,
+    pub resolver: Address,
+    pub registrar: Address,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["deployed"])]
+pub struct EvtDeployed {
+    #[topic]
+    pub admin: Address,
+    pub registry: Address,
+    pub resolver: Address,
+    pub registrar: Address,
+    pub tld: Bytes,
+}
+
+#[contract]
+pub struct Deployer;
+
+/// Salt for one contract of a deployment. `admin` is part of it, so nobody else can claim
+/// an admin's addresses by deploying with the same `salt` first.
+fn contract_salt(env: &Env, admin: &Address, salt: &BytesN<32>, name: &str) -> BytesN<32> {
+    let mut data = admin.clone().to_xdr(env);
+    data.append(&Bytes::from_array(env, &salt.to_array()));
+    data.append(&Bytes::from_slice(env, name.as_bytes()));
+    env.crypto().sha256(&data).to_bytes()
+}
+
+fn deploy_one<A: ConstructorArgs>(
+    env: &Env,
+    admin: &Address,
+    salt: &BytesN<32>,
+    name: &str,
+    wasm: &BytesN<32>,
+    constructor_args: A,
+) -> Address {
+    env.deployer()
+        .with_current_contract(contract_salt(env, admin, salt, name))
+        .deploy_v2(wasm.clone(), constructor_args)
+}
+
+fn invoke(env: &Env, contract: &Address, func: &str, args: Vec<Val>) {
+    env.invoke_contract::<Val>(contract, &Symbol::new(env, func), args);
+}
+
+/// Initializes freshly deployed contracts and connects them, in the order the manual
+/// `scripts/deploy_local.sh` flow uses. The Registry is set up by its constructor.
+fn wire(env: &Env, admin: &Address, deployment: &Deployment, tld: &Bytes, token: &Address) {
+    let Deployment {
+        registry,
+        resolver,
+        registrar,
+    } = deployment;
+    invoke(env, resolver, "init", (registry.clone(),).into_val(env));
+    invoke(
+        env,
+        registrar,
+        "init",
+        (registry.clone(), tld.clone(), admin.clone(), token.clone()).into_val(env),
+    );
+    invoke(
+        env,
+        registry,
+        "create_tld",
+        (admin.clone(), tld.clone(), registrar.clone()).into_val(env),
+    );
+    invoke(
+        env,
+        registry,
+        "set_controller",
+        (registrar.clone(), true).into_val(env),
+    );
+}
+
+#[contractimpl]
+impl Deployer {
+    /// Deploys a Registry, Resolver, and Registrar and wires them in one transaction:
+    /// `admin` becomes Registry admin and root owner and Registrar admin, the Resolver and
+    /// Registrar point at the Registry, and the `tld` node is minted to the Registrar, which
+    /// is approved as a controller. Registrar fees are paid in `token`. Addresses depend
+    /// only on `admin` and `salt`; see `addresses`. Emits `deployed`.
+    pub fn deploy(
+        env: Env,
+        admin: Address,
+        wasm: WasmHashes,
+        salt: BytesN<32>,
+        tld: Bytes,
+        token: Address,
+    ) -> Deployment {
+        admin.require_auth();
+        let deployment = Deployment {
+            registry: deploy_one(
+                &env,
+                &admin,
+                &salt,
+                "registry",
+                &wasm.registry,
+                (admin.clone(), admin.clone()),
+            ),
+            resolver: deploy_one(&env, &admin, &salt, "resolver", &wasm.resolver, ()),
+            registrar: deploy_one(&env, &admin, &salt, "registrar", &wasm.registrar, ()),
+        };
+        wire(&env, &admin, &deployment, &tld, &token);
+        EvtDeployed {
+            admin,
+            registry: deployment.registry.clone(),
+            resolver: deployment.resolver.clone(),
+            registrar: deployment.registrar.clone(),
+            tld,
+        }
+        .publish(&env);
+        deployment
+    }
+
+    /// The addresses `deploy` would use for `admin` and `salt`.
+    pub fn addresses(env: Env, admin: Address, salt: BytesN<32>) -> Deployment {
+        let address = |name| {
+            env.deployer()
+                .with_current_contract(contract_salt(&env, &admin, &salt, name))
+                .deployed_address()
+        };
+        Deployment {
+            registry: address("registry"),
+            resolver: address("resolver"),
+            registrar: address("registrar"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use registrar::{Registrar, RegistrarClient};
+    use registry::{Registry, RegistryClient};
+    use resolver::{Resolver, ResolverClient};
+    use soroban_sdk::testutils::{Address as _, MockAuth, MockAuthInvoke};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[test]
+    fn addresses_depend_on_admin_and_salt() {
+        let env = Env::default();
+        let deployer = env.register(Deployer, ());
+        let client = DeployerClient::new(&env, &deployer);
+        let admin = Address::generate(&env);
+        let other = Address::generate(&env);
+        let salt = BytesN::from_array(&env, &[1; 32]);
+
+        let first = client.addresses(&admin, &salt);
+        assert_eq!(client.addresses(&admin, &salt), first);
+        assert_ne!(first.registry, first.resolver);
+        assert_ne!(first.registry, first.registrar);
+        assert_ne!(client.addresses(&other, &salt), first);
+        assert_ne!(
+            client.addresses(&admin, &BytesN::from_array(&env, &[2; 32])),
+            first
+        );
+    }
+
+    #[test]
+    fn wire_connects_fresh_contracts() {
+        let env = Env::default();
+        // `wire` runs without the `deploy` frame whose `admin.require_auth()` roots the tree.
+        env.mock_all_auths_allowing_non_root_auth();
+        let deployer = env.register(Deployer, ());
+        let admin = Address::generate(&env);
+        let deployment = Deployment {
+            registry: env.register(Registry, (&admin, &admin)),
+            resolver: env.register(Resolver, ()),
+            registrar: env.register(Registrar, ()),
+        };
+        let token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let tld = Bytes::from_slice(&env, b"stellar");
+        env.as_contract(&deployer, || wire(&env, &admin, &deployment, &tld, &token));
+
+        let registry = RegistryClient::new(&env, &deployment.registry);
+        assert_eq!(registry.admin(), admin);
+        assert!(registry.is_controller(&deployment.registrar));
+        let tld_node = registry.namehash(&soroban_sdk::vec![&env, tld.clone()]);
+        assert_eq!(registry.owner(&tld_node), deployment.registrar);
+
+        let resolver = ResolverClient::new(&env, &deployment.resolver);
+        assert_eq!(resolver.registry(), deployment.registry);
+
+        let registrar = RegistrarClient::new(&env, &deployment.registrar);
+        assert_eq!(registrar.registry(), deployment.registry);
+        assert_eq!(registrar.admin(), admin);
+        assert_eq!(registrar.payment_token(), token);
+        assert_eq!(registrar.tlds(), soroban_sdk::vec![&env, tld]);
+    }
+
+    #[test]
+    fn deploy_needs_the_admins_signature() {
+        let env = Env::default();
+        let deployer = env.register(Deployer, ());
+        let client = DeployerClient::new(&env, &deployer);
+        let admin = Address::generate(&env);
+        let intruder = Address::generate(&env);
+        let hash = BytesN::from_array(&env, &[0; 32]);
+        let wasm = WasmHashes {
+            registry: hash.clone(),
+            resolver: hash.clone(),
+            registrar: hash,
+        };
+        let salt = BytesN::from_array(&env, &[1; 32]);
+        let tld = Bytes::from_slice(&env, b"stellar");
+        let token = Address::generate(&env);
+
+        assert!(client
+            .try_deploy(&admin, &wasm, &salt, &tld, &token)
+            .is_err());
+        // Signing as someone else does not claim the admin's addresses.
+        let args = (&admin, &wasm, &salt, &tld, &token).into_val(&env);
+        assert!(client
+            .mock_auths(&[MockAuth {
+                address: &intruder,
+                invoke: &MockAuthInvoke {
+                    contract: &deployer,
+                    fn_name: "deploy",
+                    args,
+                    sub_invokes: &[],
+                },
+            }])
+            .try_deploy(&admin, &wasm, &salt, &tld, &token)
+            .is_err());
+    }
+
+    #[test]
+    fn wire_fails_on_a_registry_the_admin_does_not_run() {
+        let env = Env::default();
+        env.mock_all_auths_allowing_non_root_auth();
+        let deployer = env.register(Deployer, ());
+        let admin = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let deployment = Deployment {
+            registry: env.register(Registry, (&owner, &owner)),
+            resolver: env.register(Resolver, ()),
+            registrar: env.register(Registrar, ()),
+        };
+        let token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let tld = Bytes::from_slice(&env, b"stellar");
+
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            env.as_contract(&deployer, || wire(&env, &admin, &deployment, &tld, &token))
+        }));
+        assert!(attempt.is_err());
+        let registry = RegistryClient::new(&env, &deployment.registry);
+        assert!(!registry.is_controller(&deployment.registrar));
+    }
+}
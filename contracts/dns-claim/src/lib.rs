@@ -0,0 +1,736 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use name_service_common::subnode;
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, IntoVal, Symbol, Val, Vec,
+};
+
+pub use verifier::{ClaimStatement, OracleSet, ProofVerifier, Verifier};
+
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+const MAX_DOMAIN_LEN: u32 = 253;
+const MAX_DNS_LABEL_LEN: u32 = 63;
+/// Separates claim digests from any other message an oracle key might sign.
+const CLAIM_DOMAIN_TAG: &[u8] = b"stellar-ns/dns-claim/v1";
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Config,
+    Claim(Bytes),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DnsConfig {
+    pub admin: Address,
+    pub registry: Address,
+    /// Registry node this contract owns and issues claimed domains under, normally the
+    /// `dns` top-level node minted to it with `registry.create_tld`.
+    pub namespace: BytesN<32>,
+    pub verifier: Verifier,
+    /// Proofs older than this many seconds are rejected.
+    pub max_proof_age: u64,
+}
+
+/// The latest accepted claim for a domain.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Claim {
+    pub owner: Address,
+    pub namehash: BytesN<32>,
+    pub issued_at: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["dns_claimed"])]
+pub struct EvtDnsClaimed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub domain: Bytes,
+    pub owner: Address,
+    pub issued_at: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["verifier_changed"])]
+pub struct EvtVerifierChanged {
+    pub verifier: Verifier,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum DnsError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    /// An oracle set with no keys, more than 255 keys, or an unreachable threshold.
+    InvalidParams = 3,
+    NotAdmin = 4,
+    /// The domain is not a lowercase `label.label…` name of at least two labels.
+    InvalidDomain = 5,
+    /// `issued_at` is in the future or older than `max_proof_age`.
+    ProofExpired = 6,
+    /// The proof is not newer than the one behind the domain's current claim.
+    StaleProof = 7,
+    InvalidProof = 8,
+    /// A parent of the domain was claimed by someone else, who controls its subnodes.
+    ParentClaimed = 9,
+}
+
+/// Proof schemes. A scheme decides whether a proof shows that `claimant` controls `domain`;
+/// `DnsClaim` only checks freshness and writes the Registry.
+pub mod verifier {
+    use super::*;
+    use soroban_sdk::contractclient;
+
+    /// What every proof attests to. Oracles sign [`claim_digest`] of it.
+    #[contracttype]
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct ClaimStatement {
+        pub domain: Bytes,
+        pub claimant: Address,
+        pub issued_at: u64,
+    }
+
+    /// `threshold` of `keys` must sign. A proof is a run of 65-byte entries, each a key
+    /// index followed by that key's ed25519 signature, with indices strictly increasing.
+    #[contracttype]
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub struct OracleSet {
+        pub keys: Vec<BytesN<32>>,
+        pub threshold: u32,
+    }
+
+    #[contracttype]
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    pub enum Verifier {
+        /// Built-in ed25519 threshold signatures from DNS oracles.
+        Oracles(OracleSet),
+        /// Any contract implementing [`ProofVerifierContract`], e.g. a DNSSEC chain checker.
+        Contract(Address),
+    }
+
+    pub trait ProofVerifier {
+        fn verify(&self, env: &Env, statement: &ClaimStatement, proof: &Bytes) -> bool;
+    }
+
+    /// Interface for proof schemes deployed as their own contract.
+    #[contractclient(name = "ProofVerifierContractClient")]
+    pub trait ProofVerifierContract {
+        fn verify(env: Env, statement: ClaimStatement, proof: Bytes) -> bool;
+    }
+
+    /// `sha256(tag || len(domain) || domain || claimant xdr || issued_at)`, big-endian.
+    pub fn claim_digest(env: &Env, statement: &ClaimStatement) -> BytesN<32> {
+        let mut data = Bytes::from_slice(env, CLAIM_DOMAIN_TAG);
+        data.extend_from_array(&statement.domain.len().to_be_bytes());
+        data.append(&statement.domain);
+        data.append(&statement.claimant.clone().to_xdr(env));
+        data.extend_from_array(&statement.issued_at.to_be_bytes());
+        env.crypto().sha256(&data).to_bytes()
+    }
+
+    impl OracleSet {
+        pub fn is_valid(&self) -> bool {
+            self.threshold > 0 && self.threshold <= self.keys.len() && self.keys.len() <= 255
+        }
+    }
+
+    impl ProofVerifier for OracleSet {
+        /// Signatures that fail to verify abort the call rather than count as absent.
+        fn verify(&self, env: &Env, statement: &ClaimStatement, proof: &Bytes) -> bool {
+            const ENTRY_LEN: u32 = 65;
+            if !proof.len().is_multiple_of(ENTRY_LEN) || proof.len() / ENTRY_LEN < self.threshold {
+                return false;
+            }
+            let message: Bytes = claim_digest(env, statement).into();
+            let mut next_index = 0u32;
+            let mut offset = 0u32;
+            while offset < proof.len() {
+                let index = proof.get_unchecked(offset) as u32;
+                if index < next_index || index >= self.keys.len() {
+                    return false;
+                }
+                let mut signature = [0u8; 64];
+                proof
+                    .slice(offset + 1..offset + ENTRY_LEN)
+                    .copy_into_slice(&mut signature);
+                env.crypto().ed25519_verify(
+                    &self.keys.get_unchecked(index),
+                    &message,
+                    &BytesN::from_array(env, &signature),
+                );
+                next_index = index + 1;
+                offset += ENTRY_LEN;
+            }
+            true
+        }
+    }
+
+    impl ProofVerifier for Verifier {
+        fn verify(&self, env: &Env, statement: &ClaimStatement, proof: &Bytes) -> bool {
+            match self {
+                Verifier::Oracles(set) => set.verify(env, statement, proof),
+                Verifier::Contract(address) => {
+                    ProofVerifierContractClient::new(env, address).verify(statement, proof)
+                }
+            }
+        }
+    }
+}
+
+/// Issues Registry names under a DNS namespace to whoever proves control of the matching
+/// real-world domain: `example.com` becomes `example.com.dns`.
+#[contract]
+pub struct DnsClaim;
+
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn read_config(env: &Env) -> DnsConfig {
+    let storage = env.storage().persistent();
+    let config = storage
+        .get(&DataKey::Config)
+        .unwrap_or_else(|| panic_with_error!(env, DnsError::NotInitialized));
+    storage.extend_ttl(&DataKey::Config, TTL_THRESHOLD, TTL_EXTEND_TO);
+    config
+}
+
+fn validate_verifier(env: &Env, verifier: &Verifier) {
+    if let Verifier::Oracles(set) = verifier {
+        if !set.is_valid() {
+            panic_with_error!(env, DnsError::InvalidParams);
+        }
+    }
+}
+
+/// A lowercase letter-digit-hyphen label that neither starts nor ends with a hyphen.
+fn validate_dns_label(env: &Env, label: &Bytes) {
+    let len = label.len();
+    if len == 0
+        || len > MAX_DNS_LABEL_LEN
+        || label.get_unchecked(0) == b'-'
+        || label.get_unchecked(len - 1) == b'-'
+        || !label
+            .iter()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+    {
+        panic_with_error!(env, DnsError::InvalidDomain);
+    }
+}
+
+/// Splits `example.com` into `["com", "example"]`, root-most label first.
+fn split_domain(env: &Env, domain: &Bytes) -> Vec<Bytes> {
+    if domain.len() > MAX_DOMAIN_LEN {
+        panic_with_error!(env, DnsError::InvalidDomain);
+    }
+    let mut labels = Vec::new(env);
+    let mut start = 0u32;
+    for index in 0..=domain.len() {
+        if index == domain.len() || domain.get_unchecked(index) == b'.' {
+            let label = domain.slice(start..index);
+            validate_dns_label(env, &label);
+            labels.push_front(label);
+            start = index + 1;
+        }
+    }
+    if labels.len() < 2 {
+        panic_with_error!(env, DnsError::InvalidDomain);
+    }
+    labels
+}
+
+mod registry_api {
+    use super::*;
+
+    pub fn owner(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "get_owner"),
+            (namehash.clone(),).into_val(env),
+        )
+    }
+
+    pub fn set_subnode_owner(
+        env: &Env,
+        registry: &Address,
+        parent: &BytesN<32>,
+        label: &Bytes,
+        owner: &Address,
+    ) -> BytesN<32> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "set_subnode_owner"),
+            (parent.clone(), label.clone(), owner.clone()).into_val(env),
+        )
+    }
+}
+
+#[contractimpl]
+impl DnsClaim {
+    /// Runs once, at deployment, so nobody can configure the claims before its deployer.
+    /// `namespace` has to be owned by this contract's address in the Registry before the
+    /// first claim.
+    pub fn __constructor(
+        env: Env,
+        admin: Address,
+        registry: Address,
+        namespace: BytesN<32>,
+        verifier: Verifier,
+        max_proof_age: u64,
+    ) {
+        validate_verifier(&env, &verifier);
+        if max_proof_age == 0 {
+            panic_with_error!(&env, DnsError::InvalidParams);
+        }
+        let config = DnsConfig {
+            admin,
+            registry,
+            namespace,
+            verifier,
+            max_proof_age,
+        };
+        write_entry(&env, &DataKey::Config, &config);
+    }
+
+    pub fn config(env: Env) -> DnsConfig {
+        read_config(&env)
+    }
+
+    /// Swaps the proof scheme; claims accepted under the old one are kept.
+    pub fn set_verifier(env: Env, admin: Address, verifier: Verifier) {
+        admin.require_auth();
+        let mut config = read_config(&env);
+        if admin != config.admin {
+            panic_with_error!(&env, DnsError::NotAdmin);
+        }
+        validate_verifier(&env, &verifier);
+        config.verifier = verifier.clone();
+        write_entry(&env, &DataKey::Config, &config);
+        EvtVerifierChanged { verifier }.publish(&env);
+    }
+
+    /// Gives `claimant` the namespace node for `domain` (e.g. `example.com`) once the
+    /// configured verifier accepts `proof`. Missing intermediate nodes (`com`) are kept by
+    /// this contract. A newer proof for the same domain moves the name to its claimant, so
+    /// a domain that changes hands in DNS can follow on-chain. Emits `dns_claimed`.
+    pub fn claim(
+        env: Env,
+        claimant: Address,
+        domain: Bytes,
+        issued_at: u64,
+        proof: Bytes,
+    ) -> BytesN<32> {
+        claimant.require_auth();
+        let config = read_config(&env);
+        let labels = split_domain(&env, &domain);
+        let now = env.ledger().timestamp();
+        if issued_at > now || now - issued_at > config.max_proof_age {
+            panic_with_error!(&env, DnsError::ProofExpired);
+        }
+        let claim_key = DataKey::Claim(domain.clone());
+        let previous: Option<Claim> = env.storage().persistent().get(&claim_key);
+        if previous.is_some_and(|claim| issued_at <= claim.issued_at) {
+            panic_with_error!(&env, DnsError::StaleProof);
+        }
+        let statement = ClaimStatement {
+            domain: domain.clone(),
+            claimant: claimant.clone(),
+            issued_at,
+        };
+        if !config.verifier.verify(&env, &statement, &proof) {
+            panic_with_error!(&env, DnsError::InvalidProof);
+        }
+
+        let this = env.current_contract_address();
+        let mut parent = config.namespace.clone();
+        let leaf = labels.len() - 1;
+        for (index, label) in labels.iter().enumerate() {
+            if index as u32 == leaf {
+                break;
+            }
+            let node = subnode(&env, &parent, &label);
+            match registry_api::owner(&env, &config.registry, &node) {
+                Some(owner) if owner == this => {}
+                Some(_) => panic_with_error!(&env, DnsError::ParentClaimed),
+                None => {
+                    registry_api::set_subnode_owner(&env, &config.registry, &parent, &label, &this);
+                }
+            }
+            parent = node;
+        }
+        let namehash = registry_api::set_subnode_owner(
+            &env,
+            &config.registry,
+            &parent,
+            &labels.get_unchecked(leaf),
+            &claimant,
+        );
+
+        let claim = Claim {
+            owner: claimant.clone(),
+            namehash: namehash.clone(),
+            issued_at,
+        };
+        write_entry(&env, &claim_key, &claim);
+        EvtDnsClaimed {
+            namehash: namehash.clone(),
+            domain,
+            owner: claimant,
+            issued_at,
+        }
+        .publish(&env);
+        namehash
+    }
+
+    pub fn claimed(env: Env, domain: Bytes) -> Option<Claim> {
+        env.storage().persistent().get(&DataKey::Claim(domain))
+    }
+
+    /// The message `Verifier::Oracles` keys sign for a claim.
+    pub fn claim_digest(env: Env, domain: Bytes, claimant: Address, issued_at: u64) -> BytesN<32> {
+        let statement = ClaimStatement {
+            domain,
+            claimant,
+            issued_at,
+        };
+        verifier::claim_digest(&env, &statement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::vec;
+
+    #[derive(Clone)]
+    #[contracttype]
+    enum MockKey {
+        Owner(BytesN<32>),
+        Accept,
+    }
+
+    /// Registry stand-in with owners and parent-authorized subnode assignment.
+    #[contract]
+    pub struct MockRegistry;
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockKey::Owner(namehash), &owner);
+        }
+
+        pub fn get_owner(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            env.storage().persistent().get(&MockKey::Owner(namehash))
+        }
+
+        pub fn set_subnode_owner(
+            env: Env,
+            parent: BytesN<32>,
+            label: Bytes,
+            owner: Address,
+        ) -> BytesN<32> {
+            let parent_owner: Address = env
+                .storage()
+                .persistent()
+                .get(&MockKey::Owner(parent.clone()))
+                .unwrap();
+            parent_owner.require_auth();
+            let node = subnode(&env, &parent, &label);
+            env.storage()
+                .persistent()
+                .set(&MockKey::Owner(node.clone()), &owner);
+            node
+        }
+    }
+
+    /// Proof scheme contract that accepts exactly the proof it was told to.
+    #[contract]
+    pub struct MockVerifier;
+
+    #[contractimpl]
+    impl MockVerifier {
+        pub fn set_accepted(env: Env, proof: Bytes) {
+            env.storage().instance().set(&MockKey::Accept, &proof);
+        }
+
+        pub fn verify(env: Env, _statement: ClaimStatement, proof: Bytes) -> bool {
+            env.storage().instance().get(&MockKey::Accept) == Some(proof)
+        }
+    }
+
+    struct Setup {
+        env: Env,
+        claims: DnsClaimClient<'static>,
+        registry: MockRegistryClient<'static>,
+        namespace: BytesN<32>,
+        admin: Address,
+        oracles: std::vec::Vec<SigningKey>,
+    }
+
+    const MAX_AGE: u64 = 3_600;
+
+    fn setup() -> Setup {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().set_timestamp(10_000);
+        let registry_id = env.register(MockRegistry, ());
+        let registry = MockRegistryClient::new(&env, &registry_id);
+        let namespace = subnode(
+            &env,
+            &BytesN::from_array(&env, &[0; 32]),
+            &Bytes::from_slice(&env, b"dns"),
+        );
+
+        let oracles: std::vec::Vec<SigningKey> = (1..=3u8)
+            .map(|seed| SigningKey::from_bytes(&[seed; 32]))
+            .collect();
+        let mut keys = Vec::new(&env);
+        for oracle in &oracles {
+            keys.push_back(BytesN::from_array(&env, &oracle.verifying_key().to_bytes()));
+        }
+        let admin = Address::generate(&env);
+        let verifier = Verifier::Oracles(OracleSet { keys, threshold: 2 });
+        let claims_id = env.register(
+            DnsClaim,
+            (&admin, &registry_id, &namespace, verifier, MAX_AGE),
+        );
+        let claims = DnsClaimClient::new(&env, &claims_id);
+        registry.set_owner(&namespace, &claims_id);
+        Setup {
+            env,
+            claims,
+            registry,
+            namespace,
+            admin,
+            oracles,
+        }
+    }
+
+    fn domain(s: &Setup, name: &str) -> Bytes {
+        Bytes::from_slice(&s.env, name.as_bytes())
+    }
+
+    /// Signatures from the oracles at `signers`, in the order given.
+    fn oracle_proof(
+        s: &Setup,
+        name: &Bytes,
+        claimant: &Address,
+        issued_at: u64,
+        signers: &[u8],
+    ) -> Bytes {
+        let digest = s.claims.claim_digest(name, claimant, &issued_at).to_array();
+        let mut proof = Bytes::new(&s.env);
+        for &index in signers {
+            let signature = s.oracles[index as usize].sign(&digest).to_bytes();
+            proof.push_back(index);
+            proof.extend_from_array(&signature);
+        }
+        proof
+    }
+
+    #[test]
+    fn oracle_threshold_proof_claims_domain_under_namespace() {
+        let s = setup();
+        let alice = Address::generate(&s.env);
+        let bob = Address::generate(&s.env);
+        let name = domain(&s, "example.com");
+        let com = subnode(&s.env, &s.namespace, &Bytes::from_slice(&s.env, b"com"));
+        let expected = subnode(&s.env, &com, &Bytes::from_slice(&s.env, b"example"));
+
+        let one_signer = oracle_proof(&s, &name, &alice, 9_000, &[0]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &9_000, &one_signer),
+            Err(Ok(DnsError::InvalidProof.into()))
+        );
+        let repeated = oracle_proof(&s, &name, &alice, 9_000, &[1, 1]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &9_000, &repeated),
+            Err(Ok(DnsError::InvalidProof.into()))
+        );
+        let too_old = oracle_proof(&s, &name, &alice, 1_000, &[0, 2]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &1_000, &too_old),
+            Err(Ok(DnsError::ProofExpired.into()))
+        );
+
+        let proof = oracle_proof(&s, &name, &alice, 9_000, &[0, 2]);
+        assert_eq!(s.claims.claim(&alice, &name, &9_000, &proof), expected);
+        assert_eq!(s.registry.get_owner(&expected), Some(alice.clone()));
+        assert_eq!(s.registry.get_owner(&com), Some(s.claims.address.clone()));
+        assert_eq!(
+            s.claims.claimed(&name),
+            Some(Claim {
+                owner: alice.clone(),
+                namehash: expected.clone(),
+                issued_at: 9_000,
+            })
+        );
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &9_000, &proof),
+            Err(Ok(DnsError::StaleProof.into()))
+        );
+
+        // The domain changed hands in DNS; a newer proof moves the name.
+        let newer = oracle_proof(&s, &name, &bob, 9_500, &[1, 2]);
+        s.claims.claim(&bob, &name, &9_500, &newer);
+        assert_eq!(s.registry.get_owner(&expected), Some(bob.clone()));
+        let other = domain(&s, "other.com");
+        let proof = oracle_proof(&s, &other, &alice, 9_500, &[0, 1]);
+        s.claims.claim(&alice, &other, &9_500, &proof);
+
+        // Subdomains of a claimed domain belong to its owner.
+        let sub = domain(&s, "shop.example.com");
+        let proof = oracle_proof(&s, &sub, &alice, 9_500, &[0, 1]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &sub, &9_500, &proof),
+            Err(Ok(DnsError::ParentClaimed.into()))
+        );
+    }
+
+    #[test]
+    fn contract_verifier_replaces_oracles_and_domains_are_validated() {
+        let s = setup();
+        let alice = Address::generate(&s.env);
+        let verifier_id = s.env.register(MockVerifier, ());
+        let dnssec = Bytes::from_slice(&s.env, b"dnssec-chain");
+        MockVerifierClient::new(&s.env, &verifier_id).set_accepted(&dnssec);
+
+        let outsider = Address::generate(&s.env);
+        let contract = Verifier::Contract(verifier_id.clone());
+        assert_eq!(
+            s.claims.try_set_verifier(&outsider, &contract),
+            Err(Ok(DnsError::NotAdmin.into()))
+        );
+        let unreachable = Verifier::Oracles(OracleSet {
+            keys: vec![&s.env, BytesN::from_array(&s.env, &[7; 32])],
+            threshold: 2,
+        });
+        assert_eq!(
+            s.claims.try_set_verifier(&s.admin, &unreachable),
+            Err(Ok(DnsError::InvalidParams.into()))
+        );
+        s.claims.set_verifier(&s.admin, &contract);
+        assert_eq!(s.claims.config().verifier, contract);
+
+        for bad in [
+            "com",
+            "Example.com",
+            "-bad.com",
+            "ex_ample.com",
+            "example..com",
+        ] {
+            assert_eq!(
+                s.claims
+                    .try_claim(&alice, &domain(&s, bad), &9_000, &dnssec),
+                Err(Ok(DnsError::InvalidDomain.into())),
+                "{bad}"
+            );
+        }
+        let name = domain(&s, "stellar.org");
+        assert_eq!(
+            s.claims
+                .try_claim(&alice, &name, &9_000, &Bytes::new(&s.env)),
+            Err(Ok(DnsError::InvalidProof.into()))
+        );
+        let namehash = s.claims.claim(&alice, &name, &9_000, &dnssec);
+        assert_eq!(s.registry.get_owner(&namehash), Some(alice));
+    }
+
+    #[test]
+    fn proofs_must_be_fresh_ordered_and_signed_for_the_claimant() {
+        let s = setup();
+        let alice = Address::generate(&s.env);
+        let mallory = Address::generate(&s.env);
+        let name = domain(&s, "example.net");
+
+        let future = oracle_proof(&s, &name, &alice, 10_001, &[0, 1]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &10_001, &future),
+            Err(Ok(DnsError::ProofExpired.into()))
+        );
+        let descending = oracle_proof(&s, &name, &alice, 9_000, &[2, 0]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &9_000, &descending),
+            Err(Ok(DnsError::InvalidProof.into()))
+        );
+        let mut unknown_key = oracle_proof(&s, &name, &alice, 9_000, &[0]);
+        unknown_key.push_back(3);
+        unknown_key.extend_from_array(&[0; 64]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &name, &9_000, &unknown_key),
+            Err(Ok(DnsError::InvalidProof.into()))
+        );
+        // A proof seen on-chain cannot be replayed for someone else.
+        let alices = oracle_proof(&s, &name, &alice, 9_000, &[0, 1]);
+        assert!(s
+            .claims
+            .try_claim(&mallory, &name, &9_000, &alices)
+            .is_err());
+
+        // An older proof than the current claim's is stale, but `MAX_AGE` itself is fresh.
+        let oldest = 10_000 - MAX_AGE;
+        let proof = oracle_proof(&s, &name, &alice, oldest + 1, &[0, 1]);
+        s.claims.claim(&alice, &name, &(oldest + 1), &proof);
+        let older = oracle_proof(&s, &name, &mallory, oldest, &[0, 1]);
+        assert_eq!(
+            s.claims.try_claim(&mallory, &name, &oldest, &older),
+            Err(Ok(DnsError::StaleProof.into()))
+        );
+        let other = domain(&s, "other.net");
+        let proof = oracle_proof(&s, &other, &alice, oldest, &[1, 2]);
+        s.claims.claim(&alice, &other, &oldest, &proof);
+        assert_eq!(s.claims.claimed(&other).unwrap().issued_at, oldest);
+    }
+
+    #[test]
+    fn intermediate_nodes_stay_with_the_contract_until_claimed() {
+        let s = setup();
+        let alice = Address::generate(&s.env);
+        let bob = Address::generate(&s.env);
+        let uk = subnode(&s.env, &s.namespace, &Bytes::from_slice(&s.env, b"uk"));
+        let co = subnode(&s.env, &uk, &Bytes::from_slice(&s.env, b"co"));
+
+        let deep = domain(&s, "shop.co.uk");
+        let proof = oracle_proof(&s, &deep, &alice, 9_000, &[0, 1]);
+        let shop = s.claims.claim(&alice, &deep, &9_000, &proof);
+        assert_eq!(s.registry.get_owner(&uk), Some(s.claims.address.clone()));
+        assert_eq!(s.registry.get_owner(&co), Some(s.claims.address.clone()));
+        assert_eq!(s.claims.claimed(&domain(&s, "co.uk")), None);
+
+        // A parent can still be claimed, which leaves the subdomain's claim alone...
+        let parent = domain(&s, "co.uk");
+        let proof = oracle_proof(&s, &parent, &bob, 9_000, &[1, 2]);
+        assert_eq!(s.claims.claim(&bob, &parent, &9_000, &proof), co);
+        assert_eq!(s.registry.get_owner(&shop), Some(alice.clone()));
+
+        // ...but nothing under it can be claimed through the DNS again.
+        let sibling = domain(&s, "store.co.uk");
+        let proof = oracle_proof(&s, &sibling, &alice, 9_000, &[0, 2]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &sibling, &9_000, &proof),
+            Err(Ok(DnsError::ParentClaimed.into()))
+        );
+        let newer = oracle_proof(&s, &deep, &alice, 9_500, &[0, 1]);
+        assert_eq!(
+            s.claims.try_claim(&alice, &deep, &9_500, &newer),
+            Err(Ok(DnsError::ParentClaimed.into()))
+        );
+    }
+}
@@ -0,0 +1,785 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    BytesN, Env, IntoVal, Symbol, Val,
+};
+
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+const MAX_BPS: u32 = 10_000;
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Config,
+    Listing(BytesN<32>),
+    Offer(BytesN<32>, Address),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MarketConfig {
+    pub admin: Address,
+    pub registry: Address,
+    /// Receives the protocol fee on every sale.
+    pub fee_recipient: Address,
+    /// Protocol fee in basis points of the sale price, taken from the seller's proceeds.
+    pub fee_bps: u32,
+}
+
+/// A fixed-price sale. The seller keeps the name until someone buys it; the marketplace
+/// must be its Registry delegate or operator so `buy` can transfer it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Listing {
+    pub seller: Address,
+    pub token: Address,
+    pub price: i128,
+}
+
+/// A bid for a name whose `amount` is held in escrow by the marketplace until it is
+/// accepted or withdrawn.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Offer {
+    pub token: Address,
+    pub amount: i128,
+    /// The offer can no longer be accepted after this timestamp; it can still be withdrawn.
+    pub expires_at: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["listed"])]
+pub struct EvtListed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub seller: Address,
+    pub token: Address,
+    pub price: i128,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["listing_cancelled"])]
+pub struct EvtListingCancelled {
+    #[topic]
+    pub namehash: BytesN<32>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["offer_made"])]
+pub struct EvtOfferMade {
+    #[topic]
+    pub namehash: BytesN<32>,
+    #[topic]
+    pub buyer: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub expires_at: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["offer_withdrawn"])]
+pub struct EvtOfferWithdrawn {
+    #[topic]
+    pub namehash: BytesN<32>,
+    #[topic]
+    pub buyer: Address,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["sold"])]
+pub struct EvtSold {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub seller: Address,
+    pub buyer: Address,
+    pub token: Address,
+    pub price: i128,
+    pub fee: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum MarketError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    /// `fee_bps` above 10 000.
+    InvalidParams = 3,
+    NotAdmin = 4,
+    /// The caller is not the name's Registry owner, or not the seller of the listing. A name
+    /// whose grace period has ended has no owner.
+    NotOwner = 5,
+    InvalidAmount = 6,
+    /// `list` was called before the marketplace was approved to transfer the name.
+    NotApproved = 7,
+    ListingNotFound = 8,
+    /// `buy` was called with a price other than the listed one.
+    PriceMismatch = 9,
+    /// The name changed hands, or its grace period ended, after it was listed.
+    ListingStale = 10,
+    OfferExists = 11,
+    OfferNotFound = 12,
+    OfferExpired = 13,
+}
+
+/// Fixed-price listings and escrowed offers for names. Payment and the Registry transfer
+/// settle in the same transaction, less an optional protocol fee.
+#[contract]
+pub struct Marketplace;
+
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn read_config(env: &Env) -> MarketConfig {
+    let storage = env.storage().persistent();
+    let config = storage
+        .get(&DataKey::Config)
+        .unwrap_or_else(|| panic_with_error!(env, MarketError::NotInitialized));
+    storage.extend_ttl(&DataKey::Config, TTL_THRESHOLD, TTL_EXTEND_TO);
+    config
+}
+
+fn read_listing(env: &Env, namehash: &BytesN<32>) -> Listing {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Listing(namehash.clone()))
+        .unwrap_or_else(|| panic_with_error!(env, MarketError::ListingNotFound))
+}
+
+fn read_offer(env: &Env, namehash: &BytesN<32>, buyer: &Address) -> Offer {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Offer(namehash.clone(), buyer.clone()))
+        .unwrap_or_else(|| panic_with_error!(env, MarketError::OfferNotFound))
+}
+
+fn validate_fee(env: &Env, fee_bps: u32) {
+    if fee_bps > MAX_BPS {
+        panic_with_error!(env, MarketError::InvalidParams);
+    }
+}
+
+fn ensure_owner(env: &Env, config: &MarketConfig, caller: &Address, namehash: &BytesN<32>) {
+    if registry_api::owner(env, &config.registry, namehash).as_ref() != Some(caller) {
+        panic_with_error!(env, MarketError::NotOwner);
+    }
+}
+
+/// Protocol fee on `price`, rounded down.
+fn fee_on(env: &Env, config: &MarketConfig, price: i128) -> i128 {
+    price
+        .checked_mul(config.fee_bps as i128)
+        .unwrap_or_else(|| panic_with_error!(env, MarketError::InvalidAmount))
+        / MAX_BPS as i128
+}
+
+/// Moves `price` of `token` from `from` to the seller and fee recipient; returns the fee.
+fn settle_payment(
+    env: &Env,
+    config: &MarketConfig,
+    token: &Address,
+    from: &Address,
+    seller: &Address,
+    price: i128,
+) -> i128 {
+    let fee = fee_on(env, config, price);
+    let token = TokenClient::new(env, token);
+    if fee > 0 {
+        token.transfer(from, &config.fee_recipient, &fee);
+    }
+    if price > fee {
+        token.transfer(from, seller, &(price - fee));
+    }
+    fee
+}
+
+mod registry_api {
+    use super::*;
+
+    /// The name's owner, or `None` once its grace period has ended.
+    pub fn owner(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "owner_checked"),
+            (namehash.clone(),).into_val(env),
+        )
+    }
+
+    pub fn approved(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "get_approved"),
+            (namehash.clone(),).into_val(env),
+        )
+    }
+
+    pub fn is_operator(env: &Env, registry: &Address, owner: &Address) -> bool {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "is_approved_for_all"),
+            (owner.clone(), env.current_contract_address()).into_val(env),
+        )
+    }
+
+    pub fn transfer(
+        env: &Env,
+        registry: &Address,
+        caller: &Address,
+        namehash: &BytesN<32>,
+        to: &Address,
+    ) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "transfer"),
+            (caller.clone(), namehash.clone(), to.clone()).into_val(env),
+        );
+    }
+}
+
+#[contractimpl]
+impl Marketplace {
+    /// Runs once, at deployment, so nobody can configure the market before its deployer.
+    /// `fee_bps` of every sale is paid to `fee_recipient`; `0` disables the fee.
+    pub fn __constructor(
+        env: Env,
+        admin: Address,
+        registry: Address,
+        fee_recipient: Address,
+        fee_bps: u32,
+    ) {
+        validate_fee(&env, fee_bps);
+        let config = MarketConfig {
+            admin,
+            registry,
+            fee_recipient,
+            fee_bps,
+        };
+        write_entry(&env, &DataKey::Config, &config);
+    }
+
+    pub fn config(env: Env) -> MarketConfig {
+        read_config(&env)
+    }
+
+    /// Admin-only: change the protocol fee and where it is paid.
+    pub fn set_fee(env: Env, admin: Address, fee_recipient: Address, fee_bps: u32) {
+        admin.require_auth();
+        let mut config = read_config(&env);
+        if config.admin != admin {
+            panic_with_error!(&env, MarketError::NotAdmin);
+        }
+        validate_fee(&env, fee_bps);
+        config.fee_recipient = fee_recipient;
+        config.fee_bps = fee_bps;
+        write_entry(&env, &DataKey::Config, &config);
+    }
+
+    /// Lists `namehash` for `price` of `token`, replacing any earlier listing. The seller
+    /// must own the name and have approved this contract in the Registry, either for the
+    /// name (`approve`) or for all their names (`set_approval_for_all`).
+    pub fn list(env: Env, seller: Address, namehash: BytesN<32>, token: Address, price: i128) {
+        seller.require_auth();
+        let config = read_config(&env);
+        ensure_owner(&env, &config, &seller, &namehash);
+        if price <= 0 {
+            panic_with_error!(&env, MarketError::InvalidAmount);
+        }
+        let this = env.current_contract_address();
+        if registry_api::approved(&env, &config.registry, &namehash) != Some(this)
+            && !registry_api::is_operator(&env, &config.registry, &seller)
+        {
+            panic_with_error!(&env, MarketError::NotApproved);
+        }
+        let listing = Listing {
+            seller: seller.clone(),
+            token: token.clone(),
+            price,
+        };
+        write_entry(&env, &DataKey::Listing(namehash.clone()), &listing);
+        EvtListed {
+            namehash,
+            seller,
+            token,
+            price,
+        }
+        .publish(&env);
+    }
+
+    pub fn cancel_listing(env: Env, seller: Address, namehash: BytesN<32>) {
+        seller.require_auth();
+        let listing = read_listing(&env, &namehash);
+        if listing.seller != seller {
+            panic_with_error!(&env, MarketError::NotOwner);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Listing(namehash.clone()));
+        EvtListingCancelled { namehash }.publish(&env);
+    }
+
+    /// Buys a listed name. `price` must match the listing, so a seller cannot reprice
+    /// between the buyer signing and the transaction landing.
+    pub fn buy(env: Env, buyer: Address, namehash: BytesN<32>, price: i128) {
+        buyer.require_auth();
+        let config = read_config(&env);
+        let listing = read_listing(&env, &namehash);
+        if listing.price != price {
+            panic_with_error!(&env, MarketError::PriceMismatch);
+        }
+        if registry_api::owner(&env, &config.registry, &namehash) != Some(listing.seller.clone()) {
+            panic_with_error!(&env, MarketError::ListingStale);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Listing(namehash.clone()));
+        let fee = settle_payment(
+            &env,
+            &config,
+            &listing.token,
+            &buyer,
+            &listing.seller,
+            price,
+        );
+        let this = env.current_contract_address();
+        registry_api::transfer(&env, &config.registry, &this, &namehash, &buyer);
+        EvtSold {
+            namehash,
+            seller: listing.seller,
+            buyer,
+            token: listing.token,
+            price,
+            fee,
+        }
+        .publish(&env);
+    }
+
+    /// Offers `amount` of `token` for `namehash` until `expires_at`. The amount is moved into
+    /// escrow now and refunded by `withdraw_offer`. One open offer per buyer and name.
+    pub fn make_offer(
+        env: Env,
+        buyer: Address,
+        namehash: BytesN<32>,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+    ) {
+        buyer.require_auth();
+        read_config(&env);
+        if amount <= 0 || expires_at <= env.ledger().timestamp() {
+            panic_with_error!(&env, MarketError::InvalidAmount);
+        }
+        let key = DataKey::Offer(namehash.clone(), buyer.clone());
+        if env.storage().persistent().has(&key) {
+            panic_with_error!(&env, MarketError::OfferExists);
+        }
+        TokenClient::new(&env, &token).transfer(&buyer, env.current_contract_address(), &amount);
+        let offer = Offer {
+            token: token.clone(),
+            amount,
+            expires_at,
+        };
+        write_entry(&env, &key, &offer);
+        EvtOfferMade {
+            namehash,
+            buyer,
+            token,
+            amount,
+            expires_at,
+        }
+        .publish(&env);
+    }
+
+    /// Refunds an open offer, expired or not.
+    pub fn withdraw_offer(env: Env, buyer: Address, namehash: BytesN<32>) {
+        buyer.require_auth();
+        let offer = read_offer(&env, &namehash, &buyer);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Offer(namehash.clone(), buyer.clone()));
+        TokenClient::new(&env, &offer.token).transfer(
+            &env.current_contract_address(),
+            &buyer,
+            &offer.amount,
+        );
+        EvtOfferWithdrawn { namehash, buyer }.publish(&env);
+    }
+
+    /// Sells `namehash` to `buyer` for their escrowed offer. The seller's authorization also
+    /// covers the Registry transfer, so no approval is needed. Any listing is cleared.
+    pub fn accept_offer(env: Env, seller: Address, namehash: BytesN<32>, buyer: Address) {
+        seller.require_auth();
+        let config = read_config(&env);
+        ensure_owner(&env, &config, &seller, &namehash);
+        let offer = read_offer(&env, &namehash, &buyer);
+        if env.ledger().timestamp() > offer.expires_at {
+            panic_with_error!(&env, MarketError::OfferExpired);
+        }
+        let storage = env.storage().persistent();
+        storage.remove(&DataKey::Offer(namehash.clone(), buyer.clone()));
+        storage.remove(&DataKey::Listing(namehash.clone()));
+        let fee = settle_payment(
+            &env,
+            &config,
+            &offer.token,
+            &env.current_contract_address(),
+            &seller,
+            offer.amount,
+        );
+        registry_api::transfer(&env, &config.registry, &seller, &namehash, &buyer);
+        EvtSold {
+            namehash,
+            seller,
+            buyer,
+            token: offer.token,
+            price: offer.amount,
+            fee,
+        }
+        .publish(&env);
+    }
+
+    pub fn listing(env: Env, namehash: BytesN<32>) -> Option<Listing> {
+        env.storage().persistent().get(&DataKey::Listing(namehash))
+    }
+
+    pub fn offer(env: Env, namehash: BytesN<32>, buyer: Address) -> Option<Offer> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Offer(namehash, buyer))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use registry::{Registry, RegistryClient};
+    use soroban_sdk::testutils::{Address as _, Ledger};
+    use soroban_sdk::token::StellarAssetClient;
+    use soroban_sdk::Bytes;
+
+    /// Registration term given to every test name.
+    const TERM: u64 = 100_000;
+    /// The Registry's default grace period after a name's expiry.
+    const GRACE_PERIOD_SECONDS: u64 = 7_776_000;
+
+    struct Setup {
+        env: Env,
+        market: MarketplaceClient<'static>,
+        registry: RegistryClient<'static>,
+        /// Approved controller that owns `tld` and registers the test names.
+        registrar: Address,
+        tld: BytesN<32>,
+        token: TokenClient<'static>,
+        fee_recipient: Address,
+    }
+
+    fn setup() -> Setup {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let registry_id = env.register(Registry, (&admin, &admin));
+        let registry = RegistryClient::new(&env, &registry_id);
+        let registrar = Address::generate(&env);
+        let tld = registry.create_tld(&admin, &Bytes::from_slice(&env, b"stellar"), &registrar);
+        registry.set_controller(&registrar, &true);
+        let token_admin = Address::generate(&env);
+        let token_id = env
+            .register_stellar_asset_contract_v2(token_admin)
+            .address();
+        let fee_recipient = Address::generate(&env);
+        // 2.5% protocol fee.
+        let market_id = env.register(
+            Marketplace,
+            (
+                Address::generate(&env),
+                &registry_id,
+                &fee_recipient,
+                250u32,
+            ),
+        );
+        let market = MarketplaceClient::new(&env, &market_id);
+        Setup {
+            registry,
+            registrar,
+            tld,
+            token: TokenClient::new(&env, &token_id),
+            market,
+            fee_recipient,
+            env,
+        }
+    }
+
+    fn funded(s: &Setup, amount: i128) -> Address {
+        let account = Address::generate(&s.env);
+        StellarAssetClient::new(&s.env, &s.token.address).mint(&account, &amount);
+        account
+    }
+
+    /// Registers `name<seed>.stellar` to `owner` until `TERM`.
+    fn name_owned_by(s: &Setup, seed: u8, owner: &Address) -> BytesN<32> {
+        let label = Bytes::from_array(&s.env, &[b'n', b'a', b'm', b'e', b'0' + seed]);
+        let namehash = s.registry.set_owner(&s.registrar, &s.tld, &label, owner);
+        s.registry.set_expiry(&s.registrar, &namehash, &TERM);
+        namehash
+    }
+
+    #[test]
+    fn buy_settles_payment_fee_and_transfer_atomically() {
+        let s = setup();
+        let seller = Address::generate(&s.env);
+        let buyer = funded(&s, 1_000);
+        let namehash = name_owned_by(&s, 1, &seller);
+
+        assert_eq!(
+            s.market
+                .try_list(&seller, &namehash, &s.token.address, &1_000),
+            Err(Ok(MarketError::NotApproved.into()))
+        );
+        s.registry.approve(&namehash, &s.market.address);
+        assert_eq!(
+            s.market
+                .try_list(&buyer, &namehash, &s.token.address, &1_000),
+            Err(Ok(MarketError::NotOwner.into()))
+        );
+        s.market.list(&seller, &namehash, &s.token.address, &1_000);
+        assert_eq!(s.market.listing(&namehash).unwrap().price, 1_000);
+
+        assert_eq!(
+            s.market.try_buy(&buyer, &namehash, &900),
+            Err(Ok(MarketError::PriceMismatch.into()))
+        );
+        s.market.buy(&buyer, &namehash, &1_000);
+        assert_eq!(s.registry.get_owner(&namehash), Some(buyer.clone()));
+        assert_eq!(s.token.balance(&seller), 975);
+        assert_eq!(s.token.balance(&s.fee_recipient), 25);
+        assert_eq!(s.token.balance(&buyer), 0);
+        assert_eq!(s.market.listing(&namehash), None);
+        assert_eq!(
+            s.market.try_buy(&buyer, &namehash, &1_000),
+            Err(Ok(MarketError::ListingNotFound.into()))
+        );
+
+        // A listing left behind after the name moves elsewhere cannot be bought.
+        s.registry.approve(&namehash, &s.market.address);
+        s.market.list(&buyer, &namehash, &s.token.address, &10);
+        s.registry.transfer(&buyer, &namehash, &seller);
+        let late = funded(&s, 10);
+        assert_eq!(
+            s.market.try_buy(&late, &namehash, &10),
+            Err(Ok(MarketError::ListingStale.into()))
+        );
+    }
+
+    #[test]
+    fn offers_are_escrowed_until_accepted_or_withdrawn() {
+        let s = setup();
+        let seller = Address::generate(&s.env);
+        let namehash = name_owned_by(&s, 2, &seller);
+        let high = funded(&s, 400);
+        let low = funded(&s, 100);
+
+        s.market
+            .make_offer(&high, &namehash, &s.token.address, &400, &1_000);
+        s.market
+            .make_offer(&low, &namehash, &s.token.address, &100, &1_000);
+        assert_eq!(s.token.balance(&s.market.address), 500);
+        assert_eq!(
+            s.market
+                .try_make_offer(&low, &namehash, &s.token.address, &100, &1_000),
+            Err(Ok(MarketError::OfferExists.into()))
+        );
+
+        s.market.withdraw_offer(&low, &namehash);
+        assert_eq!(s.token.balance(&low), 100);
+        assert_eq!(s.market.offer(&namehash, &low), None);
+
+        assert_eq!(
+            s.market.try_accept_offer(&high, &namehash, &high),
+            Err(Ok(MarketError::NotOwner.into()))
+        );
+        s.market.accept_offer(&seller, &namehash, &high);
+        assert_eq!(s.registry.get_owner(&namehash), Some(high.clone()));
+        assert_eq!(s.token.balance(&seller), 390);
+        assert_eq!(s.token.balance(&s.fee_recipient), 10);
+        assert_eq!(s.token.balance(&s.market.address), 0);
+
+        // Expired offers can only be withdrawn.
+        let other = name_owned_by(&s, 3, &seller);
+        let late = funded(&s, 50);
+        s.market
+            .make_offer(&late, &other, &s.token.address, &50, &1_000);
+        s.env.ledger().set_timestamp(1_001);
+        assert_eq!(
+            s.market.try_accept_offer(&seller, &other, &late),
+            Err(Ok(MarketError::OfferExpired.into()))
+        );
+        s.market.withdraw_offer(&late, &other);
+        assert_eq!(s.token.balance(&late), 50);
+    }
+
+    #[test]
+    fn only_the_admin_sets_fees_and_only_sellers_cancel() {
+        let s = setup();
+        let admin = s.market.config().admin;
+        let stranger = Address::generate(&s.env);
+        assert_eq!(
+            s.market.try_set_fee(&stranger, &stranger, &0),
+            Err(Ok(MarketError::NotAdmin.into()))
+        );
+        assert_eq!(
+            s.market
+                .try_set_fee(&admin, &s.fee_recipient, &(MAX_BPS + 1)),
+            Err(Ok(MarketError::InvalidParams.into()))
+        );
+        let treasury = Address::generate(&s.env);
+        s.market.set_fee(&admin, &treasury, &0);
+        assert_eq!(s.market.config().fee_recipient, treasury);
+        assert_eq!(s.market.config().fee_bps, 0);
+
+        let seller = Address::generate(&s.env);
+        let namehash = name_owned_by(&s, 4, &seller);
+        s.registry.approve(&namehash, &s.market.address);
+        assert_eq!(
+            s.market.try_list(&seller, &namehash, &s.token.address, &0),
+            Err(Ok(MarketError::InvalidAmount.into()))
+        );
+        s.market.list(&seller, &namehash, &s.token.address, &100);
+        assert_eq!(
+            s.market.try_cancel_listing(&stranger, &namehash),
+            Err(Ok(MarketError::NotOwner.into()))
+        );
+        s.market.cancel_listing(&seller, &namehash);
+        let buyer = funded(&s, 200);
+        assert_eq!(
+            s.market.try_buy(&buyer, &namehash, &100),
+            Err(Ok(MarketError::ListingNotFound.into()))
+        );
+        assert_eq!(
+            s.market.try_cancel_listing(&seller, &namehash),
+            Err(Ok(MarketError::ListingNotFound.into()))
+        );
+
+        // Relisting replaces the price, and without a fee the seller keeps it all.
+        s.market.list(&seller, &namehash, &s.token.address, &100);
+        s.market.list(&seller, &namehash, &s.token.address, &200);
+        assert_eq!(
+            s.market.try_buy(&buyer, &namehash, &100),
+            Err(Ok(MarketError::PriceMismatch.into()))
+        );
+        s.market.buy(&buyer, &namehash, &200);
+        assert_eq!(s.token.balance(&seller), 200);
+        assert_eq!(s.token.balance(&treasury), 0);
+    }
+
+    #[test]
+    fn offers_reject_bad_terms_and_outlive_other_sales() {
+        let s = setup();
+        s.env.ledger().set_timestamp(500);
+        let seller = Address::generate(&s.env);
+        let namehash = name_owned_by(&s, 5, &seller);
+        let buyer = funded(&s, 300);
+        let rival = funded(&s, 100);
+        assert_eq!(
+            s.market
+                .try_make_offer(&buyer, &namehash, &s.token.address, &0, &1_000),
+            Err(Ok(MarketError::InvalidAmount.into()))
+        );
+        assert_eq!(
+            s.market
+                .try_make_offer(&buyer, &namehash, &s.token.address, &300, &500),
+            Err(Ok(MarketError::InvalidAmount.into()))
+        );
+        assert_eq!(
+            s.market.try_withdraw_offer(&buyer, &namehash),
+            Err(Ok(MarketError::OfferNotFound.into()))
+        );
+        assert_eq!(
+            s.market.try_accept_offer(&seller, &namehash, &buyer),
+            Err(Ok(MarketError::OfferNotFound.into()))
+        );
+
+        s.registry.approve(&namehash, &s.market.address);
+        s.market.list(&seller, &namehash, &s.token.address, &1_000);
+        s.market
+            .make_offer(&buyer, &namehash, &s.token.address, &300, &1_000);
+        s.market
+            .make_offer(&rival, &namehash, &s.token.address, &100, &1_000);
+
+        // The offer is still good at its expiry, and accepting it clears the listing.
+        s.env.ledger().set_timestamp(1_000);
+        s.market.accept_offer(&seller, &namehash, &buyer);
+        assert_eq!(s.registry.get_owner(&namehash), Some(buyer.clone()));
+        assert_eq!(s.market.listing(&namehash), None);
+
+        // The losing offer stays in escrow for its buyer, and only the new owner may accept.
+        assert_eq!(
+            s.market.try_accept_offer(&seller, &namehash, &rival),
+            Err(Ok(MarketError::NotOwner.into()))
+        );
+        assert_eq!(s.token.balance(&s.market.address), 100);
+        s.market.withdraw_offer(&rival, &namehash);
+        assert_eq!(s.token.balance(&rival), 100);
+    }
+
+    #[test]
+    fn operators_can_sell_any_of_the_owners_names() {
+        let s = setup();
+        let seller = Address::generate(&s.env);
+        let namehash = name_owned_by(&s, 6, &seller);
+        s.registry
+            .set_approval_for_all(&seller, &s.market.address, &true);
+        s.market.list(&seller, &namehash, &s.token.address, &100);
+
+        let buyer = funded(&s, 100);
+        s.market.buy(&buyer, &namehash, &100);
+        assert_eq!(s.registry.get_owner(&namehash), Some(buyer));
+        assert_eq!(s.token.balance(&seller), 98);
+    }
+
+    #[test]
+    fn names_past_their_grace_period_cannot_be_sold() {
+        let s = setup();
+        let seller = Address::generate(&s.env);
+        let listed = name_owned_by(&s, 7, &seller);
+        let offered = name_owned_by(&s, 8, &seller);
+        s.registry
+            .set_approval_for_all(&seller, &s.market.address, &true);
+        s.market.list(&seller, &listed, &s.token.address, &100);
+        let buyer = funded(&s, 200);
+        s.market
+            .make_offer(&buyer, &offered, &s.token.address, &100, &(TERM * 1_000));
+
+        // During the grace period the seller still owns the names.
+        let grace_end = TERM + GRACE_PERIOD_SECONDS;
+        s.env.ledger().set_timestamp(grace_end);
+        s.market.list(&seller, &listed, &s.token.address, &100);
+
+        s.env.ledger().set_timestamp(grace_end + 1);
+        assert_eq!(s.registry.get_owner(&listed), Some(seller.clone()));
+        assert_eq!(
+            s.market.try_list(&seller, &listed, &s.token.address, &100),
+            Err(Ok(MarketError::NotOwner.into()))
+        );
+        assert_eq!(
+            s.market.try_buy(&buyer, &listed, &100),
+            Err(Ok(MarketError::ListingStale.into()))
+        );
+        assert_eq!(
+            s.market.try_accept_offer(&seller, &offered, &buyer),
+            Err(Ok(MarketError::NotOwner.into()))
+        );
+        assert_eq!(s.token.balance(&buyer), 100);
+        s.market.withdraw_offer(&buyer, &offered);
+        assert_eq!(s.token.balance(&buyer), 200);
+    }
+}
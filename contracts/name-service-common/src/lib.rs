@@ -0,0 +1,106 @@
+#![no_std]
+
+//! Hashing shared by the contracts and by host-side tooling and tests.
+//!
+//! Every node in the Registry is derived with [`namehash`], and the Registrar's commit-reveal
+//! scheme with [`compute_commitment`]. Contracts that derive nodes or commitments themselves
+//! call these rather than re-implementing them, so the derivations cannot drift apart. Input
+//! validation (label length, normalization) stays with the callers.
+
+use soroban_sdk::xdr::ToXdr;
+use soroban_sdk::{Address, Bytes, BytesN, Env, Vec};
+
+/// Node of the empty name, the parent of every TLD.
+pub fn root_node(env: &Env) -> BytesN<32> {
+    BytesN::from_array(env, &[0u8; 32])
+}
+
+/// `sha256(parent || label_hash)`: the node of a child whose label already hashes to
+/// `label_hash`.
+pub fn fold_hash(env: &Env, parent: &BytesN<32>, label_hash: &BytesN<32>) -> BytesN<32> {
+    let mut data = Bytes::from_array(env, &parent.to_array());
+    data.extend_from_array(&label_hash.to_array());
+    env.crypto().sha256(&data).to_bytes()
+}
+
+/// `sha256(parent || sha256(label))`: the node of `label` under `parent`.
+pub fn subnode(env: &Env, parent: &BytesN<32>, label: &Bytes) -> BytesN<32> {
+    let label_hash = env.crypto().sha256(label).to_bytes();
+    fold_hash(env, parent, &label_hash)
+}
+
+/// Node of a name given as labels from the TLD down, so `["stellar", "alice"]` is
+/// `alice.stellar`. No labels yields [`root_node`].
+pub fn namehash(env: &Env, labels: &Vec<Bytes>) -> BytesN<32> {
+    let mut node = root_node(env);
+    for label in labels.iter() {
+        node = subnode(env, &node, &label);
+    }
+    node
+}
+
+/// Version 1 commitment: `sha256(label || owner xdr || secret || committer xdr)`. Binding the
+/// committer means a revealed preimage is useless to anyone but the address that committed it.
+pub fn compute_commitment(
+    env: &Env,
+    label: &Bytes,
+    owner: &Address,
+    secret: &Bytes,
+    committer: &Address,
+) -> BytesN<32> {
+    let mut data = Bytes::new(env);
+    data.append(label);
+    data.append(&owner.clone().to_xdr(env));
+    data.append(secret);
+    data.append(&committer.clone().to_xdr(env));
+    env.crypto().sha256(&data).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::vec;
+
+    #[test]
+    fn namehash_folds_labels_from_the_tld_down() {
+        let env = Env::default();
+        let tld = Bytes::from_slice(&env, b"stellar");
+        let label = Bytes::from_slice(&env, b"alice");
+
+        assert_eq!(namehash(&env, &Vec::new(&env)), root_node(&env));
+        let tld_node = namehash(&env, &vec![&env, tld.clone()]);
+        assert_eq!(tld_node, subnode(&env, &root_node(&env), &tld));
+        let name = namehash(&env, &vec![&env, tld, label.clone()]);
+        let label_hash = env.crypto().sha256(&label).to_bytes();
+        assert_eq!(name, fold_hash(&env, &tld_node, &label_hash));
+        assert_ne!(name, tld_node);
+    }
+
+    #[test]
+    fn commitment_binds_every_input() {
+        let env = Env::default();
+        let label = Bytes::from_slice(&env, b"alice");
+        let secret = Bytes::from_slice(&env, b"secret");
+        let owner = Address::generate(&env);
+        let committer = Address::generate(&env);
+
+        let commitment = compute_commitment(&env, &label, &owner, &secret, &committer);
+        assert_eq!(
+            commitment,
+            compute_commitment(&env, &label, &owner, &secret, &committer)
+        );
+        assert_ne!(
+            commitment,
+            compute_commitment(&env, &label, &owner, &secret, &owner)
+        );
+        assert_ne!(
+            commitment,
+            compute_commitment(&env, &label, &committer, &secret, &committer)
+        );
+        assert_ne!(
+            commitment,
+            compute_commitment(&env, &label, &owner, &label, &committer)
+        );
+    }
+}
@@ -0,0 +1,589 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use name_service_common::subnode;
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, IntoVal, Symbol, Val,
+};
+
+/// Fuses are bits that can only ever be set. Burning any fuse other than `CANNOT_UNWRAP`
+/// (and a child's `PARENT_CANNOT_CONTROL`) requires `CANNOT_UNWRAP` to be burned as well,
+/// otherwise the holder could unwrap and bypass them.
+pub const CANNOT_UNWRAP: u32 = 1;
+pub const CANNOT_BURN_FUSES: u32 = 1 << 1;
+pub const CANNOT_TRANSFER: u32 = 1 << 2;
+pub const CANNOT_SET_RESOLVER: u32 = 1 << 3;
+pub const CANNOT_CREATE_SUBDOMAIN: u32 = 1 << 4;
+/// Set by the parent when creating a child; the parent can no longer replace its holder.
+pub const PARENT_CANNOT_CONTROL: u32 = 1 << 5;
+const ALL_FUSES: u32 = (1 << 6) - 1;
+
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Registry,
+    Wrapped(BytesN<32>),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WrappedName {
+    pub owner: Address,
+    pub fuses: u32,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["wrapped"])]
+pub struct EvtWrapped {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub fuses: u32,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["unwrapped"])]
+pub struct EvtUnwrapped {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["fuses_burned"])]
+pub struct EvtFusesBurned {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub fuses: u32,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["wrapped_transfer"])]
+pub struct EvtWrappedTransfer {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub from: Address,
+    pub to: Address,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum NameWrapperError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    NotOwner = 3,
+    NotWrapped = 4,
+    /// A burned fuse forbids the operation.
+    FuseBurned = 5,
+    InvalidFuses = 6,
+}
+
+/// Holds names in the Registry on behalf of their owners and enforces irrevocable fuses on
+/// every Registry mutation made through it. While wrapped, the Registry owner is this
+/// contract, so the holder can only act on the name here.
+#[contract]
+pub struct NameWrapper;
+
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn read_registry(env: &Env) -> Address {
+    let storage = env.storage().persistent();
+    let registry = storage
+        .get(&DataKey::Registry)
+        .unwrap_or_else(|| panic_with_error!(env, NameWrapperError::NotInitialized));
+    storage.extend_ttl(&DataKey::Registry, TTL_THRESHOLD, TTL_EXTEND_TO);
+    registry
+}
+
+/// The wrap record for `namehash`, if the Registry still has this contract as its live
+/// owner. Records left behind after a name lapsed and was re-registered are ignored.
+fn read_wrapped(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<WrappedName> {
+    let record: WrappedName = env
+        .storage()
+        .persistent()
+        .get(&DataKey::Wrapped(namehash.clone()))?;
+    let owner: Option<Address> = env.invoke_contract(
+        registry,
+        &Symbol::new(env, "owner_checked"),
+        (namehash.clone(),).into_val(env),
+    );
+    (owner == Some(env.current_contract_address())).then_some(record)
+}
+
+/// Loads `namehash`'s record, requiring `caller` to authorize and hold it.
+fn require_holder(
+    env: &Env,
+    registry: &Address,
+    caller: &Address,
+    namehash: &BytesN<32>,
+) -> WrappedName {
+    caller.require_auth();
+    let record = read_wrapped(env, registry, namehash)
+        .unwrap_or_else(|| panic_with_error!(env, NameWrapperError::NotWrapped));
+    if record.owner != *caller {
+        panic_with_error!(env, NameWrapperError::NotOwner);
+    }
+    record
+}
+
+fn require_fuse_intact(env: &Env, record: &WrappedName, fuse: u32) {
+    if record.fuses & fuse != 0 {
+        panic_with_error!(env, NameWrapperError::FuseBurned);
+    }
+}
+
+/// Rejects unknown bits and restrictive fuses that `CANNOT_UNWRAP` would not back up.
+fn validate_fuses(env: &Env, fuses: u32) {
+    let restrictive = fuses & !(CANNOT_UNWRAP | PARENT_CANNOT_CONTROL);
+    if fuses & !ALL_FUSES != 0 || (restrictive != 0 && fuses & CANNOT_UNWRAP == 0) {
+        panic_with_error!(env, NameWrapperError::InvalidFuses);
+    }
+}
+
+#[contractimpl]
+impl NameWrapper {
+    pub fn init(env: Env, registry: Address) {
+        if env.storage().persistent().has(&DataKey::Registry) {
+            panic_with_error!(&env, NameWrapperError::AlreadyInitialized);
+        }
+        write_entry(&env, &DataKey::Registry, &registry);
+    }
+
+    pub fn registry(env: Env) -> Address {
+        read_registry(&env)
+    }
+
+    /// Moves Registry ownership of `namehash` from `owner` to this contract and records
+    /// `owner` as its holder with no fuses burned. `owner` authorizes the nested
+    /// `registry.transfer`. Emits `EvtWrapped`.
+    pub fn wrap(env: Env, owner: Address, namehash: BytesN<32>) {
+        owner.require_auth();
+        let registry = read_registry(&env);
+        env.invoke_contract::<()>(
+            &registry,
+            &Symbol::new(&env, "transfer"),
+            (
+                owner.clone(),
+                namehash.clone(),
+                env.current_contract_address(),
+            )
+                .into_val(&env),
+        );
+        let record = WrappedName {
+            owner: owner.clone(),
+            fuses: 0,
+        };
+        write_entry(&env, &DataKey::Wrapped(namehash.clone()), &record);
+        EvtWrapped {
+            namehash,
+            owner,
+            fuses: 0,
+        }
+        .publish(&env);
+    }
+
+    /// Hands Registry ownership back to `to` and forgets the wrap. Blocked by `CANNOT_UNWRAP`.
+    pub fn unwrap(env: Env, caller: Address, namehash: BytesN<32>, to: Address) {
+        let registry = read_registry(&env);
+        let record = require_holder(&env, &registry, &caller, &namehash);
+        require_fuse_intact(&env, &record, CANNOT_UNWRAP);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Wrapped(namehash.clone()));
+        env.invoke_contract::<()>(
+            &registry,
+            &Symbol::new(&env, "transfer"),
+            (env.current_contract_address(), namehash.clone(), to.clone()).into_val(&env),
+        );
+        EvtUnwrapped {
+            namehash,
+            owner: to,
+        }
+        .publish(&env);
+    }
+
+    /// Burns `fuses` on `namehash` and returns the full set now burned. Fuses can never be
+    /// restored. Blocked by `CANNOT_BURN_FUSES`. Emits `EvtFusesBurned`.
+    pub fn burn_fuses(env: Env, caller: Address, namehash: BytesN<32>, fuses: u32) -> u32 {
+        let registry = read_registry(&env);
+        let mut record = require_holder(&env, &registry, &caller, &namehash);
+        require_fuse_intact(&env, &record, CANNOT_BURN_FUSES);
+        // Only a parent may set `PARENT_CANNOT_CONTROL`, when it creates the child.
+        if fuses & PARENT_CANNOT_CONTROL != 0 {
+            panic_with_error!(&env, NameWrapperError::InvalidFuses);
+        }
+        record.fuses |= fuses;
+        validate_fuses(&env, record.fuses);
+        write_entry(&env, &DataKey::Wrapped(namehash.clone()), &record);
+        EvtFusesBurned {
+            namehash,
+            fuses: record.fuses,
+        }
+        .publish(&env);
+        record.fuses
+    }
+
+    /// Moves the wrapped name to `to`. Blocked by `CANNOT_TRANSFER`.
+    pub fn transfer(env: Env, from: Address, namehash: BytesN<32>, to: Address) {
+        let registry = read_registry(&env);
+        let mut record = require_holder(&env, &registry, &from, &namehash);
+        require_fuse_intact(&env, &record, CANNOT_TRANSFER);
+        record.owner = to.clone();
+        write_entry(&env, &DataKey::Wrapped(namehash.clone()), &record);
+        EvtWrappedTransfer { namehash, from, to }.publish(&env);
+    }
+
+    /// Points the wrapped name at `resolver` in the Registry. Blocked by `CANNOT_SET_RESOLVER`.
+    pub fn set_resolver(env: Env, caller: Address, namehash: BytesN<32>, resolver: Address) {
+        let registry = read_registry(&env);
+        let record = require_holder(&env, &registry, &caller, &namehash);
+        require_fuse_intact(&env, &record, CANNOT_SET_RESOLVER);
+        env.invoke_contract::<()>(
+            &registry,
+            &Symbol::new(&env, "set_resolver"),
+            (env.current_contract_address(), namehash, resolver).into_val(&env),
+        );
+    }
+
+    /// Creates or reassigns the child `label` under the wrapped `parent`, wrapped for `owner`
+    /// with `fuses` already burned. Creating a new child is blocked by the parent's
+    /// `CANNOT_CREATE_SUBDOMAIN`; replacing a child's holder is blocked by the child's
+    /// `PARENT_CANNOT_CONTROL`, which itself needs the parent's `CANNOT_UNWRAP`.
+    /// Emits `EvtWrapped` for the child and returns its namehash.
+    pub fn set_subnode_owner(
+        env: Env,
+        caller: Address,
+        parent: BytesN<32>,
+        label: Bytes,
+        owner: Address,
+        fuses: u32,
+    ) -> BytesN<32> {
+        let registry = read_registry(&env);
+        let parent_record = require_holder(&env, &registry, &caller, &parent);
+        validate_fuses(&env, fuses);
+        if fuses & PARENT_CANNOT_CONTROL != 0 && parent_record.fuses & CANNOT_UNWRAP == 0 {
+            panic_with_error!(&env, NameWrapperError::InvalidFuses);
+        }
+
+        let node = subnode(&env, &parent, &label);
+        match read_wrapped(&env, &registry, &node) {
+            Some(child) => require_fuse_intact(&env, &child, PARENT_CANNOT_CONTROL),
+            None => {
+                let existing: Option<Address> = env.invoke_contract(
+                    &registry,
+                    &Symbol::new(&env, "owner_checked"),
+                    (node.clone(),).into_val(&env),
+                );
+                if existing.is_none() {
+                    require_fuse_intact(&env, &parent_record, CANNOT_CREATE_SUBDOMAIN);
+                }
+            }
+        }
+
+        let node: BytesN<32> = env.invoke_contract(
+            &registry,
+            &Symbol::new(&env, "set_subnode_owner"),
+            (parent, label, env.current_contract_address()).into_val(&env),
+        );
+        let record = WrappedName {
+            owner: owner.clone(),
+            fuses,
+        };
+        write_entry(&env, &DataKey::Wrapped(node.clone()), &record);
+        EvtWrapped {
+            namehash: node.clone(),
+            owner,
+            fuses,
+        }
+        .publish(&env);
+        node
+    }
+
+    /// Holder of a wrapped name, or `None` if it is not (or no longer) wrapped.
+    pub fn owner_of(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        let registry = read_registry(&env);
+        read_wrapped(&env, &registry, &namehash).map(|record| record.owner)
+    }
+
+    /// Fuses burned on a wrapped name; `0` if it is not wrapped.
+    pub fn fuses(env: Env, namehash: BytesN<32>) -> u32 {
+        let registry = read_registry(&env);
+        read_wrapped(&env, &registry, &namehash).map_or(0, |record| record.fuses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{contract, contractimpl, contracttype, testutils::Address as _};
+
+    #[derive(Clone)]
+    #[contracttype]
+    enum MockRegistryKey {
+        Owner(BytesN<32>),
+        Resolver(BytesN<32>),
+    }
+
+    /// Registry stand-in with the owner checks the wrapper relies on.
+    #[contract]
+    pub struct MockRegistry;
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Owner(namehash), &owner);
+        }
+
+        pub fn owner_checked(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Owner(namehash))
+        }
+
+        pub fn resolver(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Resolver(namehash))
+        }
+
+        pub fn transfer(env: Env, caller: Address, namehash: BytesN<32>, to: Address) {
+            caller.require_auth();
+            Self::require_owner(&env, &caller, &namehash);
+            Self::set_owner(env, namehash, to);
+        }
+
+        pub fn set_resolver(env: Env, caller: Address, namehash: BytesN<32>, resolver: Address) {
+            caller.require_auth();
+            Self::require_owner(&env, &caller, &namehash);
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Resolver(namehash), &resolver);
+        }
+
+        pub fn set_subnode_owner(
+            env: Env,
+            parent: BytesN<32>,
+            label: Bytes,
+            owner: Address,
+        ) -> BytesN<32> {
+            let parent_owner = Self::owner_checked(env.clone(), parent.clone()).unwrap();
+            parent_owner.require_auth();
+            let node = subnode(&env, &parent, &label);
+            Self::set_owner(env, node.clone(), owner);
+            node
+        }
+    }
+
+    impl MockRegistry {
+        fn require_owner(env: &Env, caller: &Address, namehash: &BytesN<32>) {
+            let owner = Self::owner_checked(env.clone(), namehash.clone());
+            assert_eq!(owner.as_ref(), Some(caller), "mock registry: not owner");
+        }
+    }
+
+    struct Setup<'a> {
+        env: Env,
+        wrapper: NameWrapperClient<'a>,
+        registry: MockRegistryClient<'a>,
+        owner: Address,
+        name: BytesN<32>,
+    }
+
+    /// A wrapper holding one name, `[1; 32]`, for `owner`.
+    fn setup<'a>() -> Setup<'a> {
+        let env = Env::default();
+        env.mock_all_auths();
+        let wrapper_id = env.register(NameWrapper, ());
+        let registry_id = env.register(MockRegistry, ());
+        let wrapper = NameWrapperClient::new(&env, &wrapper_id);
+        let registry = MockRegistryClient::new(&env, &registry_id);
+        wrapper.init(&registry_id);
+        let owner = Address::generate(&env);
+        let name = BytesN::from_array(&env, &[1; 32]);
+        registry.set_owner(&name, &owner);
+        wrapper.wrap(&owner, &name);
+        Setup {
+            env,
+            wrapper,
+            registry,
+            owner,
+            name,
+        }
+    }
+
+    fn label(env: &Env, text: &str) -> Bytes {
+        Bytes::from_slice(env, text.as_bytes())
+    }
+
+    #[test]
+    fn wrap_and_unwrap_move_registry_ownership() {
+        let s = setup();
+        assert_eq!(
+            s.registry.owner_checked(&s.name),
+            Some(s.wrapper.address.clone())
+        );
+        assert_eq!(s.wrapper.owner_of(&s.name), Some(s.owner.clone()));
+        assert_eq!(s.wrapper.fuses(&s.name), 0);
+        assert_eq!(
+            s.wrapper.try_init(&s.registry.address),
+            Err(Ok(NameWrapperError::AlreadyInitialized.into()))
+        );
+
+        let stranger = Address::generate(&s.env);
+        assert_eq!(
+            s.wrapper.try_unwrap(&stranger, &s.name, &stranger),
+            Err(Ok(NameWrapperError::NotOwner.into()))
+        );
+        s.wrapper.unwrap(&s.owner, &s.name, &s.owner);
+        assert_eq!(s.registry.owner_checked(&s.name), Some(s.owner.clone()));
+        assert_eq!(s.wrapper.owner_of(&s.name), None);
+        assert_eq!(
+            s.wrapper.try_transfer(&s.owner, &s.name, &stranger),
+            Err(Ok(NameWrapperError::NotWrapped.into()))
+        );
+    }
+
+    #[test]
+    fn fuses_block_their_operations_and_cannot_be_restored() {
+        let s = setup();
+        let buyer = Address::generate(&s.env);
+        let resolver = Address::generate(&s.env);
+
+        // Restrictive fuses are meaningless while the holder could still unwrap.
+        assert_eq!(
+            s.wrapper
+                .try_burn_fuses(&s.owner, &s.name, &CANNOT_TRANSFER),
+            Err(Ok(NameWrapperError::InvalidFuses.into()))
+        );
+        assert_eq!(
+            s.wrapper.try_burn_fuses(&s.owner, &s.name, &(1 << 9)),
+            Err(Ok(NameWrapperError::InvalidFuses.into()))
+        );
+        let burned = s.wrapper.burn_fuses(
+            &s.owner,
+            &s.name,
+            &(CANNOT_UNWRAP | CANNOT_SET_RESOLVER | CANNOT_BURN_FUSES),
+        );
+        assert_eq!(
+            burned,
+            CANNOT_UNWRAP | CANNOT_SET_RESOLVER | CANNOT_BURN_FUSES
+        );
+
+        assert_eq!(
+            s.wrapper.try_unwrap(&s.owner, &s.name, &s.owner),
+            Err(Ok(NameWrapperError::FuseBurned.into()))
+        );
+        assert_eq!(
+            s.wrapper.try_set_resolver(&s.owner, &s.name, &resolver),
+            Err(Ok(NameWrapperError::FuseBurned.into()))
+        );
+        assert_eq!(
+            s.wrapper
+                .try_burn_fuses(&s.owner, &s.name, &CANNOT_TRANSFER),
+            Err(Ok(NameWrapperError::FuseBurned.into()))
+        );
+        assert_eq!(s.registry.resolver(&s.name), None);
+
+        // Unburned operations still work and the fuses travel with the name.
+        s.wrapper.transfer(&s.owner, &s.name, &buyer);
+        assert_eq!(s.wrapper.owner_of(&s.name), Some(buyer));
+        assert_eq!(s.wrapper.fuses(&s.name), burned);
+    }
+
+    #[test]
+    fn subdomains_respect_parent_and_child_fuses() {
+        let s = setup();
+        let member = Address::generate(&s.env);
+        let usurper = Address::generate(&s.env);
+
+        let child =
+            s.wrapper
+                .set_subnode_owner(&s.owner, &s.name, &label(&s.env, "alice"), &member, &0);
+        assert_eq!(s.wrapper.owner_of(&child), Some(member.clone()));
+        assert_eq!(
+            s.registry.owner_checked(&child),
+            Some(s.wrapper.address.clone())
+        );
+        // Without `PARENT_CANNOT_CONTROL` the parent may reassign the child.
+        s.wrapper
+            .set_subnode_owner(&s.owner, &s.name, &label(&s.env, "alice"), &usurper, &0);
+        assert_eq!(s.wrapper.owner_of(&child), Some(usurper));
+
+        // Protecting holders needs the parent to give up unwrapping first.
+        assert_eq!(
+            s.wrapper.try_set_subnode_owner(
+                &s.owner,
+                &s.name,
+                &label(&s.env, "bob"),
+                &member,
+                &PARENT_CANNOT_CONTROL,
+            ),
+            Err(Ok(NameWrapperError::InvalidFuses.into()))
+        );
+        s.wrapper.burn_fuses(&s.owner, &s.name, &CANNOT_UNWRAP);
+        let bob = s.wrapper.set_subnode_owner(
+            &s.owner,
+            &s.name,
+            &label(&s.env, "bob"),
+            &member,
+            &PARENT_CANNOT_CONTROL,
+        );
+        assert_eq!(
+            s.wrapper
+                .try_set_subnode_owner(&s.owner, &s.name, &label(&s.env, "bob"), &s.owner, &0,),
+            Err(Ok(NameWrapperError::FuseBurned.into()))
+        );
+        assert_eq!(s.wrapper.owner_of(&bob), Some(member.clone()));
+        assert_eq!(
+            s.wrapper
+                .try_burn_fuses(&member, &bob, &PARENT_CANNOT_CONTROL),
+            Err(Ok(NameWrapperError::InvalidFuses.into()))
+        );
+
+        s.wrapper
+            .burn_fuses(&s.owner, &s.name, &CANNOT_CREATE_SUBDOMAIN);
+        assert_eq!(
+            s.wrapper.try_set_subnode_owner(
+                &s.owner,
+                &s.name,
+                &label(&s.env, "carol"),
+                &member,
+                &0,
+            ),
+            Err(Ok(NameWrapperError::FuseBurned.into()))
+        );
+    }
+
+    #[test]
+    fn stale_wrap_records_are_ignored() {
+        let s = setup();
+        s.wrapper
+            .burn_fuses(&s.owner, &s.name, &(CANNOT_UNWRAP | CANNOT_TRANSFER));
+
+        // The name lapsed and was re-registered to someone else in the Registry.
+        let registrant = Address::generate(&s.env);
+        s.registry.set_owner(&s.name, &registrant);
+        assert_eq!(s.wrapper.owner_of(&s.name), None);
+        assert_eq!(s.wrapper.fuses(&s.name), 0);
+
+        // Re-wrapping starts over with no fuses.
+        s.wrapper.wrap(&registrant, &s.name);
+        assert_eq!(s.wrapper.owner_of(&s.name), Some(registrant));
+        assert_eq!(s.wrapper.fuses(&s.name), 0);
+    }
+}
@@ -0,0 +1,428 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, Error, IntoVal, String, Symbol, TryFromVal, Val,
+};
+
+const MAX_BASE_URI_LEN: u32 = 192;
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+/// `base_uri` plus the 64 hex characters of a token id.
+const MAX_TOKEN_URI_LEN: usize = MAX_BASE_URI_LEN as usize + 64;
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Minter,
+    Registry,
+    Name,
+    Symbol,
+    BaseUri,
+    /// Token id (the name's namehash) -> holder.
+    Owner(BytesN<32>),
+    Balance(Address),
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["mint"])]
+pub struct EvtMint {
+    #[topic]
+    pub token_id: BytesN<32>,
+    pub to: Address,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["transfer"])]
+pub struct EvtTransfer {
+    #[topic]
+    pub from: Address,
+    #[topic]
+    pub to: Address,
+    pub token_id: BytesN<32>,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum NameTokenError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    NotOwner = 3,
+    TokenNotFound = 4,
+    InvalidInput = 5,
+}
+
+/// Transferable token view of names registered through the Registrar. Token ids are
+/// namehashes; holding the token and owning the name in the Registry move together.
+#[contract]
+pub struct NameToken;
+
+/// Persists `value` and extends the entry's TTL so live tokens are not archived.
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn read_config<V: TryFromVal<Env, Val>>(env: &Env, key: &DataKey) -> V {
+    let storage = env.storage().persistent();
+    let value = storage
+        .get(key)
+        .unwrap_or_else(|| panic_with_error!(env, NameTokenError::NotInitialized));
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    value
+}
+
+fn read_owner(env: &Env, token_id: &BytesN<32>) -> Option<Address> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Owner(token_id.clone()))
+}
+
+fn read_balance(env: &Env, owner: &Address) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Balance(owner.clone()))
+        .unwrap_or(0)
+}
+
+fn write_balance(env: &Env, owner: &Address, balance: u32) {
+    let key = DataKey::Balance(owner.clone());
+    if balance == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        write_entry(env, &key, &balance);
+    }
+}
+
+/// Moves `token_id` to `to`, keeping both holders' balances in step.
+fn move_token(env: &Env, token_id: &BytesN<32>, from: Option<&Address>, to: &Address) {
+    if let Some(from) = from {
+        write_balance(env, from, read_balance(env, from).saturating_sub(1));
+    }
+    write_balance(env, to, read_balance(env, to) + 1);
+    write_entry(env, &DataKey::Owner(token_id.clone()), to);
+}
+
+fn registry_owner(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+    let args = (namehash.clone(),).into_val(env);
+    match env.try_invoke_contract::<Address, Error>(registry, &Symbol::new(env, "owner"), args) {
+        Ok(Ok(owner)) => Some(owner),
+        _ => None,
+    }
+}
+
+fn hex_digit(nibble: u8) -> u8 {
+    match nibble {
+        0..=9 => b'0' + nibble,
+        _ => b'a' + nibble - 10,
+    }
+}
+
+#[contractimpl]
+impl NameToken {
+    /// Runs once, at deployment, so nobody can configure the token before its deployer.
+    /// `minter` is the Registrar allowed to mint; `base_uri` prefixes the hex token id in
+    /// `token_uri`.
+    pub fn __constructor(
+        env: Env,
+        minter: Address,
+        registry: Address,
+        name: String,
+        symbol: String,
+        base_uri: Bytes,
+    ) {
+        if base_uri.len() > MAX_BASE_URI_LEN {
+            panic_with_error!(&env, NameTokenError::InvalidInput);
+        }
+        write_entry(&env, &DataKey::Minter, &minter);
+        write_entry(&env, &DataKey::Registry, &registry);
+        write_entry(&env, &DataKey::Name, &name);
+        write_entry(&env, &DataKey::Symbol, &symbol);
+        write_entry(&env, &DataKey::BaseUri, &base_uri);
+    }
+
+    pub fn name(env: Env) -> String {
+        read_config(&env, &DataKey::Name)
+    }
+
+    pub fn symbol(env: Env) -> String {
+        read_config(&env, &DataKey::Symbol)
+    }
+
+    pub fn minter(env: Env) -> Address {
+        read_config(&env, &DataKey::Minter)
+    }
+
+    pub fn registry(env: Env) -> Address {
+        read_config(&env, &DataKey::Registry)
+    }
+
+    /// Issues the token for `token_id` to `to`. A token left over from a lapsed registration
+    /// is reassigned rather than duplicated. Minter only; emits `EvtMint`.
+    pub fn mint(env: Env, token_id: BytesN<32>, to: Address) {
+        let minter: Address = read_config(&env, &DataKey::Minter);
+        minter.require_auth();
+
+        let previous = read_owner(&env, &token_id);
+        move_token(&env, &token_id, previous.as_ref(), &to);
+        EvtMint { token_id, to }.publish(&env);
+    }
+
+    /// Transfers the token and the underlying Registry ownership from `from` to `to`.
+    /// `from` must authorize both this call and the nested `registry.transfer`.
+    pub fn transfer(env: Env, from: Address, to: Address, token_id: BytesN<32>) {
+        from.require_auth();
+        let owner = read_owner(&env, &token_id)
+            .unwrap_or_else(|| panic_with_error!(&env, NameTokenError::TokenNotFound));
+        if owner != from {
+            panic_with_error!(&env, NameTokenError::NotOwner);
+        }
+
+        let registry: Address = read_config(&env, &DataKey::Registry);
+        env.invoke_contract::<()>(
+            &registry,
+            &Symbol::new(&env, "transfer"),
+            (from.clone(), token_id.clone(), to.clone()).into_val(&env),
+        );
+        move_token(&env, &token_id, Some(&from), &to);
+        EvtTransfer { from, to, token_id }.publish(&env);
+    }
+
+    /// Re-reads the Registry owner and moves the token to it, repairing drift after a
+    /// transfer made directly on the Registry. Permissionless; returns the current holder.
+    pub fn sync(env: Env, token_id: BytesN<32>) -> Address {
+        let holder = read_owner(&env, &token_id)
+            .unwrap_or_else(|| panic_with_error!(&env, NameTokenError::TokenNotFound));
+        let registry: Address = read_config(&env, &DataKey::Registry);
+        match registry_owner(&env, &registry, &token_id) {
+            Some(owner) if owner != holder => {
+                move_token(&env, &token_id, Some(&holder), &owner);
+                EvtTransfer {
+                    from: holder,
+                    to: owner.clone(),
+                    token_id,
+                }
+                .publish(&env);
+                owner
+            }
+            _ => holder,
+        }
+    }
+
+    pub fn owner_of(env: Env, token_id: BytesN<32>) -> Address {
+        read_owner(&env, &token_id)
+            .unwrap_or_else(|| panic_with_error!(&env, NameTokenError::TokenNotFound))
+    }
+
+    pub fn balance(env: Env, owner: Address) -> u32 {
+        read_balance(&env, &owner)
+    }
+
+    /// `base_uri` followed by the lowercase hex token id.
+    pub fn token_uri(env: Env, token_id: BytesN<32>) -> String {
+        if read_owner(&env, &token_id).is_none() {
+            panic_with_error!(&env, NameTokenError::TokenNotFound);
+        }
+        let base_uri: Bytes = read_config(&env, &DataKey::BaseUri);
+        let mut buf = [0u8; MAX_TOKEN_URI_LEN];
+        let base_len = base_uri.len() as usize;
+        base_uri.copy_into_slice(&mut buf[..base_len]);
+        for (i, byte) in token_id.to_array().iter().enumerate() {
+            buf[base_len + 2 * i] = hex_digit(byte >> 4);
+            buf[base_len + 2 * i + 1] = hex_digit(byte & 0x0f);
+        }
+        String::from_bytes(&env, &buf[..base_len + 64])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        contract, contractimpl,
+        testutils::{Address as _, Events, MockAuth, MockAuthInvoke},
+        IntoVal,
+    };
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[contract]
+    pub struct MockRegistry;
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
+            env.storage()
+                .persistent()
+                .get(&namehash)
+                .unwrap_or_else(|| panic!("mock registry owner not set"))
+        }
+
+        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
+            env.storage().persistent().set(&namehash, &owner);
+        }
+
+        pub fn transfer(env: Env, caller: Address, namehash: BytesN<32>, to: Address) {
+            caller.require_auth();
+            let owner: Address = env.storage().persistent().get(&namehash).unwrap();
+            assert_eq!(owner, caller, "mock registry: not owner");
+            env.storage().persistent().set(&namehash, &to);
+        }
+    }
+
+    struct Setup<'a> {
+        env: Env,
+        token: NameTokenClient<'a>,
+        registry: MockRegistryClient<'a>,
+        minter: Address,
+    }
+
+    fn setup<'a>() -> Setup<'a> {
+        let env = Env::default();
+        env.mock_all_auths();
+        let registry_id = env.register(MockRegistry, ());
+        let minter = Address::generate(&env);
+        let token_id = env.register(
+            NameToken,
+            (
+                &minter,
+                &registry_id,
+                String::from_str(&env, "Stellar Names"),
+                String::from_str(&env, "SNS"),
+                Bytes::from_slice(&env, b"https://names.example/token/"),
+            ),
+        );
+        let token = NameTokenClient::new(&env, &token_id);
+        let registry = MockRegistryClient::new(&env, &registry_id);
+        Setup {
+            env,
+            token,
+            registry,
+            minter,
+        }
+    }
+
+    fn node(env: &Env, byte: u8) -> BytesN<32> {
+        BytesN::from_array(env, &[byte; 32])
+    }
+
+    #[test]
+    fn mint_assigns_token_and_balance() {
+        let s = setup();
+        let holder = Address::generate(&s.env);
+        s.token.mint(&node(&s.env, 1), &holder);
+        assert_eq!(s.env.events().all().len(), 1);
+        s.token.mint(&node(&s.env, 2), &holder);
+
+        assert_eq!(s.token.owner_of(&node(&s.env, 1)), holder);
+        assert_eq!(s.token.balance(&holder), 2);
+        assert_eq!(s.token.name(), String::from_str(&s.env, "Stellar Names"));
+        assert_eq!(s.token.symbol(), String::from_str(&s.env, "SNS"));
+
+        let newcomer = Address::generate(&s.env);
+        s.token.mint(&node(&s.env, 1), &newcomer);
+        assert_eq!(s.token.owner_of(&node(&s.env, 1)), newcomer);
+        assert_eq!(s.token.balance(&holder), 1);
+        assert_eq!(s.token.balance(&newcomer), 1);
+    }
+
+    #[test]
+    fn mint_requires_minter_auth() {
+        let s = setup();
+        let intruder = Address::generate(&s.env);
+        let token_id = node(&s.env, 3);
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            s.token
+                .mock_auths(&[MockAuth {
+                    address: &intruder,
+                    invoke: &MockAuthInvoke {
+                        contract: &s.token.address,
+                        fn_name: "mint",
+                        args: (token_id.clone(), intruder.clone()).into_val(&s.env),
+                        sub_invokes: &[],
+                    },
+                }])
+                .mint(&token_id, &intruder);
+        }));
+        assert!(attempt.is_err());
+        assert_eq!(s.token.balance(&intruder), 0);
+        assert_eq!(s.token.minter(), s.minter);
+    }
+
+    #[test]
+    fn transfer_moves_token_and_registry_owner() {
+        let s = setup();
+        let holder = Address::generate(&s.env);
+        let buyer = Address::generate(&s.env);
+        let token_id = node(&s.env, 4);
+        s.registry.set_owner(&token_id, &holder);
+        s.token.mint(&token_id, &holder);
+
+        s.token.transfer(&holder, &buyer, &token_id);
+        let events = s.env.events().all();
+        assert_eq!(events.len(), 1);
+
+        assert_eq!(s.token.owner_of(&token_id), buyer);
+        assert_eq!(s.registry.owner(&token_id), buyer);
+        assert_eq!(s.token.balance(&holder), 0);
+        assert_eq!(s.token.balance(&buyer), 1);
+    }
+
+    #[test]
+    fn transfer_rejects_non_holder_and_unknown_token() {
+        let s = setup();
+        let holder = Address::generate(&s.env);
+        let thief = Address::generate(&s.env);
+        let token_id = node(&s.env, 5);
+        s.registry.set_owner(&token_id, &holder);
+        s.token.mint(&token_id, &holder);
+
+        let res = s.token.try_transfer(&thief, &thief, &token_id);
+        assert_eq!(res, Err(Ok(NameTokenError::NotOwner.into())));
+        let res = s.token.try_transfer(&holder, &thief, &node(&s.env, 6));
+        assert_eq!(res, Err(Ok(NameTokenError::TokenNotFound.into())));
+        assert_eq!(s.token.owner_of(&token_id), holder);
+    }
+
+    #[test]
+    fn sync_follows_registry_owner() {
+        let s = setup();
+        let holder = Address::generate(&s.env);
+        let other = Address::generate(&s.env);
+        let token_id = node(&s.env, 7);
+        s.registry.set_owner(&token_id, &holder);
+        s.token.mint(&token_id, &holder);
+        assert_eq!(s.token.sync(&token_id), holder);
+
+        s.registry.set_owner(&token_id, &other);
+        assert_eq!(s.token.sync(&token_id), other);
+        assert_eq!(s.token.owner_of(&token_id), other);
+        assert_eq!(s.token.balance(&holder), 0);
+        assert_eq!(s.token.balance(&other), 1);
+    }
+
+    #[test]
+    fn token_uri_appends_hex_id() {
+        let s = setup();
+        let holder = Address::generate(&s.env);
+        let token_id = node(&s.env, 0xab);
+        s.token.mint(&token_id, &holder);
+
+        let mut expected = std::string::String::from("https://names.example/token/");
+        expected.push_str(&"ab".repeat(32));
+        assert_eq!(
+            s.token.token_uri(&token_id),
+            String::from_str(&s.env, &expected)
+        );
+        assert_eq!(
+            s.token.try_token_uri(&node(&s.env, 8)),
+            Err(Ok(NameTokenError::TokenNotFound.into()))
+        );
+    }
+}
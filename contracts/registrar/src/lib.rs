@@ -1,22 +1,96 @@
 #![no_std]
+// `register` takes eight arguments, and the generated `RegistrarClient` mirrors them.
+#![allow(clippy::too_many_arguments)]
 
 #[cfg(test)]
 extern crate std;
 
+use name_service_common::{compute_commitment, root_node, subnode};
+use resolver_interface::InterfaceDetection;
+use soroban_sdk::token::TokenClient;
 use soroban_sdk::xdr::ToXdr;
 use soroban_sdk::{
-    contract, contracterror, contractimpl, contracttype, panic_with_error, Address, Bytes, BytesN,
-    Env, Error, IntoVal, Symbol,
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, vec,
+    Address, Bytes, BytesN, Env, Error, IntoVal, Map, String, Symbol, Val, Vec,
 };
 
+const DAY_IN_LEDGERS: u32 = 17_280;
+
 mod keys {
     pub const REGISTRY: &[u8] = b"REG_ADDR";
     pub const TLD: &[u8] = b"REG_TLD";
     pub const PARAMS: &[u8] = b"REG_PARM";
     pub const ADMIN: &[u8] = b"REG_ADMN";
+    pub const PENDING_ADMIN: &[u8] = b"REG_PADM";
+    pub const PAUSED: &[u8] = b"REG_PAUS";
+    pub const STORAGE_VERSION: &[u8] = b"REG_SVER";
+    pub const ESCROW: &[u8] = b"REG_ESCR"; // ESCROW || owner xdr -> i128
+    pub const ESCROW_HELD: &[u8] = b"REG_ESCT";
+    pub const AUTO_RENEW: &[u8] = b"REG_AUTO"; // AUTO_RENEW || namehash -> Address
     pub const COMM: &[u8] = b"REG_COMM";
+    pub const PRICES: &[u8] = b"REG_PRCE";
+    pub const TOKEN: &[u8] = b"REG_TOKN";
+    pub const NAME_TOKEN: &[u8] = b"REG_NFT";
+    pub const REFERRAL: &[u8] = b"REG_REFL"; // REFERRAL || referrer xdr -> i128
+    pub const REFERRALS_OWED: &[u8] = b"REG_REFO";
+    pub const RESERVED: &[u8] = b"REG_RSVD"; // RESERVED || label -> bool
+    pub const LENGTH_POLICY: &[u8] = b"REG_LENP"; // LENGTH_POLICY || len (u32 BE) -> LengthPolicy
+    pub const AUCTION_HOUSE: &[u8] = b"REG_AUCH";
+    pub const AUCTION_HOLD: &[u8] = b"REG_AUHL"; // AUCTION_HOLD || label -> bool
+    pub const TLDS: &[u8] = b"REG_TLDS";
+    pub const TLD_CONFIG: &[u8] = b"REG_TLDC"; // TLD_CONFIG || tld -> TldConfig
+    pub const RATE: &[u8] = b"REG_RATE"; // RATE || window (u64 BE) || caller xdr || tld -> u32, temporary
+    pub const COUNCIL: &[u8] = b"REG_CNCL";
+    pub const PROPOSAL_SEQ: &[u8] = b"REG_PSEQ";
+    pub const PROPOSAL: &[u8] = b"REG_PROP"; // PROPOSAL || id (u32 BE) -> Proposal
+    pub const TIMELOCK: &[u8] = b"REG_TLCK";
+    pub const QUEUE_SEQ: &[u8] = b"REG_QSEQ";
+    pub const QUEUED: &[u8] = b"REG_QUEU"; // QUEUED || id (u32 BE) -> QueuedChange
+    pub const GIFT: &[u8] = b"REG_GIFT"; // GIFT || namehash -> Gift
+    pub const PAID: &[u8] = b"REG_PAID"; // PAID || namehash -> PaidTerm
+    pub const RELEASED: &[u8] = b"REG_RLSD"; // RELEASED || namehash -> u64
+    pub const VOUCHER: &[u8] = b"REG_VCHR"; // VOUCHER || sha256(code) -> Voucher
+    pub const NAME_COUNT: &[u8] = b"REG_NCNT";
+    pub const NAME_AT: &[u8] = b"REG_NIDX"; // NAME_AT || position (u32 BE) -> BytesN<32>
+    pub const NAME_SEEN: &[u8] = b"REG_NSEN"; // NAME_SEEN || namehash -> bool
+    pub const REVENUE: &[u8] = b"REG_REV"; // REVENUE || token xdr -> i128
+    pub const REVENUE_PERIOD: &[u8] = b"REG_REVP"; // REVENUE_PERIOD || period (u32 BE) -> Revenue
+    pub const STATS: &[u8] = b"REG_STAT";
+    pub const ALLOWANCE: &[u8] = b"REG_ALLW"; // ALLOWANCE || sponsor xdr || owner xdr -> i128
+    pub const ALLOWANCE_HELD: &[u8] = b"REG_ALWT";
+    pub const PREMIUM_AUCTION: &[u8] = b"REG_PAUC"; // PREMIUM_AUCTION || label -> PremiumAuction
+    pub const PREMIUM_HELD: &[u8] = b"REG_PAUT";
+    pub const EVENT_SEQ: &[u8] = b"REG_ESEQ"; // instance storage -> u64
 }
 
+/// Storage layout this code expects; `migrate` brings older layouts up to it.
+const STORAGE_VERSION: u32 = 1;
+
+/// Basis-point denominator for `referral_fee_bps`.
+const MAX_BPS: u32 = 10_000;
+
+/// Length of a `revenue_by_period` bucket.
+const REVENUE_PERIOD_SECS: u64 = 30 * 86_400;
+
+/// Each premium auction bid must beat the leading one by this many basis points.
+const PREMIUM_BID_INCREMENT_BPS: u32 = 500;
+
+/// A premium auction bid this close to the end pushes the end back to this far from now.
+const PREMIUM_BID_EXTENSION_SECS: u64 = 600;
+
+/// Upper bound on council members, keeping approval checks cheap.
+const MAX_COUNCIL_MEMBERS: u32 = 20;
+
+/// How long a council proposal can gather approvals and be executed.
+const PROPOSAL_TTL_SECS: u64 = 7 * 86_400;
+
+/// Longest delay `set_timelock` accepts.
+const MAX_TIMELOCK_SECS: u64 = 30 * 86_400;
+
+/// Upper bound on entries per batch call (`commit_many`, `register_many`, `renew_many`),
+/// keeping batches inside per-transaction resource limits.
+const MAX_BATCH_NAMES: u32 = 50;
+
 fn default_params() -> RegistrarParams {
     RegistrarParams {
         min_label_len: 1,
@@ -25,13 +99,62 @@ fn default_params() -> RegistrarParams {
         commit_max_age_secs: 86_400,
         renew_extension_secs: 31_536_000,
         grace_period_secs: 7_776_000,
+        max_registration_secs: 315_360_000,
+        ttl_threshold_ledgers: 29 * DAY_IN_LEDGERS,
+        ttl_extend_to_ledgers: 30 * DAY_IN_LEDGERS,
+        referral_fee_bps: 0,
+        auto_renew_window_secs: 2_592_000,
+        keeper_tip_bps: 100,
+        allow_idn: false,
+        release_refund_bps: 0,
+        rate_limit_max: 0,
+        rate_limit_window_secs: 3_600,
+        gift_claim_window_secs: 2_592_000,
+        reap_bounty: 0,
+        redemption_fee: 0,
+        pricing: PricingStrategy::LengthTiered,
+        faucet: false,
+    }
+}
+
+/// Persists `value` and extends its TTL per the configured policy.
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &Bytes, value: &V) {
+    env.storage().persistent().set(key, value);
+    extend_entry(env, key);
+}
+
+/// Extends `key` to `ttl_extend_to_ledgers` once it drops below `ttl_threshold_ledgers`.
+fn extend_entry(env: &Env, key: &Bytes) {
+    let storage = env.storage().persistent();
+    if !storage.has(key) {
+        return;
     }
+    let params: RegistrarParams = storage
+        .get(&singleton_key(env, keys::PARAMS))
+        .unwrap_or_else(default_params);
+    storage.extend_ttl(
+        key,
+        params.ttl_threshold_ledgers,
+        params.ttl_extend_to_ledgers,
+    );
 }
 
 fn singleton_key(env: &Env, tag: &[u8]) -> Bytes {
     Bytes::from_slice(env, tag)
 }
 
+/// Claims the `seq` for the next event: every Registrar event ends with its position in this
+/// contract's event stream, starting at 1, so indexers can detect gaps and order events
+/// within a ledger.
+fn next_event_seq(env: &Env) -> u64 {
+    let storage = env.storage().instance();
+    let key = singleton_key(env, keys::EVENT_SEQ);
+    let seq = storage.get::<_, u64>(&key).unwrap_or(0) + 1;
+    storage.set(&key, &seq);
+    storage.extend_ttl(29 * DAY_IN_LEDGERS, 30 * DAY_IN_LEDGERS);
+    seq
+}
+
 fn commitment_key(env: &Env, commitment: &BytesN<32>) -> Bytes {
     let mut key = Bytes::from_slice(env, keys::COMM);
     key.extend_from_array(&commitment.to_array());
@@ -54,6 +177,55 @@ fn read_tld(env: &Env) -> Bytes {
     })
 }
 
+fn tld_config_key(env: &Env, tld: &Bytes) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TLD_CONFIG);
+    key.append(tld);
+    key
+}
+
+/// TLDs added with `add_tld`; the default TLD from `init` is not included.
+fn read_extra_tlds(env: &Env) -> Vec<Bytes> {
+    let storage = env.storage().persistent();
+    storage
+        .get(&singleton_key(env, keys::TLDS))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+fn read_tld_config(env: &Env, tld: &Bytes) -> Option<TldConfig> {
+    let key = tld_config_key(env, tld);
+    let config = env.storage().persistent().get(&key);
+    if config.is_some() {
+        extend_entry(env, &key);
+    }
+    config
+}
+
+fn is_known_tld(env: &Env, tld: &Bytes) -> bool {
+    *tld == read_tld(env) || read_tld_config(env, tld).is_some()
+}
+
+fn tld_config(env: &Env, tld: &Bytes) -> TldConfig {
+    read_tld_config(env, tld).unwrap_or_else(|| panic_with_error!(env, RegistrarError::UnknownTld))
+}
+
+/// Registration parameters for names under `tld`.
+fn tld_params(env: &Env, tld: &Bytes) -> RegistrarParams {
+    if *tld == read_tld(env) {
+        read_params(env)
+    } else {
+        tld_config(env, tld).params
+    }
+}
+
+/// Price schedule for names under `tld`.
+fn tld_schedule(env: &Env, tld: &Bytes) -> PriceSchedule {
+    if *tld == read_tld(env) {
+        read_price_schedule(env)
+    } else {
+        tld_config(env, tld).schedule
+    }
+}
+
 fn read_admin(env: &Env) -> Address {
     let storage = env.storage().persistent();
     let key = singleton_key(env, keys::ADMIN);
@@ -71,27 +243,59 @@ fn read_params(env: &Env) -> RegistrarParams {
 }
 
 fn write_params(env: &Env, params: &RegistrarParams) {
-    let storage = env.storage().persistent();
     let key = singleton_key(env, keys::PARAMS);
-    storage.set(&key, params);
+    write_entry(env, &key, params);
 }
 
 fn write_registry(env: &Env, registry: &Address) {
-    let storage = env.storage().persistent();
     let key = singleton_key(env, keys::REGISTRY);
-    storage.set(&key, registry);
+    write_entry(env, &key, registry);
 }
 
 fn write_tld(env: &Env, tld: &Bytes) {
-    let storage = env.storage().persistent();
     let key = singleton_key(env, keys::TLD);
-    storage.set(&key, tld);
+    write_entry(env, &key, tld);
 }
 
 fn write_admin(env: &Env, admin: &Address) {
-    let storage = env.storage().persistent();
     let key = singleton_key(env, keys::ADMIN);
-    storage.set(&key, admin);
+    write_entry(env, &key, admin);
+}
+
+fn read_pending_admin(env: &Env) -> Option<Address> {
+    let storage = env.storage().persistent();
+    storage.get(&singleton_key(env, keys::PENDING_ADMIN))
+}
+
+fn read_token(env: &Env) -> Address {
+    let storage = env.storage().persistent();
+    let key = singleton_key(env, keys::TOKEN);
+    storage.get(&key).unwrap_or_else(|| {
+        panic_with_error!(env, RegistrarError::NotInitialized);
+    })
+}
+
+fn write_token(env: &Env, token: &Address) {
+    let key = singleton_key(env, keys::TOKEN);
+    write_entry(env, &key, token);
+}
+
+fn read_name_token(env: &Env) -> Option<Address> {
+    let storage = env.storage().persistent();
+    storage.get(&singleton_key(env, keys::NAME_TOKEN))
+}
+
+fn read_price_schedule(env: &Env) -> PriceSchedule {
+    let storage = env.storage().persistent();
+    let key = singleton_key(env, keys::PRICES);
+    storage.get(&key).unwrap_or_else(|| {
+        panic_with_error!(env, RegistrarError::NotInitialized);
+    })
+}
+
+fn write_price_schedule(env: &Env, schedule: &PriceSchedule) {
+    let key = singleton_key(env, keys::PRICES);
+    write_entry(env, &key, schedule);
 }
 
 fn ensure_label_len_bounds(env: &Env, params: &RegistrarParams, len: u32) {
@@ -100,65 +304,174 @@ fn ensure_label_len_bounds(env: &Env, params: &RegistrarParams, len: u32) {
     }
 }
 
-fn validate_label(env: &Env, label: &Bytes) {
-    let params = read_params(env);
-    let len = label.len();
-    ensure_label_len_bounds(env, &params, len);
-    let last_idx = (len - 1) as usize;
-    for (idx, b) in label.iter().enumerate() {
-        match b {
-            b'a'..=b'z' | b'0'..=b'9' => {}
-            b'-' if idx != 0 && idx != last_idx => {}
-            _ => panic_with_error!(env, RegistrarError::InvalidLabel),
-        };
+/// Only canonical labels (see `labels::normalize`) may be registered or referenced.
+fn validate_label(env: &Env, tld: &Bytes, label: &Bytes) {
+    let params = tld_params(env, tld);
+    ensure_label_len_bounds(env, &params, label.len());
+    if labels::normalize(env, label, params.allow_idn) != *label {
+        panic_with_error!(env, RegistrarError::InvalidLabel);
+    }
+}
+
+/// Longest dotted name `register_str` accepts, as in DNS.
+const MAX_NAME_LEN: usize = 255;
+
+/// Splits `label.tld` at its first dot into the TLD, ASCII-lowercased, and the label in
+/// canonical form for that TLD. Aborts with `InvalidLabel` if `name` is not UTF-8 or either
+/// part is empty.
+fn split_name(env: &Env, name: &String) -> (Bytes, Bytes) {
+    let len = name.len() as usize;
+    if len == 0 || len > MAX_NAME_LEN {
+        panic_with_error!(env, RegistrarError::InvalidLabel);
+    }
+    let mut buf = [0u8; MAX_NAME_LEN];
+    let buf = &mut buf[..len];
+    name.copy_into_slice(buf);
+    if core::str::from_utf8(buf).is_err() {
+        panic_with_error!(env, RegistrarError::InvalidLabel);
+    }
+    let dot = buf
+        .iter()
+        .position(|b| *b == b'.')
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::InvalidLabel));
+    let (label, tld) = buf.split_at_mut(dot);
+    let tld = &mut tld[1..];
+    if tld.is_empty() {
+        panic_with_error!(env, RegistrarError::InvalidLabel);
     }
+    tld.make_ascii_lowercase();
+    let tld = Bytes::from_slice(env, tld);
+    let params = tld_params(env, &tld);
+    let label = labels::normalize(env, &Bytes::from_slice(env, label), params.allow_idn);
+    (tld, label)
 }
 
+/// Commitments do not name a TLD, so `len` only has to fit one of them.
 fn validate_label_len(env: &Env, len: u32) {
     let params = read_params(env);
+    let fits =
+        |params: &RegistrarParams| len >= params.min_label_len && len <= params.max_label_len;
+    if fits(&params) {
+        return;
+    }
+    for tld in read_extra_tlds(env).iter() {
+        if fits(&tld_config(env, &tld).params) {
+            return;
+        }
+    }
     ensure_label_len_bounds(env, &params, len);
 }
 
-fn compute_commitment(env: &Env, label: &Bytes, owner: &Address, secret: &Bytes) -> BytesN<32> {
-    let mut data = Bytes::new(env);
-    data.append(label);
-    let owner_bytes = owner.clone().to_xdr(env);
-    data.append(&owner_bytes);
-    data.append(secret);
-    env.crypto().sha256(&data).to_bytes()
+fn ensure_valid_params(env: &Env, params: &RegistrarParams) {
+    if params.min_label_len == 0
+        || params.min_label_len > params.max_label_len
+        || params.max_label_len > 63
+        || params.commit_min_age_secs == 0
+        || params.commit_min_age_secs > params.commit_max_age_secs
+        || params.renew_extension_secs == 0
+        || params.grace_period_secs == 0
+        || params.max_registration_secs == 0
+        || params.ttl_threshold_ledgers == 0
+        || params.ttl_threshold_ledgers > params.ttl_extend_to_ledgers
+        || params.ttl_extend_to_ledgers > env.storage().max_ttl()
+        || params.referral_fee_bps > MAX_BPS
+        || params.keeper_tip_bps > MAX_BPS
+        || params.release_refund_bps > MAX_BPS
+        || (params.rate_limit_max > 0 && params.rate_limit_window_secs == 0)
+        || params.gift_claim_window_secs == 0
+        || params.reap_bounty < 0
+        || params.redemption_fee < 0
+        || (params.faucet && !cfg!(feature = "testnet"))
+    {
+        panic_with_error!(env, RegistrarError::InvalidParams);
+    }
+    pricing::validate_strategy(env, &params.pricing);
+}
+
+/// Commit age `register` waits for; faucet mode accepts a commitment as soon as it is made.
+fn commit_min_age(params: &RegistrarParams) -> u64 {
+    if params.faucet {
+        0
+    } else {
+        params.commit_min_age_secs
+    }
 }
 
-fn fold_hash(env: &Env, parent: &BytesN<32>, label_hash: &BytesN<32>) -> BytesN<32> {
-    let mut data = Bytes::from_array(env, &parent.to_array());
-    data.extend_from_array(&label_hash.to_array());
+/// Domain tag of the version 2 commitment scheme; see `compute_bound_commitment`.
+const COMMITMENT_V2_TAG: &[u8] = b"SNS_COMMIT_V2";
+
+/// Version 2 commitment: `sha256("SNS_COMMIT_V2" || network_id || xdr((registrar, tld, label,
+/// owner, secret, committer, resolver, duration_secs)))`. On top of version 1 it binds the
+/// reveal's TLD, resolver, and duration, so a relayer cannot swap them, and the network and
+/// Registrar, so a commitment is void on any other deployment.
+fn compute_bound_commitment(
+    env: &Env,
+    tld: &Bytes,
+    label: &Bytes,
+    owner: &Address,
+    secret: &Bytes,
+    committer: &Address,
+    resolver: &Option<Address>,
+    duration_secs: u64,
+) -> BytesN<32> {
+    let mut data = Bytes::from_slice(env, COMMITMENT_V2_TAG);
+    data.extend_from_array(&env.ledger().network_id().to_array());
+    let fields = (
+        env.current_contract_address(),
+        tld.clone(),
+        label.clone(),
+        owner.clone(),
+        secret.clone(),
+        committer.clone(),
+        resolver.clone(),
+        duration_secs,
+    );
+    data.append(&fields.to_xdr(env));
     env.crypto().sha256(&data).to_bytes()
 }
 
-fn compute_namehash(env: &Env, label: &Bytes) -> BytesN<32> {
-    let tld = read_tld(env);
-    let root = BytesN::<32>::from_array(env, &[0u8; 32]);
-    let tld_hash = env.crypto().sha256(&tld).to_bytes();
-    let node = fold_hash(env, &root, &tld_hash);
-    let label_hash = env.crypto().sha256(label).to_bytes();
-    fold_hash(env, &node, &label_hash)
+fn compute_tld_node(env: &Env, tld: &Bytes) -> BytesN<32> {
+    subnode(env, &root_node(env), tld)
+}
+
+fn compute_namehash(env: &Env, tld: &Bytes, label: &Bytes) -> BytesN<32> {
+    subnode(env, &compute_tld_node(env, tld), label)
 }
 
+/// Commitments live in temporary storage for as long as any TLD could still accept them,
+/// then expire on their own. Entries written to persistent storage before the move are still
+/// read, and `sweep_commitments` or a successful `register` removes them.
 fn commitment_info(env: &Env, commitment: &BytesN<32>) -> Option<CommitmentInfo> {
-    let storage = env.storage().persistent();
     let key = commitment_key(env, commitment);
-    storage.get(&key)
+    env.storage()
+        .temporary()
+        .get(&key)
+        .or_else(|| env.storage().persistent().get(&key))
 }
 
 fn store_commitment(env: &Env, commitment: &BytesN<32>, info: &CommitmentInfo) {
-    let storage = env.storage().persistent();
     let key = commitment_key(env, commitment);
+    let ledgers = commitment_ttl_ledgers(env);
+    let storage = env.storage().temporary();
     storage.set(&key, info);
+    storage.extend_ttl(&key, ledgers, ledgers);
 }
 
 fn remove_commitment(env: &Env, commitment: &BytesN<32>) {
-    let storage = env.storage().persistent();
     let key = commitment_key(env, commitment);
-    storage.remove(&key);
+    env.storage().temporary().remove(&key);
+    env.storage().persistent().remove(&key);
+}
+
+/// Ledgers (~5 seconds each) covering the longest `commit_max_age_secs` of any TLD, plus one
+/// so a commitment never disappears while `register` would still accept it.
+fn commitment_ttl_ledgers(env: &Env) -> u32 {
+    let mut max_age = read_params(env).commit_max_age_secs;
+    for tld in read_extra_tlds(env).iter() {
+        max_age = max_age.max(tld_config(env, &tld).params.commit_max_age_secs);
+    }
+    let ledgers = max_age.div_ceil(5).saturating_add(1);
+    ledgers.min(env.storage().max_ttl() as u64) as u32
 }
 
 fn ensure_initialized(env: &Env) {
@@ -167,6 +480,65 @@ fn ensure_initialized(env: &Env) {
     if !storage.has(&key) {
         panic_with_error!(env, RegistrarError::NotInitialized);
     }
+    // Configuration is written once but read constantly; keep it from archiving.
+    for tag in [
+        keys::REGISTRY,
+        keys::TLD,
+        keys::PARAMS,
+        keys::ADMIN,
+        keys::PENDING_ADMIN,
+        keys::PAUSED,
+        keys::STORAGE_VERSION,
+        keys::TOKEN,
+        keys::PRICES,
+        keys::NAME_TOKEN,
+        keys::TLDS,
+    ] {
+        extend_entry(env, &singleton_key(env, tag));
+    }
+}
+
+fn is_paused(env: &Env) -> bool {
+    let storage = env.storage().persistent();
+    storage
+        .get(&singleton_key(env, keys::PAUSED))
+        .unwrap_or(false)
+}
+
+fn ensure_not_paused(env: &Env) {
+    if is_paused(env) {
+        panic_with_error!(env, RegistrarError::Paused);
+    }
+}
+
+fn read_auction_house(env: &Env) -> Option<Address> {
+    let storage = env.storage().persistent();
+    storage.get(&singleton_key(env, keys::AUCTION_HOUSE))
+}
+
+fn ensure_auction_house(env: &Env, caller: &Address) {
+    caller.require_auth();
+    if read_auction_house(env).as_ref() != Some(caller) {
+        panic_with_error!(env, RegistrarError::NotAuctionHouse);
+    }
+}
+
+fn write_tld_config(env: &Env, tld: Bytes, params: RegistrarParams, schedule: PriceSchedule) {
+    ensure_valid_params(env, &params);
+    pricing::validate_schedule(env, &schedule);
+    let config = TldConfig {
+        params: params.clone(),
+        schedule: schedule.clone(),
+    };
+    write_entry(env, &tld_config_key(env, &tld), &config);
+    sync_grace_period(env, &tld, params.grace_period_secs);
+    EvtTldConfigured {
+        tld,
+        params,
+        schedule,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
 }
 
 fn ensure_admin(env: &Env, caller: &Address) {
@@ -176,1068 +548,9857 @@ fn ensure_admin(env: &Env, caller: &Address) {
     }
 }
 
-fn grace_expired(now: u64, expires_at: u64, grace: u64) -> bool {
-    if now <= expires_at {
-        return false;
+/// Admin check for the actions in `AdminAction`: once a council is configured they run only
+/// through `execute_action`.
+fn ensure_admin_action(env: &Env, caller: &Address) {
+    ensure_admin(env, caller);
+    if council::read(env).is_some() {
+        panic_with_error!(env, RegistrarError::CouncilRequired);
     }
-    let grace_end = expires_at.checked_add(grace).unwrap_or(u64::MAX);
-    now > grace_end
 }
 
-mod registry_api {
-    use super::*;
+/// With a timelock configured, the changes in `TimelockedChange` run only via `execute_params`.
+fn ensure_no_timelock(env: &Env) {
+    if timelock::delay(env) > 0 {
+        panic_with_error!(env, RegistrarError::TimelockRequired);
+    }
+}
 
-    pub fn set_owner(env: &Env, registry: &Address, namehash: &BytesN<32>, owner: &Address) {
-        env.invoke_contract::<()>(
-            &registry,
-            &Symbol::new(env, "set_owner"),
-            (namehash, owner).into_val(env),
-        );
+/// Raising the delay protects registrants at once; lowering it has to wait out the current one.
+fn lengthen_timelock(env: &Env, delay_secs: u64) {
+    if delay_secs < timelock::delay(env) {
+        panic_with_error!(env, RegistrarError::TimelockRequired);
     }
+    timelock::set_delay(env, delay_secs);
+}
 
-    pub fn set_resolver(env: &Env, registry: &Address, namehash: &BytesN<32>, resolver: &Address) {
-        env.invoke_contract::<()>(
-            &registry,
-            &Symbol::new(env, "set_resolver"),
-            (namehash, resolver).into_val(env),
-        );
+fn apply_schedule(env: &Env, schedule: PriceSchedule) {
+    pricing::validate_schedule(env, &schedule);
+    write_price_schedule(env, &schedule);
+}
+
+fn apply_params(env: &Env, params: RegistrarParams) {
+    ensure_valid_params(env, &params);
+    write_params(env, &params);
+    sync_grace_period(env, &read_tld(env), params.grace_period_secs);
+    EvtParamsChanged {
+        params,
+        seq: next_event_seq(env),
     }
+    .publish(env);
+}
 
-    pub fn renew(env: &Env, registry: &Address, namehash: &BytesN<32>) {
-        env.invoke_contract::<()>(
-            &registry,
-            &Symbol::new(env, "renew"),
-            (namehash,).into_val(env),
-        );
+/// Hands `tld`'s grace period to the Registry so its `burn`, reclaims, and `owner_checked`
+/// lapse names when this Registrar does. Skipped until the Registrar holds the TLD node; the
+/// Registry's default matches `default_params` until then.
+fn sync_grace_period(env: &Env, tld: &Bytes, grace_secs: u64) {
+    let registry = read_registry(env);
+    let tld_node = compute_tld_node(env, tld);
+    if registry_api::owner(env, &registry, &tld_node) == Some(env.current_contract_address()) {
+        registry_api::set_grace_period(env, &registry, &tld_node, grace_secs);
     }
+}
 
-    pub fn owner(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
-        let args = (namehash.clone(),).into_val(env);
-        match env.try_invoke_contract::<Address, Error>(&registry, &Symbol::new(env, "owner"), args)
-        {
-            Ok(Ok(address)) => Some(address),
-            _ => None,
-        }
+fn withdraw_fees(env: &Env, amount: i128, to: Address) {
+    if amount <= 0 {
+        panic_with_error!(env, RegistrarError::InvalidAmount);
     }
+    if amount > available_balance(env) {
+        panic_with_error!(env, RegistrarError::InvalidAmount);
+    }
+    payments::pay_out(env, &to, amount);
+    EvtFeesWithdrawn {
+        to,
+        amount,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
+}
 
-    pub fn expires(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<u64> {
-        let args = (namehash.clone(),).into_val(env);
-        match env.try_invoke_contract::<u64, Error>(&registry, &Symbol::new(env, "expires"), args) {
-            Ok(Ok(ts)) => Some(ts),
-            _ => None,
+fn upgrade_code(env: &Env, new_wasm_hash: BytesN<32>) {
+    env.deployer()
+        .update_current_contract_wasm(new_wasm_hash.clone());
+    EvtUpgraded {
+        new_wasm_hash,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
+}
+
+fn reserve_all(env: &Env, tld: Bytes, labels: Vec<Bytes>) {
+    for label in labels.iter() {
+        validate_label(env, &tld, &label);
+        reserved::set(env, &tld, &label, true);
+        EvtLabelReserved {
+            label,
+            tld: tld.clone(),
+            seq: next_event_seq(env),
         }
+        .publish(env);
     }
 }
 
-/// Registrar contract for the `.stellar` namespace.
-#[contract]
-pub struct Registrar;
+fn unreserve(env: &Env, tld: Bytes, label: Bytes) {
+    if !reserved::contains(env, &tld, &label) {
+        panic_with_error!(env, RegistrarError::NotReserved);
+    }
+    ensure_no_premium_auction(env, &tld, &label);
+    reserved::set(env, &tld, &label, false);
+    EvtLabelReleased {
+        label,
+        tld,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
+}
 
-#[contracttype]
-#[derive(Clone)]
-pub struct RegistrarParams {
-    pub min_label_len: u32,
-    pub max_label_len: u32,
-    pub commit_min_age_secs: u64,
-    pub commit_max_age_secs: u64,
-    pub renew_extension_secs: u64,
-    pub grace_period_secs: u64,
+/// Premium auctions only run for reserved labels under the default TLD, where
+/// `settle_premium` issues names.
+fn ensure_no_premium_auction(env: &Env, tld: &Bytes, label: &Bytes) {
+    if *tld == read_tld(env) {
+        premium::ensure_none(env, label);
+    }
 }
 
-#[contracterror]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
-#[repr(u32)]
-pub enum RegistrarError {
-    AlreadyInitialized = 1,
-    NotInitialized = 2,
-    NotAdmin = 3,
-    NotOwner = 4,
-    InvalidLabel = 5,
-    CommitmentExists = 6,
-    CommitmentMissing = 7,
-    CommitmentTooFresh = 8,
-    CommitmentTooOld = 9,
-    NameNotAvailable = 10,
-    ExpiryUnavailable = 11,
-    InvalidParams = 12,
+fn open_premium_auction(env: &Env, label: Bytes, start_price: i128, duration_secs: u64) {
+    let tld = read_tld(env);
+    validate_label(env, &tld, &label);
+    if !reserved::contains(env, &tld, &label) {
+        panic_with_error!(env, RegistrarError::NotReserved);
+    }
+    premium::ensure_none(env, &label);
+    if start_price <= 0 || duration_secs == 0 {
+        panic_with_error!(env, RegistrarError::InvalidParams);
+    }
+    let ends_at = env.ledger().timestamp().saturating_add(duration_secs);
+    let auction = PremiumAuction {
+        start_price,
+        ends_at,
+        highest_bidder: None,
+        highest_bid: 0,
+    };
+    premium::write(env, &label, &auction);
+    EvtPremiumAuctionCreated {
+        label,
+        start_price,
+        ends_at,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub struct EvtCommitMade {
-    pub commitment: BytesN<32>,
-    pub at: u64,
-    pub label_len: u32,
+fn write_length_policy(env: &Env, tld: Bytes, len: u32, policy: LengthPolicy) {
+    if !is_known_tld(env, &tld) {
+        panic_with_error!(env, RegistrarError::UnknownTld);
+    }
+    if len == 0 || len > 63 || policy.price_multiplier == 0 {
+        panic_with_error!(env, RegistrarError::InvalidParams);
+    }
+    pricing::set_length_policy(env, &tld, len, &policy);
+    EvtLengthPolicyChanged {
+        len,
+        tld,
+        policy,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub struct EvtNameRegistered {
-    pub namehash: BytesN<32>,
-    pub owner: Address,
-    pub expires_at: u64,
-    pub ts: u64,
-    pub label: Bytes,
+fn add_tld(env: &Env, tld: Bytes, params: RegistrarParams, schedule: PriceSchedule) {
+    if tld.is_empty() {
+        panic_with_error!(env, RegistrarError::InvalidLabel);
+    }
+    if is_known_tld(env, &tld) {
+        panic_with_error!(env, RegistrarError::TldExists);
+    }
+    let mut tlds = read_extra_tlds(env);
+    tlds.push_back(tld.clone());
+    write_entry(env, &singleton_key(env, keys::TLDS), &tlds);
+    write_tld_config(env, tld, params, schedule);
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub struct EvtNameRenewed {
-    pub namehash: BytesN<32>,
-    pub expires_at: u64,
+fn replace_tld_config(env: &Env, tld: Bytes, params: RegistrarParams, schedule: PriceSchedule) {
+    if read_tld_config(env, &tld).is_none() {
+        panic_with_error!(env, RegistrarError::UnknownTld);
+    }
+    write_tld_config(env, tld, params, schedule);
 }
 
-#[contracttype]
-#[derive(Clone)]
-pub struct EvtCommitmentMissing {
-    pub commitment: BytesN<32>,
-    pub label: Bytes,
-    pub caller: Address,
-    pub owner: Address,
-    pub secret_len: u32,
+fn mint_all_vouchers(env: &Env, hashes: Vec<BytesN<32>>, discount_bps: u32, uses: u32) {
+    ensure_batch_size(env, hashes.len());
+    if discount_bps == 0 || discount_bps > MAX_BPS || uses == 0 {
+        panic_with_error!(env, RegistrarError::InvalidParams);
+    }
+    let voucher = Voucher {
+        discount_bps,
+        uses_left: uses,
+    };
+    for hash in hashes.iter() {
+        vouchers::mint(env, &hash, &voucher);
+    }
 }
 
-#[contracttype]
-#[derive(Clone)]
-struct CommitmentInfo {
-    pub timestamp: u64,
-    pub label_len: u32,
+fn write_auction_house(env: &Env, auction_house: Option<Address>) {
+    let key = singleton_key(env, keys::AUCTION_HOUSE);
+    match auction_house {
+        Some(auction_house) => write_entry(env, &key, &auction_house),
+        None => env.storage().persistent().remove(&key),
+    }
 }
 
-#[contractimpl]
-impl Registrar {
-    /// Returns the contract version.
-    pub fn version(_env: Env) -> u32 {
-        1
+fn write_name_token(env: &Env, name_token: &Address) {
+    write_entry(env, &singleton_key(env, keys::NAME_TOKEN), name_token);
+}
+
+fn allocate_reserved_name(
+    env: &Env,
+    tld: Bytes,
+    label: Bytes,
+    owner: Address,
+    resolver: Option<Address>,
+    duration_secs: u64,
+) -> BytesN<32> {
+    ensure_not_paused(env);
+    validate_label(env, &tld, &label);
+    if !reserved::contains(env, &tld, &label) {
+        panic_with_error!(env, RegistrarError::NotReserved);
     }
+    ensure_no_premium_auction(env, &tld, &label);
+    allocate_name(env, tld, label, owner, resolver, duration_secs, 0)
+}
 
-    /// One-time initializer.
-    pub fn init(env: Env, registry: Address, tld: Bytes, admin: Address) {
-        let storage = env.storage().persistent();
-        let key = singleton_key(&env, keys::REGISTRY);
-        if storage.has(&key) {
-            panic_with_error!(&env, RegistrarError::AlreadyInitialized);
-        }
-        if tld.is_empty() {
-            panic_with_error!(&env, RegistrarError::InvalidLabel);
+fn released_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::RELEASED);
+    key.append(&Bytes::from_array(env, &namehash.to_array()));
+    key
+}
+
+/// Remembers when `namehash` went back to the pool through `release` or `reap`, which clear
+/// the Registry expiry `released_at` would otherwise derive it from.
+fn record_release(env: &Env, namehash: &BytesN<32>, at: u64) {
+    write_entry(env, &released_key(env, namehash), &at);
+}
+
+/// End of the grace period of the previous registration of `namehash`, if it had one, or
+/// the time it was released or reaped.
+fn released_at(env: &Env, registry: &Address, tld: &Bytes, namehash: &BytesN<32>) -> Option<u64> {
+    if registry_api::owner(env, registry, namehash).is_some() {
+        if let Some(expires_at) = registry_api::expires(env, registry, namehash) {
+            return Some(expires_at.saturating_add(tld_params(env, tld).grace_period_secs));
         }
-        write_registry(&env, &registry);
-        write_tld(&env, &tld);
-        write_admin(&env, &admin);
-        let params = default_params();
-        write_params(&env, &params);
     }
+    env.storage().persistent().get(&released_key(env, namehash))
+}
 
-    /// Record commitment timestamp for commit–reveal. `label_len` allows early validation
-    /// without revealing the label itself on-chain.
-    pub fn commit(env: Env, caller: Address, commitment: BytesN<32>, label_len: u32) {
-        ensure_initialized(&env);
-        caller.require_auth();
-        validate_label_len(&env, label_len);
-        let key = commitment_key(&env, &commitment);
-        let storage = env.storage().persistent();
-        if storage.has(&key) {
-            panic_with_error!(&env, RegistrarError::CommitmentExists);
-        }
-        let ts = env.ledger().timestamp();
-        let info = CommitmentInfo {
-            timestamp: ts,
-            label_len,
-        };
-        store_commitment(&env, &commitment, &info);
-        env.events().publish(
-            (Symbol::new(&env, "commit_made"), commitment.clone()),
-            EvtCommitMade {
-                commitment,
-                at: ts,
-                label_len,
-            },
-        );
+/// Premium owed on `namehash` right now, measured from the end of its previous grace period.
+fn current_premium(env: &Env, registry: &Address, tld: &Bytes, namehash: &BytesN<32>) -> i128 {
+    let schedule = tld_schedule(env, tld);
+    if schedule.premium_start == 0 {
+        return 0;
+    }
+    let Some(released_at) = released_at(env, registry, tld, namehash) else {
+        return 0;
+    };
+    let now = env.ledger().timestamp();
+    if now <= released_at {
+        return 0;
     }
+    pricing::premium(&schedule, now - released_at)
+}
 
-    /// Finalize name registration after commitment matures.
-    #[allow(clippy::too_many_arguments)]
-    pub fn register(
-        env: Env,
-        caller: Address,
-        label: Bytes,
-        owner: Address,
-        secret: Bytes,
-        resolver: Option<Address>,
-    ) -> BytesN<32> {
-        ensure_initialized(&env);
-        caller.require_auth();
-        validate_label(&env, &label);
+/// Past `expires_at` but not past the grace period.
+fn in_grace(now: u64, expires_at: u64, grace: u64) -> bool {
+    now > expires_at && !grace_expired(now, expires_at, grace)
+}
 
-        let params = read_params(&env);
-        let registry = read_registry(&env);
-        let now = env.ledger().timestamp();
-        let commitment = compute_commitment(&env, &label, &owner, &secret);
+/// With a redemption fee configured, a name in grace can only come back through `redeem`.
+fn ensure_not_in_redemption(env: &Env, params: &RegistrarParams, expires_at: Option<u64>) {
+    let now = env.ledger().timestamp();
+    if params.redemption_fee > 0
+        && expires_at.is_some_and(|expires_at| in_grace(now, expires_at, params.grace_period_secs))
+    {
+        panic_with_error!(env, RegistrarError::NameNotAvailable);
+    }
+}
 
-        let stored = commitment_info(&env, &commitment).unwrap_or_else(|| {
-            let commitment_evt = commitment.clone();
-            env.events().publish(
-                (Symbol::new(&env, "commitment_missing"), commitment_evt.clone()),
+fn grace_expired(now: u64, expires_at: u64, grace: u64) -> bool {
+    if now <= expires_at {
+        return false;
+    }
+    let grace_end = expires_at.saturating_add(grace);
+    now > grace_end
+}
+
+/// Whose allowance for `owner` pays towards a registration: `caller`'s when registering for
+/// someone else, or the `sponsor` an owner names when registering for themselves. Aborts with
+/// `NotOwner` if anyone else names a sponsor other than themselves.
+fn allowance_sponsor(
+    env: &Env,
+    caller: &Address,
+    owner: &Address,
+    sponsor: Option<Address>,
+) -> Option<Address> {
+    match sponsor {
+        Some(sponsor) if sponsor == *caller || *caller == *owner => Some(sponsor),
+        Some(_) => panic_with_error!(env, RegistrarError::NotOwner),
+        None if *caller != *owner => Some(caller.clone()),
+        None => None,
+    }
+}
+
+/// Shared body of `register`, `register_many`, and `register_gift`; the caller has already
+/// been authorized. The commitment binds `owner`, but the name goes to `holder` when set.
+fn register_name(
+    env: &Env,
+    caller: &Address,
+    tld: &Bytes,
+    label: Bytes,
+    owner: Address,
+    secret: Bytes,
+    resolver: Option<Address>,
+    duration_secs: u64,
+    referrer: Option<Address>,
+    voucher: Option<Bytes>,
+    sponsor: Option<Address>,
+    holder: Option<&Address>,
+) -> BytesN<32> {
+    validate_label(env, tld, &label);
+    if reserved::contains(env, tld, &label) {
+        panic_with_error!(env, RegistrarError::NameReserved);
+    }
+    if reserved::held(env, tld, &label) {
+        panic_with_error!(env, RegistrarError::NameInAuction);
+    }
+    if !pricing::length_policy(env, tld, label.len()).enabled {
+        panic_with_error!(env, RegistrarError::LengthDisabled);
+    }
+
+    let params = tld_params(env, tld);
+    ensure_duration(env, &params, duration_secs);
+    ensure_compatible_resolver(env, resolver.as_ref());
+    let registry = read_registry(env);
+    let now = env.ledger().timestamp();
+    // A version 2 commitment takes precedence; version 1 stays accepted for older clients.
+    let bound = compute_bound_commitment(
+        env,
+        tld,
+        &label,
+        &owner,
+        &secret,
+        caller,
+        &resolver,
+        duration_secs,
+    );
+    let (commitment, stored) = match commitment_info(env, &bound) {
+        Some(stored) => (bound, stored),
+        None => {
+            let commitment = compute_commitment(env, &label, &owner, &secret, caller);
+            let stored = commitment_info(env, &commitment).unwrap_or_else(|| {
                 EvtCommitmentMissing {
-                    commitment: commitment_evt,
+                    commitment: commitment.clone(),
                     label: label.clone(),
                     caller: caller.clone(),
                     owner: owner.clone(),
                     secret_len: secret.len(),
-                },
-            );
-            panic_with_error!(&env, RegistrarError::CommitmentMissing)
-        });
-        let age = now.saturating_sub(stored.timestamp);
-        if age < params.commit_min_age_secs {
-            panic_with_error!(&env, RegistrarError::CommitmentTooFresh);
+                    seq: next_event_seq(env),
+                }
+                .publish(env);
+                panic_with_error!(env, RegistrarError::CommitmentMissing)
+            });
+            (commitment, stored)
         }
-        if age > params.commit_max_age_secs {
-            panic_with_error!(&env, RegistrarError::CommitmentTooOld);
-        }
-        if label.len() != stored.label_len {
-            panic_with_error!(&env, RegistrarError::InvalidLabel);
-        }
-        if !Self::available(env.clone(), label.clone()) {
-            panic_with_error!(&env, RegistrarError::NameNotAvailable);
-        }
-
-        let namehash = compute_namehash(&env, &label);
-        let registrar_addr = env.current_contract_address();
+    };
+    let age = now.saturating_sub(stored.timestamp);
+    if age < commit_min_age(&params) {
+        panic_with_error!(env, RegistrarError::CommitmentTooFresh);
+    }
+    if age > params.commit_max_age_secs {
+        panic_with_error!(env, RegistrarError::CommitmentTooOld);
+    }
+    if label.len() != stored.label_len {
+        panic_with_error!(env, RegistrarError::InvalidLabel);
+    }
+    if !Registrar::available(env.clone(), tld.clone(), label.clone()) {
+        panic_with_error!(env, RegistrarError::NameNotAvailable);
+    }
 
-        // Registrar-first ownership: ensures Registry calls requiring owner auth succeed.
-        registry_api::set_owner(&env, &registry, &namehash, &registrar_addr);
-        if let Some(resolver_addr) = resolver.as_ref() {
-            registry_api::set_resolver(&env, &registry, &namehash, resolver_addr);
+    let namehash = compute_namehash(env, tld, &label);
+    // Per-label priority: a lapsed name goes back to the pool on equal terms, so commitments
+    // positioned before its release cannot take it the moment it frees up.
+    if released_at(env, &registry, tld, &namehash).is_some_and(|at| stored.timestamp <= at) {
+        panic_with_error!(env, RegistrarError::CommitmentPredatesRelease);
+    }
+    let mut price = if params.faucet {
+        0
+    } else {
+        pricing::rent_price(env, tld, label.len(), duration_secs)
+            .checked_add(current_premium(env, &registry, tld, &namehash))
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+    };
+    if let Some(code) = voucher {
+        price -= vouchers::redeem(env, &code, &namehash, price);
+    }
+    rate_limit::record(env, &params, tld, caller);
+    let sponsored = match allowance_sponsor(env, caller, &owner, sponsor) {
+        Some(sponsor) => sponsorship::spend(env, &sponsor, &owner, price),
+        None => 0,
+    };
+    payments::collect(env, caller, price - sponsored);
+    revenue::record(env, price, false);
+    if let Some(referrer) = referrer {
+        let amount = referrals::accrue(env, &referrer, price, params.referral_fee_bps);
+        if amount > 0 {
+            EvtReferralAccrued {
+                referrer,
+                namehash: namehash.clone(),
+                amount,
+                seq: next_event_seq(env),
+            }
+            .publish(env);
         }
-        registry_api::renew(&env, &registry, &namehash);
-        registry_api::set_owner(&env, &registry, &namehash, &owner);
-
-        // Delete commitment after successful registration to prevent premature burn on failed attempts.
-        remove_commitment(&env, &commitment);
+    }
+    let expires_at = now
+        .checked_add(duration_secs)
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::InvalidDuration));
+    let owner = holder.cloned().unwrap_or(owner);
+    assign_name(
+        env,
+        &registry,
+        tld,
+        &label,
+        &namehash,
+        &owner,
+        resolver.as_ref(),
+        expires_at,
+    );
+    paid::add(env, &namehash, price - sponsored, duration_secs);
+
+    // Delete commitment after successful registration to prevent premature burn on failed attempts.
+    remove_commitment(env, &commitment);
+    EvtCommitmentConsumed {
+        commitment,
+        namehash: namehash.clone(),
+        seq: next_event_seq(env),
+    }
+    .publish(env);
+
+    let ts = env.ledger().timestamp();
+
+    EvtNameRegistered {
+        namehash: namehash.clone(),
+        owner: owner.clone(),
+        expires_at,
+        ts,
+        label: label.clone(),
+        tld: tld.clone(),
+        price,
+        seq: next_event_seq(env),
+    }
+    .publish(env);
 
-        let expires_at = registry_api::expires(&env, &registry, &namehash).unwrap_or_else(|| {
-            now.checked_add(params.renew_extension_secs)
-                .unwrap_or(u64::MAX)
-        });
-        let ts = env.ledger().timestamp();
+    namehash
+}
 
-        env.events().publish(
-            (Symbol::new(&env, "name_registered"), namehash.clone()),
-            EvtNameRegistered {
-                namehash: namehash.clone(),
-                owner: owner.clone(),
-                expires_at,
-                ts,
-                label: label.clone(),
-            },
-        );
+/// Shared body of `commit` and `commit_many`.
+fn add_commitment(env: &Env, caller: &Address, commitment: &BytesN<32>, label_len: u32, ts: u64) {
+    validate_label_len(env, label_len);
+    if commitment_info(env, commitment).is_some() {
+        panic_with_error!(env, RegistrarError::CommitmentExists);
+    }
+    let info = CommitmentInfo {
+        timestamp: ts,
+        label_len,
+        committer: caller.clone(),
+    };
+    store_commitment(env, commitment, &info);
+}
 
-        namehash
+fn ensure_batch_size(env: &Env, len: u32) {
+    if len == 0 || len > MAX_BATCH_NAMES {
+        panic_with_error!(env, RegistrarError::InvalidBatchSize);
     }
+}
 
-    /// Extend an existing registration's expiry.
-    pub fn renew(env: Env, caller: Address, label: Bytes) {
-        ensure_initialized(&env);
-        caller.require_auth();
-        validate_label(&env, &label);
+fn add_fee(env: &Env, total: i128, price: i128) -> i128 {
+    total
+        .checked_add(price)
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+}
 
-        let registry = read_registry(&env);
-        let namehash = compute_namehash(&env, &label);
-        let owner = registry_api::owner(&env, &registry, &namehash)
-            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::NotOwner));
-        if owner != caller {
-            panic_with_error!(&env, RegistrarError::NotOwner);
+fn ensure_duration(env: &Env, params: &RegistrarParams, duration_secs: u64) {
+    if duration_secs == 0 || duration_secs > params.max_registration_secs {
+        panic_with_error!(env, RegistrarError::InvalidDuration);
+    }
+}
+
+/// Reject resolvers that could never answer an `addr` lookup before charging for the name.
+fn ensure_compatible_resolver(env: &Env, resolver: Option<&Address>) {
+    if let Some(resolver) = resolver {
+        if !resolver_interface::supports(env, resolver, resolver_interface::RECORD_ADDR) {
+            panic_with_error!(env, RegistrarError::IncompatibleResolver);
         }
+    }
+}
 
-        registry_api::renew(&env, &registry, &namehash);
-        let expires_at = registry_api::expires(&env, &registry, &namehash)
-            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::ExpiryUnavailable));
+/// Write a new registration of `label` to `owner` through the Registry and mint its token.
+fn assign_name(
+    env: &Env,
+    registry: &Address,
+    tld: &Bytes,
+    label: &Bytes,
+    namehash: &BytesN<32>,
+    owner: &Address,
+    resolver: Option<&Address>,
+    expires_at: u64,
+) {
+    let registrar_addr = env.current_contract_address();
+    stats::registered(
+        env,
+        label.len(),
+        registry_api::owner(env, registry, namehash).is_some(),
+    );
+
+    // Registrar-first ownership: ensures Registry calls requiring owner auth succeed.
+    let tld_node = compute_tld_node(env, tld);
+    registry_api::set_owner(env, registry, &tld_node, label, &registrar_addr);
+    // Fresh record generation so a previous holder's resolver records stop resolving.
+    registry_api::bump_record_version(env, registry, namehash);
+    if let Some(resolver_addr) = resolver {
+        registry_api::set_resolver(env, registry, namehash, resolver_addr);
+    }
+    registry_api::set_expiry(env, registry, namehash, expires_at);
+    registry_api::transfer(env, registry, namehash, owner);
+    name_index::add(env, namehash);
+    paid::clear(env, namehash);
+    env.storage()
+        .persistent()
+        .remove(&released_key(env, namehash));
+    // A held gift gets its token when it is delivered.
+    if *owner == registrar_addr {
+        return;
+    }
+    if let Some(name_token) = read_name_token(env) {
+        name_token_api::mint(env, &name_token, namehash, owner);
+    }
+}
 
-        env.events().publish(
-            (Symbol::new(&env, "name_renewed"), namehash.clone()),
-            EvtNameRenewed {
-                namehash,
-                expires_at,
-            },
-        );
+/// Whether `label` has no owner, or its registration has lapsed past the grace period.
+/// Reservations are not considered.
+fn unclaimed(env: &Env, tld: &Bytes, label: &Bytes) -> bool {
+    let params = tld_params(env, tld);
+    if label.len() < params.min_label_len || label.len() > params.max_label_len {
+        return false;
     }
+    let registry = read_registry(env);
+    let namehash = compute_namehash(env, tld, label);
 
-    /// Return whether the label is currently available.
-    pub fn available(env: Env, label: Bytes) -> bool {
-        if label.is_empty() {
-            return false;
-        }
-        if !env
-            .storage()
-            .persistent()
-            .has(&singleton_key(&env, keys::REGISTRY))
-        {
-            return false;
-        }
-        let params = read_params(&env);
-        if label.len() < params.min_label_len || label.len() > params.max_label_len {
-            return false;
-        }
-        let registry = read_registry(&env);
-        let namehash = compute_namehash(&env, &label);
+    let owner = registry_api::owner(env, &registry, &namehash);
+    if owner.is_none() {
+        return true;
+    }
 
-        let owner = registry_api::owner(&env, &registry, &namehash);
-        if owner.is_none() {
-            return true;
+    let expires_at = registry_api::expires(env, &registry, &namehash);
+    match expires_at {
+        Some(ts) => {
+            let now = env.ledger().timestamp();
+            grace_expired(now, ts, params.grace_period_secs)
         }
+        None => false,
+    }
+}
 
-        let expires_at = registry_api::expires(&env, &registry, &namehash);
-        match expires_at {
-            Some(ts) => {
-                let now = env.ledger().timestamp();
-                grace_expired(now, ts, params.grace_period_secs)
-            }
-            None => false,
-        }
+/// Register an unclaimed `label` under `tld` to `owner` without a commitment, for the
+/// allocation paths (`allocate_reserved`, `allocate_auctioned`, `settle_premium`). `price` was
+/// settled by the caller and is only reported in `EvtNameRegistered`.
+fn allocate_name(
+    env: &Env,
+    tld: Bytes,
+    label: Bytes,
+    owner: Address,
+    resolver: Option<Address>,
+    duration_secs: u64,
+    price: i128,
+) -> BytesN<32> {
+    ensure_duration(env, &tld_params(env, &tld), duration_secs);
+    ensure_compatible_resolver(env, resolver.as_ref());
+    if !unclaimed(env, &tld, &label) {
+        panic_with_error!(env, RegistrarError::NameNotAvailable);
     }
 
-    /// Update registrar parameters (admin only).
-    pub fn set_params(env: Env, caller: Address, params: RegistrarParams) {
-        ensure_initialized(&env);
-        caller.require_auth();
-        ensure_admin(&env, &caller);
-        if params.min_label_len == 0
-            || params.min_label_len > params.max_label_len
-            || params.max_label_len > 63
-            || params.commit_min_age_secs == 0
-            || params.commit_min_age_secs > params.commit_max_age_secs
-            || params.renew_extension_secs == 0
-            || params.grace_period_secs == 0
-        {
-            panic_with_error!(&env, RegistrarError::InvalidParams);
-        }
-        write_params(&env, &params);
+    let registry = read_registry(env);
+    let namehash = compute_namehash(env, &tld, &label);
+    let ts = env.ledger().timestamp();
+    let expires_at = ts
+        .checked_add(duration_secs)
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::InvalidDuration));
+    assign_name(
+        env,
+        &registry,
+        &tld,
+        &label,
+        &namehash,
+        &owner,
+        resolver.as_ref(),
+        expires_at,
+    );
+    EvtNameRegistered {
+        namehash: namehash.clone(),
+        owner,
+        expires_at,
+        ts,
+        label,
+        tld,
+        price,
+        seq: next_event_seq(env),
     }
+    .publish(env);
+    namehash
+}
 
-    /// Fetch current registrar parameters.
-    pub fn params(env: Env) -> RegistrarParams {
-        ensure_initialized(&env);
-        read_params(&env)
+/// Shared body of `renew` and `renew_many`; the caller has already been authorized.
+fn renew_name(env: &Env, caller: &Address, tld: &Bytes, label: Bytes) {
+    validate_label(env, tld, &label);
+
+    let registry = read_registry(env);
+    let namehash = compute_namehash(env, tld, &label);
+    let owner = registry_api::owner(env, &registry, &namehash)
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::NotOwner));
+    if owner != *caller {
+        panic_with_error!(env, RegistrarError::NotOwner);
     }
 
-    /// Stored registry address helper.
-    pub fn registry(env: Env) -> Address {
-        ensure_initialized(&env);
-        read_registry(&env)
+    let params = tld_params(env, tld);
+    let current = registry_api::expires(env, &registry, &namehash)
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::ExpiryUnavailable));
+    ensure_not_in_redemption(env, &params, Some(current));
+    let price = pricing::rent_price(env, tld, label.len(), params.renew_extension_secs);
+    payments::collect(env, caller, price);
+    revenue::record(env, price, true);
+    paid::add(env, &namehash, price, params.renew_extension_secs);
+
+    // Extend by exactly the term that was charged for.
+    let expires_at = current
+        .max(env.ledger().timestamp())
+        .checked_add(params.renew_extension_secs)
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::InvalidDuration));
+    registry_api::extend_expiry(env, &registry, &namehash, expires_at);
+
+    EvtNameRenewed {
+        namehash,
+        expires_at,
+        price,
+        seq: next_event_seq(env),
     }
+    .publish(env);
+}
 
+/// Refund owed by `release` for `remaining_secs` of unexpired term: the `PaidTerm` amount
+/// spread over its seconds, so terms obtained through vouchers or sponsors refund nothing.
+/// Limited to the balance not already owed to referrers or held in renewal escrow.
+fn release_refund(env: &Env, tld: &Bytes, namehash: &BytesN<32>, remaining_secs: u64) -> i128 {
+    let bps = tld_params(env, tld).release_refund_bps;
+    let paid = paid::get(env, namehash);
+    if bps == 0 || remaining_secs == 0 || paid.secs == 0 || paid.amount <= 0 {
+        return 0;
+    }
+    let refund = paid
+        .amount
+        .checked_mul(remaining_secs.min(paid.secs) as i128)
+        .and_then(|share| share.checked_mul(bps as i128))
+        .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+        / paid.secs as i128
+        / MAX_BPS as i128;
+    refund.min(available_balance(env).max(0))
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{
-        contract, contractimpl, contracttype,
-        testutils::{Address as _, Events, Ledger},
-        Address, Bytes, BytesN, Env, Symbol, TryFromVal,
-    };
-    use std::panic::{catch_unwind, AssertUnwindSafe};
+/// Fees the Registrar holds beyond referral credits, renewal escrow, and sponsor allowances.
+fn available_balance(env: &Env) -> i128 {
+    let token = TokenClient::new(env, &read_token(env));
+    token.balance(&env.current_contract_address())
+        - referrals::total_owed(env)
+        - escrow::total_held(env)
+        - sponsorship::total_held(env)
+        - premium::total_held(env)
+}
 
-    const MOCK_RENEW_EXTENSION: u64 = 31_536_000;
+/// Label canonicalization. The canonical form of a label is what `register` accepts and
+/// what gets hashed: lowercase ASCII letters, digits, and inner hyphens, or, when
+/// `allow_idn` is set, an IDNA A-label (`xn--` followed by RFC 3492 punycode) whose decoded
+/// form passes the script checks below.
+mod labels {
+    use super::*;
 
-    #[contract]
-    pub struct MockRegistry;
+    /// Longest input `normalize` accepts, in bytes; raw UTF-8 is longer than its A-label.
+    const MAX_INPUT_LEN: usize = 252;
+    /// Canonical labels never exceed 63 bytes (see `set_params`), and so never hold more
+    /// than 63 code points.
+    const MAX_OUTPUT_LEN: usize = 63;
+
+    const ACE_PREFIX: &[u8] = b"xn--";
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    const SKEW: u32 = 38;
+    const DAMP: u32 = 700;
+    const INITIAL_BIAS: u32 = 72;
+    const INITIAL_N: u32 = 0x80;
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Script {
+        Common,
+        Latin,
+        Greek,
+        Cyrillic,
+    }
 
-    #[contracttype]
-    #[derive(Clone)]
-    enum MockRegistryKey {
-        Owner(BytesN<32>),
-        Resolver(BytesN<32>),
-        Expires(BytesN<32>),
+    struct CodePoints {
+        buf: [u32; MAX_OUTPUT_LEN],
+        len: usize,
     }
 
-    #[contractimpl]
-    impl MockRegistry {
-        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Owner(namehash.clone()))
-                .unwrap_or_else(|| panic!("owner not set"))
+    impl CodePoints {
+        fn new() -> Self {
+            CodePoints {
+                buf: [0; MAX_OUTPUT_LEN],
+                len: 0,
+            }
         }
 
-        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Owner(namehash), &owner);
+        fn as_slice(&self) -> &[u32] {
+            &self.buf[..self.len]
         }
 
-        pub fn set_resolver(env: Env, namehash: BytesN<32>, resolver: Address) {
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Resolver(namehash), &resolver);
+        fn insert(&mut self, env: &Env, at: usize, cp: u32) {
+            if self.len == MAX_OUTPUT_LEN || at > self.len {
+                invalid(env);
+            }
+            self.buf.copy_within(at..self.len, at + 1);
+            self.buf[at] = cp;
+            self.len += 1;
         }
+    }
 
-        pub fn resolver(env: Env, namehash: BytesN<32>) -> Option<Address> {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Resolver(namehash))
-        }
+    struct Ascii {
+        buf: [u8; MAX_OUTPUT_LEN],
+        len: usize,
+    }
 
-        pub fn renew(env: Env, namehash: BytesN<32>) {
-            let owner: Address = env
-                .storage()
-                .persistent()
-                .get(&MockRegistryKey::Owner(namehash.clone()))
-                .unwrap_or_else(|| panic!("owner not set"));
-            owner.require_auth();
-            let now = env.ledger().timestamp();
-            let current = env
-                .storage()
-                .persistent()
-                .get(&MockRegistryKey::Expires(namehash.clone()))
-                .unwrap_or(0u64);
-            let base = if current > now { current } else { now };
-            let new_expiry = base.checked_add(MOCK_RENEW_EXTENSION).unwrap_or(u64::MAX);
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Expires(namehash), &new_expiry);
+    impl Ascii {
+        fn new() -> Self {
+            Ascii {
+                buf: [0; MAX_OUTPUT_LEN],
+                len: 0,
+            }
         }
 
-        pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Expires(namehash.clone()))
-                .unwrap_or_else(|| panic!("expiry not set"))
+        fn as_slice(&self) -> &[u8] {
+            &self.buf[..self.len]
         }
 
-        pub fn clear_expiry(env: Env, namehash: BytesN<32>) {
-            env.storage()
-                .persistent()
-                .remove(&MockRegistryKey::Expires(namehash));
+        fn push(&mut self, env: &Env, byte: u8) {
+            if self.len == MAX_OUTPUT_LEN {
+                invalid(env);
+            }
+            self.buf[self.len] = byte;
+            self.len += 1;
         }
     }
 
-    #[contract]
-    pub struct MockRegistryOwnerAuth;
+    fn invalid(env: &Env) -> ! {
+        panic_with_error!(env, RegistrarError::InvalidLabel)
+    }
 
-    #[contractimpl]
-    impl MockRegistryOwnerAuth {
-        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Owner(namehash.clone()))
-                .unwrap_or_else(|| panic!("owner not set"))
+    /// Return the canonical form of `label`, or abort with `InvalidLabel` if it has none.
+    /// ASCII input is lowercased; with `allow_idn`, UTF-8 input is lowercased and encoded
+    /// as an A-label, and an A-label input is checked to be in canonical form.
+    pub fn normalize(env: &Env, label: &Bytes, allow_idn: bool) -> Bytes {
+        let len = label.len() as usize;
+        if len == 0 || len > MAX_INPUT_LEN {
+            invalid(env);
         }
+        let mut input = [0u8; MAX_INPUT_LEN];
+        label.copy_into_slice(&mut input[..len]);
+        let input = &input[..len];
 
-        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Owner(namehash), &owner);
+        if input.is_ascii() {
+            if len > MAX_OUTPUT_LEN {
+                invalid(env);
+            }
+            let mut out = Ascii::new();
+            for b in input {
+                out.push(env, b.to_ascii_lowercase());
+            }
+            let out = out.as_slice();
+            if out.len() >= 4 && &out[2..4] == b"--" {
+                if !allow_idn || !out.starts_with(ACE_PREFIX) {
+                    invalid(env);
+                }
+                check_a_label(env, out);
+            } else {
+                check_ldh(env, out);
+            }
+            return Bytes::from_slice(env, out);
         }
 
-        pub fn set_resolver(env: Env, namehash: BytesN<32>, resolver: Address) {
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Resolver(namehash), &resolver);
+        if !allow_idn {
+            invalid(env);
         }
-
-        pub fn resolver(env: Env, namehash: BytesN<32>) -> Option<Address> {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Resolver(namehash))
+        let mut cps = CodePoints::new();
+        decode_utf8(env, input, &mut cps);
+        for idx in 0..cps.len {
+            cps.buf[idx] = fold(cps.buf[idx]).unwrap_or_else(|| invalid(env));
         }
-
-        pub fn renew(env: Env, namehash: BytesN<32>) {
-            let owner: Address = env
-                .storage()
-                .persistent()
-                .get(&MockRegistryKey::Owner(namehash.clone()))
-                .unwrap_or_else(|| panic!("owner not set"));
-            owner.require_auth();
-            let now = env.ledger().timestamp();
-            let current = env
-                .storage()
-                .persistent()
-                .get(&MockRegistryKey::Expires(namehash.clone()))
-                .unwrap_or(0u64);
-            let base = if current > now { current } else { now };
-            let new_expiry = base.checked_add(MOCK_RENEW_EXTENSION).unwrap_or(u64::MAX);
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Expires(namehash), &new_expiry);
+        check_u_label(env, cps.as_slice());
+        let mut out = Ascii::new();
+        for b in ACE_PREFIX {
+            out.push(env, *b);
         }
+        encode(env, cps.as_slice(), &mut out);
+        Bytes::from_slice(env, out.as_slice())
+    }
 
-        pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Expires(namehash.clone()))
-                .unwrap_or_else(|| panic!("expiry not set"))
+    /// Lowercase ASCII letters, digits, and hyphens that neither lead nor trail.
+    fn check_ldh(env: &Env, label: &[u8]) {
+        let last_idx = label.len() - 1;
+        for (idx, b) in label.iter().enumerate() {
+            match b {
+                b'a'..=b'z' | b'0'..=b'9' => {}
+                b'-' if idx != 0 && idx != last_idx => {}
+                _ => invalid(env),
+            }
         }
     }
 
-    fn setup_env() -> (Env, Address, Address, Address) {
-        let env = Env::default();
-        let registry_id = env.register(MockRegistry, ());
-        let registrar_id = env.register(Registrar, ());
-        let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        let admin = Address::generate(&env);
-        let tld = Bytes::from_slice(&env, b"stellar");
-        registrar_client.init(&registry_id, &tld, &admin);
-        env.mock_all_auths();
-        (env, registry_id, registrar_id, admin)
+    /// `label` starts with `xn--`; its decoding must be a valid lowercase U-label that
+    /// encodes back to exactly `label`.
+    fn check_a_label(env: &Env, label: &[u8]) {
+        let mut cps = CodePoints::new();
+        decode(env, &label[ACE_PREFIX.len()..], &mut cps);
+        for cp in cps.as_slice() {
+            if fold(*cp) != Some(*cp) {
+                invalid(env);
+            }
+        }
+        if cps.as_slice().iter().all(|cp| *cp < 0x80) {
+            invalid(env);
+        }
+        check_u_label(env, cps.as_slice());
+        let mut reencoded = Ascii::new();
+        for b in ACE_PREFIX {
+            reencoded.push(env, *b);
+        }
+        encode(env, cps.as_slice(), &mut reencoded);
+        if reencoded.as_slice() != label {
+            invalid(env);
+        }
+    }
+
+    /// Hyphen placement, a single script, and no whole-script confusables, so `аре`
+    /// (Cyrillic) cannot pass for `ape` and `аlice` cannot pass for `alice`.
+    fn check_u_label(env: &Env, cps: &[u32]) {
+        let hyphen = '-' as u32;
+        if cps.is_empty() || cps[0] == hyphen || cps[cps.len() - 1] == hyphen {
+            invalid(env);
+        }
+        if cps.len() >= 4 && cps[2] == hyphen && cps[3] == hyphen {
+            invalid(env);
+        }
+        let mut label_script = Script::Common;
+        let mut all_confusable = true;
+        for cp in cps {
+            let cp_script = script(*cp);
+            if cp_script == Script::Common {
+                continue;
+            }
+            if label_script == Script::Common {
+                label_script = cp_script;
+            } else if label_script != cp_script {
+                invalid(env);
+            }
+            all_confusable &= confusable(*cp);
+        }
+        if label_script != Script::Latin && label_script != Script::Common && all_confusable {
+            invalid(env);
+        }
+    }
+
+    /// Lowercase form of a permitted code point, or `None` if it may not appear in a label.
+    /// Latin (ASCII, Latin-1, Latin Extended-A), Greek, and basic Cyrillic letters are
+    /// permitted alongside ASCII digits and hyphens.
+    fn fold(cp: u32) -> Option<u32> {
+        match cp {
+            0x2D | 0x30..=0x39 | 0x61..=0x7A => Some(cp),
+            0x41..=0x5A => Some(cp + 0x20),
+            0xD7 | 0xF7 => None,
+            0xC0..=0xDE => Some(cp + 0x20),
+            0xDF..=0xFF => Some(cp),
+            // Dotted and dotless i fold ambiguously; `ŉ` and long s are compatibility forms.
+            0x130 | 0x131 | 0x149 | 0x17F => None,
+            0x138 => Some(cp),
+            0x100..=0x137 | 0x14A..=0x177 => Some(cp | 1),
+            0x139..=0x148 | 0x179..=0x17E => Some(cp + (cp & 1)),
+            0x178 => Some(0xFF),
+            0x386 => Some(0x3AC),
+            0x388..=0x38A => Some(cp + 0x25),
+            0x38C => Some(0x3CC),
+            0x38E | 0x38F => Some(cp + 0x3F),
+            0x3A2 => None,
+            0x391..=0x3A9 => Some(cp + 0x20),
+            0x3AC..=0x3CE => Some(cp),
+            0x400..=0x40F => Some(cp + 0x50),
+            0x410..=0x42F => Some(cp + 0x20),
+            0x430..=0x45F => Some(cp),
+            _ => None,
+        }
+    }
+
+    fn script(cp: u32) -> Script {
+        match cp {
+            0x61..=0x7A | 0xDF..=0x17F => Script::Latin,
+            0x370..=0x3FF => Script::Greek,
+            0x400..=0x4FF => Script::Cyrillic,
+            _ => Script::Common,
+        }
+    }
+
+    /// Lowercase Greek and Cyrillic letters that render like a Latin letter.
+    fn confusable(cp: u32) -> bool {
+        matches!(
+            cp,
+            0x3B1
+                | 0x3B3
+                | 0x3B9
+                | 0x3BA
+                | 0x3BD
+                | 0x3BF
+                | 0x3C1
+                | 0x3C5
+                | 0x3C7
+                | 0x430
+                | 0x435
+                | 0x43E
+                | 0x440
+                | 0x441
+                | 0x443
+                | 0x445
+                | 0x451
+                | 0x455
+                | 0x456
+                | 0x457
+                | 0x458
+        )
+    }
+
+    /// Only one- and two-byte sequences are decoded; longer ones encode code points that
+    /// `fold` rejects anyway.
+    fn decode_utf8(env: &Env, input: &[u8], out: &mut CodePoints) {
+        let mut idx = 0;
+        while idx < input.len() {
+            let lead = input[idx];
+            let cp = match lead {
+                0x00..=0x7F => {
+                    idx += 1;
+                    lead as u32
+                }
+                0xC2..=0xDF => {
+                    let cont = *input.get(idx + 1).unwrap_or_else(|| invalid(env));
+                    if cont & 0xC0 != 0x80 {
+                        invalid(env);
+                    }
+                    idx += 2;
+                    ((lead as u32 & 0x1F) << 6) | (cont as u32 & 0x3F)
+                }
+                _ => invalid(env),
+            };
+            out.insert(env, out.len, cp);
+        }
+    }
+
+    fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+        let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+        delta += delta / num_points;
+        let mut k = 0;
+        while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+            delta /= BASE - T_MIN;
+            k += BASE;
+        }
+        k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+    }
+
+    fn threshold(k: u32, bias: u32) -> u32 {
+        if k <= bias {
+            T_MIN
+        } else if k >= bias + T_MAX {
+            T_MAX
+        } else {
+            k - bias
+        }
+    }
+
+    fn encode_digit(d: u32) -> u8 {
+        if d < 26 {
+            b'a' + d as u8
+        } else {
+            b'0' + (d - 26) as u8
+        }
+    }
+
+    /// Lowercase digits only: uppercase A-labels are not canonical.
+    fn decode_digit(b: u8) -> Option<u32> {
+        match b {
+            b'a'..=b'z' => Some((b - b'a') as u32),
+            b'0'..=b'9' => Some((b - b'0') as u32 + 26),
+            _ => None,
+        }
+    }
+
+    /// RFC 3492 section 6.3.
+    fn encode(env: &Env, cps: &[u32], out: &mut Ascii) {
+        for cp in cps.iter().filter(|cp| **cp < 0x80) {
+            out.push(env, *cp as u8);
+        }
+        let basic = cps.iter().filter(|cp| **cp < 0x80).count() as u32;
+        if basic > 0 {
+            out.push(env, b'-');
+        }
+        let total = cps.len() as u32;
+        let mut handled = basic;
+        let mut n = INITIAL_N;
+        let mut delta: u32 = 0;
+        let mut bias = INITIAL_BIAS;
+        while handled < total {
+            let m = cps
+                .iter()
+                .copied()
+                .filter(|cp| *cp >= n)
+                .min()
+                .unwrap_or_else(|| invalid(env));
+            delta = (m - n)
+                .checked_mul(handled + 1)
+                .and_then(|d| delta.checked_add(d))
+                .unwrap_or_else(|| invalid(env));
+            n = m;
+            for cp in cps {
+                if *cp < n {
+                    delta = delta.checked_add(1).unwrap_or_else(|| invalid(env));
+                }
+                if *cp == n {
+                    let mut q = delta;
+                    let mut k = BASE;
+                    loop {
+                        let t = threshold(k, bias);
+                        if q < t {
+                            break;
+                        }
+                        out.push(env, encode_digit(t + (q - t) % (BASE - t)));
+                        q = (q - t) / (BASE - t);
+                        k += BASE;
+                    }
+                    out.push(env, encode_digit(q));
+                    bias = adapt(delta, handled + 1, handled == basic);
+                    delta = 0;
+                    handled += 1;
+                }
+            }
+            delta += 1;
+            n += 1;
+        }
+    }
+
+    /// RFC 3492 section 6.2.
+    fn decode(env: &Env, input: &[u8], out: &mut CodePoints) {
+        let start = match input.iter().rposition(|b| *b == b'-') {
+            Some(pos) => {
+                for b in &input[..pos] {
+                    out.insert(env, out.len, *b as u32);
+                }
+                pos + 1
+            }
+            None => 0,
+        };
+        let mut n = INITIAL_N;
+        let mut i: u32 = 0;
+        let mut bias = INITIAL_BIAS;
+        let mut idx = start;
+        while idx < input.len() {
+            let old_i = i;
+            let mut w: u32 = 1;
+            let mut k = BASE;
+            loop {
+                let b = *input.get(idx).unwrap_or_else(|| invalid(env));
+                idx += 1;
+                let digit = decode_digit(b).unwrap_or_else(|| invalid(env));
+                i = digit
+                    .checked_mul(w)
+                    .and_then(|d| i.checked_add(d))
+                    .unwrap_or_else(|| invalid(env));
+                let t = threshold(k, bias);
+                if digit < t {
+                    break;
+                }
+                w = w.checked_mul(BASE - t).unwrap_or_else(|| invalid(env));
+                k += BASE;
+            }
+            let count = out.len as u32 + 1;
+            bias = adapt(i - old_i, count, old_i == 0);
+            n = n.checked_add(i / count).unwrap_or_else(|| invalid(env));
+            i %= count;
+            out.insert(env, i as usize, n);
+            i += 1;
+        }
+    }
+}
+
+mod pricing {
+    use super::*;
+
+    /// Pricing tiers are quoted per year of registration.
+    pub const SECONDS_PER_YEAR: u64 = 31_536_000;
+
+    /// Registrations are free until the admin installs a schedule.
+    pub fn default_schedule(env: &Env) -> PriceSchedule {
+        PriceSchedule {
+            annual_prices: vec![env, 0i128],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        }
+    }
+
+    pub fn validate_schedule(env: &Env, schedule: &PriceSchedule) {
+        if schedule.annual_prices.is_empty() {
+            panic_with_error!(env, RegistrarError::InvalidParams);
+        }
+        for price in schedule.annual_prices.iter() {
+            if price < 0 {
+                panic_with_error!(env, RegistrarError::InvalidParams);
+            }
+        }
+        if schedule.premium_start < 0
+            || (schedule.premium_start > 0 && schedule.premium_decay_secs == 0)
+        {
+            panic_with_error!(env, RegistrarError::InvalidParams);
+        }
+    }
+
+    pub fn validate_strategy(env: &Env, strategy: &PricingStrategy) {
+        let valid = match strategy {
+            PricingStrategy::LengthTiered => true,
+            PricingStrategy::Fixed(price) => *price >= 0,
+            PricingStrategy::DutchAuction(auction) => {
+                auction.floor_price >= 0
+                    && auction.start_price >= auction.floor_price
+                    && auction.duration_secs > 0
+            }
+        };
+        if !valid {
+            panic_with_error!(env, RegistrarError::InvalidParams);
+        }
+    }
+
+    /// Current annual price of a Dutch auction curve. Before `starts_at` the price is
+    /// `start_price`.
+    pub fn auction_price(env: &Env, auction: &DutchAuction) -> i128 {
+        let elapsed = env.ledger().timestamp().saturating_sub(auction.starts_at);
+        if elapsed >= auction.duration_secs {
+            return auction.floor_price;
+        }
+        let spread = auction.start_price - auction.floor_price;
+        let remaining = (auction.duration_secs - elapsed) as i128;
+        spread
+            .checked_mul(remaining)
+            .map(|decay| auction.floor_price + decay / auction.duration_secs as i128)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+    }
+
+    /// Temporary premium `released_secs` after a name left its grace period: starts at
+    /// `premium_start` and falls linearly to zero over `premium_decay_secs`.
+    pub fn premium(schedule: &PriceSchedule, released_secs: u64) -> i128 {
+        if schedule.premium_start == 0 || released_secs >= schedule.premium_decay_secs {
+            return 0;
+        }
+        let remaining = (schedule.premium_decay_secs - released_secs) as i128;
+        schedule.premium_start * remaining / schedule.premium_decay_secs as i128
+    }
+
+    /// Annual price for a label of `len` bytes. Lengths beyond the last tier use the last tier.
+    pub fn annual_price(schedule: &PriceSchedule, len: u32) -> i128 {
+        let tiers = schedule.annual_prices.len();
+        let idx = len.saturating_sub(1).min(tiers - 1);
+        schedule.annual_prices.get(idx).unwrap_or(0)
+    }
+
+    /// The default TLD keeps the unsuffixed keys it used before `add_tld` existed.
+    fn length_policy_key(env: &Env, tld: &Bytes, len: u32) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::LENGTH_POLICY);
+        key.extend_from_array(&len.to_be_bytes());
+        if *tld != read_tld(env) {
+            key.append(tld);
+        }
+        key
+    }
+
+    pub fn default_length_policy() -> LengthPolicy {
+        LengthPolicy {
+            enabled: true,
+            price_multiplier: 1,
+        }
+    }
+
+    pub fn length_policy(env: &Env, tld: &Bytes, len: u32) -> LengthPolicy {
+        let storage = env.storage().persistent();
+        storage
+            .get(&length_policy_key(env, tld, len))
+            .unwrap_or_else(default_length_policy)
+    }
+
+    pub fn set_length_policy(env: &Env, tld: &Bytes, len: u32, policy: &LengthPolicy) {
+        let key = length_policy_key(env, tld, len);
+        if *policy == default_length_policy() {
+            env.storage().persistent().remove(&key);
+        } else {
+            write_entry(env, &key, policy);
+        }
+    }
+
+    /// Prorated price for holding a label of `len` bytes under `tld` for `duration_secs`,
+    /// per the TLD's pricing strategy and including the length's price multiplier.
+    pub fn rent_price(env: &Env, tld: &Bytes, len: u32, duration_secs: u64) -> i128 {
+        let annual = match tld_params(env, tld).pricing {
+            PricingStrategy::LengthTiered => annual_price(&tld_schedule(env, tld), len),
+            PricingStrategy::Fixed(price) => price,
+            PricingStrategy::DutchAuction(auction) => auction_price(env, &auction),
+        };
+        let multiplier = length_policy(env, tld, len).price_multiplier as i128;
+        annual
+            .checked_mul(multiplier)
+            .and_then(|annual| annual.checked_mul(duration_secs as i128))
+            .map(|total| total / SECONDS_PER_YEAR as i128)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+    }
+}
+
+mod payments {
+    use super::*;
+
+    /// Pull `amount` of the payment token from `payer` into the Registrar's balance.
+    pub fn collect(env: &Env, payer: &Address, amount: i128) {
+        if amount <= 0 {
+            return;
+        }
+        let token = TokenClient::new(env, &read_token(env));
+        token.transfer(payer, env.current_contract_address(), &amount);
+    }
+
+    /// Fail up front when `payer` cannot cover `total`, rather than partway through a batch.
+    pub fn ensure_covers(env: &Env, payer: &Address, total: i128) {
+        if total <= 0 {
+            return;
+        }
+        let token = TokenClient::new(env, &read_token(env));
+        if token.balance(payer) < total {
+            panic_with_error!(env, RegistrarError::InsufficientFunds);
+        }
+    }
+
+    /// Send `amount` of accrued fees from the Registrar's balance to `to`.
+    pub fn pay_out(env: &Env, to: &Address, amount: i128) {
+        let token = TokenClient::new(env, &read_token(env));
+        token.transfer(&env.current_contract_address(), to, &amount);
+    }
+}
+
+/// Headline counters for `stats`, kept in one entry.
+mod stats {
+    use super::*;
+
+    pub fn get(env: &Env) -> RegistrarStats {
+        env.storage()
+            .persistent()
+            .get(&singleton_key(env, keys::STATS))
+            .unwrap_or(RegistrarStats {
+                total_registrations: 0,
+                active_names: 0,
+                registrations_by_length: Vec::new(env),
+            })
+    }
+
+    fn put(env: &Env, stats: &RegistrarStats) {
+        write_entry(env, &singleton_key(env, keys::STATS), stats);
+    }
+
+    /// Count a registration of a `len`-byte label. `replaces` is set when the name still had
+    /// a lapsed owner, which the new registration takes over rather than adds to.
+    pub fn registered(env: &Env, len: u32, replaces: bool) {
+        let mut stats = get(env);
+        stats.total_registrations += 1;
+        if !replaces {
+            stats.active_names += 1;
+        }
+        let by_length = &mut stats.registrations_by_length;
+        while by_length.len() < len {
+            by_length.push_back(0);
+        }
+        let slot = len - 1;
+        by_length.set(slot, by_length.get_unchecked(slot) + 1);
+        put(env, &stats);
+    }
+
+    /// Count a name cleared by `release` or `reap`.
+    pub fn removed(env: &Env) {
+        let mut stats = get(env);
+        stats.active_names = stats.active_names.saturating_sub(1);
+        put(env, &stats);
+    }
+}
+
+/// Cumulative fee income, so a treasury can reconcile without replaying events.
+mod revenue {
+    use super::*;
+
+    fn total_key(env: &Env, token: &Address) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::REVENUE);
+        key.append(&token.clone().to_xdr(env));
+        key
+    }
+
+    fn period_key(env: &Env, period_id: u32) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::REVENUE_PERIOD);
+        key.extend_from_array(&period_id.to_be_bytes());
+        key
+    }
+
+    pub fn period_of(timestamp: u64) -> u32 {
+        (timestamp / REVENUE_PERIOD_SECS) as u32
+    }
+
+    pub fn total(env: &Env, token: &Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&total_key(env, token))
+            .unwrap_or(0)
+    }
+
+    pub fn period(env: &Env, period_id: u32) -> Revenue {
+        env.storage()
+            .persistent()
+            .get(&period_key(env, period_id))
+            .unwrap_or(Revenue {
+                registrations: 0,
+                renewals: 0,
+            })
+    }
+
+    /// Add a collected registration or renewal fee of `amount` payment tokens.
+    pub fn record(env: &Env, amount: i128, renewal: bool) {
+        if amount <= 0 {
+            return;
+        }
+        let token = read_token(env);
+        let total = add_fee(env, total(env, &token), amount);
+        write_entry(env, &total_key(env, &token), &total);
+
+        let period_id = period_of(env.ledger().timestamp());
+        let mut bucket = period(env, period_id);
+        if renewal {
+            bucket.renewals = add_fee(env, bucket.renewals, amount);
+        } else {
+            bucket.registrations = add_fee(env, bucket.registrations, amount);
+        }
+        write_entry(env, &period_key(env, period_id), &bucket);
+    }
+}
+
+mod referrals {
+    use super::*;
+
+    fn balance_key(env: &Env, referrer: &Address) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::REFERRAL);
+        key.append(&referrer.clone().to_xdr(env));
+        key
+    }
+
+    pub fn balance(env: &Env, referrer: &Address) -> i128 {
+        let storage = env.storage().persistent();
+        storage.get(&balance_key(env, referrer)).unwrap_or(0)
+    }
+
+    /// Fees credited to referrers but not yet claimed; `withdraw` cannot touch them.
+    pub fn total_owed(env: &Env) -> i128 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&singleton_key(env, keys::REFERRALS_OWED))
+            .unwrap_or(0)
+    }
+
+    /// Credits `referrer` with its share of `price` and returns the amount credited.
+    pub fn accrue(env: &Env, referrer: &Address, price: i128, bps: u32) -> i128 {
+        let share = price
+            .checked_mul(bps as i128)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+            / MAX_BPS as i128;
+        if share > 0 {
+            write_entry(
+                env,
+                &balance_key(env, referrer),
+                &(balance(env, referrer) + share),
+            );
+            let owed = total_owed(env) + share;
+            write_entry(env, &singleton_key(env, keys::REFERRALS_OWED), &owed);
+        }
+        share
+    }
+
+    /// Zeroes `referrer`'s balance and returns what it held.
+    pub fn take(env: &Env, referrer: &Address) -> i128 {
+        let amount = balance(env, referrer);
+        if amount > 0 {
+            env.storage()
+                .persistent()
+                .remove(&balance_key(env, referrer));
+            let owed = total_owed(env) - amount;
+            write_entry(env, &singleton_key(env, keys::REFERRALS_OWED), &owed);
+        }
+        amount
+    }
+}
+
+/// Per-caller registration limits. Counts live in temporary storage, one entry per fixed
+/// window, and a sliding window is approximated by weighting the previous window's count by
+/// how much of it still overlaps the last `rate_limit_window_secs`.
+mod rate_limit {
+    use super::*;
+
+    /// Ledgers are ~5 seconds apart.
+    const SECS_PER_LEDGER: u64 = 5;
+
+    fn count_key(env: &Env, tld: &Bytes, caller: &Address, window: u64) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::RATE);
+        key.extend_from_array(&window.to_be_bytes());
+        key.append(&caller.clone().to_xdr(env));
+        key.append(tld);
+        key
+    }
+
+    fn count(env: &Env, key: &Bytes) -> u32 {
+        env.storage().temporary().get(key).unwrap_or(0)
+    }
+
+    /// Counts one registration by `caller`, aborting with `RateLimited` if the sliding
+    /// window already holds `rate_limit_max` of them.
+    pub fn record(env: &Env, params: &RegistrarParams, tld: &Bytes, caller: &Address) {
+        if params.rate_limit_max == 0 {
+            return;
+        }
+        let span = params.rate_limit_window_secs;
+        let now = env.ledger().timestamp();
+        let window = now / span;
+        let key = count_key(env, tld, caller, window);
+        let current = count(env, &key);
+        let previous = match window.checked_sub(1) {
+            Some(prev) => count(env, &count_key(env, tld, caller, prev)),
+            None => 0,
+        };
+        let overlap = span - now % span;
+        // Rounded up, so a full previous window never lets a burst through early.
+        let carried = (previous as u64).saturating_mul(overlap).div_ceil(span);
+        let estimate = carried + current as u64;
+        if estimate >= params.rate_limit_max as u64 {
+            panic_with_error!(env, RegistrarError::RateLimited);
+        }
+
+        // Keep the entry through the next window, where it is the weighted `previous`.
+        let ledgers = (2 * span / SECS_PER_LEDGER + 1).min(env.storage().max_ttl() as u64) as u32;
+        let storage = env.storage().temporary();
+        storage.set(&key, &(current + 1));
+        storage.extend_ttl(&key, ledgers, ledgers);
+    }
+}
+
+/// M-of-N admin council and its proposals.
+mod council {
+    use super::*;
+
+    pub fn read(env: &Env) -> Option<AdminCouncil> {
+        let storage = env.storage().persistent();
+        storage.get(&singleton_key(env, keys::COUNCIL))
+    }
+
+    /// Between one and `MAX_COUNCIL_MEMBERS` distinct members and a threshold they can meet.
+    pub fn write(env: &Env, council: &AdminCouncil) {
+        let members = &council.members;
+        let unique = members
+            .iter()
+            .enumerate()
+            .all(|(i, member)| members.first_index_of(&member) == Some(i as u32));
+        if members.is_empty()
+            || members.len() > MAX_COUNCIL_MEMBERS
+            || !unique
+            || council.threshold == 0
+            || council.threshold > members.len()
+        {
+            panic_with_error!(env, RegistrarError::InvalidCouncil);
+        }
+        write_entry(env, &singleton_key(env, keys::COUNCIL), council);
+        EvtCouncilChanged {
+            members: members.clone(),
+            threshold: council.threshold,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+    }
+
+    /// The council, provided `member` sits on it.
+    pub fn require_member(env: &Env, member: &Address) -> AdminCouncil {
+        match read(env) {
+            Some(council) if council.members.contains(member) => council,
+            _ => panic_with_error!(env, RegistrarError::NotCouncilMember),
+        }
+    }
+
+    /// Approvals that still count: members removed since approving no longer do.
+    pub fn approvals(council: &AdminCouncil, proposal: &Proposal) -> u32 {
+        proposal
+            .approvals
+            .iter()
+            .filter(|member| council.members.contains(member))
+            .count() as u32
+    }
+
+    fn proposal_key(env: &Env, id: u32) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::PROPOSAL);
+        key.extend_from_array(&id.to_be_bytes());
+        key
+    }
+
+    pub fn next_id(env: &Env) -> u32 {
+        let key = singleton_key(env, keys::PROPOSAL_SEQ);
+        let id: u32 = env.storage().persistent().get(&key).unwrap_or(0) + 1;
+        write_entry(env, &key, &id);
+        id
+    }
+
+    pub fn proposal(env: &Env, id: u32) -> Option<Proposal> {
+        env.storage().persistent().get(&proposal_key(env, id))
+    }
+
+    /// A live proposal; expired ones can no longer be approved or executed.
+    pub fn open_proposal(env: &Env, id: u32) -> Proposal {
+        let proposal = proposal(env, id)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::ProposalMissing));
+        if env.ledger().timestamp() > proposal.expires_at {
+            panic_with_error!(env, RegistrarError::ProposalExpired);
+        }
+        proposal
+    }
+
+    pub fn store(env: &Env, id: u32, proposal: &Proposal) {
+        write_entry(env, &proposal_key(env, id), proposal);
+    }
+
+    pub fn remove(env: &Env, id: u32) {
+        env.storage().persistent().remove(&proposal_key(env, id));
+    }
+}
+
+/// Delay between queueing a `TimelockedChange` and applying it.
+mod timelock {
+    use super::*;
+
+    pub fn delay(env: &Env) -> u64 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&singleton_key(env, keys::TIMELOCK))
+            .unwrap_or(0)
+    }
+
+    pub fn set_delay(env: &Env, delay_secs: u64) {
+        if delay_secs > MAX_TIMELOCK_SECS {
+            panic_with_error!(env, RegistrarError::InvalidParams);
+        }
+        write_entry(env, &singleton_key(env, keys::TIMELOCK), &delay_secs);
+        EvtTimelockChanged {
+            delay_secs,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+    }
+
+    fn queued_key(env: &Env, id: u32) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::QUEUED);
+        key.extend_from_array(&id.to_be_bytes());
+        key
+    }
+
+    pub fn queued(env: &Env, id: u32) -> Option<QueuedChange> {
+        env.storage().persistent().get(&queued_key(env, id))
+    }
+
+    /// Queues `change` to become executable after the current delay. Returns its id.
+    pub fn queue(env: &Env, change: TimelockedChange) -> u32 {
+        let seq_key = singleton_key(env, keys::QUEUE_SEQ);
+        let id: u32 = env.storage().persistent().get(&seq_key).unwrap_or(0) + 1;
+        write_entry(env, &seq_key, &id);
+        let eta = env.ledger().timestamp().saturating_add(delay(env));
+        let queued = QueuedChange {
+            change: change.clone(),
+            eta,
+        };
+        write_entry(env, &queued_key(env, id), &queued);
+        EvtParamsQueued {
+            id,
+            change,
+            eta,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+        id
+    }
+
+    /// Removes a queued change, returning it.
+    pub fn take(env: &Env, id: u32) -> QueuedChange {
+        let queued = queued(env, id)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::ChangeMissing));
+        env.storage().persistent().remove(&queued_key(env, id));
+        queued
+    }
+
+    pub fn cancel(env: &Env, id: u32) {
+        take(env, id);
+        EvtParamsCancelled {
+            id,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+    }
+}
+
+/// Discount codes, stored by `sha256(code)`.
+mod vouchers {
+    use super::*;
+
+    fn key(env: &Env, hash: &BytesN<32>) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::VOUCHER);
+        key.append(&Bytes::from_array(env, &hash.to_array()));
+        key
+    }
+
+    pub fn get(env: &Env, hash: &BytesN<32>) -> Option<Voucher> {
+        env.storage().persistent().get(&key(env, hash))
+    }
+
+    pub fn mint(env: &Env, hash: &BytesN<32>, voucher: &Voucher) {
+        if get(env, hash).is_some() {
+            panic_with_error!(env, RegistrarError::VoucherExists);
+        }
+        write_entry(env, &key(env, hash), voucher);
+        EvtVoucherMinted {
+            hash: hash.clone(),
+            discount_bps: voucher.discount_bps,
+            uses: voucher.uses_left,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+    }
+
+    pub fn revoke(env: &Env, hash: &BytesN<32>) {
+        if get(env, hash).is_none() {
+            panic_with_error!(env, RegistrarError::InvalidVoucher);
+        }
+        env.storage().persistent().remove(&key(env, hash));
+        EvtVoucherRevoked {
+            hash: hash.clone(),
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+    }
+
+    /// Use one redemption of `code` against `price`, returning the discount (rounded down).
+    pub fn redeem(env: &Env, code: &Bytes, namehash: &BytesN<32>, price: i128) -> i128 {
+        let hash = env.crypto().sha256(code).to_bytes();
+        let mut voucher = get(env, &hash)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::InvalidVoucher));
+        voucher.uses_left -= 1;
+        if voucher.uses_left == 0 {
+            env.storage().persistent().remove(&key(env, &hash));
+        } else {
+            write_entry(env, &key(env, &hash), &voucher);
+        }
+        let discount = price
+            .checked_mul(voucher.discount_bps as i128)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::PriceOverflow))
+            / MAX_BPS as i128;
+        EvtVoucherRedeemed {
+            hash,
+            namehash: namehash.clone(),
+            discount,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+        discount
+    }
+}
+
+/// Every name this Registrar has issued, in first-registration order, for `export_state`.
+/// Re-registrations keep a name's original position.
+mod name_index {
+    use super::*;
+
+    fn position_key(env: &Env, position: u32) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::NAME_AT);
+        key.extend_from_array(&position.to_be_bytes());
+        key
+    }
+
+    fn seen_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::NAME_SEEN);
+        key.append(&Bytes::from_array(env, &namehash.to_array()));
+        key
+    }
+
+    pub fn count(env: &Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&singleton_key(env, keys::NAME_COUNT))
+            .unwrap_or(0)
+    }
+
+    pub fn add(env: &Env, namehash: &BytesN<32>) {
+        let seen = seen_key(env, namehash);
+        if env.storage().persistent().has(&seen) {
+            extend_entry(env, &seen);
+            return;
+        }
+        let position = count(env);
+        write_entry(env, &position_key(env, position), namehash);
+        write_entry(env, &seen, &true);
+        write_entry(env, &singleton_key(env, keys::NAME_COUNT), &(position + 1));
+    }
+
+    pub fn get(env: &Env, position: u32) -> Option<BytesN<32>> {
+        let key = position_key(env, position);
+        extend_entry(env, &key);
+        env.storage().persistent().get(&key)
+    }
+}
+
+/// Names registered by `register_gift` and held by the Registrar until delivered.
+mod gifts {
+    use super::*;
+
+    fn key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::GIFT);
+        key.append(&Bytes::from_array(env, &namehash.to_array()));
+        key
+    }
+
+    pub fn get(env: &Env, namehash: &BytesN<32>) -> Option<Gift> {
+        env.storage().persistent().get(&key(env, namehash))
+    }
+
+    pub fn read(env: &Env, namehash: &BytesN<32>) -> Gift {
+        get(env, namehash).unwrap_or_else(|| panic_with_error!(env, RegistrarError::GiftMissing))
+    }
+
+    pub fn store(env: &Env, namehash: &BytesN<32>, gift: &Gift) {
+        write_entry(env, &key(env, namehash), gift);
+    }
+
+    /// Transfer a held name to `to` and mint its token. A gift whose name lapsed and was
+    /// registered again while held is dropped without delivery.
+    pub fn deliver(env: &Env, namehash: &BytesN<32>, to: &Address) {
+        env.storage().persistent().remove(&key(env, namehash));
+        let registry = read_registry(env);
+        if registry_api::owner(env, &registry, namehash) != Some(env.current_contract_address()) {
+            panic_with_error!(env, RegistrarError::GiftMissing);
+        }
+        registry_api::transfer(env, &registry, namehash, to);
+        if let Some(name_token) = read_name_token(env) {
+            name_token_api::mint(env, &name_token, namehash, to);
+        }
+    }
+}
+
+/// Per-name record of rent paid since registration, kept until the name is released,
+/// reaped, or registered again.
+mod paid {
+    use super::*;
+
+    fn key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::PAID);
+        key.append(&Bytes::from_array(env, &namehash.to_array()));
+        key
+    }
+
+    pub fn get(env: &Env, namehash: &BytesN<32>) -> PaidTerm {
+        env.storage()
+            .persistent()
+            .get(&key(env, namehash))
+            .unwrap_or_default()
+    }
+
+    /// Adds a term of `secs` that cost `amount`.
+    pub fn add(env: &Env, namehash: &BytesN<32>, amount: i128, secs: u64) {
+        let current = get(env, namehash);
+        let updated = PaidTerm {
+            amount: add_fee(env, current.amount, amount),
+            secs: current.secs.saturating_add(secs),
+        };
+        write_entry(env, &key(env, namehash), &updated);
+    }
+
+    pub fn clear(env: &Env, namehash: &BytesN<32>) {
+        env.storage().persistent().remove(&key(env, namehash));
+    }
+}
+
+/// Prepaid renewal balances. Deposits stay in the Registrar's token balance, reserved from
+/// `withdraw`, until they pay for an `auto_renew` or are withdrawn by their owner.
+mod escrow {
+    use super::*;
+
+    fn balance_key(env: &Env, owner: &Address) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::ESCROW);
+        key.append(&owner.clone().to_xdr(env));
+        key
+    }
+
+    fn auto_renew_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::AUTO_RENEW);
+        key.extend_from_array(&namehash.to_array());
+        key
+    }
+
+    pub fn balance(env: &Env, owner: &Address) -> i128 {
+        let storage = env.storage().persistent();
+        storage.get(&balance_key(env, owner)).unwrap_or(0)
+    }
+
+    pub fn total_held(env: &Env) -> i128 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&singleton_key(env, keys::ESCROW_HELD))
+            .unwrap_or(0)
+    }
+
+    fn write_balance(env: &Env, owner: &Address, balance: i128, held: i128) {
+        let key = balance_key(env, owner);
+        if balance == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            write_entry(env, &key, &balance);
+        }
+        write_entry(env, &singleton_key(env, keys::ESCROW_HELD), &held);
+    }
+
+    pub fn credit(env: &Env, owner: &Address, amount: i128) {
+        let balance = balance(env, owner)
+            .checked_add(amount)
+            .unwrap_or_else(|| panic_with_error!(env, RegistrarError::InvalidAmount));
+        write_balance(env, owner, balance, total_held(env) + amount);
+    }
+
+    /// Releases `amount` from `owner`'s escrow; the tokens become regular Registrar balance.
+    pub fn debit(env: &Env, owner: &Address, amount: i128) {
+        let balance = balance(env, owner);
+        if amount > balance {
+            panic_with_error!(env, RegistrarError::InsufficientEscrow);
+        }
+        write_balance(env, owner, balance - amount, total_held(env) - amount);
+    }
+
+    pub fn sponsor(env: &Env, namehash: &BytesN<32>) -> Option<Address> {
+        let storage = env.storage().persistent();
+        storage.get(&auto_renew_key(env, namehash))
+    }
+
+    pub fn set_sponsor(env: &Env, namehash: &BytesN<32>, sponsor: Option<&Address>) {
+        let key = auto_renew_key(env, namehash);
+        match sponsor {
+            Some(sponsor) => write_entry(env, &key, sponsor),
+            None => env.storage().persistent().remove(&key),
+        }
+    }
+}
+
+/// Registration budgets a sponsor pre-funds for one owner, spent by `register` when the
+/// sponsor registers a name for that owner.
+mod sponsorship {
+    use super::*;
+
+    fn allowance_key(env: &Env, sponsor: &Address, owner: &Address) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::ALLOWANCE);
+        key.append(&sponsor.clone().to_xdr(env));
+        key.append(&owner.clone().to_xdr(env));
+        key
+    }
+
+    pub fn allowance(env: &Env, sponsor: &Address, owner: &Address) -> i128 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&allowance_key(env, sponsor, owner))
+            .unwrap_or(0)
+    }
+
+    pub fn total_held(env: &Env) -> i128 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&singleton_key(env, keys::ALLOWANCE_HELD))
+            .unwrap_or(0)
+    }
+
+    /// Replaces the allowance and keeps the reserved total in step. Token movements are the
+    /// caller's job.
+    pub fn set(env: &Env, sponsor: &Address, owner: &Address, amount: i128) {
+        let key = allowance_key(env, sponsor, owner);
+        let held = total_held(env) - allowance(env, sponsor, owner) + amount;
+        if amount == 0 {
+            env.storage().persistent().remove(&key);
+        } else {
+            write_entry(env, &key, &amount);
+        }
+        write_entry(env, &singleton_key(env, keys::ALLOWANCE_HELD), &held);
+        EvtSponsorAllowanceChanged {
+            sponsor: sponsor.clone(),
+            owner: owner.clone(),
+            allowance: amount,
+            seq: next_event_seq(env),
+        }
+        .publish(env);
+    }
+
+    /// Takes up to `price` from the allowance `sponsor` funded for `owner` and returns how
+    /// much; the tokens become regular Registrar balance.
+    pub fn spend(env: &Env, sponsor: &Address, owner: &Address, price: i128) -> i128 {
+        let allowance = allowance(env, sponsor, owner);
+        let spent = allowance.min(price.max(0));
+        if spent > 0 {
+            set(env, sponsor, owner, allowance - spent);
+        }
+        spent
+    }
+}
+
+/// English auctions of reserved labels, run by the Registrar itself. The leading bid is
+/// escrowed here until `settle_premium` turns it into fee income.
+mod premium {
+    use super::*;
+
+    fn key(env: &Env, label: &Bytes) -> Bytes {
+        let mut key = Bytes::from_slice(env, keys::PREMIUM_AUCTION);
+        key.append(label);
+        key
+    }
+
+    pub fn get(env: &Env, label: &Bytes) -> Option<PremiumAuction> {
+        env.storage().persistent().get(&key(env, label))
+    }
+
+    pub fn read(env: &Env, label: &Bytes) -> PremiumAuction {
+        get(env, label).unwrap_or_else(|| panic_with_error!(env, RegistrarError::NotInAuction))
+    }
+
+    /// Reserved labels with an open auction cannot be released or allocated around it.
+    pub fn ensure_none(env: &Env, label: &Bytes) {
+        if get(env, label).is_some() {
+            panic_with_error!(env, RegistrarError::NameInAuction);
+        }
+    }
+
+    pub fn write(env: &Env, label: &Bytes, auction: &PremiumAuction) {
+        write_entry(env, &key(env, label), auction);
+    }
+
+    pub fn remove(env: &Env, label: &Bytes) {
+        env.storage().persistent().remove(&key(env, label));
+    }
+
+    /// Leading bids of every open auction; `withdraw` cannot touch them.
+    pub fn total_held(env: &Env) -> i128 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&singleton_key(env, keys::PREMIUM_HELD))
+            .unwrap_or(0)
+    }
+
+    pub fn adjust_held(env: &Env, delta: i128) {
+        write_entry(
+            env,
+            &singleton_key(env, keys::PREMIUM_HELD),
+            &(total_held(env) + delta),
+        );
+    }
+
+    /// Smallest bid that can lead: the start price, then the leading bid plus
+    /// `PREMIUM_BID_INCREMENT_BPS` (at least one unit).
+    pub fn min_bid(auction: &PremiumAuction) -> i128 {
+        if auction.highest_bidder.is_none() {
+            return auction.start_price;
+        }
+        let step = auction.highest_bid * PREMIUM_BID_INCREMENT_BPS as i128 / MAX_BPS as i128;
+        auction.highest_bid.saturating_add(step.max(1))
+    }
+}
+
+mod reserved {
+    use super::*;
+
+    /// `tag || label`, then `"." || tld` for TLDs other than the default, which keeps the
+    /// keys it used before `add_tld` existed. Labels never contain a dot.
+    fn key(env: &Env, tag: &[u8], tld: &Bytes, label: &Bytes) -> Bytes {
+        let mut key = Bytes::from_slice(env, tag);
+        key.append(label);
+        if *tld != read_tld(env) {
+            key.push_back(b'.');
+            key.append(tld);
+        }
+        key
+    }
+
+    fn set_flag(env: &Env, key: &Bytes, on: bool) {
+        if on {
+            write_entry(env, key, &true);
+        } else {
+            env.storage().persistent().remove(key);
+        }
+    }
+
+    pub fn contains(env: &Env, tld: &Bytes, label: &Bytes) -> bool {
+        env.storage()
+            .persistent()
+            .has(&key(env, keys::RESERVED, tld, label))
+    }
+
+    pub fn set(env: &Env, tld: &Bytes, label: &Bytes, reserved: bool) {
+        set_flag(env, &key(env, keys::RESERVED, tld, label), reserved);
+    }
+
+    /// Labels withheld from registration while the auction house runs their auction.
+    pub fn held(env: &Env, tld: &Bytes, label: &Bytes) -> bool {
+        env.storage()
+            .persistent()
+            .has(&key(env, keys::AUCTION_HOLD, tld, label))
+    }
+
+    pub fn set_held(env: &Env, tld: &Bytes, label: &Bytes, held: bool) {
+        set_flag(env, &key(env, keys::AUCTION_HOLD, tld, label), held);
+    }
+}
+
+mod registry_api {
+    use super::*;
+
+    pub fn set_owner(
+        env: &Env,
+        registry: &Address,
+        parent: &BytesN<32>,
+        label: &Bytes,
+        owner: &Address,
+    ) -> BytesN<32> {
+        env.invoke_contract::<BytesN<32>>(
+            registry,
+            &Symbol::new(env, "set_owner"),
+            (env.current_contract_address(), parent, label.clone(), owner).into_val(env),
+        )
+    }
+
+    pub fn transfer(env: &Env, registry: &Address, namehash: &BytesN<32>, to: &Address) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "transfer"),
+            (env.current_contract_address(), namehash, to).into_val(env),
+        );
+    }
+
+    pub fn set_resolver(env: &Env, registry: &Address, namehash: &BytesN<32>, resolver: &Address) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "set_resolver"),
+            (env.current_contract_address(), namehash, resolver).into_val(env),
+        );
+    }
+
+    pub fn bump_record_version(env: &Env, registry: &Address, namehash: &BytesN<32>) -> u32 {
+        env.invoke_contract::<u32>(
+            registry,
+            &Symbol::new(env, "bump_record_version"),
+            (env.current_contract_address(), namehash).into_val(env),
+        )
+    }
+
+    pub fn set_expiry(env: &Env, registry: &Address, namehash: &BytesN<32>, expires_at: u64) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "set_expiry"),
+            (env.current_contract_address(), namehash, expires_at).into_val(env),
+        );
+    }
+
+    pub fn extend_expiry(env: &Env, registry: &Address, namehash: &BytesN<32>, expires_at: u64) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "extend_expiry"),
+            (env.current_contract_address(), namehash, expires_at).into_val(env),
+        );
+    }
+
+    pub fn set_grace_period(env: &Env, registry: &Address, node: &BytesN<32>, grace_secs: u64) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "set_grace_period"),
+            (env.current_contract_address(), node, grace_secs).into_val(env),
+        );
+    }
+
+    pub fn release(env: &Env, registry: &Address, namehash: &BytesN<32>) {
+        env.invoke_contract::<()>(
+            registry,
+            &Symbol::new(env, "release"),
+            (env.current_contract_address(), namehash).into_val(env),
+        );
+    }
+
+    pub fn owner(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+        let args = (namehash.clone(),).into_val(env);
+        match env.try_invoke_contract::<Address, Error>(registry, &Symbol::new(env, "owner"), args)
+        {
+            Ok(Ok(address)) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn resolver(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+        let args = (namehash.clone(),).into_val(env);
+        match env.try_invoke_contract::<Address, Error>(
+            registry,
+            &Symbol::new(env, "resolver"),
+            args,
+        ) {
+            Ok(Ok(address)) => Some(address),
+            _ => None,
+        }
+    }
+
+    pub fn expires(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<u64> {
+        let args = (namehash.clone(),).into_val(env);
+        match env.try_invoke_contract::<u64, Error>(registry, &Symbol::new(env, "expires"), args) {
+            Ok(Ok(ts)) => Some(ts),
+            _ => None,
+        }
+    }
+}
+
+mod name_token_api {
+    use super::*;
+
+    pub fn mint(env: &Env, name_token: &Address, namehash: &BytesN<32>, owner: &Address) {
+        env.invoke_contract::<()>(
+            name_token,
+            &Symbol::new(env, "mint"),
+            (namehash, owner).into_val(env),
+        );
+    }
+}
+
+/// Registrar contract for the `.stellar` namespace.
+#[contract]
+pub struct Registrar;
+
+#[contracttype]
+#[derive(Clone)]
+pub struct RegistrarParams {
+    pub min_label_len: u32,
+    pub max_label_len: u32,
+    pub commit_min_age_secs: u64,
+    pub commit_max_age_secs: u64,
+    pub renew_extension_secs: u64,
+    pub grace_period_secs: u64,
+    pub max_registration_secs: u64,
+    /// Persistent entries are extended once their TTL falls below this many ledgers...
+    pub ttl_threshold_ledgers: u32,
+    /// ...back up to this many ledgers.
+    pub ttl_extend_to_ledgers: u32,
+    /// Share of each registration fee credited to the `referrer`, in basis points.
+    pub referral_fee_bps: u32,
+    /// `auto_renew` is accepted once a name is within this many seconds of expiry.
+    pub auto_renew_window_secs: u64,
+    /// Keeper reward for `auto_renew`, in basis points of the renewal price, paid from escrow.
+    pub keeper_tip_bps: u32,
+    /// Accept internationalized labels in A-label (`xn--`) form; see `normalize`.
+    pub allow_idn: bool,
+    /// Share of the current rent for the unexpired term refunded by `release`, in basis
+    /// points; `0` disables refunds.
+    pub release_refund_bps: u32,
+    /// Most registrations one caller may make under this TLD in any `rate_limit_window_secs`
+    /// span; `0` disables the limit.
+    pub rate_limit_max: u32,
+    pub rate_limit_window_secs: u64,
+    /// How long the recipient of `register_gift` has to `claim_gift` before the payer can
+    /// take the name back.
+    pub gift_claim_window_secs: u64,
+    /// Payment-token bounty `reap` pays its keeper, from the free balance; `0` pays nothing.
+    pub reap_bounty: i128,
+    /// Payment-token fee `redeem` adds to the renewal price during the grace period. While
+    /// non-zero, a name in grace can only be recovered with `redeem`; `0` keeps renewals open
+    /// until the grace period ends.
+    pub redemption_fee: i128,
+    /// How `rent_price` derives the annual price; switch with `set_params` like any field.
+    pub pricing: PricingStrategy,
+    /// Testnet faucet mode: `register` charges nothing and ignores `commit_min_age_secs`.
+    /// Only builds with the `testnet` feature accept `true`; others abort with `InvalidParams`.
+    pub faucet: bool,
+}
+
+/// Where a commitment stands against the commit window, as reported by `commitment_status`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CommitmentStatus {
+    /// Never made, cancelled, consumed by a registration, or swept.
+    NotFound,
+    /// Younger than `commit_min_age_secs`; `register` accepts it from the given timestamp.
+    TooFresh(u64),
+    /// Usable by `register` until and including the given timestamp.
+    Ready(u64),
+    /// Older than `commit_max_age_secs`; only `sweep_commitments` can use it now.
+    Expired,
+}
+
+/// Source of the annual price before the length multiplier is applied.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PricingStrategy {
+    /// The TLD's `PriceSchedule`, indexed by label length.
+    LengthTiered,
+    /// One annual price for every label length.
+    Fixed(i128),
+    /// An annual price that falls linearly over time; see `DutchAuction`.
+    DutchAuction(DutchAuction),
+}
+
+/// Launch curve: the annual price is `start_price` at `starts_at` and falls linearly to
+/// `floor_price` over `duration_secs`, then holds at the floor.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DutchAuction {
+    pub start_price: i128,
+    pub floor_price: i128,
+    pub starts_at: u64,
+    pub duration_secs: u64,
+}
+
+/// Annual prices indexed by label length: entry `i` prices labels of `i + 1` bytes and the
+/// final entry applies to every longer label.
+#[contracttype]
+#[derive(Clone)]
+pub struct PriceSchedule {
+    pub annual_prices: Vec<i128>,
+    /// Extra charge for a name the moment its grace period ends; `0` disables the premium.
+    pub premium_start: i128,
+    /// Seconds over which the premium decays linearly to zero.
+    pub premium_decay_secs: u64,
+}
+
+/// One entry of a `register_many` batch; fields mirror the arguments of `register`.
+#[contracttype]
+#[derive(Clone)]
+pub struct RegistrationRequest {
+    pub tld: Bytes,
+    pub label: Bytes,
+    pub owner: Address,
+    pub secret: Bytes,
+    pub resolver: Option<Address>,
+    pub duration_secs: u64,
+    pub referrer: Option<Address>,
+    pub voucher: Option<Bytes>,
+    /// As in `register`.
+    pub sponsor: Option<Address>,
+}
+
+/// Parameters and pricing of a TLD added with `add_tld`. The default TLD uses `params` and
+/// `price_schedule` instead.
+#[contracttype]
+#[derive(Clone)]
+pub struct TldConfig {
+    pub params: RegistrarParams,
+    pub schedule: PriceSchedule,
+}
+
+/// Launch gating for one label length. Lengths without a policy are enabled at the
+/// schedule's price.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LengthPolicy {
+    /// Whether new registrations of this length are open. Existing names can still renew.
+    pub enabled: bool,
+    /// Whole-number multiple applied to the annual price for this length.
+    pub price_multiplier: u32,
+}
+
+/// Admin set whose `threshold` members must approve every `AdminAction`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminCouncil {
+    pub members: Vec<Address>,
+    pub threshold: u32,
+}
+
+/// Counters returned by `stats`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistrarStats {
+    /// Every registration, including re-registrations of lapsed names.
+    pub total_registrations: u64,
+    /// Names issued here that still hold Registry state: registrations minus `release` and
+    /// `reap`. A lapsed name counts until it is reaped or registered again.
+    pub active_names: u64,
+    /// Entry `i` counts registrations of `i + 1`-byte labels.
+    pub registrations_by_length: Vec<u64>,
+}
+
+/// Fees collected during one `revenue_by_period` bucket, in the payment token.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Revenue {
+    pub registrations: i128,
+    pub renewals: i128,
+}
+
+/// One name returned by `export_state`, as the Registry currently records it. `owner` is `None`
+/// for a name the Registry no longer knows, such as one that was burned.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NameRecord {
+    pub namehash: BytesN<32>,
+    pub owner: Option<Address>,
+    pub expires_at: Option<u64>,
+}
+
+/// A discount minted by `mint_vouchers`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Voucher {
+    /// Share of the registration fee (rent plus premium) waived, in basis points.
+    pub discount_bps: u32,
+    pub uses_left: u32,
+}
+
+/// A name bought by `payer` for `recipient`, held by the Registrar until `claim_by`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Gift {
+    pub payer: Address,
+    pub recipient: Address,
+    pub claim_by: u64,
+}
+
+/// What a name's holders paid out of pocket for its terms so far, the basis for
+/// `release` refunds. Voucher discounts and sponsor allowances are not counted.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct PaidTerm {
+    pub amount: i128,
+    pub secs: u64,
+}
+
+/// Changes that wait out the timelock once one is configured.
+#[contracttype]
+#[derive(Clone)]
+pub enum TimelockedChange {
+    Params(RegistrarParams),
+    PriceSchedule(PriceSchedule),
+    Upgrade(BytesN<32>),
+    /// Shortening the delay waits out the current one; lengthening it is immediate.
+    Delay(u64),
+    TldConfig(Bytes, RegistrarParams, PriceSchedule),
+    LengthPolicy(Bytes, u32, LengthPolicy),
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct QueuedChange {
+    pub change: TimelockedChange,
+    /// Earliest ledger timestamp at which `execute_params` applies the change.
+    pub eta: u64,
+}
+
+/// Admin operations that need council approval once a council is configured.
+#[contracttype]
+#[derive(Clone)]
+pub enum AdminAction {
+    SetParams(RegistrarParams),
+    SetPriceSchedule(PriceSchedule),
+    /// `(amount, to)`, as in `withdraw`.
+    Withdraw(i128, Address),
+    Upgrade(BytesN<32>),
+    /// `(tld, labels)`, as in `reserve_labels`.
+    ReserveLabels(Bytes, Vec<Bytes>),
+    /// `(tld, label)`, as in `release_label`.
+    ReleaseLabel(Bytes, Bytes),
+    SetCouncil(AdminCouncil),
+    /// `queue_params` and `cancel_params` on the council's behalf.
+    QueueChange(TimelockedChange),
+    CancelChange(u32),
+    /// `set_timelock`; only a longer delay takes effect at once.
+    SetTimelock(u64),
+    /// `(label, start_price, duration_secs)`, as in `create_premium_auction`.
+    CreatePremiumAuction(Bytes, i128, u64),
+    /// `(tld, len, policy)`, as in `set_length_policy`.
+    SetLengthPolicy(Bytes, u32, LengthPolicy),
+    /// `(tld, params, schedule)`, as in `add_tld`.
+    AddTld(Bytes, RegistrarParams, PriceSchedule),
+    /// `(tld, params, schedule)`, as in `set_tld_config`.
+    SetTldConfig(Bytes, RegistrarParams, PriceSchedule),
+    /// `(hashes, discount_bps, uses)`, as in `mint_vouchers`.
+    MintVouchers(Vec<BytesN<32>>, u32, u32),
+    /// `hash`, as in `revoke_voucher`.
+    RevokeVoucher(BytesN<32>),
+    SetNameToken(Address),
+    SetAuctionHouse(Option<Address>),
+    /// `(tld, label, owner, resolver, duration_secs)`, as in `allocate_reserved`.
+    AllocateReserved(Bytes, Bytes, Address, Option<Address>, u64),
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub struct Proposal {
+    pub action: AdminAction,
+    pub proposer: Address,
+    /// Members that approved, the proposer first.
+    pub approvals: Vec<Address>,
+    pub expires_at: u64,
+}
+
+/// An English auction of a reserved label, opened by `create_premium_auction`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PremiumAuction {
+    pub start_price: i128,
+    /// Bids are accepted until this timestamp; a late bid pushes it back.
+    pub ends_at: u64,
+    pub highest_bidder: Option<Address>,
+    /// Escrowed by the Registrar until the bid is beaten or the auction settles.
+    pub highest_bid: i128,
+}
+
+/// Registry and pricing state of one name, gathered by `name_info`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct NameInfo {
+    pub namehash: BytesN<32>,
+    /// Registry owner, kept until someone registers the name again.
+    pub owner: Option<Address>,
+    pub resolver: Option<Address>,
+    pub expires_at: Option<u64>,
+    /// Same answer as `available(tld, label)`.
+    pub available: bool,
+    /// Past `expires_at` but within the grace period, so only the owner can renew.
+    pub in_grace: bool,
+    /// What `redeem` adds to `price` right now: `redemption_fee` while `in_grace`, else `0`.
+    pub redemption_fee: i128,
+    /// Rent for one `renew_extension_secs` term.
+    pub price: i128,
+    /// Premium added to `price` at registration; see `current_premium`.
+    pub premium: i128,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RegistrarError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    NotAdmin = 3,
+    NotOwner = 4,
+    InvalidLabel = 5,
+    CommitmentExists = 6,
+    CommitmentMissing = 7,
+    CommitmentTooFresh = 8,
+    CommitmentTooOld = 9,
+    NameNotAvailable = 10,
+    ExpiryUnavailable = 11,
+    InvalidParams = 12,
+    PriceOverflow = 13,
+    InvalidAmount = 14,
+    InvalidDuration = 15,
+    NotCommitter = 16,
+    NotPendingAdmin = 17,
+    Paused = 18,
+    UnsupportedVersion = 19,
+    IncompatibleResolver = 20,
+    RenewalNotDue = 21,
+    AutoRenewDisabled = 22,
+    InsufficientEscrow = 23,
+    InsufficientFunds = 24,
+    InvalidBatchSize = 25,
+    NameReserved = 26,
+    NotReserved = 27,
+    LengthDisabled = 28,
+    NotAuctionHouse = 29,
+    NameInAuction = 30,
+    NotInAuction = 31,
+    UnknownTld = 32,
+    TldExists = 33,
+    /// The commitment was made before the name's previous registration left its grace period.
+    CommitmentPredatesRelease = 34,
+    /// The caller has used up its registrations for the current rate-limit window.
+    RateLimited = 35,
+    NotCouncilMember = 36,
+    /// A council is configured, so the action needs `propose_action` and `execute_action`.
+    CouncilRequired = 37,
+    ProposalMissing = 38,
+    ProposalExpired = 39,
+    AlreadyApproved = 40,
+    NotEnoughApprovals = 41,
+    /// Empty, oversized, or duplicated member list, or a threshold outside `1..=members`.
+    InvalidCouncil = 42,
+    /// A timelock is configured, so the change needs `queue_params` and `execute_params`.
+    TimelockRequired = 43,
+    ChangeMissing = 44,
+    /// `execute_params` before the queued change's `eta`.
+    ChangeNotReady = 45,
+    /// No pending gift for the name.
+    GiftMissing = 46,
+    /// `claim_gift` after the gift's `claim_by`.
+    GiftExpired = 47,
+    /// `reclaim_gift` while the recipient can still claim.
+    GiftPending = 48,
+    /// The voucher code does not match a voucher with redemptions left.
+    InvalidVoucher = 49,
+    VoucherExists = 50,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["initialized"])]
+pub struct EvtInitialized {
+    pub registry: Address,
+    pub tld: Bytes,
+    pub admin: Address,
+    pub token: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["admin_changed"])]
+pub struct EvtAdminChanged {
+    #[topic]
+    pub previous: Address,
+    #[topic]
+    pub admin: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["upgraded"])]
+pub struct EvtUpgraded {
+    pub new_wasm_hash: BytesN<32>,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["migrated"])]
+pub struct EvtMigrated {
+    pub from: u32,
+    pub to: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["paused"])]
+pub struct EvtPaused {
+    pub admin: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["unpaused"])]
+pub struct EvtUnpaused {
+    pub admin: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["voucher_minted"])]
+pub struct EvtVoucherMinted {
+    #[topic]
+    pub hash: BytesN<32>,
+    pub discount_bps: u32,
+    pub uses: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["voucher_revoked"])]
+pub struct EvtVoucherRevoked {
+    #[topic]
+    pub hash: BytesN<32>,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["voucher_redeemed"])]
+pub struct EvtVoucherRedeemed {
+    #[topic]
+    pub hash: BytesN<32>,
+    pub namehash: BytesN<32>,
+    pub discount: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["gift_registered"])]
+pub struct EvtGiftRegistered {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub payer: Address,
+    pub recipient: Address,
+    pub claim_by: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["gift_claimed"])]
+pub struct EvtGiftClaimed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub recipient: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["gift_reclaimed"])]
+pub struct EvtGiftReclaimed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub payer: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["timelock_changed"])]
+pub struct EvtTimelockChanged {
+    pub delay_secs: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["params_queued"])]
+pub struct EvtParamsQueued {
+    #[topic]
+    pub id: u32,
+    pub change: TimelockedChange,
+    pub eta: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["params_executed"])]
+pub struct EvtParamsExecuted {
+    #[topic]
+    pub id: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["params_cancelled"])]
+pub struct EvtParamsCancelled {
+    #[topic]
+    pub id: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["council_changed"])]
+pub struct EvtCouncilChanged {
+    pub members: Vec<Address>,
+    pub threshold: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["action_proposed"])]
+pub struct EvtActionProposed {
+    #[topic]
+    pub id: u32,
+    pub proposer: Address,
+    pub action: AdminAction,
+    pub expires_at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["action_approved"])]
+pub struct EvtActionApproved {
+    #[topic]
+    pub id: u32,
+    pub member: Address,
+    pub approvals: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["action_executed"])]
+pub struct EvtActionExecuted {
+    #[topic]
+    pub id: u32,
+    pub executor: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["params_changed"])]
+pub struct EvtParamsChanged {
+    pub params: RegistrarParams,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["tld_configured"])]
+pub struct EvtTldConfigured {
+    #[topic]
+    pub tld: Bytes,
+    pub params: RegistrarParams,
+    pub schedule: PriceSchedule,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["commit_made"])]
+pub struct EvtCommitMade {
+    #[topic]
+    pub commitment: BytesN<32>,
+    pub at: u64,
+    pub label_len: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["label_reserved"])]
+pub struct EvtLabelReserved {
+    #[topic]
+    pub label: Bytes,
+    pub tld: Bytes,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["label_released"])]
+pub struct EvtLabelReleased {
+    #[topic]
+    pub label: Bytes,
+    pub tld: Bytes,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["premium_auction_created"])]
+pub struct EvtPremiumAuctionCreated {
+    #[topic]
+    pub label: Bytes,
+    pub start_price: i128,
+    pub ends_at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["premium_bid_placed"])]
+pub struct EvtPremiumBidPlaced {
+    #[topic]
+    pub label: Bytes,
+    #[topic]
+    pub bidder: Address,
+    pub amount: i128,
+    pub ends_at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["premium_auction_settled"])]
+pub struct EvtPremiumAuctionSettled {
+    #[topic]
+    pub label: Bytes,
+    pub winner: Option<Address>,
+    pub price: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["length_policy_changed"])]
+pub struct EvtLengthPolicyChanged {
+    #[topic]
+    pub len: u32,
+    pub tld: Bytes,
+    pub policy: LengthPolicy,
+    pub seq: u64,
+}
+
+/// Published once by `commit_many`; `label_lens[i]` belongs to `commitments[i]`.
+#[derive(Clone)]
+#[contractevent(topics = ["commit_made"])]
+pub struct EvtCommitsMade {
+    pub commitments: Vec<BytesN<32>>,
+    pub label_lens: Vec<u32>,
+    pub at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["commitment_consumed"])]
+pub struct EvtCommitmentConsumed {
+    #[topic]
+    pub commitment: BytesN<32>,
+    pub namehash: BytesN<32>,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["name_registered"])]
+pub struct EvtNameRegistered {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub expires_at: u64,
+    pub ts: u64,
+    pub label: Bytes,
+    pub tld: Bytes,
+    pub price: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["name_renewed"])]
+pub struct EvtNameRenewed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub expires_at: u64,
+    pub price: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["name_released"])]
+pub struct EvtNameReleased {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub label: Bytes,
+    pub tld: Bytes,
+    pub refund: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["name_redeemed"])]
+pub struct EvtNameRedeemed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub fee: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["name_reaped"])]
+pub struct EvtNameReaped {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub label: Bytes,
+    pub keeper: Address,
+    pub bounty: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["referral_accrued"])]
+pub struct EvtReferralAccrued {
+    #[topic]
+    pub referrer: Address,
+    pub namehash: BytesN<32>,
+    pub amount: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["referral_claimed"])]
+pub struct EvtReferralClaimed {
+    #[topic]
+    pub referrer: Address,
+    pub amount: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["renewal_funds_deposited"])]
+pub struct EvtRenewalFundsDeposited {
+    #[topic]
+    pub owner: Address,
+    pub amount: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["sponsor_allowance_changed"])]
+pub struct EvtSponsorAllowanceChanged {
+    #[topic]
+    pub sponsor: Address,
+    #[topic]
+    pub owner: Address,
+    /// Allowance left after the change.
+    pub allowance: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["renewal_funds_withdrawn"])]
+pub struct EvtRenewalFundsWithdrawn {
+    #[topic]
+    pub owner: Address,
+    pub amount: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["auto_renew_changed"])]
+pub struct EvtAutoRenewChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub enabled: bool,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["auto_renewed"])]
+pub struct EvtAutoRenewed {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub sponsor: Address,
+    pub keeper: Address,
+    pub tip: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["fees_withdrawn"])]
+pub struct EvtFeesWithdrawn {
+    #[topic]
+    pub to: Address,
+    pub amount: i128,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["commit_cancelled"])]
+pub struct EvtCommitCancelled {
+    #[topic]
+    pub commitment: BytesN<32>,
+    pub committer: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["commitments_swept"])]
+pub struct EvtCommitmentsSwept {
+    pub count: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["commitment_missing"])]
+pub struct EvtCommitmentMissing {
+    #[topic]
+    pub commitment: BytesN<32>,
+    pub label: Bytes,
+    pub caller: Address,
+    pub owner: Address,
+    pub secret_len: u32,
+    pub seq: u64,
+}
+
+#[contracttype]
+#[derive(Clone)]
+struct CommitmentInfo {
+    pub timestamp: u64,
+    pub label_len: u32,
+    pub committer: Address,
+}
+
+#[contractimpl]
+impl Registrar {
+    /// Returns the contract version.
+    pub fn version(_env: Env) -> u32 {
+        1
+    }
+
+    /// `seq` of the latest event, or `0` before the first one. An indexer that has seen
+    /// every event up to this value is caught up.
+    pub fn event_seq(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&singleton_key(&env, keys::EVENT_SEQ))
+            .unwrap_or(0)
+    }
+
+    /// One-time initializer. `token` is the Stellar asset contract fees are paid in; collected
+    /// fees accrue to the Registrar itself until withdrawn by the admin.
+    pub fn init(env: Env, registry: Address, tld: Bytes, admin: Address, token: Address) {
+        let storage = env.storage().persistent();
+        let key = singleton_key(&env, keys::REGISTRY);
+        if storage.has(&key) {
+            panic_with_error!(&env, RegistrarError::AlreadyInitialized);
+        }
+        if tld.is_empty() {
+            panic_with_error!(&env, RegistrarError::InvalidLabel);
+        }
+        write_registry(&env, &registry);
+        write_tld(&env, &tld);
+        write_admin(&env, &admin);
+        write_token(&env, &token);
+        write_entry(
+            &env,
+            &singleton_key(&env, keys::STORAGE_VERSION),
+            &STORAGE_VERSION,
+        );
+        let params = default_params();
+        write_params(&env, &params);
+        write_price_schedule(&env, &pricing::default_schedule(&env));
+        EvtInitialized {
+            registry,
+            tld,
+            admin,
+            token,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Record commitment timestamp for commit–reveal. `label_len` allows early validation
+    /// without revealing the label itself on-chain.
+    pub fn commit(env: Env, caller: Address, commitment: BytesN<32>, label_len: u32) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        let ts = env.ledger().timestamp();
+        add_commitment(&env, &caller, &commitment, label_len, ts);
+        EvtCommitMade {
+            commitment,
+            at: ts,
+            label_len,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// The version 2 commitment for a reveal of `label.tld` by `committer`, which also binds
+    /// `resolver` and `duration_secs` to this network and Registrar. Clients that cannot
+    /// reproduce the hash off-chain can simulate this call; pass the result to `commit`.
+    pub fn make_commitment(
+        env: Env,
+        tld: Bytes,
+        label: Bytes,
+        owner: Address,
+        secret: Bytes,
+        committer: Address,
+        resolver: Option<Address>,
+        duration_secs: u64,
+    ) -> BytesN<32> {
+        compute_bound_commitment(
+            &env,
+            &tld,
+            &label,
+            &owner,
+            &secret,
+            &committer,
+            &resolver,
+            duration_secs,
+        )
+    }
+
+    /// Record several `(commitment, label_len)` pairs at once, each checked as in `commit`.
+    /// A duplicate, whether already stored or repeated within the batch, reverts the whole
+    /// call. Publishes a single `commit_made` event listing every commitment.
+    pub fn commit_many(env: Env, caller: Address, commitments: Vec<(BytesN<32>, u32)>) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        ensure_batch_size(&env, commitments.len());
+        let ts = env.ledger().timestamp();
+        let mut hashes = Vec::new(&env);
+        let mut label_lens = Vec::new(&env);
+        for (commitment, label_len) in commitments.iter() {
+            add_commitment(&env, &caller, &commitment, label_len, ts);
+            hashes.push_back(commitment);
+            label_lens.push_back(label_len);
+        }
+        EvtCommitsMade {
+            commitments: hashes,
+            label_lens,
+            at: ts,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Delete a pending commitment. Only the address that made it may cancel, at any point
+    /// before it is consumed by `register`. No fee is escrowed at commit time, so nothing is
+    /// refunded.
+    pub fn cancel_commit(env: Env, caller: Address, commitment: BytesN<32>) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        let info = commitment_info(&env, &commitment)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::CommitmentMissing));
+        if info.committer != caller {
+            panic_with_error!(&env, RegistrarError::NotCommitter);
+        }
+        remove_commitment(&env, &commitment);
+        EvtCommitCancelled {
+            commitment,
+            committer: caller,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Where `commitment` stands against the default TLD's commit window, so frontends can
+    /// count down to `TooFresh(ready_at)` and warn before `Ready(expires_at)` runs out. The
+    /// stored commitment is an opaque hash, so one made for another TLD (which a version 2
+    /// commitment binds) is only judged by that TLD's params at `register`.
+    pub fn commitment_status(env: Env, commitment: BytesN<32>) -> CommitmentStatus {
+        ensure_initialized(&env);
+        let Some(info) = commitment_info(&env, &commitment) else {
+            return CommitmentStatus::NotFound;
+        };
+        let params = read_params(&env);
+        let ready_at = info.timestamp.saturating_add(commit_min_age(&params));
+        let expires_at = info.timestamp.saturating_add(params.commit_max_age_secs);
+        let now = env.ledger().timestamp();
+        if now < ready_at {
+            CommitmentStatus::TooFresh(ready_at)
+        } else if now <= expires_at {
+            CommitmentStatus::Ready(expires_at)
+        } else {
+            CommitmentStatus::Expired
+        }
+    }
+
+    /// Delete every listed commitment that is older than `commit_max_age_secs` and so can
+    /// never be used by `register`. Permissionless; unknown or still-usable commitments are
+    /// skipped. Returns the number removed.
+    pub fn sweep_commitments(env: Env, commitments: Vec<BytesN<32>>) -> u32 {
+        ensure_initialized(&env);
+        let params = read_params(&env);
+        let now = env.ledger().timestamp();
+        let mut count = 0u32;
+        for commitment in commitments.iter() {
+            if let Some(info) = commitment_info(&env, &commitment) {
+                if now.saturating_sub(info.timestamp) > params.commit_max_age_secs {
+                    remove_commitment(&env, &commitment);
+                    count += 1;
+                }
+            }
+        }
+        if count > 0 {
+            EvtCommitmentsSwept {
+                count,
+                seq: next_event_seq(&env),
+            }
+            .publish(&env);
+        }
+        count
+    }
+
+    /// Finalize name registration after commitment matures, registering the name for
+    /// `duration_secs` (at most `max_registration_secs`). An optional `voucher` code from
+    /// `mint_vouchers` discounts the fee and uses up one of its redemptions. The fee is drawn
+    /// from the allowance `caller` funded for `owner` first or, when `owner` registers for
+    /// themselves, from the one `sponsor` funded for them.
+    pub fn register(
+        env: Env,
+        caller: Address,
+        tld: Bytes,
+        label: Bytes,
+        owner: Address,
+        secret: Bytes,
+        resolver: Option<Address>,
+        duration_secs: u64,
+        referrer: Option<Address>,
+        voucher: Option<Bytes>,
+        sponsor: Option<Address>,
+    ) -> BytesN<32> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        register_name(
+            &env,
+            &caller,
+            &tld,
+            label,
+            owner,
+            secret,
+            resolver,
+            duration_secs,
+            referrer,
+            voucher,
+            sponsor,
+            None,
+        )
+    }
+
+    /// `register` for a full name such as `Alice.stellar`, split at its first dot into the
+    /// label and a configured TLD. The label is normalized as by `normalize`, and the
+    /// commitment must be over that canonical form. Aborts with `InvalidLabel` if `name` is
+    /// not UTF-8 or has an empty part, and `UnknownTld` if the TLD is not served here.
+    pub fn register_str(
+        env: Env,
+        caller: Address,
+        name: String,
+        owner: Address,
+        secret: Bytes,
+        resolver: Option<Address>,
+        duration_secs: u64,
+        referrer: Option<Address>,
+        voucher: Option<Bytes>,
+        sponsor: Option<Address>,
+    ) -> BytesN<32> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        let (tld, label) = split_name(&env, &name);
+        register_name(
+            &env,
+            &caller,
+            &tld,
+            label,
+            owner,
+            secret,
+            resolver,
+            duration_secs,
+            referrer,
+            voucher,
+            sponsor,
+            None,
+        )
+    }
+
+    /// Register `label` for `recipient` with `caller` paying, as `register` does with
+    /// `recipient` as the committed owner. The Registrar holds the name until `recipient`
+    /// calls `claim_gift` within `gift_claim_window_secs`; after that `reclaim_gift` hands it
+    /// to `caller` instead. Emits `name_registered` with the Registrar as owner, then
+    /// `gift_registered`.
+    pub fn register_gift(
+        env: Env,
+        caller: Address,
+        tld: Bytes,
+        label: Bytes,
+        recipient: Address,
+        secret: Bytes,
+        resolver: Option<Address>,
+        duration_secs: u64,
+        referrer: Option<Address>,
+    ) -> BytesN<32> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        let namehash = register_name(
+            &env,
+            &caller,
+            &tld,
+            label,
+            recipient.clone(),
+            secret,
+            resolver,
+            duration_secs,
+            referrer,
+            None,
+            None,
+            Some(&env.current_contract_address()),
+        );
+        let claim_by = env
+            .ledger()
+            .timestamp()
+            .saturating_add(tld_params(&env, &tld).gift_claim_window_secs);
+        let gift = Gift {
+            payer: caller.clone(),
+            recipient: recipient.clone(),
+            claim_by,
+        };
+        gifts::store(&env, &namehash, &gift);
+        EvtGiftRegistered {
+            namehash: namehash.clone(),
+            payer: caller,
+            recipient,
+            claim_by,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        namehash
+    }
+
+    /// Take a gifted name. Only the gift's recipient, until its `claim_by`; the transaction
+    /// fee can be paid by someone else, so the recipient needs no balance of their own.
+    /// Emits `gift_claimed`.
+    pub fn claim_gift(env: Env, recipient: Address, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        recipient.require_auth();
+        let namehash = compute_namehash(&env, &tld, &label);
+        let gift = gifts::read(&env, &namehash);
+        if gift.recipient != recipient {
+            panic_with_error!(&env, RegistrarError::NotOwner);
+        }
+        if env.ledger().timestamp() > gift.claim_by {
+            panic_with_error!(&env, RegistrarError::GiftExpired);
+        }
+        gifts::deliver(&env, &namehash, &recipient);
+        EvtGiftClaimed {
+            namehash,
+            recipient,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Hand an unclaimed gift back to its payer once `claim_by` has passed. Anyone may call
+    /// this. Emits `gift_reclaimed`.
+    pub fn reclaim_gift(env: Env, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        let gift = gifts::read(&env, &namehash);
+        if env.ledger().timestamp() <= gift.claim_by {
+            panic_with_error!(&env, RegistrarError::GiftPending);
+        }
+        gifts::deliver(&env, &namehash, &gift.payer);
+        EvtGiftReclaimed {
+            namehash,
+            payer: gift.payer,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// The pending gift of `label`, if it has not been claimed or reclaimed.
+    pub fn gift(env: Env, tld: Bytes, label: Bytes) -> Option<Gift> {
+        ensure_initialized(&env);
+        gifts::get(&env, &compute_namehash(&env, &tld, &label))
+    }
+
+    /// Extend an existing registration's expiry.
+    pub fn renew(env: Env, caller: Address, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        renew_name(&env, &caller, &tld, label);
+    }
+
+    /// Register every request in one transaction. Each entry goes through the same checks
+    /// as `register`; any failure reverts the whole batch. The summed fee, less sponsor
+    /// allowances, is checked against `caller`'s balance before anything is registered.
+    /// Returns the namehashes in order.
+    pub fn register_many(
+        env: Env,
+        caller: Address,
+        requests: Vec<RegistrationRequest>,
+    ) -> Vec<BytesN<32>> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        ensure_batch_size(&env, requests.len());
+
+        let registry = read_registry(&env);
+        let mut total = 0i128;
+        // Sponsor allowances left per sponsor and owner as the batch draws them down.
+        let mut budgets: Map<(Address, Address), i128> = Map::new(&env);
+        for request in requests.iter() {
+            validate_label(&env, &request.tld, &request.label);
+            let namehash = compute_namehash(&env, &request.tld, &request.label);
+            let price = pricing::rent_price(
+                &env,
+                &request.tld,
+                request.label.len(),
+                request.duration_secs,
+            );
+            let mut fee = add_fee(
+                &env,
+                price,
+                current_premium(&env, &registry, &request.tld, &namehash),
+            );
+            if let Some(sponsor) =
+                allowance_sponsor(&env, &caller, &request.owner, request.sponsor.clone())
+            {
+                let pair = (sponsor, request.owner.clone());
+                let budget = budgets
+                    .get(pair.clone())
+                    .unwrap_or_else(|| sponsorship::allowance(&env, &pair.0, &pair.1));
+                let drawn = budget.min(fee);
+                budgets.set(pair, budget - drawn);
+                fee -= drawn;
+            }
+            total = add_fee(&env, total, fee);
+        }
+        payments::ensure_covers(&env, &caller, total);
+
+        let mut namehashes = Vec::new(&env);
+        for request in requests.iter() {
+            namehashes.push_back(register_name(
+                &env,
+                &caller,
+                &request.tld,
+                request.label,
+                request.owner,
+                request.secret,
+                request.resolver,
+                request.duration_secs,
+                request.referrer,
+                request.voucher,
+                request.sponsor,
+                None,
+            ));
+        }
+        namehashes
+    }
+
+    /// Renew every label under `tld` in one transaction with the same rules as `renew`; any
+    /// failure reverts the whole batch. The summed fee is checked against `caller`'s balance
+    /// first.
+    pub fn renew_many(env: Env, caller: Address, tld: Bytes, labels: Vec<Bytes>) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        ensure_batch_size(&env, labels.len());
+
+        let extension = tld_params(&env, &tld).renew_extension_secs;
+        let mut total = 0i128;
+        for label in labels.iter() {
+            validate_label(&env, &tld, &label);
+            total = add_fee(
+                &env,
+                total,
+                pricing::rent_price(&env, &tld, label.len(), extension),
+            );
+        }
+        payments::ensure_covers(&env, &caller, total);
+
+        for label in labels.iter() {
+            renew_name(&env, &caller, &tld, label);
+        }
+    }
+
+    /// Gives up `label` under `tld` so it is available again at once. The Registry clears its
+    /// owner, resolver, and expiry, and auto-renewal stops. With `release_refund_bps` set, the
+    /// owner gets that share of the rent actually paid for the unexpired term back, as far as
+    /// the Registrar's free balance allows. Returns the refund; emits `name_released`.
+    pub fn release(env: Env, caller: Address, tld: Bytes, label: Bytes) -> i128 {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        validate_label(&env, &tld, &label);
+
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        let owner = registry_api::owner(&env, &registry, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::NotOwner));
+        if owner != caller {
+            panic_with_error!(&env, RegistrarError::NotOwner);
+        }
+        let now = env.ledger().timestamp();
+        let remaining = registry_api::expires(&env, &registry, &namehash)
+            .map_or(0, |expires_at| expires_at.saturating_sub(now));
+        let refund = release_refund(&env, &tld, &namehash, remaining);
+
+        registry_api::release(&env, &registry, &namehash);
+        escrow::set_sponsor(&env, &namehash, None);
+        paid::clear(&env, &namehash);
+        record_release(&env, &namehash, now);
+        stats::removed(&env);
+        if refund > 0 {
+            payments::pay_out(&env, &caller, refund);
+        }
+        EvtNameReleased {
+            namehash,
+            owner,
+            label,
+            tld,
+            refund,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        refund
+    }
+
+    /// Permissionless cleanup: once `label` under `tld` is past its grace period, clears its
+    /// Registry owner, resolver, and expiry so its storage stops being extended, and pays
+    /// `keeper` the TLD's `reap_bounty` as far as the free balance allows. Returns the bounty;
+    /// emits `name_reaped`. Aborts with `ExpiryUnavailable` for a name without an owner and
+    /// expiry, and `NameNotAvailable` while it is still registered or in grace.
+    pub fn reap(env: Env, keeper: Address, tld: Bytes, label: Bytes) -> i128 {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        validate_label(&env, &tld, &label);
+
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        let (Some(owner), Some(expires_at)) = (
+            registry_api::owner(&env, &registry, &namehash),
+            registry_api::expires(&env, &registry, &namehash),
+        ) else {
+            panic_with_error!(&env, RegistrarError::ExpiryUnavailable);
+        };
+        let params = tld_params(&env, &tld);
+        if !grace_expired(
+            env.ledger().timestamp(),
+            expires_at,
+            params.grace_period_secs,
+        ) {
+            panic_with_error!(&env, RegistrarError::NameNotAvailable);
+        }
+
+        registry_api::release(&env, &registry, &namehash);
+        escrow::set_sponsor(&env, &namehash, None);
+        paid::clear(&env, &namehash);
+        record_release(
+            &env,
+            &namehash,
+            expires_at.saturating_add(params.grace_period_secs),
+        );
+        stats::removed(&env);
+        let bounty = params.reap_bounty.min(available_balance(&env).max(0));
+        if bounty > 0 {
+            payments::pay_out(&env, &keeper, bounty);
+        }
+        EvtNameReaped {
+            namehash,
+            owner,
+            label,
+            keeper,
+            bounty,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        bounty
+    }
+
+    /// Recovers `label` under `tld` during its grace period for its owner, who pays the rent
+    /// for one of the TLD's `renew_extension_secs` terms plus its `redemption_fee`. The term
+    /// continues from the old expiry. Aborts with `RenewalNotDue` before expiry and
+    /// `NameNotAvailable` after the grace period. Returns the new expiry; emits `name_renewed` (with the full
+    /// price) and `name_redeemed`.
+    pub fn redeem(env: Env, caller: Address, tld: Bytes, label: Bytes) -> u64 {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        validate_label(&env, &tld, &label);
+
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        if registry_api::owner(&env, &registry, &namehash) != Some(caller.clone()) {
+            panic_with_error!(&env, RegistrarError::NotOwner);
+        }
+        let current = registry_api::expires(&env, &registry, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::ExpiryUnavailable));
+        let params = tld_params(&env, &tld);
+        let now = env.ledger().timestamp();
+        if now <= current {
+            panic_with_error!(&env, RegistrarError::RenewalNotDue);
+        }
+        if grace_expired(now, current, params.grace_period_secs) {
+            panic_with_error!(&env, RegistrarError::NameNotAvailable);
+        }
+
+        let fee = params.redemption_fee;
+        let rent = pricing::rent_price(&env, &tld, label.len(), params.renew_extension_secs);
+        let price = add_fee(&env, rent, fee);
+        payments::collect(&env, &caller, price);
+        revenue::record(&env, price, true);
+        paid::add(&env, &namehash, rent, params.renew_extension_secs);
+
+        let expires_at = current
+            .checked_add(params.renew_extension_secs)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::InvalidDuration));
+        registry_api::extend_expiry(&env, &registry, &namehash, expires_at);
+
+        EvtNameRenewed {
+            namehash: namehash.clone(),
+            expires_at,
+            price,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        EvtNameRedeemed {
+            namehash,
+            owner: caller,
+            fee,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        expires_at
+    }
+
+    /// Extend `label`'s expiry under `tld` by the TLD's renewal extension on behalf of its
+    /// owner. Anyone may pay; ownership does not change. The name must still be registered or
+    /// in its grace period.
+    pub fn renew_for(env: Env, caller: Address, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        caller.require_auth();
+        validate_label(&env, &tld, &label);
+
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        if registry_api::owner(&env, &registry, &namehash).is_none() {
+            panic_with_error!(&env, RegistrarError::NameNotAvailable);
+        }
+        let current = registry_api::expires(&env, &registry, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::ExpiryUnavailable));
+        let params = tld_params(&env, &tld);
+        if grace_expired(env.ledger().timestamp(), current, params.grace_period_secs) {
+            panic_with_error!(&env, RegistrarError::NameNotAvailable);
+        }
+        ensure_not_in_redemption(&env, &params, Some(current));
+
+        let price = pricing::rent_price(&env, &tld, label.len(), params.renew_extension_secs);
+        payments::collect(&env, &caller, price);
+        revenue::record(&env, price, true);
+        paid::add(&env, &namehash, price, params.renew_extension_secs);
+
+        let expires_at = current
+            .checked_add(params.renew_extension_secs)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::InvalidDuration));
+        registry_api::extend_expiry(&env, &registry, &namehash, expires_at);
+
+        EvtNameRenewed {
+            namehash,
+            expires_at,
+            price,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Add `amount` of the fee token to `owner`'s renewal escrow.
+    pub fn deposit_renewal_funds(env: Env, owner: Address, amount: i128) {
+        ensure_initialized(&env);
+        owner.require_auth();
+        if amount <= 0 {
+            panic_with_error!(&env, RegistrarError::InvalidAmount);
+        }
+        payments::collect(&env, &owner, amount);
+        escrow::credit(&env, &owner, amount);
+        EvtRenewalFundsDeposited {
+            owner,
+            amount,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Return unspent escrow to `owner`.
+    pub fn withdraw_renewal_funds(env: Env, owner: Address, amount: i128) {
+        ensure_initialized(&env);
+        owner.require_auth();
+        if amount <= 0 {
+            panic_with_error!(&env, RegistrarError::InvalidAmount);
+        }
+        escrow::debit(&env, &owner, amount);
+        payments::pay_out(&env, &owner, amount);
+        EvtRenewalFundsWithdrawn {
+            owner,
+            amount,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Sets the budget `sponsor` reserves for registering names to `owner`, collecting the
+    /// increase from `sponsor` or refunding the decrease; `0` revokes it. When `sponsor`
+    /// registers a name for `owner`, or `owner` registers one naming `sponsor`, `register` pays
+    /// from the budget first and collects only the rest, so an exchange can fund users' names
+    /// ahead of time and revoke what is left.
+    /// Emits `sponsor_allowance_changed`.
+    pub fn sponsor_allowance(env: Env, sponsor: Address, owner: Address, amount: i128) {
+        ensure_initialized(&env);
+        sponsor.require_auth();
+        if amount < 0 || sponsor == owner {
+            panic_with_error!(&env, RegistrarError::InvalidAmount);
+        }
+        let current = sponsorship::allowance(&env, &sponsor, &owner);
+        if amount > current {
+            payments::collect(&env, &sponsor, amount - current);
+        } else if amount < current {
+            payments::pay_out(&env, &sponsor, current - amount);
+        }
+        sponsorship::set(&env, &sponsor, &owner, amount);
+    }
+
+    /// Unspent budget `sponsor` reserved for `owner`'s registrations.
+    pub fn allowance(env: Env, sponsor: Address, owner: Address) -> i128 {
+        ensure_initialized(&env);
+        sponsorship::allowance(&env, &sponsor, &owner)
+    }
+
+    /// Unspent renewal escrow held for `owner`.
+    pub fn renewal_funds(env: Env, owner: Address) -> i128 {
+        ensure_initialized(&env);
+        escrow::balance(&env, &owner)
+    }
+
+    /// Opt `label` under `tld` in or out of keeper renewals paid from `owner`'s escrow. Only the
+    /// current Registry owner may opt in; the setting lapses if the name changes hands.
+    pub fn set_auto_renew(env: Env, owner: Address, tld: Bytes, label: Bytes, enabled: bool) {
+        ensure_initialized(&env);
+        owner.require_auth();
+        validate_label(&env, &tld, &label);
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        if registry_api::owner(&env, &registry, &namehash) != Some(owner.clone()) {
+            panic_with_error!(&env, RegistrarError::NotOwner);
+        }
+        escrow::set_sponsor(&env, &namehash, enabled.then_some(&owner));
+        EvtAutoRenewChanged {
+            namehash,
+            owner,
+            enabled,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Escrow owner paying for the auto-renewals of `label` under `tld`, if enabled.
+    pub fn auto_renew_sponsor(env: Env, tld: Bytes, label: Bytes) -> Option<Address> {
+        ensure_initialized(&env);
+        escrow::sponsor(&env, &compute_namehash(&env, &tld, &label))
+    }
+
+    /// Permissionless keeper entrypoint: renews an opted-in `label` under `tld` by its
+    /// `renew_extension_secs` once it is within `auto_renew_window_secs` of expiry. The
+    /// sponsor's escrow pays the renewal price plus a `keeper_tip_bps` tip sent to `keeper`.
+    /// Returns the new expiry.
+    pub fn auto_renew(env: Env, keeper: Address, tld: Bytes, label: Bytes) -> u64 {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        validate_label(&env, &tld, &label);
+
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        let sponsor = escrow::sponsor(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::AutoRenewDisabled));
+        if registry_api::owner(&env, &registry, &namehash) != Some(sponsor.clone()) {
+            panic_with_error!(&env, RegistrarError::AutoRenewDisabled);
+        }
+        let current = registry_api::expires(&env, &registry, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::ExpiryUnavailable));
+        let params = tld_params(&env, &tld);
+        let now = env.ledger().timestamp();
+        if grace_expired(now, current, params.grace_period_secs) {
+            panic_with_error!(&env, RegistrarError::NameNotAvailable);
+        }
+        if now.saturating_add(params.auto_renew_window_secs) < current {
+            panic_with_error!(&env, RegistrarError::RenewalNotDue);
+        }
+        ensure_not_in_redemption(&env, &params, Some(current));
+
+        let price = pricing::rent_price(&env, &tld, label.len(), params.renew_extension_secs);
+        let tip = price
+            .checked_mul(params.keeper_tip_bps as i128)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::PriceOverflow))
+            / MAX_BPS as i128;
+        escrow::debit(&env, &sponsor, price + tip);
+        revenue::record(&env, price, true);
+        paid::add(&env, &namehash, price, params.renew_extension_secs);
+        if tip > 0 {
+            payments::pay_out(&env, &keeper, tip);
+        }
+
+        let expires_at = current
+            .checked_add(params.renew_extension_secs)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::InvalidDuration));
+        registry_api::extend_expiry(&env, &registry, &namehash, expires_at);
+
+        EvtNameRenewed {
+            namehash: namehash.clone(),
+            expires_at,
+            price,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        EvtAutoRenewed {
+            namehash,
+            sponsor,
+            keeper,
+            tip,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        expires_at
+    }
+
+    /// Canonical form of `label` (lowercased, and for internationalized labels when
+    /// `allow_idn` is set, punycode-encoded) so clients can pre-check before committing.
+    /// Aborts with `InvalidLabel` if the label can never be registered.
+    pub fn normalize(env: Env, label: Bytes) -> Bytes {
+        let params = read_params(&env);
+        let canonical = labels::normalize(&env, &label, params.allow_idn);
+        ensure_label_len_bounds(&env, &params, canonical.len());
+        canonical
+    }
+
+    /// Return whether `label` is currently available under `tld`.
+    pub fn available(env: Env, tld: Bytes, label: Bytes) -> bool {
+        if label.is_empty() {
+            return false;
+        }
+        if !env
+            .storage()
+            .persistent()
+            .has(&singleton_key(&env, keys::REGISTRY))
+        {
+            return false;
+        }
+        is_known_tld(&env, &tld)
+            && !reserved::contains(&env, &tld, &label)
+            && !reserved::held(&env, &tld, &label)
+            && pricing::length_policy(&env, &tld, label.len()).enabled
+            && unclaimed(&env, &tld, &label)
+    }
+
+    /// Admin-only: block public registration of each label (protocol or brand terms).
+    /// Already-registered names keep working; they just cannot be re-registered publicly.
+    pub fn reserve_labels(env: Env, admin: Address, tld: Bytes, labels: Vec<Bytes>) {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        reserve_all(&env, tld, labels);
+    }
+
+    /// Admin-only: make each hash in `hashes` a voucher worth `discount_bps` off the
+    /// registration fee for `uses` registrations. Hashes are `sha256(code)`; the code itself is
+    /// handed out off-chain and passed to `register`. Emits `voucher_minted` per hash.
+    pub fn mint_vouchers(
+        env: Env,
+        admin: Address,
+        hashes: Vec<BytesN<32>>,
+        discount_bps: u32,
+        uses: u32,
+    ) {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        mint_all_vouchers(&env, hashes, discount_bps, uses);
+    }
+
+    /// Admin-only: withdraw an unused or partly used voucher. Emits `voucher_revoked`.
+    pub fn revoke_voucher(env: Env, admin: Address, hash: BytesN<32>) {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        vouchers::revoke(&env, &hash);
+    }
+
+    /// The voucher minted for `hash`, while it has redemptions left.
+    pub fn voucher(env: Env, hash: BytesN<32>) -> Option<Voucher> {
+        ensure_initialized(&env);
+        vouchers::get(&env, &hash)
+    }
+
+    /// Admin-only: one page of every name this Registrar has issued, with its current Registry
+    /// owner and expiry, for audited migrations and mirrors. Pages are `limit` (1 to 50)
+    /// names in first-registration order starting at position `cursor`; the second value is
+    /// the cursor of the next page, or `None` after the last one.
+    pub fn export_state(
+        env: Env,
+        admin: Address,
+        cursor: u32,
+        limit: u32,
+    ) -> (Vec<NameRecord>, Option<u32>) {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin(&env, &admin);
+        ensure_batch_size(&env, limit);
+        let registry = read_registry(&env);
+        let total = name_index::count(&env);
+        let end = cursor.saturating_add(limit).min(total);
+        let mut page = Vec::new(&env);
+        for position in cursor..end {
+            let Some(namehash) = name_index::get(&env, position) else {
+                continue;
+            };
+            page.push_back(NameRecord {
+                owner: registry_api::owner(&env, &registry, &namehash),
+                expires_at: registry_api::expires(&env, &registry, &namehash),
+                namehash,
+            });
+        }
+        (page, (end < total).then_some(end))
+    }
+
+    /// Registration and renewal fees collected in `token` since revenue tracking began. Gross
+    /// of referral shares, keeper tips, and release refunds, which are paid out of it.
+    pub fn revenue(env: Env, token: Address) -> i128 {
+        ensure_initialized(&env);
+        revenue::total(&env, &token)
+    }
+
+    /// Fees collected during period `period_id`, which covers the 30 days from
+    /// `period_id * 2_592_000` (Unix seconds); see `revenue_period`.
+    pub fn revenue_by_period(env: Env, period_id: u32) -> Revenue {
+        ensure_initialized(&env);
+        revenue::period(&env, period_id)
+    }
+
+    /// The `revenue_by_period` bucket `timestamp` falls in.
+    pub fn revenue_period(_env: Env, timestamp: u64) -> u32 {
+        revenue::period_of(timestamp)
+    }
+
+    /// Registration counters for dashboards; see `RegistrarStats`.
+    pub fn stats(env: Env) -> RegistrarStats {
+        ensure_initialized(&env);
+        stats::get(&env)
+    }
+
+    /// How many names `export_state` walks.
+    pub fn exported_count(env: Env) -> u32 {
+        ensure_initialized(&env);
+        name_index::count(&env)
+    }
+
+    /// Admin-only: return a reserved label under `tld` to the public pool.
+    pub fn release_label(env: Env, admin: Address, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        unreserve(&env, tld, label);
+    }
+
+    /// Whether `label` is on the reserved list of `tld`.
+    pub fn is_reserved(env: Env, tld: Bytes, label: Bytes) -> bool {
+        reserved::contains(&env, &tld, &label)
+    }
+
+    /// Admin-only: register a reserved label to `owner` for `duration_secs` without a
+    /// commitment or fee. The label stays reserved, so once the allocation lapses it can only
+    /// be allocated again. Emits `EvtNameRegistered` with a price of `0`.
+    pub fn allocate_reserved(
+        env: Env,
+        admin: Address,
+        tld: Bytes,
+        label: Bytes,
+        owner: Address,
+        resolver: Option<Address>,
+        duration_secs: u64,
+    ) -> BytesN<32> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        allocate_reserved_name(&env, tld, label, owner, resolver, duration_secs)
+    }
+
+    /// Admin-only (or `AdminAction::CreatePremiumAuction` through the council): put a
+    /// reserved label up for English auction, open for `duration_secs` from now with bids
+    /// starting at `start_price`. Aborts with `NotReserved` for an unreserved label,
+    /// `NameInAuction` if one is already open, and `InvalidParams` for a non-positive price
+    /// or zero duration. Emits `premium_auction_created`.
+    pub fn create_premium_auction(
+        env: Env,
+        admin: Address,
+        label: Bytes,
+        start_price: i128,
+        duration_secs: u64,
+    ) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        open_premium_auction(&env, label, start_price, duration_secs);
+    }
+
+    /// Bid `amount` of the payment token on a premium auction. The bid must reach the start
+    /// price, then beat the leading bid by 5% (`InvalidAmount` otherwise); it is escrowed and
+    /// the previous leader refunded at once. A bid in the last 10 minutes extends the auction
+    /// to 10 minutes from now. `NotInAuction` once bidding has closed. Emits
+    /// `premium_bid_placed`.
+    pub fn bid_premium(env: Env, bidder: Address, label: Bytes, amount: i128) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        bidder.require_auth();
+        let mut auction = premium::read(&env, &label);
+        let now = env.ledger().timestamp();
+        if now >= auction.ends_at {
+            panic_with_error!(&env, RegistrarError::NotInAuction);
+        }
+        if amount < premium::min_bid(&auction) {
+            panic_with_error!(&env, RegistrarError::InvalidAmount);
+        }
+        payments::collect(&env, &bidder, amount);
+        if let Some(previous) = auction.highest_bidder.as_ref() {
+            payments::pay_out(&env, previous, auction.highest_bid);
+        }
+        premium::adjust_held(&env, amount - auction.highest_bid);
+        auction.highest_bidder = Some(bidder.clone());
+        auction.highest_bid = amount;
+        auction.ends_at = auction
+            .ends_at
+            .max(now.saturating_add(PREMIUM_BID_EXTENSION_SECS));
+        premium::write(&env, &label, &auction);
+        EvtPremiumBidPlaced {
+            label,
+            bidder,
+            amount,
+            ends_at: auction.ends_at,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Close a premium auction once bidding has ended (`NameInAuction` before). The winner
+    /// is registered the label for `renew_extension_secs` and the winning bid becomes fee
+    /// income, withdrawn to the treasury like any fee; the label stays reserved. Without
+    /// bids the label just stays reserved. Permissionless; returns the winner. Emits
+    /// `EvtNameRegistered` for a sale, then `premium_auction_settled`.
+    pub fn settle_premium(env: Env, label: Bytes) -> Option<Address> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        let auction = premium::read(&env, &label);
+        if env.ledger().timestamp() < auction.ends_at {
+            panic_with_error!(&env, RegistrarError::NameInAuction);
+        }
+        premium::remove(&env, &label);
+        if let Some(winner) = auction.highest_bidder.as_ref() {
+            premium::adjust_held(&env, -auction.highest_bid);
+            revenue::record(&env, auction.highest_bid, false);
+            allocate_name(
+                &env,
+                read_tld(&env),
+                label.clone(),
+                winner.clone(),
+                None,
+                read_params(&env).renew_extension_secs,
+                auction.highest_bid,
+            );
+        }
+        EvtPremiumAuctionSettled {
+            label,
+            winner: auction.highest_bidder.clone(),
+            price: auction.highest_bid,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        auction.highest_bidder
+    }
+
+    /// The open premium auction of `label`, if any.
+    pub fn premium_auction(env: Env, label: Bytes) -> Option<PremiumAuction> {
+        ensure_initialized(&env);
+        premium::get(&env, &label)
+    }
+
+    /// Admin-only: set (or clear) the auction contract allowed to hold and allocate labels.
+    pub fn set_auction_house(env: Env, caller: Address, auction_house: Option<Address>) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        write_auction_house(&env, auction_house);
+    }
+
+    /// The configured auction contract, if any.
+    pub fn auction_house(env: Env) -> Option<Address> {
+        read_auction_house(&env)
+    }
+
+    /// Auction house only: withhold an available label under `tld` from commit–reveal
+    /// registration while its auction runs.
+    pub fn hold_for_auction(env: Env, auction_house: Address, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        ensure_auction_house(&env, &auction_house);
+        validate_label(&env, &tld, &label);
+        if !Self::available(env.clone(), tld.clone(), label.clone()) {
+            panic_with_error!(&env, RegistrarError::NameNotAvailable);
+        }
+        reserved::set_held(&env, &tld, &label, true);
+    }
+
+    /// Auction house only: return a held label to open registration (no winning bid).
+    pub fn release_auction_hold(env: Env, auction_house: Address, tld: Bytes, label: Bytes) {
+        ensure_initialized(&env);
+        ensure_auction_house(&env, &auction_house);
+        if !reserved::held(&env, &tld, &label) {
+            panic_with_error!(&env, RegistrarError::NotInAuction);
+        }
+        reserved::set_held(&env, &tld, &label, false);
+    }
+
+    /// Auction house only: register a held label under `tld` to the auction winner for
+    /// `duration_secs`. The auction house collected `price` itself; it is reported in
+    /// `EvtNameRegistered`.
+    pub fn allocate_auctioned(
+        env: Env,
+        auction_house: Address,
+        tld: Bytes,
+        label: Bytes,
+        owner: Address,
+        duration_secs: u64,
+        price: i128,
+    ) -> BytesN<32> {
+        ensure_initialized(&env);
+        ensure_not_paused(&env);
+        ensure_auction_house(&env, &auction_house);
+        if !reserved::held(&env, &tld, &label) {
+            panic_with_error!(&env, RegistrarError::NotInAuction);
+        }
+        reserved::set_held(&env, &tld, &label, false);
+        allocate_name(&env, tld, label, owner, None, duration_secs, price)
+    }
+
+    /// Admin-only: start issuing names under another TLD with its own parameters and price
+    /// schedule. The Registry must let this contract create children of the TLD node (see its
+    /// `create_tld`).
+    pub fn add_tld(
+        env: Env,
+        caller: Address,
+        tld: Bytes,
+        params: RegistrarParams,
+        schedule: PriceSchedule,
+    ) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        add_tld(&env, tld, params, schedule);
+    }
+
+    /// Admin-only: replace the parameters and price schedule of a TLD added with `add_tld`.
+    /// The default TLD is configured with `set_params` and `set_price_schedule`.
+    pub fn set_tld_config(
+        env: Env,
+        caller: Address,
+        tld: Bytes,
+        params: RegistrarParams,
+        schedule: PriceSchedule,
+    ) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        ensure_no_timelock(&env);
+        replace_tld_config(&env, tld, params, schedule);
+    }
+
+    /// Every TLD this Registrar issues names under, starting with the default TLD.
+    pub fn tlds(env: Env) -> Vec<Bytes> {
+        ensure_initialized(&env);
+        let mut tlds = vec![&env, read_tld(&env)];
+        tlds.append(&read_extra_tlds(&env));
+        tlds
+    }
+
+    /// Registration parameters for names under `tld`.
+    pub fn tld_params(env: Env, tld: Bytes) -> RegistrarParams {
+        ensure_initialized(&env);
+        tld_params(&env, &tld)
+    }
+
+    /// Price schedule for names under `tld`.
+    pub fn tld_price_schedule(env: Env, tld: Bytes) -> PriceSchedule {
+        ensure_initialized(&env);
+        tld_schedule(&env, &tld)
+    }
+
+    /// Update registrar parameters (admin only).
+    pub fn set_params(env: Env, caller: Address, params: RegistrarParams) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        ensure_no_timelock(&env);
+        apply_params(&env, params);
+    }
+
+    /// Fetch current registrar parameters.
+    pub fn params(env: Env) -> RegistrarParams {
+        ensure_initialized(&env);
+        read_params(&env)
+    }
+
+    /// Replace the price schedule used by `register`, `renew`, and `rent_price` (admin only).
+    pub fn set_price_schedule(env: Env, caller: Address, schedule: PriceSchedule) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        ensure_no_timelock(&env);
+        apply_schedule(&env, schedule);
+    }
+
+    /// Admin-only: open, close, or reprice registrations of `len`-byte labels under `tld`,
+    /// e.g. to keep 1–4 character names closed at launch or sell them at a multiple of the
+    /// schedule.
+    pub fn set_length_policy(
+        env: Env,
+        caller: Address,
+        tld: Bytes,
+        len: u32,
+        policy: LengthPolicy,
+    ) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        ensure_no_timelock(&env);
+        write_length_policy(&env, tld, len, policy);
+    }
+
+    /// Policy applied to `len`-byte labels under `tld`.
+    pub fn length_policy(env: Env, tld: Bytes, len: u32) -> LengthPolicy {
+        pricing::length_policy(&env, &tld, len)
+    }
+
+    /// Fetch the active price schedule.
+    pub fn price_schedule(env: Env) -> PriceSchedule {
+        ensure_initialized(&env);
+        read_price_schedule(&env)
+    }
+
+    /// Quote the cost of holding `label` under `tld` for `duration_secs`, prorated from its
+    /// annual tier.
+    pub fn rent_price(env: Env, tld: Bytes, label: Bytes, duration_secs: u64) -> i128 {
+        ensure_initialized(&env);
+        validate_label(&env, &tld, &label);
+        pricing::rent_price(&env, &tld, label.len(), duration_secs)
+    }
+
+    /// Premium currently added to the rent for `label` under `tld`, or `0` once it has fully
+    /// decayed or if the name was never registered.
+    pub fn current_premium(env: Env, tld: Bytes, label: Bytes) -> i128 {
+        ensure_initialized(&env);
+        validate_label(&env, &tld, &label);
+        let registry = read_registry(&env);
+        current_premium(&env, &registry, &tld, &compute_namehash(&env, &tld, &label))
+    }
+
+    /// Everything a UI needs about `label` under `tld` in one call: Registry owner, resolver,
+    /// and expiry, availability, grace status, and the current price and premium.
+    pub fn name_info(env: Env, tld: Bytes, label: Bytes) -> NameInfo {
+        ensure_initialized(&env);
+        validate_label(&env, &tld, &label);
+        let registry = read_registry(&env);
+        let namehash = compute_namehash(&env, &tld, &label);
+        let params = tld_params(&env, &tld);
+        let expires_at = registry_api::expires(&env, &registry, &namehash);
+        let now = env.ledger().timestamp();
+        let in_grace = expires_at
+            .is_some_and(|expires_at| in_grace(now, expires_at, params.grace_period_secs));
+        NameInfo {
+            owner: registry_api::owner(&env, &registry, &namehash),
+            resolver: registry_api::resolver(&env, &registry, &namehash),
+            expires_at,
+            available: Self::available(env.clone(), tld.clone(), label.clone()),
+            in_grace,
+            redemption_fee: if in_grace { params.redemption_fee } else { 0 },
+            price: pricing::rent_price(&env, &tld, label.len(), params.renew_extension_secs),
+            premium: current_premium(&env, &registry, &tld, &namehash),
+            namehash,
+        }
+    }
+
+    /// Transfer `amount` of collected fees to `to` (admin only). Fees are kept here rather
+    /// than forwarded to a treasury set at `init`, since refunds, referral claims, and escrow
+    /// are paid from the same balance; `to` is the treasury for this withdrawal.
+    pub fn withdraw(env: Env, caller: Address, amount: i128, to: Address) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        withdraw_fees(&env, amount, to);
+    }
+
+    /// Pay out everything credited to `referrer` by past registrations.
+    pub fn claim_referral_fees(env: Env, referrer: Address) -> i128 {
+        ensure_initialized(&env);
+        referrer.require_auth();
+        let amount = referrals::take(&env, &referrer);
+        if amount <= 0 {
+            panic_with_error!(&env, RegistrarError::InvalidAmount);
+        }
+        payments::pay_out(&env, &referrer, amount);
+        EvtReferralClaimed {
+            referrer,
+            amount,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        amount
+    }
+
+    /// Unclaimed referral fees credited to `referrer`.
+    pub fn referral_balance(env: Env, referrer: Address) -> i128 {
+        ensure_initialized(&env);
+        referrals::balance(&env, &referrer)
+    }
+
+    /// Point `register` at a name token contract that mints the new owner a token per name
+    /// (admin only). This Registrar must be that contract's minter.
+    pub fn set_name_token(env: Env, caller: Address, name_token: Address) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        write_name_token(&env, &name_token);
+    }
+
+    /// Replaces this contract's code with `new_wasm_hash` (already uploaded), keeping its
+    /// address, configuration, and commitments. Call `migrate` afterwards.
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin_action(&env, &admin);
+        ensure_no_timelock(&env);
+        upgrade_code(&env, new_wasm_hash);
+    }
+
+    /// Runs the storage migrations between the stored layout version and `STORAGE_VERSION`,
+    /// then records the new version. A no-op when storage is already current.
+    pub fn migrate(env: Env, admin: Address) -> u32 {
+        ensure_initialized(&env);
+        admin.require_auth();
+        ensure_admin(&env, &admin);
+        let from = Self::storage_version(env.clone());
+        if from > STORAGE_VERSION {
+            panic_with_error!(&env, RegistrarError::UnsupportedVersion);
+        }
+        if from < STORAGE_VERSION {
+            // Layout changes add their rewrite steps here, keyed on `from`.
+            let key = singleton_key(&env, keys::STORAGE_VERSION);
+            write_entry(&env, &key, &STORAGE_VERSION);
+            EvtMigrated {
+                from,
+                to: STORAGE_VERSION,
+                seq: next_event_seq(&env),
+            }
+            .publish(&env);
+        }
+        STORAGE_VERSION
+    }
+
+    /// Layout version of stored data; deployments that predate versioning report 0, so
+    /// `migrate` runs their rewrite steps.
+    pub fn storage_version(env: Env) -> u32 {
+        let storage = env.storage().persistent();
+        storage
+            .get(&singleton_key(&env, keys::STORAGE_VERSION))
+            .unwrap_or(0)
+    }
+
+    /// Emergency stop: blocks `commit`, `register`, `renew`, and `renew_for` until
+    /// `unpause`. Views, withdrawals, and commitment cleanup keep working.
+    pub fn pause(env: Env, caller: Address) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin(&env, &caller);
+        write_entry(&env, &singleton_key(&env, keys::PAUSED), &true);
+        EvtPaused {
+            admin: caller,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Lift an earlier `pause`.
+    pub fn unpause(env: Env, caller: Address) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin(&env, &caller);
+        env.storage()
+            .persistent()
+            .remove(&singleton_key(&env, keys::PAUSED));
+        EvtUnpaused {
+            admin: caller,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Whether the Registrar is currently paused.
+    pub fn paused(env: Env) -> bool {
+        ensure_initialized(&env);
+        is_paused(&env)
+    }
+
+    /// Nominate `new_admin`; the role moves only once they call `accept_admin`.
+    /// Nominating again replaces any earlier pending nominee.
+    pub fn transfer_admin(env: Env, current_admin: Address, new_admin: Address) {
+        ensure_initialized(&env);
+        current_admin.require_auth();
+        ensure_admin(&env, &current_admin);
+        write_entry(&env, &singleton_key(&env, keys::PENDING_ADMIN), &new_admin);
+    }
+
+    /// Complete a handover started by `transfer_admin`.
+    pub fn accept_admin(env: Env, new_admin: Address) {
+        ensure_initialized(&env);
+        new_admin.require_auth();
+        if read_pending_admin(&env) != Some(new_admin.clone()) {
+            panic_with_error!(&env, RegistrarError::NotPendingAdmin);
+        }
+        let previous = read_admin(&env);
+        env.storage()
+            .persistent()
+            .remove(&singleton_key(&env, keys::PENDING_ADMIN));
+        write_admin(&env, &new_admin);
+        EvtAdminChanged {
+            previous,
+            admin: new_admin,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Current admin.
+    pub fn admin(env: Env) -> Address {
+        ensure_initialized(&env);
+        read_admin(&env)
+    }
+
+    /// Admin-only, once: hand `set_params`, `set_price_schedule`, `withdraw`, `upgrade`, and
+    /// reserved-label changes to an M-of-N council. From then on those calls abort with
+    /// `CouncilRequired` and run only through `execute_action`; the council changes itself
+    /// with `AdminAction::SetCouncil`. Emits `council_changed`.
+    pub fn set_council(env: Env, caller: Address, council: AdminCouncil) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        council::write(&env, &council);
+    }
+
+    pub fn council(env: Env) -> Option<AdminCouncil> {
+        ensure_initialized(&env);
+        council::read(&env)
+    }
+
+    /// Council members only: open a proposal to run `action`, counting the proposer's
+    /// approval. It can be approved and executed for `PROPOSAL_TTL_SECS` (7 days). Returns
+    /// its id. Emits `action_proposed`.
+    pub fn propose_action(env: Env, member: Address, action: AdminAction) -> u32 {
+        ensure_initialized(&env);
+        member.require_auth();
+        council::require_member(&env, &member);
+        let id = council::next_id(&env);
+        let expires_at = env.ledger().timestamp().saturating_add(PROPOSAL_TTL_SECS);
+        let proposal = Proposal {
+            action: action.clone(),
+            proposer: member.clone(),
+            approvals: vec![&env, member.clone()],
+            expires_at,
+        };
+        council::store(&env, id, &proposal);
+        EvtActionProposed {
+            id,
+            proposer: member,
+            action,
+            expires_at,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+        id
+    }
+
+    /// Council members only: approve an open proposal. Emits `action_approved` with the
+    /// number of approvals that now count.
+    pub fn approve_action(env: Env, member: Address, id: u32) {
+        ensure_initialized(&env);
+        member.require_auth();
+        let council = council::require_member(&env, &member);
+        let mut proposal = council::open_proposal(&env, id);
+        if proposal.approvals.contains(&member) {
+            panic_with_error!(&env, RegistrarError::AlreadyApproved);
+        }
+        proposal.approvals.push_back(member.clone());
+        council::store(&env, id, &proposal);
+        EvtActionApproved {
+            id,
+            member,
+            approvals: council::approvals(&council, &proposal),
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Council members only: run a proposal once `threshold` current members have approved
+    /// it, then delete it. The action emits its usual events, followed by `action_executed`.
+    pub fn execute_action(env: Env, member: Address, id: u32) {
+        ensure_initialized(&env);
+        member.require_auth();
+        let council = council::require_member(&env, &member);
+        let proposal = council::open_proposal(&env, id);
+        if council::approvals(&council, &proposal) < council.threshold {
+            panic_with_error!(&env, RegistrarError::NotEnoughApprovals);
+        }
+        council::remove(&env, id);
+        match proposal.action {
+            AdminAction::SetParams(params) => {
+                ensure_no_timelock(&env);
+                apply_params(&env, params);
+            }
+            AdminAction::SetPriceSchedule(schedule) => {
+                ensure_no_timelock(&env);
+                apply_schedule(&env, schedule);
+            }
+            AdminAction::Withdraw(amount, to) => withdraw_fees(&env, amount, to),
+            AdminAction::Upgrade(new_wasm_hash) => {
+                ensure_no_timelock(&env);
+                upgrade_code(&env, new_wasm_hash);
+            }
+            AdminAction::ReserveLabels(tld, labels) => reserve_all(&env, tld, labels),
+            AdminAction::ReleaseLabel(tld, label) => unreserve(&env, tld, label),
+            AdminAction::SetCouncil(council) => council::write(&env, &council),
+            AdminAction::QueueChange(change) => {
+                timelock::queue(&env, change);
+            }
+            AdminAction::CancelChange(id) => timelock::cancel(&env, id),
+            AdminAction::SetTimelock(delay_secs) => lengthen_timelock(&env, delay_secs),
+            AdminAction::CreatePremiumAuction(label, start_price, duration_secs) => {
+                open_premium_auction(&env, label, start_price, duration_secs)
+            }
+            AdminAction::SetLengthPolicy(tld, len, policy) => {
+                ensure_no_timelock(&env);
+                write_length_policy(&env, tld, len, policy);
+            }
+            AdminAction::AddTld(tld, params, schedule) => add_tld(&env, tld, params, schedule),
+            AdminAction::SetTldConfig(tld, params, schedule) => {
+                ensure_no_timelock(&env);
+                replace_tld_config(&env, tld, params, schedule);
+            }
+            AdminAction::MintVouchers(hashes, discount_bps, uses) => {
+                mint_all_vouchers(&env, hashes, discount_bps, uses)
+            }
+            AdminAction::RevokeVoucher(hash) => vouchers::revoke(&env, &hash),
+            AdminAction::SetNameToken(name_token) => write_name_token(&env, &name_token),
+            AdminAction::SetAuctionHouse(auction_house) => write_auction_house(&env, auction_house),
+            AdminAction::AllocateReserved(tld, label, owner, resolver, duration_secs) => {
+                allocate_reserved_name(&env, tld, label, owner, resolver, duration_secs);
+            }
+        }
+        EvtActionExecuted {
+            id,
+            executor: member,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// A proposal that has not been executed, including expired ones.
+    pub fn proposal(env: Env, id: u32) -> Option<Proposal> {
+        ensure_initialized(&env);
+        council::proposal(&env, id)
+    }
+
+    /// Requires `queue_params`/`execute_params` for `set_params`, `set_price_schedule`,
+    /// `set_tld_config`, `set_length_policy`, and `upgrade`, delaying each change by
+    /// `delay_secs` (at most 30 days) so registrants can react. Only a longer delay applies at
+    /// once; shorten it by queueing `TimelockedChange::Delay`. Emits `timelock_changed`.
+    pub fn set_timelock(env: Env, caller: Address, delay_secs: u64) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        lengthen_timelock(&env, delay_secs);
+    }
+
+    /// Current timelock delay; `0` when changes apply immediately.
+    pub fn timelock(env: Env) -> u64 {
+        ensure_initialized(&env);
+        timelock::delay(&env)
+    }
+
+    /// Queue `change` to be applied by `execute_params` once the timelock delay has passed.
+    /// Same admin check as `set_params`; a council queues through
+    /// `AdminAction::QueueChange`. Returns the change's id. Emits `params_queued`.
+    pub fn queue_params(env: Env, caller: Address, change: TimelockedChange) -> u32 {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        timelock::queue(&env, change)
+    }
+
+    /// Apply a queued change whose `eta` has passed. Permissionless: anyone may execute once
+    /// the delay is over. Emits the change's own events followed by `params_executed`.
+    pub fn execute_params(env: Env, id: u32) {
+        ensure_initialized(&env);
+        let queued = timelock::queued(&env, id)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistrarError::ChangeMissing));
+        if env.ledger().timestamp() < queued.eta {
+            panic_with_error!(&env, RegistrarError::ChangeNotReady);
+        }
+        timelock::take(&env, id);
+        match queued.change {
+            TimelockedChange::Params(params) => apply_params(&env, params),
+            TimelockedChange::PriceSchedule(schedule) => apply_schedule(&env, schedule),
+            TimelockedChange::Upgrade(new_wasm_hash) => upgrade_code(&env, new_wasm_hash),
+            TimelockedChange::Delay(delay_secs) => timelock::set_delay(&env, delay_secs),
+            TimelockedChange::TldConfig(tld, params, schedule) => {
+                replace_tld_config(&env, tld, params, schedule)
+            }
+            TimelockedChange::LengthPolicy(tld, len, policy) => {
+                write_length_policy(&env, tld, len, policy)
+            }
+        }
+        EvtParamsExecuted {
+            id,
+            seq: next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Drop a queued change before it is executed. Same admin check as `queue_params`; a
+    /// council cancels through `AdminAction::CancelChange`. Emits `params_cancelled`.
+    pub fn cancel_params(env: Env, caller: Address, id: u32) {
+        ensure_initialized(&env);
+        caller.require_auth();
+        ensure_admin_action(&env, &caller);
+        timelock::cancel(&env, id);
+    }
+
+    /// A change waiting for `execute_params`.
+    pub fn queued_change(env: Env, id: u32) -> Option<QueuedChange> {
+        ensure_initialized(&env);
+        timelock::queued(&env, id)
+    }
+
+    /// Nominee awaiting `accept_admin`, if any.
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        ensure_initialized(&env);
+        read_pending_admin(&env)
+    }
+
+    /// Name token contract minted on `register`, if configured.
+    pub fn name_token(env: Env) -> Option<Address> {
+        ensure_initialized(&env);
+        read_name_token(&env)
+    }
+
+    /// Stellar asset contract used for registration and renewal fees.
+    pub fn payment_token(env: Env) -> Address {
+        ensure_initialized(&env);
+        read_token(&env)
+    }
+
+    /// Stored registry address helper.
+    pub fn registry(env: Env) -> Address {
+        ensure_initialized(&env);
+        read_registry(&env)
+    }
+}
+
+/// Interfaces answered by `supports_interface`.
+const SUPPORTED_INTERFACES: [&str; 3] = [
+    resolver_interface::INTERFACE_DETECTION,
+    resolver_interface::INTERFACE_REGISTRAR,
+    resolver_interface::INTERFACE_RENT_PRICE,
+];
+
+#[contractimpl]
+impl InterfaceDetection for Registrar {
+    fn supports_interface(env: Env, interface_id: Symbol) -> bool {
+        SUPPORTED_INTERFACES
+            .iter()
+            .any(|name| interface_id == Symbol::new(&env, name))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soroban_sdk::{
+        contract, contractimpl, contracttype,
+        testutils::{
+            storage::{Persistent as _, Temporary as _},
+            Address as _, Events, Ledger,
+        },
+        token::{StellarAssetClient, TokenClient},
+        Address, Bytes, BytesN, Env, Map, Symbol, TryFromVal, Val,
+    };
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    const MOCK_RENEW_EXTENSION: u64 = 31_536_000;
+    const DEFAULT_DURATION: u64 = 31_536_000;
+
+    #[contract]
+    pub struct MockRegistry;
+
+    #[contracttype]
+    #[derive(Clone)]
+    enum MockRegistryKey {
+        Owner(BytesN<32>),
+        Resolver(BytesN<32>),
+        Expires(BytesN<32>),
+        RecordVersion(BytesN<32>),
+    }
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Owner(namehash.clone()))
+                .unwrap_or_else(|| panic!("owner not set"))
+        }
+
+        pub fn set_owner(
+            env: Env,
+            _caller: Address,
+            parent: BytesN<32>,
+            label: Bytes,
+            owner: Address,
+        ) -> BytesN<32> {
+            let label_hash = env.crypto().sha256(&label).to_bytes();
+            let namehash = name_service_common::fold_hash(&env, &parent, &label_hash);
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Owner(namehash.clone()), &owner);
+            namehash
+        }
+
+        pub fn transfer(env: Env, _caller: Address, namehash: BytesN<32>, to: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Owner(namehash), &to);
+        }
+
+        pub fn set_resolver(env: Env, _caller: Address, namehash: BytesN<32>, resolver: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Resolver(namehash), &resolver);
+        }
+
+        pub fn resolver(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Resolver(namehash))
+        }
+
+        pub fn release(env: Env, _caller: Address, namehash: BytesN<32>) {
+            let storage = env.storage().persistent();
+            storage.remove(&MockRegistryKey::Owner(namehash.clone()));
+            storage.remove(&MockRegistryKey::Resolver(namehash.clone()));
+            storage.remove(&MockRegistryKey::Expires(namehash));
+        }
+
+        pub fn set_expiry(env: Env, _caller: Address, namehash: BytesN<32>, expires_at: u64) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Expires(namehash), &expires_at);
+        }
+
+        pub fn bump_record_version(env: Env, _caller: Address, namehash: BytesN<32>) -> u32 {
+            let key = MockRegistryKey::RecordVersion(namehash);
+            let version: u32 = env.storage().persistent().get(&key).unwrap_or(0) + 1;
+            env.storage().persistent().set(&key, &version);
+            version
+        }
+
+        pub fn record_version(env: Env, namehash: BytesN<32>) -> u32 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::RecordVersion(namehash))
+                .unwrap_or(0)
+        }
+
+        pub fn extend_expiry(env: Env, _caller: Address, namehash: BytesN<32>, expires_at: u64) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Expires(namehash), &expires_at);
+        }
+
+        pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Expires(namehash.clone()))
+                .unwrap_or_else(|| panic!("expiry not set"))
+        }
+
+        pub fn clear_expiry(env: Env, namehash: BytesN<32>) {
+            env.storage()
+                .persistent()
+                .remove(&MockRegistryKey::Expires(namehash));
+        }
+    }
+
+    #[contract]
+    pub struct MockRegistryOwnerAuth;
+
+    #[contractimpl]
+    impl MockRegistryOwnerAuth {
+        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Owner(namehash.clone()))
+                .unwrap_or_else(|| panic!("owner not set"))
+        }
+
+        pub fn set_owner(
+            env: Env,
+            _caller: Address,
+            parent: BytesN<32>,
+            label: Bytes,
+            owner: Address,
+        ) -> BytesN<32> {
+            let label_hash = env.crypto().sha256(&label).to_bytes();
+            let namehash = name_service_common::fold_hash(&env, &parent, &label_hash);
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Owner(namehash.clone()), &owner);
+            namehash
+        }
+
+        pub fn transfer(env: Env, _caller: Address, namehash: BytesN<32>, to: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Owner(namehash), &to);
+        }
+
+        pub fn set_resolver(env: Env, _caller: Address, namehash: BytesN<32>, resolver: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Resolver(namehash), &resolver);
+        }
+
+        pub fn resolver(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Resolver(namehash))
+        }
+
+        pub fn extend_expiry(env: Env, _caller: Address, namehash: BytesN<32>, expires_at: u64) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Expires(namehash), &expires_at);
+        }
+
+        pub fn set_expiry(env: Env, _caller: Address, namehash: BytesN<32>, expires_at: u64) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Expires(namehash), &expires_at);
+        }
+
+        pub fn bump_record_version(env: Env, _caller: Address, namehash: BytesN<32>) -> u32 {
+            let key = MockRegistryKey::RecordVersion(namehash);
+            let version: u32 = env.storage().persistent().get(&key).unwrap_or(0) + 1;
+            env.storage().persistent().set(&key, &version);
+            version
+        }
+
+        pub fn record_version(env: Env, namehash: BytesN<32>) -> u32 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::RecordVersion(namehash))
+                .unwrap_or(0)
+        }
+
+        pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Expires(namehash.clone()))
+                .unwrap_or_else(|| panic!("expiry not set"))
+        }
+    }
+
+    /// Resolver stand-in that only serves `addr` records.
+    #[contract]
+    pub struct MockResolver;
+
+    #[contractimpl]
+    impl MockResolver {
+        pub fn supports(env: Env, record_type: Symbol) -> bool {
+            record_type == Symbol::new(&env, resolver_interface::RECORD_ADDR)
+        }
+    }
+
+    #[contract]
+    pub struct MockNameToken;
+
+    #[contractimpl]
+    impl MockNameToken {
+        pub fn mint(env: Env, token_id: BytesN<32>, to: Address) {
+            env.storage().persistent().set(&token_id, &to);
+        }
+
+        pub fn owner_of(env: Env, token_id: BytesN<32>) -> Address {
+            env.storage().persistent().get(&token_id).unwrap()
+        }
+    }
+
+    fn event_field<T: TryFromVal<Env, Val>>(env: &Env, data: &Val, field: &str) -> T {
+        let map = Map::<Symbol, Val>::try_from_val(env, data).unwrap();
+        T::try_from_val(env, &map.get(Symbol::new(env, field)).unwrap()).unwrap()
+    }
+
+    fn setup_env() -> (Env, Address, Address, Address) {
+        let env = Env::default();
+        let registry_id = env.register(MockRegistry, ());
+        let registrar_id = env.register(Registrar, ());
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let admin = Address::generate(&env);
+        let tld = Bytes::from_slice(&env, b"stellar");
+        let token = create_token(&env, &admin);
+        registrar_client.init(&registry_id, &tld, &admin, &token);
+        env.mock_all_auths();
+        (env, registry_id, registrar_id, admin)
+    }
+
+    fn create_token(env: &Env, admin: &Address) -> Address {
+        env.register_stellar_asset_contract_v2(admin.clone())
+            .address()
+    }
+
+    fn mint(env: &Env, token: &Address, to: &Address, amount: i128) {
+        StellarAssetClient::new(env, token).mint(to, &amount);
+    }
+
+    fn default_tld(env: &Env) -> Bytes {
+        Bytes::from_slice(env, b"stellar")
     }
 
     fn make_label(env: &Env, text: &str) -> Bytes {
         Bytes::from_slice(env, text.as_bytes())
     }
 
-    fn make_bytes(env: &Env, data: &[u8]) -> Bytes {
-        Bytes::from_slice(env, data)
+    fn make_bytes(env: &Env, data: &[u8]) -> Bytes {
+        Bytes::from_slice(env, data)
+    }
+
+    fn make_commitment(
+        env: &Env,
+        label: &Bytes,
+        owner: &Address,
+        secret: &Bytes,
+        committer: &Address,
+    ) -> BytesN<32> {
+        super::compute_commitment(env, label, owner, secret, committer)
+    }
+
+    fn expected_namehash(env: &Env, label: &Bytes) -> BytesN<32> {
+        let tld = Bytes::from_slice(env, b"stellar");
+        let root = BytesN::<32>::from_array(env, &[0u8; 32]);
+        let tld_hash = env.crypto().sha256(&tld).to_bytes();
+        let node = name_service_common::fold_hash(env, &root, &tld_hash);
+        let label_hash = env.crypto().sha256(label).to_bytes();
+        name_service_common::fold_hash(env, &node, &label_hash)
+    }
+
+    fn register_name(
+        env: &Env,
+        registry_client: &MockRegistryClient,
+        registrar_client: &RegistrarClient,
+        caller: &Address,
+        label: &Bytes,
+        owner: &Address,
+        secret: &Bytes,
+        resolver: Option<&Address>,
+    ) -> BytesN<32> {
+        let commitment = make_commitment(env, label, owner, secret, caller);
+        let label_len = label.len();
+        registrar_client.commit(caller, &commitment, &label_len);
+        let params = registrar_client.params();
+        let now = env.ledger().timestamp();
+        env.ledger().set_timestamp(now + params.commit_min_age_secs);
+        let resolver_arg = resolver.cloned();
+        let result = registrar_client.register(
+            caller,
+            &default_tld(env),
+            label,
+            owner,
+            secret,
+            &resolver_arg,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        let namehash = expected_namehash(env, label);
+        assert_eq!(result, namehash);
+        let stored_owner = registry_client.owner(&namehash);
+        assert_eq!(stored_owner, *owner);
+        namehash
+    }
+
+    fn commitment_exists(env: &Env, registrar_id: &Address, commitment: &BytesN<32>) -> bool {
+        env.as_contract(registrar_id, || super::commitment_info(env, commitment))
+            .is_some()
+    }
+
+    /// The one address whose authorization the last call required.
+    fn sole_signer(env: &Env) -> Address {
+        let auths = env.auths();
+        assert_eq!(auths.len(), 1);
+        auths[0].0.clone()
+    }
+
+    #[test]
+    fn version_returns_1() {
+        let env = Env::default();
+        let registrar_id = env.register(Registrar, ());
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+
+        assert_eq!(registrar_client.version(), 1);
+    }
+
+    #[test]
+    fn init_only_once() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let tld = Bytes::from_slice(&env, b"stellar");
+        let token = registrar_client.payment_token();
+        let second = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.init(&registry_id, &tld, &admin, &token);
+        }));
+        assert!(second.is_err());
+    }
+
+    #[test]
+    fn commit_register_happy_path() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(1_000);
+        let caller = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let resolver = env.register(MockResolver, ());
+        let label = make_label(&env, "alice");
+        let secret = make_bytes(&env, b"secret");
+
+        let commitment = make_commitment(&env, &label, &owner, &secret, &caller);
+        let label_len = label.len();
+        registrar_client.commit(&caller, &commitment, &label_len);
+
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(1_000 + params.commit_min_age_secs);
+        let resolver_arg = Some(resolver.clone());
+        let expected_ts = env.ledger().timestamp();
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &owner,
+            &secret,
+            &resolver_arg,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        let events = env.events().all();
+
+        let stored_owner = registry_client.owner(&namehash);
+        assert_eq!(stored_owner, owner);
+
+        let expires = registry_client.expires(&namehash);
+        assert_eq!(
+            expires,
+            env.ledger()
+                .timestamp()
+                .checked_add(MOCK_RENEW_EXTENSION)
+                .unwrap()
+        );
+
+        let stored_resolver = registry_client.resolver(&namehash).unwrap();
+        assert_eq!(stored_resolver, resolver);
+        let mut found = false;
+        for idx in 0..events.len() {
+            let (contract_id, topics, data) = events.get(idx).unwrap();
+            if contract_id != registrar_id {
+                continue;
+            }
+            let symbol = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+            if symbol != Symbol::new(&env, "name_registered") {
+                continue;
+            }
+            let topic_namehash = BytesN::<32>::try_from_val(&env, &topics.get(1).unwrap()).unwrap();
+            assert_eq!(topic_namehash, namehash);
+            assert_eq!(event_field::<Address>(&env, &data, "owner"), owner);
+            assert_eq!(event_field::<u64>(&env, &data, "expires_at"), expires);
+            assert_eq!(event_field::<u64>(&env, &data, "ts"), expected_ts);
+            found = true;
+        }
+        assert!(found, "expected name_registered event");
+    }
+
+    #[test]
+    fn register_without_resolver_succeeds() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(1_500);
+
+        let caller = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "noresolver");
+        let secret = make_bytes(&env, b"none");
+
+        let commitment = make_commitment(&env, &label, &owner, &secret, &caller);
+        let label_len = label.len();
+        registrar_client.commit(&caller, &commitment, &label_len);
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(1_500 + params.commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &owner,
+            &secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+
+        assert_eq!(registry_client.owner(&namehash), owner);
+        assert!(registry_client.resolver(&namehash).is_none());
+    }
+
+    #[test]
+    fn register_handles_registry_owner_auth_requirements() {
+        let env = Env::default();
+        let registry_id = env.register(MockRegistryOwnerAuth, ());
+        let registrar_id = env.register(Registrar, ());
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let admin = Address::generate(&env);
+        let tld = Bytes::from_slice(&env, b"stellar");
+        let token = create_token(&env, &admin);
+        registrar_client.init(&registry_id, &tld, &admin, &token);
+        env.mock_all_auths();
+        env.ledger().set_timestamp(2_000);
+
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "ownerflow");
+        let secret = make_bytes(&env, b"flow_secret");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &caller);
+        let label_len = label.len();
+        registrar_client.commit(&caller, &commitment, &label_len);
+
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(2_000 + params.commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &owner,
+            &secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+
+        let registry_client = MockRegistryOwnerAuthClient::new(&env, &registry_id);
+        assert_eq!(registry_client.owner(&namehash), owner);
+    }
+
+    #[test]
+    fn commitment_window_invalid() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "fresh");
+        let secret = make_bytes(&env, b"123");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &caller);
+        let label_len = label.len();
+        let none_resolver: Option<Address> = None;
+        let without_commit = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &owner,
+                &secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(without_commit.is_err());
+
+        registrar_client.commit(&caller, &commitment, &label_len);
+        let too_fresh = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &owner,
+                &secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(too_fresh.is_err());
+
+        env.ledger()
+            .set_timestamp(registrar_client.params().commit_max_age_secs + 10);
+        let too_old = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &owner,
+                &secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(too_old.is_err());
+    }
+
+    #[test]
+    fn availability_cycles_through_lifecycle() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(5_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "cycle");
+        let secret = make_bytes(&env, b"cycle_secret");
+
+        assert!(registrar_client.available(&default_tld(&env), &label));
+
+        let commitment = make_commitment(&env, &label, &owner, &secret, &caller);
+        let label_len = label.len();
+        registrar_client.commit(&caller, &commitment, &label_len);
+        env.ledger()
+            .set_timestamp(5_000 + registrar_client.params().commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &owner,
+            &secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        assert!(!registrar_client.available(&default_tld(&env), &label));
+
+        let expires = registry_client.expires(&namehash);
+        env.ledger()
+            .set_timestamp(expires + registrar_client.params().grace_period_secs + 1);
+        assert!(registrar_client.available(&default_tld(&env), &label));
+    }
+
+    #[test]
+    fn unavailable_when_in_grace() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(10_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "grace");
+        let secret = make_bytes(&env, b"abc");
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        assert!(!registrar_client.available(&default_tld(&env), &label));
+
+        let expires = registry_client.expires(&namehash);
+        env.ledger()
+            .set_timestamp(expires + registrar_client.params().grace_period_secs);
+        assert!(!registrar_client.available(&default_tld(&env), &label));
+    }
+
+    #[test]
+    fn unavailable_when_owner_missing_expiry() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(11_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "noexpiry");
+        let secret = make_bytes(&env, b"missing_expiry");
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        assert!(!registrar_client.available(&default_tld(&env), &label));
+
+        registry_client.clear_expiry(&namehash);
+        assert!(
+            !registrar_client.available(&default_tld(&env), &label),
+            "ownership without expiry must remain unavailable"
+        );
+    }
+
+    #[test]
+    fn register_when_unavailable_fails() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(12_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "taken");
+        let secret = make_bytes(&env, b"secret");
+
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+
+        let new_owner = Address::generate(&env);
+        let new_secret = make_bytes(&env, b"secret2");
+        let commitment = make_commitment(&env, &label, &new_owner, &new_secret, &caller);
+        let label_len = label.len();
+        registrar_client.commit(&caller, &commitment, &label_len);
+        env.ledger()
+            .set_timestamp(12_000 + registrar_client.params().commit_min_age_secs);
+
+        let none_resolver: Option<Address> = None;
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &new_owner,
+                &new_secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(attempt.is_err());
+    }
+
+    #[test]
+    fn commitment_deleted_only_after_successful_registration() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(14_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "locked");
+        let secret = make_bytes(&env, b"lock_secret");
+
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+
+        env.ledger().set_timestamp(15_000);
+        let challenger = Address::generate(&env);
+        let challenger_secret = make_bytes(&env, b"challenge");
+        let challenger_commitment =
+            make_commitment(&env, &label, &challenger, &challenger_secret, &caller);
+        let label_len = label.len();
+        registrar_client.commit(&caller, &challenger_commitment, &label_len);
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(15_000 + params.commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &challenger,
+                &challenger_secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(attempt.is_err());
+        assert!(
+            commitment_exists(&env, &registrar_id, &challenger_commitment),
+            "commitment must remain after failed registration"
+        );
+
+        let fresh_label = make_label(&env, "freshpolicy");
+        let fresh_secret = make_bytes(&env, b"fresh_secret");
+        let fresh_commitment = make_commitment(&env, &fresh_label, &owner, &fresh_secret, &caller);
+        let fresh_len = fresh_label.len();
+        registrar_client.commit(&caller, &fresh_commitment, &fresh_len);
+        env.ledger()
+            .set_timestamp(15_000 + 2 * params.commit_min_age_secs);
+        registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &fresh_label,
+            &owner,
+            &fresh_secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        assert!(
+            !commitment_exists(&env, &registrar_id, &fresh_commitment),
+            "commitment must be removed after successful registration"
+        );
+    }
+
+    #[test]
+    fn renew_happy_path() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(20_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "renew");
+        let secret = make_bytes(&env, b"renew");
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        let before = registry_client.expires(&namehash);
+
+        env.ledger().set_timestamp(before + 1);
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+        let events = env.events().all();
+        let after = registry_client.expires(&namehash);
+        assert!(after > before);
+
+        let mut found = false;
+        for idx in 0..events.len() {
+            let (contract_id, topics, data) = events.get(idx).unwrap();
+            if contract_id != registrar_id {
+                continue;
+            }
+            let symbol = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+            if symbol != Symbol::new(&env, "name_renewed") {
+                continue;
+            }
+            let topic_namehash = BytesN::<32>::try_from_val(&env, &topics.get(1).unwrap()).unwrap();
+            assert_eq!(topic_namehash, namehash);
+            assert_eq!(event_field::<u64>(&env, &data, "expires_at"), after);
+            found = true;
+            break;
+        }
+        assert!(found, "expected name_renewed event");
+    }
+
+    #[test]
+    fn renew_extends_by_the_term_charged_for() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(20_000);
+        let mut params = registrar_client.params();
+        params.renew_extension_secs = 30 * 86_400;
+        registrar_client.set_params(&admin, &params);
+        let caller = Address::generate(&env);
+        let label = make_label(&env, "month");
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &caller,
+            &make_bytes(&env, b"month"),
+            None,
+        );
+
+        let before = registry_client.expires(&namehash);
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+        assert_eq!(registry_client.expires(&namehash), before + 30 * 86_400);
+
+        // After expiry the term runs from now, not from the lapsed expiry.
+        let now = before + 30 * 86_400 + 10;
+        env.ledger().set_timestamp(now);
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+        assert_eq!(registry_client.expires(&namehash), now + 30 * 86_400);
+    }
+
+    #[test]
+    fn renew_not_owner_rejected() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(30_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "guard");
+        let secret = make_bytes(&env, b"guard");
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+
+        let attacker = Address::generate(&env);
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.renew(&attacker, &default_tld(&env), &label);
+        }));
+        assert!(attempt.is_err());
+    }
+
+    #[test]
+    fn invalid_label_rejected() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let secret = make_bytes(&env, b"x");
+        let empty_label = Bytes::from_slice(&env, b"");
+        let none_resolver: Option<Address> = None;
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &empty_label,
+                &owner,
+                &secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(attempt.is_err());
+    }
+
+    #[test]
+    fn label_character_validation() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(45_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let secret = make_bytes(&env, b"validate");
+        let none_resolver: Option<Address> = None;
+
+        let invalid_label = Bytes::from_slice(&env, b"Bad!");
+        let leading_hyphen = Bytes::from_slice(&env, b"-lead");
+        let trailing_hyphen = Bytes::from_slice(&env, b"trail-");
+        for label in [&invalid_label, &leading_hyphen, &trailing_hyphen] {
+            let attempt = catch_unwind(AssertUnwindSafe(|| {
+                registrar_client.register(
+                    &caller,
+                    &default_tld(&env),
+                    label,
+                    &owner,
+                    &secret,
+                    &none_resolver,
+                    &DEFAULT_DURATION,
+                    &None,
+                    &None,
+                    &None,
+                );
+            }));
+            assert!(
+                attempt.is_err(),
+                "invalid label {:?} must be rejected",
+                label
+            );
+        }
+
+        let valid_label = make_label(&env, "abc-123");
+        let commitment = make_commitment(&env, &valid_label, &owner, &secret, &caller);
+        let label_len = valid_label.len();
+        registrar_client.commit(&caller, &commitment, &label_len);
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(45_000 + params.commit_min_age_secs);
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &valid_label,
+            &owner,
+            &secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        assert_eq!(registry_client.owner(&namehash), owner);
+    }
+
+    #[test]
+    fn commitment_replay_disallowed() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(40_000);
+        let caller = Address::generate(&env);
+        let owner = caller.clone();
+        let label = make_label(&env, "single");
+        let secret = make_bytes(&env, b"s1");
+
+        env.ledger().set_timestamp(40_000);
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &caller,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+
+        let none_resolver: Option<Address> = None;
+        let replay = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &owner,
+                &secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(replay.is_err());
+    }
+
+    fn tiered_schedule(env: &Env) -> PriceSchedule {
+        PriceSchedule {
+            annual_prices: soroban_sdk::vec![env, 1_000i128, 500, 100, 10],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        }
+    }
+
+    #[test]
+    fn rent_price_defaults_to_free() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let label = make_label(&env, "alice");
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &label, &pricing::SECONDS_PER_YEAR),
+            0
+        );
+    }
+
+    #[test]
+    fn rent_price_uses_length_tiers_and_prorates() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+
+        let year = pricing::SECONDS_PER_YEAR;
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &make_label(&env, "a"), &year),
+            1_000
+        );
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &make_label(&env, "ab"), &year),
+            500
+        );
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &make_label(&env, "abc"), &year),
+            100
+        );
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &make_label(&env, "abcd"), &year),
+            10
+        );
+        assert_eq!(
+            registrar_client.rent_price(
+                &default_tld(&env),
+                &make_label(&env, "longerlabel"),
+                &year
+            ),
+            10,
+            "labels past the last tier use the last tier"
+        );
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &make_label(&env, "a"), &(year / 2)),
+            500
+        );
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &make_label(&env, "a"), &(year * 3)),
+            3_000
+        );
+    }
+
+    #[test]
+    fn set_price_schedule_admin_only_and_validated() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let stranger = Address::generate(&env);
+
+        let not_admin = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.set_price_schedule(&stranger, &tiered_schedule(&env));
+        }));
+        assert!(not_admin.is_err());
+
+        let empty = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        };
+        let empty_attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.set_price_schedule(&admin, &empty);
+        }));
+        assert!(empty_attempt.is_err());
+
+        let negative = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env, 10i128, -1],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        };
+        let negative_attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.set_price_schedule(&admin, &negative);
+        }));
+        assert!(negative_attempt.is_err());
+
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        assert_eq!(
+            registrar_client.price_schedule().annual_prices,
+            tiered_schedule(&env).annual_prices
+        );
+    }
+
+    #[test]
+    fn register_and_renew_events_carry_price() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        env.ledger().set_timestamp(50_000);
+
+        let caller = Address::generate(&env);
+        mint(&env, &registrar_client.payment_token(), &caller, 1_000);
+        let label = make_label(&env, "abc");
+        let secret = make_bytes(&env, b"priced");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(50_000 + registrar_client.params().commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &caller,
+            &secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        let events = env.events().all();
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+        let renew_events = env.events().all();
+
+        let expected =
+            registrar_client.rent_price(&default_tld(&env), &label, &MOCK_RENEW_EXTENSION);
+        assert_eq!(expected, 100);
+
+        let mut registered_price = None;
+        for (contract_id, topics, data) in events.iter() {
+            if contract_id != registrar_id {
+                continue;
+            }
+            let symbol = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+            if symbol == Symbol::new(&env, "name_registered") {
+                registered_price = Some(event_field::<i128>(&env, &data, "price"));
+            }
+        }
+        assert_eq!(registered_price, Some(expected));
+
+        let mut renewed_price = None;
+        for (contract_id, topics, data) in renew_events.iter() {
+            if contract_id != registrar_id {
+                continue;
+            }
+            let symbol = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
+            if symbol == Symbol::new(&env, "name_renewed") {
+                renewed_price = Some(event_field::<i128>(&env, &data, "price"));
+            }
+        }
+        assert_eq!(renewed_price, Some(expected));
+    }
+
+    #[test]
+    fn register_and_renew_collect_fees() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(60_000);
+
+        let caller = Address::generate(&env);
+        mint(&env, &token_id, &caller, 250);
+        let label = make_label(&env, "fee");
+        let secret = make_bytes(&env, b"fee_secret");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(60_000 + registrar_client.params().commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &caller,
+            &secret,
+            &none_resolver,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+
+        assert_eq!(token.balance(&caller), 150);
+        assert_eq!(token.balance(&registrar_id), 100);
+
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+        assert_eq!(token.balance(&caller), 50);
+        assert_eq!(token.balance(&registrar_id), 200);
+    }
+
+    #[test]
+    fn register_fails_without_funds() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        env.ledger().set_timestamp(61_000);
+
+        let caller = Address::generate(&env);
+        let label = make_label(&env, "broke");
+        let secret = make_bytes(&env, b"broke_secret");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(61_000 + registrar_client.params().commit_min_age_secs);
+        let none_resolver: Option<Address> = None;
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                &label,
+                &caller,
+                &secret,
+                &none_resolver,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            );
+        }));
+        assert!(attempt.is_err());
+        let namehash = expected_namehash(&env, &label);
+        assert!(catch_unwind(AssertUnwindSafe(|| registry_client.owner(&namehash))).is_err());
+    }
+
+    #[test]
+    fn withdraw_admin_only() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        mint(&env, &token_id, &registrar_id, 500);
+
+        let stranger = Address::generate(&env);
+        let not_admin = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.withdraw(&stranger, &100, &stranger);
+        }));
+        assert!(not_admin.is_err());
+
+        let zero = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.withdraw(&admin, &0, &admin);
+        }));
+        assert!(zero.is_err());
+
+        let treasury = Address::generate(&env);
+        registrar_client.withdraw(&admin, &300, &treasury);
+        assert_eq!(token.balance(&treasury), 300);
+        assert_eq!(token.balance(&registrar_id), 200);
+
+        let overdraw = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.withdraw(&admin, &201, &treasury);
+        }));
+        assert!(overdraw.is_err());
+    }
+
+    #[test]
+    fn register_multi_year_sets_expiry_and_price() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(70_000);
+
+        let caller = Address::generate(&env);
+        mint(&env, &token_id, &caller, 1_000);
+        let label = make_label(&env, "multi");
+        let secret = make_bytes(&env, b"multi_secret");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        let now = 70_000 + registrar_client.params().commit_min_age_secs;
+        env.ledger().set_timestamp(now);
+
+        let duration = 3 * DEFAULT_DURATION;
+        let none_resolver: Option<Address> = None;
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &caller,
+            &secret,
+            &none_resolver,
+            &duration,
+            &None,
+            &None,
+            &None,
+        );
+
+        assert_eq!(registry_client.expires(&namehash), now + duration);
+        assert_eq!(token.balance(&caller), 970);
+    }
+
+    #[test]
+    fn register_rejects_invalid_duration() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(71_000);
+
+        let caller = Address::generate(&env);
+        let label = make_label(&env, "duration");
+        let secret = make_bytes(&env, b"duration_secret");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(71_000 + params.commit_min_age_secs);
+
+        let none_resolver: Option<Address> = None;
+        for duration in [0u64, params.max_registration_secs + 1] {
+            let attempt = catch_unwind(AssertUnwindSafe(|| {
+                registrar_client.register(
+                    &caller,
+                    &default_tld(&env),
+                    &label,
+                    &caller,
+                    &secret,
+                    &none_resolver,
+                    &duration,
+                    &None,
+                    &None,
+                    &None,
+                );
+            }));
+            assert!(attempt.is_err(), "duration {} must be rejected", duration);
+        }
+        assert!(commitment_exists(&env, &registrar_id, &commitment));
+    }
+
+    #[test]
+    fn register_bumps_record_version_on_each_registration() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(20_000);
+        let first = Address::generate(&env);
+        let second = Address::generate(&env);
+        let label = make_label(&env, "recycled");
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &first,
+            &label,
+            &first,
+            &make_bytes(&env, b"one"),
+            None,
+        );
+        assert_eq!(registry_client.record_version(&namehash), 1);
+
+        let expires = registry_client.expires(&namehash);
+        env.ledger()
+            .set_timestamp(expires + registrar_client.params().grace_period_secs + 1);
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &second,
+            &label,
+            &second,
+            &make_bytes(&env, b"two"),
+            None,
+        );
+        assert_eq!(registry_client.record_version(&namehash), 2);
+    }
+
+    #[test]
+    fn expired_name_premium_decays_to_base_price() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let mut schedule = tiered_schedule(&env);
+        schedule.premium_start = 10_000;
+        schedule.premium_decay_secs = 1_000;
+        registrar_client.set_price_schedule(&admin, &schedule);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(70_000);
+
+        let first = Address::generate(&env);
+        let sniper = Address::generate(&env);
+        let label = make_label(&env, "valuable");
+        assert_eq!(
+            registrar_client.current_premium(&default_tld(&env), &label),
+            0
+        );
+        mint(&env, &token_id, &first, 10);
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &first,
+            &label,
+            &first,
+            &make_bytes(&env, b"first"),
+            None,
+        );
+        assert_eq!(token.balance(&first), 0, "fresh names carry no premium");
+
+        let released =
+            registry_client.expires(&namehash) + registrar_client.params().grace_period_secs;
+        env.ledger().set_timestamp(released);
+        assert_eq!(
+            registrar_client.current_premium(&default_tld(&env), &label),
+            0
+        );
+        env.ledger().set_timestamp(released + 250);
+        assert_eq!(
+            registrar_client.current_premium(&default_tld(&env), &label),
+            7_500
+        );
+        env.ledger().set_timestamp(released + 1_000);
+        assert_eq!(
+            registrar_client.current_premium(&default_tld(&env), &label),
+            0
+        );
+
+        // register_name advances the clock by commit_min_age_secs before revealing.
+        let commit_age = registrar_client.params().commit_min_age_secs;
+        env.ledger().set_timestamp(released + 500 - commit_age);
+        mint(&env, &token_id, &sniper, 5_010);
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &sniper,
+            &label,
+            &sniper,
+            &make_bytes(&env, b"second"),
+            None,
+        );
+        assert_eq!(token.balance(&sniper), 0, "rent plus half the premium");
+        assert_eq!(
+            registrar_client.current_premium(&default_tld(&env), &label),
+            0
+        );
+    }
+
+    #[test]
+    fn premium_requires_decay_window() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let mut schedule = tiered_schedule(&env);
+        schedule.premium_start = 1_000;
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.set_price_schedule(&admin, &schedule);
+        }));
+        assert!(attempt.is_err());
+
+        schedule.premium_start = -1;
+        schedule.premium_decay_secs = 100;
+        let negative = catch_unwind(AssertUnwindSafe(|| {
+            registrar_client.set_price_schedule(&admin, &schedule);
+        }));
+        assert!(negative.is_err());
+    }
+
+    #[test]
+    fn register_mints_name_token_when_configured() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(20_000);
+        let owner = Address::generate(&env);
+        assert!(registrar_client.name_token().is_none());
+
+        let plain = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &make_label(&env, "plain"),
+            &owner,
+            &make_bytes(&env, b"one"),
+            None,
+        );
+
+        let name_token_id = env.register(MockNameToken, ());
+        let name_token = MockNameTokenClient::new(&env, &name_token_id);
+        registrar_client.set_name_token(&admin, &name_token_id);
+        assert_eq!(registrar_client.name_token(), Some(name_token_id.clone()));
+
+        let minted = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &make_label(&env, "minted"),
+            &owner,
+            &make_bytes(&env, b"two"),
+            None,
+        );
+        assert_eq!(name_token.owner_of(&minted), owner);
+        assert!(name_token.try_owner_of(&plain).is_err());
+
+        let outsider = Address::generate(&env);
+        assert_eq!(
+            registrar_client.try_set_name_token(&outsider, &name_token_id),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+    }
+
+    #[test]
+    fn renew_for_lets_anyone_extend_without_owning() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(20_000);
+        let owner = Address::generate(&env);
+        let bot = Address::generate(&env);
+        let label = make_label(&env, "opsbot");
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &make_bytes(&env, b"bot"),
+            None,
+        );
+        let before = registry_client.expires(&namehash);
+        let extension = registrar_client.params().renew_extension_secs;
+
+        env.ledger().set_timestamp(before + 1);
+        registrar_client.renew_for(&bot, &default_tld(&env), &label);
+        assert_eq!(registry_client.expires(&namehash), before + extension);
+        assert_eq!(registry_client.owner(&namehash), owner);
+
+        let unregistered =
+            registrar_client.try_renew_for(&bot, &default_tld(&env), &make_label(&env, "nobody"));
+        assert_eq!(
+            unregistered,
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+
+        let grace = registrar_client.params().grace_period_secs;
+        env.ledger().set_timestamp(before + extension + grace + 1);
+        assert_eq!(
+            registrar_client.try_renew_for(&bot, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+    }
+
+    #[test]
+    fn cancel_commit_removes_commitment_for_committer_only() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(40_000);
+        let committer = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        let label = make_label(&env, "cancel");
+        let commitment = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"cancel"),
+            &committer,
+        );
+        registrar_client.commit(&committer, &commitment, &label.len());
+
+        assert_eq!(
+            registrar_client.try_cancel_commit(&stranger, &commitment),
+            Err(Ok(RegistrarError::NotCommitter.into()))
+        );
+        assert!(commitment_exists(&env, &registrar_id, &commitment));
+
+        registrar_client.cancel_commit(&committer, &commitment);
+        let events = env.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "commit_cancelled")
+        );
+        assert_eq!(
+            BytesN::<32>::try_from_val(&env, &topics.get(1).unwrap()).unwrap(),
+            commitment
+        );
+        assert_eq!(event_field::<Address>(&env, &data, "committer"), committer);
+        assert!(!commitment_exists(&env, &registrar_id, &commitment));
+
+        assert_eq!(
+            registrar_client.try_cancel_commit(&committer, &commitment),
+            Err(Ok(RegistrarError::CommitmentMissing.into()))
+        );
+        // The slot is free again, so the same commitment can be re-made later.
+        registrar_client.commit(&committer, &commitment, &label.len());
+    }
+
+    #[test]
+    fn sweep_commitments_removes_only_expired_entries() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let committer = Address::generate(&env);
+        let label = make_label(&env, "sweep");
+        let stale = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"old"),
+            &committer,
+        );
+        let fresh = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"new"),
+            &committer,
+        );
+        let unknown = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"nope"),
+            &committer,
+        );
+
+        env.ledger().set_timestamp(50_000);
+        registrar_client.commit(&committer, &stale, &label.len());
+        let max_age = registrar_client.params().commit_max_age_secs;
+        env.ledger().set_timestamp(50_000 + max_age);
+        registrar_client.commit(&committer, &fresh, &label.len());
+
+        // Exactly at the age limit the stale commitment is still usable.
+        let batch = vec![&env, stale.clone(), fresh.clone(), unknown];
+        assert_eq!(registrar_client.sweep_commitments(&batch), 0);
+        assert!(env.events().all().is_empty());
+
+        env.ledger().set_timestamp(50_000 + max_age + 1);
+        assert_eq!(registrar_client.sweep_commitments(&batch), 1);
+        let events = env.events().all();
+        let (_, _, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(event_field::<u32>(&env, &data, "count"), 1);
+        assert!(!commitment_exists(&env, &registrar_id, &stale));
+        assert!(commitment_exists(&env, &registrar_id, &fresh));
+    }
+
+    #[test]
+    fn writes_follow_configured_ttl_policy() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(60_000);
+        let label = make_label(&env, "ttl");
+        let reserved_ttl = |label: &Bytes| {
+            let mut key = Bytes::from_slice(&env, super::keys::RESERVED);
+            key.append(label);
+            env.as_contract(&registrar_id, || env.storage().persistent().get_ttl(&key))
+        };
+
+        registrar_client.reserve_labels(&admin, &default_tld(&env), &vec![&env, label.clone()]);
+        assert_eq!(reserved_ttl(&label), 30 * DAY_IN_LEDGERS);
+
+        let mut params = registrar_client.params();
+        params.ttl_threshold_ledgers = DAY_IN_LEDGERS;
+        params.ttl_extend_to_ledgers = 2 * DAY_IN_LEDGERS;
+        registrar_client.set_params(&admin, &params);
+        let other = make_label(&env, "ttl2");
+        registrar_client.reserve_labels(&admin, &default_tld(&env), &vec![&env, other.clone()]);
+        assert_eq!(reserved_ttl(&other), 2 * DAY_IN_LEDGERS);
+
+        params.ttl_threshold_ledgers = 3 * DAY_IN_LEDGERS;
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+        params.ttl_threshold_ledgers = 0;
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+    }
+
+    #[test]
+    fn admin_and_commitment_lifecycle_events() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let registry_id = env.register(MockRegistry, ());
+        let registrar_id = env.register(Registrar, ());
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let admin = Address::generate(&env);
+        let token = create_token(&env, &admin);
+        let tld = Bytes::from_slice(&env, b"stellar");
+        registrar_client.init(&registry_id, &tld, &admin, &token);
+        let events = env.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "initialized")
+        );
+        assert_eq!(event_field::<Address>(&env, &data, "admin"), admin);
+        assert_eq!(event_field::<Bytes>(&env, &data, "tld"), tld);
+
+        let mut params = registrar_client.params();
+        params.commit_min_age_secs = 20;
+        registrar_client.set_params(&admin, &params);
+        let events = env.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "params_changed")
+        );
+        let stored = event_field::<RegistrarParams>(&env, &data, "params");
+        assert_eq!(stored.commit_min_age_secs, 20);
+
+        env.ledger().set_timestamp(70_000);
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "consumed");
+        let secret = make_bytes(&env, b"consumed");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &owner);
+        registrar_client.commit(&owner, &commitment, &label.len());
+        env.ledger().set_timestamp(70_000 + 20);
+        let namehash = registrar_client.register(
+            &owner,
+            &default_tld(&env),
+            &label,
+            &owner,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        let events = env.events().all();
+        let consumed = events
+            .iter()
+            .find(|(contract, topics, _)| {
+                *contract == registrar_id
+                    && Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap()
+                        == Symbol::new(&env, "commitment_consumed")
+            })
+            .expect("expected commitment_consumed event");
+        assert_eq!(
+            BytesN::<32>::try_from_val(&env, &consumed.1.get(1).unwrap()).unwrap(),
+            commitment
+        );
+        assert_eq!(
+            event_field::<BytesN<32>>(&env, &consumed.2, "namehash"),
+            namehash
+        );
+        assert_eq!(registry_client.owner(&namehash), owner);
+    }
+
+    #[test]
+    fn referrer_accrues_and_claims_share_of_fee() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let mut params = registrar_client.params();
+        params.referral_fee_bps = 2_500;
+        registrar_client.set_params(&admin, &params);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(80_000);
+
+        let caller = Address::generate(&env);
+        let wallet = Address::generate(&env);
+        mint(&env, &token_id, &caller, 100);
+        let label = make_label(&env, "ref");
+        let secret = make_bytes(&env, b"refd");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(80_000 + registrar_client.params().commit_min_age_secs);
+        registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &caller,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &Some(wallet.clone()),
+            &None,
+            &None,
+        );
+        assert_eq!(token.balance(&registrar_id), 100);
+        assert_eq!(registrar_client.referral_balance(&wallet), 25);
+
+        // The referrer's share is reserved from admin withdrawals.
+        let treasury = Address::generate(&env);
+        assert_eq!(
+            registrar_client.try_withdraw(&admin, &76, &treasury),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+        registrar_client.withdraw(&admin, &75, &treasury);
+
+        assert_eq!(registrar_client.claim_referral_fees(&wallet), 25);
+        assert_eq!(token.balance(&wallet), 25);
+        assert_eq!(registrar_client.referral_balance(&wallet), 0);
+        assert_eq!(token.balance(&registrar_id), 0);
+        assert_eq!(
+            registrar_client.try_claim_referral_fees(&wallet),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+
+        params.referral_fee_bps = MAX_BPS + 1;
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+    }
+
+    #[test]
+    fn admin_handover_requires_acceptance() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let typo = Address::generate(&env);
+        let successor = Address::generate(&env);
+        assert_eq!(
+            registrar_client.try_transfer_admin(&typo, &successor),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+
+        // A mistyped nominee can be replaced before anyone accepts.
+        registrar_client.transfer_admin(&admin, &typo);
+        registrar_client.transfer_admin(&admin, &successor);
+        assert_eq!(registrar_client.pending_admin(), Some(successor.clone()));
+        assert_eq!(
+            registrar_client.try_accept_admin(&typo),
+            Err(Ok(RegistrarError::NotPendingAdmin.into()))
+        );
+        assert_eq!(registrar_client.admin(), admin);
+
+        registrar_client.accept_admin(&successor);
+        let events = env.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "admin_changed")
+        );
+        assert_eq!(
+            Address::try_from_val(&env, &topics.get(1).unwrap()).unwrap(),
+            admin
+        );
+        assert_eq!(
+            Address::try_from_val(&env, &topics.get(2).unwrap()).unwrap(),
+            successor
+        );
+        assert_eq!(registrar_client.admin(), successor);
+        assert_eq!(registrar_client.pending_admin(), None);
+
+        let params = registrar_client.params();
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        registrar_client.set_params(&successor, &params);
+        assert_eq!(
+            registrar_client.try_accept_admin(&successor),
+            Err(Ok(RegistrarError::NotPendingAdmin.into()))
+        );
+    }
+
+    #[test]
+    fn pause_blocks_mutations_until_unpaused() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(90_000);
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "halted");
+        let secret = make_bytes(&env, b"halted");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &owner);
+        registrar_client.commit(&owner, &commitment, &label.len());
+
+        assert_eq!(
+            registrar_client.try_pause(&owner),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        registrar_client.pause(&admin);
+        assert!(registrar_client.paused());
+        env.ledger()
+            .set_timestamp(90_000 + registrar_client.params().commit_min_age_secs);
+        assert_eq!(
+            registrar_client.try_register(
+                &owner,
+                &default_tld(&env),
+                &label,
+                &owner,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::Paused.into()))
+        );
+        let other = make_commitment(&env, &make_label(&env, "other"), &owner, &secret, &owner);
+        assert_eq!(
+            registrar_client.try_commit(&owner, &other, &5),
+            Err(Ok(RegistrarError::Paused.into()))
+        );
+        assert_eq!(
+            registrar_client.try_renew(&owner, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::Paused.into()))
+        );
+        // Reads stay available during an incident.
+        assert!(registrar_client.available(&default_tld(&env), &label));
+
+        registrar_client.unpause(&admin);
+        assert!(!registrar_client.paused());
+        registrar_client.register(
+            &owner,
+            &default_tld(&env),
+            &label,
+            &owner,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        registrar_client.renew(&owner, &default_tld(&env), &label);
+    }
+
+    #[test]
+    fn upgrade_and_migrate_require_admin() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let outsider = Address::generate(&env);
+        let hash = BytesN::from_array(&env, &[7u8; 32]);
+        assert_eq!(
+            registrar_client.try_upgrade(&outsider, &hash),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        assert_eq!(
+            registrar_client.try_migrate(&outsider),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        assert_eq!(registrar_client.storage_version(), STORAGE_VERSION);
+        assert_eq!(registrar_client.migrate(&admin), STORAGE_VERSION);
+
+        // Storage from before versioning reads as 0 and is brought up to date.
+        env.as_contract(&registrar_id, || {
+            env.storage()
+                .persistent()
+                .remove(&singleton_key(&env, keys::STORAGE_VERSION));
+        });
+        assert_eq!(registrar_client.storage_version(), 0);
+        assert_eq!(registrar_client.migrate(&admin), STORAGE_VERSION);
+        let data = env.events().all().last().unwrap().2;
+        assert_eq!(event_field::<u32>(&env, &data, "from"), 0);
+        assert_eq!(registrar_client.storage_version(), STORAGE_VERSION);
+
+        env.as_contract(&registrar_id, || {
+            env.storage().persistent().set(
+                &singleton_key(&env, keys::STORAGE_VERSION),
+                &(STORAGE_VERSION + 1),
+            );
+        });
+        assert_eq!(
+            registrar_client.try_migrate(&admin),
+            Err(Ok(RegistrarError::UnsupportedVersion.into()))
+        );
+    }
+
+    #[test]
+    fn register_rejects_incompatible_resolver() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(1_000);
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "bogus");
+        let secret = make_bytes(&env, b"bogus");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &owner);
+        registrar_client.commit(&owner, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(1_000 + registrar_client.params().commit_min_age_secs);
+
+        // An account, and a contract with no `supports` entrypoint.
+        for resolver in [Address::generate(&env), registry_id] {
+            assert_eq!(
+                registrar_client.try_register(
+                    &owner,
+                    &default_tld(&env),
+                    &label,
+                    &owner,
+                    &secret,
+                    &Some(resolver),
+                    &DEFAULT_DURATION,
+                    &None,
+                    &None,
+                    &None
+                ),
+                Err(Ok(RegistrarError::IncompatibleResolver.into()))
+            );
+        }
+        assert!(commitment_exists(&env, &registrar_id, &commitment));
+    }
+
+    #[test]
+    fn keepers_auto_renew_from_escrow_near_expiry() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(5_000);
+
+        let owner = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let label = make_label(&env, "vault");
+        let secret = make_bytes(&env, b"vault");
+        mint(&env, &token_id, &owner, 1_000);
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        let expires = registry_client.expires(&namehash);
+
+        assert_eq!(
+            registrar_client.try_auto_renew(&keeper, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::AutoRenewDisabled.into()))
+        );
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            registrar_client.try_set_auto_renew(&stranger, &default_tld(&env), &label, &true),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+        registrar_client.set_auto_renew(&owner, &default_tld(&env), &label, &true);
+        assert_eq!(
+            registrar_client.auto_renew_sponsor(&default_tld(&env), &label),
+            Some(owner.clone())
+        );
+        registrar_client.deposit_renewal_funds(&owner, &15);
+        assert_eq!(registrar_client.renewal_funds(&owner), 15);
+
+        // Escrow is reserved from admin withdrawals.
+        let fees = token.balance(&registrar_id) - 15;
+        assert_eq!(
+            registrar_client.try_withdraw(&admin, &(fees + 1), &admin),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+
+        assert_eq!(
+            registrar_client.try_auto_renew(&keeper, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::RenewalNotDue.into()))
+        );
+        let window = registrar_client.params().auto_renew_window_secs;
+        env.ledger().set_timestamp(expires - window);
+        // A 5-byte label costs 10 a year; the keeper earns 1% of that, rounded down.
+        let renewed = registrar_client.auto_renew(&keeper, &default_tld(&env), &label);
+        assert_eq!(
+            renewed,
+            expires + registrar_client.params().renew_extension_secs
+        );
+        assert_eq!(registry_client.expires(&namehash), renewed);
+        assert_eq!(registrar_client.renewal_funds(&owner), 5);
+        assert_eq!(token.balance(&keeper), 0);
+
+        env.ledger().set_timestamp(renewed - window);
+        assert_eq!(
+            registrar_client.try_auto_renew(&keeper, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::InsufficientEscrow.into()))
+        );
+        registrar_client.withdraw_renewal_funds(&owner, &5);
+        assert_eq!(registrar_client.renewal_funds(&owner), 0);
+        assert_eq!(token.balance(&owner), 1_000 - 10 - 10);
+
+        registrar_client.set_auto_renew(&owner, &default_tld(&env), &label, &false);
+        assert_eq!(
+            registrar_client.auto_renew_sponsor(&default_tld(&env), &label),
+            None
+        );
+    }
+
+    #[test]
+    fn keeper_tip_comes_out_of_escrow() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let mut params = registrar_client.params();
+        params.keeper_tip_bps = 1_000;
+        registrar_client.set_params(&admin, &params);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(5_000);
+
+        let owner = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        let label = make_label(&env, "ab");
+        mint(&env, &token_id, &owner, 2_000);
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &make_bytes(&env, b"ab"),
+            None,
+        );
+        registrar_client.set_auto_renew(&owner, &default_tld(&env), &label, &true);
+        registrar_client.deposit_renewal_funds(&owner, &600);
+        env.ledger()
+            .set_timestamp(registry_client.expires(&namehash));
+
+        registrar_client.auto_renew(&keeper, &default_tld(&env), &label);
+        assert_eq!(token.balance(&keeper), 50);
+        assert_eq!(registrar_client.renewal_funds(&owner), 50);
+
+        // A transferred name stops drawing on the old owner's escrow.
+        let buyer = Address::generate(&env);
+        registry_client.transfer(&owner, &namehash, &buyer);
+        assert_eq!(
+            registrar_client.try_auto_renew(&keeper, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::AutoRenewDisabled.into()))
+        );
+    }
+
+    fn batch_request(label: &Bytes, owner: &Address, secret: &Bytes) -> RegistrationRequest {
+        RegistrationRequest {
+            tld: default_tld(label.env()),
+            label: label.clone(),
+            owner: owner.clone(),
+            secret: secret.clone(),
+            resolver: None,
+            duration_secs: DEFAULT_DURATION,
+            referrer: None,
+            voucher: None,
+            sponsor: None,
+        }
+    }
+
+    #[test]
+    fn register_many_and_renew_many_process_whole_batches() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(90_000);
+
+        let caller = Address::generate(&env);
+        let secret = make_bytes(&env, b"bulk");
+        let mut requests = Vec::new(&env);
+        let mut labels = Vec::new(&env);
+        for text in ["alpha", "bravo", "charlie"] {
+            let label = make_label(&env, text);
+            let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+            registrar_client.commit(&caller, &commitment, &label.len());
+            requests.push_back(batch_request(&label, &caller, &secret));
+            labels.push_back(label);
+        }
+        env.ledger()
+            .set_timestamp(90_000 + registrar_client.params().commit_min_age_secs);
+
+        // The aggregate fee is checked before any name is registered.
+        mint(&env, &token_id, &caller, 29);
+        assert_eq!(
+            registrar_client.try_register_many(&caller, &requests),
+            Err(Ok(RegistrarError::InsufficientFunds.into()))
+        );
+        mint(&env, &token_id, &caller, 1);
+        let namehashes = registrar_client.register_many(&caller, &requests);
+        assert_eq!(namehashes.len(), 3);
+        for (label, namehash) in labels.iter().zip(namehashes.iter()) {
+            assert_eq!(namehash, expected_namehash(&env, &label));
+            assert_eq!(registry_client.owner(&namehash), caller);
+        }
+        assert_eq!(token.balance(&registrar_id), 30);
+
+        let before = registry_client.expires(&namehashes.get(0).unwrap());
+        let renewal = registrar_client.rent_price(
+            &default_tld(&env),
+            &labels.get(0).unwrap(),
+            &registrar_client.params().renew_extension_secs,
+        );
+        mint(&env, &token_id, &caller, renewal * 3);
+        registrar_client.renew_many(&caller, &default_tld(&env), &labels);
+        assert!(registry_client.expires(&namehashes.get(0).unwrap()) > before);
+        assert_eq!(token.balance(&caller), 0);
+
+        assert_eq!(
+            registrar_client.try_renew_many(&caller, &default_tld(&env), &Vec::new(&env)),
+            Err(Ok(RegistrarError::InvalidBatchSize.into()))
+        );
+    }
+
+    #[test]
+    fn register_many_reverts_every_name_when_one_fails() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(95_000);
+
+        let caller = Address::generate(&env);
+        let secret = make_bytes(&env, b"atomic");
+        let committed = make_label(&env, "committed");
+        let commitment = make_commitment(&env, &committed, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &committed.len());
+        env.ledger()
+            .set_timestamp(95_000 + registrar_client.params().commit_min_age_secs);
+
+        let uncommitted = make_label(&env, "uncommitted");
+        let requests = soroban_sdk::vec![
+            &env,
+            batch_request(&committed, &caller, &secret),
+            batch_request(&uncommitted, &caller, &secret),
+        ];
+        assert_eq!(
+            registrar_client.try_register_many(&caller, &requests),
+            Err(Ok(RegistrarError::CommitmentMissing.into()))
+        );
+        assert!(registrar_client.available(&default_tld(&env), &committed));
+
+        // The first name's commitment survived the revert and still registers on its own.
+        let single = soroban_sdk::vec![&env, batch_request(&committed, &caller, &secret)];
+        registrar_client.register_many(&caller, &single);
+        assert!(!registrar_client.available(&default_tld(&env), &committed));
+    }
+
+    #[test]
+    fn commit_many_records_all_commitments_in_one_event() {
+        let (env, _registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(97_000);
+        let caller = Address::generate(&env);
+        let secret = make_bytes(&env, b"many");
+        let first = make_label(&env, "first");
+        let second = make_label(&env, "second");
+        let first_commitment = make_commitment(&env, &first, &caller, &secret, &caller);
+        let second_commitment = make_commitment(&env, &second, &caller, &secret, &caller);
+
+        // A commitment repeated inside the batch reverts the whole call.
+        let repeated = soroban_sdk::vec![
+            &env,
+            (first_commitment.clone(), first.len()),
+            (first_commitment.clone(), first.len()),
+        ];
+        assert_eq!(
+            registrar_client.try_commit_many(&caller, &repeated),
+            Err(Ok(RegistrarError::CommitmentExists.into()))
+        );
+
+        let batch = soroban_sdk::vec![
+            &env,
+            (first_commitment.clone(), first.len()),
+            (second_commitment.clone(), second.len()),
+        ];
+        registrar_client.commit_many(&caller, &batch);
+        let events = env.events().all();
+        assert_eq!(events.len(), 1);
+        let (_, topics, data) = events.get(0).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "commit_made")
+        );
+        let hashes = event_field::<Vec<BytesN<32>>>(&env, &data, "commitments");
+        assert_eq!(
+            hashes,
+            soroban_sdk::vec![&env, first_commitment.clone(), second_commitment]
+        );
+
+        // Already-stored commitments are rejected on a second batch.
+        let again = soroban_sdk::vec![&env, (first_commitment, first.len())];
+        assert_eq!(
+            registrar_client.try_commit_many(&caller, &again),
+            Err(Ok(RegistrarError::CommitmentExists.into()))
+        );
+
+        env.ledger()
+            .set_timestamp(97_000 + registrar_client.params().commit_min_age_secs);
+        let requests = soroban_sdk::vec![
+            &env,
+            batch_request(&first, &caller, &secret),
+            batch_request(&second, &caller, &secret),
+        ];
+        registrar_client.register_many(&caller, &requests);
+        assert!(!registrar_client.available(&default_tld(&env), &second));
+    }
+
+    #[test]
+    fn reserved_labels_block_registration_until_allocated_or_released() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(99_000);
+        let brand = make_label(&env, "brand");
+        let protocol = make_label(&env, "protocol");
+        let outsider = Address::generate(&env);
+        let labels = soroban_sdk::vec![&env, brand.clone(), protocol.clone()];
+        assert_eq!(
+            registrar_client.try_reserve_labels(&outsider, &default_tld(&env), &labels),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        registrar_client.reserve_labels(&admin, &default_tld(&env), &labels);
+        assert!(registrar_client.is_reserved(&default_tld(&env), &brand));
+        assert!(!registrar_client.available(&default_tld(&env), &brand));
+
+        // The commit–reveal path refuses reserved labels even with a valid commitment.
+        let secret = make_bytes(&env, b"squat");
+        let commitment = make_commitment(&env, &brand, &outsider, &secret, &outsider);
+        registrar_client.commit(&outsider, &commitment, &brand.len());
+        env.ledger()
+            .set_timestamp(99_000 + registrar_client.params().commit_min_age_secs);
+        assert_eq!(
+            registrar_client.try_register(
+                &outsider,
+                &default_tld(&env),
+                &brand,
+                &outsider,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::NameReserved.into()))
+        );
+
+        let holder = Address::generate(&env);
+        let namehash = registrar_client.allocate_reserved(
+            &admin,
+            &default_tld(&env),
+            &brand,
+            &holder,
+            &None,
+            &DEFAULT_DURATION,
+        );
+        assert_eq!(registry_client.owner(&namehash), holder);
+        assert_eq!(
+            registry_client.expires(&namehash),
+            env.ledger().timestamp() + DEFAULT_DURATION
+        );
+        assert_eq!(
+            registrar_client.try_allocate_reserved(
+                &admin,
+                &default_tld(&env),
+                &brand,
+                &outsider,
+                &None,
+                &DEFAULT_DURATION
+            ),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+
+        let open = make_label(&env, "open");
+        assert_eq!(
+            registrar_client.try_allocate_reserved(
+                &admin,
+                &default_tld(&env),
+                &open,
+                &holder,
+                &None,
+                &DEFAULT_DURATION
+            ),
+            Err(Ok(RegistrarError::NotReserved.into()))
+        );
+        registrar_client.release_label(&admin, &default_tld(&env), &protocol);
+        assert!(!registrar_client.is_reserved(&default_tld(&env), &protocol));
+        assert!(registrar_client.available(&default_tld(&env), &protocol));
+        assert_eq!(
+            registrar_client.try_release_label(&admin, &default_tld(&env), &protocol),
+            Err(Ok(RegistrarError::NotReserved.into()))
+        );
+    }
+
+    #[test]
+    fn normalize_lowercases_and_rejects_homographs() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let invalid = Err(Ok(RegistrarError::InvalidLabel.into()));
+        assert_eq!(
+            registrar_client.normalize(&make_label(&env, "Alice")),
+            make_label(&env, "alice")
+        );
+        assert_eq!(
+            registrar_client.try_normalize(&make_label(&env, "ab--cd")),
+            invalid
+        );
+        // Internationalized labels are off by default, in either form.
+        assert_eq!(
+            registrar_client.try_normalize(&make_label(&env, "привет")),
+            invalid
+        );
+        assert_eq!(
+            registrar_client.try_normalize(&make_label(&env, "xn--b1agh1afp")),
+            invalid
+        );
+
+        let mut params = registrar_client.params();
+        params.allow_idn = true;
+        registrar_client.set_params(&admin, &params);
+        for (raw, canonical) in [
+            ("Привет", "xn--b1agh1afp"),
+            ("xn--b1agh1afp", "xn--b1agh1afp"),
+            ("CAFÉ", "xn--caf-dma"),
+            ("XN--CAF-DMA", "xn--caf-dma"),
+            ("ελλάδα", "xn--hxakic4aa"),
+        ] {
+            assert_eq!(
+                registrar_client.normalize(&make_label(&env, raw)),
+                make_label(&env, canonical)
+            );
+        }
+        for rejected in [
+            // Cyrillic `а` followed by Latin `lice`.
+            "\u{430}lice",
+            // Cyrillic `аре`, which renders like `ape`, raw and encoded.
+            "\u{430}\u{440}\u{435}",
+            "xn--80ak6a",
+            // ASCII-only content behind an ACE prefix, and a truncated encoding.
+            "xn--abc",
+            "xn--caf-dma9",
+            "caf\u{e9}\u{1f600}",
+        ] {
+            assert_eq!(
+                registrar_client.try_normalize(&make_label(&env, rejected)),
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn register_requires_canonical_labels() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let mut params = registrar_client.params();
+        params.allow_idn = true;
+        registrar_client.set_params(&admin, &params);
+        env.ledger().set_timestamp(101_000);
+        let caller = Address::generate(&env);
+        let secret = make_bytes(&env, b"idn");
+
+        let raw = make_label(&env, "привет");
+        let raw_commitment = make_commitment(&env, &raw, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &raw_commitment, &raw.len());
+        let canonical = registrar_client.normalize(&raw);
+        let commitment = make_commitment(&env, &canonical, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &canonical.len());
+        env.ledger()
+            .set_timestamp(101_000 + registrar_client.params().commit_min_age_secs);
+
+        assert_eq!(
+            registrar_client.try_register(
+                &caller,
+                &default_tld(&env),
+                &raw,
+                &caller,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::InvalidLabel.into()))
+        );
+        let namehash = registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &canonical,
+            &caller,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        assert_eq!(namehash, expected_namehash(&env, &canonical));
+        assert_eq!(registry_client.owner(&namehash), caller);
+    }
+
+    #[test]
+    fn length_policies_gate_and_reprice_short_names() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        env.ledger().set_timestamp(103_000);
+        let closed = LengthPolicy {
+            enabled: false,
+            price_multiplier: 1,
+        };
+        let premium = LengthPolicy {
+            enabled: true,
+            price_multiplier: 5,
+        };
+        registrar_client.set_length_policy(&admin, &default_tld(&env), &3, &closed);
+        registrar_client.set_length_policy(&admin, &default_tld(&env), &4, &premium);
+        assert_eq!(
+            registrar_client.length_policy(&default_tld(&env), &4),
+            premium
+        );
+        assert_eq!(
+            registrar_client.try_set_length_policy(
+                &admin,
+                &default_tld(&env),
+                &5,
+                &LengthPolicy {
+                    enabled: true,
+                    price_multiplier: 0
+                }
+            ),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+
+        let short = make_label(&env, "abc");
+        let four = make_label(&env, "abcd");
+        assert!(!registrar_client.available(&default_tld(&env), &short));
+        assert!(registrar_client.available(&default_tld(&env), &four));
+        assert_eq!(
+            registrar_client.rent_price(&default_tld(&env), &four, &DEFAULT_DURATION),
+            50
+        );
+        assert_eq!(
+            registrar_client.rent_price(
+                &default_tld(&env),
+                &make_label(&env, "abcde"),
+                &DEFAULT_DURATION
+            ),
+            10
+        );
+
+        let caller = Address::generate(&env);
+        mint(&env, &token_id, &caller, 50);
+        let secret = make_bytes(&env, b"short");
+        for label in [&short, &four] {
+            let commitment = make_commitment(&env, label, &caller, &secret, &caller);
+            registrar_client.commit(&caller, &commitment, &label.len());
+        }
+        env.ledger()
+            .set_timestamp(103_000 + registrar_client.params().commit_min_age_secs);
+        assert_eq!(
+            registrar_client.try_register(
+                &caller,
+                &default_tld(&env),
+                &short,
+                &caller,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::LengthDisabled.into()))
+        );
+        registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &four,
+            &caller,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        assert_eq!(TokenClient::new(&env, &token_id).balance(&caller), 0);
+
+        registrar_client.set_length_policy(
+            &admin,
+            &default_tld(&env),
+            &3,
+            &pricing::default_length_policy(),
+        );
+        assert!(registrar_client.available(&default_tld(&env), &short));
+    }
+
+    #[test]
+    fn auction_house_holds_and_allocates_labels() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(105_000);
+        let house = Address::generate(&env);
+        let impostor = Address::generate(&env);
+        let contested = make_label(&env, "contested");
+        let quiet = make_label(&env, "quiet");
+        assert_eq!(
+            registrar_client.try_hold_for_auction(&house, &default_tld(&env), &contested),
+            Err(Ok(RegistrarError::NotAuctionHouse.into()))
+        );
+        registrar_client.set_auction_house(&admin, &Some(house.clone()));
+        assert_eq!(registrar_client.auction_house(), Some(house.clone()));
+        assert_eq!(
+            registrar_client.try_hold_for_auction(&impostor, &default_tld(&env), &contested),
+            Err(Ok(RegistrarError::NotAuctionHouse.into()))
+        );
+        registrar_client.hold_for_auction(&house, &default_tld(&env), &contested);
+        registrar_client.hold_for_auction(&house, &default_tld(&env), &quiet);
+        assert!(!registrar_client.available(&default_tld(&env), &contested));
+        assert_eq!(
+            registrar_client.try_hold_for_auction(&house, &default_tld(&env), &contested),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+
+        let bidder = Address::generate(&env);
+        let secret = make_bytes(&env, b"snipe");
+        let commitment = make_commitment(&env, &contested, &bidder, &secret, &bidder);
+        registrar_client.commit(&bidder, &commitment, &contested.len());
+        env.ledger()
+            .set_timestamp(105_000 + registrar_client.params().commit_min_age_secs);
+        assert_eq!(
+            registrar_client.try_register(
+                &bidder,
+                &default_tld(&env),
+                &contested,
+                &bidder,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::NameInAuction.into()))
+        );
+
+        let namehash = registrar_client.allocate_auctioned(
+            &house,
+            &default_tld(&env),
+            &contested,
+            &bidder,
+            &DEFAULT_DURATION,
+            &7,
+        );
+        assert_eq!(registry_client.owner(&namehash), bidder);
+        assert_eq!(
+            registrar_client.try_allocate_auctioned(
+                &house,
+                &default_tld(&env),
+                &contested,
+                &bidder,
+                &DEFAULT_DURATION,
+                &7
+            ),
+            Err(Ok(RegistrarError::NotInAuction.into()))
+        );
+
+        registrar_client.release_auction_hold(&house, &default_tld(&env), &quiet);
+        assert!(registrar_client.available(&default_tld(&env), &quiet));
+    }
+
+    #[test]
+    fn add_tld_issues_names_under_each_tld_with_its_own_pricing() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let token_id = registrar_client.payment_token();
+        let stellar = default_tld(&env);
+        let xlm = make_label(&env, "xlm");
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+
+        let cheap = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env, 2i128],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        };
+        let params = registrar_client.params();
+        let mut strict = params.clone();
+        strict.min_label_len = 3;
+        registrar_client.set_params(&admin, &strict);
+        let label = make_label(&env, "alice");
+        assert!(!registrar_client.available(&xlm, &label));
+        assert_eq!(
+            registrar_client.try_rent_price(&xlm, &label, &DEFAULT_DURATION),
+            Err(Ok(RegistrarError::UnknownTld.into()))
+        );
+        registrar_client.add_tld(&admin, &xlm, &params, &cheap);
+        let events = env.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "tld_configured")
+        );
+        assert_eq!(
+            registrar_client.try_add_tld(&admin, &stellar, &params, &cheap),
+            Err(Ok(RegistrarError::TldExists.into()))
+        );
+        assert_eq!(
+            registrar_client.try_set_tld_config(&admin, &stellar, &params, &cheap),
+            Err(Ok(RegistrarError::UnknownTld.into()))
+        );
+        assert_eq!(
+            registrar_client.tlds(),
+            soroban_sdk::vec![&env, stellar.clone(), xlm.clone()]
+        );
+        assert_eq!(registrar_client.tld_params(&xlm).min_label_len, 1);
+        assert_eq!(
+            registrar_client.rent_price(&stellar, &label, &DEFAULT_DURATION),
+            10
+        );
+        assert_eq!(
+            registrar_client.rent_price(&xlm, &label, &DEFAULT_DURATION),
+            2
+        );
+
+        // One commitment per registration; the same label is independent under each TLD.
+        let owner = Address::generate(&env);
+        mint(&env, &token_id, &owner, 12);
+        let secrets = [make_bytes(&env, b"one"), make_bytes(&env, b"two")];
+        for secret in secrets.iter() {
+            let commitment = make_commitment(&env, &label, &owner, secret, &owner);
+            registrar_client.commit(&owner, &commitment, &label.len());
+        }
+        env.ledger()
+            .set_timestamp(registrar_client.params().commit_min_age_secs);
+        let in_stellar = registrar_client.register(
+            &owner,
+            &stellar,
+            &label,
+            &owner,
+            &secrets[0],
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        assert!(registrar_client.available(&xlm, &label));
+        let in_xlm = registrar_client.register(
+            &owner,
+            &xlm,
+            &label,
+            &owner,
+            &secrets[1],
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        assert_eq!(in_stellar, expected_namehash(&env, &label));
+        let xlm_node = name_service_common::fold_hash(
+            &env,
+            &BytesN::from_array(&env, &[0u8; 32]),
+            &env.crypto().sha256(&xlm).to_bytes(),
+        );
+        let label_hash = env.crypto().sha256(&label).to_bytes();
+        assert_eq!(
+            in_xlm,
+            name_service_common::fold_hash(&env, &xlm_node, &label_hash)
+        );
+        assert_eq!(registry_client.owner(&in_xlm), owner);
+        assert_eq!(TokenClient::new(&env, &token_id).balance(&owner), 0);
+        assert!(!registrar_client.available(&xlm, &label));
+
+        // Short labels only fit the new TLD's bounds, yet commitments for them are accepted.
+        let short = make_label(&env, "x");
+        let commitment = make_commitment(&env, &short, &owner, &secrets[0], &owner);
+        registrar_client.commit(&owner, &commitment, &short.len());
+        assert!(!registrar_client.available(&stellar, &short));
+        assert!(registrar_client.available(&xlm, &short));
+
+        mint(&env, &token_id, &owner, 2);
+        registrar_client.renew(&owner, &xlm, &label);
+        assert_eq!(TokenClient::new(&env, &token_id).balance(&owner), 0);
+        let unknown = make_label(&env, "nope");
+        assert_eq!(
+            registrar_client.try_renew(&owner, &unknown, &label),
+            Err(Ok(RegistrarError::UnknownTld.into()))
+        );
+    }
+
+    #[test]
+    fn release_frees_name_and_refunds_unused_term() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let token = registrar_client.payment_token();
+        let tld = default_tld(&env);
+        env.ledger().set_timestamp(10_000);
+        let schedule = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env, 10_000i128],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        };
+        registrar_client.set_price_schedule(&admin, &schedule);
+        let mut params = registrar_client.params();
+        params.release_refund_bps = 5_000;
+        registrar_client.set_params(&admin, &params);
+
+        let owner = Address::generate(&env);
+        mint(&env, &token, &owner, 10_000);
+        let label = make_label(&env, "leaving");
+        let secret = make_bytes(&env, b"leaving");
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        assert!(!registrar_client.available(&tld, &label));
+
+        let stranger = Address::generate(&env);
+        assert_eq!(
+            registrar_client.try_release(&stranger, &tld, &label),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+
+        // Half the term is left: half the rent, refunded at 50%.
+        let expires = registry_client.expires(&namehash);
+        env.ledger().set_timestamp(expires - DEFAULT_DURATION / 2);
+        assert_eq!(registrar_client.release(&owner, &tld, &label), 2_500);
+        let (_, topics, data) = env.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&env, "name_released")
+        );
+        assert_eq!(event_field::<i128>(&env, &data, "refund"), 2_500);
+        assert_eq!(TokenClient::new(&env, &token).balance(&owner), 2_500);
+        assert!(registrar_client.available(&tld, &label));
+        assert_eq!(
+            registrar_client.try_release(&owner, &tld, &label),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+
+        // A term the owner did not pay for refunds nothing, even with fees on hand.
+        let sponsor = Address::generate(&env);
+        mint(&env, &token, &sponsor, 10_000);
+        registrar_client.sponsor_allowance(&sponsor, &owner, &10_000);
+        let gifted = make_label(&env, "gifted");
+        let secret = make_bytes(&env, b"gifted");
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &sponsor,
+            &gifted,
+            &owner,
+            &secret,
+            None,
+        );
+        assert_eq!(registrar_client.release(&owner, &tld, &gifted), 0);
+        assert_eq!(TokenClient::new(&env, &token).balance(&owner), 2_500);
+    }
+
+    #[test]
+    fn name_info_aggregates_registry_and_pricing_state() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let tld = default_tld(&env);
+        env.ledger().set_timestamp(10_000);
+        let schedule = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env, 0i128],
+            premium_start: 500,
+            premium_decay_secs: 1_000,
+        };
+        registrar_client.set_price_schedule(&admin, &schedule);
+
+        let label = make_label(&env, "infoname");
+        let info = registrar_client.name_info(&tld, &label);
+        assert_eq!(info.namehash, compute_namehash(&env, &tld, &label));
+        assert_eq!(info.owner, None);
+        assert_eq!(info.expires_at, None);
+        assert!(info.available && !info.in_grace);
+        assert_eq!(info.price, 0);
+
+        let owner = Address::generate(&env);
+        let secret = make_bytes(&env, b"infoname");
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        let resolver = Address::generate(&env);
+        registry_client.set_resolver(&owner, &namehash, &resolver);
+        let expires = registry_client.expires(&namehash);
+        let info = registrar_client.name_info(&tld, &label);
+        assert_eq!(info.owner, Some(owner.clone()));
+        assert_eq!(info.resolver, Some(resolver));
+        assert_eq!(info.expires_at, Some(expires));
+        assert!(!info.available && !info.in_grace);
+
+        env.ledger().set_timestamp(expires + 1);
+        let info = registrar_client.name_info(&tld, &label);
+        assert!(!info.available && info.in_grace);
+
+        let grace = registrar_client.params().grace_period_secs;
+        env.ledger().set_timestamp(expires + grace + 500);
+        let info = registrar_client.name_info(&tld, &label);
+        assert!(info.available && !info.in_grace);
+        assert_eq!(info.owner, Some(owner));
+        assert_eq!(info.premium, 250);
+    }
+
+    #[test]
+    fn commitment_binds_committer_and_postdates_release() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let tld = default_tld(&env);
+        let params = registrar_client.params();
+        env.ledger().set_timestamp(10_000);
+
+        // A griefer replaying the revealed preimage from its own account finds no commitment.
+        let owner = Address::generate(&env);
+        let griefer = Address::generate(&env);
+        let label = make_label(&env, "bound");
+        let secret = make_bytes(&env, b"bound");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &owner);
+        registrar_client.commit(&owner, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(10_000 + params.commit_min_age_secs);
+        let register = |caller: &Address| {
+            registrar_client.try_register(
+                caller,
+                &tld,
+                &label,
+                &owner,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            )
+        };
+        assert_eq!(
+            register(&griefer).map(|_| ()),
+            Err(Ok(RegistrarError::CommitmentMissing.into()))
+        );
+        let namehash = register(&owner).unwrap().unwrap();
+
+        // Commitments made before the name's release cannot claim it afterwards.
+        let expires = registry_client.expires(&namehash);
+        let released_at = expires + params.grace_period_secs;
+        let sniper = Address::generate(&env);
+        let early = make_commitment(&env, &label, &sniper, &secret, &sniper);
+        env.ledger().set_timestamp(released_at);
+        registrar_client.commit(&sniper, &early, &label.len());
+        env.ledger()
+            .set_timestamp(released_at + params.commit_min_age_secs);
+        assert_eq!(
+            registrar_client.try_register(
+                &sniper,
+                &tld,
+                &label,
+                &sniper,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::CommitmentPredatesRelease.into()))
+        );
+
+        let later = Address::generate(&env);
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &later,
+            &label,
+            &later,
+            &secret,
+            None,
+        );
+    }
+
+    #[test]
+    fn rate_limit_caps_registrations_per_caller_window() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let tld = default_tld(&env);
+        env.ledger().set_timestamp(10_000);
+        let schedule = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env, 0i128],
+            premium_start: 0,
+            premium_decay_secs: 0,
+        };
+        registrar_client.set_price_schedule(&admin, &schedule);
+        let mut params = registrar_client.params();
+        params.rate_limit_max = 2;
+        params.rate_limit_window_secs = 1_000;
+        registrar_client.set_params(&admin, &params);
+
+        let bot = Address::generate(&env);
+        let secret = make_bytes(&env, b"rate");
+        for text in ["ratea", "rateb"] {
+            let label = make_label(&env, text);
+            register_name(
+                &env,
+                &registry_client,
+                &registrar_client,
+                &bot,
+                &label,
+                &bot,
+                &secret,
+                None,
+            );
+        }
+        let label = make_label(&env, "ratec");
+        let commitment = make_commitment(&env, &label, &bot, &secret, &bot);
+        registrar_client.commit(&bot, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + params.commit_min_age_secs);
+        let register = || {
+            registrar_client.try_register(
+                &bot,
+                &tld,
+                &label,
+                &bot,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            )
+        };
+        assert_eq!(
+            register().map(|_| ()),
+            Err(Ok(RegistrarError::RateLimited.into()))
+        );
+
+        // Another caller has its own allowance.
+        let human = Address::generate(&env);
+        let other = make_label(&env, "humanname");
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &human,
+            &other,
+            &human,
+            &secret,
+            None,
+        );
+
+        // Early in the next window the previous one still weighs in; once it has slid past,
+        // the bot may register again.
+        env.ledger().set_timestamp(11_100);
+        assert_eq!(
+            register().map(|_| ()),
+            Err(Ok(RegistrarError::RateLimited.into()))
+        );
+        env.ledger().set_timestamp(11_600);
+        assert!(register().is_ok());
+    }
+
+    #[test]
+    fn commitments_live_in_temporary_storage_for_max_age() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(60_000);
+        let committer = Address::generate(&env);
+        let label = make_label(&env, "temp");
+        let stored = |commitment: &BytesN<32>| {
+            env.as_contract(&registrar_id, || {
+                let key = super::commitment_key(&env, commitment);
+                (
+                    env.storage().temporary().get_ttl(&key),
+                    env.storage().persistent().has(&key),
+                )
+            })
+        };
+
+        let first = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"a"),
+            &committer,
+        );
+        registrar_client.commit(&committer, &first, &label.len());
+        // 86_400 s at 5 s per ledger, plus one ledger of slack.
+        assert_eq!(stored(&first), (17_281, false));
+
+        // The longest max age of any TLD bounds the lifetime.
+        let mut params = registrar_client.params();
+        params.commit_max_age_secs = 2 * 86_400;
+        registrar_client.set_params(&admin, &params);
+        let second = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"b"),
+            &committer,
+        );
+        registrar_client.commit(&committer, &second, &label.len());
+        assert_eq!(stored(&second), (34_561, false));
+
+        // A commitment written to persistent storage before the move is still honoured.
+        let legacy = make_commitment(
+            &env,
+            &label,
+            &committer,
+            &make_bytes(&env, b"c"),
+            &committer,
+        );
+        env.as_contract(&registrar_id, || {
+            let info = super::CommitmentInfo {
+                timestamp: 60_000,
+                committer: committer.clone(),
+                label_len: label.len(),
+            };
+            env.storage()
+                .persistent()
+                .set(&super::commitment_key(&env, &legacy), &info);
+        });
+        assert_eq!(
+            registrar_client.try_commit(&committer, &legacy, &label.len()),
+            Err(Ok(RegistrarError::CommitmentExists.into()))
+        );
+        registrar_client.cancel_commit(&committer, &legacy);
+        assert!(!commitment_exists(&env, &registrar_id, &legacy));
+    }
+
+    #[test]
+    fn council_actions_need_threshold_approvals() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(70_000);
+        let members = [
+            Address::generate(&env),
+            Address::generate(&env),
+            Address::generate(&env),
+        ];
+        let council = AdminCouncil {
+            members: vec![
+                &env,
+                members[0].clone(),
+                members[1].clone(),
+                members[2].clone(),
+            ],
+            threshold: 2,
+        };
+        let duplicated = AdminCouncil {
+            members: vec![&env, members[0].clone(), members[0].clone()],
+            threshold: 1,
+        };
+        assert_eq!(
+            registrar_client.try_set_council(&admin, &duplicated),
+            Err(Ok(RegistrarError::InvalidCouncil.into()))
+        );
+        registrar_client.set_council(&admin, &council);
+        assert_eq!(registrar_client.council(), Some(council.clone()));
+
+        // The single admin can no longer act alone on guarded actions.
+        let mut params = registrar_client.params();
+        params.referral_fee_bps = 250;
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::CouncilRequired.into()))
+        );
+        assert_eq!(
+            registrar_client.try_reserve_labels(
+                &admin,
+                &default_tld(&env),
+                &vec![&env, make_label(&env, "x")]
+            ),
+            Err(Ok(RegistrarError::CouncilRequired.into()))
+        );
+        assert_eq!(
+            registrar_client.try_propose_action(&admin, &AdminAction::SetParams(params.clone())),
+            Err(Ok(RegistrarError::NotCouncilMember.into()))
+        );
+
+        let id = registrar_client.propose_action(&members[0], &AdminAction::SetParams(params));
+        assert_eq!(
+            registrar_client.try_execute_action(&members[0], &id),
+            Err(Ok(RegistrarError::NotEnoughApprovals.into()))
+        );
+        assert_eq!(
+            registrar_client.try_approve_action(&members[0], &id),
+            Err(Ok(RegistrarError::AlreadyApproved.into()))
+        );
+        registrar_client.approve_action(&members[1], &id);
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(event_field::<u32>(&env, &data, "approvals"), 2);
+        registrar_client.execute_action(&members[2], &id);
+        assert_eq!(registrar_client.params().referral_fee_bps, 250);
+        assert!(registrar_client.proposal(&id).is_none());
+        assert_eq!(
+            registrar_client.try_execute_action(&members[2], &id),
+            Err(Ok(RegistrarError::ProposalMissing.into()))
+        );
+
+        // Proposals lapse after a week.
+        let label = make_label(&env, "brand");
+        let reserve = registrar_client.propose_action(
+            &members[1],
+            &AdminAction::ReserveLabels(default_tld(&env), vec![&env, label.clone()]),
+        );
+        env.ledger().set_timestamp(70_000 + 7 * 86_400 + 1);
+        assert_eq!(
+            registrar_client.try_approve_action(&members[2], &reserve),
+            Err(Ok(RegistrarError::ProposalExpired.into()))
+        );
+
+        // Shrinking the council drops the removed member's pending approval.
+        let smaller = AdminCouncil {
+            members: vec![&env, members[1].clone(), members[2].clone()],
+            threshold: 2,
+        };
+        let change =
+            registrar_client.propose_action(&members[0], &AdminAction::SetCouncil(smaller.clone()));
+        let reserve = registrar_client.propose_action(
+            &members[0],
+            &AdminAction::ReserveLabels(default_tld(&env), vec![&env, label.clone()]),
+        );
+        registrar_client.approve_action(&members[1], &change);
+        registrar_client.execute_action(&members[1], &change);
+        assert_eq!(registrar_client.council(), Some(smaller));
+        registrar_client.approve_action(&members[1], &reserve);
+        assert_eq!(
+            registrar_client.try_execute_action(&members[1], &reserve),
+            Err(Ok(RegistrarError::NotEnoughApprovals.into()))
+        );
+        registrar_client.approve_action(&members[2], &reserve);
+        registrar_client.execute_action(&members[2], &reserve);
+        assert!(registrar_client.is_reserved(&default_tld(&env), &label));
+    }
+
+    #[test]
+    fn council_guards_policy_tld_voucher_and_allocation_calls() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let member = Address::generate(&env);
+        let council = AdminCouncil {
+            members: vec![&env, member.clone()],
+            threshold: 1,
+        };
+        let label = make_label(&env, "vault");
+        registrar_client.reserve_labels(&admin, &default_tld(&env), &vec![&env, label.clone()]);
+        registrar_client.set_council(&admin, &council);
+
+        let policy = LengthPolicy {
+            enabled: false,
+            price_multiplier: 1,
+        };
+        let params = registrar_client.params();
+        let schedule = registrar_client.price_schedule();
+        let xlm = make_label(&env, "xlm");
+        let hash = BytesN::from_array(&env, &[7; 32]);
+        let owner = Address::generate(&env);
+        let required = Err(Ok(RegistrarError::CouncilRequired.into()));
+        assert_eq!(
+            registrar_client.try_set_length_policy(&admin, &default_tld(&env), &3, &policy),
+            required
+        );
+        assert_eq!(
+            registrar_client.try_add_tld(&admin, &xlm, &params, &schedule),
+            required
+        );
+        assert_eq!(
+            registrar_client.try_set_tld_config(&admin, &xlm, &params, &schedule),
+            required
+        );
+        assert_eq!(
+            registrar_client.try_mint_vouchers(&admin, &vec![&env, hash.clone()], &5_000, &1),
+            required
+        );
+        assert_eq!(
+            registrar_client.try_set_auction_house(&admin, &Some(member.clone())),
+            required
+        );
+        assert_eq!(registrar_client.try_revoke_voucher(&admin, &hash), required);
+        assert_eq!(
+            registrar_client.try_set_name_token(&admin, &member),
+            required
+        );
+        assert_eq!(
+            registrar_client
+                .try_allocate_reserved(
+                    &admin,
+                    &default_tld(&env),
+                    &label,
+                    &owner,
+                    &None,
+                    &DEFAULT_DURATION
+                )
+                .err(),
+            Some(Ok(RegistrarError::CouncilRequired.into()))
+        );
+        // Non-members cannot route around the check either.
+        assert_eq!(
+            registrar_client.try_propose_action(
+                &admin,
+                &AdminAction::SetLengthPolicy(default_tld(&env), 3, policy.clone())
+            ),
+            Err(Ok(RegistrarError::NotCouncilMember.into()))
+        );
+
+        for action in [
+            AdminAction::SetLengthPolicy(default_tld(&env), 3, policy.clone()),
+            AdminAction::AddTld(xlm.clone(), params.clone(), schedule.clone()),
+            AdminAction::SetTldConfig(xlm.clone(), params.clone(), schedule.clone()),
+            AdminAction::MintVouchers(vec![&env, hash.clone()], 5_000, 1),
+            AdminAction::SetAuctionHouse(Some(member.clone())),
+            AdminAction::AllocateReserved(
+                default_tld(&env),
+                label.clone(),
+                owner.clone(),
+                None,
+                DEFAULT_DURATION,
+            ),
+            AdminAction::SetNameToken(member.clone()),
+        ] {
+            let id = registrar_client.propose_action(&member, &action);
+            registrar_client.execute_action(&member, &id);
+        }
+        assert_eq!(
+            registrar_client.length_policy(&default_tld(&env), &3),
+            policy
+        );
+        assert!(registrar_client.tlds().contains(&xlm));
+        assert!(registrar_client.voucher(&hash).is_some());
+        assert_eq!(registrar_client.auction_house(), Some(member.clone()));
+        assert_eq!(registrar_client.name_token(), Some(member.clone()));
+        assert!(!registrar_client.available(&default_tld(&env), &label));
+
+        let id =
+            registrar_client.propose_action(&member, &AdminAction::RevokeVoucher(hash.clone()));
+        registrar_client.execute_action(&member, &id);
+        assert!(registrar_client.voucher(&hash).is_none());
+
+        // A council action still runs the entrypoint's own checks.
+        let id =
+            registrar_client.propose_action(&member, &AdminAction::AddTld(xlm, params, schedule));
+        assert_eq!(
+            registrar_client.try_execute_action(&member, &id),
+            Err(Ok(RegistrarError::TldExists.into()))
+        );
+    }
+
+    #[test]
+    fn council_setup_and_votes_need_the_right_signers() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(70_000);
+        let outsider = Address::generate(&env);
+        let members = [Address::generate(&env), Address::generate(&env)];
+        let council = AdminCouncil {
+            members: vec![&env, members[0].clone(), members[1].clone()],
+            threshold: 2,
+        };
+
+        assert_eq!(
+            registrar_client.try_set_council(&outsider, &council),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        let oversized = AdminCouncil {
+            members: Vec::from_iter(
+                &env,
+                (0..=MAX_COUNCIL_MEMBERS).map(|_| Address::generate(&env)),
+            ),
+            threshold: 1,
+        };
+        for invalid in [
+            AdminCouncil {
+                members: Vec::new(&env),
+                threshold: 0,
+            },
+            AdminCouncil {
+                members: council.members.clone(),
+                threshold: 0,
+            },
+            AdminCouncil {
+                members: council.members.clone(),
+                threshold: 3,
+            },
+            oversized,
+        ] {
+            assert_eq!(
+                registrar_client.try_set_council(&admin, &invalid),
+                Err(Ok(RegistrarError::InvalidCouncil.into()))
+            );
+        }
+        assert_eq!(
+            registrar_client.try_propose_action(&members[0], &AdminAction::SetTimelock(0)),
+            Err(Ok(RegistrarError::NotCouncilMember.into()))
+        );
+
+        registrar_client.set_council(&admin, &council);
+        assert_eq!(sole_signer(&env), admin);
+        // Only the council can replace itself from now on.
+        assert_eq!(
+            registrar_client.try_set_council(&admin, &council),
+            Err(Ok(RegistrarError::CouncilRequired.into()))
+        );
+
+        let id = registrar_client.propose_action(&members[0], &AdminAction::SetTimelock(60));
+        assert_eq!(sole_signer(&env), members[0]);
+        assert_eq!(
+            registrar_client.try_approve_action(&outsider, &id),
+            Err(Ok(RegistrarError::NotCouncilMember.into()))
+        );
+        assert_eq!(
+            registrar_client.try_approve_action(&members[1], &(id + 1)),
+            Err(Ok(RegistrarError::ProposalMissing.into()))
+        );
+        registrar_client.approve_action(&members[1], &id);
+        assert_eq!(sole_signer(&env), members[1]);
+        assert_eq!(
+            registrar_client.try_execute_action(&outsider, &id),
+            Err(Ok(RegistrarError::NotCouncilMember.into()))
+        );
+
+        // A fully approved proposal still lapses if nobody executes it in time.
+        env.ledger().set_timestamp(70_000 + PROPOSAL_TTL_SECS + 1);
+        assert_eq!(
+            registrar_client.try_execute_action(&members[0], &id),
+            Err(Ok(RegistrarError::ProposalExpired.into()))
+        );
+        assert_eq!(registrar_client.timelock(), 0);
+
+        let id = registrar_client.propose_action(&members[1], &AdminAction::SetTimelock(60));
+        registrar_client.approve_action(&members[0], &id);
+        registrar_client.execute_action(&members[0], &id);
+        assert_eq!(sole_signer(&env), members[0]);
+        assert_eq!(registrar_client.timelock(), 60);
+    }
+
+    #[test]
+    fn timelock_covers_tld_config_and_length_policy() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let xlm = make_label(&env, "xlm");
+        let mut params = registrar_client.params();
+        let schedule = registrar_client.price_schedule();
+        registrar_client.add_tld(&admin, &xlm, &params, &schedule);
+        env.ledger().set_timestamp(10_000);
+        registrar_client.set_timelock(&admin, &3_600);
+
+        params.referral_fee_bps = 250;
+        let policy = LengthPolicy {
+            enabled: false,
+            price_multiplier: 1,
+        };
+        assert_eq!(
+            registrar_client.try_set_tld_config(&admin, &xlm, &params, &schedule),
+            Err(Ok(RegistrarError::TimelockRequired.into()))
+        );
+        assert_eq!(
+            registrar_client.try_set_length_policy(&admin, &default_tld(&env), &3, &policy),
+            Err(Ok(RegistrarError::TimelockRequired.into()))
+        );
+
+        let config = registrar_client.queue_params(
+            &admin,
+            &TimelockedChange::TldConfig(xlm.clone(), params.clone(), schedule.clone()),
+        );
+        let length = registrar_client.queue_params(
+            &admin,
+            &TimelockedChange::LengthPolicy(default_tld(&env), 3, policy.clone()),
+        );
+        env.ledger().set_timestamp(10_000 + 3_599);
+        assert_eq!(
+            registrar_client.try_execute_params(&length),
+            Err(Ok(RegistrarError::ChangeNotReady.into()))
+        );
+        env.ledger().set_timestamp(10_000 + 3_600);
+        registrar_client.execute_params(&config);
+        registrar_client.execute_params(&length);
+        assert_eq!(registrar_client.tld_params(&xlm).referral_fee_bps, 250);
+        assert_eq!(
+            registrar_client.length_policy(&default_tld(&env), &3),
+            policy
+        );
+
+        // A council is held to the same delay.
+        let member = Address::generate(&env);
+        registrar_client.set_council(
+            &admin,
+            &AdminCouncil {
+                members: vec![&env, member.clone()],
+                threshold: 1,
+            },
+        );
+        for action in [
+            AdminAction::SetTldConfig(xlm.clone(), params.clone(), schedule.clone()),
+            AdminAction::SetLengthPolicy(default_tld(&env), 3, policy.clone()),
+        ] {
+            let id = registrar_client.propose_action(&member, &action);
+            assert_eq!(
+                registrar_client.try_execute_action(&member, &id),
+                Err(Ok(RegistrarError::TimelockRequired.into()))
+            );
+        }
+    }
+
+    #[test]
+    fn timelocked_changes_wait_out_the_delay() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(80_000);
+        let day = 86_400;
+        registrar_client.set_timelock(&admin, &(2 * day));
+        assert_eq!(registrar_client.timelock(), 2 * day);
+
+        let mut params = registrar_client.params();
+        params.referral_fee_bps = 300;
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::TimelockRequired.into()))
+        );
+        assert_eq!(
+            registrar_client.try_set_timelock(&admin, &0),
+            Err(Ok(RegistrarError::TimelockRequired.into()))
+        );
+
+        let id = registrar_client.queue_params(&admin, &TimelockedChange::Params(params));
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(event_field::<u64>(&env, &data, "eta"), 80_000 + 2 * day);
+        env.ledger().set_timestamp(80_000 + 2 * day - 1);
+        assert_eq!(
+            registrar_client.try_execute_params(&id),
+            Err(Ok(RegistrarError::ChangeNotReady.into()))
+        );
+        env.ledger().set_timestamp(80_000 + 2 * day);
+        registrar_client.execute_params(&id);
+        assert_eq!(registrar_client.params().referral_fee_bps, 300);
+        assert_eq!(
+            registrar_client.try_execute_params(&id),
+            Err(Ok(RegistrarError::ChangeMissing.into()))
+        );
+
+        // A cancelled change never applies; shortening the delay goes through the queue too.
+        let shorten = registrar_client.queue_params(&admin, &TimelockedChange::Delay(0));
+        let upgrade = registrar_client.queue_params(
+            &admin,
+            &TimelockedChange::Upgrade(BytesN::from_array(&env, &[9; 32])),
+        );
+        registrar_client.cancel_params(&admin, &upgrade);
+        assert!(registrar_client.queued_change(&upgrade).is_none());
+        env.ledger().set_timestamp(80_000 + 4 * day);
+        assert_eq!(
+            registrar_client.try_execute_params(&upgrade),
+            Err(Ok(RegistrarError::ChangeMissing.into()))
+        );
+        registrar_client.execute_params(&shorten);
+        assert_eq!(registrar_client.timelock(), 0);
+        registrar_client.set_params(&admin, &registrar_client.params());
+        assert_eq!(
+            registrar_client.try_set_timelock(&admin, &(31 * day)),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+    }
+
+    #[test]
+    fn timelock_admin_checks_and_permissionless_execution() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        env.ledger().set_timestamp(80_000);
+        let day = 86_400;
+        let outsider = Address::generate(&env);
+        assert_eq!(
+            registrar_client.try_set_timelock(&outsider, &day),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        registrar_client.set_timelock(&admin, &day);
+        assert_eq!(sole_signer(&env), admin);
+        // Lengthening applies at once, even while a delay is configured.
+        registrar_client.set_timelock(&admin, &(2 * day));
+        assert_eq!(registrar_client.timelock(), 2 * day);
+
+        let mut params = registrar_client.params();
+        params.referral_fee_bps = 300;
+        let change = TimelockedChange::Params(params.clone());
+        assert_eq!(
+            registrar_client.try_queue_params(&outsider, &change),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        let id = registrar_client.queue_params(&admin, &change);
+        assert_eq!(sole_signer(&env), admin);
+        assert_eq!(
+            registrar_client.try_cancel_params(&outsider, &id),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        assert_eq!(
+            registrar_client.try_cancel_params(&admin, &(id + 1)),
+            Err(Ok(RegistrarError::ChangeMissing.into()))
+        );
+
+        // A change keeps the eta it was queued with, and anyone may apply it without signing.
+        registrar_client.set_timelock(&admin, &(5 * day));
+        env.ledger().set_timestamp(80_000 + 2 * day);
+        registrar_client.execute_params(&id);
+        assert!(env.auths().is_empty());
+        assert_eq!(registrar_client.params().referral_fee_bps, 300);
+
+        // Invalid changes are still rejected when they are applied.
+        params.ttl_threshold_ledgers = 0;
+        let invalid = registrar_client.queue_params(&admin, &TimelockedChange::Params(params));
+        env.ledger().set_timestamp(80_000 + 7 * day);
+        assert_eq!(
+            registrar_client.try_execute_params(&invalid),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+        assert!(registrar_client.queued_change(&invalid).is_some());
+
+        // Once a council exists, queueing and cancelling go through it.
+        let member = Address::generate(&env);
+        registrar_client.set_council(
+            &admin,
+            &AdminCouncil {
+                members: vec![&env, member.clone()],
+                threshold: 1,
+            },
+        );
+        assert_eq!(
+            registrar_client.try_queue_params(&admin, &TimelockedChange::Delay(day)),
+            Err(Ok(RegistrarError::CouncilRequired.into()))
+        );
+        assert_eq!(
+            registrar_client.try_cancel_params(&admin, &invalid),
+            Err(Ok(RegistrarError::CouncilRequired.into()))
+        );
+        let cancel = registrar_client.propose_action(&member, &AdminAction::CancelChange(invalid));
+        registrar_client.execute_action(&member, &cancel);
+        assert!(registrar_client.queued_change(&invalid).is_none());
+    }
+
+    #[test]
+    fn gifts_are_claimed_by_the_recipient_or_revert_to_the_payer() {
+        let (env, registry_id, registrar_id, _) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(1_000);
+        let payer = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let secret = make_bytes(&env, b"secret");
+        let window = registrar_client.params().gift_claim_window_secs;
+
+        let gift = |label: &Bytes| {
+            let commitment = make_commitment(&env, label, &recipient, &secret, &payer);
+            registrar_client.commit(&payer, &commitment, &label.len());
+            let now = env.ledger().timestamp();
+            env.ledger()
+                .set_timestamp(now + registrar_client.params().commit_min_age_secs);
+            registrar_client.register_gift(
+                &payer,
+                &default_tld(&env),
+                label,
+                &recipient,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+            )
+        };
+
+        let claimed = make_label(&env, "present");
+        let namehash = gift(&claimed);
+        assert_eq!(registry_client.owner(&namehash), registrar_id);
+        let pending = registrar_client.gift(&default_tld(&env), &claimed).unwrap();
+        assert_eq!(pending.payer, payer);
+        assert_eq!(pending.claim_by, env.ledger().timestamp() + window);
+        assert_eq!(
+            registrar_client.try_claim_gift(&payer, &default_tld(&env), &claimed),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+        assert_eq!(
+            registrar_client.try_reclaim_gift(&default_tld(&env), &claimed),
+            Err(Ok(RegistrarError::GiftPending.into()))
+        );
+        registrar_client.claim_gift(&recipient, &default_tld(&env), &claimed);
+        assert_eq!(registry_client.owner(&namehash), recipient);
+        assert!(registrar_client
+            .gift(&default_tld(&env), &claimed)
+            .is_none());
+
+        let unclaimed = make_label(&env, "surprise");
+        let namehash = gift(&unclaimed);
+        let now = env.ledger().timestamp();
+        env.ledger().set_timestamp(now + window + 1);
+        assert_eq!(
+            registrar_client.try_claim_gift(&recipient, &default_tld(&env), &unclaimed),
+            Err(Ok(RegistrarError::GiftExpired.into()))
+        );
+        registrar_client.reclaim_gift(&default_tld(&env), &unclaimed);
+        assert_eq!(registry_client.owner(&namehash), payer);
+        assert_eq!(
+            registrar_client.try_reclaim_gift(&default_tld(&env), &unclaimed),
+            Err(Ok(RegistrarError::GiftMissing.into()))
+        );
+    }
+
+    #[test]
+    fn gifts_bind_the_recipient_charge_the_payer_and_end_at_claim_by() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(1_000);
+        let tld = default_tld(&env);
+        let payer = Address::generate(&env);
+        let recipient = Address::generate(&env);
+        let someone_else = Address::generate(&env);
+        mint(&env, &token_id, &payer, 1_000);
+        let label = make_label(&env, "present");
+        let secret = make_bytes(&env, b"secret");
+        let commitment = make_commitment(&env, &label, &recipient, &secret, &payer);
+        registrar_client.commit(&payer, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(1_000 + registrar_client.params().commit_min_age_secs);
+
+        assert_eq!(
+            registrar_client.try_claim_gift(&recipient, &tld, &label),
+            Err(Ok(RegistrarError::GiftMissing.into()))
+        );
+        // The commitment names the recipient, so the gift cannot be redirected.
+        assert_eq!(
+            registrar_client.try_register_gift(
+                &payer,
+                &tld,
+                &label,
+                &someone_else,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+            ),
+            Err(Ok(RegistrarError::CommitmentMissing.into()))
+        );
+        let price = registrar_client.rent_price(&tld, &label, &DEFAULT_DURATION);
+        registrar_client.register_gift(
+            &payer,
+            &tld,
+            &label,
+            &recipient,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+        );
+        assert_eq!(sole_signer(&env), payer);
+        assert_eq!(token.balance(&payer), 1_000 - price);
+
+        // `claim_by` is the last moment the recipient can claim, and reclaiming waits past it.
+        let claim_by = registrar_client.gift(&tld, &label).unwrap().claim_by;
+        env.ledger().set_timestamp(claim_by);
+        assert_eq!(
+            registrar_client.try_reclaim_gift(&tld, &label),
+            Err(Ok(RegistrarError::GiftPending.into()))
+        );
+        registrar_client.claim_gift(&recipient, &tld, &label);
+        assert_eq!(sole_signer(&env), recipient);
+        assert_eq!(
+            registrar_client.try_claim_gift(&recipient, &tld, &label),
+            Err(Ok(RegistrarError::GiftMissing.into()))
+        );
+    }
+
+    #[test]
+    fn vouchers_discount_registrations_until_used_up() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(80_000);
+        let code = make_bytes(&env, b"PARTNER25");
+        let hash = env.crypto().sha256(&code).to_bytes();
+        registrar_client.mint_vouchers(&admin, &vec![&env, hash.clone()], &2_500, &1);
+        assert_eq!(
+            registrar_client.try_mint_vouchers(&admin, &vec![&env, hash.clone()], &2_500, &1),
+            Err(Ok(RegistrarError::VoucherExists.into()))
+        );
+
+        let caller = Address::generate(&env);
+        mint(&env, &token_id, &caller, 200);
+        let secret = make_bytes(&env, b"vchr");
+        for text in ["vou", "vox"] {
+            let label = make_label(&env, text);
+            let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+            registrar_client.commit(&caller, &commitment, &label.len());
+        }
+        env.ledger()
+            .set_timestamp(80_000 + registrar_client.params().commit_min_age_secs);
+        let register = |text: &str| {
+            registrar_client.try_register(
+                &caller,
+                &default_tld(&env),
+                &make_label(&env, text),
+                &caller,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &Some(code.clone()),
+                &None,
+            )
+        };
+        register("vou").unwrap().unwrap();
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(event_field::<i128>(&env, &data, "price"), 75);
+        assert_eq!(token.balance(&registrar_id), 75);
+        assert!(registrar_client.voucher(&hash).is_none());
+        assert_eq!(
+            register("vox"),
+            Err(Ok(RegistrarError::InvalidVoucher.into()))
+        );
+
+        let other = env.crypto().sha256(&make_bytes(&env, b"SPARE")).to_bytes();
+        registrar_client.mint_vouchers(&admin, &vec![&env, other.clone()], &MAX_BPS, &3);
+        assert_eq!(registrar_client.voucher(&other).unwrap().uses_left, 3);
+        registrar_client.revoke_voucher(&admin, &other);
+        assert!(registrar_client.voucher(&other).is_none());
+        assert_eq!(
+            registrar_client.try_mint_vouchers(&admin, &vec![&env, other], &(MAX_BPS + 1), &1),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
     }
 
-    fn make_commitment(env: &Env, label: &Bytes, owner: &Address, secret: &Bytes) -> BytesN<32> {
-        super::compute_commitment(env, label, owner, secret)
+    #[test]
+    fn voucher_admin_checks_free_codes_and_counted_uses() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(80_000);
+        let outsider = Address::generate(&env);
+        let code = make_bytes(&env, b"LAUNCH100");
+        let hash = env.crypto().sha256(&code).to_bytes();
+        let hashes = vec![&env, hash.clone()];
+
+        assert_eq!(
+            registrar_client.try_mint_vouchers(&outsider, &hashes, &MAX_BPS, &2),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        for (discount_bps, uses) in [(0, 2), (MAX_BPS, 0)] {
+            assert_eq!(
+                registrar_client.try_mint_vouchers(&admin, &hashes, &discount_bps, &uses),
+                Err(Ok(RegistrarError::InvalidParams.into()))
+            );
+        }
+        assert_eq!(
+            registrar_client.try_mint_vouchers(&admin, &Vec::new(&env), &MAX_BPS, &2),
+            Err(Ok(RegistrarError::InvalidBatchSize.into()))
+        );
+        assert_eq!(
+            registrar_client.try_revoke_voucher(&admin, &hash),
+            Err(Ok(RegistrarError::InvalidVoucher.into()))
+        );
+        registrar_client.mint_vouchers(&admin, &hashes, &MAX_BPS, &2);
+        assert_eq!(
+            registrar_client.try_revoke_voucher(&outsider, &hash),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+
+        // A full discount needs no balance at all, and each registration uses one redemption.
+        let caller = Address::generate(&env);
+        let secret = make_bytes(&env, b"free");
+        let labels = ["fre", "frf"].map(|text| make_label(&env, text));
+        for label in labels.iter() {
+            let commitment = make_commitment(&env, label, &caller, &secret, &caller);
+            registrar_client.commit(&caller, &commitment, &label.len());
+        }
+        env.ledger()
+            .set_timestamp(80_000 + registrar_client.params().commit_min_age_secs);
+        for (label, uses_left) in labels.iter().zip([Some(1), None]) {
+            registrar_client.register(
+                &caller,
+                &default_tld(&env),
+                label,
+                &caller,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &Some(code.clone()),
+                &None,
+            );
+            let (_, _, data) = env.events().all().last().unwrap();
+            assert_eq!(event_field::<i128>(&env, &data, "price"), 0);
+            assert_eq!(
+                registrar_client
+                    .voucher(&hash)
+                    .map(|voucher| voucher.uses_left),
+                uses_left
+            );
+        }
+        assert_eq!(token.balance(&registrar_id), 0);
     }
 
-    fn expected_namehash(env: &Env, label: &Bytes) -> BytesN<32> {
-        let tld = Bytes::from_slice(env, b"stellar");
-        let root = BytesN::<32>::from_array(env, &[0u8; 32]);
-        let tld_hash = env.crypto().sha256(&tld).to_bytes();
-        let node = super::fold_hash(env, &root, &tld_hash);
-        let label_hash = env.crypto().sha256(label).to_bytes();
-        super::fold_hash(env, &node, &label_hash)
+    #[test]
+    fn export_state_pages_through_issued_names() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        env.ledger().set_timestamp(1_000);
+        let owner = Address::generate(&env);
+        let mut namehashes = Vec::new(&env);
+        for label in ["alpha", "bravo", "charlie"] {
+            namehashes.push_back(register_name(
+                &env,
+                &registry_client,
+                &registrar_client,
+                &owner,
+                &make_label(&env, label),
+                &owner,
+                &make_bytes(&env, label.as_bytes()),
+                None,
+            ));
+        }
+        // Re-registering after the grace period keeps the name's original position.
+        let expires = registry_client.expires(&namehashes.get(0).unwrap());
+        env.ledger()
+            .set_timestamp(expires + registrar_client.params().grace_period_secs + 1);
+        let buyer = Address::generate(&env);
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &buyer,
+            &make_label(&env, "alpha"),
+            &buyer,
+            &make_bytes(&env, b"again"),
+            None,
+        );
+        assert_eq!(registrar_client.exported_count(), 3);
+
+        let (first, next) = registrar_client.export_state(&admin, &0, &2);
+        assert_eq!(next, Some(2));
+        assert_eq!(first.len(), 2);
+        let alpha = first.get(0).unwrap();
+        assert_eq!(alpha.namehash, namehashes.get(0).unwrap());
+        assert_eq!(alpha.owner, Some(buyer));
+        assert_eq!(
+            alpha.expires_at,
+            Some(registry_client.expires(&alpha.namehash))
+        );
+        assert_eq!(first.get(1).unwrap().namehash, namehashes.get(1).unwrap());
+        let (rest, next) = registrar_client.export_state(&admin, &2, &2);
+        assert_eq!(next, None);
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest.get(0).unwrap().owner, Some(owner.clone()));
+
+        assert_eq!(
+            registrar_client.try_export_state(&owner, &0, &2),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        assert_eq!(
+            registrar_client.try_export_state(&admin, &0, &51),
+            Err(Ok(RegistrarError::InvalidBatchSize.into()))
+        );
     }
 
-    fn register_name(
-        env: &Env,
-        registry_client: &MockRegistryClient,
-        registrar_client: &RegistrarClient,
-        caller: &Address,
-        label: &Bytes,
-        owner: &Address,
-        secret: &Bytes,
-        resolver: Option<&Address>,
-    ) -> BytesN<32> {
-        let commitment = make_commitment(env, label, owner, secret);
-        let label_len = label.len();
-        registrar_client.commit(caller, &commitment, &label_len);
+    #[test]
+    fn revenue_tracks_fees_per_token_and_period() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        env.ledger().set_timestamp(60_000);
+        let caller = Address::generate(&env);
+        mint(&env, &token_id, &caller, 300);
+
+        let label = make_label(&env, "fee");
+        let secret = make_bytes(&env, b"fee_secret");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(60_000 + registrar_client.params().commit_min_age_secs);
+        registrar_client.register(
+            &caller,
+            &default_tld(&env),
+            &label,
+            &caller,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        let first = registrar_client.revenue_period(&env.ledger().timestamp());
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+
+        env.ledger().set_timestamp(60_000 + REVENUE_PERIOD_SECS);
+        let second = registrar_client.revenue_period(&env.ledger().timestamp());
+        assert_eq!(second, first + 1);
+        registrar_client.renew(&caller, &default_tld(&env), &label);
+
+        assert_eq!(registrar_client.revenue(&token_id), 300);
+        assert_eq!(registrar_client.revenue(&Address::generate(&env)), 0);
+        assert_eq!(
+            registrar_client.revenue_by_period(&first),
+            Revenue {
+                registrations: 100,
+                renewals: 100,
+            }
+        );
+        assert_eq!(
+            registrar_client.revenue_by_period(&second),
+            Revenue {
+                registrations: 0,
+                renewals: 100,
+            }
+        );
+    }
+
+    #[test]
+    fn reap_clears_lapsed_names_and_pays_the_keeper() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let mut params = registrar_client.params();
+        params.reap_bounty = 30;
+        registrar_client.set_params(&admin, &params);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(10_000);
+        let owner = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        mint(&env, &token_id, &owner, 100);
+        let label = make_label(&env, "old");
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &make_bytes(&env, b"old"),
+            None,
+        );
+        let expires = registry_client.expires(&namehash);
+        env.ledger()
+            .set_timestamp(expires + params.grace_period_secs);
+        assert_eq!(
+            registrar_client.try_reap(&keeper, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+
+        env.ledger()
+            .set_timestamp(expires + params.grace_period_secs + 1);
+        assert_eq!(
+            registrar_client.reap(&keeper, &default_tld(&env), &label),
+            30
+        );
+        let data = env.events().all().last().unwrap().2;
+        assert_eq!(event_field::<Address>(&env, &data, "owner"), owner);
+        assert_eq!(token.balance(&keeper), 30);
+        assert_eq!(token.balance(&registrar_id), 70);
+        assert!(registrar_client.available(&default_tld(&env), &label));
+        assert_eq!(
+            registrar_client.try_reap(&keeper, &default_tld(&env), &label),
+            Err(Ok(RegistrarError::ExpiryUnavailable.into()))
+        );
+    }
+
+    #[test]
+    fn reaped_names_keep_their_release_time() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let tld = default_tld(&env);
         let params = registrar_client.params();
-        let now = env.ledger().timestamp();
-        env.ledger().set_timestamp(now + params.commit_min_age_secs);
-        let resolver_arg = resolver.cloned();
-        let result = registrar_client.register(caller, label, owner, secret, &resolver_arg);
-        let namehash = expected_namehash(env, label);
-        assert_eq!(result, namehash);
-        let stored_owner = registry_client.owner(&namehash);
-        assert_eq!(stored_owner, *owner);
-        namehash
+        env.ledger().set_timestamp(10_000);
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "reaped");
+        let secret = make_bytes(&env, b"reaped");
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &secret,
+            None,
+        );
+        let released_at = registry_client.expires(&namehash) + params.grace_period_secs;
+
+        // Committed before the grace period ended, registered after the reap.
+        let sniper = Address::generate(&env);
+        env.ledger().set_timestamp(released_at);
+        registrar_client.commit(
+            &sniper,
+            &make_commitment(&env, &label, &sniper, &secret, &sniper),
+            &label.len(),
+        );
+        env.ledger()
+            .set_timestamp(released_at + params.commit_min_age_secs);
+        registrar_client.reap(&Address::generate(&env), &tld, &label);
+        assert_eq!(
+            registrar_client.try_register(
+                &sniper,
+                &tld,
+                &label,
+                &sniper,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None
+            ),
+            Err(Ok(RegistrarError::CommitmentPredatesRelease.into()))
+        );
+
+        // The premium still decays from the end of the grace period.
+        let schedule = PriceSchedule {
+            annual_prices: soroban_sdk::vec![&env, 0i128],
+            premium_start: 1_000,
+            premium_decay_secs: 86_400,
+        };
+        registrar_client.set_price_schedule(&admin, &schedule);
+        assert!(registrar_client.current_premium(&tld, &label) > 0);
+        env.ledger().set_timestamp(released_at + 86_400);
+        assert_eq!(registrar_client.current_premium(&tld, &label), 0);
+
+        let later = Address::generate(&env);
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &later,
+            &label,
+            &later,
+            &secret,
+            None,
+        );
     }
 
-    fn commitment_exists(env: &Env, registrar_id: &Address, commitment: &BytesN<32>) -> bool {
-        env.as_contract(registrar_id, || super::commitment_info(env, commitment))
-            .is_some()
+    #[test]
+    fn reap_is_permissionless_capped_by_free_funds_and_paused_with_the_contract() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let mut params = registrar_client.params();
+        params.reap_bounty = 500;
+        registrar_client.set_params(&admin, &params);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        let tld = default_tld(&env);
+        env.ledger().set_timestamp(10_000);
+        let owner = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        mint(&env, &token_id, &owner, 20);
+        let first = make_label(&env, "first");
+        let second = make_label(&env, "second");
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &first,
+            &owner,
+            &make_bytes(&env, b"first"),
+            None,
+        );
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &second,
+            &owner,
+            &make_bytes(&env, b"second"),
+            None,
+        );
+        assert_eq!(token.balance(&registrar_id), 20);
+        env.ledger()
+            .set_timestamp(registry_client.expires(&namehash) + params.grace_period_secs + 1);
+
+        registrar_client.pause(&admin);
+        assert_eq!(
+            registrar_client.try_reap(&keeper, &tld, &first),
+            Err(Ok(RegistrarError::Paused.into()))
+        );
+        registrar_client.unpause(&admin);
+
+        // No signature is needed, and the bounty is capped at the free balance.
+        assert_eq!(registrar_client.reap(&keeper, &tld, &first), 20);
+        assert!(env.auths().is_empty());
+        assert_eq!(token.balance(&keeper), 20);
+        assert_eq!(token.balance(&registrar_id), 0);
+
+        // With nothing left to pay, the name is still reaped for no bounty.
+        assert_eq!(registrar_client.reap(&keeper, &tld, &second), 0);
+        assert_eq!(token.balance(&keeper), 20);
+        assert!(registrar_client.available(&tld, &second));
+
+        let unregistered = make_label(&env, "never");
+        assert_eq!(
+            registrar_client.try_reap(&keeper, &tld, &unregistered),
+            Err(Ok(RegistrarError::ExpiryUnavailable.into()))
+        );
+    }
+
+    #[test]
+    fn redeem_recovers_names_in_grace_for_the_owner_at_a_fee() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let mut params = registrar_client.params();
+        params.redemption_fee = 50;
+        registrar_client.set_params(&admin, &params);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(10_000);
+        let owner = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        mint(&env, &token_id, &owner, 250);
+        mint(&env, &token_id, &stranger, 100);
+        let label = make_label(&env, "lost");
+        let tld = default_tld(&env);
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &make_bytes(&env, b"lost"),
+            None,
+        );
+        assert_eq!(
+            registrar_client.try_redeem(&owner, &tld, &label),
+            Err(Ok(RegistrarError::RenewalNotDue.into()))
+        );
+
+        let expires = registry_client.expires(&namehash);
+        env.ledger().set_timestamp(expires + 1);
+        let info = registrar_client.name_info(&tld, &label);
+        assert!(info.in_grace && !info.available);
+        assert_eq!(info.redemption_fee, 50);
+        assert_eq!(
+            registrar_client.try_renew(&owner, &tld, &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+        assert_eq!(
+            registrar_client.try_renew_for(&stranger, &tld, &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+        assert_eq!(
+            registrar_client.try_redeem(&stranger, &tld, &label),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+
+        let before = token.balance(&owner);
+        let renewed = registrar_client.redeem(&owner, &tld, &label);
+        let data = env.events().all().last().unwrap().2;
+        assert_eq!(event_field::<i128>(&env, &data, "fee"), 50);
+        assert_eq!(renewed, expires + params.renew_extension_secs);
+        assert_eq!(registry_client.expires(&namehash), renewed);
+        assert_eq!(before - token.balance(&owner), info.price + 50);
+        assert_eq!(registrar_client.name_info(&tld, &label).redemption_fee, 0);
+
+        env.ledger()
+            .set_timestamp(renewed + params.grace_period_secs + 1);
+        assert_eq!(
+            registrar_client.try_redeem(&owner, &tld, &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+    }
+
+    #[test]
+    fn redeem_needs_the_owners_signature_and_lasts_to_the_end_of_grace() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let params = registrar_client.params();
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(10_000);
+        let owner = Address::generate(&env);
+        mint(&env, &token_id, &owner, 100);
+        let label = make_label(&env, "late");
+        let tld = default_tld(&env);
+        assert_eq!(
+            registrar_client.try_redeem(&owner, &tld, &label),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &owner,
+            &label,
+            &owner,
+            &make_bytes(&env, b"late"),
+            None,
+        );
+        let expires = registry_client.expires(&namehash);
+        env.ledger()
+            .set_timestamp(expires + params.grace_period_secs);
+        registrar_client.pause(&admin);
+        assert_eq!(
+            registrar_client.try_redeem(&owner, &tld, &label),
+            Err(Ok(RegistrarError::Paused.into()))
+        );
+        registrar_client.unpause(&admin);
+
+        // On the last second of grace, with no redemption fee, only the rent is charged.
+        let info = registrar_client.name_info(&tld, &label);
+        assert_eq!(info.redemption_fee, 0);
+        let before = token.balance(&owner);
+        let renewed = registrar_client.redeem(&owner, &tld, &label);
+        assert_eq!(sole_signer(&env), owner);
+        let data = env.events().all().last().unwrap().2;
+        assert_eq!(event_field::<i128>(&env, &data, "fee"), 0);
+        assert_eq!(renewed, expires + params.renew_extension_secs);
+        assert_eq!(before - token.balance(&owner), info.price);
+        assert_eq!(
+            registrar_client.try_redeem(&owner, &tld, &label),
+            Err(Ok(RegistrarError::RenewalNotDue.into()))
+        );
     }
 
     #[test]
-    fn version_returns_1() {
-        let env = Env::default();
-        let registrar_id = env.register(Registrar, ());
+    fn lapse_paths_use_the_names_tld_params() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(10_000);
+        let stellar = default_tld(&env);
+        let xlm = make_label(&env, "xlm");
+        let defaults = registrar_client.params();
+        let mut params = defaults.clone();
+        params.renew_extension_secs = 100 * 86_400;
+        params.grace_period_secs = 10 * 86_400;
+        params.redemption_fee = 20;
+        assert!(defaults.grace_period_secs > params.grace_period_secs);
+        registrar_client.add_tld(&admin, &xlm, &params, &registrar_client.price_schedule());
 
-        assert_eq!(registrar_client.version(), 1);
-    }
+        let owner = Address::generate(&env);
+        let keeper = Address::generate(&env);
+        mint(&env, &token_id, &owner, 10_000);
+        let label = make_label(&env, "lapsing");
+        let secret = make_bytes(&env, b"lapsing");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &owner);
+        registrar_client.commit(&owner, &commitment, &label.len());
+        env.ledger()
+            .set_timestamp(10_000 + params.commit_min_age_secs);
+        let namehash = registrar_client.register(
+            &owner,
+            &xlm,
+            &label,
+            &owner,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
+        );
+        let expires = registry_client.expires(&namehash);
 
-    #[test]
-    fn init_only_once() {
-        let (env, registry_id, registrar_id, admin) = setup_env();
-        let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        let tld = Bytes::from_slice(&env, b"stellar");
-        let second = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.init(&registry_id, &tld, &admin);
-        }));
-        assert!(second.is_err());
+        registrar_client.renew_for(&keeper, &xlm, &label);
+        let expires = expires + params.renew_extension_secs;
+        assert_eq!(registry_client.expires(&namehash), expires);
+        assert_eq!(
+            registrar_client.try_renew_for(&keeper, &stellar, &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+
+        registrar_client.deposit_renewal_funds(&owner, &1_000);
+        registrar_client.set_auto_renew(&owner, &xlm, &label, &true);
+        assert_eq!(
+            registrar_client.auto_renew_sponsor(&xlm, &label),
+            Some(owner.clone())
+        );
+        assert_eq!(registrar_client.auto_renew_sponsor(&stellar, &label), None);
+        env.ledger()
+            .set_timestamp(expires - params.auto_renew_window_secs);
+        let expires = registrar_client.auto_renew(&keeper, &xlm, &label);
+        assert_eq!(expires, registry_client.expires(&namehash));
+        registrar_client.set_auto_renew(&owner, &xlm, &label, &false);
+
+        // Redemption charges the TLD's fee and ends with its shorter grace period.
+        env.ledger().set_timestamp(expires + 1);
+        let before = token.balance(&owner);
+        let expires = registrar_client.redeem(&owner, &xlm, &label);
+        assert_eq!(
+            before - token.balance(&owner),
+            registrar_client.rent_price(&xlm, &label, &params.renew_extension_secs) + 20
+        );
+        env.ledger()
+            .set_timestamp(expires + params.grace_period_secs + 1);
+        assert_eq!(
+            registrar_client.try_redeem(&owner, &xlm, &label),
+            Err(Ok(RegistrarError::NameNotAvailable.into()))
+        );
+        assert_eq!(
+            registrar_client.try_reap(&keeper, &stellar, &label),
+            Err(Ok(RegistrarError::ExpiryUnavailable.into()))
+        );
+        registrar_client.reap(&keeper, &xlm, &label);
+        assert!(registrar_client.available(&xlm, &label));
     }
 
     #[test]
-    fn commit_register_happy_path() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn reservations_length_policies_and_holds_are_per_tld() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(1_000);
-        let caller = Address::generate(&env);
+        env.ledger().set_timestamp(10_000);
+        let stellar = default_tld(&env);
+        let xlm = make_label(&env, "xlm");
+        registrar_client.add_tld(
+            &admin,
+            &xlm,
+            &registrar_client.params(),
+            &registrar_client.price_schedule(),
+        );
         let owner = Address::generate(&env);
-        let resolver = Address::generate(&env);
-        let label = make_label(&env, "alice");
-        let secret = make_bytes(&env, b"secret");
-
-        let commitment = make_commitment(&env, &label, &owner, &secret);
-        let label_len = label.len();
-        registrar_client.commit(&caller, &commitment, &label_len);
-
-        let params = registrar_client.params();
-        env.ledger()
-            .set_timestamp(1_000 + params.commit_min_age_secs);
-        let resolver_arg = Some(resolver.clone());
-        let expected_ts = env.ledger().timestamp();
-        let namehash = registrar_client.register(&caller, &label, &owner, &secret, &resolver_arg);
-        let events = env.events().all();
 
-        let stored_owner = registry_client.owner(&namehash);
-        assert_eq!(stored_owner, owner);
+        let brand = make_label(&env, "brand");
+        registrar_client.reserve_labels(&admin, &xlm, &vec![&env, brand.clone()]);
+        assert!(registrar_client.is_reserved(&xlm, &brand));
+        assert!(!registrar_client.is_reserved(&stellar, &brand));
+        assert!(!registrar_client.available(&xlm, &brand));
+        assert!(registrar_client.available(&stellar, &brand));
+        assert_eq!(
+            registrar_client.try_allocate_reserved(
+                &admin,
+                &stellar,
+                &brand,
+                &owner,
+                &None,
+                &DEFAULT_DURATION
+            ),
+            Err(Ok(RegistrarError::NotReserved.into()))
+        );
+        let namehash = registrar_client.allocate_reserved(
+            &admin,
+            &xlm,
+            &brand,
+            &owner,
+            &None,
+            &DEFAULT_DURATION,
+        );
+        assert_eq!(namehash, compute_namehash(&env, &xlm, &brand));
+        assert_eq!(registry_client.owner(&namehash), owner);
+        assert_eq!(
+            registrar_client.try_release_label(&admin, &stellar, &brand),
+            Err(Ok(RegistrarError::NotReserved.into()))
+        );
+        registrar_client.release_label(&admin, &xlm, &brand);
+        assert!(!registrar_client.is_reserved(&xlm, &brand));
 
-        let expires = registry_client.expires(&namehash);
+        let closed = LengthPolicy {
+            enabled: false,
+            price_multiplier: 1,
+        };
+        registrar_client.set_length_policy(&admin, &xlm, &3, &closed);
+        assert_eq!(registrar_client.length_policy(&xlm, &3), closed);
         assert_eq!(
-            expires,
-            env.ledger()
-                .timestamp()
-                .checked_add(MOCK_RENEW_EXTENSION)
-                .unwrap()
+            registrar_client.length_policy(&stellar, &3),
+            pricing::default_length_policy()
+        );
+        let abc = make_label(&env, "abc");
+        assert!(!registrar_client.available(&xlm, &abc));
+        assert!(registrar_client.available(&stellar, &abc));
+        assert_eq!(
+            registrar_client.try_set_length_policy(&admin, &make_label(&env, "nope"), &3, &closed),
+            Err(Ok(RegistrarError::UnknownTld.into()))
         );
 
-        let stored_resolver = registry_client.resolver(&namehash).unwrap();
-        assert_eq!(stored_resolver, resolver);
-        let mut found = false;
-        for idx in 0..events.len() {
-            let (contract_id, topics, data) = events.get(idx).unwrap();
-            if contract_id != registrar_id {
-                continue;
-            }
-            let symbol = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
-            if symbol != Symbol::new(&env, "name_registered") {
-                continue;
-            }
-            let evt = EvtNameRegistered::try_from_val(&env, &data).unwrap();
-            assert_eq!(evt.namehash, namehash);
-            assert_eq!(evt.owner, owner);
-            assert_eq!(evt.expires_at, expires);
-            assert_eq!(evt.ts, expected_ts);
-            found = true;
-        }
-        assert!(found, "expected name_registered event");
+        let house = Address::generate(&env);
+        registrar_client.set_auction_house(&admin, &Some(house.clone()));
+        let contested = make_label(&env, "contested");
+        registrar_client.hold_for_auction(&house, &xlm, &contested);
+        assert!(!registrar_client.available(&xlm, &contested));
+        assert!(registrar_client.available(&stellar, &contested));
+        assert_eq!(
+            registrar_client.try_release_auction_hold(&house, &stellar, &contested),
+            Err(Ok(RegistrarError::NotInAuction.into()))
+        );
+        let namehash = registrar_client.allocate_auctioned(
+            &house,
+            &xlm,
+            &contested,
+            &owner,
+            &DEFAULT_DURATION,
+            &7,
+        );
+        assert_eq!(namehash, compute_namehash(&env, &xlm, &contested));
+        assert_eq!(registry_client.owner(&namehash), owner);
     }
 
     #[test]
-    fn register_without_resolver_succeeds() {
+    fn stats_count_registrations_and_active_names() {
         let (env, registry_id, registrar_id, _) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(1_500);
-
-        let caller = Address::generate(&env);
+        env.ledger().set_timestamp(1_000);
         let owner = Address::generate(&env);
-        let label = make_label(&env, "noresolver");
-        let secret = make_bytes(&env, b"none");
+        let register = |label: &str, secret: &[u8]| {
+            register_name(
+                &env,
+                &registry_client,
+                &registrar_client,
+                &owner,
+                &make_label(&env, label),
+                &owner,
+                &make_bytes(&env, secret),
+                None,
+            )
+        };
 
-        let commitment = make_commitment(&env, &label, &owner, &secret);
-        let label_len = label.len();
-        registrar_client.commit(&caller, &commitment, &label_len);
-        let params = registrar_client.params();
+        let short = register("ab", b"one");
+        register("abc", b"two");
+        let expires = registry_client.expires(&short);
         env.ledger()
-            .set_timestamp(1_500 + params.commit_min_age_secs);
-        let none_resolver: Option<Address> = None;
-        let namehash = registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-
-        assert_eq!(registry_client.owner(&namehash), owner);
-        assert!(registry_client.resolver(&namehash).is_none());
+            .set_timestamp(expires + registrar_client.params().grace_period_secs + 1);
+        // The lapsed name is taken over, not added.
+        register("ab", b"three");
+        let stats = registrar_client.stats();
+        assert_eq!(stats.total_registrations, 3);
+        assert_eq!(stats.active_names, 2);
+        assert_eq!(stats.registrations_by_length, vec![&env, 0, 2, 1]);
+
+        registrar_client.release(&owner, &default_tld(&env), &make_label(&env, "abc"));
+        assert_eq!(registrar_client.stats().active_names, 1);
+        assert_eq!(registrar_client.stats().total_registrations, 3);
     }
 
     #[test]
-    fn register_handles_registry_owner_auth_requirements() {
-        let env = Env::default();
-        let registry_id = env.register(MockRegistryOwnerAuth, ());
-        let registrar_id = env.register(Registrar, ());
+    fn pricing_strategy_switches_from_dutch_auction_to_fixed() {
+        let (env, _registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        let admin = Address::generate(&env);
-        let tld = Bytes::from_slice(&env, b"stellar");
-        registrar_client.init(&registry_id, &tld, &admin);
-        env.mock_all_auths();
-        env.ledger().set_timestamp(2_000);
-
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "ownerflow");
-        let secret = make_bytes(&env, b"flow_secret");
-        let commitment = make_commitment(&env, &label, &owner, &secret);
-        let label_len = label.len();
-        registrar_client.commit(&caller, &commitment, &label_len);
-
-        let params = registrar_client.params();
-        env.ledger()
-            .set_timestamp(2_000 + params.commit_min_age_secs);
-        let none_resolver: Option<Address> = None;
-        let namehash = registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-
-        let registry_client = MockRegistryOwnerAuthClient::new(&env, &registry_id);
-        assert_eq!(registry_client.owner(&namehash), owner);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        env.ledger().set_timestamp(10_000);
+        let tld = default_tld(&env);
+        let year = pricing::SECONDS_PER_YEAR;
+
+        let mut params = registrar_client.params();
+        params.pricing = PricingStrategy::DutchAuction(DutchAuction {
+            start_price: 10_000,
+            floor_price: 1_000,
+            starts_at: 10_000,
+            duration_secs: 1_000,
+        });
+        registrar_client.set_params(&admin, &params);
+        let quote =
+            |label: &str| registrar_client.rent_price(&tld, &make_label(&env, label), &year);
+        assert_eq!(quote("a"), 10_000);
+        assert_eq!(quote("longerlabel"), 10_000);
+        env.ledger().set_timestamp(10_500);
+        assert_eq!(quote("abc"), 5_500);
+        env.ledger().set_timestamp(12_000);
+        assert_eq!(quote("abc"), 1_000);
+
+        params.pricing = PricingStrategy::Fixed(250);
+        registrar_client.set_params(&admin, &params);
+        assert_eq!(quote("a"), 250);
+        assert_eq!(quote("longerlabel"), 250);
+
+        params.pricing = PricingStrategy::LengthTiered;
+        registrar_client.set_params(&admin, &params);
+        assert_eq!(quote("abc"), 100);
+
+        params.pricing = PricingStrategy::DutchAuction(DutchAuction {
+            start_price: 100,
+            floor_price: 200,
+            starts_at: 0,
+            duration_secs: 1,
+        });
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+        params.pricing = PricingStrategy::Fixed(-1);
+        assert_eq!(
+            registrar_client.try_set_params(&admin, &params),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
     }
 
     #[test]
-    fn commitment_window_invalid() {
-        let (env, _registry_id, registrar_id, _) = setup_env();
+    fn supports_interface_reports_registrar_interfaces() {
+        let (env, _registry_id, registrar_id, _admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "fresh");
-        let secret = make_bytes(&env, b"123");
-        let commitment = make_commitment(&env, &label, &owner, &secret);
-        let label_len = label.len();
-        let none_resolver: Option<Address> = None;
-        let without_commit = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-        }));
-        assert!(without_commit.is_err());
-
-        registrar_client.commit(&caller, &commitment, &label_len);
-        let too_fresh = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-        }));
-        assert!(too_fresh.is_err());
-
-        env.ledger()
-            .set_timestamp(registrar_client.params().commit_max_age_secs + 10);
-        let too_old = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-        }));
-        assert!(too_old.is_err());
+        for name in SUPPORTED_INTERFACES {
+            assert!(resolver_interface::supports_interface(
+                &env,
+                &registrar_id,
+                name
+            ));
+        }
+        assert!(!registrar_client
+            .supports_interface(&Symbol::new(&env, resolver_interface::INTERFACE_REGISTRY)));
     }
 
     #[test]
-    fn availability_cycles_through_lifecycle() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn bound_commitment_pins_tld_resolver_and_duration() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(5_000);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "cycle");
-        let secret = make_bytes(&env, b"cycle_secret");
-
-        assert!(registrar_client.available(&label));
+        let tld = default_tld(&env);
+        env.ledger().set_timestamp(10_000);
+        let relayer = Address::generate(&env);
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "pinned");
+        let secret = make_bytes(&env, b"pinned");
+        let xlm = make_label(&env, "xlm");
+        registrar_client.add_tld(
+            &admin,
+            &xlm,
+            &registrar_client.params(),
+            &registrar_client.price_schedule(),
+        );
 
-        let commitment = make_commitment(&env, &label, &owner, &secret);
-        let label_len = label.len();
-        registrar_client.commit(&caller, &commitment, &label_len);
+        let commitment = registrar_client.make_commitment(
+            &tld,
+            &label,
+            &owner,
+            &secret,
+            &relayer,
+            &None,
+            &DEFAULT_DURATION,
+        );
+        assert_ne!(
+            commitment,
+            make_commitment(&env, &label, &owner, &secret, &relayer)
+        );
+        registrar_client.commit(&relayer, &commitment, &label.len());
         env.ledger()
-            .set_timestamp(5_000 + registrar_client.params().commit_min_age_secs);
-        let none_resolver: Option<Address> = None;
-        let namehash = registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-        assert!(!registrar_client.available(&label));
+            .set_timestamp(10_000 + registrar_client.params().commit_min_age_secs);
 
-        let expires = registry_client.expires(&namehash);
-        env.ledger()
-            .set_timestamp(expires + registrar_client.params().grace_period_secs + 1);
-        assert!(registrar_client.available(&label));
+        // The relayer cannot change the TLD or term the commitment was made for.
+        let register = |tld: &Bytes, duration: u64| {
+            registrar_client.try_register(
+                &relayer, tld, &label, &owner, &secret, &None, &duration, &None, &None, &None,
+            )
+        };
+        assert_eq!(
+            register(&tld, DEFAULT_DURATION / 2).map(|_| ()),
+            Err(Ok(RegistrarError::CommitmentMissing.into()))
+        );
+        assert_eq!(
+            register(&xlm, DEFAULT_DURATION).map(|_| ()),
+            Err(Ok(RegistrarError::CommitmentMissing.into()))
+        );
+        let namehash = register(&tld, DEFAULT_DURATION).unwrap().unwrap();
+        assert_eq!(registry_client.owner(&namehash), owner);
+        assert!(!commitment_exists(&env, &registrar_id, &commitment));
     }
 
     #[test]
-    fn unavailable_when_in_grace() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn sponsor_allowance_pays_for_registrations_to_the_owner() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
         env.ledger().set_timestamp(10_000);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "grace");
-        let secret = make_bytes(&env, b"abc");
+        let exchange = Address::generate(&env);
+        let user = Address::generate(&env);
+        mint(&env, &token_id, &exchange, 1_000);
+        let price = registrar_client.rent_price(
+            &default_tld(&env),
+            &make_label(&env, "abc"),
+            &DEFAULT_DURATION,
+        );
+
+        registrar_client.sponsor_allowance(&exchange, &user, &(price + 30));
+        let (_, topics, data) = env.events().all().last().unwrap();
+        assert_eq!(topics.len(), 3);
+        assert_eq!(event_field::<i128>(&env, &data, "allowance"), price + 30);
+        assert_eq!(token.balance(&exchange), 1_000 - price - 30);
+        assert_eq!(registrar_client.allowance(&exchange, &user), price + 30);
+
+        // The allowance is reserved from the admin's `withdraw`.
+        assert_eq!(
+            registrar_client.try_withdraw(&admin, &1, &admin),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
 
         let namehash = register_name(
             &env,
             &registry_client,
             &registrar_client,
-            &caller,
-            &label,
-            &owner,
-            &secret,
+            &exchange,
+            &make_label(&env, "abc"),
+            &user,
+            &make_bytes(&env, b"abc"),
             None,
         );
-        assert!(!registrar_client.available(&label));
+        assert_eq!(registry_client.owner(&namehash), user);
+        assert_eq!(registrar_client.allowance(&exchange, &user), 30);
+        assert_eq!(token.balance(&exchange), 1_000 - price - 30);
 
-        let expires = registry_client.expires(&namehash);
+        // A second name draws the remaining 30 and the exchange pays the rest.
+        register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &exchange,
+            &make_label(&env, "abd"),
+            &user,
+            &make_bytes(&env, b"abd"),
+            None,
+        );
+        assert_eq!(registrar_client.allowance(&exchange, &user), 0);
+        assert_eq!(token.balance(&exchange), 1_000 - 2 * price);
+
+        // Lowering an allowance refunds the sponsor.
+        registrar_client.sponsor_allowance(&exchange, &user, &40);
+        registrar_client.sponsor_allowance(&exchange, &user, &0);
+        assert_eq!(token.balance(&exchange), 1_000 - 2 * price);
+        assert_eq!(
+            registrar_client.try_sponsor_allowance(&exchange, &exchange, &10),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+    }
+
+    #[test]
+    fn owners_spend_their_allowance_by_naming_the_sponsor() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let registry_client = MockRegistryClient::new(&env, &registry_id);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(10_000);
+        let tld = default_tld(&env);
+        let exchange = Address::generate(&env);
+        let user = Address::generate(&env);
+        let stranger = Address::generate(&env);
+        mint(&env, &token_id, &exchange, 1_000);
+        mint(&env, &token_id, &user, 1_000);
+        mint(&env, &token_id, &stranger, 1_000);
+        let price = registrar_client.rent_price(&tld, &make_label(&env, "abc"), &DEFAULT_DURATION);
+        registrar_client.sponsor_allowance(&exchange, &user, &(price * 2));
+
+        let labels = ["abc", "abd", "abe"].map(|label| make_label(&env, label));
+        let secret = make_bytes(&env, b"sponsored");
+        for label in labels.iter() {
+            for committer in [&user, &stranger] {
+                let commitment = make_commitment(&env, label, &user, &secret, committer);
+                registrar_client.commit(committer, &commitment, &label.len());
+            }
+        }
         env.ledger()
-            .set_timestamp(expires + registrar_client.params().grace_period_secs);
-        assert!(!registrar_client.available(&label));
+            .set_timestamp(10_000 + registrar_client.params().commit_min_age_secs);
+
+        // Only the owner may draw someone else's allowance for themselves.
+        assert_eq!(
+            registrar_client.try_register(
+                &stranger,
+                &tld,
+                &labels[0],
+                &user,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &Some(exchange.clone()),
+            ),
+            Err(Ok(RegistrarError::NotOwner.into()))
+        );
+
+        let namehash = registrar_client.register(
+            &user,
+            &tld,
+            &labels[0],
+            &user,
+            &secret,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &Some(exchange.clone()),
+        );
+        assert_eq!(registry_client.owner(&namehash), user);
+        assert_eq!(token.balance(&user), 1_000);
+        assert_eq!(registrar_client.allowance(&exchange, &user), price);
+
+        // A batch draws the rest of the allowance once across its entries.
+        let mut first = batch_request(&labels[1], &user, &secret);
+        first.sponsor = Some(exchange.clone());
+        let mut second = batch_request(&labels[2], &user, &secret);
+        second.sponsor = Some(exchange.clone());
+        registrar_client.register_many(&user, &vec![&env, first, second]);
+        assert_eq!(registrar_client.allowance(&exchange, &user), 0);
+        assert_eq!(token.balance(&user), 1_000 - price);
+        assert_eq!(token.balance(&stranger), 1_000);
     }
 
     #[test]
-    fn unavailable_when_owner_missing_expiry() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn allowances_need_the_sponsors_signature_and_stay_with_one_owner() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(11_000);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "noexpiry");
-        let secret = make_bytes(&env, b"missing_expiry");
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(10_000);
+        let tld = default_tld(&env);
+        let exchange = Address::generate(&env);
+        let user = Address::generate(&env);
+        let other = Address::generate(&env);
+        mint(&env, &token_id, &exchange, 1_000);
+        mint(&env, &token_id, &user, 1_000);
+        let price = registrar_client.rent_price(&tld, &make_label(&env, "abc"), &DEFAULT_DURATION);
+
+        assert_eq!(
+            registrar_client.try_sponsor_allowance(&exchange, &user, &-1),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+        registrar_client.sponsor_allowance(&exchange, &user, &price);
+        assert_eq!(sole_signer(&env), exchange);
 
-        let namehash = register_name(
+        // The owner's own registration leaves the allowance alone unless it names the sponsor.
+        register_name(
             &env,
             &registry_client,
             &registrar_client,
-            &caller,
-            &label,
-            &owner,
-            &secret,
+            &user,
+            &make_label(&env, "abc"),
+            &user,
+            &make_bytes(&env, b"abc"),
             None,
         );
-        assert!(!registrar_client.available(&label));
+        assert_eq!(token.balance(&user), 1_000 - price);
+        assert_eq!(registrar_client.allowance(&exchange, &user), price);
 
-        registry_client.clear_expiry(&namehash);
-        assert!(
-            !registrar_client.available(&label),
-            "ownership without expiry must remain unavailable"
+        // Registering for an owner without an allowance charges the sponsor in full.
+        let namehash = register_name(
+            &env,
+            &registry_client,
+            &registrar_client,
+            &exchange,
+            &make_label(&env, "abd"),
+            &other,
+            &make_bytes(&env, b"abd"),
+            None,
         );
+        assert_eq!(registry_client.owner(&namehash), other);
+        assert_eq!(token.balance(&exchange), 1_000 - 2 * price);
+        assert_eq!(registrar_client.allowance(&exchange, &user), price);
+        assert_eq!(registrar_client.allowance(&exchange, &other), 0);
+
+        // Revoking refunds what is left.
+        registrar_client.sponsor_allowance(&exchange, &user, &0);
+        assert_eq!(token.balance(&exchange), 1_000 - price);
+        assert_eq!(registrar_client.allowance(&exchange, &user), 0);
     }
 
     #[test]
-    fn register_when_unavailable_fails() {
+    fn register_str_splits_and_normalizes_full_names() {
         let (env, registry_id, registrar_id, _) = setup_env();
-        let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(12_000);
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "taken");
+        let owner = Address::generate(&env);
+        let label = make_label(&env, "gamma");
         let secret = make_bytes(&env, b"secret");
+        let commitment = make_commitment(&env, &label, &owner, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        let params = registrar_client.params();
+        env.ledger()
+            .set_timestamp(env.ledger().timestamp() + params.commit_min_age_secs);
+
+        for bad in ["gamma", "gamma.", ".stellar"] {
+            assert_eq!(
+                registrar_client.try_register_str(
+                    &caller,
+                    &String::from_str(&env, bad),
+                    &owner,
+                    &secret,
+                    &None,
+                    &DEFAULT_DURATION,
+                    &None,
+                    &None,
+                    &None,
+                ),
+                Err(Ok(RegistrarError::InvalidLabel.into()))
+            );
+        }
+        assert_eq!(
+            registrar_client.try_register_str(
+                &caller,
+                &String::from_str(&env, "gamma.nowhere"),
+                &owner,
+                &secret,
+                &None,
+                &DEFAULT_DURATION,
+                &None,
+                &None,
+                &None,
+            ),
+            Err(Ok(RegistrarError::UnknownTld.into()))
+        );
 
-        register_name(
-            &env,
-            &registry_client,
-            &registrar_client,
+        let namehash = registrar_client.register_str(
             &caller,
-            &label,
+            &String::from_str(&env, "GaMma.Stellar"),
             &owner,
             &secret,
-            None,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
         );
-
-        let new_owner = Address::generate(&env);
-        let new_secret = make_bytes(&env, b"secret2");
-        let commitment = make_commitment(&env, &label, &new_owner, &new_secret);
-        let label_len = label.len();
-        registrar_client.commit(&caller, &commitment, &label_len);
-        env.ledger()
-            .set_timestamp(12_000 + registrar_client.params().commit_min_age_secs);
-
-        let none_resolver: Option<Address> = None;
-        let attempt = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(&caller, &label, &new_owner, &new_secret, &none_resolver);
-        }));
-        assert!(attempt.is_err());
+        assert_eq!(namehash, expected_namehash(&env, &label));
+        assert_eq!(registry_client.owner(&namehash), owner);
     }
 
     #[test]
-    fn commitment_deleted_only_after_successful_registration() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn commitment_status_tracks_the_commit_window() {
+        let (env, _, registrar_id, _) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(14_000);
         let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "locked");
-        let secret = make_bytes(&env, b"lock_secret");
-
-        register_name(
+        let commitment = make_commitment(
             &env,
-            &registry_client,
-            &registrar_client,
+            &make_label(&env, "delta"),
+            &caller,
+            &make_bytes(&env, b"secret"),
             &caller,
-            &label,
-            &owner,
-            &secret,
-            None,
+        );
+        assert_eq!(
+            registrar_client.commitment_status(&commitment),
+            CommitmentStatus::NotFound
         );
 
-        env.ledger().set_timestamp(15_000);
-        let challenger = Address::generate(&env);
-        let challenger_secret = make_bytes(&env, b"challenge");
-        let challenger_commitment = make_commitment(&env, &label, &challenger, &challenger_secret);
-        let label_len = label.len();
-        registrar_client.commit(&caller, &challenger_commitment, &label_len);
         let params = registrar_client.params();
-        env.ledger()
-            .set_timestamp(15_000 + params.commit_min_age_secs);
-        let none_resolver: Option<Address> = None;
-        let attempt = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(
-                &caller,
-                &label,
-                &challenger,
-                &challenger_secret,
-                &none_resolver,
-            );
-        }));
-        assert!(attempt.is_err());
-        assert!(
-            commitment_exists(&env, &registrar_id, &challenger_commitment),
-            "commitment must remain after failed registration"
+        let committed_at = env.ledger().timestamp();
+        registrar_client.commit(&caller, &commitment, &5);
+        let ready_at = committed_at + params.commit_min_age_secs;
+        let expires_at = committed_at + params.commit_max_age_secs;
+        assert_eq!(
+            registrar_client.commitment_status(&commitment),
+            CommitmentStatus::TooFresh(ready_at)
         );
-
-        let fresh_label = make_label(&env, "freshpolicy");
-        let fresh_secret = make_bytes(&env, b"fresh_secret");
-        let fresh_commitment = make_commitment(&env, &fresh_label, &owner, &fresh_secret);
-        let fresh_len = fresh_label.len();
-        registrar_client.commit(&caller, &fresh_commitment, &fresh_len);
-        env.ledger()
-            .set_timestamp(15_000 + 2 * params.commit_min_age_secs);
-        registrar_client.register(&caller, &fresh_label, &owner, &fresh_secret, &none_resolver);
-        assert!(
-            !commitment_exists(&env, &registrar_id, &fresh_commitment),
-            "commitment must be removed after successful registration"
+        env.ledger().set_timestamp(ready_at);
+        assert_eq!(
+            registrar_client.commitment_status(&commitment),
+            CommitmentStatus::Ready(expires_at)
+        );
+        env.ledger().set_timestamp(expires_at + 1);
+        assert_eq!(
+            registrar_client.commitment_status(&commitment),
+            CommitmentStatus::Expired
+        );
+        registrar_client.sweep_commitments(&vec![&env, commitment.clone()]);
+        assert_eq!(
+            registrar_client.commitment_status(&commitment),
+            CommitmentStatus::NotFound
         );
     }
 
     #[test]
-    fn renew_happy_path() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn premium_auction_sells_a_reserved_label_to_the_highest_bidder() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(20_000);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "renew");
-        let secret = make_bytes(&env, b"renew");
+        let token_id = registrar_client.payment_token();
+        let token = TokenClient::new(&env, &token_id);
+        env.ledger().set_timestamp(50_000);
+        let word = make_label(&env, "coffee");
+        let alice = Address::generate(&env);
+        let bob = Address::generate(&env);
+        mint(&env, &token_id, &alice, 1_000);
+        mint(&env, &token_id, &bob, 1_000);
 
-        let namehash = register_name(
-            &env,
-            &registry_client,
-            &registrar_client,
-            &caller,
-            &label,
-            &owner,
-            &secret,
-            None,
+        assert_eq!(
+            registrar_client.try_create_premium_auction(&admin, &word, &100, &3_600),
+            Err(Ok(RegistrarError::NotReserved.into()))
+        );
+        registrar_client.reserve_labels(&admin, &default_tld(&env), &vec![&env, word.clone()]);
+        registrar_client.create_premium_auction(&admin, &word, &100, &3_600);
+        assert_eq!(
+            registrar_client.try_release_label(&admin, &default_tld(&env), &word),
+            Err(Ok(RegistrarError::NameInAuction.into()))
         );
-        let before = registry_client.expires(&namehash);
 
-        env.ledger().set_timestamp(before + 1);
-        registrar_client.renew(&caller, &label);
-        let events = env.events().all();
-        let after = registry_client.expires(&namehash);
-        assert!(after > before);
+        assert_eq!(
+            registrar_client.try_bid_premium(&alice, &word, &99),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+        registrar_client.bid_premium(&alice, &word, &100);
+        // The next bid must clear the leading one by 5%.
+        assert_eq!(
+            registrar_client.try_bid_premium(&bob, &word, &104),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
+        // A late bid pushes the end back.
+        env.ledger().set_timestamp(50_000 + 3_500);
+        registrar_client.bid_premium(&bob, &word, &105);
+        assert_eq!(token.balance(&alice), 1_000);
+        assert_eq!(token.balance(&bob), 895);
+        let auction = registrar_client.premium_auction(&word).unwrap();
+        assert_eq!(auction.ends_at, 50_000 + 3_500 + 600);
+        // Escrowed bids are not withdrawable fees.
+        assert_eq!(
+            registrar_client.try_withdraw(&admin, &1, &admin),
+            Err(Ok(RegistrarError::InvalidAmount.into()))
+        );
 
-        let mut found = false;
-        for idx in 0..events.len() {
-            let (contract_id, topics, data) = events.get(idx).unwrap();
-            if contract_id != registrar_id {
-                continue;
-            }
-            let symbol = Symbol::try_from_val(&env, &topics.get(0).unwrap()).unwrap();
-            if symbol != Symbol::new(&env, "name_renewed") {
-                continue;
-            }
-            let evt = EvtNameRenewed::try_from_val(&env, &data).unwrap();
-            assert_eq!(evt.namehash, namehash);
-            assert_eq!(evt.expires_at, after);
-            found = true;
-            break;
-        }
-        assert!(found, "expected name_renewed event");
+        assert_eq!(
+            registrar_client.try_settle_premium(&word),
+            Err(Ok(RegistrarError::NameInAuction.into()))
+        );
+        env.ledger().set_timestamp(auction.ends_at);
+        assert_eq!(
+            registrar_client.try_bid_premium(&alice, &word, &200),
+            Err(Ok(RegistrarError::NotInAuction.into()))
+        );
+        assert_eq!(registrar_client.settle_premium(&word), Some(bob.clone()));
+        assert_eq!(registry_client.owner(&expected_namehash(&env, &word)), bob);
+        assert_eq!(registrar_client.premium_auction(&word), None);
+        assert!(registrar_client.is_reserved(&default_tld(&env), &word));
+
+        let treasury = Address::generate(&env);
+        registrar_client.withdraw(&admin, &105, &treasury);
+        assert_eq!(token.balance(&treasury), 105);
     }
 
     #[test]
-    fn renew_not_owner_rejected() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn premium_auctions_need_the_admin_valid_terms_and_may_close_unsold() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(30_000);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "guard");
-        let secret = make_bytes(&env, b"guard");
-        register_name(
-            &env,
-            &registry_client,
-            &registrar_client,
-            &caller,
-            &label,
-            &owner,
-            &secret,
-            None,
+        env.ledger().set_timestamp(50_000);
+        let tld = default_tld(&env);
+        let word = make_label(&env, "coffee");
+        let stranger = Address::generate(&env);
+        mint(&env, &registrar_client.payment_token(), &stranger, 1_000);
+        registrar_client.reserve_labels(&admin, &tld, &vec![&env, word.clone()]);
+
+        assert_eq!(
+            registrar_client.try_create_premium_auction(&stranger, &word, &100, &3_600),
+            Err(Ok(RegistrarError::NotAdmin.into()))
+        );
+        assert_eq!(
+            registrar_client.try_create_premium_auction(&admin, &word, &0, &3_600),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+        assert_eq!(
+            registrar_client.try_create_premium_auction(&admin, &word, &100, &0),
+            Err(Ok(RegistrarError::InvalidParams.into()))
+        );
+        assert_eq!(
+            registrar_client.try_bid_premium(&stranger, &word, &100),
+            Err(Ok(RegistrarError::NotInAuction.into()))
+        );
+        assert_eq!(
+            registrar_client.try_settle_premium(&word),
+            Err(Ok(RegistrarError::NotInAuction.into()))
         );
 
-        let attacker = Address::generate(&env);
-        let attempt = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.renew(&attacker, &label);
-        }));
-        assert!(attempt.is_err());
-    }
+        registrar_client.create_premium_auction(&admin, &word, &100, &3_600);
+        assert_eq!(sole_signer(&env), admin);
+        assert_eq!(
+            registrar_client.try_create_premium_auction(&admin, &word, &100, &3_600),
+            Err(Ok(RegistrarError::NameInAuction.into()))
+        );
 
-    #[test]
-    fn invalid_label_rejected() {
-        let (env, _registry_id, registrar_id, _) = setup_env();
-        let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let secret = make_bytes(&env, b"x");
-        let empty_label = Bytes::from_slice(&env, b"");
-        let none_resolver: Option<Address> = None;
-        let attempt = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(&caller, &empty_label, &owner, &secret, &none_resolver);
-        }));
-        assert!(attempt.is_err());
+        // Without bids, anyone may close it and the label stays reserved and unowned.
+        env.ledger().set_timestamp(50_000 + 3_600);
+        assert_eq!(registrar_client.settle_premium(&word), None);
+        assert!(env.auths().is_empty());
+        let data = env.events().all().last().unwrap().2;
+        assert_eq!(event_field::<i128>(&env, &data, "price"), 0);
+        assert_eq!(registrar_client.premium_auction(&word), None);
+        assert!(registrar_client.is_reserved(&tld, &word));
+        assert!(registry_client
+            .try_owner(&expected_namehash(&env, &word))
+            .is_err());
+
+        // Once closed, it may be put up again.
+        registrar_client.create_premium_auction(&admin, &word, &100, &3_600);
+        assert!(registrar_client.premium_auction(&word).is_some());
     }
 
     #[test]
-    fn label_character_validation() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn faucet_mode_needs_a_testnet_build() {
+        let (env, _, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
-        env.ledger().set_timestamp(45_000);
-        let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let secret = make_bytes(&env, b"validate");
-        let none_resolver: Option<Address> = None;
-
-        let invalid_label = Bytes::from_slice(&env, b"Bad!");
-        let leading_hyphen = Bytes::from_slice(&env, b"-lead");
-        let trailing_hyphen = Bytes::from_slice(&env, b"trail-");
-        for label in [&invalid_label, &leading_hyphen, &trailing_hyphen] {
-            let attempt = catch_unwind(AssertUnwindSafe(|| {
-                registrar_client.register(&caller, label, &owner, &secret, &none_resolver);
-            }));
-            assert!(
-                attempt.is_err(),
-                "invalid label {:?} must be rejected",
-                label
+        let faucet = RegistrarParams {
+            faucet: true,
+            ..registrar_client.params()
+        };
+        if cfg!(feature = "testnet") {
+            registrar_client.set_params(&admin, &faucet);
+            assert!(registrar_client.params().faucet);
+        } else {
+            assert_eq!(
+                registrar_client.try_set_params(&admin, &faucet),
+                Err(Ok(RegistrarError::InvalidParams.into()))
             );
+            assert!(!registrar_client.params().faucet);
         }
-
-        let valid_label = make_label(&env, "abc-123");
-        let commitment = make_commitment(&env, &valid_label, &owner, &secret);
-        let label_len = valid_label.len();
-        registrar_client.commit(&caller, &commitment, &label_len);
-        let params = registrar_client.params();
-        env.ledger()
-            .set_timestamp(45_000 + params.commit_min_age_secs);
-        let namehash =
-            registrar_client.register(&caller, &valid_label, &owner, &secret, &none_resolver);
-
-        let registry_client = MockRegistryClient::new(&env, &registry_id);
-        assert_eq!(registry_client.owner(&namehash), owner);
     }
 
+    #[cfg(feature = "testnet")]
     #[test]
-    fn commitment_replay_disallowed() {
-        let (env, registry_id, registrar_id, _) = setup_env();
+    fn faucet_mode_registers_for_free_without_waiting() {
+        let (env, registry_id, registrar_id, admin) = setup_env();
         let registrar_client = RegistrarClient::new(&env, &registrar_id);
         let registry_client = MockRegistryClient::new(&env, &registry_id);
-        env.ledger().set_timestamp(40_000);
+        registrar_client.set_price_schedule(&admin, &tiered_schedule(&env));
+        registrar_client.set_params(
+            &admin,
+            &RegistrarParams {
+                faucet: true,
+                ..registrar_client.params()
+            },
+        );
+        let token = TokenClient::new(&env, &registrar_client.payment_token());
         let caller = Address::generate(&env);
-        let owner = caller.clone();
-        let label = make_label(&env, "single");
-        let secret = make_bytes(&env, b"s1");
+        let label = make_label(&env, "tester");
+        let secret = make_bytes(&env, b"secret");
+        let commitment = make_commitment(&env, &label, &caller, &secret, &caller);
+        registrar_client.commit(&caller, &commitment, &label.len());
+        assert_eq!(
+            registrar_client.commitment_status(&commitment),
+            CommitmentStatus::Ready(
+                env.ledger().timestamp() + registrar_client.params().commit_max_age_secs
+            )
+        );
 
-        env.ledger().set_timestamp(40_000);
-        register_name(
-            &env,
-            &registry_client,
-            &registrar_client,
+        let namehash = registrar_client.register(
             &caller,
+            &default_tld(&env),
             &label,
-            &owner,
+            &caller,
             &secret,
-            None,
+            &None,
+            &DEFAULT_DURATION,
+            &None,
+            &None,
+            &None,
         );
+        assert_eq!(registry_client.owner(&namehash), caller);
+        assert_eq!(token.balance(&caller), 0);
+    }
 
-        let none_resolver: Option<Address> = None;
-        let replay = catch_unwind(AssertUnwindSafe(|| {
-            registrar_client.register(&caller, &label, &owner, &secret, &none_resolver);
-        }));
-        assert!(replay.is_err());
+    #[test]
+    fn events_carry_consecutive_seq_numbers() {
+        let (env, _, registrar_id, admin) = setup_env();
+        let registrar_client = RegistrarClient::new(&env, &registrar_id);
+        let initialized = registrar_client.event_seq();
+        assert!(initialized > 0);
+
+        let caller = Address::generate(&env);
+        registrar_client.commit(&caller, &BytesN::from_array(&env, &[7; 32]), &5);
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(event_field::<u64>(&env, &data, "seq"), initialized + 1);
+        registrar_client.pause(&admin);
+        let (_, _, data) = env.events().all().last().unwrap();
+        assert_eq!(event_field::<u64>(&env, &data, "seq"), initialized + 2);
+        assert_eq!(registrar_client.event_seq(), initialized + 2);
     }
 }
@@ -3,23 +3,50 @@
 #[cfg(test)]
 extern crate std;
 
+use resolver_interface::InterfaceDetection;
+use soroban_sdk::xdr::ToXdr;
 use soroban_sdk::{
-    contract, contractevent, contractimpl, contracttype, Address, Bytes, BytesN, Env, Vec,
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, IntoVal, String, Symbol, Val, Vec,
 };
 
 const RENEW_EXTENSION_SECONDS: u64 = 31_536_000;
+/// Time after expiry before `burn` may delete a node, for parents without a
+/// `set_grace_period` (matches the Registrar's default grace).
+const GRACE_PERIOD_SECONDS: u64 = 7_776_000;
+/// How long before expiry `poke_expiry` starts signalling `ExpiryPhase::Expiring`.
+const EXPIRY_WARNING_SECONDS: u64 = 2_592_000;
 const MAX_LABEL_LENGTH: u32 = 63;
+/// Longest dotted name `namehash_str` accepts, as in DNS.
+const MAX_NAME_LENGTH: usize = 255;
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+/// Storage layout this code expects; `migrate` brings older layouts up to it.
+const STORAGE_VERSION: u32 = 1;
+/// Ownership changes `history` keeps per name; older ones are dropped first.
+const HISTORY_LEN: u32 = 8;
 const ZERO_ACCOUNT_STR: &str = "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF";
 
-/// Storage key namespaces (placeholders for future data layout).
-mod keys {
-    pub const OWNER: &[u8] = b"REG_OWNER"; // namehash -> Address
-    pub const RESOLVER: &[u8] = b"REG_RESOLV"; // namehash -> Address
-    pub const EXPIRES: &[u8] = b"REG_EXPIRE"; // namehash -> u64
-    pub const FLAGS: &[u8] = b"REG_FLAGS"; // namehash -> u32 (bitflags)
+/// Bits for `set_flags`. Flags can only be added; they fall away when the name is burned,
+/// released, or reclaimed after expiry.
+pub mod flags {
+    /// `set_resolver` and `clear_resolver` abort with `Locked`.
+    pub const FROZEN_RESOLVER: u32 = 1 << 0;
+    /// `transfer` and owner-side `set_owner` abort with `Locked`.
+    pub const FROZEN_TRANSFER: u32 = 1 << 1;
+    /// No child node can be created or reassigned under this name.
+    pub const SUBNODES_LOCKED: u32 = 1 << 2;
+    pub const ALL: u32 = FROZEN_RESOLVER | FROZEN_TRANSFER | SUBNODES_LOCKED;
+    /// Set by `export_name` and cleared by `import_name`, never by `set_flags`. Blocks every
+    /// change the other flags can block, plus `release`.
+    pub const EXPORTED: u32 = 1 << 3;
 }
 
-/// Event payload types (expand as needed).
+/// Event payload types (expand as needed). Every event ends with `seq`, its position in this
+/// contract's event stream starting at 1, so indexers can detect gaps and order events
+/// within a ledger.
 #[derive(Clone)]
 #[contractevent(topics = ["transfer"])]
 pub struct EvtTransfer {
@@ -27,6 +54,7 @@ pub struct EvtTransfer {
     pub namehash: BytesN<32>,
     pub from: Address,
     pub to: Address,
+    pub seq: u64,
 }
 
 #[derive(Clone)]
@@ -35,6 +63,123 @@ pub struct EvtResolverChanged {
     #[topic]
     pub namehash: BytesN<32>,
     pub resolver: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["resolver_cleared"])]
+pub struct EvtResolverCleared {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["burn"])]
+pub struct EvtBurn {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["grace_period_changed"])]
+pub struct EvtGracePeriodChanged {
+    #[topic]
+    pub node: BytesN<32>,
+    pub grace_secs: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["released"])]
+pub struct EvtReleased {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["expiry_warning"])]
+pub struct EvtExpiryWarning {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub expires_at: u64,
+    pub phase: ExpiryPhase,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["record_version_changed"])]
+pub struct EvtRecordVersionChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub version: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["upgraded"])]
+pub struct EvtUpgraded {
+    pub new_wasm_hash: BytesN<32>,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["migrated"])]
+pub struct EvtMigrated {
+    pub from: u32,
+    pub to: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["initialized"])]
+pub struct EvtInitialized {
+    pub admin: Address,
+    pub root_owner: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["tld_created"])]
+pub struct EvtTldCreated {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub label: Bytes,
+    pub owner: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["lease_granted"])]
+pub struct EvtLeaseGranted {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub lessee: Address,
+    pub expires_at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["lease_ended"])]
+pub struct EvtLeaseEnded {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub lessee: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["controller_changed"])]
+pub struct EvtControllerChanged {
+    #[topic]
+    pub controller: Address,
+    pub approved: bool,
+    pub seq: u64,
 }
 
 #[derive(Clone)]
@@ -43,6 +188,167 @@ pub struct EvtRenew {
     #[topic]
     pub namehash: BytesN<32>,
     pub expires_at: u64,
+    pub seq: u64,
+}
+
+/// Every write or removal of a node's expiry. `reason` names the entrypoint: `renew`,
+/// `set_expiry`, `extend_expiry`, `import`, `burn`, or `release`.
+#[derive(Clone)]
+#[contractevent(topics = ["expiry_changed"])]
+pub struct EvtExpiryChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub old: Option<u64>,
+    pub new: Option<u64>,
+    pub reason: Symbol,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["approval"])]
+pub struct EvtApproval {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub approved: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["transfer_offered"])]
+pub struct EvtTransferOffered {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub to: Address,
+    pub deadline: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["transfer_offer_cancelled"])]
+pub struct EvtTransferOfferCancelled {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["subnode_operator_changed"])]
+pub struct EvtSubnodeOperatorChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub operator: Option<Address>,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["bridge_changed"])]
+pub struct EvtBridgeChanged {
+    pub bridge: Option<Address>,
+    pub seq: u64,
+}
+
+/// Everything a relayer needs to mirror the name on `target_chain`.
+#[derive(Clone)]
+#[contractevent(topics = ["name_exported"])]
+pub struct EvtNameExported {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub name: Bytes,
+    pub owner: Address,
+    pub expires_at: Option<u64>,
+    pub target_chain: Symbol,
+    pub target_address: Bytes,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["name_imported"])]
+pub struct EvtNameImported {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub owner: Address,
+    pub source_chain: Symbol,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["migration_opened"])]
+pub struct EvtMigrationOpened {
+    pub snapshot_root: BytesN<32>,
+    pub closes_at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["migration_closed"])]
+pub struct EvtMigrationClosed {
+    pub closed_at: u64,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["flags_changed"])]
+pub struct EvtFlagsChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub flags: u32,
+    pub seq: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["approval_for_all"])]
+pub struct EvtApprovalForAll {
+    #[topic]
+    pub owner: Address,
+    #[topic]
+    pub operator: Address,
+    pub approved: bool,
+    pub seq: u64,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum RegistryError {
+    /// The name has no owner, resolver, expiry, or recorded label.
+    NotFound = 1,
+    NotAuthorized = 2,
+    ZeroAddress = 3,
+    /// The name's grace period has ended, or its parent has expired.
+    Expired = 4,
+    Overflow = 5,
+    AlreadyInitialized = 6,
+    NotInitialized = 7,
+    InvalidLabel = 8,
+    /// `burn` was called before the grace period ended.
+    GraceActive = 9,
+    /// A new expiry is not in the future or not later than the current one.
+    InvalidExpiry = 10,
+    InvalidOperator = 11,
+    UnsupportedVersion = 12,
+    /// `create_tld` was called for a top-level node that already has an owner.
+    TldExists = 13,
+    /// `lease` was called while another lease on the name is still running.
+    LeaseActive = 14,
+    /// `end_lease` was called for a name without a lease.
+    NoLease = 15,
+    /// A flag set with `set_flags` forbids the change.
+    Locked = 16,
+    /// `set_flags` was given an unknown bit or tried to clear a set one.
+    InvalidFlags = 17,
+    /// `import_name` was called for a name that is not exported.
+    NotExported = 18,
+    /// `import_names` outside the migration window, or `open_migration` after one was opened.
+    MigrationClosed = 19,
+    /// `import_names` for a name that already has an owner.
+    NameTaken = 20,
+    /// An imported name's Merkle proof does not lead to the snapshot root.
+    InvalidProof = 21,
+    /// `accept_transfer` or `cancel_transfer_offer` for a name without a live offer.
+    NoOffer = 22,
 }
 
 #[contract]
@@ -54,9 +360,117 @@ enum DataKey {
     Owner(BytesN<32>),
     Resolver(BytesN<32>),
     Expires(BytesN<32>),
+    Operator(Address, Address),
+    Approved(BytesN<32>),
+    RecordVersion(BytesN<32>),
+    Admin,
+    Controller(Address),
+    /// Every namehash currently owned by the address.
+    OwnedNames(Address),
+    /// Every live child node created under the parent.
+    Subnodes(BytesN<32>),
+    /// Immutable facts about a node's preimage, kept across `burn` so it stays nameable.
+    Parent(BytesN<32>),
+    Label(BytesN<32>),
+    /// Layout version of the data above; absent on deployments that predate `migrate`.
+    StorageVersion,
+    Lease(BytesN<32>),
+    /// `(expires_at, phase)` of the last `expiry_warning`, so each phase is signalled once.
+    ExpiryWarned(BytesN<32>),
+    /// `flags` bits locking parts of the name.
+    Flags(BytesN<32>),
+    /// Authority allowed to `import_name`.
+    Bridge,
+    /// Contract allowed to create unowned children of the node, such as a subregistrar.
+    SubnodeOperator(BytesN<32>),
+    /// The one migration window `import_names` accepts names in.
+    Migration,
+    /// Pending `offer_transfer`, dropped on any ownership change.
+    TransferOffer(BytesN<32>),
+    /// Last `seq` handed to an event; kept in instance storage.
+    EventSeq,
+    /// The last `HISTORY_LEN` `(owner, timestamp)` assignments, oldest first; kept across
+    /// `burn` for provenance.
+    History(BytesN<32>),
+    /// Grace period of the parent's children, set by its controller.
+    GracePeriod(BytesN<32>),
+    /// Counter bumped whenever the owner, expiry, or record version of the name changes.
+    OwnerNonce(BytesN<32>),
+}
+
+/// Stage of a name's run-down to release, as reported by `poke_expiry`.
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum ExpiryPhase {
+    /// Within `EXPIRY_WARNING_SECONDS` of `expires_at`.
+    Expiring = 1,
+    /// Past `expires_at` but not yet burnable.
+    Grace = 2,
+}
+
+/// Time-bounded right to edit a name's Resolver records without owning it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Lease {
+    pub lessee: Address,
+    pub expires_at: u64,
+}
+
+/// A transfer `to` may take with `accept_transfer` until `deadline`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferOffer {
+    pub to: Address,
+    pub deadline: u64,
+}
+
+/// The snapshot a legacy registry is migrated from, and when imports stop.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigrationWindow {
+    pub snapshot_root: BytesN<32>,
+    pub closes_at: u64,
+}
+
+/// One name for `import_names`. `proof` is the sibling path from `migration_leaf(name)` to
+/// the window's snapshot root.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportedName {
+    pub parent: BytesN<32>,
+    pub label: Bytes,
+    pub owner: Address,
+    pub expires_at: Option<u64>,
+    pub proof: Vec<BytesN<32>>,
+}
+
+/// Leaf committed to by a migration snapshot: `sha256(xdr((parent, label, owner, expires_at)))`.
+pub fn migration_leaf(env: &Env, name: &ImportedName) -> BytesN<32> {
+    let fields = (
+        name.parent.clone(),
+        name.label.clone(),
+        name.owner.clone(),
+        name.expires_at,
+    );
+    env.crypto().sha256(&fields.to_xdr(env)).to_bytes()
+}
+
+/// Hashes `leaf` up `proof`, ordering each pair so proofs carry no left/right bits.
+fn merkle_root(env: &Env, leaf: BytesN<32>, proof: &Vec<BytesN<32>>) -> BytesN<32> {
+    proof.iter().fold(leaf, |node, sibling| {
+        let (lo, hi) = if node.to_array() <= sibling.to_array() {
+            (node, sibling)
+        } else {
+            (sibling, node)
+        };
+        let mut data = Bytes::from_array(env, &lo.to_array());
+        data.extend_from_array(&hi.to_array());
+        env.crypto().sha256(&data).to_bytes()
+    })
 }
 
-/// Minimal, compilable interface. Add real logic later.
+/// Name ownership, resolvers, expiries, and the locks, leases, and controllers around them.
 #[contractimpl]
 impl Registry {
     /// Trivial function so the contract exports at least one method.
@@ -64,11 +478,219 @@ impl Registry {
         1
     }
 
+    /// Runs once, at deployment, so nobody can claim the Registry before its deployer: records
+    /// the admin that manages controllers and TLDs, and gives the namespace root (the all-zero
+    /// node) to `root_owner`.
+    pub fn __constructor(env: Env, admin: Address, root_owner: Address) {
+        if Self::is_zero_account(&env, &root_owner) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        Self::write_entry(&env, &DataKey::Admin, &admin);
+        Self::write_entry(&env, &DataKey::StorageVersion, &STORAGE_VERSION);
+        let root = Self::root(&env);
+        Self::write_owner(&env, root, root_owner.clone(), root_owner.clone());
+        EvtInitialized {
+            admin,
+            root_owner,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Admin-only: mint the top-level node `label` (e.g. `stellar`, `xlm`) to `owner`,
+    /// normally the Registrar that will issue names under it. Returns its namehash.
+    pub fn create_tld(env: Env, admin: Address, label: Bytes, owner: Address) -> BytesN<32> {
+        admin.require_auth();
+        if admin != Self::read_admin(&env) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        if Self::is_zero_account(&env, &owner) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let root = Self::root(&env);
+        let namehash = Self::subnode(&env, &root, &label);
+        if Self::read_owner(&env, &namehash).is_some() {
+            panic_with_error!(&env, RegistryError::TldExists);
+        }
+        Self::index_subnode(&env, &root, &label, &namehash);
+        Self::write_owner(&env, namehash.clone(), owner.clone(), owner.clone());
+        EvtTldCreated {
+            namehash: namehash.clone(),
+            label,
+            owner,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+        namehash
+    }
+
+    pub fn admin(env: Env) -> Address {
+        Self::read_admin(&env)
+    }
+
+    /// Replaces this contract's code with `new_wasm_hash` (already uploaded), keeping its
+    /// address and storage. Call `migrate` afterwards so the new code can update old data.
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        admin.require_auth();
+        if admin != Self::read_admin(&env) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        EvtUpgraded {
+            new_wasm_hash,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Runs the storage migrations between the stored layout version and `STORAGE_VERSION`,
+    /// then records the new version. A no-op when storage is already current.
+    pub fn migrate(env: Env, admin: Address) -> u32 {
+        admin.require_auth();
+        if admin != Self::read_admin(&env) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        let from = Self::storage_version(env.clone());
+        if from > STORAGE_VERSION {
+            panic_with_error!(&env, RegistryError::UnsupportedVersion);
+        }
+        if from < STORAGE_VERSION {
+            // Layout changes add their rewrite steps here, keyed on `from`.
+            Self::write_entry(&env, &DataKey::StorageVersion, &STORAGE_VERSION);
+            EvtMigrated {
+                from,
+                to: STORAGE_VERSION,
+                seq: Self::next_event_seq(&env),
+            }
+            .publish(&env);
+        }
+        STORAGE_VERSION
+    }
+
+    /// Layout version of stored data; `0` for deployments that predate versioning, so
+    /// `migrate` runs their rewrite steps.
+    pub fn storage_version(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::StorageVersion)
+            .unwrap_or(0)
+    }
+
+    /// Approves or removes a controller (normally a Registrar contract). Only controllers
+    /// may create first-time owner entries or reclaim expired ones. Emits `controller_changed`.
+    pub fn set_controller(env: Env, controller: Address, approved: bool) {
+        Self::read_admin(&env).require_auth();
+        let key = DataKey::Controller(controller.clone());
+        if approved {
+            Self::write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        EvtControllerChanged {
+            controller,
+            approved,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    pub fn is_controller(env: Env, controller: Address) -> bool {
+        let key = DataKey::Controller(controller);
+        let approved = env.storage().persistent().get(&key).unwrap_or(false);
+        // Controllers are written once but read on every registration; keep them live.
+        Self::extend_entry(&env, &key);
+        approved
+    }
+
+    fn read_admin(env: &Env) -> Address {
+        let admin = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic_with_error!(env, RegistryError::NotInitialized));
+        Self::extend_entry(env, &DataKey::Admin);
+        admin
+    }
+
+    fn root(env: &Env) -> BytesN<32> {
+        BytesN::from_array(env, &[0u8; 32])
+    }
+
     fn is_zero_account(env: &Env, address: &Address) -> bool {
         let zero = Address::from_str(env, ZERO_ACCOUNT_STR);
         address == &zero
     }
 
+    /// Claims the `seq` for the next event.
+    fn next_event_seq(env: &Env) -> u64 {
+        let storage = env.storage().instance();
+        let seq = storage.get::<_, u64>(&DataKey::EventSeq).unwrap_or(0) + 1;
+        storage.set(&DataKey::EventSeq, &seq);
+        storage.extend_ttl(TTL_THRESHOLD, TTL_EXTEND_TO);
+        seq
+    }
+
+    /// `seq` of the latest event, or `0` before the first one. An indexer that has seen
+    /// every event up to this value is caught up.
+    pub fn event_seq(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::EventSeq)
+            .unwrap_or(0)
+    }
+
+    /// Persists `value` and extends the entry's TTL so live names are not archived.
+    fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+        let storage = env.storage().persistent();
+        storage.set(key, value);
+        storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+
+    fn read_index(env: &Env, key: &DataKey) -> Vec<BytesN<32>> {
+        env.storage()
+            .persistent()
+            .get(key)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn index_add(env: &Env, key: &DataKey, namehash: &BytesN<32>) {
+        let mut names = Self::read_index(env, key);
+        if names.first_index_of(namehash).is_none() {
+            names.push_back(namehash.clone());
+            Self::write_entry(env, key, &names);
+        }
+    }
+
+    fn index_remove(env: &Env, key: &DataKey, namehash: &BytesN<32>) {
+        let mut names = Self::read_index(env, key);
+        if let Some(index) = names.first_index_of(namehash) {
+            names.remove(index);
+            if names.is_empty() {
+                env.storage().persistent().remove(key);
+            } else {
+                Self::write_entry(env, key, &names);
+            }
+        }
+    }
+
+    /// Records `node` as a child of `parent` (and its plaintext `label`) the first time it
+    /// gets an owner.
+    fn index_subnode(env: &Env, parent: &BytesN<32>, label: &Bytes, node: &BytesN<32>) {
+        if Self::read_owner(env, node).is_none() {
+            Self::index_add(env, &DataKey::Subnodes(parent.clone()), node);
+            Self::write_entry(env, &DataKey::Parent(node.clone()), parent);
+            Self::write_entry(env, &DataKey::Label(node.clone()), label);
+        }
+    }
+
+    fn extend_entry(env: &Env, key: &DataKey) {
+        let storage = env.storage().persistent();
+        if storage.has(key) {
+            storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+        }
+    }
+
     fn read_owner(env: &Env, namehash: &BytesN<32>) -> Option<Address> {
         env.storage()
             .persistent()
@@ -81,67 +703,981 @@ impl Registry {
             .get(&DataKey::Resolver(namehash.clone()))
     }
 
+    /// Stores or, for `None`, removes the expiry of `namehash`, emitting `expiry_changed`
+    /// when the value differs from the stored one.
+    fn write_expiry(env: &Env, namehash: &BytesN<32>, expires_at: Option<u64>, reason: &str) {
+        let old = Self::read_expires(env, namehash);
+        let key = DataKey::Expires(namehash.clone());
+        match expires_at {
+            Some(value) => Self::write_entry(env, &key, &value),
+            None => env.storage().persistent().remove(&key),
+        }
+        if old != expires_at {
+            Self::bump_owner_nonce(env, namehash);
+            EvtExpiryChanged {
+                namehash: namehash.clone(),
+                old,
+                new: expires_at,
+                reason: Symbol::new(env, reason),
+                seq: Self::next_event_seq(env),
+            }
+            .publish(env);
+        }
+    }
+
     pub(crate) fn read_expires(env: &Env, namehash: &BytesN<32>) -> Option<u64> {
         env.storage()
             .persistent()
             .get(&DataKey::Expires(namehash.clone()))
     }
 
-    // --- Stubs to be implemented later ---
-    pub fn set_owner(env: Env, namehash: BytesN<32>, new_owner: Address) {
-        if Self::is_zero_account(&env, &new_owner) {
-            panic!("zero owner not allowed");
+    fn read_approved(env: &Env, namehash: &BytesN<32>) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Approved(namehash.clone()))
+    }
+
+    fn read_flags(env: &Env, namehash: &BytesN<32>) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Flags(namehash.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Exported names are frozen whole, so `flags::EXPORTED` always counts.
+    fn require_unlocked(env: &Env, namehash: &BytesN<32>, flag: u32) {
+        if Self::read_flags(env, namehash) & (flag | flags::EXPORTED) != 0 {
+            panic_with_error!(env, RegistryError::Locked);
         }
-        let key = DataKey::Owner(namehash.clone());
-        let storage = env.storage().persistent();
-        let current_owner: Option<Address> = storage.get(&key);
+    }
 
-        match current_owner.as_ref() {
-            Some(owner) => owner.require_auth(),
-            None => new_owner.require_auth(),
+    /// Panics unless `caller` authorized this call and is the owner of `namehash`, an
+    /// operator approved via `set_approval_for_all`, or the name's single `approve`d
+    /// delegate. Returns the owner.
+    fn require_owner_or_operator(env: &Env, caller: &Address, namehash: &BytesN<32>) -> Address {
+        let owner = Self::read_owner(env, namehash)
+            .unwrap_or_else(|| panic_with_error!(env, RegistryError::NotFound));
+        caller.require_auth();
+        if caller != &owner
+            && !Self::is_approved_for_all(env.clone(), owner.clone(), caller.clone())
+            && Self::read_approved(env, namehash).as_ref() != Some(caller)
+        {
+            panic_with_error!(env, RegistryError::NotAuthorized);
         }
+        owner
+    }
 
-        env.storage().persistent().set(&key, &new_owner);
+    /// Whether `caller` owns the node `namehash` was created under.
+    fn owns_parent(env: &Env, caller: &Address, namehash: &BytesN<32>) -> bool {
+        let parent = env
+            .storage()
+            .persistent()
+            .get::<_, BytesN<32>>(&DataKey::Parent(namehash.clone()));
+        parent
+            .and_then(|parent| Self::read_owner(env, &parent))
+            .as_ref()
+            == Some(caller)
+    }
+
+    /// Expiry writes are scoped per TLD: `caller` must have authorized the call, be an
+    /// approved controller, and own the name's parent, so one TLD's registrar cannot rewrite
+    /// another's terms.
+    fn require_parent_controller(env: &Env, caller: &Address, namehash: &BytesN<32>) {
+        caller.require_auth();
+        if !Self::is_controller(env.clone(), caller.clone()) {
+            panic_with_error!(env, RegistryError::NotAuthorized);
+        }
+        if Self::read_owner(env, namehash).is_none() {
+            panic_with_error!(env, RegistryError::NotFound);
+        }
+        if !Self::owns_parent(env, caller, namehash) {
+            panic_with_error!(env, RegistryError::NotAuthorized);
+        }
+    }
 
-        let from = current_owner.unwrap_or_else(|| new_owner.clone());
+    /// Writes a new owner, drops any per-name delegate and subnode operator, and emits
+    /// `transfer`.
+    fn write_owner(env: &Env, namehash: BytesN<32>, from: Address, to: Address) {
+        if let Some(previous) = Self::read_owner(env, &namehash) {
+            Self::index_remove(env, &DataKey::OwnedNames(previous), &namehash);
+        }
+        Self::index_add(env, &DataKey::OwnedNames(to.clone()), &namehash);
+        Self::write_entry(env, &DataKey::Owner(namehash.clone()), &to);
+        Self::record_history(env, &namehash, &to);
+        Self::bump_owner_nonce(env, &namehash);
+        let storage = env.storage().persistent();
+        storage.remove(&DataKey::Approved(namehash.clone()));
+        storage.remove(&DataKey::SubnodeOperator(namehash.clone()));
+        storage.remove(&DataKey::TransferOffer(namehash.clone()));
         EvtTransfer {
             namehash,
             from,
-            to: new_owner,
+            to,
+            seq: Self::next_event_seq(env),
         }
-        .publish(&env);
+        .publish(env);
     }
 
-    pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
-        Self::read_owner(&env, &namehash).unwrap_or_else(|| panic!("owner not set"))
+    fn record_history(env: &Env, namehash: &BytesN<32>, owner: &Address) {
+        let key = DataKey::History(namehash.clone());
+        let mut history: Vec<(Address, u64)> = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| Vec::new(env));
+        if history.len() >= HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back((owner.clone(), env.ledger().timestamp()));
+        Self::write_entry(env, &key, &history);
+    }
+
+    /// Sets the owner of the child node `label` under `parent` and returns its namehash.
+    /// Creating an unowned node needs the parent's owner (or its operator, delegate, or
+    /// subnode operator); an approved controller must own the parent itself. Nobody creates
+    /// nodes under an expired parent (`Expired`). Such a controller may also reclaim a node
+    /// once its grace period has ended. Otherwise the node's own owner, operator, or delegate
+    /// may reassign it.
+    pub fn set_owner(
+        env: Env,
+        caller: Address,
+        parent: BytesN<32>,
+        label: Bytes,
+        new_owner: Address,
+    ) -> BytesN<32> {
+        if Self::is_zero_account(&env, &new_owner) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let namehash = Self::subnode(&env, &parent, &label);
+        let from = match Self::read_owner(&env, &namehash) {
+            Some(owner)
+                if caller != owner
+                    && Self::is_controller(env.clone(), caller.clone())
+                    && Self::read_owner(&env, &parent).as_ref() == Some(&caller)
+                    && Self::is_released(&env, &namehash) =>
+            {
+                caller.require_auth();
+                // The previous holder's locks lapse with their term.
+                env.storage()
+                    .persistent()
+                    .remove(&DataKey::Flags(namehash.clone()));
+                owner
+            }
+            Some(_) => {
+                let owner = Self::require_owner_or_operator(&env, &caller, &namehash);
+                Self::require_unlocked(&env, &namehash, flags::FROZEN_TRANSFER);
+                owner
+            }
+            None => {
+                Self::require_unlocked(&env, &parent, flags::SUBNODES_LOCKED);
+                if Self::is_expired(&env, &parent) {
+                    panic_with_error!(&env, RegistryError::Expired);
+                }
+                let controller = Self::is_controller(env.clone(), caller.clone())
+                    && Self::read_owner(&env, &parent).as_ref() == Some(&caller);
+                if controller
+                    || Self::subnode_operator(env.clone(), parent.clone()).as_ref() == Some(&caller)
+                {
+                    caller.require_auth();
+                } else {
+                    Self::require_owner_or_operator(&env, &caller, &parent);
+                }
+                new_owner.clone()
+            }
+        };
+        Self::index_subnode(&env, &parent, &label, &namehash);
+        Self::write_owner(&env, namehash.clone(), from, new_owner);
+        namehash
     }
 
-    pub fn transfer(env: Env, namehash: BytesN<32>, to: Address) {
-        let current_owner =
-            Self::read_owner(&env, &namehash).unwrap_or_else(|| panic!("owner not set"));
-        current_owner.require_auth();
-        Self::set_owner(env, namehash, to);
+    /// True once `expires_at` plus the parent's grace period has passed. Nodes without an
+    /// expiry never lapse.
+    fn is_released(env: &Env, namehash: &BytesN<32>) -> bool {
+        Self::read_expires(env, namehash).is_some_and(|expires| {
+            env.ledger().timestamp() > expires.saturating_add(Self::grace_of(env, namehash))
+        })
     }
 
-    pub fn set_resolver(env: Env, namehash: BytesN<32>, resolver: Address) {
-        if Self::is_zero_account(&env, &resolver) {
-            panic!("zero resolver not allowed");
-        }
-        let owner = Self::read_owner(&env, &namehash).unwrap_or_else(|| panic!("owner not set"));
-        owner.require_auth();
+    /// Grace period that applies to `namehash`: the one set on its parent, or
+    /// `GRACE_PERIOD_SECONDS`.
+    fn grace_of(env: &Env, namehash: &BytesN<32>) -> u64 {
+        let storage = env.storage().persistent();
+        storage
+            .get::<_, BytesN<32>>(&DataKey::Parent(namehash.clone()))
+            .and_then(|parent| storage.get(&DataKey::GracePeriod(parent)))
+            .unwrap_or(GRACE_PERIOD_SECONDS)
+    }
+
+    /// Grace period granted to children of `node` after they expire, before `burn` and
+    /// controller reclaims apply and `owner_checked` stops reporting them.
+    pub fn grace_period(env: Env, node: BytesN<32>) -> u64 {
         env.storage()
             .persistent()
-            .set(&DataKey::Resolver(namehash.clone()), &resolver);
-        EvtResolverChanged { namehash, resolver }.publish(&env);
+            .get(&DataKey::GracePeriod(node))
+            .unwrap_or(GRACE_PERIOD_SECONDS)
     }
 
-    pub fn resolver(env: Env, namehash: BytesN<32>) -> Address {
-        Self::read_resolver(&env, &namehash).unwrap_or_else(|| panic!("resolver not set"))
+    /// Controller-only: sets the grace period of `node`'s children, so the Registry lapses
+    /// names on the same schedule as the controller issuing them. `caller` must own `node`.
+    /// Emits `grace_period_changed`.
+    pub fn set_grace_period(env: Env, caller: Address, node: BytesN<32>, grace_secs: u64) {
+        caller.require_auth();
+        if !Self::is_controller(env.clone(), caller.clone())
+            || Self::read_owner(&env, &node).as_ref() != Some(&caller)
+        {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        Self::write_entry(&env, &DataKey::GracePeriod(node.clone()), &grace_secs);
+        EvtGracePeriodChanged {
+            node,
+            grace_secs,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
     }
 
-    pub fn renew(env: Env, namehash: BytesN<32>) {
-        let owner = Self::read_owner(&env, &namehash).unwrap_or_else(|| panic!("owner not set"));
-        owner.require_auth();
+    fn is_expired(env: &Env, namehash: &BytesN<32>) -> bool {
+        Self::read_expires(env, namehash).is_some_and(|expires| env.ledger().timestamp() > expires)
+    }
+
+    pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
+        Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound))
+    }
+
+    /// Non-aborting `owner`: `None` if the name has never been assigned or was burned.
+    pub fn get_owner(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        Self::read_owner(&env, &namehash)
+    }
+
+    /// Expiry-aware `owner`: `None` if unset or if the name's grace period has ended.
+    pub fn owner_checked(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        if Self::is_released(&env, &namehash) {
+            return None;
+        }
+        Self::read_owner(&env, &namehash)
+    }
+
+    pub fn transfer(env: Env, caller: Address, namehash: BytesN<32>, to: Address) {
+        if Self::is_zero_account(&env, &to) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let from = Self::require_owner_or_operator(&env, &caller, &namehash);
+        Self::require_unlocked(&env, &namehash, flags::FROZEN_TRANSFER);
+        Self::write_owner(&env, namehash, from, to);
+    }
+
+    /// `transfer` that also clears the resolver pointer, so the recipient starts without a
+    /// resolver the previous owner controls. Emits `transfer`, then `resolver_cleared` if a
+    /// resolver was set.
+    pub fn transfer_clean(env: Env, caller: Address, namehash: BytesN<32>, to: Address) {
+        if Self::is_zero_account(&env, &to) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let from = Self::require_owner_or_operator(&env, &caller, &namehash);
+        Self::require_unlocked(
+            &env,
+            &namehash,
+            flags::FROZEN_TRANSFER | flags::FROZEN_RESOLVER,
+        );
+        Self::write_owner(&env, namehash.clone(), from, to);
+        let key = DataKey::Resolver(namehash.clone());
+        if env.storage().persistent().has(&key) {
+            env.storage().persistent().remove(&key);
+            EvtResolverCleared {
+                namehash,
+                seq: Self::next_event_seq(&env),
+            }
+            .publish(&env);
+        }
+    }
+
+    pub fn set_resolver(env: Env, caller: Address, namehash: BytesN<32>, resolver: Address) {
+        if Self::is_zero_account(&env, &resolver) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        Self::require_owner_or_operator(&env, &caller, &namehash);
+        Self::require_unlocked(&env, &namehash, flags::FROZEN_RESOLVER);
+        Self::write_entry(&env, &DataKey::Resolver(namehash.clone()), &resolver);
+        EvtResolverChanged {
+            namehash,
+            resolver,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Grants or revokes `operator`'s right to call `set_owner`, `transfer`, and
+    /// `set_resolver` for every name `owner` holds. Emits `approval_for_all`.
+    pub fn set_approval_for_all(env: Env, owner: Address, operator: Address, approved: bool) {
+        owner.require_auth();
+        if owner == operator {
+            panic_with_error!(&env, RegistryError::InvalidOperator);
+        }
+        let key = DataKey::Operator(owner.clone(), operator.clone());
+        if approved {
+            Self::write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        EvtApprovalForAll {
+            owner,
+            operator,
+            approved,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    pub fn is_approved_for_all(env: Env, owner: Address, operator: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Operator(owner, operator))
+            .unwrap_or(false)
+    }
+
+    /// Lets `delegate` manage this one name until the next ownership change. Passing the
+    /// zero strkey clears the approval. Emits `approval`.
+    pub fn approve(env: Env, namehash: BytesN<32>, delegate: Address) {
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        owner.require_auth();
+        let key = DataKey::Approved(namehash.clone());
+        if Self::is_zero_account(&env, &delegate) {
+            env.storage().persistent().remove(&key);
+        } else {
+            Self::write_entry(&env, &key, &delegate);
+        }
+        EvtApproval {
+            namehash,
+            owner,
+            approved: delegate,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Offers the name to `to`, who takes it with `accept_transfer` before `deadline`. Unlike
+    /// `transfer`, nothing moves until the recipient signs, so a name cannot be pushed to an
+    /// address that will never sign. Replaces any earlier offer; any ownership change drops
+    /// it. Emits `transfer_offered`.
+    pub fn offer_transfer(env: Env, namehash: BytesN<32>, to: Address, deadline: u64) {
+        if Self::is_zero_account(&env, &to) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        owner.require_auth();
+        Self::require_unlocked(&env, &namehash, flags::FROZEN_TRANSFER);
+        if deadline <= env.ledger().timestamp() {
+            panic_with_error!(&env, RegistryError::InvalidExpiry);
+        }
+        let offer = TransferOffer {
+            to: to.clone(),
+            deadline,
+        };
+        Self::write_entry(&env, &DataKey::TransferOffer(namehash.clone()), &offer);
+        EvtTransferOffered {
+            namehash,
+            owner,
+            to,
+            deadline,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Takes a name offered with `offer_transfer`. Only the offer's recipient, up to and
+    /// including its `deadline`. Emits `transfer`.
+    pub fn accept_transfer(env: Env, namehash: BytesN<32>) {
+        let offer = Self::read_transfer_offer(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NoOffer));
+        offer.to.require_auth();
+        Self::require_unlocked(&env, &namehash, flags::FROZEN_TRANSFER);
+        let from = Self::owner(env.clone(), namehash.clone());
+        Self::write_owner(&env, namehash, from, offer.to);
+    }
+
+    /// Withdraws the owner's pending offer before it is accepted. Emits
+    /// `transfer_offer_cancelled`.
+    pub fn cancel_transfer_offer(env: Env, namehash: BytesN<32>) {
+        let owner = Self::owner(env.clone(), namehash.clone());
+        owner.require_auth();
+        if Self::read_transfer_offer(&env, &namehash).is_none() {
+            panic_with_error!(&env, RegistryError::NoOffer);
+        }
+        env.storage()
+            .persistent()
+            .remove(&DataKey::TransferOffer(namehash.clone()));
+        EvtTransferOfferCancelled {
+            namehash,
+            owner,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// The name's last 8 owners with the ledger time each took over, oldest first, so escrow
+    /// and marketplace contracts can check provenance on-chain. Every ownership write counts,
+    /// including creation, re-registration, and transfers to the current owner; the history
+    /// survives `burn` and `release`.
+    pub fn history(env: Env, namehash: BytesN<32>) -> Vec<(Address, u64)> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::History(namehash))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// The pending offer, or `None` if there is none or its deadline has passed.
+    pub fn transfer_offer(env: Env, namehash: BytesN<32>) -> Option<TransferOffer> {
+        Self::read_transfer_offer(&env, &namehash)
+    }
+
+    fn read_transfer_offer(env: &Env, namehash: &BytesN<32>) -> Option<TransferOffer> {
+        let offer: TransferOffer = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TransferOffer(namehash.clone()))?;
+        if env.ledger().timestamp() > offer.deadline {
+            return None;
+        }
+        Some(offer)
+    }
+
+    /// Lets `lessee` manage the name's Resolver records until `expires_at` without being
+    /// able to transfer it. The lease cannot outlive the name's own expiry, survives
+    /// ownership changes, and cannot be replaced while it runs. Emits `lease_granted`.
+    pub fn lease(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        lessee: Address,
+        expires_at: u64,
+    ) {
+        if Self::is_zero_account(&env, &lessee) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        Self::require_owner_or_operator(&env, &caller, &namehash);
+        if expires_at <= env.ledger().timestamp()
+            || Self::read_expires(&env, &namehash).is_some_and(|expires| expires_at > expires)
+        {
+            panic_with_error!(&env, RegistryError::InvalidExpiry);
+        }
+        if Self::read_lease(&env, &namehash).is_some() {
+            panic_with_error!(&env, RegistryError::LeaseActive);
+        }
+        let lease = Lease {
+            lessee: lessee.clone(),
+            expires_at,
+        };
+        Self::write_entry(&env, &DataKey::Lease(namehash.clone()), &lease);
+        EvtLeaseGranted {
+            namehash,
+            lessee,
+            expires_at,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Ends a lease early. The lessee may give it up at any time; once it has lapsed the
+    /// owner (or an operator or delegate) may clear it too. Emits `lease_ended`.
+    pub fn end_lease(env: Env, caller: Address, namehash: BytesN<32>) {
+        let key = DataKey::Lease(namehash.clone());
+        let lease: Lease = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NoLease));
+        if caller == lease.lessee {
+            caller.require_auth();
+        } else if Self::read_lease(&env, &namehash).is_some() {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        } else {
+            Self::require_owner_or_operator(&env, &caller, &namehash);
+        }
+        env.storage().persistent().remove(&key);
+        EvtLeaseEnded {
+            namehash,
+            lessee: lease.lessee,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Current lessee, or `None` if there is no lease or it has lapsed.
+    pub fn lessee(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        Self::read_lease(&env, &namehash).map(|lease| lease.lessee)
+    }
+
+    /// End of the current lease, or `None` if there is no running lease.
+    pub fn lease_expires(env: Env, namehash: BytesN<32>) -> Option<u64> {
+        Self::read_lease(&env, &namehash).map(|lease| lease.expires_at)
+    }
+
+    /// The stored lease if it is still running and the name itself has not expired.
+    fn read_lease(env: &Env, namehash: &BytesN<32>) -> Option<Lease> {
+        let lease: Lease = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Lease(namehash.clone()))?;
+        if env.ledger().timestamp() >= lease.expires_at || Self::is_expired(env, namehash) {
+            return None;
+        }
+        Some(lease)
+    }
+
+    pub fn get_approved(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        Self::read_approved(&env, &namehash)
+    }
+
+    /// Lets `operator` create unowned children of `namehash` through `set_owner`, without any
+    /// other right over the name or its existing children. Meant for a subregistrar contract
+    /// selling subnames. Cleared on every ownership change; `None` clears it now. Emits
+    /// `subnode_operator_changed`.
+    pub fn set_subnode_operator(env: Env, namehash: BytesN<32>, operator: Option<Address>) {
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        owner.require_auth();
+        let key = DataKey::SubnodeOperator(namehash.clone());
+        match &operator {
+            Some(operator) => Self::write_entry(&env, &key, operator),
+            None => env.storage().persistent().remove(&key),
+        }
+        EvtSubnodeOperatorChanged {
+            namehash,
+            operator,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    pub fn subnode_operator(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::SubnodeOperator(namehash))
+    }
+
+    /// Adds `flags` (see the `flags` module) to the name's locks. Only the owner may set
+    /// them, and set bits cannot be cleared, so `flags` must include the current value.
+    /// Emits `flags_changed`.
+    pub fn set_flags(env: Env, namehash: BytesN<32>, flags: u32) {
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        owner.require_auth();
+        let current = Self::read_flags(&env, &namehash);
+        if flags & !flags::ALL != 0 || flags & current != current {
+            panic_with_error!(&env, RegistryError::InvalidFlags);
+        }
+        Self::write_entry(&env, &DataKey::Flags(namehash.clone()), &flags);
+        EvtFlagsChanged {
+            namehash,
+            flags,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    pub fn flags(env: Env, namehash: BytesN<32>) -> u32 {
+        Self::read_flags(&env, &namehash)
+    }
+
+    /// Sets or removes the authority (normally a relayer's contract or multisig) allowed to
+    /// `import_name`. Admin only. Emits `bridge_changed`.
+    pub fn set_bridge(env: Env, bridge: Option<Address>) {
+        Self::read_admin(&env).require_auth();
+        match &bridge {
+            Some(bridge) => Self::write_entry(&env, &DataKey::Bridge, bridge),
+            None => env.storage().persistent().remove(&DataKey::Bridge),
+        }
+        EvtBridgeChanged {
+            bridge,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    pub fn bridge(env: Env) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Bridge)
+    }
+
+    /// Freezes the name here (`flags::EXPORTED`) and emits `name_exported` for a relayer to
+    /// mirror it to `target_address` on `target_chain`. Only the live owner may export. The
+    /// name stays owned here and keeps resolving, but nothing about it can change until the
+    /// bridge brings it back with `import_name`.
+    pub fn export_name(
+        env: Env,
+        owner: Address,
+        namehash: BytesN<32>,
+        target_chain: Symbol,
+        target_address: Bytes,
+    ) {
+        owner.require_auth();
+        if Self::read_owner(&env, &namehash).as_ref() != Some(&owner) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        if Self::is_expired(&env, &namehash) {
+            panic_with_error!(&env, RegistryError::Expired);
+        }
+        Self::require_unlocked(&env, &namehash, flags::EXPORTED);
+        let flags = Self::read_flags(&env, &namehash) | flags::EXPORTED;
+        Self::write_entry(&env, &DataKey::Flags(namehash.clone()), &flags);
+        EvtNameExported {
+            name: Self::name_of(env.clone(), namehash.clone()),
+            expires_at: Self::read_expires(&env, &namehash),
+            namehash,
+            owner,
+            target_chain,
+            target_address,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Returns an exported name from `source_chain`: clears `flags::EXPORTED` and gives the
+    /// name to `owner`, whoever held it on the other chain. Only the configured bridge may
+    /// call. Emits `transfer` and then `name_imported`.
+    pub fn import_name(
+        env: Env,
+        bridge: Address,
+        namehash: BytesN<32>,
+        owner: Address,
+        source_chain: Symbol,
+    ) {
+        bridge.require_auth();
+        if Self::bridge(env.clone()).as_ref() != Some(&bridge) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        if Self::is_zero_account(&env, &owner) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let flags = Self::read_flags(&env, &namehash);
+        if flags & flags::EXPORTED == 0 {
+            panic_with_error!(&env, RegistryError::NotExported);
+        }
+        let from = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        let key = DataKey::Flags(namehash.clone());
+        match flags & !flags::EXPORTED {
+            0 => env.storage().persistent().remove(&key),
+            rest => Self::write_entry(&env, &key, &rest),
+        }
+        Self::write_owner(&env, namehash.clone(), from, owner.clone());
+        EvtNameImported {
+            namehash,
+            owner,
+            source_chain,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Opens the one-time window for migrating a legacy registry in with `import_names`:
+    /// only names committed to by `snapshot_root` (see `migration_leaf`) are accepted, until
+    /// `closes_at`. A window can be opened once per deployment; later calls abort with
+    /// `MigrationClosed`. Admin only. Emits `migration_opened`.
+    pub fn open_migration(env: Env, admin: Address, snapshot_root: BytesN<32>, closes_at: u64) {
+        admin.require_auth();
+        if admin != Self::read_admin(&env) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        if env.storage().persistent().has(&DataKey::Migration) {
+            panic_with_error!(&env, RegistryError::MigrationClosed);
+        }
+        if closes_at <= env.ledger().timestamp() {
+            panic_with_error!(&env, RegistryError::InvalidExpiry);
+        }
+        let window = MigrationWindow {
+            snapshot_root: snapshot_root.clone(),
+            closes_at,
+        };
+        Self::write_entry(&env, &DataKey::Migration, &window);
+        EvtMigrationOpened {
+            snapshot_root,
+            closes_at,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Ends the migration window now instead of at `closes_at`. Admin only. Emits
+    /// `migration_closed`.
+    pub fn close_migration(env: Env, admin: Address) {
+        admin.require_auth();
+        if admin != Self::read_admin(&env) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        let mut window = Self::open_window(&env);
+        window.closes_at = env.ledger().timestamp();
+        Self::write_entry(&env, &DataKey::Migration, &window);
+        EvtMigrationClosed {
+            closed_at: window.closes_at,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    pub fn migration_window(env: Env) -> Option<MigrationWindow> {
+        env.storage().persistent().get(&DataKey::Migration)
+    }
+
+    /// Creates each of `names` under its parent with the owner and expiry it had in the
+    /// legacy registry, in order, so a parent must be imported before its children. Each
+    /// name must be unowned here and carry a Merkle proof against the window's snapshot
+    /// root. Imports cannot be undone. Admin only, while the migration window is open. Emits
+    /// `transfer`, `renew` for a name with an expiry, and then `name_imported` (with
+    /// `source_chain` `migration`) per name.
+    pub fn import_names(env: Env, admin: Address, names: Vec<ImportedName>) {
+        admin.require_auth();
+        if admin != Self::read_admin(&env) {
+            panic_with_error!(&env, RegistryError::NotAuthorized);
+        }
+        let window = Self::open_window(&env);
+        let source_chain = Symbol::new(&env, "migration");
+        for name in names.iter() {
+            if merkle_root(&env, migration_leaf(&env, &name), &name.proof) != window.snapshot_root {
+                panic_with_error!(&env, RegistryError::InvalidProof);
+            }
+            if Self::is_zero_account(&env, &name.owner) {
+                panic_with_error!(&env, RegistryError::ZeroAddress);
+            }
+            if Self::read_owner(&env, &name.parent).is_none() {
+                panic_with_error!(&env, RegistryError::NotFound);
+            }
+            let namehash = Self::subnode(&env, &name.parent, &name.label);
+            if Self::read_owner(&env, &namehash).is_some() {
+                panic_with_error!(&env, RegistryError::NameTaken);
+            }
+            Self::index_subnode(&env, &name.parent, &name.label, &namehash);
+            Self::write_owner(
+                &env,
+                namehash.clone(),
+                name.owner.clone(),
+                name.owner.clone(),
+            );
+            if let Some(expires_at) = name.expires_at {
+                Self::write_expiry(&env, &namehash, Some(expires_at), "import");
+                EvtRenew {
+                    namehash: namehash.clone(),
+                    expires_at,
+                    seq: Self::next_event_seq(&env),
+                }
+                .publish(&env);
+            }
+            EvtNameImported {
+                namehash,
+                owner: name.owner,
+                source_chain: source_chain.clone(),
+                seq: Self::next_event_seq(&env),
+            }
+            .publish(&env);
+        }
+    }
+
+    fn open_window(env: &Env) -> MigrationWindow {
+        let window: MigrationWindow = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Migration)
+            .unwrap_or_else(|| panic_with_error!(env, RegistryError::MigrationClosed));
+        if env.ledger().timestamp() >= window.closes_at {
+            panic_with_error!(env, RegistryError::MigrationClosed);
+        }
+        window
+    }
+
+    pub fn clear_resolver(env: Env, caller: Address, namehash: BytesN<32>) {
+        Self::require_owner_or_operator(&env, &caller, &namehash);
+        Self::require_unlocked(&env, &namehash, flags::FROZEN_RESOLVER);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Resolver(namehash.clone()));
+        EvtResolverCleared {
+            namehash,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Deletes owner, resolver, expiry, and delegate for a node whose expiry plus its
+    /// parent's grace period has passed. Anyone may call it. Emits `burn`.
+    pub fn burn(env: Env, namehash: BytesN<32>) {
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        let expires = Self::read_expires(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        if env.ledger().timestamp() <= expires.saturating_add(Self::grace_of(&env, &namehash)) {
+            panic_with_error!(&env, RegistryError::GraceActive);
+        }
+
+        Self::clear_node(&env, &namehash, &owner, "burn");
+        EvtBurn {
+            namehash,
+            owner,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Gives up a name before it expires, clearing its owner, resolver, and expiry so it can
+    /// be claimed again at once. Subnodes keep their owners, as with `burn`. The owner (or an
+    /// operator or delegate) may release directly; an approved controller may release names
+    /// under a parent it owns, on the owner's behalf after checking ownership itself. Emits
+    /// `released`.
+    pub fn release(env: Env, caller: Address, namehash: BytesN<32>) {
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        if Self::is_controller(env.clone(), caller.clone())
+            && Self::owns_parent(&env, &caller, &namehash)
+        {
+            caller.require_auth();
+        } else {
+            Self::require_owner_or_operator(&env, &caller, &namehash);
+        }
+        Self::require_unlocked(&env, &namehash, flags::EXPORTED);
+        Self::clear_node(&env, &namehash, &owner, "release");
+        EvtReleased {
+            namehash,
+            owner,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Permissionless signal for notifier services: emits `expiry_warning` the first time it
+    /// is called in each phase of the current term, i.e. once within the last 30 days before
+    /// `expires_at` and once during the grace period. Renewing starts a new term, so the
+    /// warnings fire again as it runs down. Returns whether an event was emitted.
+    pub fn poke_expiry(env: Env, namehash: BytesN<32>) -> bool {
+        let owner = Self::read_owner(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        let expires_at = Self::read_expires(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        let now = env.ledger().timestamp();
+        let phase = if now > expires_at.saturating_add(Self::grace_of(&env, &namehash)) {
+            return false;
+        } else if now > expires_at {
+            ExpiryPhase::Grace
+        } else if expires_at - now <= EXPIRY_WARNING_SECONDS {
+            ExpiryPhase::Expiring
+        } else {
+            return false;
+        };
+
+        let key = DataKey::ExpiryWarned(namehash.clone());
+        let warned: Option<(u64, ExpiryPhase)> = env.storage().persistent().get(&key);
+        if warned == Some((expires_at, phase)) {
+            return false;
+        }
+        Self::write_entry(&env, &key, &(expires_at, phase));
+        EvtExpiryWarning {
+            namehash,
+            owner,
+            expires_at,
+            phase,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+        true
+    }
+
+    /// Removes a node's ownership data and starts a new record generation. `reason` is
+    /// reported in `expiry_changed`.
+    fn clear_node(env: &Env, namehash: &BytesN<32>, owner: &Address, reason: &str) {
+        Self::write_expiry(env, namehash, None, reason);
+        let storage = env.storage().persistent();
+        storage.remove(&DataKey::Owner(namehash.clone()));
+        storage.remove(&DataKey::Resolver(namehash.clone()));
+        storage.remove(&DataKey::Approved(namehash.clone()));
+        storage.remove(&DataKey::SubnodeOperator(namehash.clone()));
+        storage.remove(&DataKey::Lease(namehash.clone()));
+        storage.remove(&DataKey::TransferOffer(namehash.clone()));
+        storage.remove(&DataKey::ExpiryWarned(namehash.clone()));
+        storage.remove(&DataKey::Flags(namehash.clone()));
+        Self::index_remove(env, &DataKey::OwnedNames(owner.clone()), namehash);
+        if let Some(parent) = storage.get::<_, BytesN<32>>(&DataKey::Parent(namehash.clone())) {
+            Self::index_remove(env, &DataKey::Subnodes(parent), namehash);
+        }
+
+        // The version outlives the node so a later claimant never sees the old records.
+        Self::write_record_version(env, namehash.clone());
+    }
+
+    fn write_record_version(env: &Env, namehash: BytesN<32>) -> u32 {
+        let key = DataKey::RecordVersion(namehash.clone());
+        let version = env
+            .storage()
+            .persistent()
+            .get::<_, u32>(&key)
+            .unwrap_or(0)
+            .checked_add(1)
+            .unwrap_or_else(|| panic_with_error!(env, RegistryError::Overflow));
+        Self::write_entry(env, &key, &version);
+        Self::bump_owner_nonce(env, &namehash);
+        EvtRecordVersionChanged {
+            namehash,
+            version,
+            seq: Self::next_event_seq(env),
+        }
+        .publish(env);
+        version
+    }
+
+    /// Starts a fresh record generation for `namehash`. Resolvers key records by this
+    /// version, so everything written under the previous one stops resolving. The
+    /// Registrar calls it on every registration. Returns the new version.
+    pub fn bump_record_version(env: Env, caller: Address, namehash: BytesN<32>) -> u32 {
+        Self::require_owner_or_operator(&env, &caller, &namehash);
+        Self::write_record_version(&env, namehash)
+    }
+
+    fn bump_owner_nonce(env: &Env, namehash: &BytesN<32>) {
+        let key = DataKey::OwnerNonce(namehash.clone());
+        let nonce: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+        Self::write_entry(env, &key, &nonce.wrapping_add(1));
+    }
+
+    /// Changes whenever `namehash`'s owner, expiry, or record version does, so callers such
+    /// as the Resolver can keep what they read about the name until it moves. `0` until the
+    /// name is first written.
+    pub fn owner_nonce(env: Env, namehash: BytesN<32>) -> u64 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::OwnerNonce(namehash))
+            .unwrap_or(0)
+    }
+
+    /// Current record generation for `namehash`; `0` until first bumped.
+    pub fn record_version(env: Env, namehash: BytesN<32>) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RecordVersion(namehash))
+            .unwrap_or(0)
+    }
+
+    pub fn resolver(env: Env, namehash: BytesN<32>) -> Address {
+        Self::read_resolver(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound))
+    }
+
+    /// Non-aborting `resolver`: `None` if no resolver is set.
+    pub fn get_resolver(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        Self::read_resolver(&env, &namehash)
+    }
+
+    /// Expiry-aware `resolver`: `None` if unset or if the name's grace period has ended.
+    pub fn resolver_checked(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        if Self::is_released(&env, &namehash) {
+            return None;
+        }
+        Self::read_resolver(&env, &namehash)
+    }
+
+    /// Extends `expires_at` by one year from the later of the current expiry and now. Only
+    /// an approved controller owning the name's parent may call it; owners renew through
+    /// their registrar, which charges for the term. Emits `renew`.
+    pub fn renew(env: Env, caller: Address, namehash: BytesN<32>) {
+        Self::require_parent_controller(&env, &caller, &namehash);
 
         let now = env.ledger().timestamp();
         let current_expiry = Self::read_expires(&env, &namehash).unwrap_or(now);
@@ -153,1361 +1689,3763 @@ impl Registry {
 
         let new_expiry = base
             .checked_add(RENEW_EXTENSION_SECONDS)
-            .unwrap_or_else(|| panic!("expiry overflow"));
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::Overflow));
 
-        env.storage()
-            .persistent()
-            .set(&DataKey::Expires(namehash.clone()), &new_expiry);
+        Self::write_expiry(&env, &namehash, Some(new_expiry), "renew");
+
+        EvtRenew {
+            namehash,
+            expires_at: new_expiry,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Sets an absolute expiry for `namehash`. Only an approved controller owning the name's
+    /// parent may call it, since it charges for the term; registrars use it during
+    /// registration so multi-year terms are written in one call. A live registration cannot
+    /// be shortened (`InvalidExpiry`). Emits `renew`.
+    pub fn set_expiry(env: Env, caller: Address, namehash: BytesN<32>, expires_at: u64) {
+        Self::require_parent_controller(&env, &caller, &namehash);
+        let now = env.ledger().timestamp();
+        let live = Self::read_expires(&env, &namehash).filter(|current| *current > now);
+        if expires_at <= now || live.is_some_and(|current| expires_at < current) {
+            panic_with_error!(&env, RegistryError::InvalidExpiry);
+        }
+
+        Self::write_expiry(&env, &namehash, Some(expires_at), "set_expiry");
+
+        EvtRenew {
+            namehash,
+            expires_at,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Pushes the expiry of a live name forward to `expires_at` on behalf of whoever paid the
+    /// controller, which must own the name's parent; ownership is untouched. Names past their
+    /// grace period cannot be extended. Emits `renew`.
+    pub fn extend_expiry(env: Env, caller: Address, namehash: BytesN<32>, expires_at: u64) {
+        Self::require_parent_controller(&env, &caller, &namehash);
+        if Self::is_released(&env, &namehash) {
+            panic_with_error!(&env, RegistryError::Expired);
+        }
+        let current = Self::read_expires(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        if expires_at <= current {
+            panic_with_error!(&env, RegistryError::InvalidExpiry);
+        }
+
+        Self::write_expiry(&env, &namehash, Some(expires_at), "extend_expiry");
+
+        EvtRenew {
+            namehash,
+            expires_at,
+            seq: Self::next_event_seq(&env),
+        }
+        .publish(&env);
+    }
+
+    /// Extends the TTL of every stored entry for `namehash`, including its place in its
+    /// owner's `names_of` index. Permissionless, so anyone can keep a name from archiving.
+    pub fn bump(env: Env, namehash: BytesN<32>) {
+        if let Some(owner) = Self::read_owner(&env, &namehash) {
+            Self::extend_entry(&env, &DataKey::OwnedNames(owner));
+        }
+        Self::extend_entry(&env, &DataKey::Owner(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Resolver(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Expires(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Approved(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::SubnodeOperator(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::TransferOffer(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::History(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::OwnerNonce(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Flags(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Lease(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::ExpiryWarned(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Parent(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Label(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::Subnodes(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::GracePeriod(namehash.clone()));
+        Self::extend_entry(&env, &DataKey::RecordVersion(namehash));
+    }
+
+    /// Namehashes currently owned by `owner`, in the order they were acquired.
+    pub fn names_of(env: Env, owner: Address) -> Vec<BytesN<32>> {
+        Self::read_index(&env, &DataKey::OwnedNames(owner))
+    }
+
+    /// Child nodes of `parent` that have an owner, in creation order.
+    pub fn subnodes_of(env: Env, parent: BytesN<32>) -> Vec<BytesN<32>> {
+        Self::read_index(&env, &DataKey::Subnodes(parent))
+    }
+
+    /// Dotted, human-readable name for `namehash` (e.g. `alice.stellar`), rebuilt from the
+    /// labels recorded when each node was first created.
+    pub fn name_of(env: Env, namehash: BytesN<32>) -> Bytes {
+        let root = BytesN::<32>::from_array(&env, &[0u8; 32]);
+        let storage = env.storage().persistent();
+        let mut name = Bytes::new(&env);
+        let mut node = namehash;
+        while node != root {
+            let label: Bytes = storage
+                .get(&DataKey::Label(node.clone()))
+                .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+            if !name.is_empty() {
+                name.push_back(b'.');
+            }
+            name.append(&label);
+            node = storage
+                .get(&DataKey::Parent(node))
+                .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        }
+        name
+    }
+
+    pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
+        Self::read_expires(&env, &namehash)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound))
+    }
+
+    /// Assigns `owner` to the child node `label` under `parent`. Only the parent's
+    /// owner may create or reassign its subnodes, and not once the parent has expired
+    /// (`Expired`) or the child is frozen against transfer or exported (`Locked`). Returns
+    /// the child namehash.
+    pub fn set_subnode_owner(
+        env: Env,
+        parent: BytesN<32>,
+        label: Bytes,
+        owner: Address,
+    ) -> BytesN<32> {
+        if Self::is_zero_account(&env, &owner) {
+            panic_with_error!(&env, RegistryError::ZeroAddress);
+        }
+        let parent_owner = Self::read_owner(&env, &parent)
+            .unwrap_or_else(|| panic_with_error!(&env, RegistryError::NotFound));
+        parent_owner.require_auth();
+        Self::require_unlocked(&env, &parent, flags::SUBNODES_LOCKED);
+        if Self::is_expired(&env, &parent) {
+            panic_with_error!(&env, RegistryError::Expired);
+        }
+
+        let node = Self::subnode(&env, &parent, &label);
+        let from = match Self::read_owner(&env, &node) {
+            Some(current) => {
+                Self::require_unlocked(&env, &node, flags::FROZEN_TRANSFER);
+                current
+            }
+            None => owner.clone(),
+        };
+        Self::index_subnode(&env, &parent, &label, &node);
+        Self::write_owner(&env, node.clone(), from, owner);
+        node
+    }
+
+    pub fn namehash(env: Env, labels: Vec<Bytes>) -> BytesN<32> {
+        let mut node = BytesN::<32>::from_array(&env, &[0u8; 32]);
+        for label in labels.iter() {
+            node = Self::subnode(&env, &node, &label);
+        }
+        node
+    }
+
+    /// `namehash` of a dotted name such as `alice.stellar`, for callers holding a string. The
+    /// name must be valid UTF-8 of at most 255 bytes with no empty label; ASCII letters are
+    /// lowercased, and an internationalized label must be passed as its `xn--` form (see the
+    /// Registrar's `normalize`). Aborts with `InvalidLabel` otherwise.
+    pub fn namehash_str(env: Env, name: String) -> BytesN<32> {
+        let len = name.len() as usize;
+        if len == 0 || len > MAX_NAME_LENGTH {
+            panic_with_error!(&env, RegistryError::InvalidLabel);
+        }
+        let mut buf = [0u8; MAX_NAME_LENGTH];
+        let buf = &mut buf[..len];
+        name.copy_into_slice(buf);
+        buf.make_ascii_lowercase();
+        if core::str::from_utf8(buf).is_err() {
+            panic_with_error!(&env, RegistryError::InvalidLabel);
+        }
+        Self::namehash_dotted(&env, buf)
+    }
+
+    /// `namehash` of a dotted name such as `sub.alice.stellar` given as bytes, hashed from
+    /// the TLD down so callers need not reverse the labels themselves. Labels are hashed
+    /// exactly as given. Aborts with `InvalidLabel` if the name is empty, over 255 bytes, or
+    /// any label is empty or longer than 63 bytes.
+    pub fn namehash_name(env: Env, name: Bytes) -> BytesN<32> {
+        let len = name.len() as usize;
+        if len == 0 || len > MAX_NAME_LENGTH {
+            panic_with_error!(&env, RegistryError::InvalidLabel);
+        }
+        let mut buf = [0u8; MAX_NAME_LENGTH];
+        let buf = &mut buf[..len];
+        name.copy_into_slice(buf);
+        Self::namehash_dotted(&env, buf)
+    }
+
+    /// Folds the labels of a dotted name into its namehash, rightmost label first.
+    fn namehash_dotted(env: &Env, name: &[u8]) -> BytesN<32> {
+        let mut node = BytesN::<32>::from_array(env, &[0u8; 32]);
+        for label in name.rsplit(|b| *b == b'.') {
+            node = Self::subnode(env, &node, &Bytes::from_slice(env, label));
+        }
+        node
+    }
+
+    fn validate_label(env: &Env, label: &Bytes) {
+        if label.is_empty() {
+            panic_with_error!(&env, RegistryError::InvalidLabel);
+        }
+        if label.len() > MAX_LABEL_LENGTH {
+            panic_with_error!(&env, RegistryError::InvalidLabel);
+        }
+    }
+
+    /// `sha256(parent || sha256(label))`, validating the label first.
+    fn subnode(env: &Env, parent: &BytesN<32>, label: &Bytes) -> BytesN<32> {
+        Self::validate_label(env, label);
+        name_service_common::subnode(env, parent, label)
+    }
+}
+
+/// Interfaces answered by `supports_interface`.
+const SUPPORTED_INTERFACES: [&str; 4] = [
+    resolver_interface::INTERFACE_DETECTION,
+    resolver_interface::INTERFACE_REGISTRY,
+    resolver_interface::INTERFACE_NAME_EXPIRY,
+    resolver_interface::INTERFACE_OPERATORS,
+];
+
+#[contractimpl]
+impl InterfaceDetection for Registry {
+    fn supports_interface(env: Env, interface_id: Symbol) -> bool {
+        SUPPORTED_INTERFACES
+            .iter()
+            .any(|name| interface_id == Symbol::new(&env, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        testutils::{
+            storage::Persistent as _, Address as _, Events, Ledger, MockAuth, MockAuthInvoke,
+        },
+        vec, Bytes, Env, IntoVal, Map, Symbol, TryFromVal, Vec as SorobanVec,
+    };
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    fn root_node(e: &Env) -> BytesN<32> {
+        BytesN::from_array(e, &[0u8; 32])
+    }
+
+    fn label_of(e: &Env, n: u8) -> Bytes {
+        Bytes::from_array(e, &[n])
+    }
+
+    /// Namehash of the one-byte label `n` directly under the root.
+    fn node_of(e: &Env, n: u8) -> BytesN<32> {
+        Registry::subnode(e, &root_node(e), &label_of(e, n))
+    }
+
+    /// Approves `controller` directly in storage so tests can create first-time owners.
+    /// Controllers only issue names under a node they own, so it also takes over the root.
+    /// Deploys a Registry whose admin and root owner are fresh addresses, for tests that
+    /// never act as either.
+    fn deploy(e: &Env) -> Address {
+        e.register(Registry, (Address::generate(e), Address::generate(e)))
+    }
+
+    fn allow_controller(e: &Env, id: &Address, controller: &Address) {
+        e.as_contract(id, || {
+            let storage = e.storage().persistent();
+            storage.set(&DataKey::Controller(controller.clone()), &true);
+            storage.set(&DataKey::Owner(root_node(e)), controller);
+        });
+    }
+
+    #[test]
+    fn it_compiles_and_returns_version() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        assert_eq!(client.version(), 1);
+    }
+
+    #[test]
+    fn owner_can_set_new_owner() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 1);
+        let owner = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 1), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 1), &owner);
+
+        assert_eq!(client.owner(&namehash), owner);
+    }
+
+    #[test]
+    fn owner_set_emits_transfer_event() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 10);
+        let owner = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 10), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 10), &owner);
+
+        let events = e.events().all();
+        let mut found = false;
+        for idx in 0..events.len() {
+            let (event_contract, event_topics, event_data) = events.get(idx).unwrap();
+            if event_contract != id {
+                continue;
+            }
+
+            let topic_symbol = Symbol::try_from_val(&e, &event_topics.get(0).unwrap()).unwrap();
+            if topic_symbol != Symbol::new(&e, "transfer") {
+                continue;
+            }
+
+            let topic_namehash =
+                BytesN::<32>::try_from_val(&e, &event_topics.get(1).unwrap()).unwrap();
+            assert_eq!(topic_namehash, namehash);
+
+            let data_map = Map::<Symbol, Address>::try_from_val(&e, &event_data).unwrap();
+            let from = data_map.get(Symbol::new(&e, "from")).unwrap();
+            let to = data_map.get(Symbol::new(&e, "to")).unwrap();
+            assert_eq!(from, owner);
+            assert_eq!(to, owner);
+
+            found = true;
+        }
+
+        assert!(found, "expected transfer event on set_owner");
+    }
+
+    #[test]
+    fn history_keeps_last_owners() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 12);
+        let first = Address::generate(&e);
+        allow_controller(&e, &id, &first);
+        e.ledger().set_timestamp(100);
+        client.set_owner(&first, &root_node(&e), &label_of(&e, 12), &first);
+        assert_eq!(client.history(&namehash), vec![&e, (first.clone(), 100)]);
+
+        let mut owners = std::vec![first];
+        for step in 1..=HISTORY_LEN as u64 {
+            let next = Address::generate(&e);
+            e.ledger().set_timestamp(100 + step);
+            client.transfer(owners.last().unwrap(), &namehash, &next);
+            owners.push(next);
+        }
+
+        let history = client.history(&namehash);
+        assert_eq!(history.len(), HISTORY_LEN);
+        let (oldest, at) = history.first().unwrap();
+        assert_eq!((oldest, at), (owners[1].clone(), 101));
+        let (latest, at) = history.last().unwrap();
+        assert_eq!(
+            (latest, at),
+            (client.owner(&namehash), 100 + HISTORY_LEN as u64)
+        );
+        assert!(client.history(&node_of(&e, 13)).is_empty());
+    }
+
+    #[test]
+    fn transfer_emits_transfer_event() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 11);
+        let owner = Address::generate(&e);
+        let recipient = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 11), &owner);
+        client.transfer(&owner, &namehash, &recipient);
+
+        let events = e.events().all();
+        let mut found = false;
+        for idx in 0..events.len() {
+            let (event_contract, event_topics, event_data) = events.get(idx).unwrap();
+            if event_contract != id {
+                continue;
+            }
+            let topic_symbol = Symbol::try_from_val(&e, &event_topics.get(0).unwrap()).unwrap();
+            if topic_symbol != Symbol::new(&e, "transfer") {
+                continue;
+            }
+            let topic_namehash =
+                BytesN::<32>::try_from_val(&e, &event_topics.get(1).unwrap()).unwrap();
+            if topic_namehash != namehash {
+                continue;
+            }
+            let data_map = Map::<Symbol, Address>::try_from_val(&e, &event_data).unwrap();
+            let from = data_map.get(Symbol::new(&e, "from")).unwrap();
+            let to = data_map.get(Symbol::new(&e, "to")).unwrap();
+            if from == owner && to == recipient {
+                found = true;
+                break;
+            }
+        }
+
+        assert!(found, "expected transfer event on transfer");
+    }
+
+    #[test]
+    fn transfer_event_payload_matches_state() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 23);
+        let owner = Address::generate(&e);
+        let recipient = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 23), &owner);
+        client.transfer(&owner, &namehash, &recipient);
+
+        let events = e.events().all();
+        assert_eq!(events.len(), 1);
+        let (contract_id, topics, data) = events.get(0).unwrap().clone();
+        assert_eq!(contract_id, id);
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "transfer")
+        );
+        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
+        assert_eq!(topic_namehash, namehash);
+        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "from")).unwrap(), owner);
+        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), recipient);
+
+        let stored_owner = e
+            .as_contract(&id, || Registry::read_owner(&e, &namehash))
+            .unwrap();
+        assert_eq!(stored_owner, recipient);
+    }
+
+    #[test]
+    fn transfer_to_self_noop() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 12);
+        let owner = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 12), &owner);
+        client.transfer(&owner, &namehash, &owner);
+
+        assert_eq!(client.owner(&namehash), owner);
+    }
+
+    #[test]
+    fn uninitialized_namehash_transfer_panics() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 13);
+        let recipient = Address::generate(&e);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            client.transfer(&recipient, &namehash, &recipient)
+        }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn ownership_isolation_between_namehashes() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash_a = node_of(&e, 14);
+        let namehash_b = node_of(&e, 15);
+        let owner_a = Address::generate(&e);
+        let owner_b = Address::generate(&e);
+        let new_owner_a = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner_a);
+        client.set_owner(&owner_a, &root_node(&e), &label_of(&e, 14), &owner_a);
+        allow_controller(&e, &id, &owner_b);
+        client.set_owner(&owner_b, &root_node(&e), &label_of(&e, 15), &owner_b);
+
+        client.transfer(&owner_a, &namehash_a, &new_owner_a);
+
+        assert_eq!(client.owner(&namehash_a), new_owner_a);
+        assert_eq!(client.owner(&namehash_b), owner_b);
+    }
+
+    #[test]
+    fn owner_rejects_non_owner_update() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 2);
+        let owner = Address::generate(&e);
+        let attacker = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 2), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 2), &owner);
+
+        let attempted_takeover = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &attacker,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "set_owner",
+                        args: (&attacker, root_node(&e), label_of(&e, 2), &attacker).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .set_owner(&attacker, &root_node(&e), &label_of(&e, 2), &attacker);
+        }));
+
+        assert!(attempted_takeover.is_err());
+        assert_eq!(client.owner(&namehash), owner);
+    }
+
+    #[test]
+    fn transfer_updates_owner_correctly() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 3);
+        let owner = Address::generate(&e);
+        let recipient = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 3), &owner);
+        client.transfer(&owner, &namehash, &recipient);
+
+        assert_eq!(client.owner(&namehash), recipient);
+    }
+
+    #[test]
+    fn resolver_owner_can_set() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 4);
+        let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 4), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 4), &owner);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner, &namehash, &resolver).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner, &namehash, &resolver);
+
+        assert_eq!(client.resolver(&namehash), resolver);
+    }
+
+    #[test]
+    fn resolver_set_emits_event() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 21);
+        let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 21), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 21), &owner);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner, &namehash, &resolver).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner, &namehash, &resolver);
+
+        let events = e.events().all();
+        let mut found = false;
+        for idx in 0..events.len() {
+            let (event_contract, event_topics, event_data) = events.get(idx).unwrap();
+            if event_contract != id {
+                continue;
+            }
+            let topic_symbol = Symbol::try_from_val(&e, &event_topics.get(0).unwrap()).unwrap();
+            if topic_symbol != Symbol::new(&e, "resolver_changed") {
+                continue;
+            }
+            let topic_namehash =
+                BytesN::<32>::try_from_val(&e, &event_topics.get(1).unwrap()).unwrap();
+            if topic_namehash != namehash {
+                continue;
+            }
+            let data_map = Map::<Symbol, Address>::try_from_val(&e, &event_data).unwrap();
+            let resolver_record = data_map.get(Symbol::new(&e, "resolver")).unwrap();
+            assert_eq!(resolver_record, resolver);
+            found = true;
+            break;
+        }
+        assert!(found, "expected resolver_changed event");
+    }
+
+    #[test]
+    fn resolver_event_payload_matches_state() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 24);
+        let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 24), &owner);
+        client.set_resolver(&owner, &namehash, &resolver);
+
+        let events = e.events().all();
+        assert_eq!(events.len(), 1);
+        let (contract_id, topics, data) = events.get(0).unwrap().clone();
+        assert_eq!(contract_id, id);
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "resolver_changed")
+        );
+        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
+        assert_eq!(topic_namehash, namehash);
+        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "resolver")).unwrap(), resolver);
+
+        let stored_resolver = e
+            .as_contract(&id, || Registry::read_resolver(&e, &namehash))
+            .unwrap();
+        assert_eq!(stored_resolver, resolver);
+    }
+
+    #[test]
+    fn resolver_idempotent_same_value() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 22);
+        let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 22), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 22), &owner);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner, &namehash, &resolver).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner, &namehash, &resolver);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner, &namehash, &resolver).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner, &namehash, &resolver);
+
+        assert_eq!(client.resolver(&namehash), resolver);
+    }
+
+    #[test]
+    fn resolver_isolation_between_namehashes() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash_a = node_of(&e, 23);
+        let namehash_b = node_of(&e, 24);
+        let owner_a = Address::generate(&e);
+        let owner_b = Address::generate(&e);
+        let resolver_a1 = Address::generate(&e);
+        let resolver_a2 = Address::generate(&e);
+        let resolver_b = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner_a);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner_a,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner_a, root_node(&e), label_of(&e, 23), &owner_a).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner_a, &root_node(&e), &label_of(&e, 23), &owner_a);
+
+        allow_controller(&e, &id, &owner_b);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner_b,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner_b, root_node(&e), label_of(&e, 24), &owner_b).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner_b, &root_node(&e), &label_of(&e, 24), &owner_b);
+
+        // Set resolvers
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner_a,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner_a, &namehash_a, &resolver_a1).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner_a, &namehash_a, &resolver_a1);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner_b,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner_b, &namehash_b, &resolver_b).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner_b, &namehash_b, &resolver_b);
+
+        // Update resolver for A
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner_a,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_resolver",
+                    args: (&owner_a, &namehash_a, &resolver_a2).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_resolver(&owner_a, &namehash_a, &resolver_a2);
+
+        assert_eq!(client.resolver(&namehash_a), resolver_a2);
+        assert_eq!(client.resolver(&namehash_b), resolver_b);
+    }
+
+    #[test]
+    fn resolver_rejects_non_owner() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 5);
+        let owner = Address::generate(&e);
+        let attacker = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 5), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 5), &owner);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &attacker,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "set_resolver",
+                        args: (&owner, &namehash, &resolver).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .set_resolver(&owner, &namehash, &resolver);
+        }));
+
+        assert!(result.is_err());
+        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
+        assert!(stored.is_none());
+    }
+
+    #[test]
+    fn namehash_root_is_zero() {
+        let e = Env::default();
+        let labels: SorobanVec<Bytes> = vec![&e];
+        let hash = Registry::namehash(e.clone(), labels);
+        assert_eq!(hash.to_array(), [0u8; 32]);
+    }
+
+    #[test]
+    fn namehash_single_label_deterministic() {
+        let e = Env::default();
+        let label = Bytes::from_slice(&e, b"stellar");
+
+        let mut labels_a = vec![&e];
+        labels_a.push_back(label.clone());
+        let hash_a = Registry::namehash(e.clone(), labels_a);
+
+        let mut labels_b = vec![&e];
+        labels_b.push_back(label);
+        let hash_b = Registry::namehash(e.clone(), labels_b);
+
+        assert_eq!(hash_a, hash_b);
+    }
+
+    #[test]
+    fn namehash_multiple_labels_order_sensitive() {
+        let e = Env::default();
+
+        let mut labels_one = vec![&e];
+        labels_one.push_back(Bytes::from_slice(&e, b"foo"));
+        labels_one.push_back(Bytes::from_slice(&e, b"bar"));
+        let hash_one = Registry::namehash(e.clone(), labels_one);
+
+        let mut labels_two = vec![&e];
+        labels_two.push_back(Bytes::from_slice(&e, b"bar"));
+        labels_two.push_back(Bytes::from_slice(&e, b"foo"));
+        let hash_two = Registry::namehash(e.clone(), labels_two);
+
+        assert_ne!(hash_one, hash_two);
+    }
+
+    #[test]
+    fn namehash_unicode_utf8_stability() {
+        let e = Env::default();
+        let unicode_label = "stêllar🚀";
+
+        let mut labels_one = vec![&e];
+        labels_one.push_back(Bytes::from_slice(&e, unicode_label.as_bytes()));
+        let hash_one = Registry::namehash(e.clone(), labels_one);
+
+        let mut labels_two = vec![&e];
+        labels_two.push_back(Bytes::from_slice(&e, unicode_label.as_bytes()));
+        let hash_two = Registry::namehash(e.clone(), labels_two);
+
+        assert_eq!(hash_one, hash_two);
+    }
+
+    #[test]
+    fn namehash_rejects_empty_label() {
+        let e = Env::default();
+        let mut labels = vec![&e];
+        labels.push_back(Bytes::new(&e));
+
+        let result = catch_unwind(AssertUnwindSafe(|| Registry::namehash(e.clone(), labels)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn namehash_rejects_overlong_label() {
+        let e = Env::default();
+        let mut labels = vec![&e];
+        let long_label_bytes: std::vec::Vec<u8> =
+            core::iter::repeat_n(b'a', (MAX_LABEL_LENGTH + 1) as usize).collect();
+        let long_label = Bytes::from_slice(&e, &long_label_bytes);
+        labels.push_back(long_label);
+
+        let result = catch_unwind(AssertUnwindSafe(|| Registry::namehash(e.clone(), labels)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn set_subnode_owner_creates_child_under_parent() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let parent = node_of(&e, 26);
+        let parent_owner = Address::generate(&e);
+        let sub_owner = Address::generate(&e);
+        let label = Bytes::from_slice(&e, b"sub");
+        allow_controller(&e, &id, &parent_owner);
+        client.set_owner(
+            &parent_owner,
+            &root_node(&e),
+            &label_of(&e, 26),
+            &parent_owner,
+        );
+
+        let node = client.set_subnode_owner(&parent, &label, &sub_owner);
+        let events = e.events().all();
+
+        let mut data = Bytes::from_slice(&e, &parent.to_array());
+        data.extend_from_slice(&e.crypto().sha256(&label).to_bytes().to_array());
+        let expected: BytesN<32> = e.crypto().sha256(&data).to_bytes();
+        assert_eq!(node, expected);
+        assert_eq!(client.owner(&node), sub_owner);
+        assert_eq!(client.owner(&parent), parent_owner);
+
+        assert_eq!(events.len(), 1);
+        let (_, topics, data) = events.get(0).unwrap().clone();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "transfer")
+        );
+        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
+        assert_eq!(topic_namehash, node);
+        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), sub_owner);
+    }
+
+    #[test]
+    fn set_subnode_owner_matches_namehash_of_labels() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let tld = Bytes::from_slice(&e, b"stellar");
+        let label = Bytes::from_slice(&e, b"alice");
+        let parent = client.namehash(&vec![&e, tld.clone()]);
+        let owner = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &tld, &owner);
+
+        let node = client.set_subnode_owner(&parent, &label, &owner);
+        assert_eq!(node, client.namehash(&vec![&e, tld, label]));
+    }
+
+    #[test]
+    fn set_subnode_owner_parent_can_reassign_child() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let parent = node_of(&e, 27);
+        let parent_owner = Address::generate(&e);
+        let first = Address::generate(&e);
+        let second = Address::generate(&e);
+        let label = Bytes::from_slice(&e, b"team");
+        allow_controller(&e, &id, &parent_owner);
+        client.set_owner(
+            &parent_owner,
+            &root_node(&e),
+            &label_of(&e, 27),
+            &parent_owner,
+        );
+
+        let node = client.set_subnode_owner(&parent, &label, &first);
+        client.set_subnode_owner(&parent, &label, &second);
+        assert_eq!(client.owner(&node), second);
+    }
+
+    #[test]
+    fn set_subnode_owner_cannot_take_back_frozen_or_exported_children() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let parent = node_of(&e, 28);
+        let parent_owner = Address::generate(&e);
+        let holder = Address::generate(&e);
+        allow_controller(&e, &id, &parent_owner);
+        client.set_owner(
+            &parent_owner,
+            &root_node(&e),
+            &label_of(&e, 28),
+            &parent_owner,
+        );
+        let (frozen_label, exported_label) = (
+            Bytes::from_slice(&e, b"frozen"),
+            Bytes::from_slice(&e, b"exported"),
+        );
+        let frozen = client.set_subnode_owner(&parent, &frozen_label, &holder);
+        let exported = client.set_subnode_owner(&parent, &exported_label, &holder);
+        client.set_flags(&frozen, &flags::FROZEN_TRANSFER);
+        client.export_name(
+            &holder,
+            &exported,
+            &Symbol::new(&e, "evm"),
+            &Bytes::from_slice(&e, b"0xabc"),
+        );
+
+        for (label, node) in [(frozen_label, frozen), (exported_label, exported)] {
+            assert_eq!(
+                client.try_set_subnode_owner(&parent, &label, &parent_owner),
+                Err(Ok(RegistryError::Locked.into()))
+            );
+            assert_eq!(client.owner(&node), holder);
+        }
+    }
+
+    #[test]
+    fn set_subnode_owner_requires_parent_owner_auth() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let parent = node_of(&e, 28);
+        let parent_owner = Address::generate(&e);
+        let attacker = Address::generate(&e);
+        let label = Bytes::from_slice(&e, b"stolen");
+
+        allow_controller(&e, &id, &parent_owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &parent_owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (
+                        &parent_owner,
+                        root_node(&e),
+                        label_of(&e, 28),
+                        &parent_owner,
+                    )
+                        .into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(
+                &parent_owner,
+                &root_node(&e),
+                &label_of(&e, 28),
+                &parent_owner,
+            );
+
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &attacker,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "set_subnode_owner",
+                        args: (&parent, &label, &attacker).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .set_subnode_owner(&parent, &label, &attacker);
+        }));
+        assert!(attempt.is_err());
+    }
+
+    #[test]
+    fn set_subnode_owner_rejects_unowned_parent_and_bad_labels() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let parent = node_of(&e, 29);
+        let owner = Address::generate(&e);
+        let label = Bytes::from_slice(&e, b"orphan");
+
+        let unowned = catch_unwind(AssertUnwindSafe(|| {
+            client.set_subnode_owner(&parent, &label, &owner);
+        }));
+        assert!(unowned.is_err(), "parent must be owned");
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 29), &owner);
+        let empty = catch_unwind(AssertUnwindSafe(|| {
+            client.set_subnode_owner(&parent, &Bytes::new(&e), &owner);
+        }));
+        assert!(empty.is_err(), "empty label must be rejected");
+    }
+
+    #[test]
+    fn expired_parents_get_no_new_subnodes() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let owner = Address::generate(&e);
+        let holder = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        e.ledger().set_timestamp(1_000);
+        let parent = client.set_owner(&owner, &root_node(&e), &label_of(&e, 30), &owner);
+        client.set_expiry(&owner, &parent, &5_000);
+        let kept = client.set_subnode_owner(&parent, &label_of(&e, 1), &holder);
+
+        e.ledger().set_timestamp(5_001);
+        assert_eq!(
+            client.try_set_subnode_owner(&parent, &label_of(&e, 2), &holder),
+            Err(Ok(RegistryError::Expired.into()))
+        );
+        assert_eq!(
+            client.try_set_subnode_owner(&parent, &label_of(&e, 1), &owner),
+            Err(Ok(RegistryError::Expired.into()))
+        );
+        assert_eq!(
+            client.try_set_owner(&owner, &parent, &label_of(&e, 2), &holder),
+            Err(Ok(RegistryError::Expired.into()))
+        );
+        // Existing children stay with their owners, who can still move them.
+        client.set_owner(&holder, &parent, &label_of(&e, 1), &owner);
+        assert_eq!(client.owner(&kept), owner);
+
+        client.extend_expiry(&owner, &parent, &10_000);
+        client.set_subnode_owner(&parent, &label_of(&e, 2), &holder);
+    }
+
+    #[test]
+    fn owner_default_panics() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        let namehash = node_of(&e, 9);
+
+        let result = catch_unwind(AssertUnwindSafe(|| client.owner(&namehash)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn resolver_default_panics() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        let namehash = node_of(&e, 20);
+
+        let result = catch_unwind(AssertUnwindSafe(|| client.resolver(&namehash)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn renew_extends_from_now_if_unset() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 6);
+        let owner = Address::generate(&e);
+        let now = 1_700_000_000u64;
+        e.ledger().set_timestamp(now);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 6), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 6), &owner);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "renew",
+                    args: (&owner, &namehash).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .renew(&owner, &namehash);
+
+        let expiry = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .unwrap();
+        assert_eq!(expiry, now + RENEW_EXTENSION_SECONDS);
+    }
+
+    #[test]
+    fn renew_emits_event_with_new_expiry() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 25);
+        let owner = Address::generate(&e);
+        let now = 42_000u64;
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 25), &owner);
+        e.ledger().set_timestamp(now);
+        client.renew(&owner, &namehash);
+
+        let events = e.events().all();
+        assert_eq!(events.len(), 2);
+        let (contract_id, topics, data) = events.get(1).unwrap().clone();
+        assert_eq!(contract_id, id);
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "renew")
+        );
+        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
+        assert_eq!(topic_namehash, namehash);
+        let map = Map::<Symbol, u64>::try_from_val(&e, &data).unwrap();
+        let expires_at = map.get(Symbol::new(&e, "expires_at")).unwrap();
+
+        let stored_expiry = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .unwrap();
+        assert_eq!(stored_expiry, now + RENEW_EXTENSION_SECONDS);
+        assert_eq!(expires_at, stored_expiry);
+    }
+
+    #[test]
+    fn renew_requires_controller_auth() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 8);
+        let owner = Address::generate(&e);
+        let attacker = Address::generate(&e);
+
+        e.ledger().set_timestamp(1_234_567u64);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 8), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 8), &owner);
+
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &attacker,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "renew",
+                        args: (&attacker, &namehash).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .renew(&attacker, &namehash);
+        }));
+
+        assert!(attempt.is_err());
+        let expiry = e.as_contract(&id, || Registry::read_expires(&e, &namehash));
+        assert!(expiry.is_none());
+
+        // Holding the name is not enough; renewals go through a controller that charges.
+        e.as_contract(&id, || {
+            e.storage()
+                .persistent()
+                .remove(&DataKey::Controller(owner.clone()))
+        });
+        e.mock_all_auths();
+        assert_eq!(
+            client.try_renew(&owner, &namehash),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+    }
+
+    #[test]
+    fn renew_before_expiry_increases_expiry() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 9);
+        let owner = Address::generate(&e);
+
+        let first_now = 1_000u64;
+        e.ledger().set_timestamp(first_now);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 9), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 9), &owner);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "renew",
+                    args: (&owner, &namehash).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .renew(&owner, &namehash);
+
+        let first_expiry = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .unwrap();
+
+        let second_now = first_expiry - RENEW_EXTENSION_SECONDS / 2;
+        e.ledger().set_timestamp(second_now);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "renew",
+                    args: (&owner, &namehash).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .renew(&owner, &namehash);
+
+        let second_expiry = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .unwrap();
+        assert_eq!(second_expiry, first_expiry + RENEW_EXTENSION_SECONDS);
+    }
+
+    #[test]
+    fn renew_after_expiry_advances_to_now() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 10);
+        let owner = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 10), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 10), &owner);
+
+        // Seed storage with an expired timestamp.
+        e.as_contract(&id, || {
+            let past = 1_000u64;
+            e.storage()
+                .persistent()
+                .set(&DataKey::Expires(namehash.clone()), &past);
+        });
+
+        let now = 5_000u64;
+        e.ledger().set_timestamp(now);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "renew",
+                    args: (&owner, &namehash).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .renew(&owner, &namehash);
+
+        let expiry = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .unwrap();
+        assert_eq!(expiry, now + RENEW_EXTENSION_SECONDS);
+    }
+
+    #[test]
+    fn renew_isolation_between_namehashes() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash_a = node_of(&e, 11);
+        let namehash_b = node_of(&e, 12);
+        let controller = Address::generate(&e);
+        let owner_a = Address::generate(&e);
+        let owner_b = Address::generate(&e);
+
+        allow_controller(&e, &id, &controller);
+        client
+            .mock_auths(&[MockAuth {
+                address: &controller,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&controller, root_node(&e), label_of(&e, 11), &owner_a).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&controller, &root_node(&e), &label_of(&e, 11), &owner_a);
+        client
+            .mock_auths(&[MockAuth {
+                address: &controller,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&controller, root_node(&e), label_of(&e, 12), &owner_b).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&controller, &root_node(&e), &label_of(&e, 12), &owner_b);
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &controller,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "renew",
+                    args: (&controller, &namehash_b).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .renew(&controller, &namehash_b);
+
+        let expiry_b_before = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash_b))
+            .unwrap();
+
+        client
+            .mock_auths(&[MockAuth {
+                address: &controller,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "renew",
+                    args: (&controller, &namehash_a).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .renew(&controller, &namehash_a);
+
+        let expiry_a_after = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash_a))
+            .unwrap();
+        let expiry_b_after = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash_b))
+            .unwrap();
+
+        assert_eq!(
+            expiry_b_after, expiry_b_before,
+            "unrelated namehash changed"
+        );
+        assert!(expiry_a_after >= expiry_b_after);
+    }
+
+    #[test]
+    fn expiry_writes_are_scoped_to_the_controllers_tld_and_never_shorten() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let admin = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
+        let client = RegistryClient::new(&e, &id);
+        let (registrar, other_registrar) = (Address::generate(&e), Address::generate(&e));
+        let tld = client.create_tld(&admin, &Bytes::from_slice(&e, b"stellar"), &registrar);
+        client.create_tld(&admin, &Bytes::from_slice(&e, b"xlm"), &other_registrar);
+        client.set_controller(&registrar, &true);
+        client.set_controller(&other_registrar, &true);
+        let owner = Address::generate(&e);
+        let node = client.set_owner(&registrar, &tld, &Bytes::from_slice(&e, b"alice"), &owner);
+        e.ledger().set_timestamp(1_000);
+        client.set_expiry(&registrar, &node, &5_000);
+
+        // Another TLD's controller cannot touch the name's term.
+        assert_eq!(
+            client.try_set_expiry(&other_registrar, &node, &2_000),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_extend_expiry(&other_registrar, &node, &9_000),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_renew(&other_registrar, &node),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+
+        // Its own controller cannot cut a live term short, only once it has lapsed.
+        assert_eq!(
+            client.try_set_expiry(&registrar, &node, &4_999),
+            Err(Ok(RegistryError::InvalidExpiry.into()))
+        );
+        client.set_expiry(&registrar, &node, &5_000);
+        e.ledger().set_timestamp(5_001);
+        client.set_expiry(&registrar, &node, &5_002);
+        assert_eq!(client.expires(&node), 5_002);
+    }
+
+    #[test]
+    fn non_owner_cannot_mutate_fields() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 13);
+        let owner = Address::generate(&e);
+        let attacker = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client
+            .mock_auths(&[MockAuth {
+                address: &owner,
+                invoke: &MockAuthInvoke {
+                    contract: &id,
+                    fn_name: "set_owner",
+                    args: (&owner, root_node(&e), label_of(&e, 13), &owner).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .set_owner(&owner, &root_node(&e), &label_of(&e, 13), &owner);
+
+        let set_owner_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&attacker, &root_node(&e), &label_of(&e, 13), &attacker);
+        }));
+        assert!(set_owner_attempt.is_err());
+        assert_eq!(client.owner(&namehash), owner);
+
+        let transfer_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.transfer(&attacker, &namehash, &attacker);
+        }));
+        assert!(transfer_attempt.is_err());
+        assert_eq!(client.owner(&namehash), owner);
+
+        let resolver_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.set_resolver(&attacker, &namehash, &resolver);
+        }));
+        assert!(resolver_attempt.is_err());
+        let resolver_state = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
+        assert!(resolver_state.is_none());
+
+        let renew_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.renew(&attacker, &namehash);
+        }));
+        assert!(renew_attempt.is_err());
+        let expires_state = e.as_contract(&id, || Registry::read_expires(&e, &namehash));
+        assert!(expires_state.is_none());
+    }
+
+    #[test]
+    fn owner_can_mutate_all_fields() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 14);
+        let owner = Address::generate(&e);
+        let resolver1 = Address::generate(&e);
+        let new_owner = Address::generate(&e);
+        let resolver2 = Address::generate(&e);
+
+        let first_now = 10_000u64;
+        e.ledger().set_timestamp(first_now);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 14), &owner);
+        client.set_resolver(&owner, &namehash, &resolver1);
+        client.transfer(&owner, &namehash, &new_owner);
+        client.set_resolver(&new_owner, &namehash, &resolver2);
+
+        let second_now = first_now + 1;
+        e.ledger().set_timestamp(second_now);
+
+        client.renew(&owner, &namehash);
+
+        assert_eq!(client.owner(&namehash), new_owner);
+        assert_eq!(client.resolver(&namehash), resolver2);
+        let expiry = e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .unwrap();
+        assert_eq!(expiry, second_now + RENEW_EXTENSION_SECONDS);
+    }
+
+    #[test]
+    fn events_ordered_and_well_formed() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 15);
+        let owner = Address::generate(&e);
+        let new_owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 15), &owner);
+        let events = e.events().all();
+        assert_eq!(events.len(), 1);
+        let (contract_id, topics, data) = events.get(0).unwrap().clone();
+        assert_eq!(contract_id, id);
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "transfer")
+        );
+        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "from")).unwrap(), owner);
+        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), owner);
+
+        client.transfer(&owner, &namehash, &new_owner);
+        let events = e.events().all();
+        assert_eq!(events.len(), 1);
+        let (contract_id, topics, data) = events.get(0).unwrap().clone();
+        assert_eq!(contract_id, id);
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "transfer")
+        );
+        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "from")).unwrap(), owner);
+        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), new_owner);
+
+        client.set_resolver(&new_owner, &namehash, &resolver);
+        let events = e.events().all();
+        assert_eq!(events.len(), 1);
+        let (contract_id, topics, data) = events.get(0).unwrap().clone();
+        assert_eq!(contract_id, id);
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "resolver_changed")
+        );
+        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "resolver")).unwrap(), resolver);
+    }
+
+    #[test]
+    fn set_owner_rejects_zero_address() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 16);
+        let zero_owner = Address::from_str(
+            &e,
+            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
+        );
+
+        allow_controller(&e, &id, &zero_owner);
+        let outcome = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &zero_owner,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "set_owner",
+                        args: (&zero_owner, root_node(&e), label_of(&e, 16), &zero_owner)
+                            .into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .set_owner(&zero_owner, &root_node(&e), &label_of(&e, 16), &zero_owner);
+        }));
+        assert!(outcome.is_err(), "zero owner should be rejected");
+
+        let stored = e.as_contract(&id, || Registry::read_owner(&e, &namehash));
+        assert!(stored.is_none(), "zero owner should not persist");
+    }
+
+    #[test]
+    fn set_resolver_rejects_zero_address() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 17);
+        let owner = Address::generate(&e);
+        let zero_address = Address::from_str(
+            &e,
+            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
+        );
 
-        EvtRenew {
-            namehash,
-            expires_at: new_expiry,
-        }
-        .publish(&env);
-    }
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 17), &owner);
 
-    pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
-        Self::read_expires(&env, &namehash).unwrap_or_else(|| panic!("expiry not set"))
-    }
+        let outcome = catch_unwind(AssertUnwindSafe(|| {
+            client.set_resolver(&owner, &namehash, &zero_address);
+        }));
+        assert!(
+            outcome.is_err(),
+            "zero resolver should be rejected even for the owner"
+        );
 
-    pub fn namehash(env: Env, labels: Vec<Bytes>) -> BytesN<32> {
-        let mut node = BytesN::<32>::from_array(&env, &[0u8; 32]);
-        for label in labels.iter() {
-            if label.is_empty() {
-                panic!("empty label");
-            }
-            if label.len() > MAX_LABEL_LENGTH {
-                panic!("label too long");
-            }
-            let label_hash = env.crypto().sha256(&label).to_bytes();
-            let mut data = Bytes::from_slice(&env, &node.to_array());
-            data.extend_from_slice(&label_hash.to_array());
-            node = env.crypto().sha256(&data).to_bytes();
-        }
-        node
+        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
+        assert!(stored.is_none(), "zero resolver should not persist");
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::{
-        testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke},
-        vec, Bytes, Env, IntoVal, Map, Symbol, TryFromVal, Vec as SorobanVec,
-    };
-    use std::panic::{catch_unwind, AssertUnwindSafe};
 
     #[test]
-    fn it_compiles_and_returns_version() {
+    fn set_resolver_without_owner_panics() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
-        assert_eq!(client.version(), 1);
+
+        let namehash = node_of(&e, 18);
+        let resolver = Address::generate(&e);
+
+        let outcome = catch_unwind(AssertUnwindSafe(|| {
+            client.set_resolver(&resolver, &namehash, &resolver);
+        }));
+        assert!(
+            outcome.is_err(),
+            "setting resolver without owner should panic"
+        );
+        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
+        assert!(stored.is_none(), "resolver should remain unset");
     }
 
     #[test]
-    fn owner_can_set_new_owner() {
+    fn storage_keys_do_not_collide() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[1u8; 32]);
+        let namehash = node_of(&e, 19);
         let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        // Initially nothing is stored.
+        assert!(e
+            .as_contract(&id, || Registry::read_owner(&e, &namehash))
+            .is_none());
+        assert!(e
+            .as_contract(&id, || Registry::read_resolver(&e, &namehash))
+            .is_none());
+        assert!(e
+            .as_contract(&id, || Registry::read_expires(&e, &namehash))
+            .is_none());
 
-        assert_eq!(client.owner(&namehash), owner);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 19), &owner);
+        assert_eq!(
+            e.as_contract(&id, || Registry::read_owner(&e, &namehash)),
+            Some(owner.clone())
+        );
+        assert!(
+            e.as_contract(&id, || Registry::read_resolver(&e, &namehash))
+                .is_none(),
+            "resolver slot should remain untouched after set_owner"
+        );
+        assert!(
+            e.as_contract(&id, || Registry::read_expires(&e, &namehash))
+                .is_none(),
+            "expiry slot should remain untouched after set_owner"
+        );
+
+        client.set_resolver(&owner, &namehash, &resolver);
+        assert_eq!(
+            e.as_contract(&id, || Registry::read_resolver(&e, &namehash)),
+            Some(resolver.clone())
+        );
+        assert_eq!(
+            e.as_contract(&id, || Registry::read_owner(&e, &namehash)),
+            Some(owner.clone()),
+            "owner slot should remain intact after set_resolver"
+        );
+        assert!(
+            e.as_contract(&id, || Registry::read_expires(&e, &namehash))
+                .is_none(),
+            "expiry slot should remain untouched after set_resolver"
+        );
+
+        let now = 555u64;
+        e.ledger().set_timestamp(now);
+        client.renew(&owner, &namehash);
+        let expected_expiry = now + RENEW_EXTENSION_SECONDS;
+        assert_eq!(
+            e.as_contract(&id, || Registry::read_expires(&e, &namehash)),
+            Some(expected_expiry),
+            "expiry slot should be written by renew"
+        );
+        assert_eq!(
+            e.as_contract(&id, || Registry::read_owner(&e, &namehash)),
+            Some(owner),
+            "owner slot should remain intact after renew"
+        );
+        assert_eq!(
+            e.as_contract(&id, || Registry::read_resolver(&e, &namehash)),
+            Some(resolver),
+            "resolver slot should remain intact after renew"
+        );
     }
 
     #[test]
-    fn owner_set_emits_transfer_event() {
+    fn unknown_namehash_returns_defaults() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[10u8; 32]);
+        let known = node_of(&e, 21);
+        let unknown = node_of(&e, 22);
         let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
-
-        let events = e.events().all();
-        let mut found = false;
-        for idx in 0..events.len() {
-            let (event_contract, event_topics, event_data) = events.get(idx).unwrap();
-            if event_contract != id {
-                continue;
-            }
-
-            let topic_symbol = Symbol::try_from_val(&e, &event_topics.get(0).unwrap()).unwrap();
-            if topic_symbol != Symbol::new(&e, "transfer") {
-                continue;
-            }
+        e.ledger().set_timestamp(1_000u64);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 21), &owner);
+        client.set_resolver(&owner, &known, &resolver);
+        client.renew(&owner, &known);
 
-            let topic_namehash =
-                BytesN::<32>::try_from_val(&e, &event_topics.get(1).unwrap()).unwrap();
-            assert_eq!(topic_namehash, namehash);
+        assert!(e
+            .as_contract(&id, || Registry::read_owner(&e, &unknown))
+            .is_none());
+        assert!(e
+            .as_contract(&id, || Registry::read_resolver(&e, &unknown))
+            .is_none());
+        assert!(e
+            .as_contract(&id, || Registry::read_expires(&e, &unknown))
+            .is_none());
 
-            let data_map = Map::<Symbol, Address>::try_from_val(&e, &event_data).unwrap();
-            let from = data_map.get(Symbol::new(&e, "from")).unwrap();
-            let to = data_map.get(Symbol::new(&e, "to")).unwrap();
-            assert_eq!(from, owner);
-            assert_eq!(to, owner);
+        let owner_call = catch_unwind(AssertUnwindSafe(|| client.owner(&unknown)));
+        assert!(
+            owner_call.is_err(),
+            "owner() should panic for unknown namehash"
+        );
 
-            found = true;
-        }
+        let resolver_call = catch_unwind(AssertUnwindSafe(|| client.resolver(&unknown)));
+        assert!(
+            resolver_call.is_err(),
+            "resolver() should panic for unknown namehash"
+        );
 
-        assert!(found, "expected transfer event on set_owner");
+        let expires_call = catch_unwind(AssertUnwindSafe(|| client.expires(&unknown)));
+        assert!(
+            expires_call.is_err(),
+            "expires() should panic for unknown namehash"
+        );
     }
 
     #[test]
-    fn transfer_emits_transfer_event() {
+    fn set_expiry_writes_absolute_timestamp() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[11u8; 32]);
+        let namehash = node_of(&e, 30);
         let owner = Address::generate(&e);
-        let recipient = Address::generate(&e);
-
-        client.set_owner(&namehash, &owner);
-        client.transfer(&namehash, &recipient);
+        e.ledger().set_timestamp(1_000);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 30), &owner);
 
+        let target = 1_000 + 3 * RENEW_EXTENSION_SECONDS;
+        client.set_expiry(&owner, &namehash, &target);
         let events = e.events().all();
-        let mut found = false;
-        for idx in 0..events.len() {
-            let (event_contract, event_topics, event_data) = events.get(idx).unwrap();
-            if event_contract != id {
-                continue;
-            }
-            let topic_symbol = Symbol::try_from_val(&e, &event_topics.get(0).unwrap()).unwrap();
-            if topic_symbol != Symbol::new(&e, "transfer") {
-                continue;
-            }
-            let topic_namehash =
-                BytesN::<32>::try_from_val(&e, &event_topics.get(1).unwrap()).unwrap();
-            if topic_namehash != namehash {
-                continue;
-            }
-            let data_map = Map::<Symbol, Address>::try_from_val(&e, &event_data).unwrap();
-            let from = data_map.get(Symbol::new(&e, "from")).unwrap();
-            let to = data_map.get(Symbol::new(&e, "to")).unwrap();
-            if from == owner && to == recipient {
-                found = true;
-                break;
-            }
-        }
+        assert_eq!(client.expires(&namehash), target);
 
-        assert!(found, "expected transfer event on transfer");
+        assert_eq!(events.len(), 2);
+        let (_, topics, data) = events.get(1).unwrap().clone();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "renew")
+        );
+        let map = Map::<Symbol, u64>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "expires_at")).unwrap(), target);
     }
 
     #[test]
-    fn transfer_event_payload_matches_state() {
+    fn set_expiry_rejects_past_and_non_controller() {
         let e = Env::default();
-        e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[23u8; 32]);
+        let namehash = node_of(&e, 31);
         let owner = Address::generate(&e);
-        let recipient = Address::generate(&e);
-
-        client.set_owner(&namehash, &owner);
-        client.transfer(&namehash, &recipient);
+        let registrar = Address::generate(&e);
+        e.ledger().set_timestamp(5_000);
+        e.mock_all_auths();
+        allow_controller(&e, &id, &registrar);
+        client.set_owner(&registrar, &root_node(&e), &label_of(&e, 31), &owner);
 
-        let events = e.events().all();
-        assert_eq!(events.len(), 1);
-        let (contract_id, topics, data) = events.get(0).unwrap().clone();
-        assert_eq!(contract_id, id);
         assert_eq!(
-            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
-            Symbol::new(&e, "transfer")
+            client.try_set_expiry(&registrar, &namehash, &5_000),
+            Err(Ok(RegistryError::InvalidExpiry.into()))
+        );
+        // The owner cannot give itself a free term.
+        assert_eq!(
+            client.try_set_expiry(&owner, &namehash, &u64::MAX),
+            Err(Ok(RegistryError::NotAuthorized.into()))
         );
-        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
-        assert_eq!(topic_namehash, namehash);
-        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
-        assert_eq!(map.get(Symbol::new(&e, "from")).unwrap(), owner);
-        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), recipient);
 
-        let stored_owner = e
-            .as_contract(&id, || Registry::read_owner(&e, &namehash))
-            .unwrap();
-        assert_eq!(stored_owner, recipient);
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &owner,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "set_expiry",
+                        args: (&owner, &namehash, &u64::MAX).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .set_expiry(&registrar, &namehash, &u64::MAX);
+        }));
+        assert!(attempt.is_err(), "a controller's authorization is required");
+        let expiry = e.as_contract(&id, || Registry::read_expires(&e, &namehash));
+        assert!(expiry.is_none());
     }
 
     #[test]
-    fn transfer_to_self_noop() {
+    fn approved_operator_can_manage_owner_names() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[12u8; 32]);
+        let namehash = node_of(&e, 32);
         let owner = Address::generate(&e);
+        let operator = Address::generate(&e);
+        let resolver = Address::generate(&e);
+        let buyer = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 32), &owner);
 
-        client.set_owner(&namehash, &owner);
-        client.transfer(&namehash, &owner);
-
-        assert_eq!(client.owner(&namehash), owner);
-    }
+        assert!(!client.is_approved_for_all(&owner, &operator));
+        client.set_approval_for_all(&owner, &operator, &true);
+        let events = e.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "approval_for_all")
+        );
+        assert_eq!(
+            Address::try_from_val(&e, &topics.get(2).unwrap()).unwrap(),
+            operator
+        );
+        let map = Map::<Symbol, bool>::try_from_val(&e, &data).unwrap();
+        assert!(map.get(Symbol::new(&e, "approved")).unwrap());
+        assert!(client.is_approved_for_all(&owner, &operator));
 
-    #[test]
-    fn uninitialized_namehash_transfer_panics() {
-        let e = Env::default();
-        e.mock_all_auths();
-        let id = e.register(Registry, ());
-        let client = RegistryClient::new(&e, &id);
+        client.set_resolver(&operator, &namehash, &resolver);
+        assert_eq!(client.resolver(&namehash), resolver);
 
-        let namehash = BytesN::from_array(&e, &[13u8; 32]);
-        let recipient = Address::generate(&e);
+        client.transfer(&operator, &namehash, &buyer);
+        assert_eq!(client.owner(&namehash), buyer);
 
-        let result = catch_unwind(AssertUnwindSafe(|| client.transfer(&namehash, &recipient)));
-        assert!(result.is_err());
+        // Approval is scoped to the granting owner, not to the name.
+        let after_sale = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&operator, &root_node(&e), &label_of(&e, 32), &operator);
+        }));
+        assert!(after_sale.is_err());
+        assert_eq!(client.owner(&namehash), buyer);
     }
 
     #[test]
-    fn ownership_isolation_between_namehashes() {
+    fn revoked_or_unapproved_operator_is_rejected() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash_a = BytesN::from_array(&e, &[14u8; 32]);
-        let namehash_b = BytesN::from_array(&e, &[15u8; 32]);
-        let owner_a = Address::generate(&e);
-        let owner_b = Address::generate(&e);
-        let new_owner_a = Address::generate(&e);
-
-        client.set_owner(&namehash_a, &owner_a);
-        client.set_owner(&namehash_b, &owner_b);
-
-        client.transfer(&namehash_a, &new_owner_a);
+        let namehash = node_of(&e, 33);
+        let owner = Address::generate(&e);
+        let operator = Address::generate(&e);
+        let stranger = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 33), &owner);
+        client.set_approval_for_all(&owner, &operator, &true);
+
+        let stranger_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.transfer(&stranger, &namehash, &stranger);
+        }));
+        assert!(stranger_attempt.is_err());
 
-        assert_eq!(client.owner(&namehash_a), new_owner_a);
-        assert_eq!(client.owner(&namehash_b), owner_b);
+        client.set_approval_for_all(&owner, &operator, &false);
+        assert!(!client.is_approved_for_all(&owner, &operator));
+        let revoked_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&operator, &root_node(&e), &label_of(&e, 33), &operator);
+        }));
+        assert!(revoked_attempt.is_err());
+        assert_eq!(client.owner(&namehash), owner);
     }
 
     #[test]
-    fn owner_rejects_non_owner_update() {
+    fn set_approval_for_all_requires_owner_auth() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[2u8; 32]);
         let owner = Address::generate(&e);
-        let attacker = Address::generate(&e);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        let operator = Address::generate(&e);
 
-        let attempted_takeover = catch_unwind(AssertUnwindSafe(|| {
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
             client
                 .mock_auths(&[MockAuth {
-                    address: &attacker,
+                    address: &operator,
                     invoke: &MockAuthInvoke {
                         contract: &id,
-                        fn_name: "set_owner",
-                        args: (&namehash, &attacker).into_val(&e),
+                        fn_name: "set_approval_for_all",
+                        args: (&owner, &operator, true).into_val(&e),
                         sub_invokes: &[],
                     },
                 }])
-                .set_owner(&namehash, &attacker);
+                .set_approval_for_all(&owner, &operator, &true);
         }));
-
-        assert!(attempted_takeover.is_err());
-        assert_eq!(client.owner(&namehash), owner);
+        assert!(attempt.is_err());
+        assert!(!client.is_approved_for_all(&owner, &operator));
     }
 
     #[test]
-    fn transfer_updates_owner_correctly() {
+    fn approved_delegate_can_transfer_single_name() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[3u8; 32]);
+        let namehash = node_of(&e, 34);
+        let other = node_of(&e, 35);
         let owner = Address::generate(&e);
-        let recipient = Address::generate(&e);
+        let escrow = Address::generate(&e);
+        let buyer = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 34), &owner);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 35), &owner);
+
+        assert_eq!(client.get_approved(&namehash), None);
+        client.approve(&namehash, &escrow);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "approval")
+        );
+        assert_eq!(client.get_approved(&namehash), Some(escrow.clone()));
+
+        // The approval covers only the named node.
+        let other_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.transfer(&escrow, &other, &buyer);
+        }));
+        assert!(other_attempt.is_err());
 
-        client.set_owner(&namehash, &owner);
-        client.transfer(&namehash, &recipient);
+        client.transfer(&escrow, &namehash, &buyer);
+        assert_eq!(client.owner(&namehash), buyer);
+        assert_eq!(client.get_approved(&namehash), None);
 
-        assert_eq!(client.owner(&namehash), recipient);
+        let stale_attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.set_resolver(&escrow, &namehash, &escrow);
+        }));
+        assert!(stale_attempt.is_err());
     }
 
     #[test]
-    fn resolver_owner_can_set() {
+    fn approve_zero_address_clears_and_requires_owner() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[4u8; 32]);
+        let namehash = node_of(&e, 36);
         let owner = Address::generate(&e);
-        let resolver = Address::generate(&e);
+        let delegate = Address::generate(&e);
+        let zero = Address::from_str(&e, ZERO_ACCOUNT_STR);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.approve(&namehash, &delegate);
+        }));
+        assert!(attempt.is_err(), "unowned names cannot be approved");
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash, &resolver).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash, &resolver);
+        e.mock_all_auths();
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 36), &owner);
+        client.approve(&namehash, &delegate);
+        client.approve(&namehash, &zero);
+        assert_eq!(client.get_approved(&namehash), None);
 
-        assert_eq!(client.resolver(&namehash), resolver);
+        let unauthorized = catch_unwind(AssertUnwindSafe(|| {
+            client
+                .mock_auths(&[MockAuth {
+                    address: &delegate,
+                    invoke: &MockAuthInvoke {
+                        contract: &id,
+                        fn_name: "approve",
+                        args: (&namehash, &delegate).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .approve(&namehash, &delegate);
+        }));
+        assert!(unauthorized.is_err());
+        assert_eq!(client.get_approved(&namehash), None);
     }
 
     #[test]
-    fn resolver_set_emits_event() {
+    fn clear_resolver_removes_pointer() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[21u8; 32]);
+        let namehash = node_of(&e, 37);
         let owner = Address::generate(&e);
+        let attacker = Address::generate(&e);
         let resolver = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 37), &owner);
+        client.set_resolver(&owner, &namehash, &resolver);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash, &resolver).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash, &resolver);
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.clear_resolver(&attacker, &namehash);
+        }));
+        assert!(attempt.is_err());
+        assert_eq!(client.resolver(&namehash), resolver);
 
+        client.clear_resolver(&owner, &namehash);
         let events = e.events().all();
-        let mut found = false;
-        for idx in 0..events.len() {
-            let (event_contract, event_topics, event_data) = events.get(idx).unwrap();
-            if event_contract != id {
-                continue;
-            }
-            let topic_symbol = Symbol::try_from_val(&e, &event_topics.get(0).unwrap()).unwrap();
-            if topic_symbol != Symbol::new(&e, "resolver_changed") {
-                continue;
-            }
-            let topic_namehash =
-                BytesN::<32>::try_from_val(&e, &event_topics.get(1).unwrap()).unwrap();
-            if topic_namehash != namehash {
-                continue;
-            }
-            let data_map = Map::<Symbol, Address>::try_from_val(&e, &event_data).unwrap();
-            let resolver_record = data_map.get(Symbol::new(&e, "resolver")).unwrap();
-            assert_eq!(resolver_record, resolver);
-            found = true;
-            break;
-        }
-        assert!(found, "expected resolver_changed event");
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "resolver_cleared")
+        );
+        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
+        assert!(stored.is_none());
     }
 
     #[test]
-    fn resolver_event_payload_matches_state() {
+    fn burn_only_after_grace_and_wipes_node() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[24u8; 32]);
+        let namehash = node_of(&e, 38);
         let owner = Address::generate(&e);
         let resolver = Address::generate(&e);
-
-        client.set_owner(&namehash, &owner);
-        client.set_resolver(&namehash, &resolver);
-
+        let newcomer = Address::generate(&e);
+        e.ledger().set_timestamp(1_000);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 38), &owner);
+        client.set_resolver(&owner, &namehash, &resolver);
+        client.set_expiry(&owner, &namehash, &2_000);
+
+        e.ledger().set_timestamp(2_000 + GRACE_PERIOD_SECONDS);
+        let early = catch_unwind(AssertUnwindSafe(|| client.burn(&namehash)));
+        assert!(early.is_err(), "burn must wait out the grace period");
+
+        e.ledger().set_timestamp(2_001 + GRACE_PERIOD_SECONDS);
+        client.burn(&namehash);
         let events = e.events().all();
-        assert_eq!(events.len(), 1);
-        let (contract_id, topics, data) = events.get(0).unwrap().clone();
-        assert_eq!(contract_id, id);
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
         assert_eq!(
             Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
-            Symbol::new(&e, "resolver_changed")
+            Symbol::new(&e, "burn")
         );
-        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
-        assert_eq!(topic_namehash, namehash);
         let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
-        assert_eq!(map.get(Symbol::new(&e, "resolver")).unwrap(), resolver);
+        assert_eq!(map.get(Symbol::new(&e, "owner")).unwrap(), owner);
 
-        let stored_resolver = e
-            .as_contract(&id, || Registry::read_resolver(&e, &namehash))
-            .unwrap();
-        assert_eq!(stored_resolver, resolver);
+        e.as_contract(&id, || {
+            assert!(Registry::read_owner(&e, &namehash).is_none());
+            assert!(Registry::read_resolver(&e, &namehash).is_none());
+            assert!(Registry::read_expires(&e, &namehash).is_none());
+        });
+
+        allow_controller(&e, &id, &newcomer);
+        client.set_owner(&newcomer, &root_node(&e), &label_of(&e, 38), &newcomer);
+        assert_eq!(client.owner(&namehash), newcomer);
     }
 
     #[test]
-    fn resolver_idempotent_same_value() {
+    fn grace_period_follows_the_parent_controller() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[22u8; 32]);
+        let controller = Address::generate(&e);
         let owner = Address::generate(&e);
-        let resolver = Address::generate(&e);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash, &resolver).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash, &resolver);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash, &resolver).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash, &resolver);
+        allow_controller(&e, &id, &controller);
+        e.ledger().set_timestamp(1_000);
+        let tld = client.set_owner(&controller, &root_node(&e), &label_of(&e, 60), &controller);
+        let name = client.set_owner(&controller, &tld, &label_of(&e, 61), &owner);
+        client.set_expiry(&controller, &name, &2_000);
+        assert_eq!(client.grace_period(&tld), GRACE_PERIOD_SECONDS);
+
+        let outsider = Address::generate(&e);
+        assert_eq!(
+            client.try_set_grace_period(&outsider, &tld, &100),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        // A controller that does not own the parent cannot set it either.
+        let foreign = Address::generate(&e);
+        allow_controller(&e, &id, &foreign);
+        assert_eq!(
+            client.try_set_grace_period(&foreign, &tld, &100),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
 
-        assert_eq!(client.resolver(&namehash), resolver);
+        client.set_grace_period(&controller, &tld, &100);
+        assert_eq!(client.grace_period(&tld), 100);
+        e.ledger().set_timestamp(2_100);
+        assert_eq!(
+            client.try_burn(&name),
+            Err(Ok(RegistryError::GraceActive.into()))
+        );
+        assert_eq!(client.owner_checked(&name), Some(owner));
+        e.ledger().set_timestamp(2_101);
+        assert_eq!(client.owner_checked(&name), None);
+        client.burn(&name);
+        assert_eq!(client.get_owner(&name), None);
     }
 
     #[test]
-    fn resolver_isolation_between_namehashes() {
+    fn owner_nonce_moves_with_owner_expiry_and_records() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash_a = BytesN::from_array(&e, &[23u8; 32]);
-        let namehash_b = BytesN::from_array(&e, &[24u8; 32]);
-        let owner_a = Address::generate(&e);
-        let owner_b = Address::generate(&e);
-        let resolver_a1 = Address::generate(&e);
-        let resolver_a2 = Address::generate(&e);
-        let resolver_b = Address::generate(&e);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_a,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash_a, &owner_a).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash_a, &owner_a);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_b,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash_b, &owner_b).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash_b, &owner_b);
-
-        // Set resolvers
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_a,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash_a, &resolver_a1).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash_a, &resolver_a1);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_b,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash_b, &resolver_b).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash_b, &resolver_b);
-
-        // Update resolver for A
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_a,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_resolver",
-                    args: (&namehash_a, &resolver_a2).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_resolver(&namehash_a, &resolver_a2);
+        let controller = Address::generate(&e);
+        let owner = Address::generate(&e);
+        allow_controller(&e, &id, &controller);
+        e.ledger().set_timestamp(1_000);
+        let name = node_of(&e, 62);
+        assert_eq!(client.owner_nonce(&name), 0);
+        client.set_owner(&controller, &root_node(&e), &label_of(&e, 62), &owner);
+        let mut last = client.owner_nonce(&name);
+        assert!(last > 0);
+
+        let mut moved = |e: &Env| {
+            let nonce = RegistryClient::new(e, &id).owner_nonce(&name);
+            let changed = nonce != last;
+            last = nonce;
+            changed
+        };
+        client.set_expiry(&controller, &name, &5_000);
+        assert!(moved(&e));
+        client.set_resolver(&owner, &name, &Address::generate(&e));
+        assert!(!moved(&e));
+        client.bump_record_version(&owner, &name);
+        assert!(moved(&e));
+        client.transfer(&owner, &name, &Address::generate(&e));
+        assert!(moved(&e));
+    }
 
-        assert_eq!(client.resolver(&namehash_a), resolver_a2);
-        assert_eq!(client.resolver(&namehash_b), resolver_b);
+    #[test]
+    fn burn_rejects_names_without_expiry() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+
+        let namehash = node_of(&e, 39);
+        let owner = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 39), &owner);
+
+        let attempt = catch_unwind(AssertUnwindSafe(|| client.burn(&namehash)));
+        assert!(attempt.is_err());
+        assert_eq!(client.owner(&namehash), owner);
     }
 
     #[test]
-    fn resolver_rejects_non_owner() {
+    fn record_version_bumps_on_request_and_burn() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[5u8; 32]);
+        let namehash = node_of(&e, 40);
         let owner = Address::generate(&e);
         let attacker = Address::generate(&e);
-        let resolver = Address::generate(&e);
+        assert_eq!(client.record_version(&namehash), 0);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        e.ledger().set_timestamp(1_000);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 40), &owner);
+        assert_eq!(client.bump_record_version(&owner, &namehash), 1);
+        let events = e.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "record_version_changed")
+        );
+        let map = Map::<Symbol, u32>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "version")).unwrap(), 1);
 
-        let result = catch_unwind(AssertUnwindSafe(|| {
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            client.bump_record_version(&attacker, &namehash);
+        }));
+        assert!(attempt.is_err());
+
+        client.set_expiry(&owner, &namehash, &2_000);
+        e.ledger().set_timestamp(2_001 + GRACE_PERIOD_SECONDS);
+        client.burn(&namehash);
+        assert_eq!(client.record_version(&namehash), 2);
+    }
+
+    #[test]
+    fn constructor_sets_the_admin_who_manages_controllers() {
+        let e = Env::default();
+        let admin = Address::generate(&e);
+        let registrar = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
+        let client = RegistryClient::new(&e, &id);
+        assert_eq!(client.admin(), admin);
+
+        let unauthorized = catch_unwind(AssertUnwindSafe(|| {
             client
                 .mock_auths(&[MockAuth {
-                    address: &attacker,
+                    address: &registrar,
                     invoke: &MockAuthInvoke {
                         contract: &id,
-                        fn_name: "set_resolver",
-                        args: (&namehash, &resolver).into_val(&e),
+                        fn_name: "set_controller",
+                        args: (&registrar, true).into_val(&e),
                         sub_invokes: &[],
                     },
                 }])
-                .set_resolver(&namehash, &resolver);
+                .set_controller(&registrar, &true);
         }));
+        assert!(unauthorized.is_err());
+        assert!(!client.is_controller(&registrar));
 
-        assert!(result.is_err());
-        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
-        assert!(stored.is_none());
-    }
+        e.mock_all_auths();
+        client.set_controller(&registrar, &true);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "controller_changed")
+        );
+        assert!(client.is_controller(&registrar));
 
-    #[test]
-    fn namehash_root_is_zero() {
-        let e = Env::default();
-        let labels: SorobanVec<Bytes> = vec![&e];
-        let hash = Registry::namehash(e.clone(), labels);
-        assert_eq!(hash.to_array(), [0u8; 32]);
+        client.set_controller(&registrar, &false);
+        assert!(!client.is_controller(&registrar));
     }
 
     #[test]
-    fn namehash_single_label_deterministic() {
+    fn constructor_mints_root_and_admin_creates_tlds() {
         let e = Env::default();
-        let label = Bytes::from_slice(&e, b"stellar");
+        e.mock_all_auths();
 
-        let mut labels_a = vec![&e];
-        labels_a.push_back(label.clone());
-        let hash_a = Registry::namehash(e.clone(), labels_a);
+        let admin = Address::generate(&e);
+        let root_owner = Address::generate(&e);
+        let registrar = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &root_owner));
+        let client = RegistryClient::new(&e, &id);
+        assert_eq!(client.owner(&root_node(&e)), root_owner);
 
-        let mut labels_b = vec![&e];
-        labels_b.push_back(label);
-        let hash_b = Registry::namehash(e.clone(), labels_b);
+        let stellar = Bytes::from_slice(&e, b"stellar");
+        let tld = client.create_tld(&admin, &stellar, &registrar);
+        let events = e.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "tld_created")
+        );
+        assert_eq!(
+            BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap(),
+            tld
+        );
+        let data = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        let owner = Address::try_from_val(&e, &data.get(Symbol::new(&e, "owner")).unwrap());
+        assert_eq!(owner.unwrap(), registrar);
+        assert_eq!(tld, Registry::subnode(&e, &root_node(&e), &stellar));
+        assert_eq!(client.owner(&tld), registrar);
+        assert_eq!(client.subnodes_of(&root_node(&e)), vec![&e, tld.clone()]);
 
-        assert_eq!(hash_a, hash_b);
+        assert_eq!(
+            client.try_create_tld(&admin, &stellar, &root_owner),
+            Err(Ok(RegistryError::TldExists.into()))
+        );
+        assert_eq!(
+            client.try_create_tld(&root_owner, &Bytes::from_slice(&e, b"xlm"), &registrar),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
     }
 
     #[test]
-    fn namehash_multiple_labels_order_sensitive() {
+    fn only_controllers_create_first_time_owners() {
         let e = Env::default();
+        e.mock_all_auths();
 
-        let mut labels_one = vec![&e];
-        labels_one.push_back(Bytes::from_slice(&e, b"foo"));
-        labels_one.push_back(Bytes::from_slice(&e, b"bar"));
-        let hash_one = Registry::namehash(e.clone(), labels_one);
+        let admin = Address::generate(&e);
+        let registrar = Address::generate(&e);
+        let squatter = Address::generate(&e);
+        let buyer = Address::generate(&e);
+        let namehash = node_of(&e, 41);
+        let id = e.register(Registry, (&admin, &registrar));
+        let client = RegistryClient::new(&e, &id);
 
-        let mut labels_two = vec![&e];
-        labels_two.push_back(Bytes::from_slice(&e, b"bar"));
-        labels_two.push_back(Bytes::from_slice(&e, b"foo"));
-        let hash_two = Registry::namehash(e.clone(), labels_two);
+        let squat = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&squatter, &root_node(&e), &label_of(&e, 41), &squatter);
+        }));
+        assert!(squat.is_err(), "non-controllers must not create names");
 
-        assert_ne!(hash_one, hash_two);
+        client.set_controller(&registrar, &true);
+        client.set_owner(&registrar, &root_node(&e), &label_of(&e, 41), &buyer);
+        assert_eq!(client.owner(&namehash), buyer);
     }
 
     #[test]
-    fn namehash_unicode_utf8_stability() {
+    fn controller_reclaims_only_expired_names() {
         let e = Env::default();
-        let unicode_label = "stêllar🚀";
+        e.mock_all_auths();
 
-        let mut labels_one = vec![&e];
-        labels_one.push_back(Bytes::from_slice(&e, unicode_label.as_bytes()));
-        let hash_one = Registry::namehash(e.clone(), labels_one);
+        let admin = Address::generate(&e);
+        let registrar = Address::generate(&e);
+        let holder = Address::generate(&e);
+        let namehash = node_of(&e, 42);
+        let id = e.register(Registry, (&admin, &registrar));
+        let client = RegistryClient::new(&e, &id);
+        client.set_controller(&registrar, &true);
 
-        let mut labels_two = vec![&e];
-        labels_two.push_back(Bytes::from_slice(&e, unicode_label.as_bytes()));
-        let hash_two = Registry::namehash(e.clone(), labels_two);
+        e.ledger().set_timestamp(1_000);
+        client.set_owner(&registrar, &root_node(&e), &label_of(&e, 42), &holder);
+        client.set_expiry(&registrar, &namehash, &5_000);
 
-        assert_eq!(hash_one, hash_two);
+        let live = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&registrar, &root_node(&e), &label_of(&e, 42), &registrar);
+        }));
+        assert!(live.is_err(), "controllers cannot take live names");
+        assert_eq!(client.owner(&namehash), holder);
+
+        // Expired but still in grace: the holder can renew, so it stays theirs.
+        e.ledger().set_timestamp(5_001);
+        assert_eq!(
+            client.try_set_owner(&registrar, &root_node(&e), &label_of(&e, 42), &registrar),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+
+        // Past grace only a controller holding the parent may reclaim it.
+        e.ledger().set_timestamp(5_001 + GRACE_PERIOD_SECONDS);
+        let foreign = Address::generate(&e);
+        client.set_controller(&foreign, &true);
+        assert_eq!(
+            client.try_set_owner(&foreign, &root_node(&e), &label_of(&e, 42), &foreign),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        client.set_owner(&registrar, &root_node(&e), &label_of(&e, 42), &registrar);
+        assert_eq!(client.owner(&namehash), registrar);
     }
 
     #[test]
-    fn namehash_rejects_empty_label() {
+    fn controllers_create_names_only_under_parents_they_own() {
         let e = Env::default();
-        let mut labels = vec![&e];
-        labels.push_back(Bytes::new(&e));
+        e.mock_all_auths();
 
-        let result = catch_unwind(AssertUnwindSafe(|| Registry::namehash(e.clone(), labels)));
-        assert!(result.is_err());
+        let admin = Address::generate(&e);
+        let registrar = Address::generate(&e);
+        let other = Address::generate(&e);
+        let buyer = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
+        let client = RegistryClient::new(&e, &id);
+        client.set_controller(&registrar, &true);
+        client.set_controller(&other, &true);
+        let tld = client.create_tld(&admin, &Bytes::from_slice(&e, b"stellar"), &registrar);
+
+        assert_eq!(
+            client.try_set_owner(&other, &tld, &label_of(&e, 1), &buyer),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_set_owner(&registrar, &root_node(&e), &label_of(&e, 2), &buyer),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        let name = client.set_owner(&registrar, &tld, &label_of(&e, 1), &buyer);
+        assert_eq!(client.owner(&name), buyer);
     }
 
     #[test]
-    fn namehash_rejects_overlong_label() {
+    fn parent_owner_creates_children_via_set_owner() {
         let e = Env::default();
-        let mut labels = vec![&e];
-        let long_label_bytes: std::vec::Vec<u8> = core::iter::repeat(b'a')
-            .take((MAX_LABEL_LENGTH + 1) as usize)
-            .collect();
-        let long_label = Bytes::from_slice(&e, &long_label_bytes);
-        labels.push_back(long_label);
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
 
-        let result = catch_unwind(AssertUnwindSafe(|| Registry::namehash(e.clone(), labels)));
-        assert!(result.is_err());
+        let tld_owner = Address::generate(&e);
+        let operator = Address::generate(&e);
+        let alice = Address::generate(&e);
+        let bob = Address::generate(&e);
+        let tld = Bytes::from_slice(&e, b"stellar");
+        allow_controller(&e, &id, &tld_owner);
+        let tld_node = client.set_owner(&tld_owner, &root_node(&e), &tld, &tld_owner);
+        assert_eq!(tld_node, client.namehash(&vec![&e, tld.clone()]));
+
+        let alice_label = Bytes::from_slice(&e, b"alice");
+        let alice_node = client.set_owner(&tld_owner, &tld_node, &alice_label, &alice);
+        assert_eq!(
+            alice_node,
+            client.namehash(&vec![&e, tld.clone(), alice_label])
+        );
+        assert_eq!(client.owner(&alice_node), alice);
+
+        client.set_approval_for_all(&tld_owner, &operator, &true);
+        let bob_node = client.set_owner(&operator, &tld_node, &Bytes::from_slice(&e, b"bob"), &bob);
+        assert_eq!(client.owner(&bob_node), bob);
     }
 
     #[test]
-    fn owner_default_panics() {
+    fn creating_child_requires_parent_authority() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
-        let namehash = BytesN::from_array(&e, &[9u8; 32]);
 
-        let result = catch_unwind(AssertUnwindSafe(|| client.owner(&namehash)));
-        assert!(result.is_err());
+        let tld_owner = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        let label = Bytes::from_slice(&e, b"squat");
+        let unowned_parent = node_of(&e, 43);
+        allow_controller(&e, &id, &tld_owner);
+        let tld_node = client.set_owner(
+            &tld_owner,
+            &root_node(&e),
+            &Bytes::from_slice(&e, b"stellar"),
+            &tld_owner,
+        );
+
+        let under_owned = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&outsider, &tld_node, &label, &outsider);
+        }));
+        assert!(
+            under_owned.is_err(),
+            "only the parent's owner may create children"
+        );
+
+        let under_unowned = catch_unwind(AssertUnwindSafe(|| {
+            client.set_owner(&outsider, &unowned_parent, &label, &outsider);
+        }));
+        assert!(under_unowned.is_err(), "unowned parents grant no authority");
     }
 
     #[test]
-    fn resolver_default_panics() {
+    fn checked_reads_hide_names_after_grace() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
-        let namehash = BytesN::from_array(&e, &[20u8; 32]);
 
-        let result = catch_unwind(AssertUnwindSafe(|| client.resolver(&namehash)));
-        assert!(result.is_err());
+        let namehash = node_of(&e, 44);
+        let permanent = node_of(&e, 45);
+        let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        e.ledger().set_timestamp(1_000);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 44), &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 45), &owner);
+        client.set_resolver(&owner, &namehash, &resolver);
+        client.set_expiry(&owner, &namehash, &2_000);
+        assert_eq!(client.owner_checked(&node_of(&e, 46)), None);
+
+        e.ledger().set_timestamp(2_000 + GRACE_PERIOD_SECONDS);
+        assert_eq!(client.owner_checked(&namehash), Some(owner.clone()));
+        assert_eq!(client.resolver_checked(&namehash), Some(resolver.clone()));
+
+        e.ledger().set_timestamp(2_001 + GRACE_PERIOD_SECONDS);
+        assert_eq!(client.owner_checked(&namehash), None);
+        assert_eq!(client.resolver_checked(&namehash), None);
+        assert_eq!(client.owner(&namehash), owner, "raw reads are unchanged");
+        assert_eq!(client.owner_checked(&permanent), Some(owner));
     }
 
     #[test]
-    fn renew_extends_from_now_if_unset() {
+    fn controller_extends_expiry_without_owner_auth() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[6u8; 32]);
+        let namehash = node_of(&e, 70);
         let owner = Address::generate(&e);
-        let now = 1_700_000_000u64;
-        e.ledger().set_timestamp(now);
+        let controller = Address::generate(&e);
+        e.ledger().set_timestamp(1_000);
+        allow_controller(&e, &id, &controller);
+        client.set_owner(&controller, &root_node(&e), &label_of(&e, 70), &owner);
+        client.set_expiry(&controller, &namehash, &2_000);
 
+        e.ledger().set_timestamp(2_500);
         client
             .mock_auths(&[MockAuth {
-                address: &owner,
+                address: &controller,
                 invoke: &MockAuthInvoke {
                     contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
+                    fn_name: "extend_expiry",
+                    args: (&controller, &namehash, &5_000u64).into_val(&e),
                     sub_invokes: &[],
                 },
             }])
-            .set_owner(&namehash, &owner);
+            .extend_expiry(&controller, &namehash, &5_000);
+        let events = e.events().all();
+        assert_eq!(events.len(), 2);
+        assert_eq!(client.expires(&namehash), 5_000);
+        assert_eq!(client.owner(&namehash), owner);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "renew",
-                    args: (&namehash,).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .renew(&namehash);
+        let shrink = catch_unwind(AssertUnwindSafe(|| {
+            client.extend_expiry(&controller, &namehash, &4_000);
+        }));
+        assert!(shrink.is_err());
 
-        let expiry = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .unwrap();
-        assert_eq!(expiry, now + RENEW_EXTENSION_SECONDS);
+        e.ledger().set_timestamp(5_000 + GRACE_PERIOD_SECONDS + 1);
+        let released = catch_unwind(AssertUnwindSafe(|| {
+            client.extend_expiry(&controller, &namehash, &u64::MAX);
+        }));
+        assert!(released.is_err());
     }
 
     #[test]
-    fn renew_emits_event_with_new_expiry() {
+    fn extend_expiry_rejects_non_controller() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
-        let client = RegistryClient::new(&e, &id);
-
-        let namehash = BytesN::from_array(&e, &[25u8; 32]);
-        let owner = Address::generate(&e);
-        let now = 42_000u64;
-
-        client.set_owner(&namehash, &owner);
-        e.ledger().set_timestamp(now);
-        client.renew(&namehash);
-
-        let events = e.events().all();
-        assert_eq!(events.len(), 1);
-        let (contract_id, topics, data) = events.get(0).unwrap().clone();
-        assert_eq!(contract_id, id);
-        assert_eq!(
-            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
-            Symbol::new(&e, "renew")
-        );
-        let topic_namehash = BytesN::<32>::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
-        assert_eq!(topic_namehash, namehash);
-        let map = Map::<Symbol, u64>::try_from_val(&e, &data).unwrap();
-        let expires_at = map.get(Symbol::new(&e, "expires_at")).unwrap();
-
-        let stored_expiry = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .unwrap();
-        assert_eq!(stored_expiry, now + RENEW_EXTENSION_SECONDS);
-        assert_eq!(expires_at, stored_expiry);
-    }
-
-    #[test]
-    fn renew_requires_owner_auth() {
-        let e = Env::default();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[8u8; 32]);
+        let namehash = node_of(&e, 71);
         let owner = Address::generate(&e);
-        let attacker = Address::generate(&e);
-
-        e.ledger().set_timestamp(1_234_567u64);
-
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        let stranger = Address::generate(&e);
+        e.ledger().set_timestamp(1_000);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 71), &owner);
+        client.set_expiry(&owner, &namehash, &2_000);
 
         let attempt = catch_unwind(AssertUnwindSafe(|| {
-            client
-                .mock_auths(&[MockAuth {
-                    address: &attacker,
-                    invoke: &MockAuthInvoke {
-                        contract: &id,
-                        fn_name: "renew",
-                        args: (&namehash,).into_val(&e),
-                        sub_invokes: &[],
-                    },
-                }])
-                .renew(&namehash);
+            client.extend_expiry(&stranger, &namehash, &9_000);
         }));
-
         assert!(attempt.is_err());
-        let expiry = e.as_contract(&id, || Registry::read_expires(&e, &namehash));
-        assert!(expiry.is_none());
+        assert_eq!(client.expires(&namehash), 2_000);
     }
 
     #[test]
-    fn renew_before_expiry_increases_expiry() {
+    fn writes_and_bump_extend_entry_ttl() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[9u8; 32]);
+        let namehash = node_of(&e, 72);
         let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 72), &owner);
+        client.set_resolver(&owner, &namehash, &resolver);
+        client.set_expiry(&owner, &namehash, &2_000);
+        client.lease(&owner, &namehash, &Address::generate(&e), &1_000);
+        client.set_flags(&namehash, &flags::FROZEN_RESOLVER);
+        client.set_grace_period(&owner, &namehash, &100);
+        client.set_owner(&owner, &namehash, &label_of(&e, 74), &owner);
+        let ttl = |key: DataKey| e.as_contract(&id, || e.storage().persistent().get_ttl(&key));
+        let keys = [
+            DataKey::Owner(namehash.clone()),
+            DataKey::Resolver(namehash.clone()),
+            DataKey::Expires(namehash.clone()),
+            DataKey::Lease(namehash.clone()),
+            DataKey::Flags(namehash.clone()),
+            DataKey::Parent(namehash.clone()),
+            DataKey::Label(namehash.clone()),
+            DataKey::Subnodes(namehash.clone()),
+            DataKey::GracePeriod(namehash.clone()),
+            DataKey::OwnedNames(owner.clone()),
+        ];
+        for key in keys.clone() {
+            assert_eq!(ttl(key), TTL_EXTEND_TO);
+        }
 
-        let first_now = 1_000u64;
-        e.ledger().set_timestamp(first_now);
+        e.ledger()
+            .with_mut(|li| li.sequence_number += 10 * DAY_IN_LEDGERS);
+        assert_eq!(
+            ttl(DataKey::Owner(namehash.clone())),
+            TTL_EXTEND_TO - 10 * DAY_IN_LEDGERS
+        );
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        client.bump(&namehash);
+        for key in keys {
+            assert_eq!(ttl(key), TTL_EXTEND_TO);
+        }
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "renew",
-                    args: (&namehash,).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .renew(&namehash);
+        // Unknown names are a no-op rather than a panic.
+        client.bump(&node_of(&e, 73));
+    }
+
+    #[test]
+    fn names_of_tracks_acquisitions_and_transfers() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
 
-        let first_expiry = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .unwrap();
+        let alice = Address::generate(&e);
+        let bob = Address::generate(&e);
+        allow_controller(&e, &id, &alice);
+        let first = client.set_owner(&alice, &root_node(&e), &label_of(&e, 74), &alice);
+        let second = client.set_owner(&alice, &root_node(&e), &label_of(&e, 75), &alice);
+        assert_eq!(
+            client.names_of(&alice),
+            vec![&e, first.clone(), second.clone()]
+        );
 
-        let second_now = first_expiry - RENEW_EXTENSION_SECONDS / 2;
-        e.ledger().set_timestamp(second_now);
+        client.transfer(&alice, &first, &bob);
+        assert_eq!(client.names_of(&alice), vec![&e, second.clone()]);
+        assert_eq!(client.names_of(&bob), vec![&e, first.clone()]);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "renew",
-                    args: (&namehash,).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .renew(&namehash);
+        // Re-asserting the current owner does not duplicate the entry.
+        client.transfer(&bob, &first, &bob);
+        assert_eq!(client.names_of(&bob), vec![&e, first.clone()]);
 
-        let second_expiry = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .unwrap();
-        assert_eq!(second_expiry, first_expiry + RENEW_EXTENSION_SECONDS);
+        let sub = client.set_subnode_owner(&second, &label_of(&e, 1), &bob);
+        assert_eq!(client.names_of(&bob), vec![&e, first, sub]);
     }
 
     #[test]
-    fn renew_after_expiry_advances_to_now() {
+    fn subnodes_of_tracks_children_until_burn() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[10u8; 32]);
         let owner = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        e.ledger().set_timestamp(1_000);
+        let tld = client.set_owner(&owner, &root_node(&e), &label_of(&e, 76), &owner);
+        let a = client.set_owner(&owner, &tld, &label_of(&e, 1), &owner);
+        let b = client.set_subnode_owner(&tld, &label_of(&e, 2), &owner);
+        client.set_subnode_owner(&tld, &label_of(&e, 2), &owner);
+        assert_eq!(client.subnodes_of(&tld), vec![&e, a.clone(), b.clone()]);
+        assert_eq!(client.subnodes_of(&root_node(&e)), vec![&e, tld.clone()]);
+
+        client.set_expiry(&owner, &a, &2_000);
+        e.ledger().set_timestamp(2_000 + GRACE_PERIOD_SECONDS + 1);
+        client.burn(&a);
+        assert_eq!(client.subnodes_of(&tld), vec![&e, b.clone()]);
+        assert_eq!(client.names_of(&owner), vec![&e, tld, b]);
+    }
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
-
-        // Seed storage with an expired timestamp.
-        e.as_contract(&id, || {
-            let past = 1_000u64;
-            e.storage()
-                .persistent()
-                .set(&DataKey::Expires(namehash.clone()), &past);
-        });
+    #[test]
+    fn name_of_rebuilds_dotted_name() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
 
-        let now = 5_000u64;
-        e.ledger().set_timestamp(now);
+        let owner = Address::generate(&e);
+        allow_controller(&e, &id, &owner);
+        e.ledger().set_timestamp(1_000);
+        let stellar = Bytes::from_slice(&e, b"stellar");
+        let alice = Bytes::from_slice(&e, b"alice");
+        let pay = Bytes::from_slice(&e, b"pay");
+        let tld = client.set_owner(&owner, &root_node(&e), &stellar, &owner);
+        let name = client.set_owner(&owner, &tld, &alice, &owner);
+        let sub = client.set_subnode_owner(&name, &pay, &owner);
+
+        assert_eq!(client.name_of(&tld), stellar);
+        assert_eq!(
+            client.name_of(&name),
+            Bytes::from_slice(&e, b"alice.stellar")
+        );
+        assert_eq!(
+            client.name_of(&sub),
+            Bytes::from_slice(&e, b"pay.alice.stellar")
+        );
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "renew",
-                    args: (&namehash,).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .renew(&namehash);
+        // Burned nodes stay nameable.
+        client.set_expiry(&owner, &sub, &2_000);
+        e.ledger().set_timestamp(2_000 + GRACE_PERIOD_SECONDS + 1);
+        client.burn(&sub);
+        assert_eq!(
+            client.name_of(&sub),
+            Bytes::from_slice(&e, b"pay.alice.stellar")
+        );
 
-        let expiry = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .unwrap();
-        assert_eq!(expiry, now + RENEW_EXTENSION_SECONDS);
+        let unknown = catch_unwind(AssertUnwindSafe(|| client.name_of(&node_of(&e, 77))));
+        assert!(unknown.is_err());
     }
 
     #[test]
-    fn renew_isolation_between_namehashes() {
+    fn upgrade_and_migrate_require_admin() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let admin = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
         let client = RegistryClient::new(&e, &id);
 
-        let namehash_a = BytesN::from_array(&e, &[11u8; 32]);
-        let namehash_b = BytesN::from_array(&e, &[12u8; 32]);
-        let owner_a = Address::generate(&e);
-        let owner_b = Address::generate(&e);
+        let hash = BytesN::from_array(&e, &[7u8; 32]);
+        let res = catch_unwind(AssertUnwindSafe(|| client.upgrade(&outsider, &hash)));
+        assert!(res.is_err());
+        let res = catch_unwind(AssertUnwindSafe(|| client.migrate(&outsider)));
+        assert!(res.is_err());
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_a,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash_a, &owner_a).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash_a, &owner_a);
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_b,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash_b, &owner_b).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash_b, &owner_b);
+        // Current storage migrates as a no-op.
+        assert_eq!(client.storage_version(), STORAGE_VERSION);
+        assert_eq!(client.migrate(&admin), STORAGE_VERSION);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_b,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "renew",
-                    args: (&namehash_b,).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .renew(&namehash_b);
+        // Storage from before versioning reads as 0 and is brought up to date.
+        e.as_contract(&id, || {
+            e.storage().persistent().remove(&DataKey::StorageVersion);
+        });
+        assert_eq!(client.storage_version(), 0);
+        assert_eq!(client.migrate(&admin), STORAGE_VERSION);
+        let (_, topics, data) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "migrated")
+        );
+        let data = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        assert_eq!(
+            u32::try_from_val(&e, &data.get(Symbol::new(&e, "from")).unwrap()).unwrap(),
+            0
+        );
+        assert_eq!(client.storage_version(), STORAGE_VERSION);
 
-        let expiry_b_before = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash_b))
-            .unwrap();
+        // Storage written by newer code must not be touched by older code.
+        e.as_contract(&id, || {
+            e.storage()
+                .persistent()
+                .set(&DataKey::StorageVersion, &(STORAGE_VERSION + 1));
+        });
+        let res = catch_unwind(AssertUnwindSafe(|| client.migrate(&admin)));
+        assert!(res.is_err());
+    }
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner_a,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "renew",
-                    args: (&namehash_a,).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .renew(&namehash_a);
+    #[test]
+    fn failures_surface_typed_errors() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let admin = Address::generate(&e);
+        let controller = Address::generate(&e);
+        let owner = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        let zero = Address::from_str(&e, ZERO_ACCOUNT_STR);
+        let root = root_node(&e);
+        let node = node_of(&e, 1);
 
-        let expiry_a_after = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash_a))
-            .unwrap();
-        let expiry_b_after = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash_b))
-            .unwrap();
+        let id = e.register(Registry, (&admin, &admin));
+        let client = RegistryClient::new(&e, &id);
+        assert_eq!(
+            client.try_owner(&node),
+            Err(Ok(RegistryError::NotFound.into()))
+        );
+        assert_eq!(
+            client.try_resolver(&node),
+            Err(Ok(RegistryError::NotFound.into()))
+        );
+        assert_eq!(client.get_owner(&node), None);
+        assert_eq!(client.get_resolver(&node), None);
+        // Without controller rights only the root owner can authorize a child.
+        assert_eq!(
+            client.try_set_owner(&controller, &root, &label_of(&e, 1), &owner),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_set_owner(&controller, &root, &Bytes::new(&e), &owner),
+            Err(Ok(RegistryError::InvalidLabel.into()))
+        );
 
+        allow_controller(&e, &id, &controller);
         assert_eq!(
-            expiry_b_after, expiry_b_before,
-            "unrelated namehash changed"
+            client.try_set_owner(&controller, &root, &label_of(&e, 1), &zero),
+            Err(Ok(RegistryError::ZeroAddress.into()))
+        );
+        client.set_owner(&controller, &root, &label_of(&e, 1), &owner);
+        assert_eq!(client.get_owner(&node), Some(owner.clone()));
+        assert_eq!(
+            client.try_transfer(&outsider, &node, &outsider),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_set_approval_for_all(&owner, &owner, &true),
+            Err(Ok(RegistryError::InvalidOperator.into()))
+        );
+
+        e.ledger().set_timestamp(1_000);
+        assert_eq!(
+            client.try_set_expiry(&controller, &node, &999),
+            Err(Ok(RegistryError::InvalidExpiry.into()))
+        );
+        client.set_expiry(&controller, &node, &2_000);
+        assert_eq!(
+            client.try_burn(&node),
+            Err(Ok(RegistryError::GraceActive.into()))
+        );
+        e.ledger().set_timestamp(2_000 + GRACE_PERIOD_SECONDS + 1);
+        assert_eq!(
+            client.try_extend_expiry(&controller, &node, &u64::MAX),
+            Err(Ok(RegistryError::Expired.into()))
+        );
+        client.set_expiry(&controller, &node, &u64::MAX);
+        assert_eq!(
+            client.try_renew(&controller, &node),
+            Err(Ok(RegistryError::Overflow.into()))
         );
-        assert!(expiry_a_after >= expiry_b_after);
     }
 
     #[test]
-    fn non_owner_cannot_mutate_fields() {
+    fn lease_grants_time_bounded_lessee_until_it_lapses() {
         let e = Env::default();
-        let id = e.register(Registry, ());
+        e.mock_all_auths();
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[13u8; 32]);
+        let controller = Address::generate(&e);
         let owner = Address::generate(&e);
-        let attacker = Address::generate(&e);
-        let resolver = Address::generate(&e);
+        let lessee = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        let node = node_of(&e, 40);
+        allow_controller(&e, &id, &controller);
+        client.set_owner(&controller, &root_node(&e), &label_of(&e, 40), &owner);
+        e.ledger().set_timestamp(1_000);
+        client.set_expiry(&controller, &node, &5_000);
 
-        client
-            .mock_auths(&[MockAuth {
-                address: &owner,
-                invoke: &MockAuthInvoke {
-                    contract: &id,
-                    fn_name: "set_owner",
-                    args: (&namehash, &owner).into_val(&e),
-                    sub_invokes: &[],
-                },
-            }])
-            .set_owner(&namehash, &owner);
+        assert_eq!(
+            client.try_lease(&outsider, &node, &lessee, &2_000),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_lease(&owner, &node, &lessee, &6_000),
+            Err(Ok(RegistryError::InvalidExpiry.into()))
+        );
+        client.lease(&owner, &node, &lessee, &2_000);
+        let (_, topics, _) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "lease_granted")
+        );
+        assert_eq!(client.lessee(&node), Some(lessee.clone()));
+        assert_eq!(client.lease_expires(&node), Some(2_000));
+        assert_eq!(
+            client.try_lease(&owner, &node, &outsider, &3_000),
+            Err(Ok(RegistryError::LeaseActive.into()))
+        );
+        assert_eq!(
+            client.try_end_lease(&owner, &node),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
 
-        let set_owner_attempt = catch_unwind(AssertUnwindSafe(|| {
-            client.set_owner(&namehash, &attacker);
-        }));
-        assert!(set_owner_attempt.is_err());
-        assert_eq!(client.owner(&namehash), owner);
+        e.ledger().set_timestamp(2_000);
+        assert_eq!(client.lessee(&node), None);
+        assert_eq!(client.lease_expires(&node), None);
+        client.end_lease(&owner, &node);
+        assert_eq!(
+            client.try_end_lease(&owner, &node),
+            Err(Ok(RegistryError::NoLease.into()))
+        );
 
-        let transfer_attempt = catch_unwind(AssertUnwindSafe(|| {
-            client.transfer(&namehash, &attacker);
-        }));
-        assert!(transfer_attempt.is_err());
-        assert_eq!(client.owner(&namehash), owner);
+        client.lease(&owner, &node, &outsider, &3_000);
+        client.end_lease(&outsider, &node);
+        assert_eq!(client.lessee(&node), None);
+    }
 
-        let resolver_attempt = catch_unwind(AssertUnwindSafe(|| {
-            client.set_resolver(&namehash, &resolver);
-        }));
-        assert!(resolver_attempt.is_err());
-        let resolver_state = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
-        assert!(resolver_state.is_none());
+    #[test]
+    fn release_clears_live_name_for_owner_or_controller() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
 
-        let renew_attempt = catch_unwind(AssertUnwindSafe(|| {
-            client.renew(&namehash);
-        }));
-        assert!(renew_attempt.is_err());
-        let expires_state = e.as_contract(&id, || Registry::read_expires(&e, &namehash));
-        assert!(expires_state.is_none());
+        let controller = Address::generate(&e);
+        let owner = Address::generate(&e);
+        let resolver = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        let root = root_node(&e);
+        allow_controller(&e, &id, &controller);
+        e.ledger().set_timestamp(1_000);
+
+        let a = client.set_owner(&controller, &root, &label_of(&e, 41), &owner);
+        client.set_expiry(&controller, &a, &5_000);
+        client.set_resolver(&owner, &a, &resolver);
+        assert_eq!(
+            client.try_release(&outsider, &a),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        client.release(&owner, &a);
+        let (_, topics, _) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "released")
+        );
+        assert_eq!(client.get_owner(&a), None);
+        assert_eq!(client.get_resolver(&a), None);
+        assert_eq!(client.record_version(&a), 1);
+        assert_eq!(
+            client.try_release(&owner, &a),
+            Err(Ok(RegistryError::NotFound.into()))
+        );
+
+        // Controllers only release names under a node they own.
+        let tld = client.set_owner(&controller, &root, &label_of(&e, 40), &controller);
+        let b = client.set_owner(&controller, &tld, &label_of(&e, 42), &owner);
+        let foreign = Address::generate(&e);
+        allow_controller(&e, &id, &foreign);
+        assert_eq!(
+            client.try_release(&foreign, &b),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        client.release(&controller, &b);
+        assert_eq!(client.get_owner(&b), None);
+        assert_eq!(client.names_of(&owner).len(), 0);
     }
 
     #[test]
-    fn owner_can_mutate_all_fields() {
+    fn poke_expiry_warns_once_per_phase_and_term() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[14u8; 32]);
+        let controller = Address::generate(&e);
         let owner = Address::generate(&e);
-        let resolver1 = Address::generate(&e);
-        let new_owner = Address::generate(&e);
-        let resolver2 = Address::generate(&e);
+        allow_controller(&e, &id, &controller);
+        e.ledger().set_timestamp(1_000);
+        let a = client.set_owner(&controller, &root_node(&e), &label_of(&e, 43), &owner);
+        let expires = 1_000 + 2 * EXPIRY_WARNING_SECONDS;
+        client.set_expiry(&controller, &a, &expires);
+
+        assert!(!client.poke_expiry(&a));
+        e.ledger().set_timestamp(expires - EXPIRY_WARNING_SECONDS);
+        assert!(client.poke_expiry(&a));
+        let (_, topics, data) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "expiry_warning")
+        );
+        let map = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        let phase = map.get(Symbol::new(&e, "phase")).unwrap();
+        assert_eq!(
+            ExpiryPhase::try_from_val(&e, &phase).unwrap(),
+            ExpiryPhase::Expiring
+        );
+        assert!(!client.poke_expiry(&a));
 
-        let first_now = 10_000u64;
-        e.ledger().set_timestamp(first_now);
+        e.ledger().set_timestamp(expires + 1);
+        assert!(client.poke_expiry(&a));
+        let (_, _, data) = e.events().all().last().unwrap();
+        let map = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        let phase = map.get(Symbol::new(&e, "phase")).unwrap();
+        assert_eq!(
+            ExpiryPhase::try_from_val(&e, &phase).unwrap(),
+            ExpiryPhase::Grace
+        );
+        assert!(!client.poke_expiry(&a));
 
-        client.set_owner(&namehash, &owner);
-        client.set_resolver(&namehash, &resolver1);
-        client.transfer(&namehash, &new_owner);
-        client.set_resolver(&namehash, &resolver2);
+        // A renewal starts a new term whose grace period is signalled afresh.
+        client.set_expiry(&controller, &a, &(expires + 10));
+        assert!(client.poke_expiry(&a));
 
-        let second_now = first_now + 1;
-        e.ledger().set_timestamp(second_now);
+        e.ledger()
+            .set_timestamp(expires + 11 + GRACE_PERIOD_SECONDS);
+        assert!(!client.poke_expiry(&a));
+        assert_eq!(
+            client.try_poke_expiry(&node_of(&e, 99)),
+            Err(Ok(RegistryError::NotFound.into()))
+        );
+    }
 
-        client.renew(&namehash);
+    #[test]
+    fn flags_lock_resolver_transfer_and_subnodes() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
 
-        assert_eq!(client.owner(&namehash), new_owner);
-        assert_eq!(client.resolver(&namehash), resolver2);
-        let expiry = e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .unwrap();
-        assert_eq!(expiry, second_now + RENEW_EXTENSION_SECONDS);
+        let controller = Address::generate(&e);
+        let owner = Address::generate(&e);
+        let other = Address::generate(&e);
+        let resolver = Address::generate(&e);
+        allow_controller(&e, &id, &controller);
+        e.ledger().set_timestamp(1_000);
+        let a = client.set_owner(&controller, &root_node(&e), &label_of(&e, 44), &owner);
+        client.set_expiry(&controller, &a, &5_000);
+        client.set_resolver(&owner, &a, &resolver);
+
+        client.set_flags(&a, &flags::FROZEN_RESOLVER);
+        assert_eq!(client.flags(&a), flags::FROZEN_RESOLVER);
+        assert_eq!(
+            client.try_set_resolver(&owner, &a, &other),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        assert_eq!(
+            client.try_clear_resolver(&owner, &a),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        // Locks can be added, never removed, and only defined bits exist.
+        assert_eq!(
+            client.try_set_flags(&a, &flags::FROZEN_TRANSFER),
+            Err(Ok(RegistryError::InvalidFlags.into()))
+        );
+        assert_eq!(
+            client.try_set_flags(&a, &(flags::ALL + 1)),
+            Err(Ok(RegistryError::InvalidFlags.into()))
+        );
+
+        let child = client.set_subnode_owner(&a, &label_of(&e, 1), &other);
+        client.set_flags(&a, &flags::ALL);
+        assert_eq!(
+            client.try_transfer(&owner, &a, &other),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        assert_eq!(
+            client.try_set_owner(&owner, &root_node(&e), &label_of(&e, 44), &other),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        assert_eq!(
+            client.try_set_subnode_owner(&a, &label_of(&e, 2), &other),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        assert_eq!(
+            client.try_set_subnode_owner(&a, &label_of(&e, 1), &owner),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        assert_eq!(
+            client.try_set_owner(&owner, &a, &label_of(&e, 3), &other),
+            Err(Ok(RegistryError::Locked.into()))
+        );
+        // The child's own locks are separate.
+        client.transfer(&other, &child, &owner);
+
+        // A controller reclaiming the lapsed name starts the new holder unlocked.
+        e.ledger().set_timestamp(5_001 + GRACE_PERIOD_SECONDS);
+        client.set_owner(&controller, &root_node(&e), &label_of(&e, 44), &other);
+        assert_eq!(client.flags(&a), 0);
+        client.set_resolver(&other, &a, &resolver);
     }
 
     #[test]
-    fn events_ordered_and_well_formed() {
+    fn transfer_clean_moves_owner_and_drops_resolver() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[15u8; 32]);
-        let owner = Address::generate(&e);
-        let new_owner = Address::generate(&e);
+        let controller = Address::generate(&e);
+        let seller = Address::generate(&e);
+        let buyer = Address::generate(&e);
         let resolver = Address::generate(&e);
+        allow_controller(&e, &id, &controller);
+        let a = client.set_owner(&controller, &root_node(&e), &label_of(&e, 45), &seller);
+        client.set_resolver(&seller, &a, &resolver);
 
-        client.set_owner(&namehash, &owner);
+        client.transfer_clean(&seller, &a, &buyer);
         let events = e.events().all();
-        assert_eq!(events.len(), 1);
-        let (contract_id, topics, data) = events.get(0).unwrap().clone();
-        assert_eq!(contract_id, id);
+        let name_at = |idx: u32| {
+            let (_, topics, _) = events.get(idx).unwrap();
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap()
+        };
+        assert_eq!(name_at(events.len() - 2), Symbol::new(&e, "transfer"));
         assert_eq!(
-            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
-            Symbol::new(&e, "transfer")
+            name_at(events.len() - 1),
+            Symbol::new(&e, "resolver_cleared")
         );
-        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
-        assert_eq!(map.get(Symbol::new(&e, "from")).unwrap(), owner);
-        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), owner);
+        assert_eq!(client.owner(&a), buyer);
+        assert_eq!(client.get_resolver(&a), None);
+        assert_eq!(client.names_of(&buyer), vec![&e, a.clone()]);
 
-        client.transfer(&namehash, &new_owner);
+        // Without a resolver there is nothing to clear, so only `transfer` is emitted.
+        client.transfer_clean(&buyer, &a, &seller);
         let events = e.events().all();
         assert_eq!(events.len(), 1);
-        let (contract_id, topics, data) = events.get(0).unwrap().clone();
-        assert_eq!(contract_id, id);
+        let (_, topics, _) = events.get(0).unwrap();
         assert_eq!(
             Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
             Symbol::new(&e, "transfer")
         );
-        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
-        assert_eq!(map.get(Symbol::new(&e, "from")).unwrap(), owner);
-        assert_eq!(map.get(Symbol::new(&e, "to")).unwrap(), new_owner);
+        assert_eq!(client.owner(&a), seller);
+        client.transfer(&seller, &a, &buyer);
 
-        client.set_resolver(&namehash, &resolver);
-        let events = e.events().all();
-        assert_eq!(events.len(), 1);
-        let (contract_id, topics, data) = events.get(0).unwrap().clone();
-        assert_eq!(contract_id, id);
+        // A frozen resolver cannot be cleared, so the clean transfer is refused as a whole.
+        client.set_resolver(&buyer, &a, &resolver);
+        client.set_flags(&a, &flags::FROZEN_RESOLVER);
         assert_eq!(
-            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
-            Symbol::new(&e, "resolver_changed")
+            client.try_transfer_clean(&buyer, &a, &seller),
+            Err(Ok(RegistryError::Locked.into()))
         );
-        let map = Map::<Symbol, Address>::try_from_val(&e, &data).unwrap();
-        assert_eq!(map.get(Symbol::new(&e, "resolver")).unwrap(), resolver);
     }
 
     #[test]
-    fn set_owner_rejects_zero_address() {
+    fn exported_names_are_frozen_until_imported() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let admin = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[16u8; 32]);
-        let zero_owner = Address::from_str(
-            &e,
-            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
-        );
+        let controller = Address::generate(&e);
+        let owner = Address::generate(&e);
+        let other = Address::generate(&e);
+        let bridge = Address::generate(&e);
+        allow_controller(&e, &id, &controller);
+        let a = client.set_owner(&controller, &root_node(&e), &label_of(&e, 46), &owner);
+        client.set_flags(&a, &flags::FROZEN_RESOLVER);
+        let chain = Symbol::new(&e, "ethereum");
+        let target = Bytes::from_array(&e, &[0xab; 20]);
 
-        let outcome = catch_unwind(AssertUnwindSafe(|| {
+        assert_eq!(
+            client.try_export_name(&other, &a, &chain, &target),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        client.export_name(&owner, &a, &chain, &target);
+        let (_, topics, data) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "name_exported")
+        );
+        let data = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        let exported_to =
+            Bytes::try_from_val(&e, &data.get(Symbol::new(&e, "target_address")).unwrap());
+        assert_eq!(exported_to.unwrap(), target);
+        assert_eq!(client.flags(&a), flags::FROZEN_RESOLVER | flags::EXPORTED);
+        assert_eq!(client.owner(&a), owner);
+
+        // Nothing about the name can change while it lives on the other chain.
+        for result in [
+            client.try_transfer(&owner, &a, &other).map(|_| ()),
+            client.try_set_resolver(&owner, &a, &other).map(|_| ()),
+            client.try_release(&owner, &a).map(|_| ()),
             client
-                .mock_auths(&[MockAuth {
-                    address: &zero_owner,
-                    invoke: &MockAuthInvoke {
-                        contract: &id,
-                        fn_name: "set_owner",
-                        args: (&namehash, &zero_owner).into_val(&e),
-                        sub_invokes: &[],
-                    },
-                }])
-                .set_owner(&namehash, &zero_owner);
-        }));
-        assert!(outcome.is_err(), "zero owner should be rejected");
+                .try_set_subnode_owner(&a, &label_of(&e, 1), &other)
+                .map(|_| ()),
+            client
+                .try_export_name(&owner, &a, &chain, &target)
+                .map(|_| ()),
+        ] {
+            assert_eq!(result, Err(Ok(RegistryError::Locked.into())));
+        }
+        assert_eq!(
+            client.try_set_flags(&a, &(flags::ALL | flags::EXPORTED)),
+            Err(Ok(RegistryError::InvalidFlags.into()))
+        );
 
-        let stored = e.as_contract(&id, || Registry::read_owner(&e, &namehash));
-        assert!(stored.is_none(), "zero owner should not persist");
+        // Only the configured bridge brings it back, to whoever held it over there.
+        assert_eq!(
+            client.try_import_name(&bridge, &a, &other, &chain),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        client.set_bridge(&Some(bridge.clone()));
+        assert_eq!(client.bridge(), Some(bridge.clone()));
+        client.import_name(&bridge, &a, &other, &chain);
+        assert_eq!(client.owner(&a), other);
+        assert_eq!(client.flags(&a), flags::FROZEN_RESOLVER);
+        assert_eq!(
+            client.try_import_name(&bridge, &a, &other, &chain),
+            Err(Ok(RegistryError::NotExported.into()))
+        );
+        client.transfer(&other, &a, &owner);
     }
 
     #[test]
-    fn set_resolver_rejects_zero_address() {
+    fn subnode_operator_only_creates_children() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
 
-        let namehash = BytesN::from_array(&e, &[17u8; 32]);
+        let controller = Address::generate(&e);
         let owner = Address::generate(&e);
-        let zero_address = Address::from_str(
-            &e,
-            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
+        let operator = Address::generate(&e);
+        let buyer = Address::generate(&e);
+        allow_controller(&e, &id, &controller);
+        let parent = client.set_owner(&controller, &root_node(&e), &label_of(&e, 47), &owner);
+        assert_eq!(
+            client.try_set_owner(&operator, &parent, &label_of(&e, 1), &buyer),
+            Err(Ok(RegistryError::NotAuthorized.into()))
         );
 
-        client.set_owner(&namehash, &owner);
-
-        let outcome = catch_unwind(AssertUnwindSafe(|| {
-            client.set_resolver(&namehash, &zero_address);
-        }));
-        assert!(
-            outcome.is_err(),
-            "zero resolver should be rejected even for the owner"
+        client.set_subnode_operator(&parent, &Some(operator.clone()));
+        assert_eq!(client.subnode_operator(&parent), Some(operator.clone()));
+        let child = client.set_owner(&operator, &parent, &label_of(&e, 1), &buyer);
+        assert_eq!(client.owner(&child), buyer);
+        // Existing children and the parent itself stay out of reach.
+        assert_eq!(
+            client.try_set_owner(&operator, &parent, &label_of(&e, 1), &operator),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_transfer(&operator, &parent, &operator),
+            Err(Ok(RegistryError::NotAuthorized.into()))
         );
 
-        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
-        assert!(stored.is_none(), "zero resolver should not persist");
+        // A new owner does not inherit the previous owner's operator.
+        client.transfer(&owner, &parent, &buyer);
+        assert_eq!(client.subnode_operator(&parent), None);
+        assert_eq!(
+            client.try_set_owner(&operator, &parent, &label_of(&e, 2), &operator),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
     }
 
     #[test]
-    fn set_resolver_without_owner_panics() {
+    fn import_names_migrates_a_snapshot_within_the_window() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let admin = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
         let client = RegistryClient::new(&e, &id);
+        e.ledger().set_timestamp(1_000);
 
-        let namehash = BytesN::from_array(&e, &[18u8; 32]);
-        let resolver = Address::generate(&e);
+        let owner = Address::generate(&e);
+        let tld = ImportedName {
+            parent: root_node(&e),
+            label: label_of(&e, 48),
+            owner: owner.clone(),
+            expires_at: None,
+            proof: SorobanVec::new(&e),
+        };
+        let tld_node = node_of(&e, 48);
+        let mut child = ImportedName {
+            parent: tld_node.clone(),
+            label: label_of(&e, 49),
+            owner: owner.clone(),
+            // Preserved even though it has already lapsed.
+            expires_at: Some(900),
+            proof: SorobanVec::new(&e),
+        };
+        let (tld_leaf, child_leaf) = (migration_leaf(&e, &tld), migration_leaf(&e, &child));
+        let snapshot_root = merkle_root(&e, tld_leaf.clone(), &vec![&e, child_leaf.clone()]);
+        let tld = ImportedName {
+            proof: vec![&e, child_leaf],
+            ..tld
+        };
+        child.proof = vec![&e, tld_leaf];
 
-        let outcome = catch_unwind(AssertUnwindSafe(|| {
-            client.set_resolver(&namehash, &resolver);
-        }));
-        assert!(
-            outcome.is_err(),
-            "setting resolver without owner should panic"
+        assert_eq!(
+            client.try_import_names(&admin, &vec![&e, tld.clone()]),
+            Err(Ok(RegistryError::MigrationClosed.into()))
+        );
+        client.open_migration(&admin, &snapshot_root, &2_000);
+        assert_eq!(
+            client.try_open_migration(&admin, &snapshot_root, &3_000),
+            Err(Ok(RegistryError::MigrationClosed.into()))
+        );
+        let forged = ImportedName {
+            owner: Address::generate(&e),
+            ..tld.clone()
+        };
+        assert_eq!(
+            client.try_import_names(&admin, &vec![&e, forged]),
+            Err(Ok(RegistryError::InvalidProof.into()))
+        );
+
+        client.import_names(&admin, &vec![&e, tld.clone(), child.clone()]);
+        let child_node = Registry::subnode(&e, &tld_node, &label_of(&e, 49));
+        assert_eq!(client.owner(&child_node), owner);
+        assert_eq!(client.expires(&child_node), 900);
+        assert_eq!(
+            client.name_of(&child_node),
+            Bytes::from_array(&e, &[49, b'.', 48])
+        );
+        assert_eq!(client.names_of(&owner).len(), 2);
+        assert_eq!(
+            client.try_import_names(&admin, &vec![&e, tld.clone()]),
+            Err(Ok(RegistryError::NameTaken.into()))
+        );
+
+        client.close_migration(&admin);
+        assert_eq!(client.migration_window().unwrap().closes_at, 1_000);
+        assert_eq!(
+            client.try_import_names(&admin, &vec![&e, child]),
+            Err(Ok(RegistryError::MigrationClosed.into()))
         );
-        let stored = e.as_contract(&id, || Registry::read_resolver(&e, &namehash));
-        assert!(stored.is_none(), "resolver should remain unset");
     }
 
     #[test]
-    fn storage_keys_do_not_collide() {
+    fn import_names_rejects_outsiders_orphans_and_late_batches() {
         let e = Env::default();
         e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let admin = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        let id = e.register(Registry, (&admin, &admin));
         let client = RegistryClient::new(&e, &id);
+        e.ledger().set_timestamp(1_000);
 
-        let namehash = BytesN::from_array(&e, &[19u8; 32]);
         let owner = Address::generate(&e);
-        let resolver = Address::generate(&e);
+        let mut tld = ImportedName {
+            parent: root_node(&e),
+            label: label_of(&e, 52),
+            owner: owner.clone(),
+            expires_at: None,
+            proof: SorobanVec::new(&e),
+        };
+        let mut child = ImportedName {
+            parent: node_of(&e, 52),
+            label: label_of(&e, 53),
+            owner: owner.clone(),
+            expires_at: Some(5_000),
+            proof: SorobanVec::new(&e),
+        };
+        let (tld_leaf, child_leaf) = (migration_leaf(&e, &tld), migration_leaf(&e, &child));
+        let snapshot_root = merkle_root(&e, tld_leaf.clone(), &vec![&e, child_leaf.clone()]);
+        tld.proof = vec![&e, child_leaf];
+        child.proof = vec![&e, tld_leaf];
 
-        // Initially nothing is stored.
-        assert!(e
-            .as_contract(&id, || Registry::read_owner(&e, &namehash))
-            .is_none());
-        assert!(e
-            .as_contract(&id, || Registry::read_resolver(&e, &namehash))
-            .is_none());
-        assert!(e
-            .as_contract(&id, || Registry::read_expires(&e, &namehash))
-            .is_none());
+        assert_eq!(
+            client.try_open_migration(&outsider, &snapshot_root, &2_000),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_open_migration(&admin, &snapshot_root, &1_000),
+            Err(Ok(RegistryError::InvalidExpiry.into()))
+        );
+        client.open_migration(&admin, &snapshot_root, &2_000);
+        assert_eq!(
+            client.try_import_names(&outsider, &vec![&e, tld.clone()]),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
+        assert_eq!(
+            client.try_close_migration(&outsider),
+            Err(Ok(RegistryError::NotAuthorized.into()))
+        );
 
-        client.set_owner(&namehash, &owner);
+        // Children need their parent imported first, and one bad entry reverts the batch.
         assert_eq!(
-            e.as_contract(&id, || Registry::read_owner(&e, &namehash)),
-            Some(owner.clone())
+            client.try_import_names(&admin, &vec![&e, child.clone()]),
+            Err(Ok(RegistryError::NotFound.into()))
         );
-        assert!(
-            e.as_contract(&id, || Registry::read_resolver(&e, &namehash))
-                .is_none(),
-            "resolver slot should remain untouched after set_owner"
+        let tampered = ImportedName {
+            expires_at: Some(u64::MAX),
+            ..child.clone()
+        };
+        assert_eq!(
+            client.try_import_names(&admin, &vec![&e, tld.clone(), tampered]),
+            Err(Ok(RegistryError::InvalidProof.into()))
         );
-        assert!(
-            e.as_contract(&id, || Registry::read_expires(&e, &namehash))
-                .is_none(),
-            "expiry slot should remain untouched after set_owner"
+        assert_eq!(client.names_of(&owner).len(), 0);
+
+        // The window closes on its own at `closes_at`.
+        e.ledger().set_timestamp(2_000);
+        assert_eq!(
+            client.try_import_names(&admin, &vec![&e, tld]),
+            Err(Ok(RegistryError::MigrationClosed.into()))
         );
+        assert_eq!(
+            client.try_close_migration(&admin),
+            Err(Ok(RegistryError::MigrationClosed.into()))
+        );
+    }
+
+    fn expiry_change(e: &Env) -> (Option<u64>, Option<u64>, Symbol) {
+        let events = e.events().all();
+        let (_, topics, data) = events
+            .iter()
+            .find(|(_, topics, _)| {
+                Symbol::try_from_val(e, &topics.get(0).unwrap()).unwrap()
+                    == Symbol::new(e, "expiry_changed")
+            })
+            .expect("expiry_changed");
+        assert_eq!(topics.len(), 2);
+        let map = Map::<Symbol, Val>::try_from_val(e, &data).unwrap();
+        let field = |name: &str| map.get(Symbol::new(e, name)).unwrap();
+        (
+            Option::<u64>::try_from_val(e, &field("old")).unwrap(),
+            Option::<u64>::try_from_val(e, &field("new")).unwrap(),
+            Symbol::try_from_val(e, &field("reason")).unwrap(),
+        )
+    }
+
+    #[test]
+    fn every_expiry_mutation_emits_expiry_changed() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        let namehash = node_of(&e, 50);
+        let owner = Address::generate(&e);
+        e.ledger().set_timestamp(1_000);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 50), &owner);
 
-        client.set_resolver(&namehash, &resolver);
+        client.set_expiry(&owner, &namehash, &5_000);
         assert_eq!(
-            e.as_contract(&id, || Registry::read_resolver(&e, &namehash)),
-            Some(resolver.clone())
+            expiry_change(&e),
+            (None, Some(5_000), Symbol::new(&e, "set_expiry"))
         );
+        client.renew(&owner, &namehash);
+        let renewed = 5_000 + RENEW_EXTENSION_SECONDS;
         assert_eq!(
-            e.as_contract(&id, || Registry::read_owner(&e, &namehash)),
-            Some(owner.clone()),
-            "owner slot should remain intact after set_resolver"
+            expiry_change(&e),
+            (Some(5_000), Some(renewed), Symbol::new(&e, "renew"))
         );
-        assert!(
-            e.as_contract(&id, || Registry::read_expires(&e, &namehash))
-                .is_none(),
-            "expiry slot should remain untouched after set_resolver"
+        client.extend_expiry(&owner, &namehash, &(renewed + 1));
+        assert_eq!(
+            expiry_change(&e),
+            (
+                Some(renewed),
+                Some(renewed + 1),
+                Symbol::new(&e, "extend_expiry")
+            )
+        );
+        client.release(&owner, &namehash);
+        assert_eq!(
+            expiry_change(&e),
+            (Some(renewed + 1), None, Symbol::new(&e, "release"))
         );
+    }
 
-        let now = 555u64;
-        e.ledger().set_timestamp(now);
-        client.renew(&namehash);
-        let expected_expiry = now + RENEW_EXTENSION_SECONDS;
+    #[test]
+    fn supports_interface_reports_registry_interfaces() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        for name in SUPPORTED_INTERFACES {
+            assert!(client.supports_interface(&Symbol::new(&e, name)));
+        }
+        assert!(!client
+            .supports_interface(&Symbol::new(&e, resolver_interface::INTERFACE_TEXT_RECORDS)));
+    }
+
+    #[test]
+    fn namehash_str_splits_and_lowercases_dotted_names() {
+        let e = Env::default();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        let labels = vec![
+            &e,
+            Bytes::from_slice(&e, b"stellar"),
+            Bytes::from_slice(&e, b"alice"),
+        ];
         assert_eq!(
-            e.as_contract(&id, || Registry::read_expires(&e, &namehash)),
-            Some(expected_expiry),
-            "expiry slot should be written by renew"
+            client.namehash_str(&String::from_str(&e, "Alice.STELLAR")),
+            client.namehash(&labels)
         );
         assert_eq!(
-            e.as_contract(&id, || Registry::read_owner(&e, &namehash)),
-            Some(owner),
-            "owner slot should remain intact after renew"
+            client.namehash_str(&String::from_str(&e, "stellar")),
+            client.namehash(&vec![&e, Bytes::from_slice(&e, b"stellar")])
         );
+
+        for bad in ["", "alice..stellar", "alice.stellar."] {
+            assert_eq!(
+                client.try_namehash_str(&String::from_str(&e, bad)),
+                Err(Ok(RegistryError::InvalidLabel.into()))
+            );
+        }
         assert_eq!(
-            e.as_contract(&id, || Registry::read_resolver(&e, &namehash)),
-            Some(resolver),
-            "resolver slot should remain intact after renew"
+            client.try_namehash_str(&String::from_bytes(&e, &[0xff, b'.', b'a'])),
+            Err(Ok(RegistryError::InvalidLabel.into()))
         );
     }
 
     #[test]
-    fn unknown_namehash_returns_defaults() {
+    fn namehash_name_hashes_tld_first() {
         let e = Env::default();
-        e.mock_all_auths();
-        let id = e.register(Registry, ());
+        let id = deploy(&e);
         let client = RegistryClient::new(&e, &id);
+        let labels = vec![
+            &e,
+            Bytes::from_slice(&e, b"stellar"),
+            Bytes::from_slice(&e, b"alice"),
+            Bytes::from_slice(&e, b"sub"),
+        ];
+        assert_eq!(
+            client.namehash_name(&Bytes::from_slice(&e, b"sub.alice.stellar")),
+            client.namehash(&labels)
+        );
+        // Bytes are hashed as given; only `namehash_str` folds case.
+        assert_ne!(
+            client.namehash_name(&Bytes::from_slice(&e, b"Sub.alice.stellar")),
+            client.namehash(&labels)
+        );
 
-        let known = BytesN::from_array(&e, &[21u8; 32]);
-        let unknown = BytesN::from_array(&e, &[22u8; 32]);
-        let owner = Address::generate(&e);
-        let resolver = Address::generate(&e);
+        let long: std::vec::Vec<u8> =
+            core::iter::repeat_n(b'a', (MAX_LABEL_LENGTH + 1) as usize).collect();
+        for bad in [&b""[..], b".stellar", b"alice..stellar", &long] {
+            assert_eq!(
+                client.try_namehash_name(&Bytes::from_slice(&e, bad)),
+                Err(Ok(RegistryError::InvalidLabel.into()))
+            );
+        }
+    }
 
-        e.ledger().set_timestamp(1_000u64);
-        client.set_owner(&known, &owner);
-        client.set_resolver(&known, &resolver);
-        client.renew(&known);
+    #[test]
+    fn offered_transfer_moves_only_when_recipient_accepts() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        let owner = Address::generate(&e);
+        let to = Address::generate(&e);
+        let node = node_of(&e, 51);
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 51), &owner);
 
-        assert!(e
-            .as_contract(&id, || Registry::read_owner(&e, &unknown))
-            .is_none());
-        assert!(e
-            .as_contract(&id, || Registry::read_resolver(&e, &unknown))
-            .is_none());
-        assert!(e
-            .as_contract(&id, || Registry::read_expires(&e, &unknown))
-            .is_none());
+        let now = e.ledger().timestamp();
+        assert_eq!(
+            client.try_offer_transfer(&node, &to, &now),
+            Err(Ok(RegistryError::InvalidExpiry.into()))
+        );
+        assert_eq!(
+            client.try_accept_transfer(&node),
+            Err(Ok(RegistryError::NoOffer.into()))
+        );
 
-        let owner_call = catch_unwind(AssertUnwindSafe(|| client.owner(&unknown)));
-        assert!(
-            owner_call.is_err(),
-            "owner() should panic for unknown namehash"
+        client.offer_transfer(&node, &to, &(now + 100));
+        assert_eq!(
+            client.transfer_offer(&node),
+            Some(TransferOffer {
+                to: to.clone(),
+                deadline: now + 100
+            })
         );
+        assert_eq!(client.owner(&node), owner);
 
-        let resolver_call = catch_unwind(AssertUnwindSafe(|| client.resolver(&unknown)));
-        assert!(
-            resolver_call.is_err(),
-            "resolver() should panic for unknown namehash"
+        // Offers lapse after their deadline.
+        e.ledger().set_timestamp(now + 101);
+        assert_eq!(client.transfer_offer(&node), None);
+        assert_eq!(
+            client.try_accept_transfer(&node),
+            Err(Ok(RegistryError::NoOffer.into()))
         );
 
-        let expires_call = catch_unwind(AssertUnwindSafe(|| client.expires(&unknown)));
-        assert!(
-            expires_call.is_err(),
-            "expires() should panic for unknown namehash"
+        client.offer_transfer(&node, &to, &(now + 200));
+        client.cancel_transfer_offer(&node);
+        assert_eq!(
+            client.try_accept_transfer(&node),
+            Err(Ok(RegistryError::NoOffer.into()))
         );
+
+        client.offer_transfer(&node, &to, &(now + 200));
+        client.accept_transfer(&node);
+        assert_eq!(e.auths()[0].0, to);
+        assert_eq!(client.owner(&node), to);
+        assert_eq!(client.transfer_offer(&node), None);
+    }
+
+    #[test]
+    fn events_carry_consecutive_seq_numbers() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let id = deploy(&e);
+        let client = RegistryClient::new(&e, &id);
+        let owner = Address::generate(&e);
+        let node = node_of(&e, 52);
+        // The constructor's root `transfer` and `initialized` events take 0 and 1.
+        assert_eq!(client.event_seq(), 2);
+        let last_seq = |e: &Env| {
+            let (_, _, data) = e.events().all().last().unwrap();
+            let map = Map::<Symbol, Val>::try_from_val(e, &data).unwrap();
+            u64::try_from_val(e, &map.get(Symbol::new(e, "seq")).unwrap()).unwrap()
+        };
+
+        allow_controller(&e, &id, &owner);
+        client.set_owner(&owner, &root_node(&e), &label_of(&e, 52), &owner);
+        assert_eq!(last_seq(&e), 3);
+        client.set_resolver(&owner, &node, &Address::generate(&e));
+        assert_eq!(last_seq(&e), 4);
+        // `renew` emits `expiry_changed` and then `renew`, each with its own number.
+        client.renew(&owner, &node);
+        let seqs: std::vec::Vec<u64> = e
+            .events()
+            .all()
+            .iter()
+            .map(|(_, _, data)| {
+                let map = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+                u64::try_from_val(&e, &map.get(Symbol::new(&e, "seq")).unwrap()).unwrap()
+            })
+            .collect();
+        assert_eq!(seqs, [5, 6]);
+        assert_eq!(client.event_seq(), 6);
     }
 }
@@ -0,0 +1,98 @@
+#![no_std]
+
+//! Interface shared by every contract that a Registry entry may name as its resolver.
+//!
+//! The Resolver contract implements [`ResolverInterface`]; the Registrar and the Resolver's
+//! `resolve` gateway talk to arbitrary resolvers through [`ResolverInterfaceClient`].
+//!
+//! It also holds the identifiers answered by `supports_interface` on the Registry, Registrar,
+//! and Resolver, see [`InterfaceDetection`].
+
+use soroban_sdk::{contractclient, Address, Bytes, BytesN, Env, Symbol};
+
+/// Record type names accepted by `supports`.
+pub const RECORD_ADDR: &str = "addr";
+pub const RECORD_COIN_ADDR: &str = "coin_addr";
+pub const RECORD_TEXT: &str = "text";
+pub const RECORD_CONTENTHASH: &str = "contenthash";
+pub const RECORD_WILDCARD: &str = "wildcard";
+pub const RECORD_MUXED: &str = "muxed";
+pub const RECORD_SERVICE: &str = "service";
+pub const RECORD_METADATA: &str = "metadata";
+pub const RECORD_PUBKEY: &str = "pubkey";
+
+/// Interface identifiers answered by `supports_interface`. Each names a group of entrypoints a
+/// client may rely on once a contract reports it.
+pub const INTERFACE_DETECTION: &str = "interface_detection";
+/// Registry: `owner`, `resolver`, `set_owner`, `set_resolver`, `namehash`.
+pub const INTERFACE_REGISTRY: &str = "registry";
+/// Registry: `expires`, `renew`, `extend_expiry`.
+pub const INTERFACE_NAME_EXPIRY: &str = "name_expiry";
+/// Registry: `set_approval_for_all`, `is_approved_for_all`, `approve`.
+pub const INTERFACE_OPERATORS: &str = "operators";
+/// Registrar: `commit`, `register`, `renew`, `available`.
+pub const INTERFACE_REGISTRAR: &str = "registrar";
+/// Registrar: `rent_price`, `name_info`.
+pub const INTERFACE_RENT_PRICE: &str = "rent_price";
+/// Resolver: `addr`, `set_addr`.
+pub const INTERFACE_ADDR_RESOLUTION: &str = "addr_resolution";
+/// Resolver: `text`, `set_text`, `text_keys`, `texts`.
+pub const INTERFACE_TEXT_RECORDS: &str = "text_records";
+/// Resolver: `set_reverse`, `reverse`.
+pub const INTERFACE_REVERSE_RECORDS: &str = "reverse_records";
+/// Resolver: `contenthash`, `set_contenthash`.
+pub const INTERFACE_CONTENTHASH: &str = "contenthash";
+/// Resolver: `addr_by_coin`, `set_addr_by_coin`.
+pub const INTERFACE_COIN_ADDR: &str = "coin_addr";
+/// Resolver: `wildcard_addr`, `resolve_wildcard`.
+pub const INTERFACE_WILDCARD: &str = "wildcard";
+/// Resolver: `set_muxed`, `resolve_to_muxed`.
+pub const INTERFACE_MUXED: &str = "muxed";
+/// Resolver: `set_service`, `clear_service`, `service`, `services`.
+pub const INTERFACE_SERVICES: &str = "services";
+/// Resolver: `set_metadata`, `metadata`.
+pub const INTERFACE_METADATA: &str = "metadata";
+/// Resolver: `set_pubkey`, `pubkey`.
+pub const INTERFACE_PUBKEY: &str = "pubkey";
+/// Resolver: `set_record_schema`, `record_schema`, `set_typed_record`, `typed_record`,
+/// `record_types`.
+pub const INTERFACE_RECORD_TYPES: &str = "record_types";
+
+/// ERC-165-style feature detection, so clients need not try-invoke and catch errors.
+#[contractclient(name = "InterfaceDetectionClient")]
+pub trait InterfaceDetection {
+    /// Whether this contract implements `interface_id` (one of the `INTERFACE_*` names).
+    fn supports_interface(env: Env, interface_id: Symbol) -> bool;
+}
+
+/// Asks `contract` whether it implements `interface_id`. Like [`supports`], anything but an
+/// explicit `true` reads as `false`.
+pub fn supports_interface(env: &Env, contract: &Address, interface_id: &str) -> bool {
+    let client = InterfaceDetectionClient::new(env, contract);
+    matches!(
+        client.try_supports_interface(&Symbol::new(env, interface_id)),
+        Ok(Ok(true))
+    )
+}
+
+#[contractclient(name = "ResolverInterfaceClient")]
+pub trait ResolverInterface {
+    /// Whether this resolver serves records of `record_type` (one of the `RECORD_*` names).
+    fn supports(env: Env, record_type: Symbol) -> bool;
+
+    fn addr(env: Env, namehash: BytesN<32>) -> Option<Address>;
+
+    fn text(env: Env, namehash: BytesN<32>, key: Bytes) -> Option<Bytes>;
+
+    fn contenthash(env: Env, namehash: BytesN<32>) -> Option<Bytes>;
+}
+
+/// Asks `resolver` whether it supports `record_type`. Accounts, missing contracts, and
+/// contracts without a `supports` entrypoint all answer `false` instead of aborting.
+pub fn supports(env: &Env, resolver: &Address, record_type: &str) -> bool {
+    let client = ResolverInterfaceClient::new(env, resolver);
+    matches!(
+        client.try_supports(&Symbol::new(env, record_type)),
+        Ok(Ok(true))
+    )
+}
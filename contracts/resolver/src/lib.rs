@@ -3,18 +3,94 @@
 #[cfg(test)]
 extern crate std;
 
+use resolver_interface::{InterfaceDetection, ResolverInterface, ResolverInterfaceClient};
+use soroban_sdk::xdr::ToXdr;
 use soroban_sdk::{
-    contract, contracterror, contractevent, contractimpl, panic_with_error, Address, Bytes, BytesN,
-    Env, IntoVal, Symbol,
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, IntoVal, Map, Symbol, TryFromVal, Val, Vec,
 };
 
 /// Storage keys
 mod keys {
-    pub const ADDR: &[u8] = b"RES_ADDR"; // namehash -> Address
-    pub const TEXT: &[u8] = b"RES_TEXT_"; // namespace prefix: TEXT || key || namehash -> String
+    pub const ADDR: &[u8] = b"RES_ADDR"; // ADDR || namehash || version -> Address
+    pub const TEXT: &[u8] = b"RES_TEXT_"; // TEXT || namehash || version || key -> Bytes
     pub const REGISTRY: &[u8] = b"RES_REG"; // singleton: Address (Registry contract)
+    pub const REVERSE: &[u8] = b"RES_REV"; // REVERSE || xdr(Address) -> BytesN<32>
+    pub const COIN_ADDR: &[u8] = b"RES_COIN"; // COIN_ADDR || namehash || version || coin_type -> Bytes
+    pub const CONTENTHASH: &[u8] = b"RES_CHASH"; // CONTENTHASH || namehash || version -> Bytes
+    pub const STORAGE_VERSION: &[u8] = b"RES_SVER"; // singleton: u32 layout version
+    pub const WILDCARD: &[u8] = b"RES_WILD"; // WILDCARD || namehash || version -> Address
+    pub const TEXT_KEYS: &[u8] = b"RES_TKEYS"; // TEXT_KEYS || namehash || version -> Vec<Bytes>
+    pub const MANAGER: &[u8] = b"RES_MGR"; // MANAGER || namehash || xdr(owner) || xdr(manager) -> bool
+    pub const OWNER_CACHE_ON: &[u8] = b"RES_OCON"; // singleton: bool, set while caching is enabled
+    pub const OWNER_CACHE: &[u8] = b"RES_OC"; // temporary: OWNER_CACHE || namehash -> (owner_nonce, owner, expires)
+    pub const REVISION: &[u8] = b"RES_RVSN"; // REVISION || namehash -> u32
+    pub const SNAPSHOT: &[u8] = b"RES_SNAP"; // SNAPSHOT || namehash || revision -> RecordSnapshot
+    pub const MUXED: &[u8] = b"RES_MUX"; // MUXED || namehash || version -> (Address, Option<u64>)
+    pub const VERIFIER: &[u8] = b"RES_VRFR"; // VERIFIER || xdr(verifier) -> bool
+    pub const VERIFIED: &[u8] = b"RES_VREC"; // VERIFIED || namehash || version || xdr(owner) || key -> (Address, Bytes)
+    pub const FROZEN: &[u8] = b"RES_FRZN"; // FROZEN || namehash || version -> u64
+    pub const CHALLENGE: &[u8] = b"RES_CHAL"; // temporary: CHALLENGE || namehash || xdr(requester) -> (BytesN<32>, u64)
+    pub const TEXT_LIMITS: &[u8] = b"RES_TLIM"; // singleton: TextLimits
+    pub const TEXT_BYTES: &[u8] = b"RES_TBYT"; // TEXT_BYTES || namehash || version -> u32
+    pub const TEXT_CHUNK: &[u8] = b"RES_TCHK"; // TEXT_CHUNK || namehash || version || index || key -> Bytes
+    pub const TEXT_CHUNKS: &[u8] = b"RES_TCCT"; // TEXT_CHUNKS || namehash || version || key -> u32
+    pub const SERVICE: &[u8] = b"RES_SVC"; // SERVICE || namehash || version || xdr(service_key) -> Address
+    pub const SERVICES: &[u8] = b"RES_SVCS"; // SERVICES || namehash || version -> Vec<Symbol>
+    pub const METADATA: &[u8] = b"RES_META"; // METADATA || namehash || version -> Metadata
+    pub const PUBKEY: &[u8] = b"RES_PKEY"; // PUBKEY || namehash || version || key_type -> Bytes
+    pub const RECORD_SCHEMA: &[u8] = b"RES_RSCH"; // RECORD_SCHEMA || xdr(record_type) -> u32
+    pub const TYPED: &[u8] = b"RES_TREC"; // TYPED || namehash || version || xdr(record_type) -> TypedRecord
+    pub const TYPED_INDEX: &[u8] = b"RES_TRTS"; // TYPED_INDEX || namehash || version -> Vec<Symbol>
+    pub const CHUNKED_KEYS: &[u8] = b"RES_TCKS"; // CHUNKED_KEYS || namehash || version -> Vec<Bytes>
+    pub const COIN_TYPES: &[u8] = b"RES_CTYP"; // COIN_TYPES || namehash || version -> Vec<u32>
+    pub const MANAGERS: &[u8] = b"RES_MLST"; // MANAGERS || namehash || xdr(owner) -> Vec<Address>
+    pub const VERIFIED_KEYS: &[u8] = b"RES_VKEY"; // VERIFIED_KEYS || namehash || version || xdr(owner) -> Vec<Bytes>
 }
 
+/// A name's `addr`, content hash, and text records as of one record revision.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RecordSnapshot {
+    pub revision: u32,
+    /// Registry record version the records belonged to.
+    pub generation: u32,
+    pub addr: Option<Address>,
+    pub contenthash: Option<Bytes>,
+    pub texts: Map<Bytes, Bytes>,
+}
+
+/// Off-chain JSON metadata for a name and the `sha256` of its bytes, so a reader can check
+/// what it fetched from `uri`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Metadata {
+    pub uri: Bytes,
+    pub hash: BytesN<32>,
+}
+
+/// A record of a type approved with `set_record_schema`, encoded as `schema_version` of that
+/// type prescribes. The Resolver stores `value` without interpreting it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TypedRecord {
+    pub schema_version: u32,
+    pub value: Bytes,
+}
+
+/// Size caps on text values, set with `set_text_limits`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TextLimits {
+    /// Largest single text value or chunk, in bytes.
+    pub max_value_len: u32,
+    /// Largest total of a name's text values and chunks, in bytes.
+    pub max_name_bytes: u32,
+}
+
+/// Storage layout this code expects; `migrate` brings older layouts up to it.
+const STORAGE_VERSION: u32 = 1;
+
 /// Events
 #[derive(Clone)]
 #[contractevent(topics = ["address_changed"])]
@@ -32,6 +108,239 @@ pub struct EvtTextChanged {
     pub key: Bytes,
 }
 
+#[derive(Clone)]
+#[contractevent(topics = ["coin_address_changed"])]
+pub struct EvtCoinAddressChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub coin_type: u32,
+    pub value: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["pubkey_changed"])]
+pub struct EvtPubkeyChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub key_type: u32,
+    pub key: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["contenthash_changed"])]
+pub struct EvtContenthashChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub hash: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["upgraded"])]
+pub struct EvtUpgraded {
+    pub new_wasm_hash: BytesN<32>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["migrated"])]
+pub struct EvtMigrated {
+    pub from: u32,
+    pub to: u32,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["address_cleared"])]
+pub struct EvtAddressCleared {
+    #[topic]
+    pub namehash: BytesN<32>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["wildcard_changed"])]
+pub struct EvtWildcardChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub addr: Option<Address>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["text_cleared"])]
+pub struct EvtTextCleared {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub key: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["avatar_changed"])]
+pub struct EvtAvatarChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub avatar: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["url_changed"])]
+pub struct EvtUrlChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub url: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["email_changed"])]
+pub struct EvtEmailChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub email: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["social_changed"])]
+pub struct EvtSocialChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    #[topic]
+    pub platform: Bytes,
+    pub handle: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["metadata_changed"])]
+pub struct EvtMetadataChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub uri: Bytes,
+    pub hash: BytesN<32>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["service_changed"])]
+pub struct EvtServiceChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    #[topic]
+    pub service_key: Symbol,
+    pub contract: Option<Address>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["muxed_changed"])]
+pub struct EvtMuxedChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub addr: Option<Address>,
+    pub id: Option<u64>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["challenge_issued"])]
+pub struct EvtChallengeIssued {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub requester: Address,
+    pub challenge: BytesN<32>,
+    pub expires_at: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["challenge_verified"])]
+pub struct EvtChallengeVerified {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub requester: Address,
+    pub addr: Address,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["records_frozen"])]
+pub struct EvtRecordsFrozen {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub until: u64,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["record_schema_changed"])]
+pub struct EvtRecordSchemaChanged {
+    #[topic]
+    pub record_type: Symbol,
+    pub schema_version: u32,
+}
+
+/// `value` is empty when the record was cleared.
+#[derive(Clone)]
+#[contractevent(topics = ["typed_record_changed"])]
+pub struct EvtTypedRecordChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    #[topic]
+    pub record_type: Symbol,
+    pub schema_version: u32,
+    pub value: Bytes,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["record_verifier_changed"])]
+pub struct EvtRecordVerifierChanged {
+    #[topic]
+    pub verifier: Address,
+    pub enabled: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["verified_record_changed"])]
+pub struct EvtVerifiedRecordChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub key: Bytes,
+    pub verifier: Address,
+    pub owner: Address,
+    pub value: Option<Bytes>,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["text_chunk_changed"])]
+pub struct EvtTextChunkChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub key: Bytes,
+    pub index: u32,
+    /// Chunks stored under `key` after the write.
+    pub count: u32,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["text_limits_changed"])]
+pub struct EvtTextLimitsChanged {
+    pub max_value_len: u32,
+    pub max_name_bytes: u32,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["owner_cache_changed"])]
+pub struct EvtOwnerCacheChanged {
+    pub enabled: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["manager_changed"])]
+pub struct EvtManagerChanged {
+    #[topic]
+    pub namehash: BytesN<32>,
+    #[topic]
+    pub manager: Address,
+    pub owner: Address,
+    pub approved: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["reverse_changed"])]
+pub struct EvtReverseChanged {
+    #[topic]
+    pub addr: Address,
+    pub namehash: BytesN<32>,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[contracterror]
 #[repr(u32)]
@@ -40,404 +349,4635 @@ pub enum ResolverError {
     AlreadyInitialized = 2,
     NotOwner = 3,
     InvalidInput = 4,
+    ReverseMismatch = 5,
+    NotAdmin = 6,
+    UnsupportedVersion = 7,
+    NotVerifier = 8,
+    /// The name's records are frozen by `freeze_records`.
+    RecordsFrozen = 9,
+    /// No unexpired challenge is outstanding for the name.
+    ChallengeMissing = 10,
+    /// The Registry has no live owner for the name, or could not answer.
+    NameNotRegistered = 11,
+    /// A text value or chunk is longer than `TextLimits.max_value_len`.
+    ValueTooLarge = 12,
+    /// The write would take the name's text past `TextLimits.max_name_bytes`.
+    TextBudgetExceeded = 13,
+    /// The record type is not approved with `set_record_schema`, or not at that schema
+    /// version.
+    UnsupportedRecordType = 14,
 }
 
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
 const MAX_TEXT_KEY_LEN: u32 = 256;
+/// SLIP-44 coin type for Stellar; served from the native `addr` record.
+pub const COIN_TYPE_STELLAR: u32 = 148;
+const MAX_COIN_ADDR_LEN: u32 = 128;
+/// `pubkey` key type for a 32-byte Ed25519 public key.
+pub const KEY_TYPE_ED25519: u32 = 0;
+/// `pubkey` key type for a secp256k1 public key, SEC1-encoded as 33 bytes (compressed) or 65
+/// bytes (uncompressed).
+pub const KEY_TYPE_SECP256K1: u32 = 1;
+const MAX_CONTENTHASH_LEN: u32 = 256;
+/// How long an `issue_challenge` nonce can be answered.
+const CHALLENGE_TTL_SECS: u64 = 300;
+/// Temporary-storage lifetime of a challenge; comfortably covers `CHALLENGE_TTL_SECS`.
+const CHALLENGE_TTL_LEDGERS: u32 = 120;
+/// Longest freeze `freeze_records` accepts, measured from now.
+const MAX_FREEZE_SECS: u64 = 365 * 86_400;
+/// Upper bound on text records written by a single `set_records` call.
+const MAX_BATCH_TEXTS: u32 = 32;
+/// Upper bound on distinct text keys per name, so `texts` stays within one call's budget.
+const MAX_TEXT_KEYS: u32 = 64;
+/// Upper bound on service records per name, for the same reason.
+const MAX_SERVICES: u32 = 16;
+/// Upper bound on typed records per name, for the same reason.
+const MAX_TYPED_RECORDS: u32 = 16;
+/// Upper bounds on non-Stellar coin addresses, managers, and verified records per name, so
+/// `bump` can reach every entry in one call.
+const MAX_COIN_TYPES: u32 = 32;
+const MAX_MANAGERS: u32 = 16;
+const MAX_VERIFIED_RECORDS: u32 = 32;
+const MAX_TYPED_RECORD_LEN: u32 = 1_024;
+/// Record snapshots kept per name; each new revision drops the oldest, so a name's storage
+/// stays bounded however often it is written.
+const SNAPSHOTS_KEPT: u32 = 8;
+/// `TextLimits` until the admin sets others: a profile JSON fits one value, and a name can
+/// hold a few chunked images.
+const DEFAULT_MAX_TEXT_VALUE_LEN: u32 = 4_096;
+const DEFAULT_MAX_NAME_TEXT_BYTES: u32 = 65_536;
+/// Varint multicodec prefixes accepted in content hashes: ipfs, ipns, swarm, arweave.
+const CONTENTHASH_CODECS: [&[u8]; 4] = [
+    &[0xe3, 0x01],
+    &[0xe5, 0x01],
+    &[0xe4, 0x01],
+    &[0x90, 0xb2, 0xca, 0x05],
+];
+/// Persists `value` and extends the entry's TTL so live records are not archived.
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &Bytes, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn extend_entry(env: &Env, key: &Bytes) {
+    let storage = env.storage().persistent();
+    if storage.has(key) {
+        storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    }
+}
+
 fn registry_storage_key(env: &Env) -> Bytes {
     Bytes::from_slice(env, keys::REGISTRY)
 }
 
+/// Record generation reported by the Registry. Keys embed it, so records written before a
+/// re-registration bumped the version are no longer reachable.
+fn record_version(env: &Env, namehash: &BytesN<32>) -> u32 {
+    let registry = ensure_initialized(env);
+    env.invoke_contract(
+        &registry,
+        &Symbol::new(env, "record_version"),
+        (namehash,).into_val(env),
+    )
+}
+
+fn owner_cache_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::OWNER_CACHE);
+    key.extend_from_array(&namehash.to_array());
+    key
+}
+
+fn owner_cache_enabled(env: &Env) -> bool {
+    env.storage()
+        .persistent()
+        .has(&Bytes::from_slice(env, keys::OWNER_CACHE_ON))
+}
+
+/// The Registry owner and expiry a write check last saw, under the Registry's
+/// `owner_nonce` at the time.
+type CachedOwner = (u64, Address, Option<u64>);
+
+fn owner_nonce(env: &Env, namehash: &BytesN<32>) -> u64 {
+    let registry = ensure_initialized(env);
+    env.invoke_contract(
+        &registry,
+        &Symbol::new(env, "owner_nonce"),
+        (namehash,).into_val(env),
+    )
+}
+
+/// The owner that write checks compare against. With the owner cache enabled, the
+/// Registry's owner and expiry are kept in temporary storage with the name's `owner_nonce`;
+/// while the nonce is unchanged, a write costs one Registry call instead of two. Any owner,
+/// expiry, or record version change bumps the nonce, so the cache never outlives them.
+/// Aborts with `NameNotRegistered` when there is no live owner, and `NotOwner` once the name
+/// is past its expiry, even during the grace period.
+fn write_check_owner(env: &Env, namehash: &BytesN<32>) -> Address {
+    if !owner_cache_enabled(env) {
+        return checked_owner(env, namehash, registry_expires(env, namehash));
+    }
+    let key = owner_cache_key(env, namehash);
+    let nonce = owner_nonce(env, namehash);
+    let now = env.ledger().timestamp();
+    let cached: Option<CachedOwner> = env.storage().temporary().get(&key);
+    if let Some((_, owner, _)) = cached
+        .filter(|(at, _, expires)| *at == nonce && !expires.is_some_and(|expires| now > expires))
+    {
+        return owner;
+    }
+    let expires = registry_expires(env, namehash);
+    let owner = checked_owner(env, namehash, expires);
+    env.storage()
+        .temporary()
+        .set(&key, &(nonce, owner.clone(), expires));
+    owner
+}
+
+/// The live Registry owner given the name's `expires`; see `write_check_owner` for the
+/// errors.
+fn checked_owner(env: &Env, namehash: &BytesN<32>, expires: Option<u64>) -> Address {
+    let owner = registry_owner(env, namehash)
+        .unwrap_or_else(|| panic_with_error!(env, ResolverError::NameNotRegistered));
+    if expires.is_some_and(|expires| env.ledger().timestamp() > expires) {
+        panic_with_error!(env, ResolverError::NotOwner);
+    }
+    owner
+}
+
 fn addr_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
     let mut key = Bytes::from_slice(env, keys::ADDR);
     key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
     key
 }
 
 fn text_storage_key(env: &Env, namehash: &BytesN<32>, text_key: &Bytes) -> Bytes {
     let mut key = Bytes::from_slice(env, keys::TEXT);
     key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
     key.append(text_key);
     key
 }
 
-fn validate_text_key(env: &Env, key: &Bytes) {
-    if key.is_empty() || key.len() > MAX_TEXT_KEY_LEN {
+fn text_keys_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TEXT_KEYS);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn read_text_keys(env: &Env, namehash: &BytesN<32>) -> Vec<Bytes> {
+    env.storage()
+        .persistent()
+        .get(&text_keys_storage_key(env, namehash))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Adds `text_key` to the name's key index unless it is already listed.
+fn index_text_key(env: &Env, namehash: &BytesN<32>, text_key: &Bytes) {
+    let mut keys = read_text_keys(env, namehash);
+    if keys.contains(text_key) {
+        return;
+    }
+    if keys.len() >= MAX_TEXT_KEYS {
         panic_with_error!(env, ResolverError::InvalidInput);
     }
+    keys.push_back(text_key.clone());
+    write_entry(env, &text_keys_storage_key(env, namehash), &keys);
 }
 
-fn ensure_initialized(env: &Env) -> Address {
-    let storage = env.storage().persistent();
-    let key = registry_storage_key(env);
-    storage
-        .get(&key)
-        .unwrap_or_else(|| panic_with_error!(env, ResolverError::NotInitialized))
+fn unindex_text_key(env: &Env, namehash: &BytesN<32>, text_key: &Bytes) {
+    let mut keys = read_text_keys(env, namehash);
+    let Some(index) = keys.first_index_of(text_key) else {
+        return;
+    };
+    keys.remove(index);
+    let storage_key = text_keys_storage_key(env, namehash);
+    if keys.is_empty() {
+        env.storage().persistent().remove(&storage_key);
+    } else {
+        write_entry(env, &storage_key, &keys);
+    }
 }
 
-fn require_owner(env: &Env, caller: &Address, namehash: &BytesN<32>) {
-    let registry = ensure_initialized(env);
-    let owner: Address = env.invoke_contract(
-        &registry,
-        &Symbol::new(env, "owner"),
-        (namehash,).into_val(env),
-    );
-    if owner != *caller {
-        panic_with_error!(env, ResolverError::NotOwner);
-    }
+fn coin_addr_storage_key(env: &Env, namehash: &BytesN<32>, coin_type: u32) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::COIN_ADDR);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.extend_from_array(&coin_type.to_be_bytes());
+    key
 }
 
-#[contract]
-pub struct Resolver;
+fn coin_types_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::COIN_TYPES);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
 
-#[contractimpl]
-impl Resolver {
-    pub fn version(_env: Env) -> u32 {
-        1
-    }
+fn read_list<T: IntoVal<Env, Val> + TryFromVal<Env, Val>>(env: &Env, key: &Bytes) -> Vec<T> {
+    env.storage()
+        .persistent()
+        .get(key)
+        .unwrap_or_else(|| Vec::new(env))
+}
 
-    pub fn init(env: Env, registry: Address) {
-        let storage = env.storage().persistent();
-        let key = registry_storage_key(&env);
-        if storage.has(&key) {
-            panic_with_error!(&env, ResolverError::AlreadyInitialized);
+/// Adds or, when `listed` is false, removes `item` in the list stored at `key`, which holds at
+/// most `max` items.
+fn index_item<T: IntoVal<Env, Val> + TryFromVal<Env, Val> + Clone>(
+    env: &Env,
+    key: &Bytes,
+    item: &T,
+    listed: bool,
+    max: u32,
+) {
+    let mut items: Vec<T> = read_list(env, key);
+    match (items.first_index_of(item), listed) {
+        (None, true) => {
+            if items.len() >= max {
+                panic_with_error!(env, ResolverError::InvalidInput);
+            }
+            items.push_back(item.clone());
         }
-        storage.set(&key, &registry);
+        (Some(index), false) => {
+            items.remove(index);
+        }
+        _ => return,
     }
-
-    pub fn addr(env: Env, namehash: BytesN<32>) -> Option<Address> {
-        ensure_initialized(&env);
-        let storage = env.storage().persistent();
-        let key = addr_storage_key(&env, &namehash);
-        storage.get(&key)
+    if items.is_empty() {
+        env.storage().persistent().remove(key);
+    } else {
+        write_entry(env, key, &items);
     }
+}
 
-    pub fn text(env: Env, namehash: BytesN<32>, key: Bytes) -> Option<Bytes> {
-        ensure_initialized(&env);
-        validate_text_key(&env, &key);
-        let storage = env.storage().persistent();
-        let data_key = text_storage_key(&env, &namehash, &key);
-        storage.get(&data_key)
+fn pubkey_storage_key(env: &Env, namehash: &BytesN<32>, key_type: u32) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::PUBKEY);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.extend_from_array(&key_type.to_be_bytes());
+    key
+}
+
+/// Rejects unknown key types and keys whose length or SEC1 prefix does not fit the type.
+fn validate_pubkey(env: &Env, key_type: u32, key: &Bytes) {
+    let valid = match key_type {
+        KEY_TYPE_ED25519 => key.len() == 32,
+        KEY_TYPE_SECP256K1 => matches!(
+            (key.len(), key.get(0)),
+            (33, Some(0x02 | 0x03)) | (65, Some(0x04))
+        ),
+        _ => false,
+    };
+    if !valid {
+        panic_with_error!(env, ResolverError::InvalidInput);
     }
+}
 
-    pub fn set_addr(env: Env, caller: Address, namehash: BytesN<32>, addr: Address) {
-        caller.require_auth();
-        require_owner(&env, &caller, &namehash);
+fn record_schema_storage_key(env: &Env, record_type: &Symbol) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::RECORD_SCHEMA);
+    key.append(&record_type.clone().to_xdr(env));
+    key
+}
 
-        let storage = env.storage().persistent();
-        let key = addr_storage_key(&env, &namehash);
-        storage.set(&key, &addr);
+/// Current schema version of `record_type`, or `0` if it was never approved.
+fn read_record_schema(env: &Env, record_type: &Symbol) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&record_schema_storage_key(env, record_type))
+        .unwrap_or(0)
+}
 
-        EvtAddressChanged { namehash, addr }.publish(&env);
-    }
+fn typed_record_storage_key(env: &Env, namehash: &BytesN<32>, record_type: &Symbol) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TYPED);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.append(&record_type.clone().to_xdr(env));
+    key
+}
 
-    pub fn set_text(env: Env, caller: Address, namehash: BytesN<32>, key: Bytes, value: Bytes) {
-        caller.require_auth();
-        validate_text_key(&env, &key);
-        require_owner(&env, &caller, &namehash);
+fn typed_index_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TYPED_INDEX);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
 
-        let storage = env.storage().persistent();
-        let data_key = text_storage_key(&env, &namehash, &key);
-        storage.set(&data_key, &value);
+fn read_record_types(env: &Env, namehash: &BytesN<32>) -> Vec<Symbol> {
+    env.storage()
+        .persistent()
+        .get(&typed_index_storage_key(env, namehash))
+        .unwrap_or_else(|| Vec::new(env))
+}
 
-        EvtTextChanged { namehash, key }.publish(&env);
+/// Adds or, when `listed` is false, removes `record_type` in the name's typed record index.
+fn index_record_type(env: &Env, namehash: &BytesN<32>, record_type: &Symbol, listed: bool) {
+    let mut types = read_record_types(env, namehash);
+    match (types.first_index_of(record_type), listed) {
+        (None, true) => {
+            if types.len() >= MAX_TYPED_RECORDS {
+                panic_with_error!(env, ResolverError::InvalidInput);
+            }
+            types.push_back(record_type.clone());
+        }
+        (Some(index), false) => {
+            types.remove(index);
+        }
+        _ => return,
+    }
+    let key = typed_index_storage_key(env, namehash);
+    if types.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        write_entry(env, &key, &types);
     }
+}
 
-    pub fn registry(env: Env) -> Address {
-        ensure_initialized(&env)
+fn wildcard_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::WILDCARD);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn registry_namehash(env: &Env, registry: &Address, labels: Vec<Bytes>) -> BytesN<32> {
+    env.invoke_contract(
+        registry,
+        &Symbol::new(env, "namehash"),
+        (labels,).into_val(env),
+    )
+}
+
+fn registry_resolver(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+    env.invoke_contract(
+        registry,
+        &Symbol::new(env, "resolver_checked"),
+        (namehash.clone(),).into_val(env),
+    )
+}
+
+/// Record types answered by `supports`.
+const SUPPORTED_RECORDS: [&str; 9] = [
+    resolver_interface::RECORD_ADDR,
+    resolver_interface::RECORD_COIN_ADDR,
+    resolver_interface::RECORD_TEXT,
+    resolver_interface::RECORD_CONTENTHASH,
+    resolver_interface::RECORD_WILDCARD,
+    resolver_interface::RECORD_MUXED,
+    resolver_interface::RECORD_SERVICE,
+    resolver_interface::RECORD_METADATA,
+    resolver_interface::RECORD_PUBKEY,
+];
+
+/// Interfaces answered by `supports_interface`.
+const SUPPORTED_INTERFACES: [&str; 12] = [
+    resolver_interface::INTERFACE_DETECTION,
+    resolver_interface::INTERFACE_ADDR_RESOLUTION,
+    resolver_interface::INTERFACE_TEXT_RECORDS,
+    resolver_interface::INTERFACE_REVERSE_RECORDS,
+    resolver_interface::INTERFACE_CONTENTHASH,
+    resolver_interface::INTERFACE_COIN_ADDR,
+    resolver_interface::INTERFACE_WILDCARD,
+    resolver_interface::INTERFACE_MUXED,
+    resolver_interface::INTERFACE_SERVICES,
+    resolver_interface::INTERFACE_METADATA,
+    resolver_interface::INTERFACE_PUBKEY,
+    resolver_interface::INTERFACE_RECORD_TYPES,
+];
+
+/// Calls `func(args)` on `resolver`, treating any failure as "no answer".
+fn query_resolver(env: &Env, resolver: &Address, func: &str, args: Vec<Val>) -> Option<Address> {
+    match env.try_invoke_contract::<Option<Address>, soroban_sdk::Error>(
+        resolver,
+        &Symbol::new(env, func),
+        args,
+    ) {
+        Ok(Ok(addr)) => addr,
+        _ => None,
+    }
+}
+
+fn contenthash_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::CONTENTHASH);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn muxed_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::MUXED);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn service_storage_key(env: &Env, namehash: &BytesN<32>, service_key: &Symbol) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::SERVICE);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.append(&service_key.clone().to_xdr(env));
+    key
+}
+
+fn services_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::SERVICES);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn read_service_keys(env: &Env, namehash: &BytesN<32>) -> Vec<Symbol> {
+    env.storage()
+        .persistent()
+        .get(&services_storage_key(env, namehash))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Adds or, when `listed` is false, removes `service_key` in the name's service index.
+fn index_service(env: &Env, namehash: &BytesN<32>, service_key: &Symbol, listed: bool) {
+    let mut services = read_service_keys(env, namehash);
+    match (services.first_index_of(service_key), listed) {
+        (None, true) => {
+            if services.len() >= MAX_SERVICES {
+                panic_with_error!(env, ResolverError::InvalidInput);
+            }
+            services.push_back(service_key.clone());
+        }
+        (Some(index), false) => {
+            services.remove(index);
+        }
+        _ => return,
+    }
+    let key = services_storage_key(env, namehash);
+    if services.is_empty() {
+        env.storage().persistent().remove(&key);
+    } else {
+        write_entry(env, &key, &services);
+    }
+}
+
+fn metadata_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::METADATA);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn challenge_storage_key(env: &Env, namehash: &BytesN<32>, requester: &Address) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::CHALLENGE);
+    key.extend_from_array(&namehash.to_array());
+    key.append(&requester.clone().to_xdr(env));
+    key
+}
+
+/// The ed25519 public key behind a classic `G...` account, or `None` for contracts. An
+/// account address encodes as `ScVal::Address` (4 bytes), `ScAddress::Account` (4 bytes),
+/// `PublicKeyTypeEd25519` (4 bytes), then the 32-byte key.
+fn account_public_key(env: &Env, addr: &Address) -> Option<BytesN<32>> {
+    let xdr = addr.clone().to_xdr(env);
+    if xdr.len() != 44 || xdr.slice(4..12) != Bytes::from_array(env, &[0; 8]) {
+        return None;
+    }
+    BytesN::try_from(xdr.slice(12..44)).ok()
+}
+
+/// Whether `value` is a `G...` account or `C...` contract strkey with a valid checksum, the
+/// only input `Address::from_string_bytes` accepts without trapping. The 56 base32
+/// characters decode to a version byte, a 32-byte key, and a little-endian CRC16-XModem of
+/// the first 33 bytes.
+fn is_address_strkey(value: &Bytes) -> bool {
+    const VERSION_ACCOUNT: u8 = 6 << 3;
+    const VERSION_CONTRACT: u8 = 2 << 3;
+    if value.len() != 56 {
+        return false;
+    }
+    let mut decoded = [0u8; 35];
+    let (mut acc, mut bits, mut len) = (0u32, 0u32, 0usize);
+    for c in value.iter() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'2'..=b'7' => c - b'2' + 26,
+            _ => return false,
+        };
+        acc = ((acc << 5) | digit as u32) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            decoded[len] = (acc >> bits) as u8;
+            len += 1;
+        }
+    }
+    if decoded[0] != VERSION_ACCOUNT && decoded[0] != VERSION_CONTRACT {
+        return false;
+    }
+    let mut crc = 0u16;
+    for byte in &decoded[..33] {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    decoded[33..] == crc.to_le_bytes()
+}
+
+/// Freezes belong to the record generation, so a new registration starts unfrozen.
+fn frozen_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::FROZEN);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+/// End of a freeze still in effect.
+fn active_freeze(env: &Env, namehash: &BytesN<32>) -> Option<u64> {
+    let until: u64 = env
+        .storage()
+        .persistent()
+        .get(&frozen_storage_key(env, namehash))?;
+    (until > env.ledger().timestamp()).then_some(until)
+}
+
+/// Whether `addr` is a contract (`C...` strkey) rather than a classic account (`G...`).
+fn is_contract_address(addr: &Address) -> bool {
+    addr.to_string().to_bytes().first() == Some(b'C')
+}
+
+/// Only classic accounts (`G...`) have muxed forms; contract addresses (`C...`) cannot carry
+/// an ID. Every `u64` is a valid SEP-23 multiplexing ID.
+fn validate_muxed(env: &Env, addr: &Address, id: Option<u64>) {
+    if id.is_some() && is_contract_address(addr) {
+        panic_with_error!(env, ResolverError::InvalidInput);
+    }
+}
+
+fn verifier_storage_key(env: &Env, verifier: &Address) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::VERIFIER);
+    key.append(&verifier.clone().to_xdr(env));
+    key
+}
+
+fn is_verifier(env: &Env, verifier: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .has(&verifier_storage_key(env, verifier))
+}
+
+/// Keyed by the owner the record was attested for, like manager approvals, so attestations
+/// lapse when the name changes hands.
+fn verified_storage_key(
+    env: &Env,
+    namehash: &BytesN<32>,
+    owner: &Address,
+    record_key: &Bytes,
+) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::VERIFIED);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.append(&owner.clone().to_xdr(env));
+    key.append(record_key);
+    key
+}
+
+fn verified_keys_storage_key(env: &Env, namehash: &BytesN<32>, owner: &Address) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::VERIFIED_KEYS);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.append(&owner.clone().to_xdr(env));
+    key
+}
+
+fn revision_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::REVISION);
+    key.extend_from_array(&namehash.to_array());
+    key
+}
+
+fn snapshot_storage_key(env: &Env, namehash: &BytesN<32>, revision: u32) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::SNAPSHOT);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&revision.to_be_bytes());
+    key
+}
+
+fn read_revision(env: &Env, namehash: &BytesN<32>) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&revision_storage_key(env, namehash))
+        .unwrap_or(0)
+}
+
+fn read_texts(env: &Env, namehash: &BytesN<32>) -> Map<Bytes, Bytes> {
+    let storage = env.storage().persistent();
+    let mut texts = Map::new(env);
+    for key in read_text_keys(env, namehash).iter() {
+        if let Some(value) = storage.get(&text_storage_key(env, namehash, &key)) {
+            texts.set(key, value);
+        }
+    }
+    texts
+}
+
+/// Advances the name's record revision and stores its `addr`, content hash, and text records
+/// under the new revision, dropping the snapshot `SNAPSHOTS_KEPT` revisions back. Called once
+/// per write, after the write, so a batch is one revision.
+fn snapshot_records(env: &Env, namehash: &BytesN<32>) {
+    let revision = read_revision(env, namehash)
+        .checked_add(1)
+        .unwrap_or_else(|| panic_with_error!(env, ResolverError::InvalidInput));
+    let storage = env.storage().persistent();
+    let snapshot = RecordSnapshot {
+        revision,
+        generation: record_version(env, namehash),
+        addr: storage.get(&addr_storage_key(env, namehash)),
+        contenthash: storage.get(&contenthash_storage_key(env, namehash)),
+        texts: read_texts(env, namehash),
+    };
+    write_entry(env, &revision_storage_key(env, namehash), &revision);
+    write_entry(
+        env,
+        &snapshot_storage_key(env, namehash, revision),
+        &snapshot,
+    );
+    if revision > SNAPSHOTS_KEPT {
+        storage.remove(&snapshot_storage_key(
+            env,
+            namehash,
+            revision - SNAPSHOTS_KEPT,
+        ));
+    }
+}
+
+/// Requires a supported multicodec prefix followed by at least one byte of payload.
+fn validate_contenthash(env: &Env, hash: &Bytes) {
+    if hash.len() > MAX_CONTENTHASH_LEN {
+        panic_with_error!(env, ResolverError::InvalidInput);
+    }
+    let known = CONTENTHASH_CODECS.iter().any(|codec| {
+        let prefix_len = codec.len() as u32;
+        hash.len() > prefix_len && hash.slice(0..prefix_len) == Bytes::from_slice(env, codec)
+    });
+    if !known {
+        panic_with_error!(env, ResolverError::InvalidInput);
+    }
+}
+
+fn reverse_storage_key(env: &Env, addr: &Address) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::REVERSE);
+    key.append(&addr.clone().to_xdr(env));
+    key
+}
+
+fn validate_text_key(env: &Env, key: &Bytes) {
+    if key.is_empty() || key.len() > MAX_TEXT_KEY_LEN {
+        panic_with_error!(env, ResolverError::InvalidInput);
+    }
+}
+
+/// Profile fields stored as text records under fixed keys. The typed setters validate the
+/// value and emit a field-specific event; `set_text` on the same keys stays freeform.
+mod profile {
+    use super::*;
+
+    pub const AVATAR: &[u8] = b"avatar";
+    pub const URL: &[u8] = b"url";
+    pub const EMAIL: &[u8] = b"email";
+    /// `social.<platform>`, e.g. `social.github`.
+    pub const SOCIAL_PREFIX: &[u8] = b"social.";
+
+    const MAX_URI_LEN: u32 = 512;
+    const MAX_EMAIL_LEN: u32 = 254;
+    const MAX_PLATFORM_LEN: u32 = 32;
+    const MAX_HANDLE_LEN: u32 = 64;
+    const AVATAR_SCHEMES: [&[u8]; 3] = [b"https://", b"ipfs://", b"ar://"];
+    const URL_SCHEMES: [&[u8]; 2] = [b"https://", b"http://"];
+
+    fn has_prefix(env: &Env, value: &Bytes, prefix: &[u8]) -> bool {
+        let len = prefix.len() as u32;
+        value.len() >= len && value.slice(0..len) == Bytes::from_slice(env, prefix)
+    }
+
+    /// Printable ASCII without spaces.
+    fn is_visible_ascii(value: &Bytes) -> bool {
+        value.iter().all(|byte| byte.is_ascii_graphic())
+    }
+
+    fn position(value: &Bytes, needle: u8) -> Option<u32> {
+        value
+            .iter()
+            .position(|byte| byte == needle)
+            .map(|index| index as u32)
+    }
+
+    fn reject(env: &Env) -> ! {
+        panic_with_error!(env, ResolverError::InvalidInput)
+    }
+
+    fn validate_uri(env: &Env, value: &Bytes, schemes: &[&[u8]]) {
+        let known = schemes
+            .iter()
+            .any(|scheme| has_prefix(env, value, scheme) && value.len() > scheme.len() as u32);
+        if value.len() > MAX_URI_LEN || !is_visible_ascii(value) || !known {
+            reject(env);
+        }
+    }
+
+    /// An `https`, `ipfs`, or `ar` URI of at most 512 bytes.
+    pub fn validate_avatar(env: &Env, value: &Bytes) {
+        validate_uri(env, value, &AVATAR_SCHEMES);
+    }
+
+    /// Same rules as `validate_avatar`: content-addressed or `https` storage.
+    pub fn validate_metadata_uri(env: &Env, value: &Bytes) {
+        validate_uri(env, value, &AVATAR_SCHEMES);
+    }
+
+    /// An `http` or `https` URL of at most 512 bytes.
+    pub fn validate_url(env: &Env, value: &Bytes) {
+        validate_uri(env, value, &URL_SCHEMES);
+    }
+
+    /// `local@domain.tld`: one `@`, a non-empty local part, and a dotted domain.
+    pub fn validate_email(env: &Env, value: &Bytes) {
+        if value.len() > MAX_EMAIL_LEN || !is_visible_ascii(value) {
+            reject(env);
+        }
+        let Some(at) = position(value, b'@') else {
+            reject(env);
+        };
+        let domain = value.slice(at + 1..);
+        let dot = position(&domain, b'.');
+        if at == 0
+            || position(&domain, b'@').is_some()
+            || !dot.is_some_and(|dot| dot > 0 && dot + 1 < domain.len())
+        {
+            reject(env);
+        }
+    }
+
+    /// A lowercase alphanumeric platform name of at most 32 bytes.
+    pub fn validate_platform(env: &Env, platform: &Bytes) {
+        if platform.is_empty()
+            || platform.len() > MAX_PLATFORM_LEN
+            || !platform
+                .iter()
+                .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit())
+        {
+            reject(env);
+        }
+    }
+
+    /// A non-empty handle of at most 64 printable ASCII bytes.
+    pub fn validate_handle(env: &Env, handle: &Bytes) {
+        if handle.is_empty() || handle.len() > MAX_HANDLE_LEN || !is_visible_ascii(handle) {
+            reject(env);
+        }
+    }
+
+    pub fn social_key(env: &Env, platform: &Bytes) -> Bytes {
+        let mut key = Bytes::from_slice(env, SOCIAL_PREFIX);
+        key.append(platform);
+        key
+    }
+}
+
+/// Stores one text record and adds its key to the name's index.
+fn write_text(env: &Env, namehash: &BytesN<32>, key: &Bytes, value: &Bytes) {
+    let storage_key = text_storage_key(env, namehash, key);
+    let old: Option<Bytes> = env.storage().persistent().get(&storage_key);
+    charge_text_bytes(env, namehash, old.map_or(0, |old| old.len()), value.len());
+    write_entry(env, &storage_key, value);
+    index_text_key(env, namehash, key);
+}
+
+fn text_limits(env: &Env) -> TextLimits {
+    env.storage()
+        .persistent()
+        .get(&Bytes::from_slice(env, keys::TEXT_LIMITS))
+        .unwrap_or(TextLimits {
+            max_value_len: DEFAULT_MAX_TEXT_VALUE_LEN,
+            max_name_bytes: DEFAULT_MAX_NAME_TEXT_BYTES,
+        })
+}
+
+fn text_bytes_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TEXT_BYTES);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn read_text_bytes(env: &Env, namehash: &BytesN<32>) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&text_bytes_storage_key(env, namehash))
+        .unwrap_or(0)
+}
+
+/// Replaces `old_len` bytes of the name's text with `new_len`. A new value must fit
+/// `max_value_len`, and growth must stay within `max_name_bytes`; shrinking always works,
+/// so lowered limits still let owners trim records.
+fn charge_text_bytes(env: &Env, namehash: &BytesN<32>, old_len: u32, new_len: u32) {
+    let limits = text_limits(env);
+    if new_len > limits.max_value_len {
+        panic_with_error!(env, ResolverError::ValueTooLarge);
+    }
+    // Records written before the counter existed are not in it, hence the saturation.
+    let total = read_text_bytes(env, namehash).saturating_sub(old_len) + new_len;
+    if new_len > old_len && total > limits.max_name_bytes {
+        panic_with_error!(env, ResolverError::TextBudgetExceeded);
+    }
+    let key = text_bytes_storage_key(env, namehash);
+    if total == 0 {
+        env.storage().persistent().remove(&key);
+    } else {
+        write_entry(env, &key, &total);
+    }
+}
+
+fn text_chunk_storage_key(env: &Env, namehash: &BytesN<32>, text_key: &Bytes, index: u32) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TEXT_CHUNK);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.extend_from_array(&index.to_be_bytes());
+    key.append(text_key);
+    key
+}
+
+fn text_chunks_storage_key(env: &Env, namehash: &BytesN<32>, text_key: &Bytes) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::TEXT_CHUNKS);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key.append(text_key);
+    key
+}
+
+fn chunked_keys_storage_key(env: &Env, namehash: &BytesN<32>) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::CHUNKED_KEYS);
+    key.extend_from_array(&namehash.to_array());
+    key.extend_from_array(&record_version(env, namehash).to_be_bytes());
+    key
+}
+
+fn read_text_chunk_count(env: &Env, namehash: &BytesN<32>, text_key: &Bytes) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&text_chunks_storage_key(env, namehash, text_key))
+        .unwrap_or(0)
+}
+
+/// Deletes every chunk stored under `text_key` and returns their bytes to the budget.
+fn clear_text_chunks(env: &Env, namehash: &BytesN<32>, text_key: &Bytes) {
+    let storage = env.storage().persistent();
+    let mut freed = 0;
+    for index in 0..read_text_chunk_count(env, namehash, text_key) {
+        let key = text_chunk_storage_key(env, namehash, text_key, index);
+        if let Some(chunk) = storage.get::<_, Bytes>(&key) {
+            freed += chunk.len();
+        }
+        storage.remove(&key);
+    }
+    storage.remove(&text_chunks_storage_key(env, namehash, text_key));
+    index_item(
+        env,
+        &chunked_keys_storage_key(env, namehash),
+        text_key,
+        false,
+        MAX_TEXT_KEYS,
+    );
+    if freed > 0 {
+        charge_text_bytes(env, namehash, freed, 0);
+    }
+}
+
+fn read_text(env: &Env, namehash: &BytesN<32>, key: &Bytes) -> Option<Bytes> {
+    ensure_initialized(env);
+    env.storage()
+        .persistent()
+        .get(&text_storage_key(env, namehash, key))
+}
+
+/// The Resolver has no admin of its own; it defers to the Registry's.
+fn require_admin(env: &Env, admin: &Address) {
+    admin.require_auth();
+    let registry = ensure_initialized(env);
+    let registry_admin: Address =
+        env.invoke_contract(&registry, &Symbol::new(env, "admin"), Vec::new(env));
+    if registry_admin != *admin {
+        panic_with_error!(env, ResolverError::NotAdmin);
+    }
+}
+
+fn ensure_initialized(env: &Env) -> Address {
+    let storage = env.storage().persistent();
+    let key = registry_storage_key(env);
+    let registry = storage
+        .get(&key)
+        .unwrap_or_else(|| panic_with_error!(env, ResolverError::NotInitialized));
+    // Every call reads the Registry pointer, so keep it live.
+    storage.extend_ttl(&key, TTL_THRESHOLD, TTL_EXTEND_TO);
+    registry
+}
+
+/// Approvals are keyed by the owner that granted them, so they lapse when the name changes
+/// hands.
+fn manager_storage_key(
+    env: &Env,
+    namehash: &BytesN<32>,
+    owner: &Address,
+    manager: &Address,
+) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::MANAGER);
+    key.extend_from_array(&namehash.to_array());
+    key.append(&owner.clone().to_xdr(env));
+    key.append(&manager.clone().to_xdr(env));
+    key
+}
+
+fn managers_storage_key(env: &Env, namehash: &BytesN<32>, owner: &Address) -> Bytes {
+    let mut key = Bytes::from_slice(env, keys::MANAGERS);
+    key.extend_from_array(&namehash.to_array());
+    key.append(&owner.clone().to_xdr(env));
+    key
+}
+
+/// The Registry's expiry-aware owner. A failed lookup reads as "no owner" rather than
+/// surfacing the Registry's host error.
+fn registry_owner(env: &Env, namehash: &BytesN<32>) -> Option<Address> {
+    let registry = ensure_initialized(env);
+    match env.try_invoke_contract::<Option<Address>, soroban_sdk::Error>(
+        &registry,
+        &Symbol::new(env, "owner_checked"),
+        (namehash,).into_val(env),
+    ) {
+        Ok(Ok(owner)) => owner,
+        _ => None,
+    }
+}
+
+/// The name's Registry expiry. Names without one (TLDs, subnames) make `expires` abort,
+/// which reads as `None`: they never expire.
+fn registry_expires(env: &Env, namehash: &BytesN<32>) -> Option<u64> {
+    let registry = ensure_initialized(env);
+    match env.try_invoke_contract::<u64, soroban_sdk::Error>(
+        &registry,
+        &Symbol::new(env, "expires"),
+        (namehash,).into_val(env),
+    ) {
+        Ok(Ok(expires)) => Some(expires),
+        _ => None,
+    }
+}
+
+/// Uses the Registry's expiry-aware `owner_checked` plus `expires`, so an owner can no longer
+/// write once the name expires; see `write_check_owner` for the errors.
+/// Managers approved by the current owner, and the lessee of a running Registry lease, may
+/// write records as well. When the owner is a contract (a smart wallet or multisig), any
+/// other `caller` is accepted as long as the owner itself authorizes the call, so the
+/// wallet's own policy decides who may act for it. Nobody may write while the records are
+/// frozen.
+fn require_owner(env: &Env, caller: &Address, namehash: &BytesN<32>) {
+    let registry = ensure_initialized(env);
+    if active_freeze(env, namehash).is_some() {
+        panic_with_error!(env, ResolverError::RecordsFrozen);
+    }
+    let owner = write_check_owner(env, namehash);
+    if owner == *caller
+        || env
+            .storage()
+            .persistent()
+            .has(&manager_storage_key(env, namehash, &owner, caller))
+    {
+        return;
+    }
+    let lessee: Option<Address> = env.invoke_contract(
+        &registry,
+        &Symbol::new(env, "lessee"),
+        (namehash,).into_val(env),
+    );
+    if lessee.as_ref() == Some(caller) {
+        return;
+    }
+    if is_contract_address(&owner) {
+        owner.require_auth();
+        return;
     }
+    panic_with_error!(env, ResolverError::NotOwner);
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use soroban_sdk::{
-        contract, contractimpl, contracttype,
-        testutils::{Address as _, Events},
-        Address, Bytes, BytesN, Env, Map, Symbol, TryFromVal,
-    };
-    use std::panic::{catch_unwind, AssertUnwindSafe};
+#[contract]
+pub struct Resolver;
+
+#[contractimpl]
+impl Resolver {
+    pub fn version(_env: Env) -> u32 {
+        1
+    }
+
+    pub fn init(env: Env, registry: Address) {
+        let storage = env.storage().persistent();
+        let key = registry_storage_key(&env);
+        if storage.has(&key) {
+            panic_with_error!(&env, ResolverError::AlreadyInitialized);
+        }
+        write_entry(&env, &key, &registry);
+        write_entry(
+            &env,
+            &Bytes::from_slice(&env, keys::STORAGE_VERSION),
+            &STORAGE_VERSION,
+        );
+    }
+
+    /// Replaces this contract's code with `new_wasm_hash` (already uploaded), keeping its
+    /// address and records. Only the Registry admin may upgrade.
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) {
+        require_admin(&env, &admin);
+        env.deployer()
+            .update_current_contract_wasm(new_wasm_hash.clone());
+        EvtUpgraded { new_wasm_hash }.publish(&env);
+    }
+
+    /// Runs the storage migrations between the stored layout version and `STORAGE_VERSION`,
+    /// then records the new version. A no-op when storage is already current.
+    pub fn migrate(env: Env, admin: Address) -> u32 {
+        require_admin(&env, &admin);
+        let from = Self::storage_version(env.clone());
+        if from > STORAGE_VERSION {
+            panic_with_error!(&env, ResolverError::UnsupportedVersion);
+        }
+        if from < STORAGE_VERSION {
+            // Layout changes add their rewrite steps here, keyed on `from`.
+            let key = Bytes::from_slice(&env, keys::STORAGE_VERSION);
+            write_entry(&env, &key, &STORAGE_VERSION);
+            EvtMigrated {
+                from,
+                to: STORAGE_VERSION,
+            }
+            .publish(&env);
+        }
+        STORAGE_VERSION
+    }
+
+    /// Turns the owner cache on or off. While it is on, write checks reuse the Registry owner
+    /// and expiry until the Registry's `owner_nonce` for the name changes. Same admin check
+    /// as `upgrade`. Emits `owner_cache_changed`.
+    pub fn set_owner_cache(env: Env, admin: Address, enabled: bool) {
+        require_admin(&env, &admin);
+        let key = Bytes::from_slice(&env, keys::OWNER_CACHE_ON);
+        if enabled {
+            write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        EvtOwnerCacheChanged { enabled }.publish(&env);
+    }
+
+    /// Approves `verifier` to attest records with `set_verified_record`, or revokes it.
+    /// Revoking hides every record the verifier attested. Same admin check as `upgrade`.
+    /// Emits `record_verifier_changed`.
+    pub fn set_record_verifier(env: Env, admin: Address, verifier: Address, enabled: bool) {
+        require_admin(&env, &admin);
+        let key = verifier_storage_key(&env, &verifier);
+        if enabled {
+            write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        EvtRecordVerifierChanged { verifier, enabled }.publish(&env);
+    }
+
+    /// Sets the per-value and per-name size caps on text records. Records already over a
+    /// lowered cap stay readable and can still shrink. Same admin check as `upgrade`. Emits
+    /// `text_limits_changed`.
+    pub fn set_text_limits(env: Env, admin: Address, limits: TextLimits) {
+        require_admin(&env, &admin);
+        if limits.max_value_len == 0 || limits.max_value_len > limits.max_name_bytes {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        write_entry(&env, &Bytes::from_slice(&env, keys::TEXT_LIMITS), &limits);
+        EvtTextLimitsChanged {
+            max_value_len: limits.max_value_len,
+            max_name_bytes: limits.max_name_bytes,
+        }
+        .publish(&env);
+    }
+
+    pub fn text_limits(env: Env) -> TextLimits {
+        text_limits(&env)
+    }
+
+    /// Bytes of text values and chunks the name currently stores, counted against
+    /// `max_name_bytes`.
+    pub fn text_bytes(env: Env, namehash: BytesN<32>) -> u32 {
+        ensure_initialized(&env);
+        read_text_bytes(&env, &namehash)
+    }
+
+    /// Approves `record_type` for `set_typed_record` at `schema_version`, or moves an approved
+    /// type to a newer schema. Versions only increase and approvals are never withdrawn, so a
+    /// type keeps its meaning for every client that reads it; anything else is
+    /// `InvalidInput`. Same admin check as `upgrade`. Emits `record_schema_changed`.
+    pub fn set_record_schema(env: Env, admin: Address, record_type: Symbol, schema_version: u32) {
+        require_admin(&env, &admin);
+        if schema_version <= read_record_schema(&env, &record_type) {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        write_entry(
+            &env,
+            &record_schema_storage_key(&env, &record_type),
+            &schema_version,
+        );
+        EvtRecordSchemaChanged {
+            record_type,
+            schema_version,
+        }
+        .publish(&env);
+    }
+
+    /// Current schema version of `record_type`, if the admin approved it.
+    pub fn record_schema(env: Env, record_type: Symbol) -> Option<u32> {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&record_schema_storage_key(&env, &record_type))
+    }
+
+    pub fn is_record_verifier(env: Env, verifier: Address) -> bool {
+        ensure_initialized(&env);
+        is_verifier(&env, &verifier)
+    }
+
+    pub fn owner_cache(env: Env) -> bool {
+        owner_cache_enabled(&env)
+    }
+
+    /// Layout version of stored records; deployments that predate versioning report 0, so
+    /// `migrate` runs their rewrite steps.
+    pub fn storage_version(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&Bytes::from_slice(&env, keys::STORAGE_VERSION))
+            .unwrap_or(0)
+    }
+
+    pub fn set_addr(env: Env, caller: Address, namehash: BytesN<32>, addr: Address) {
+        caller.require_auth();
+        require_owner(&env, &caller, &namehash);
+
+        let key = addr_storage_key(&env, &namehash);
+        write_entry(&env, &key, &addr);
+        snapshot_records(&env, &namehash);
+
+        EvtAddressChanged { namehash, addr }.publish(&env);
+    }
+
+    pub fn set_text(env: Env, caller: Address, namehash: BytesN<32>, key: Bytes, value: Bytes) {
+        caller.require_auth();
+        validate_text_key(&env, &key);
+        require_owner(&env, &caller, &namehash);
+
+        write_text(&env, &namehash, &key, &value);
+        snapshot_records(&env, &namehash);
+
+        EvtTextChanged { namehash, key }.publish(&env);
+    }
+
+    /// Stores chunk `index` of a large value under `key`, such as profile JSON or an inline
+    /// image too big for one text record. `index` may overwrite an existing chunk or append
+    /// the next one; readers fetch chunks `0..text_chunk_count` and concatenate them. Each
+    /// chunk is capped like a text value and counts toward the name's budget. `clear_text`
+    /// removes a key's chunks. Same checks as `set_text`. Emits `text_chunk_changed`.
+    pub fn set_text_chunk(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        key: Bytes,
+        index: u32,
+        chunk: Bytes,
+    ) {
+        caller.require_auth();
+        validate_text_key(&env, &key);
+        require_owner(&env, &caller, &namehash);
+        let count = read_text_chunk_count(&env, &namehash, &key);
+        if index > count || chunk.is_empty() {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+
+        index_item(
+            &env,
+            &chunked_keys_storage_key(&env, &namehash),
+            &key,
+            true,
+            MAX_TEXT_KEYS,
+        );
+        let storage_key = text_chunk_storage_key(&env, &namehash, &key, index);
+        let old: Option<Bytes> = env.storage().persistent().get(&storage_key);
+        charge_text_bytes(&env, &namehash, old.map_or(0, |old| old.len()), chunk.len());
+        write_entry(&env, &storage_key, &chunk);
+        let count = count.max(index + 1);
+        write_entry(
+            &env,
+            &text_chunks_storage_key(&env, &namehash, &key),
+            &count,
+        );
+
+        EvtTextChunkChanged {
+            namehash,
+            key,
+            index,
+            count,
+        }
+        .publish(&env);
+    }
+
+    pub fn text_chunk(env: Env, namehash: BytesN<32>, key: Bytes, index: u32) -> Option<Bytes> {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&text_chunk_storage_key(&env, &namehash, &key, index))
+    }
+
+    pub fn text_chunk_count(env: Env, namehash: BytesN<32>, key: Bytes) -> u32 {
+        ensure_initialized(&env);
+        read_text_chunk_count(&env, &namehash, &key)
+    }
+
+    /// Sets the address (when given) and every `(key, value)` text record in one invocation.
+    /// All inputs are validated before anything is written; emits the same events as the
+    /// individual setters.
+    pub fn set_records(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        addr: Option<Address>,
+        texts: Vec<(Bytes, Bytes)>,
+    ) {
+        caller.require_auth();
+        if texts.len() > MAX_BATCH_TEXTS {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        for (key, _) in texts.iter() {
+            validate_text_key(&env, &key);
+        }
+        require_owner(&env, &caller, &namehash);
+
+        if let Some(addr) = addr {
+            write_entry(&env, &addr_storage_key(&env, &namehash), &addr);
+            EvtAddressChanged {
+                namehash: namehash.clone(),
+                addr,
+            }
+            .publish(&env);
+        }
+        for (key, value) in texts.iter() {
+            write_text(&env, &namehash, &key, &value);
+            EvtTextChanged {
+                namehash: namehash.clone(),
+                key,
+            }
+            .publish(&env);
+        }
+        snapshot_records(&env, &namehash);
+    }
+
+    /// Sets the `avatar` text record to an `https`, `ipfs`, or `ar` URI. Emits `avatar_changed`.
+    pub fn set_avatar(env: Env, caller: Address, namehash: BytesN<32>, avatar: Bytes) {
+        caller.require_auth();
+        profile::validate_avatar(&env, &avatar);
+        require_owner(&env, &caller, &namehash);
+        write_text(
+            &env,
+            &namehash,
+            &Bytes::from_slice(&env, profile::AVATAR),
+            &avatar,
+        );
+        snapshot_records(&env, &namehash);
+        EvtAvatarChanged { namehash, avatar }.publish(&env);
+    }
+
+    /// Sets the `url` text record to an `http` or `https` URL. Emits `url_changed`.
+    pub fn set_url(env: Env, caller: Address, namehash: BytesN<32>, url: Bytes) {
+        caller.require_auth();
+        profile::validate_url(&env, &url);
+        require_owner(&env, &caller, &namehash);
+        write_text(
+            &env,
+            &namehash,
+            &Bytes::from_slice(&env, profile::URL),
+            &url,
+        );
+        snapshot_records(&env, &namehash);
+        EvtUrlChanged { namehash, url }.publish(&env);
+    }
+
+    /// Sets the `email` text record. Emits `email_changed`.
+    pub fn set_email(env: Env, caller: Address, namehash: BytesN<32>, email: Bytes) {
+        caller.require_auth();
+        profile::validate_email(&env, &email);
+        require_owner(&env, &caller, &namehash);
+        write_text(
+            &env,
+            &namehash,
+            &Bytes::from_slice(&env, profile::EMAIL),
+            &email,
+        );
+        snapshot_records(&env, &namehash);
+        EvtEmailChanged { namehash, email }.publish(&env);
+    }
+
+    /// Sets the `social.<platform>` text record to `handle`. Emits `social_changed`.
+    pub fn set_social(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        platform: Bytes,
+        handle: Bytes,
+    ) {
+        caller.require_auth();
+        profile::validate_platform(&env, &platform);
+        profile::validate_handle(&env, &handle);
+        require_owner(&env, &caller, &namehash);
+        write_text(
+            &env,
+            &namehash,
+            &profile::social_key(&env, &platform),
+            &handle,
+        );
+        snapshot_records(&env, &namehash);
+        EvtSocialChanged {
+            namehash,
+            platform,
+            handle,
+        }
+        .publish(&env);
+    }
+
+    pub fn avatar(env: Env, namehash: BytesN<32>) -> Option<Bytes> {
+        read_text(&env, &namehash, &Bytes::from_slice(&env, profile::AVATAR))
+    }
+
+    pub fn url(env: Env, namehash: BytesN<32>) -> Option<Bytes> {
+        read_text(&env, &namehash, &Bytes::from_slice(&env, profile::URL))
+    }
+
+    pub fn email(env: Env, namehash: BytesN<32>) -> Option<Bytes> {
+        read_text(&env, &namehash, &Bytes::from_slice(&env, profile::EMAIL))
+    }
+
+    pub fn social(env: Env, namehash: BytesN<32>, platform: Bytes) -> Option<Bytes> {
+        profile::validate_platform(&env, &platform);
+        read_text(&env, &namehash, &profile::social_key(&env, &platform))
+    }
+
+    /// Address of `namehash` on the chain identified by SLIP-44 `coin_type`, in that chain's
+    /// binary encoding. Stellar (`148`) returns the `addr` record as strkey bytes.
+    pub fn addr_by_coin(env: Env, namehash: BytesN<32>, coin_type: u32) -> Option<Bytes> {
+        ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        if coin_type == COIN_TYPE_STELLAR {
+            let addr: Option<Address> = storage.get(&addr_storage_key(&env, &namehash));
+            return addr.map(|addr| addr.to_string().to_bytes());
+        }
+        storage.get(&coin_addr_storage_key(&env, &namehash, coin_type))
+    }
+
+    /// Sets the address for SLIP-44 `coin_type`. A Stellar (`148`) value must be a `G...` or
+    /// `C...` strkey (`InvalidInput` otherwise) and updates the `addr` record exactly like
+    /// `set_addr`.
+    pub fn set_addr_by_coin(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        coin_type: u32,
+        value: Bytes,
+    ) {
+        caller.require_auth();
+        if value.is_empty() || value.len() > MAX_COIN_ADDR_LEN {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        require_owner(&env, &caller, &namehash);
+
+        if coin_type == COIN_TYPE_STELLAR {
+            if !is_address_strkey(&value) {
+                panic_with_error!(&env, ResolverError::InvalidInput);
+            }
+            let addr = Address::from_string_bytes(&value);
+            write_entry(&env, &addr_storage_key(&env, &namehash), &addr);
+            snapshot_records(&env, &namehash);
+            EvtAddressChanged { namehash, addr }.publish(&env);
+            return;
+        }
+        index_item(
+            &env,
+            &coin_types_storage_key(&env, &namehash),
+            &coin_type,
+            true,
+            MAX_COIN_TYPES,
+        );
+        write_entry(
+            &env,
+            &coin_addr_storage_key(&env, &namehash, coin_type),
+            &value,
+        );
+
+        EvtCoinAddressChanged {
+            namehash,
+            coin_type,
+            value,
+        }
+        .publish(&env);
+    }
+
+    /// Public key of `key_type` that the name publishes for encrypted messaging or signature
+    /// checks, e.g. by a chat dApp addressing `alice.stellar`.
+    pub fn pubkey(env: Env, namehash: BytesN<32>, key_type: u32) -> Option<Bytes> {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&pubkey_storage_key(&env, &namehash, key_type))
+    }
+
+    /// Sets the name's `key_type` public key; an empty `key` clears it. Ed25519 (`0`) keys are
+    /// 32 bytes and secp256k1 (`1`) keys are 33-byte compressed or 65-byte uncompressed
+    /// SEC1; anything else is `InvalidInput`. Same auth and ownership checks as `set_addr`.
+    /// Emits `EvtPubkeyChanged`.
+    pub fn set_pubkey(env: Env, caller: Address, namehash: BytesN<32>, key_type: u32, key: Bytes) {
+        caller.require_auth();
+        if !key.is_empty() {
+            validate_pubkey(&env, key_type, &key);
+        }
+        require_owner(&env, &caller, &namehash);
+
+        let storage_key = pubkey_storage_key(&env, &namehash, key_type);
+        if key.is_empty() {
+            env.storage().persistent().remove(&storage_key);
+        } else {
+            write_entry(&env, &storage_key, &key);
+        }
+
+        EvtPubkeyChanged {
+            namehash,
+            key_type,
+            key,
+        }
+        .publish(&env);
+    }
+
+    /// Sets the content hash; an empty `hash` clears it. Emits `EvtContenthashChanged`.
+    pub fn set_contenthash(env: Env, caller: Address, namehash: BytesN<32>, hash: Bytes) {
+        caller.require_auth();
+        if !hash.is_empty() {
+            validate_contenthash(&env, &hash);
+        }
+        require_owner(&env, &caller, &namehash);
+
+        let storage = env.storage().persistent();
+        let key = contenthash_storage_key(&env, &namehash);
+        if hash.is_empty() {
+            storage.remove(&key);
+        } else {
+            write_entry(&env, &key, &hash);
+        }
+        snapshot_records(&env, &namehash);
+
+        EvtContenthashChanged { namehash, hash }.publish(&env);
+    }
+
+    /// Starts a "sign in with your name" flow for `requester`, e.g. a dApp's account: returns
+    /// a fresh 32-byte challenge that the account in the name's `addr` record signs off-chain,
+    /// to be checked by `verify_response` within 5 minutes. Each requester has its own
+    /// outstanding challenge per name, which only it can replace. Emits `challenge_issued`.
+    pub fn issue_challenge(env: Env, requester: Address, namehash: BytesN<32>) -> BytesN<32> {
+        requester.require_auth();
+        ensure_initialized(&env);
+        let mut seed = Bytes::from_array(&env, &namehash.to_array());
+        seed.extend_from_array(&env.ledger().sequence().to_be_bytes());
+        seed.extend_from_array(&env.prng().gen::<u64>().to_be_bytes());
+        let challenge = env.crypto().sha256(&seed).to_bytes();
+        let expires_at = env.ledger().timestamp() + CHALLENGE_TTL_SECS;
+
+        let key = challenge_storage_key(&env, &namehash, &requester);
+        let storage = env.storage().temporary();
+        storage.set(&key, &(challenge.clone(), expires_at));
+        storage.extend_ttl(&key, CHALLENGE_TTL_LEDGERS, CHALLENGE_TTL_LEDGERS);
+        EvtChallengeIssued {
+            namehash,
+            requester,
+            challenge: challenge.clone(),
+            expires_at,
+        }
+        .publish(&env);
+        challenge
+    }
+
+    /// Checks `signature`, an ed25519 signature over the 32 bytes of `requester`'s outstanding
+    /// challenge, by the classic account the name currently resolves to, and returns that
+    /// account. The challenge is consumed, so each one signs in once. Aborts with
+    /// `ChallengeMissing` when none is outstanding, `InvalidInput` when the name does not resolve to a `G...`
+    /// account, and with a host error for a bad signature. Emits `challenge_verified`.
+    pub fn verify_response(
+        env: Env,
+        requester: Address,
+        namehash: BytesN<32>,
+        signature: BytesN<64>,
+    ) -> Address {
+        ensure_initialized(&env);
+        let key = challenge_storage_key(&env, &namehash, &requester);
+        let (challenge, expires_at): (BytesN<32>, u64) = env
+            .storage()
+            .temporary()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(&env, ResolverError::ChallengeMissing));
+        if env.ledger().timestamp() > expires_at {
+            panic_with_error!(&env, ResolverError::ChallengeMissing);
+        }
+        let addr: Address = env
+            .storage()
+            .persistent()
+            .get(&addr_storage_key(&env, &namehash))
+            .unwrap_or_else(|| panic_with_error!(&env, ResolverError::InvalidInput));
+        let public_key = account_public_key(&env, &addr)
+            .unwrap_or_else(|| panic_with_error!(&env, ResolverError::InvalidInput));
+        env.crypto()
+            .ed25519_verify(&public_key, &challenge.into(), &signature);
+
+        env.storage().temporary().remove(&key);
+        EvtChallengeVerified {
+            namehash,
+            requester,
+            addr: addr.clone(),
+        }
+        .publish(&env);
+        addr
+    }
+
+    /// Blocks every record write for `namehash` until `until_ts`, by anyone including the
+    /// owner, so a compromised key cannot repoint the name during the cooldown. Owner only.
+    /// A freeze can be extended but never shortened, and may last at most 365 days from now.
+    /// Emits `records_frozen`.
+    pub fn freeze_records(env: Env, caller: Address, namehash: BytesN<32>, until_ts: u64) {
+        caller.require_auth();
+        ensure_initialized(&env);
+        if write_check_owner(&env, &namehash) != caller {
+            panic_with_error!(&env, ResolverError::NotOwner);
+        }
+        let now = env.ledger().timestamp();
+        if until_ts <= now
+            || until_ts - now > MAX_FREEZE_SECS
+            || active_freeze(&env, &namehash).is_some_and(|until| until_ts < until)
+        {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        write_entry(&env, &frozen_storage_key(&env, &namehash), &until_ts);
+        EvtRecordsFrozen {
+            namehash,
+            until: until_ts,
+        }
+        .publish(&env);
+    }
+
+    /// When the current freeze of `namehash` ends, if its records are frozen.
+    pub fn frozen_until(env: Env, namehash: BytesN<32>) -> Option<u64> {
+        ensure_initialized(&env);
+        active_freeze(&env, &namehash)
+    }
+
+    /// Sets the muxed account that exchanges should credit for `namehash`: the base account
+    /// `addr` and, when given, the multiplexing `id` that routes a deposit to one customer.
+    /// Contract addresses are accepted only without an `id`. Emits `muxed_changed`.
+    pub fn set_muxed(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        addr: Address,
+        id: Option<u64>,
+    ) {
+        caller.require_auth();
+        validate_muxed(&env, &addr, id);
+        require_owner(&env, &caller, &namehash);
+
+        write_entry(
+            &env,
+            &muxed_storage_key(&env, &namehash),
+            &(addr.clone(), id),
+        );
+        EvtMuxedChanged {
+            namehash,
+            addr: Some(addr),
+            id,
+        }
+        .publish(&env);
+    }
+
+    /// Points marketplaces and NFT views at the name's JSON metadata: `uri` is an `https`,
+    /// `ipfs`, or `ar` URI of at most 512 bytes (`InvalidInput` otherwise), and `hash` is the
+    /// `sha256` of the document, which readers compare against what they fetch. Same checks
+    /// as `set_addr`. Emits `metadata_changed`.
+    pub fn set_metadata(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        uri: Bytes,
+        hash: BytesN<32>,
+    ) {
+        caller.require_auth();
+        profile::validate_metadata_uri(&env, &uri);
+        require_owner(&env, &caller, &namehash);
+
+        let metadata = Metadata {
+            uri: uri.clone(),
+            hash: hash.clone(),
+        };
+        write_entry(&env, &metadata_storage_key(&env, &namehash), &metadata);
+        EvtMetadataChanged {
+            namehash,
+            uri,
+            hash,
+        }
+        .publish(&env);
+    }
+
+    pub fn metadata(env: Env, namehash: BytesN<32>) -> Option<Metadata> {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&metadata_storage_key(&env, &namehash))
+    }
+
+    /// Advertises `contract` as the name's `service_key` endpoint (e.g. `amm`, `oracle`, or
+    /// `vault`), separate from the primary `addr` record. Only contract addresses are
+    /// accepted, and a name holds at most 16 services; otherwise `InvalidInput`. Same checks
+    /// as `set_addr`. Emits `service_changed`.
+    pub fn set_service(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        service_key: Symbol,
+        contract: Address,
+    ) {
+        caller.require_auth();
+        if !is_contract_address(&contract) {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        require_owner(&env, &caller, &namehash);
+
+        write_entry(
+            &env,
+            &service_storage_key(&env, &namehash, &service_key),
+            &contract,
+        );
+        index_service(&env, &namehash, &service_key, true);
+        EvtServiceChanged {
+            namehash,
+            service_key,
+            contract: Some(contract),
+        }
+        .publish(&env);
+    }
+
+    /// Removes the `service_key` endpoint. Emits `service_changed` with no contract.
+    pub fn clear_service(env: Env, caller: Address, namehash: BytesN<32>, service_key: Symbol) {
+        caller.require_auth();
+        require_owner(&env, &caller, &namehash);
+
+        env.storage()
+            .persistent()
+            .remove(&service_storage_key(&env, &namehash, &service_key));
+        index_service(&env, &namehash, &service_key, false);
+        EvtServiceChanged {
+            namehash,
+            service_key,
+            contract: None,
+        }
+        .publish(&env);
+    }
+
+    pub fn service(env: Env, namehash: BytesN<32>, service_key: Symbol) -> Option<Address> {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&service_storage_key(&env, &namehash, &service_key))
+    }
+
+    /// Every service endpoint of the name, so clients can discover them in one call.
+    pub fn services(env: Env, namehash: BytesN<32>) -> Map<Symbol, Address> {
+        ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        let mut services = Map::new(&env);
+        for service_key in read_service_keys(&env, &namehash).iter() {
+            if let Some(contract) = storage.get(&service_storage_key(&env, &namehash, &service_key))
+            {
+                services.set(service_key, contract);
+            }
+        }
+        services
+    }
+
+    /// Sets the name's `record_type` record, encoded as `schema_version` of that type, which
+    /// must be its current version from `record_schema` (`UnsupportedRecordType`
+    /// otherwise). Values are up to 1024 bytes and a name holds at most 16 typed records
+    /// (`InvalidInput`); an empty `value` clears the record. Same auth and ownership checks as
+    /// `set_addr`. Emits `typed_record_changed`.
+    pub fn set_typed_record(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        record_type: Symbol,
+        schema_version: u32,
+        value: Bytes,
+    ) {
+        caller.require_auth();
+        if value.len() > MAX_TYPED_RECORD_LEN {
+            panic_with_error!(&env, ResolverError::InvalidInput);
+        }
+        let current = read_record_schema(&env, &record_type);
+        if current == 0 || schema_version != current {
+            panic_with_error!(&env, ResolverError::UnsupportedRecordType);
+        }
+        require_owner(&env, &caller, &namehash);
+
+        extend_entry(&env, &record_schema_storage_key(&env, &record_type));
+        let key = typed_record_storage_key(&env, &namehash, &record_type);
+        if value.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            let record = TypedRecord {
+                schema_version,
+                value: value.clone(),
+            };
+            write_entry(&env, &key, &record);
+        }
+        index_record_type(&env, &namehash, &record_type, !value.is_empty());
+        EvtTypedRecordChanged {
+            namehash,
+            record_type,
+            schema_version,
+            value,
+        }
+        .publish(&env);
+    }
+
+    /// The name's `record_type` record with the schema version it was written at, which may
+    /// be older than the type's current one.
+    pub fn typed_record(
+        env: Env,
+        namehash: BytesN<32>,
+        record_type: Symbol,
+    ) -> Option<TypedRecord> {
+        ensure_initialized(&env);
+        env.storage()
+            .persistent()
+            .get(&typed_record_storage_key(&env, &namehash, &record_type))
+    }
+
+    /// Types of the name's typed records, in the order they were first set, so clients can
+    /// discover record kinds this contract has no dedicated entrypoints for.
+    pub fn record_types(env: Env, namehash: BytesN<32>) -> Vec<Symbol> {
+        ensure_initialized(&env);
+        read_record_types(&env, &namehash)
+    }
+
+    /// Removes the muxed record, so `resolve_to_muxed` falls back to `addr`. Emits
+    /// `muxed_changed` with no address.
+    pub fn clear_muxed(env: Env, caller: Address, namehash: BytesN<32>) {
+        caller.require_auth();
+        require_owner(&env, &caller, &namehash);
+
+        env.storage()
+            .persistent()
+            .remove(&muxed_storage_key(&env, &namehash));
+        EvtMuxedChanged {
+            namehash,
+            addr: None,
+            id: None,
+        }
+        .publish(&env);
+    }
+
+    /// Deposit destination for `namehash` as a base address and optional muxed ID. Names
+    /// without a muxed record resolve to their `addr` record with no ID.
+    pub fn resolve_to_muxed(env: Env, namehash: BytesN<32>) -> Option<(Address, Option<u64>)> {
+        ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        storage
+            .get(&muxed_storage_key(&env, &namehash))
+            .or_else(|| {
+                let addr: Address = storage.get(&addr_storage_key(&env, &namehash))?;
+                Some((addr, None))
+            })
+    }
+
+    /// Resolves `labels` (root-first, e.g. `["xlm", "alice"]`) to an address in one call:
+    /// hashes via the Registry, looks up the name's resolver, and reads its `addr` record.
+    /// Returns `None` if the name has no live resolver or that resolver cannot answer.
+    pub fn resolve(env: Env, labels: Vec<Bytes>) -> Option<Address> {
+        let registry = ensure_initialized(&env);
+        let namehash = registry_namehash(&env, &registry, labels);
+        let resolver = registry_resolver(&env, &registry, &namehash)?;
+        if resolver == env.current_contract_address() {
+            return Self::addr(env, namehash);
+        }
+        match ResolverInterfaceClient::new(&env, &resolver).try_addr(&namehash) {
+            Ok(Ok(addr)) => addr,
+            _ => None,
+        }
+    }
+
+    /// `resolve` with ENSIP-10 style fallback: when the exact name has no live resolver, walks
+    /// up its ancestors and asks the nearest one that has a resolver to answer through
+    /// `wildcard_addr(ancestor, labels)`. The walk stops at that first resolver, even if it
+    /// has no answer.
+    pub fn resolve_wildcard(env: Env, labels: Vec<Bytes>) -> Option<Address> {
+        let registry = ensure_initialized(&env);
+        let namehash = registry_namehash(&env, &registry, labels.clone());
+        if registry_resolver(&env, &registry, &namehash).is_some() {
+            return Self::resolve(env, labels);
+        }
+        let mut depth = labels.len();
+        while depth > 1 {
+            depth -= 1;
+            let ancestor = registry_namehash(&env, &registry, labels.slice(0..depth));
+            let Some(resolver) = registry_resolver(&env, &registry, &ancestor) else {
+                continue;
+            };
+            if resolver == env.current_contract_address() {
+                return Self::wildcard_addr(env, ancestor, labels);
+            }
+            return query_resolver(
+                &env,
+                &resolver,
+                "wildcard_addr",
+                (ancestor, labels).into_val(&env),
+            );
+        }
+        None
+    }
+
+    /// Wildcard handler called by `resolve_wildcard` for names under `ancestor` that have no
+    /// resolver of their own. This implementation answers every such name with the wildcard
+    /// address set on `ancestor`; other resolvers may use `labels` to answer per name.
+    pub fn wildcard_addr(env: Env, ancestor: BytesN<32>, labels: Vec<Bytes>) -> Option<Address> {
+        ensure_initialized(&env);
+        let _ = labels;
+        let storage = env.storage().persistent();
+        storage.get(&wildcard_storage_key(&env, &ancestor))
+    }
+
+    /// Sets (or clears, with `None`) the address that every unresolved name below `namehash`
+    /// resolves to through `resolve_wildcard`. Emits `EvtWildcardChanged`.
+    pub fn set_wildcard_addr(
+        env: Env,
+        caller: Address,
+        namehash: BytesN<32>,
+        addr: Option<Address>,
+    ) {
+        caller.require_auth();
+        require_owner(&env, &caller, &namehash);
+
+        let key = wildcard_storage_key(&env, &namehash);
+        match &addr {
+            Some(addr) => write_entry(&env, &key, addr),
+            None => env.storage().persistent().remove(&key),
+        }
+        EvtWildcardChanged { namehash, addr }.publish(&env);
+    }
+
+    /// Extends the TTL of every record `namehash` holds under its current record version,
+    /// its kept record snapshots and freeze, the current owner's manager approvals and
+    /// verified records, and the Registry pointer. Permissionless.
+    pub fn bump(env: Env, namehash: BytesN<32>) {
+        ensure_initialized(&env);
+        extend_entry(&env, &frozen_storage_key(&env, &namehash));
+        let coin_types = coin_types_storage_key(&env, &namehash);
+        extend_entry(&env, &coin_types);
+        for coin_type in read_list::<u32>(&env, &coin_types).iter() {
+            extend_entry(&env, &coin_addr_storage_key(&env, &namehash, coin_type));
+        }
+        if let Some(owner) = registry_owner(&env, &namehash) {
+            let managers = managers_storage_key(&env, &namehash, &owner);
+            extend_entry(&env, &managers);
+            for manager in read_list::<Address>(&env, &managers).iter() {
+                extend_entry(
+                    &env,
+                    &manager_storage_key(&env, &namehash, &owner, &manager),
+                );
+            }
+            let verified = verified_keys_storage_key(&env, &namehash, &owner);
+            extend_entry(&env, &verified);
+            for key in read_list::<Bytes>(&env, &verified).iter() {
+                extend_entry(&env, &verified_storage_key(&env, &namehash, &owner, &key));
+            }
+        }
+        extend_entry(&env, &addr_storage_key(&env, &namehash));
+        extend_entry(&env, &contenthash_storage_key(&env, &namehash));
+        extend_entry(&env, &wildcard_storage_key(&env, &namehash));
+        extend_entry(&env, &muxed_storage_key(&env, &namehash));
+        extend_entry(&env, &text_keys_storage_key(&env, &namehash));
+        extend_entry(&env, &text_bytes_storage_key(&env, &namehash));
+        extend_entry(&env, &services_storage_key(&env, &namehash));
+        extend_entry(&env, &metadata_storage_key(&env, &namehash));
+        for key_type in [KEY_TYPE_ED25519, KEY_TYPE_SECP256K1] {
+            extend_entry(&env, &pubkey_storage_key(&env, &namehash, key_type));
+        }
+        for service_key in read_service_keys(&env, &namehash).iter() {
+            extend_entry(&env, &service_storage_key(&env, &namehash, &service_key));
+        }
+        extend_entry(&env, &typed_index_storage_key(&env, &namehash));
+        for record_type in read_record_types(&env, &namehash).iter() {
+            extend_entry(
+                &env,
+                &typed_record_storage_key(&env, &namehash, &record_type),
+            );
+        }
+        let revision = read_revision(&env, &namehash);
+        extend_entry(&env, &revision_storage_key(&env, &namehash));
+        for kept in revision.saturating_sub(SNAPSHOTS_KEPT) + 1..=revision {
+            extend_entry(&env, &snapshot_storage_key(&env, &namehash, kept));
+        }
+        for key in read_text_keys(&env, &namehash).iter() {
+            extend_entry(&env, &text_storage_key(&env, &namehash, &key));
+        }
+        let chunked = chunked_keys_storage_key(&env, &namehash);
+        extend_entry(&env, &chunked);
+        for key in read_list::<Bytes>(&env, &chunked).iter() {
+            extend_entry(&env, &text_chunks_storage_key(&env, &namehash, &key));
+            for index in 0..read_text_chunk_count(&env, &namehash, &key) {
+                extend_entry(&env, &text_chunk_storage_key(&env, &namehash, &key, index));
+            }
+        }
+    }
+
+    /// Keys of the name's current text records, in the order they were first set.
+    pub fn text_keys(env: Env, namehash: BytesN<32>) -> Vec<Bytes> {
+        ensure_initialized(&env);
+        read_text_keys(&env, &namehash)
+    }
+
+    /// Every current text record of the name, so a profile can be read in one call.
+    pub fn texts(env: Env, namehash: BytesN<32>) -> Map<Bytes, Bytes> {
+        ensure_initialized(&env);
+        read_texts(&env, &namehash)
+    }
+
+    /// Number of `addr`, content hash, and text writes the name has seen, counting each
+    /// `set_records` batch once; `0` before the first. Never resets, also across
+    /// re-registrations.
+    pub fn record_revision(env: Env, namehash: BytesN<32>) -> u32 {
+        ensure_initialized(&env);
+        read_revision(&env, &namehash)
+    }
+
+    /// The name's `addr`, content hash, and text records as they were right after `revision`,
+    /// so an agreement can pin "records as of revision N". `None` for an unknown revision,
+    /// one more than `SNAPSHOTS_KEPT` revisions old, or one from before the name's current
+    /// registration, whose records are unreachable.
+    pub fn records_at(env: Env, namehash: BytesN<32>, revision: u32) -> Option<RecordSnapshot> {
+        ensure_initialized(&env);
+        let snapshot: RecordSnapshot = env
+            .storage()
+            .persistent()
+            .get(&snapshot_storage_key(&env, &namehash, revision))?;
+        (snapshot.generation == record_version(&env, &namehash)).then_some(snapshot)
+    }
+
+    /// Removes the `addr` record. Reverse claims pointing at this name stop resolving.
+    pub fn clear_addr(env: Env, caller: Address, namehash: BytesN<32>) {
+        caller.require_auth();
+        require_owner(&env, &caller, &namehash);
+
+        let storage = env.storage().persistent();
+        storage.remove(&addr_storage_key(&env, &namehash));
+        snapshot_records(&env, &namehash);
+
+        EvtAddressCleared { namehash }.publish(&env);
+    }
+
+    pub fn clear_text(env: Env, caller: Address, namehash: BytesN<32>, key: Bytes) {
+        caller.require_auth();
+        validate_text_key(&env, &key);
+        require_owner(&env, &caller, &namehash);
+
+        let storage = env.storage().persistent();
+        let storage_key = text_storage_key(&env, &namehash, &key);
+        if let Some(old) = storage.get::<_, Bytes>(&storage_key) {
+            charge_text_bytes(&env, &namehash, old.len(), 0);
+        }
+        storage.remove(&storage_key);
+        unindex_text_key(&env, &namehash, &key);
+        clear_text_chunks(&env, &namehash, &key);
+        snapshot_records(&env, &namehash);
+
+        EvtTextCleared { namehash, key }.publish(&env);
+    }
+
+    /// Attests `value` under `key` (e.g. `verified:twitter`) for the name's current owner, or
+    /// removes the attestation with `None`. Only approved verifiers may call; owners and
+    /// managers cannot write these records. The record stops resolving when the name changes
+    /// hands or is re-registered. Emits `verified_record_changed`.
+    pub fn set_verified_record(
+        env: Env,
+        verifier: Address,
+        namehash: BytesN<32>,
+        key: Bytes,
+        value: Option<Bytes>,
+    ) {
+        verifier.require_auth();
+        validate_text_key(&env, &key);
+        ensure_initialized(&env);
+        if !is_verifier(&env, &verifier) {
+            panic_with_error!(&env, ResolverError::NotVerifier);
+        }
+        let Some(owner) = registry_owner(&env, &namehash) else {
+            panic_with_error!(&env, ResolverError::NotOwner);
+        };
+
+        index_item(
+            &env,
+            &verified_keys_storage_key(&env, &namehash, &owner),
+            &key,
+            value.is_some(),
+            MAX_VERIFIED_RECORDS,
+        );
+        let storage_key = verified_storage_key(&env, &namehash, &owner, &key);
+        match &value {
+            Some(value) => write_entry(&env, &storage_key, &(verifier.clone(), value.clone())),
+            None => env.storage().persistent().remove(&storage_key),
+        }
+        EvtVerifiedRecordChanged {
+            namehash,
+            key,
+            verifier,
+            owner,
+            value,
+        }
+        .publish(&env);
+    }
+
+    /// The attested record under `key` for the name's current owner, with the verifier that
+    /// attested it. `None` if unset, attested for a previous owner, or its verifier has been
+    /// revoked.
+    pub fn verified_record(env: Env, namehash: BytesN<32>, key: Bytes) -> Option<(Address, Bytes)> {
+        ensure_initialized(&env);
+        validate_text_key(&env, &key);
+        let owner = registry_owner(&env, &namehash)?;
+        let record: (Address, Bytes) = env
+            .storage()
+            .persistent()
+            .get(&verified_storage_key(&env, &namehash, &owner, &key))?;
+        is_verifier(&env, &record.0).then_some(record)
+    }
+
+    /// Lets `manager` (e.g. a profile dApp) write this name's records, or revokes that right.
+    /// Managers cannot transfer the name, and the approval ends when the name changes hands.
+    /// Emits `manager_changed`.
+    pub fn approve_manager(
+        env: Env,
+        owner: Address,
+        namehash: BytesN<32>,
+        manager: Address,
+        approved: bool,
+    ) {
+        owner.require_auth();
+        if registry_owner(&env, &namehash).as_ref() != Some(&owner) {
+            panic_with_error!(&env, ResolverError::NotOwner);
+        }
+        index_item(
+            &env,
+            &managers_storage_key(&env, &namehash, &owner),
+            &manager,
+            approved,
+            MAX_MANAGERS,
+        );
+        let key = manager_storage_key(&env, &namehash, &owner, &manager);
+        if approved {
+            write_entry(&env, &key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+        EvtManagerChanged {
+            namehash,
+            manager,
+            owner,
+            approved,
+        }
+        .publish(&env);
+    }
+
+    /// Whether `manager` may write the name's records on behalf of its current owner.
+    pub fn is_manager(env: Env, namehash: BytesN<32>, manager: Address) -> bool {
+        registry_owner(&env, &namehash).is_some_and(|owner| {
+            env.storage()
+                .persistent()
+                .has(&manager_storage_key(&env, &namehash, &owner, &manager))
+        })
+    }
+
+    /// Declare `namehash` as the primary name of `addr`. The address must authorize the claim
+    /// and the name's forward `addr` record must already point back at it.
+    pub fn set_reverse(env: Env, caller: Address, addr: Address, namehash: BytesN<32>) {
+        caller.require_auth();
+        if caller != addr {
+            addr.require_auth();
+        }
+        ensure_initialized(&env);
+
+        let storage = env.storage().persistent();
+        let forward: Option<Address> = storage.get(&addr_storage_key(&env, &namehash));
+        if forward.as_ref() != Some(&addr) {
+            panic_with_error!(&env, ResolverError::ReverseMismatch);
+        }
+        write_entry(&env, &reverse_storage_key(&env, &addr), &namehash);
+
+        EvtReverseChanged { addr, namehash }.publish(&env);
+    }
+
+    /// Primary name of `addr`, or `None` if unset or the forward record no longer matches.
+    pub fn reverse(env: Env, addr: Address) -> Option<BytesN<32>> {
+        ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        let namehash: BytesN<32> = storage.get(&reverse_storage_key(&env, &addr))?;
+        let forward: Option<Address> = storage.get(&addr_storage_key(&env, &namehash));
+        if forward == Some(addr) {
+            Some(namehash)
+        } else {
+            None
+        }
+    }
+
+    pub fn registry(env: Env) -> Address {
+        ensure_initialized(&env)
+    }
+}
+
+#[contractimpl]
+impl ResolverInterface for Resolver {
+    /// Also `true` for every type approved with `set_record_schema`.
+    fn supports(env: Env, record_type: Symbol) -> bool {
+        SUPPORTED_RECORDS
+            .iter()
+            .any(|name| record_type == Symbol::new(&env, name))
+            || env
+                .storage()
+                .persistent()
+                .has(&record_schema_storage_key(&env, &record_type))
+    }
+
+    fn addr(env: Env, namehash: BytesN<32>) -> Option<Address> {
+        ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        let key = addr_storage_key(&env, &namehash);
+        storage.get(&key)
+    }
+
+    fn text(env: Env, namehash: BytesN<32>, key: Bytes) -> Option<Bytes> {
+        ensure_initialized(&env);
+        validate_text_key(&env, &key);
+        let storage = env.storage().persistent();
+        let data_key = text_storage_key(&env, &namehash, &key);
+        storage.get(&data_key)
+    }
+
+    /// Multicodec-prefixed content hash (IPFS, IPNS, Swarm, or Arweave) for `namehash`.
+    fn contenthash(env: Env, namehash: BytesN<32>) -> Option<Bytes> {
+        ensure_initialized(&env);
+        let storage = env.storage().persistent();
+        storage.get(&contenthash_storage_key(&env, &namehash))
+    }
+}
+
+#[contractimpl]
+impl InterfaceDetection for Resolver {
+    fn supports_interface(env: Env, interface_id: Symbol) -> bool {
+        SUPPORTED_INTERFACES
+            .iter()
+            .any(|name| interface_id == Symbol::new(&env, name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use soroban_sdk::{
+        contract, contractimpl, contracttype,
+        testutils::{
+            storage::Persistent as _, Address as _, Events, Ledger, MockAuth, MockAuthInvoke,
+        },
+        xdr, Address, Bytes, BytesN, Env, IntoVal, Map, Symbol, TryFromVal,
+    };
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    #[contract]
+    pub struct MockRegistry;
+
+    #[derive(Clone)]
+    #[contracttype]
+    enum MockRegistryKey {
+        Owner(BytesN<32>),
+        RecordVersion(BytesN<32>),
+        Released(BytesN<32>),
+        Expires(BytesN<32>),
+        Resolver(BytesN<32>),
+        Lessee(BytesN<32>),
+        OwnerNonce(BytesN<32>),
+        Admin,
+        OwnerCalls,
+    }
+
+    fn bump_mock_nonce(env: &Env, namehash: &BytesN<32>) {
+        let key = MockRegistryKey::OwnerNonce(namehash.clone());
+        let nonce: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(nonce + 1));
+    }
+
+    #[contractimpl]
+    impl MockRegistry {
+        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Owner(namehash))
+                .unwrap_or_else(|| panic!("mock registry owner not set"))
+        }
+
+        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
+            bump_mock_nonce(&env, &namehash);
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Owner(namehash), &owner);
+        }
+
+        pub fn owner_nonce(env: Env, namehash: BytesN<32>) -> u64 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::OwnerNonce(namehash))
+                .unwrap_or(0)
+        }
+
+        pub fn owner_checked(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            let calls: u32 = env
+                .storage()
+                .instance()
+                .get(&MockRegistryKey::OwnerCalls)
+                .unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&MockRegistryKey::OwnerCalls, &(calls + 1));
+            if env
+                .storage()
+                .persistent()
+                .has(&MockRegistryKey::Released(namehash.clone()))
+            {
+                return None;
+            }
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Owner(namehash))
+        }
+
+        /// Stand-in hash: a name's node is `namehash(last label's first byte)`.
+        pub fn namehash(env: Env, labels: Vec<Bytes>) -> BytesN<32> {
+            let last = labels.get(labels.len() - 1).unwrap();
+            BytesN::from_array(&env, &[last.get(0).unwrap(); 32])
+        }
+
+        pub fn set_resolver(env: Env, namehash: BytesN<32>, resolver: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Resolver(namehash), &resolver);
+        }
+
+        pub fn resolver_checked(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            if env
+                .storage()
+                .persistent()
+                .has(&MockRegistryKey::Released(namehash.clone()))
+            {
+                return None;
+            }
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Resolver(namehash))
+        }
+
+        pub fn set_lessee(env: Env, namehash: BytesN<32>, lessee: Option<Address>) {
+            let key = MockRegistryKey::Lessee(namehash);
+            match lessee {
+                Some(lessee) => env.storage().persistent().set(&key, &lessee),
+                None => env.storage().persistent().remove(&key),
+            }
+        }
+
+        pub fn lessee(env: Env, namehash: BytesN<32>) -> Option<Address> {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Lessee(namehash))
+        }
+
+        pub fn set_expires(env: Env, namehash: BytesN<32>, expires: u64) {
+            bump_mock_nonce(&env, &namehash);
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Expires(namehash), &expires);
+        }
+
+        pub fn expires(env: Env, namehash: BytesN<32>) -> u64 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Expires(namehash))
+                .unwrap_or_else(|| panic!("mock registry expiry not set"))
+        }
+
+        pub fn release(env: Env, namehash: BytesN<32>) {
+            bump_mock_nonce(&env, &namehash);
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Released(namehash), &true);
+        }
+
+        pub fn owner_calls(env: Env) -> u32 {
+            env.storage()
+                .instance()
+                .get(&MockRegistryKey::OwnerCalls)
+                .unwrap_or(0)
+        }
+
+        pub fn set_admin(env: Env, admin: Address) {
+            env.storage()
+                .persistent()
+                .set(&MockRegistryKey::Admin, &admin);
+        }
+
+        pub fn admin(env: Env) -> Address {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::Admin)
+                .unwrap_or_else(|| panic!("mock registry admin not set"))
+        }
+
+        pub fn record_version(env: Env, namehash: BytesN<32>) -> u32 {
+            env.storage()
+                .persistent()
+                .get(&MockRegistryKey::RecordVersion(namehash))
+                .unwrap_or(0)
+        }
+
+        pub fn bump_record_version(env: Env, namehash: BytesN<32>) {
+            bump_mock_nonce(&env, &namehash);
+            let key = MockRegistryKey::RecordVersion(namehash);
+            let version: u32 = env.storage().persistent().get(&key).unwrap_or(0);
+            env.storage().persistent().set(&key, &(version + 1));
+        }
+    }
+
+    fn namehash(env: &Env, byte: u8) -> BytesN<32> {
+        BytesN::from_array(env, &[byte; 32])
+    }
+
+    fn bytes(env: &Env, data: &[u8]) -> Bytes {
+        Bytes::from_slice(env, data)
+    }
+
+    /// A fresh classic (`G...`) account. `Address::generate` makes contract addresses, which
+    /// the Resolver treats as smart wallets that may authorize for any caller.
+    fn account(e: &Env) -> Address {
+        let xdr::ScAddress::Contract(xdr::ContractId(xdr::Hash(seed))) =
+            (&Address::generate(e)).into()
+        else {
+            unreachable!("generated addresses are contracts");
+        };
+        let key = xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256(seed));
+        Address::try_from_val(e, &xdr::ScAddress::Account(xdr::AccountId(key))).unwrap()
+    }
+
+    #[test]
+    fn it_compiles_and_returns_version() {
+        let e = Env::default();
+        let resolver_id = e.register(Resolver, ());
+        let client = ResolverClient::new(&e, &resolver_id);
+        assert_eq!(client.version(), 1);
+    }
+
+    #[test]
+    fn init_once() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+
+        resolver.init(&registry_id);
+
+        let second_call = catch_unwind(AssertUnwindSafe(|| resolver.init(&registry_id)));
+        assert!(second_call.is_err());
+        assert_eq!(resolver.registry(), registry_id);
+    }
+
+    #[test]
+    fn unset_reads_return_none() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 1);
+        let key = bytes(&e, b"profile");
+
+        assert!(resolver.addr(&namehash).is_none());
+        assert!(resolver.text(&namehash, &key).is_none());
+    }
+
+    #[test]
+    fn set_addr_happy_path() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+
+        let namehash = namehash(&e, 2);
+        let owner = Address::generate(&e);
+        let addr = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        resolver.init(&registry_id);
+        resolver.set_addr(&owner, &namehash, &addr);
+
+        let events = e.events().all();
+        assert_eq!(resolver.addr(&namehash), Some(addr.clone()));
+        assert_eq!(events.len(), 1, "expected address event");
+        let (event_contract, topics, data) = events.get(0).unwrap();
+        assert_eq!(event_contract, resolver_id);
+
+        let topic_symbol =
+            Symbol::try_from_val(&e, &topics.get(0).expect("topic symbol missing")).unwrap();
+        assert_eq!(topic_symbol, Symbol::new(&e, "address_changed"));
+
+        let topic_namehash =
+            BytesN::<32>::try_from_val(&e, &topics.get(1).expect("topic namehash missing"))
+                .unwrap();
+        assert_eq!(topic_namehash, namehash);
+
+        let data_map =
+            Map::<Symbol, Address>::try_from_val(&e, &data).expect("unable to decode event data");
+        let recorded_addr = data_map
+            .get(Symbol::new(&e, "addr"))
+            .expect("addr missing in event");
+        assert_eq!(recorded_addr, addr);
+    }
+
+    #[test]
+    fn set_addr_rejects_non_owner() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 3);
+        let owner = account(&e);
+        let stranger = Address::generate(&e);
+        let addr = Address::generate(&e);
+
+        registry.set_owner(&namehash, &owner);
+
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_addr(&stranger, &namehash, &addr)
+        }));
+        assert!(attempt.is_err());
+        assert!(resolver.addr(&namehash).is_none());
+    }
+
+    #[test]
+    fn set_text_happy_path() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+
+        let namehash = namehash(&e, 4);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        let key = bytes(&e, b"profile");
+        let value = bytes(&e, b"ipfs://hash");
+
+        resolver.init(&registry_id);
+        resolver.set_text(&owner, &namehash, &key, &value);
+
+        let events = e.events().all();
+        assert_eq!(resolver.text(&namehash, &key), Some(value.clone()));
+        assert_eq!(events.len(), 1, "expected text event");
+        let (event_contract, topics, data) = events.get(0).unwrap();
+        assert_eq!(event_contract, resolver_id);
+
+        let topic_symbol =
+            Symbol::try_from_val(&e, &topics.get(0).expect("topic symbol missing")).unwrap();
+        assert_eq!(topic_symbol, Symbol::new(&e, "text_changed"));
+
+        let topic_namehash =
+            BytesN::<32>::try_from_val(&e, &topics.get(1).expect("topic namehash missing"))
+                .unwrap();
+        assert_eq!(topic_namehash, namehash);
+
+        let data_map = Map::<Symbol, Bytes>::try_from_val(&e, &data)
+            .expect("unable to decode text event data");
+        let recorded_key = data_map
+            .get(Symbol::new(&e, "key"))
+            .expect("key missing in event");
+        assert_eq!(recorded_key, key);
+    }
+
+    #[test]
+    fn set_text_invalid_key_rejected() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 5);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        let empty_key = bytes(&e, &[]);
+        let value = bytes(&e, b"data");
+        let long_vec = {
+            let mut buffer = std::vec::Vec::new();
+            buffer.resize((MAX_TEXT_KEY_LEN + 1) as usize, 7u8);
+            buffer
+        };
+        let too_long_key = Bytes::from_slice(&e, &long_vec);
+
+        let empty_attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_text(&owner, &namehash, &empty_key, &value)
+        }));
+        assert!(empty_attempt.is_err());
+
+        let long_attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_text(&owner, &namehash, &too_long_key, &value)
+        }));
+        assert!(long_attempt.is_err());
+    }
+
+    #[test]
+    fn records_are_isolated() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+
+        resolver.init(&registry_id);
+
+        let name_a = namehash(&e, 6);
+        let name_b = namehash(&e, 7);
+        let owner_a = Address::generate(&e);
+        let owner_b = Address::generate(&e);
+        registry.set_owner(&name_a, &owner_a);
+        registry.set_owner(&name_b, &owner_b);
+
+        let addr_a = Address::generate(&e);
+        resolver.set_addr(&owner_a, &name_a, &addr_a);
+
+        assert_eq!(resolver.addr(&name_a), Some(addr_a.clone()));
+        assert!(resolver.addr(&name_b).is_none());
+
+        let key_primary = bytes(&e, b"profile");
+        let key_secondary = bytes(&e, b"avatar");
+        let val_primary = bytes(&e, b"primary");
+        let val_secondary = bytes(&e, b"secondary");
+
+        resolver.set_text(&owner_a, &name_a, &key_primary, &val_primary);
+        resolver.set_text(&owner_a, &name_a, &key_secondary, &val_secondary);
+
+        assert_eq!(resolver.text(&name_a, &key_primary), Some(val_primary));
+        assert_eq!(resolver.text(&name_a, &key_secondary), Some(val_secondary));
+        assert!(resolver.text(&name_b, &key_primary).is_none());
+    }
+
+    #[test]
+    fn ownership_changes_require_new_auth() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 8);
+        let owner_initial = account(&e);
+        let owner_new = account(&e);
+        let first_addr = Address::generate(&e);
+        let second_addr = Address::generate(&e);
+
+        registry.set_owner(&namehash, &owner_initial);
+        resolver.set_addr(&owner_initial, &namehash, &first_addr);
+        assert_eq!(resolver.addr(&namehash), Some(first_addr));
+
+        registry.set_owner(&namehash, &owner_new);
+
+        let stale_owner_attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_addr(&owner_initial, &namehash, &second_addr)
+        }));
+        assert!(stale_owner_attempt.is_err());
+
+        resolver.set_addr(&owner_new, &namehash, &second_addr);
+        assert_eq!(resolver.addr(&namehash), Some(second_addr));
+    }
+
+    #[test]
+    fn set_reverse_happy_path() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 9);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        resolver.set_addr(&owner, &namehash, &owner);
+
+        assert!(resolver.reverse(&owner).is_none());
+        resolver.set_reverse(&owner, &owner, &namehash);
+
+        let events = e.events().all();
+        assert_eq!(events.len(), 1, "expected reverse event");
+        let (event_contract, topics, data) = events.get(0).unwrap();
+        assert_eq!(event_contract, resolver_id);
+        let topic_symbol = Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap();
+        assert_eq!(topic_symbol, Symbol::new(&e, "reverse_changed"));
+        let topic_addr = Address::try_from_val(&e, &topics.get(1).unwrap()).unwrap();
+        assert_eq!(topic_addr, owner);
+        let data_map = Map::<Symbol, BytesN<32>>::try_from_val(&e, &data).unwrap();
+        assert_eq!(data_map.get(Symbol::new(&e, "namehash")).unwrap(), namehash);
+
+        assert_eq!(resolver.reverse(&owner), Some(namehash));
+    }
+
+    #[test]
+    fn set_reverse_requires_matching_forward_record() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 10);
+        let owner = Address::generate(&e);
+        let other = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        let unset = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_reverse(&other, &other, &namehash)
+        }));
+        assert!(unset.is_err(), "forward record must exist");
+
+        resolver.set_addr(&owner, &namehash, &owner);
+        let mismatch = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_reverse(&other, &other, &namehash)
+        }));
+        assert!(
+            mismatch.is_err(),
+            "forward record must point at the claimant"
+        );
+        assert!(resolver.reverse(&other).is_none());
+    }
+
+    #[test]
+    fn reverse_hidden_once_forward_record_changes() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 11);
+        let owner = Address::generate(&e);
+        let wallet = Address::generate(&e);
+        let new_wallet = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        resolver.set_addr(&owner, &namehash, &wallet);
+        resolver.set_reverse(&wallet, &wallet, &namehash);
+        assert_eq!(resolver.reverse(&wallet), Some(namehash.clone()));
+
+        resolver.set_addr(&owner, &namehash, &new_wallet);
+        assert!(resolver.reverse(&wallet).is_none());
+    }
+
+    #[test]
+    fn set_reverse_requires_address_auth() {
+        let e = Env::default();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 12);
+        let owner = Address::generate(&e);
+        let wallet = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        e.mock_all_auths();
+        resolver.set_addr(&owner, &namehash, &wallet);
+
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver
+                .mock_auths(&[MockAuth {
+                    address: &owner,
+                    invoke: &MockAuthInvoke {
+                        contract: &resolver_id,
+                        fn_name: "set_reverse",
+                        args: (&owner, &wallet, &namehash).into_val(&e),
+                        sub_invokes: &[],
+                    },
+                }])
+                .set_reverse(&owner, &wallet, &namehash)
+        }));
+        assert!(attempt.is_err(), "claimed address must authorize");
+        assert!(resolver.reverse(&wallet).is_none());
+    }
+
+    #[test]
+    fn clear_addr_and_text_remove_records() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 13);
+        let owner = Address::generate(&e);
+        let wallet = Address::generate(&e);
+        let key = bytes(&e, b"url");
+        registry.set_owner(&namehash, &owner);
+        resolver.set_addr(&owner, &namehash, &wallet);
+        resolver.set_text(&owner, &namehash, &key, &bytes(&e, b"https://example.com"));
+        resolver.set_reverse(&wallet, &wallet, &namehash);
+
+        resolver.clear_text(&owner, &namehash, &key);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "text_cleared")
+        );
+        assert!(resolver.text(&namehash, &key).is_none());
+
+        resolver.clear_addr(&owner, &namehash);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "address_cleared")
+        );
+        assert!(resolver.addr(&namehash).is_none());
+        assert!(resolver.reverse(&wallet).is_none());
+    }
+
+    #[test]
+    fn clear_records_reject_non_owner() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 14);
+        let owner = account(&e);
+        let attacker = Address::generate(&e);
+        let key = bytes(&e, b"avatar");
+        registry.set_owner(&namehash, &owner);
+        resolver.set_addr(&owner, &namehash, &owner);
+        resolver.set_text(&owner, &namehash, &key, &bytes(&e, b"ipfs://x"));
+
+        let addr_attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.clear_addr(&attacker, &namehash);
+        }));
+        assert!(addr_attempt.is_err());
+        let text_attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.clear_text(&attacker, &namehash, &key);
+        }));
+        assert!(text_attempt.is_err());
+
+        assert_eq!(resolver.addr(&namehash), Some(owner));
+        assert!(resolver.text(&namehash, &key).is_some());
+    }
+
+    #[test]
+    fn records_forgotten_after_record_version_bump() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 15);
+        let old_owner = Address::generate(&e);
+        let new_owner = Address::generate(&e);
+        let key = bytes(&e, b"url");
+        registry.set_owner(&namehash, &old_owner);
+        resolver.set_addr(&old_owner, &namehash, &old_owner);
+        resolver.set_text(
+            &old_owner,
+            &namehash,
+            &key,
+            &bytes(&e, b"https://old.example"),
+        );
+        resolver.set_reverse(&old_owner, &old_owner, &namehash);
+
+        // Simulates the Registrar re-registering the expired name for someone else.
+        registry.set_owner(&namehash, &new_owner);
+        registry.bump_record_version(&namehash);
+
+        assert!(resolver.addr(&namehash).is_none());
+        assert!(resolver.text(&namehash, &key).is_none());
+        assert!(resolver.reverse(&old_owner).is_none());
+
+        resolver.set_addr(&new_owner, &namehash, &new_owner);
+        assert_eq!(resolver.addr(&namehash), Some(new_owner));
+    }
+
+    #[test]
+    fn lapsed_owner_cannot_write_records() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 16);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        resolver.set_addr(&owner, &namehash, &owner);
+
+        registry.release(&namehash);
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_text(&owner, &namehash, &bytes(&e, b"url"), &bytes(&e, b"x"));
+        }));
+        assert!(attempt.is_err(), "owners past grace lose write access");
+    }
+
+    #[test]
+    fn addr_by_coin_stores_multiple_chains() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 17);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        let btc = bytes(&e, b"\x00\x14\x75\x1e\x76\xe8\x19\x91\x96\xd4\x54\x94\x1c\x45\xd1\xb3\xa3\x23\xf1\x43\x3b\xd6");
+        let evm = bytes(&e, &[0xabu8; 20]);
+        resolver.set_addr_by_coin(&owner, &namehash, &0, &btc);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "coin_address_changed")
+        );
+        resolver.set_addr_by_coin(&owner, &namehash, &60, &evm);
+
+        assert_eq!(resolver.addr_by_coin(&namehash, &0), Some(btc));
+        assert_eq!(resolver.addr_by_coin(&namehash, &60), Some(evm));
+        assert_eq!(resolver.addr_by_coin(&namehash, &2), None);
+        assert!(resolver.addr(&namehash).is_none());
+    }
+
+    #[test]
+    fn pubkeys_validate_length_per_type() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 18);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        let ed25519 = bytes(&e, &[0x11; 32]);
+        let mut secp256k1 = [0x22u8; 33];
+        secp256k1[0] = 0x02;
+        let secp256k1 = bytes(&e, &secp256k1);
+        resolver.set_pubkey(&owner, &namehash, &KEY_TYPE_ED25519, &ed25519);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "pubkey_changed")
+        );
+        resolver.set_pubkey(&owner, &namehash, &KEY_TYPE_SECP256K1, &secp256k1);
+        assert_eq!(
+            resolver.pubkey(&namehash, &KEY_TYPE_ED25519),
+            Some(ed25519.clone())
+        );
+        assert_eq!(
+            resolver.pubkey(&namehash, &KEY_TYPE_SECP256K1),
+            Some(secp256k1)
+        );
+
+        let invalid = Err(Ok(ResolverError::InvalidInput.into()));
+        let short = bytes(&e, &[0x11; 31]);
+        assert_eq!(
+            resolver.try_set_pubkey(&owner, &namehash, &KEY_TYPE_ED25519, &short),
+            invalid
+        );
+        assert_eq!(
+            resolver.try_set_pubkey(&owner, &namehash, &KEY_TYPE_SECP256K1, &ed25519),
+            invalid
+        );
+        let unprefixed = bytes(&e, &[0x22; 33]);
+        assert_eq!(
+            resolver.try_set_pubkey(&owner, &namehash, &KEY_TYPE_SECP256K1, &unprefixed),
+            invalid
+        );
+        assert_eq!(
+            resolver.try_set_pubkey(&owner, &namehash, &2, &ed25519),
+            invalid
+        );
+
+        resolver.set_pubkey(&owner, &namehash, &KEY_TYPE_ED25519, &Bytes::new(&e));
+        assert_eq!(resolver.pubkey(&namehash, &KEY_TYPE_ED25519), None);
+    }
+
+    #[test]
+    fn typed_records_follow_approved_schemas() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let admin = Address::generate(&e);
+        let owner = Address::generate(&e);
+        let node = namehash(&e, 19);
+        registry.set_admin(&admin);
+        registry.set_owner(&node, &owner);
+        let abi = Symbol::new(&e, "abi");
+        let value = bytes(&e, b"{\"fns\":[]}");
+
+        assert_eq!(
+            resolver.try_set_typed_record(&owner, &node, &abi, &1, &value),
+            Err(Ok(ResolverError::UnsupportedRecordType.into()))
+        );
+        assert!(!resolver.supports(&abi));
+        assert_eq!(
+            resolver.try_set_record_schema(&owner, &abi, &1),
+            Err(Ok(ResolverError::NotAdmin.into()))
+        );
+        resolver.set_record_schema(&admin, &abi, &1);
+        assert!(resolver.supports(&abi));
+        assert_eq!(resolver.record_schema(&abi), Some(1));
+        assert_eq!(
+            resolver.try_set_record_schema(&admin, &abi, &1),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+
+        resolver.set_typed_record(&owner, &node, &abi, &1, &value);
+        let events = e.events().all();
+        let (_, topics, _) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "typed_record_changed")
+        );
+        assert_eq!(
+            resolver.record_types(&node),
+            soroban_sdk::vec![&e, abi.clone()]
+        );
+
+        // A schema bump keeps old records readable at their version but gates new writes.
+        resolver.set_record_schema(&admin, &abi, &2);
+        let v1 = TypedRecord {
+            schema_version: 1,
+            value: value.clone(),
+        };
+        assert_eq!(resolver.typed_record(&node, &abi), Some(v1));
+        assert_eq!(
+            resolver.try_set_typed_record(&owner, &node, &abi, &1, &value),
+            Err(Ok(ResolverError::UnsupportedRecordType.into()))
+        );
+        resolver.set_typed_record(&owner, &node, &abi, &2, &value);
+        assert_eq!(
+            resolver.typed_record(&node, &abi).unwrap().schema_version,
+            2
+        );
+
+        resolver.set_typed_record(&owner, &node, &abi, &2, &Bytes::new(&e));
+        assert_eq!(resolver.typed_record(&node, &abi), None);
+        assert!(resolver.record_types(&node).is_empty());
+    }
+
+    #[test]
+    fn typed_records_check_owners_sizes_and_the_per_name_cap() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let admin = Address::generate(&e);
+        let owner = account(&e);
+        let stranger = account(&e);
+        let node = namehash(&e, 20);
+        registry.set_admin(&admin);
+        registry.set_owner(&node, &owner);
+        let types: std::vec::Vec<Symbol> = (0..=MAX_TYPED_RECORDS)
+            .map(|i| Symbol::new(&e, &std::format!("kind{i}")))
+            .collect();
+        for record_type in &types {
+            resolver.set_record_schema(&admin, record_type, &1);
+        }
+        let abi = types[0].clone();
+        let value = bytes(&e, b"v");
+
+        assert_eq!(
+            resolver.try_set_typed_record(&stranger, &node, &abi, &1, &value),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        assert_eq!(
+            resolver.try_set_typed_record(&owner, &node, &abi, &2, &value),
+            Err(Ok(ResolverError::UnsupportedRecordType.into()))
+        );
+        let largest = Bytes::from_slice(&e, &[7; MAX_TYPED_RECORD_LEN as usize]);
+        let mut too_large = largest.clone();
+        too_large.push_back(7);
+        assert_eq!(
+            resolver.try_set_typed_record(&owner, &node, &abi, &1, &too_large),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        resolver.set_typed_record(&owner, &node, &abi, &1, &largest);
+        assert_eq!(resolver.typed_record(&node, &abi).unwrap().value, largest);
+
+        // A full name may still rewrite its types, and clearing one frees a slot.
+        let (last, extra) = (types.len() - 2, types.len() - 1);
+        for record_type in &types[1..=last] {
+            resolver.set_typed_record(&owner, &node, record_type, &1, &value);
+        }
+        assert_eq!(resolver.record_types(&node).len(), MAX_TYPED_RECORDS);
+        assert_eq!(
+            resolver.try_set_typed_record(&owner, &node, &types[extra], &1, &value),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        resolver.set_typed_record(&owner, &node, &abi, &1, &value);
+        resolver.set_typed_record(&owner, &node, &abi, &1, &Bytes::new(&e));
+        resolver.set_typed_record(&owner, &node, &types[extra], &1, &value);
+        let listed = resolver.record_types(&node);
+        assert_eq!(listed.get(0), Some(types[1].clone()));
+        assert_eq!(listed.last(), Some(types[extra].clone()));
+        assert!(!listed.contains(&abi));
+    }
+
+    #[test]
+    fn stellar_coin_type_maps_to_addr_record() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 18);
+        let owner = Address::generate(&e);
+        let wallet = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        resolver.set_addr(&owner, &namehash, &wallet);
+        let strkey = wallet.to_string().to_bytes();
+        assert_eq!(
+            resolver.addr_by_coin(&namehash, &COIN_TYPE_STELLAR),
+            Some(strkey)
+        );
+
+        let other = Address::generate(&e);
+        resolver.set_addr_by_coin(
+            &owner,
+            &namehash,
+            &COIN_TYPE_STELLAR,
+            &other.to_string().to_bytes(),
+        );
+        assert_eq!(resolver.addr(&namehash), Some(other.clone()));
+        let classic = account(&e);
+        resolver.set_addr_by_coin(
+            &owner,
+            &namehash,
+            &COIN_TYPE_STELLAR,
+            &classic.to_string().to_bytes(),
+        );
+        assert_eq!(resolver.addr(&namehash), Some(classic.clone()));
+
+        // Garbage, a bad checksum, and a non-address strkey are typed errors, not traps.
+        let mut corrupt = std::vec![0u8; 56];
+        other.to_string().copy_into_slice(&mut corrupt);
+        corrupt[55] = if corrupt[55] == b'A' { b'B' } else { b'A' };
+        let seed = "SAAACAQDAQCQMBYIBEFAWDANBYHRAEISCMKBKFQXDAMRUGY4DUPB6NKI";
+        for value in [
+            bytes(&e, b"not a strkey"),
+            bytes(&e, &[0xff; 56]),
+            bytes(&e, &corrupt),
+            bytes(&e, seed.as_bytes()),
+        ] {
+            assert_eq!(
+                resolver.try_set_addr_by_coin(&owner, &namehash, &COIN_TYPE_STELLAR, &value),
+                Err(Ok(ResolverError::InvalidInput.into()))
+            );
+        }
+        assert_eq!(resolver.addr(&namehash), Some(classic));
+
+        let empty = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_addr_by_coin(&owner, &namehash, &60, &Bytes::new(&e));
+        }));
+        assert!(empty.is_err());
+    }
+
+    #[test]
+    fn contenthash_set_read_and_clear() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 19);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        let mut ipfs = bytes(&e, &[0xe3, 0x01, 0x01, 0x70, 0x12, 0x20]);
+        ipfs.extend_from_array(&[0x42u8; 32]);
+
+        resolver.set_contenthash(&owner, &namehash, &ipfs);
+        let events = e.events().all();
+        let (_, topics, data) = events.get(events.len() - 1).unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "contenthash_changed")
+        );
+        let map = Map::<Symbol, Bytes>::try_from_val(&e, &data).unwrap();
+        assert_eq!(map.get(Symbol::new(&e, "hash")).unwrap(), ipfs);
+        assert_eq!(resolver.contenthash(&namehash), Some(ipfs));
+
+        let arweave = bytes(&e, &[0x90, 0xb2, 0xca, 0x05, 0x01]);
+        resolver.set_contenthash(&owner, &namehash, &arweave);
+        assert_eq!(resolver.contenthash(&namehash), Some(arweave));
+
+        resolver.set_contenthash(&owner, &namehash, &Bytes::new(&e));
+        assert!(resolver.contenthash(&namehash).is_none());
+    }
+
+    #[test]
+    fn contenthash_rejects_unknown_codec_and_non_owner() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 20);
+        let owner = account(&e);
+        let attacker = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        let unknown = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_contenthash(&owner, &namehash, &bytes(&e, &[0x12, 0x20, 0x01]));
+        }));
+        assert!(unknown.is_err());
+        let bare_prefix = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_contenthash(&owner, &namehash, &bytes(&e, &[0xe3, 0x01]));
+        }));
+        assert!(bare_prefix.is_err());
+        let not_owner = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_contenthash(&attacker, &namehash, &bytes(&e, &[0xe5, 0x01, 0x01]));
+        }));
+        assert!(not_owner.is_err());
+        assert!(resolver.contenthash(&namehash).is_none());
+    }
+
+    #[test]
+    fn set_records_writes_addr_and_texts_together() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 21);
+        let owner = Address::generate(&e);
+        let target = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        let mut texts = Vec::new(&e);
+        texts.push_back((bytes(&e, b"url"), bytes(&e, b"https://example.xlm")));
+        texts.push_back((bytes(&e, b"avatar"), bytes(&e, b"ipfs://avatar")));
+        resolver.set_records(&owner, &namehash, &Some(target.clone()), &texts);
+        assert_eq!(e.events().all().len(), 3);
+
+        assert_eq!(resolver.addr(&namehash), Some(target));
+        assert_eq!(
+            resolver.text(&namehash, &bytes(&e, b"url")),
+            Some(bytes(&e, b"https://example.xlm"))
+        );
+        assert_eq!(
+            resolver.text(&namehash, &bytes(&e, b"avatar")),
+            Some(bytes(&e, b"ipfs://avatar"))
+        );
+
+        let mut texts = Vec::new(&e);
+        texts.push_back((bytes(&e, b"url"), bytes(&e, b"https://new.xlm")));
+        resolver.set_records(&owner, &namehash, &None, &texts);
+        assert_eq!(
+            resolver.text(&namehash, &bytes(&e, b"url")),
+            Some(bytes(&e, b"https://new.xlm"))
+        );
+        assert!(resolver.addr(&namehash).is_some());
+    }
+
+    #[test]
+    fn set_records_rejects_invalid_batch_without_writing() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 22);
+        let owner = account(&e);
+        let attacker = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+
+        let mut bad = Vec::new(&e);
+        bad.push_back((bytes(&e, b"url"), bytes(&e, b"https://example.xlm")));
+        bad.push_back((bytes(&e, &[]), bytes(&e, b"value")));
+        let attempt = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_records(&owner, &namehash, &Some(owner.clone()), &bad);
+        }));
+        assert!(attempt.is_err());
+        assert!(resolver.addr(&namehash).is_none());
+        assert!(resolver.text(&namehash, &bytes(&e, b"url")).is_none());
+
+        let mut too_many = Vec::new(&e);
+        for i in 0..=MAX_BATCH_TEXTS {
+            too_many.push_back((bytes(&e, &[b'k', i as u8]), bytes(&e, b"v")));
+        }
+        let oversized = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_records(&owner, &namehash, &None, &too_many);
+        }));
+        assert!(oversized.is_err());
+
+        let not_owner = catch_unwind(AssertUnwindSafe(|| {
+            resolver.set_records(&attacker, &namehash, &Some(attacker.clone()), &Vec::new(&e));
+        }));
+        assert!(not_owner.is_err());
+        assert!(resolver.addr(&namehash).is_none());
+    }
+
+    #[test]
+    fn resolve_follows_registry_resolver() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let other_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let other = ResolverClient::new(&e, &other_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+        other.init(&registry_id);
+
+        let owner = Address::generate(&e);
+        let local = namehash(&e, 23);
+        let remote = namehash(&e, 24);
+        let local_target = Address::generate(&e);
+        let remote_target = Address::generate(&e);
+        registry.set_owner(&local, &owner);
+        registry.set_owner(&remote, &owner);
+        registry.set_resolver(&local, &resolver_id);
+        registry.set_resolver(&remote, &other_id);
+        resolver.set_addr(&owner, &local, &local_target);
+        other.set_addr(&owner, &remote, &remote_target);
+
+        let labels = |byte: u8| {
+            let mut labels = Vec::new(&e);
+            labels.push_back(bytes(&e, b"xlm"));
+            labels.push_back(bytes(&e, &[byte]));
+            labels
+        };
+        assert_eq!(resolver.resolve(&labels(23)), Some(local_target));
+        assert_eq!(resolver.resolve(&labels(24)), Some(remote_target));
+        assert!(resolver.resolve(&labels(25)).is_none());
+
+        registry.release(&remote);
+        assert!(resolver.resolve(&labels(24)).is_none());
+    }
+
+    #[test]
+    fn resolve_returns_none_when_resolver_cannot_answer() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let node = namehash(&e, 26);
+        registry.set_resolver(&node, &registry_id);
+
+        let mut labels = Vec::new(&e);
+        labels.push_back(bytes(&e, &[26]));
+        assert!(resolver.resolve(&labels).is_none());
+    }
+
+    #[test]
+    fn writes_and_bump_extend_record_ttl() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let namehash = namehash(&e, 27);
+        let owner = Address::generate(&e);
+        let admin = Address::generate(&e);
+        let verifier = Address::generate(&e);
+        let manager = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        registry.set_admin(&admin);
+        resolver.set_record_verifier(&admin, &verifier, &true);
+        let key = bytes(&e, b"avatar");
+        let handle = bytes(&e, b"verified:twitter");
+        resolver.set_addr(&owner, &namehash, &owner);
+        resolver.set_text_chunk(&owner, &namehash, &key, &0, &bytes(&e, b"chunk"));
+        resolver.set_addr_by_coin(&owner, &namehash, &60, &bytes(&e, &[0xab; 20]));
+        resolver.approve_manager(&owner, &namehash, &manager, &true);
+        resolver.set_verified_record(&verifier, &namehash, &handle, &Some(bytes(&e, b"@x")));
+        resolver.freeze_records(&owner, &namehash, &86_400);
+        let keys = e.as_contract(&resolver_id, || {
+            [
+                addr_storage_key(&e, &namehash),
+                text_chunk_storage_key(&e, &namehash, &key, 0),
+                text_chunks_storage_key(&e, &namehash, &key),
+                chunked_keys_storage_key(&e, &namehash),
+                coin_types_storage_key(&e, &namehash),
+                coin_addr_storage_key(&e, &namehash, 60),
+                managers_storage_key(&e, &namehash, &owner),
+                manager_storage_key(&e, &namehash, &owner, &manager),
+                verified_keys_storage_key(&e, &namehash, &owner),
+                verified_storage_key(&e, &namehash, &owner, &handle),
+                frozen_storage_key(&e, &namehash),
+            ]
+        });
+        let ttls = || {
+            e.as_contract(&resolver_id, || {
+                keys.clone()
+                    .map(|key| e.storage().persistent().get_ttl(&key))
+            })
+        };
+        assert_eq!(ttls(), [TTL_EXTEND_TO; 11]);
+
+        e.ledger()
+            .with_mut(|li| li.sequence_number += 5 * DAY_IN_LEDGERS);
+        assert_eq!(ttls(), [TTL_EXTEND_TO - 5 * DAY_IN_LEDGERS; 11]);
+        resolver.bump(&namehash);
+        assert_eq!(ttls(), [TTL_EXTEND_TO; 11]);
+    }
+
+    #[test]
+    fn upgrade_and_migrate_require_registry_admin() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        let admin = Address::generate(&e);
+        let outsider = Address::generate(&e);
+        registry.set_admin(&admin);
+        resolver.init(&registry_id);
+
+        let hash = BytesN::from_array(&e, &[7u8; 32]);
+        assert_eq!(
+            resolver.try_upgrade(&outsider, &hash),
+            Err(Ok(ResolverError::NotAdmin.into()))
+        );
+        assert_eq!(
+            resolver.try_migrate(&outsider),
+            Err(Ok(ResolverError::NotAdmin.into()))
+        );
+        assert_eq!(resolver.storage_version(), STORAGE_VERSION);
+        assert_eq!(resolver.migrate(&admin), STORAGE_VERSION);
+
+        // Storage from before versioning reads as 0 and is brought up to date.
+        e.as_contract(&resolver_id, || {
+            e.storage()
+                .persistent()
+                .remove(&Bytes::from_slice(&e, keys::STORAGE_VERSION));
+        });
+        assert_eq!(resolver.storage_version(), 0);
+        assert_eq!(resolver.migrate(&admin), STORAGE_VERSION);
+        let (_, topics, data) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "migrated")
+        );
+        let data = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        assert_eq!(
+            u32::try_from_val(&e, &data.get(Symbol::new(&e, "from")).unwrap()).unwrap(),
+            0
+        );
+        assert_eq!(resolver.storage_version(), STORAGE_VERSION);
+
+        e.as_contract(&resolver_id, || {
+            e.storage().persistent().set(
+                &Bytes::from_slice(&e, keys::STORAGE_VERSION),
+                &(STORAGE_VERSION + 1),
+            );
+        });
+        assert_eq!(
+            resolver.try_migrate(&admin),
+            Err(Ok(ResolverError::UnsupportedVersion.into()))
+        );
+    }
+
+    #[test]
+    fn resolve_wildcard_falls_back_to_nearest_ancestor_resolver() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let other_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let other = ResolverClient::new(&e, &other_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+        other.init(&registry_id);
+
+        // MockRegistry names a node after its last label's first byte.
+        let dao = namehash(&e, b'd');
+        let exact = namehash(&e, b'x');
+        let owner = Address::generate(&e);
+        let catch_all = Address::generate(&e);
+        let direct = Address::generate(&e);
+        registry.set_owner(&dao, &owner);
+        registry.set_owner(&exact, &owner);
+        registry.set_resolver(&dao, &resolver_id);
+        resolver.set_wildcard_addr(&owner, &dao, &Some(catch_all.clone()));
+
+        let name = |parts: &[&[u8]]| {
+            let mut labels = Vec::new(&e);
+            for part in parts {
+                labels.push_back(bytes(&e, part));
+            }
+            labels
+        };
+        let sub = name(&[b"stellar", b"dao", b"alice"]);
+        let deep = name(&[b"stellar", b"dao", b"team", b"bob"]);
+        assert_eq!(resolver.resolve(&sub), None);
+        assert_eq!(resolver.resolve_wildcard(&sub), Some(catch_all.clone()));
+        assert_eq!(resolver.resolve_wildcard(&deep), Some(catch_all.clone()));
+
+        // A name with its own resolver never falls back.
+        registry.set_resolver(&exact, &resolver_id);
+        let exact_name = name(&[b"stellar", b"dao", b"xavier"]);
+        assert_eq!(resolver.resolve_wildcard(&exact_name), None);
+        resolver.set_addr(&owner, &exact, &direct);
+        assert_eq!(resolver.resolve_wildcard(&exact_name), Some(direct));
+
+        // Ancestors served by another resolver are queried through its handler.
+        registry.set_resolver(&dao, &other_id);
+        assert_eq!(resolver.resolve_wildcard(&sub), None);
+        other.set_wildcard_addr(&owner, &dao, &Some(owner.clone()));
+        assert_eq!(resolver.resolve_wildcard(&sub), Some(owner.clone()));
+
+        other.set_wildcard_addr(&owner, &dao, &None);
+        assert_eq!(resolver.resolve_wildcard(&sub), None);
+        assert_eq!(resolver.resolve_wildcard(&name(&[b"nowhere"])), None);
+    }
+
+    #[test]
+    fn supports_reports_served_record_types() {
+        let e = Env::default();
+        let resolver_id = e.register(Resolver, ());
+        let client = ResolverClient::new(&e, &resolver_id);
+        for name in SUPPORTED_RECORDS {
+            assert!(client.supports(&Symbol::new(&e, name)));
+        }
+        assert!(!client.supports(&Symbol::new(&e, "abi")));
+        assert!(resolver_interface::supports(
+            &e,
+            &resolver_id,
+            resolver_interface::RECORD_ADDR
+        ));
+    }
+
+    #[test]
+    fn supports_interface_reports_record_interfaces() {
+        let e = Env::default();
+        let resolver_id = e.register(Resolver, ());
+        let client = ResolverClient::new(&e, &resolver_id);
+        for name in SUPPORTED_INTERFACES {
+            assert!(resolver_interface::supports_interface(
+                &e,
+                &resolver_id,
+                name
+            ));
+        }
+        assert!(
+            !client.supports_interface(&Symbol::new(&e, resolver_interface::INTERFACE_REGISTRY))
+        );
+        // An account has no entrypoints and supports nothing.
+        let account = Address::generate(&e);
+        assert!(!resolver_interface::supports_interface(
+            &e,
+            &account,
+            resolver_interface::INTERFACE_DETECTION
+        ));
+    }
+
+    #[test]
+    fn lessee_can_write_records_while_lease_runs() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let owner = account(&e);
+        let lessee = Address::generate(&e);
+        let node = namehash(&e, 41);
+        registry.set_owner(&node, &owner);
+        let key = bytes(&e, b"url");
+
+        assert_eq!(
+            resolver.try_set_text(&lessee, &node, &key, &bytes(&e, b"https://a")),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        registry.set_lessee(&node, &Some(lessee.clone()));
+        resolver.set_text(&lessee, &node, &key, &bytes(&e, b"https://a"));
+        resolver.set_addr(&lessee, &node, &lessee);
+        assert_eq!(resolver.text(&node, &key), Some(bytes(&e, b"https://a")));
+        assert_eq!(resolver.addr(&node), Some(lessee.clone()));
+
+        registry.set_lessee(&node, &None);
+        assert_eq!(
+            resolver.try_set_addr(&lessee, &node, &lessee),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        resolver.set_addr(&owner, &node, &owner);
+    }
+
+    #[test]
+    fn text_keys_and_texts_enumerate_current_records() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let owner = Address::generate(&e);
+        let node = namehash(&e, 42);
+        registry.set_owner(&node, &owner);
+        let (url, avatar, email) = (bytes(&e, b"url"), bytes(&e, b"avatar"), bytes(&e, b"email"));
+        assert_eq!(resolver.text_keys(&node), Vec::new(&e));
+
+        resolver.set_text(&owner, &node, &url, &bytes(&e, b"https://a"));
+        resolver.set_records(
+            &owner,
+            &node,
+            &None,
+            &soroban_sdk::vec![
+                &e,
+                (avatar.clone(), bytes(&e, b"ipfs://x")),
+                (url.clone(), bytes(&e, b"https://b")),
+            ],
+        );
+        resolver.set_text(&owner, &node, &email, &bytes(&e, b"a@b.c"));
+        resolver.clear_text(&owner, &node, &avatar);
+        assert_eq!(
+            resolver.text_keys(&node),
+            soroban_sdk::vec![&e, url.clone(), email.clone()]
+        );
+        let texts = resolver.texts(&node);
+        assert_eq!(texts.len(), 2);
+        assert_eq!(texts.get(url), Some(bytes(&e, b"https://b")));
+        assert_eq!(texts.get(email), Some(bytes(&e, b"a@b.c")));
+
+        // A new record generation starts with an empty index.
+        registry.bump_record_version(&node);
+        assert_eq!(resolver.text_keys(&node), Vec::new(&e));
+        assert_eq!(resolver.texts(&node).len(), 0);
+    }
+
+    #[test]
+    fn typed_profile_setters_validate_and_share_the_text_store() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let owner = Address::generate(&e);
+        let node = namehash(&e, 43);
+        registry.set_owner(&node, &owner);
+        let invalid = Err(Ok(ResolverError::InvalidInput.into()));
+
+        resolver.set_avatar(&owner, &node, &bytes(&e, b"ipfs://bafy"));
+        resolver.set_url(&owner, &node, &bytes(&e, b"https://alice.dev"));
+        resolver.set_email(&owner, &node, &bytes(&e, b"alice@example.com"));
+        resolver.set_social(&owner, &node, &bytes(&e, b"github"), &bytes(&e, b"alice"));
+        let (_, topics, _) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "social_changed")
+        );
+        assert_eq!(resolver.avatar(&node), Some(bytes(&e, b"ipfs://bafy")));
+        assert_eq!(resolver.url(&node), Some(bytes(&e, b"https://alice.dev")));
+        assert_eq!(resolver.email(&node), Some(bytes(&e, b"alice@example.com")));
+        assert_eq!(
+            resolver.social(&node, &bytes(&e, b"github")),
+            Some(bytes(&e, b"alice"))
+        );
+        assert_eq!(
+            resolver.text(&node, &bytes(&e, b"social.github")),
+            Some(bytes(&e, b"alice"))
+        );
+        assert_eq!(resolver.text_keys(&node).len(), 4);
+
+        assert_eq!(
+            resolver.try_set_avatar(&owner, &node, &bytes(&e, b"ftp://x")),
+            invalid
+        );
+        assert_eq!(
+            resolver.try_set_url(&owner, &node, &bytes(&e, b"https://")),
+            invalid
+        );
+        for email in [
+            &b"alice"[..],
+            b"@example.com",
+            b"a@b",
+            b"a@b@c.d",
+            b"a b@c.d",
+        ] {
+            assert_eq!(
+                resolver.try_set_email(&owner, &node, &bytes(&e, email)),
+                invalid
+            );
+        }
+        assert_eq!(
+            resolver.try_set_social(&owner, &node, &bytes(&e, b"GitHub"), &bytes(&e, b"a")),
+            invalid
+        );
+        assert_eq!(
+            resolver.try_set_social(&owner, &node, &bytes(&e, b"x"), &bytes(&e, b"")),
+            invalid
+        );
+        assert_eq!(resolver.email(&node), Some(bytes(&e, b"alice@example.com")));
+    }
+
+    #[test]
+    fn approved_manager_writes_records_until_revoked_or_transfer() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let owner = account(&e);
+        let manager = Address::generate(&e);
+        let buyer = account(&e);
+        let node = namehash(&e, 44);
+        registry.set_owner(&node, &owner);
+        let key = bytes(&e, b"description");
+        let not_owner = Err(Ok(ResolverError::NotOwner.into()));
+
+        assert_eq!(
+            resolver.try_approve_manager(&manager, &node, &manager, &true),
+            not_owner
+        );
+        resolver.approve_manager(&owner, &node, &manager, &true);
+        assert!(resolver.is_manager(&node, &manager));
+        resolver.set_text(&manager, &node, &key, &bytes(&e, b"managed"));
+        resolver.set_addr(&manager, &node, &owner);
+        assert_eq!(resolver.text(&node, &key), Some(bytes(&e, b"managed")));
+
+        resolver.approve_manager(&owner, &node, &manager, &false);
+        assert!(!resolver.is_manager(&node, &manager));
+        assert_eq!(resolver.try_clear_text(&manager, &node, &key), not_owner);
+
+        // Approvals belong to the owner that granted them.
+        resolver.approve_manager(&owner, &node, &manager, &true);
+        registry.set_owner(&node, &buyer);
+        assert!(!resolver.is_manager(&node, &manager));
+        assert_eq!(resolver.try_set_addr(&manager, &node, &manager), not_owner);
+    }
+
+    #[test]
+    fn owner_cache_skips_registry_until_the_owner_nonce_moves() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let admin = Address::generate(&e);
+        let owner = account(&e);
+        let node = namehash(&e, 45);
+        registry.set_admin(&admin);
+        registry.set_owner(&node, &owner);
+        let key = bytes(&e, b"url");
+
+        resolver.set_text(&owner, &node, &key, &bytes(&e, b"https://a"));
+        resolver.set_text(&owner, &node, &key, &bytes(&e, b"https://b"));
+        assert_eq!(registry.owner_calls(), 2);
+
+        assert_eq!(
+            resolver.try_set_owner_cache(&owner, &true),
+            Err(Ok(ResolverError::NotAdmin.into()))
+        );
+        resolver.set_owner_cache(&admin, &true);
+        assert!(resolver.owner_cache());
+        resolver.set_text(&owner, &node, &key, &bytes(&e, b"https://c"));
+        resolver.set_addr(&owner, &node, &owner);
+        resolver.clear_text(&owner, &node, &key);
+        assert_eq!(registry.owner_calls(), 3);
+        assert_eq!(resolver.addr(&node), Some(owner.clone()));
+        // The cache is not tied to the ledger.
+        e.ledger().with_mut(|ledger| ledger.sequence_number += 1);
+        resolver.set_addr(&owner, &node, &owner);
+        assert_eq!(registry.owner_calls(), 3);
+
+        // A transfer moves the nonce, so it reaches the very next write.
+        let buyer = account(&e);
+        registry.set_owner(&node, &buyer);
+        assert_eq!(
+            resolver.try_set_addr(&owner, &node, &owner),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        resolver.set_addr(&buyer, &node, &buyer);
+        // Calls made by a reverted write are not counted.
+        assert_eq!(registry.owner_calls(), 4);
+
+        // So does a new expiry, and a cached expiry is checked against the current time.
+        registry.set_expires(&node, &(e.ledger().timestamp() + 10));
+        resolver.set_addr(&buyer, &node, &buyer);
+        assert_eq!(registry.owner_calls(), 5);
+        e.ledger().with_mut(|ledger| ledger.timestamp += 11);
+        assert_eq!(
+            resolver.try_set_addr(&buyer, &node, &buyer),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+
+        resolver.set_owner_cache(&admin, &false);
+        registry.set_expires(&node, &(e.ledger().timestamp() + 10));
+        resolver.set_addr(&buyer, &node, &buyer);
+        resolver.set_addr(&buyer, &node, &buyer);
+        assert_eq!(registry.owner_calls(), 7);
+    }
+
+    #[test]
+    fn owner_cache_is_per_name_and_still_checks_every_caller() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let admin = Address::generate(&e);
+        let owner = account(&e);
+        let stranger = account(&e);
+        let (first, second) = (namehash(&e, 50), namehash(&e, 51));
+        registry.set_admin(&admin);
+        resolver.set_owner_cache(&admin, &true);
+        assert_eq!(e.auths().len(), 1);
+        assert_eq!(e.auths()[0].0, admin);
+        let key = bytes(&e, b"url");
+
+        // A missing owner is not cached, so the name is writable once registered.
+        assert_eq!(
+            resolver.try_set_addr(&owner, &first, &owner),
+            Err(Ok(ResolverError::NameNotRegistered.into()))
+        );
+        registry.set_owner(&first, &owner);
+        registry.set_owner(&second, &owner);
+        resolver.set_addr(&owner, &first, &owner);
+        assert_eq!(registry.owner_calls(), 1);
 
-    #[contract]
-    pub struct MockRegistry;
+        // A cached owner still rejects other callers, and each name has its own entry.
+        assert_eq!(
+            resolver.try_set_text(&stranger, &first, &key, &bytes(&e, b"x")),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        resolver.set_addr(&owner, &second, &owner);
+        assert_eq!(registry.owner_calls(), 2);
+        resolver.set_text(&owner, &first, &key, &bytes(&e, b"x"));
+        resolver.set_text(&owner, &second, &key, &bytes(&e, b"x"));
+        assert_eq!(registry.owner_calls(), 2);
 
-    #[derive(Clone)]
-    #[contracttype]
-    enum MockRegistryKey {
-        Owner(BytesN<32>),
+        // A re-registration moves the nonce too.
+        registry.bump_record_version(&first);
+        resolver.set_addr(&owner, &first, &owner);
+        assert_eq!(registry.owner_calls(), 3);
     }
 
-    #[contractimpl]
-    impl MockRegistry {
-        pub fn owner(env: Env, namehash: BytesN<32>) -> Address {
-            env.storage()
-                .persistent()
-                .get(&MockRegistryKey::Owner(namehash))
-                .unwrap_or_else(|| panic!("mock registry owner not set"))
-        }
+    #[test]
+    fn records_at_serves_each_revision() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
 
-        pub fn set_owner(env: Env, namehash: BytesN<32>, owner: Address) {
-            env.storage()
-                .persistent()
-                .set(&MockRegistryKey::Owner(namehash), &owner);
-        }
-    }
+        let namehash = namehash(&e, 40);
+        let owner = Address::generate(&e);
+        let addr = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        assert_eq!(resolver.record_revision(&namehash), 0);
+        assert_eq!(resolver.records_at(&namehash, &0), None);
 
-    fn namehash(env: &Env, byte: u8) -> BytesN<32> {
-        BytesN::from_array(env, &[byte; 32])
-    }
+        resolver.set_addr(&owner, &namehash, &addr);
+        let key = bytes(&e, b"note");
+        let texts = soroban_sdk::vec![
+            &e,
+            (key.clone(), bytes(&e, b"v1")),
+            (bytes(&e, b"other"), bytes(&e, b"x")),
+        ];
+        resolver.set_records(&owner, &namehash, &None, &texts);
+        resolver.set_text(&owner, &namehash, &key, &bytes(&e, b"v2"));
+        assert_eq!(resolver.record_revision(&namehash), 3);
 
-    fn bytes(env: &Env, data: &[u8]) -> Bytes {
-        Bytes::from_slice(env, data)
+        let first = resolver.records_at(&namehash, &1).unwrap();
+        assert_eq!(first.revision, 1);
+        assert_eq!(first.addr, Some(addr.clone()));
+        assert!(first.texts.is_empty());
+        let second = resolver.records_at(&namehash, &2).unwrap();
+        assert_eq!(second.texts.get(key.clone()), Some(bytes(&e, b"v1")));
+        assert_eq!(second.texts.len(), 2);
+        let third = resolver.records_at(&namehash, &3).unwrap();
+        assert_eq!(third.texts.get(key.clone()), Some(bytes(&e, b"v2")));
+        assert_eq!(third.addr, Some(addr));
+        assert_eq!(third.contenthash, None);
+        assert_eq!(resolver.records_at(&namehash, &4), None);
+
+        // A re-registration retires earlier revisions; the counter keeps counting.
+        registry.bump_record_version(&namehash);
+        assert_eq!(resolver.records_at(&namehash, &3), None);
+        resolver.clear_addr(&owner, &namehash);
+        assert_eq!(resolver.record_revision(&namehash), 4);
+        let fresh = resolver.records_at(&namehash, &4).unwrap();
+        assert_eq!(fresh.addr, None);
+        assert!(fresh.texts.is_empty());
     }
 
     #[test]
-    fn it_compiles_and_returns_version() {
+    fn snapshots_track_content_hashes_per_name_and_skip_rejected_writes() {
         let e = Env::default();
+        e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
-        let client = ResolverClient::new(&e, &resolver_id);
-        assert_eq!(client.version(), 1);
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+
+        let (first, second) = (namehash(&e, 42), namehash(&e, 43));
+        let owner = account(&e);
+        let stranger = account(&e);
+        registry.set_owner(&first, &owner);
+        registry.set_owner(&second, &owner);
+        let hash = bytes(&e, &[0xe3, 0x01, 0xaa]);
+
+        resolver.set_contenthash(&owner, &first, &hash);
+        resolver.set_contenthash(&owner, &first, &Bytes::new(&e));
+        assert_eq!(
+            resolver.records_at(&first, &1).unwrap().contenthash,
+            Some(hash)
+        );
+        assert_eq!(resolver.records_at(&first, &2).unwrap().contenthash, None);
+
+        // Rejected writes and other names leave the revision alone.
+        assert_eq!(
+            resolver.try_set_addr(&stranger, &first, &stranger),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        assert_eq!(
+            resolver.try_set_contenthash(&owner, &first, &bytes(&e, &[0xe3, 0x01])),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        resolver.set_addr(&owner, &second, &owner);
+        assert_eq!(resolver.record_revision(&first), 2);
+        assert_eq!(resolver.record_revision(&second), 1);
+        assert_eq!(resolver.records_at(&second, &2), None);
     }
 
     #[test]
-    fn init_once() {
+    fn only_the_latest_snapshots_are_kept_and_bumped() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
-
+        let registry = MockRegistryClient::new(&e, &registry_id);
         resolver.init(&registry_id);
 
-        let second_call = catch_unwind(AssertUnwindSafe(|| resolver.init(&registry_id)));
-        assert!(second_call.is_err());
-        assert_eq!(resolver.registry(), registry_id);
+        let namehash = namehash(&e, 41);
+        let owner = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        let key = bytes(&e, b"note");
+        for n in 0..SNAPSHOTS_KEPT + 2 {
+            resolver.set_text(&owner, &namehash, &key, &bytes(&e, &[n as u8]));
+        }
+        let latest = SNAPSHOTS_KEPT + 2;
+        assert_eq!(resolver.record_revision(&namehash), latest);
+        assert_eq!(resolver.records_at(&namehash, &1), None);
+        assert_eq!(resolver.records_at(&namehash, &2), None);
+        let oldest = resolver.records_at(&namehash, &3).unwrap();
+        assert_eq!(oldest.texts.get(key.clone()), Some(bytes(&e, &[2])));
+        assert!(resolver.records_at(&namehash, &latest).is_some());
+
+        let snapshot_ttl = |revision: u32| {
+            e.as_contract(&resolver_id, || {
+                let key = snapshot_storage_key(&e, &namehash, revision);
+                e.storage().persistent().get_ttl(&key)
+            })
+        };
+        e.ledger()
+            .with_mut(|li| li.sequence_number += 5 * DAY_IN_LEDGERS);
+        resolver.bump(&namehash);
+        for revision in 3..=latest {
+            assert_eq!(snapshot_ttl(revision), TTL_EXTEND_TO);
+        }
     }
 
     #[test]
-    fn unset_reads_return_none() {
+    fn muxed_record_routes_deposits() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
-
+        let registry = MockRegistryClient::new(&e, &registry_id);
         resolver.init(&registry_id);
 
-        let namehash = namehash(&e, 1);
-        let key = bytes(&e, b"profile");
+        let namehash = namehash(&e, 41);
+        let owner = account(&e);
+        let stranger = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        assert_eq!(resolver.resolve_to_muxed(&namehash), None);
 
-        assert!(resolver.addr(&namehash).is_none());
-        assert!(resolver.text(&namehash, &key).is_none());
+        // Without a muxed record the `addr` record is the destination.
+        resolver.set_addr(&owner, &namehash, &owner);
+        assert_eq!(
+            resolver.resolve_to_muxed(&namehash),
+            Some((owner.clone(), None))
+        );
+
+        let exchange = Address::from_str(
+            &e,
+            "GADQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOBYHA4DQOZPI",
+        );
+        resolver.set_muxed(&owner, &namehash, &exchange, &Some(u64::MAX));
+        let (_, topics, data) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "muxed_changed")
+        );
+        let data = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        let id = Option::<u64>::try_from_val(&e, &data.get(Symbol::new(&e, "id")).unwrap());
+        assert_eq!(id.unwrap(), Some(u64::MAX));
+        assert_eq!(
+            resolver.resolve_to_muxed(&namehash),
+            Some((exchange.clone(), Some(u64::MAX)))
+        );
+
+        // Contracts cannot be muxed, and only the owner may write.
+        assert_eq!(
+            resolver.try_set_muxed(&owner, &namehash, &resolver_id, &Some(1)),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        resolver.set_muxed(&owner, &namehash, &resolver_id, &None);
+        assert_eq!(
+            resolver.try_set_muxed(&stranger, &namehash, &exchange, &Some(7)),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+
+        resolver.clear_muxed(&owner, &namehash);
+        assert_eq!(resolver.resolve_to_muxed(&namehash), Some((owner, None)));
     }
 
     #[test]
-    fn set_addr_happy_path() {
+    fn verified_records_follow_verifier_and_owner() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
         let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+        let admin = Address::generate(&e);
+        registry.set_admin(&admin);
 
-        let namehash = namehash(&e, 2);
+        let namehash = namehash(&e, 42);
         let owner = Address::generate(&e);
-        let addr = Address::generate(&e);
+        let verifier = Address::generate(&e);
+        registry.set_owner(&namehash, &owner);
+        let key = bytes(&e, b"verified:twitter");
+        let handle = Some(bytes(&e, b"@alice"));
+
+        // Neither the owner nor an unapproved address can attest.
+        assert_eq!(
+            resolver.try_set_verified_record(&owner, &namehash, &key, &handle),
+            Err(Ok(ResolverError::NotVerifier.into()))
+        );
+        assert_eq!(
+            resolver.try_set_record_verifier(&owner, &verifier, &true),
+            Err(Ok(ResolverError::NotAdmin.into()))
+        );
+        resolver.set_record_verifier(&admin, &verifier, &true);
+        assert!(resolver.is_record_verifier(&verifier));
+
+        resolver.set_verified_record(&verifier, &namehash, &key, &handle);
+        let (_, topics, _) = e.events().all().last().unwrap();
+        assert_eq!(
+            Symbol::try_from_val(&e, &topics.get(0).unwrap()).unwrap(),
+            Symbol::new(&e, "verified_record_changed")
+        );
+        let attested = Some((verifier.clone(), handle.clone().unwrap()));
+        assert_eq!(resolver.verified_record(&namehash, &key), attested);
+        // The plain text store is separate, so the owner cannot overwrite the attestation.
+        resolver.set_text(&owner, &namehash, &key, &bytes(&e, b"@mallory"));
+        assert_eq!(resolver.verified_record(&namehash, &key), attested);
+
+        // Revoking the verifier hides its records; re-approving restores them.
+        resolver.set_record_verifier(&admin, &verifier, &false);
+        assert_eq!(resolver.verified_record(&namehash, &key), None);
+        resolver.set_record_verifier(&admin, &verifier, &true);
+        assert_eq!(resolver.verified_record(&namehash, &key), attested);
+
+        // After a transfer the badge no longer resolves; it was attested for the seller.
+        let buyer = Address::generate(&e);
+        registry.set_owner(&namehash, &buyer);
+        assert_eq!(resolver.verified_record(&namehash, &key), None);
         registry.set_owner(&namehash, &owner);
+        assert_eq!(resolver.verified_record(&namehash, &key), attested);
+        resolver.set_verified_record(&verifier, &namehash, &key, &None);
+        assert_eq!(resolver.verified_record(&namehash, &key), None);
+    }
 
+    #[test]
+    fn contract_owner_authorizes_other_callers() {
+        let e = Env::default();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
         resolver.init(&registry_id);
-        resolver.set_addr(&owner, &namehash, &addr);
 
-        let events = e.events().all();
-        assert_eq!(resolver.addr(&namehash), Some(addr.clone()));
-        assert_eq!(events.len(), 1, "expected address event");
-        let (event_contract, topics, data) = events.get(0).unwrap();
-        assert_eq!(event_contract, resolver_id);
+        let namehash = namehash(&e, 43);
+        let wallet = Address::generate(&e);
+        let session_key = Address::generate(&e);
+        let addr = Address::generate(&e);
+        registry.set_owner(&namehash, &wallet);
 
-        let topic_symbol =
-            Symbol::try_from_val(&e, &topics.get(0).expect("topic symbol missing")).unwrap();
-        assert_eq!(topic_symbol, Symbol::new(&e, "address_changed"));
+        // The session key's signature alone is not enough...
+        let session_only = resolver
+            .mock_auths(&[MockAuth {
+                address: &session_key,
+                invoke: &MockAuthInvoke {
+                    contract: &resolver_id,
+                    fn_name: "set_addr",
+                    args: (&session_key, &namehash, &addr).into_val(&e),
+                    sub_invokes: &[],
+                },
+            }])
+            .try_set_addr(&session_key, &namehash, &addr);
+        assert!(session_only.is_err());
+        assert_eq!(resolver.addr(&namehash), None);
 
-        let topic_namehash =
-            BytesN::<32>::try_from_val(&e, &topics.get(1).expect("topic namehash missing"))
-                .unwrap();
-        assert_eq!(topic_namehash, namehash);
+        // ...but once the wallet authorizes the call too, its policy lets the key act for it.
+        e.mock_all_auths();
+        resolver.set_addr(&session_key, &namehash, &addr);
+        let wallet_auth = e.auths().into_iter().any(|(address, _)| address == wallet);
+        assert!(wallet_auth);
+        assert_eq!(resolver.addr(&namehash), Some(addr.clone()));
 
-        let data_map =
-            Map::<Symbol, Address>::try_from_val(&e, &data).expect("unable to decode event data");
-        let recorded_addr = data_map
-            .get(Symbol::new(&e, "addr"))
-            .expect("addr missing in event");
-        assert_eq!(recorded_addr, addr);
+        // Classic-account owners get no such fallback.
+        let account_owner = account(&e);
+        registry.set_owner(&namehash, &account_owner);
+        assert_eq!(
+            resolver.try_set_addr(&session_key, &namehash, &addr),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
     }
 
     #[test]
-    fn set_addr_rejects_non_owner() {
+    fn frozen_records_reject_every_writer_until_the_freeze_ends() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
         let registry = MockRegistryClient::new(&e, &registry_id);
-
         resolver.init(&registry_id);
+        e.ledger().set_timestamp(1_000);
 
-        let namehash = namehash(&e, 3);
-        let owner = Address::generate(&e);
-        let stranger = Address::generate(&e);
+        let namehash = namehash(&e, 44);
+        let owner = account(&e);
+        let manager = account(&e);
         let addr = Address::generate(&e);
-
         registry.set_owner(&namehash, &owner);
+        resolver.approve_manager(&owner, &namehash, &manager, &true);
+        assert_eq!(
+            resolver.try_freeze_records(&manager, &namehash, &2_000),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        resolver.freeze_records(&owner, &namehash, &2_000);
+        assert_eq!(resolver.frozen_until(&namehash), Some(2_000));
 
-        let attempt = catch_unwind(AssertUnwindSafe(|| {
-            resolver.set_addr(&stranger, &namehash, &addr)
-        }));
-        assert!(attempt.is_err());
-        assert!(resolver.addr(&namehash).is_none());
+        for caller in [&owner, &manager] {
+            assert_eq!(
+                resolver.try_set_addr(caller, &namehash, &addr),
+                Err(Ok(ResolverError::RecordsFrozen.into()))
+            );
+        }
+        assert_eq!(
+            resolver.try_set_text(&owner, &namehash, &bytes(&e, b"url"), &bytes(&e, b"x")),
+            Err(Ok(ResolverError::RecordsFrozen.into()))
+        );
+        // Extending is allowed; shortening or an open-ended freeze is not.
+        for until in [1_500, 1_000 + MAX_FREEZE_SECS + 1] {
+            assert_eq!(
+                resolver.try_freeze_records(&owner, &namehash, &until),
+                Err(Ok(ResolverError::InvalidInput.into()))
+            );
+        }
+        resolver.freeze_records(&owner, &namehash, &3_000);
+
+        e.ledger().set_timestamp(3_000);
+        assert_eq!(resolver.frozen_until(&namehash), None);
+        resolver.set_addr(&owner, &namehash, &addr);
+        assert_eq!(resolver.addr(&namehash), Some(addr));
     }
 
     #[test]
-    fn set_text_happy_path() {
+    fn challenge_signed_by_the_resolved_account_verifies_once() {
+        use ed25519_dalek::{Signer, SigningKey};
+
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
         let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+        e.ledger().set_timestamp(1_000);
 
-        let namehash = namehash(&e, 4);
-        let owner = Address::generate(&e);
-        registry.set_owner(&namehash, &owner);
+        let signer = SigningKey::from_bytes(&[7; 32]);
+        let key =
+            xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256(signer.verifying_key().to_bytes()));
+        let wallet =
+            Address::try_from_val(&e, &xdr::ScAddress::Account(xdr::AccountId(key))).unwrap();
+        let namehash = namehash(&e, 45);
+        registry.set_owner(&namehash, &wallet);
+        resolver.set_addr(&wallet, &namehash, &wallet);
+        let dapp = Address::generate(&e);
 
-        let key = bytes(&e, b"profile");
-        let value = bytes(&e, b"ipfs://hash");
+        let sign = |challenge: &BytesN<32>| {
+            BytesN::from_array(&e, &signer.sign(&challenge.to_array()).to_bytes())
+        };
+        let challenge = resolver.issue_challenge(&dapp, &namehash);
+        assert!(resolver
+            .try_verify_response(&dapp, &namehash, &BytesN::from_array(&e, &[0; 64]))
+            .is_err());
+        assert_eq!(
+            resolver.verify_response(&dapp, &namehash, &sign(&challenge)),
+            wallet
+        );
+        assert_eq!(
+            resolver.try_verify_response(&dapp, &namehash, &sign(&challenge)),
+            Err(Ok(ResolverError::ChallengeMissing.into()))
+        );
+
+        // Another requester neither sees nor replaces the dApp's challenge.
+        let challenge = resolver.issue_challenge(&dapp, &namehash);
+        let other = Address::generate(&e);
+        assert_eq!(
+            resolver.try_verify_response(&other, &namehash, &sign(&challenge)),
+            Err(Ok(ResolverError::ChallengeMissing.into()))
+        );
+        resolver.issue_challenge(&other, &namehash);
+        assert_eq!(
+            resolver.verify_response(&dapp, &namehash, &sign(&challenge)),
+            wallet
+        );
+        e.set_auths(&[]);
+        assert!(resolver.try_issue_challenge(&other, &namehash).is_err());
+        e.mock_all_auths();
 
+        // Stale challenges and contract addresses cannot sign in.
+        let challenge = resolver.issue_challenge(&dapp, &namehash);
+        e.ledger().set_timestamp(1_000 + CHALLENGE_TTL_SECS + 1);
+        assert_eq!(
+            resolver.try_verify_response(&dapp, &namehash, &sign(&challenge)),
+            Err(Ok(ResolverError::ChallengeMissing.into()))
+        );
+        resolver.set_addr(&wallet, &namehash, &Address::generate(&e));
+        let challenge = resolver.issue_challenge(&dapp, &namehash);
+        assert_eq!(
+            resolver.try_verify_response(&dapp, &namehash, &sign(&challenge)),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+    }
+
+    #[test]
+    fn challenges_follow_the_current_addr_and_the_latest_issue() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
         resolver.init(&registry_id);
-        resolver.set_text(&owner, &namehash, &key, &value);
+        e.ledger().set_timestamp(1_000);
 
-        let events = e.events().all();
-        assert_eq!(resolver.text(&namehash, &key), Some(value.clone()));
-        assert_eq!(events.len(), 1, "expected text event");
-        let (event_contract, topics, data) = events.get(0).unwrap();
-        assert_eq!(event_contract, resolver_id);
+        let wallet_of = |signer: &SigningKey| {
+            let key = xdr::PublicKey::PublicKeyTypeEd25519(xdr::Uint256(
+                signer.verifying_key().to_bytes(),
+            ));
+            Address::try_from_val(&e, &xdr::ScAddress::Account(xdr::AccountId(key))).unwrap()
+        };
+        let (old_key, new_key) = (
+            SigningKey::from_bytes(&[8; 32]),
+            SigningKey::from_bytes(&[9; 32]),
+        );
+        let (old_wallet, new_wallet) = (wallet_of(&old_key), wallet_of(&new_key));
+        let sign = |signer: &SigningKey, challenge: &BytesN<32>| {
+            BytesN::from_array(&e, &signer.sign(&challenge.to_array()).to_bytes())
+        };
+        let namehash = namehash(&e, 46);
+        registry.set_owner(&namehash, &old_wallet);
+        let dapp = Address::generate(&e);
 
-        let topic_symbol =
-            Symbol::try_from_val(&e, &topics.get(0).expect("topic symbol missing")).unwrap();
-        assert_eq!(topic_symbol, Symbol::new(&e, "text_changed"));
+        // A name without an `addr` record cannot sign in.
+        let challenge = resolver.issue_challenge(&dapp, &namehash);
+        assert_eq!(
+            resolver.try_verify_response(&dapp, &namehash, &sign(&old_key, &challenge)),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
 
-        let topic_namehash =
-            BytesN::<32>::try_from_val(&e, &topics.get(1).expect("topic namehash missing"))
-                .unwrap();
-        assert_eq!(topic_namehash, namehash);
+        // A new challenge replaces the outstanding one.
+        resolver.set_addr(&old_wallet, &namehash, &old_wallet);
+        let stale = resolver.issue_challenge(&dapp, &namehash);
+        let challenge = resolver.issue_challenge(&dapp, &namehash);
+        assert_ne!(stale, challenge);
+        assert!(resolver
+            .try_verify_response(&dapp, &namehash, &sign(&old_key, &stale))
+            .is_err());
 
-        let data_map = Map::<Symbol, Bytes>::try_from_val(&e, &data)
-            .expect("unable to decode text event data");
-        let recorded_key = data_map
-            .get(Symbol::new(&e, "key"))
-            .expect("key missing in event");
-        assert_eq!(recorded_key, key);
+        // The signer is whoever the name resolves to now, up to the last second of the TTL.
+        resolver.set_addr(&old_wallet, &namehash, &new_wallet);
+        assert!(resolver
+            .try_verify_response(&dapp, &namehash, &sign(&old_key, &challenge))
+            .is_err());
+        e.ledger().set_timestamp(1_000 + CHALLENGE_TTL_SECS);
+        e.set_auths(&[]);
+        assert_eq!(
+            resolver.verify_response(&dapp, &namehash, &sign(&new_key, &challenge)),
+            new_wallet
+        );
     }
 
     #[test]
-    fn set_text_invalid_key_rejected() {
+    fn writes_to_unregistered_or_expired_names_fail_with_typed_errors() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
         let registry = MockRegistryClient::new(&e, &registry_id);
-
         resolver.init(&registry_id);
+        e.ledger().set_timestamp(1_000);
 
-        let namehash = namehash(&e, 5);
         let owner = Address::generate(&e);
+        let namehash = namehash(&e, 46);
+        assert_eq!(
+            resolver.try_set_addr(&owner, &namehash, &owner),
+            Err(Ok(ResolverError::NameNotRegistered.into()))
+        );
+
         registry.set_owner(&namehash, &owner);
+        registry.set_expires(&namehash, &2_000);
+        resolver.set_addr(&owner, &namehash, &owner);
 
-        let empty_key = bytes(&e, &[]);
-        let value = bytes(&e, b"data");
-        let long_vec = {
-            let mut buffer = std::vec::Vec::new();
-            buffer.resize((MAX_TEXT_KEY_LEN + 1) as usize, 7u8);
-            buffer
+        // Expired but still in grace: the owner is known but no longer authorized.
+        e.ledger().set_timestamp(2_001);
+        assert_eq!(
+            resolver.try_set_addr(&owner, &namehash, &owner),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        registry.release(&namehash);
+        assert_eq!(
+            resolver.try_set_addr(&owner, &namehash, &owner),
+            Err(Ok(ResolverError::NameNotRegistered.into()))
+        );
+    }
+
+    #[test]
+    fn text_limits_cap_values_and_chunks_share_the_name_budget() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+        let admin = Address::generate(&e);
+        registry.set_admin(&admin);
+
+        let owner = Address::generate(&e);
+        let node = namehash(&e, 47);
+        registry.set_owner(&node, &owner);
+        let limits = TextLimits {
+            max_value_len: 4,
+            max_name_bytes: 11,
         };
-        let too_long_key = Bytes::from_slice(&e, &long_vec);
+        resolver.set_text_limits(&admin, &limits);
+        assert_eq!(resolver.text_limits(), limits);
 
-        let empty_attempt = catch_unwind(AssertUnwindSafe(|| {
-            resolver.set_text(&owner, &namehash, &empty_key, &value)
-        }));
-        assert!(empty_attempt.is_err());
+        let (bio, json) = (bytes(&e, b"bio"), bytes(&e, b"profile"));
+        assert_eq!(
+            resolver.try_set_text(&owner, &node, &bio, &bytes(&e, b"12345")),
+            Err(Ok(ResolverError::ValueTooLarge.into()))
+        );
+        resolver.set_text(&owner, &node, &bio, &bytes(&e, b"1234"));
+        resolver.set_text_chunk(&owner, &node, &json, &0, &bytes(&e, b"{\"a\""));
+        resolver.set_text_chunk(&owner, &node, &json, &1, &bytes(&e, b":1}"));
+        assert_eq!(resolver.text_chunk_count(&node, &json), 2);
+        assert_eq!(
+            resolver.text_chunk(&node, &json, &1),
+            Some(bytes(&e, b":1}"))
+        );
+        assert_eq!(resolver.text_bytes(&node), 11);
+        assert_eq!(
+            resolver.try_set_text_chunk(&owner, &node, &json, &3, &bytes(&e, b"x")),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        assert_eq!(
+            resolver.try_set_text_chunk(&owner, &node, &json, &2, &bytes(&e, b"x")),
+            Err(Ok(ResolverError::TextBudgetExceeded.into()))
+        );
 
-        let long_attempt = catch_unwind(AssertUnwindSafe(|| {
-            resolver.set_text(&owner, &namehash, &too_long_key, &value)
-        }));
-        assert!(long_attempt.is_err());
+        // Shrinking a record frees budget, and `clear_text` drops a key's chunks.
+        resolver.set_text(&owner, &node, &bio, &bytes(&e, b"1"));
+        resolver.set_text_chunk(&owner, &node, &json, &2, &bytes(&e, b"xy"));
+        assert_eq!(resolver.text_bytes(&node), 10);
+        resolver.clear_text(&owner, &node, &json);
+        assert_eq!(resolver.text_chunk_count(&node, &json), 0);
+        assert_eq!(resolver.text_chunk(&node, &json, &0), None);
+        assert_eq!(resolver.text_bytes(&node), 1);
+
+        assert_eq!(
+            resolver.try_set_text_limits(
+                &admin,
+                &TextLimits {
+                    max_value_len: 12,
+                    max_name_bytes: 11
+                }
+            ),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        assert_eq!(
+            resolver.try_set_text_limits(&owner, &limits),
+            Err(Ok(ResolverError::NotAdmin.into()))
+        );
     }
 
     #[test]
-    fn records_are_isolated() {
+    fn text_limits_default_check_owners_and_reset_with_the_record_version() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
         let registry = MockRegistryClient::new(&e, &registry_id);
-
         resolver.init(&registry_id);
+        let admin = Address::generate(&e);
+        registry.set_admin(&admin);
+        assert_eq!(
+            resolver.text_limits(),
+            TextLimits {
+                max_value_len: DEFAULT_MAX_TEXT_VALUE_LEN,
+                max_name_bytes: DEFAULT_MAX_NAME_TEXT_BYTES,
+            }
+        );
 
-        let name_a = namehash(&e, 6);
-        let name_b = namehash(&e, 7);
-        let owner_a = Address::generate(&e);
-        let owner_b = Address::generate(&e);
-        registry.set_owner(&name_a, &owner_a);
-        registry.set_owner(&name_b, &owner_b);
-
-        let addr_a = Address::generate(&e);
-        resolver.set_addr(&owner_a, &name_a, &addr_a);
-
-        assert_eq!(resolver.addr(&name_a), Some(addr_a.clone()));
-        assert!(resolver.addr(&name_b).is_none());
+        let owner = account(&e);
+        let stranger = account(&e);
+        let node = namehash(&e, 49);
+        registry.set_owner(&node, &owner);
+        let (bio, json) = (bytes(&e, b"bio"), bytes(&e, b"profile"));
+        assert_eq!(
+            resolver.try_set_text_chunk(&stranger, &node, &json, &0, &bytes(&e, b"{}")),
+            Err(Ok(ResolverError::NotOwner.into()))
+        );
+        assert_eq!(
+            resolver.try_set_text_chunk(&owner, &node, &json, &0, &bytes(&e, b"")),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        assert_eq!(
+            resolver.try_set_text_limits(
+                &admin,
+                &TextLimits {
+                    max_value_len: 0,
+                    max_name_bytes: 8
+                }
+            ),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
 
-        let key_primary = bytes(&e, b"profile");
-        let key_secondary = bytes(&e, b"avatar");
-        let val_primary = bytes(&e, b"primary");
-        let val_secondary = bytes(&e, b"secondary");
+        // A single value may take the whole budget.
+        resolver.set_text_limits(
+            &admin,
+            &TextLimits {
+                max_value_len: 8,
+                max_name_bytes: 8,
+            },
+        );
+        assert_eq!(
+            resolver.try_set_text_chunk(&owner, &node, &json, &0, &bytes(&e, b"123456789")),
+            Err(Ok(ResolverError::ValueTooLarge.into()))
+        );
+        resolver.set_text_chunk(&owner, &node, &json, &0, &bytes(&e, b"12345678"));
+        assert_eq!(resolver.text_bytes(&node), 8);
 
-        resolver.set_text(&owner_a, &name_a, &key_primary, &val_primary);
-        resolver.set_text(&owner_a, &name_a, &key_secondary, &val_secondary);
+        // Under a tighter budget, records over it may shrink but not grow.
+        resolver.set_text_limits(
+            &admin,
+            &TextLimits {
+                max_value_len: 4,
+                max_name_bytes: 4,
+            },
+        );
+        assert_eq!(
+            resolver.try_set_text(&owner, &node, &bio, &bytes(&e, b"1")),
+            Err(Ok(ResolverError::TextBudgetExceeded.into()))
+        );
+        resolver.set_text_chunk(&owner, &node, &json, &0, &bytes(&e, b"1234"));
+        assert_eq!(resolver.text_bytes(&node), 4);
 
-        assert_eq!(resolver.text(&name_a, &key_primary), Some(val_primary));
-        assert_eq!(resolver.text(&name_a, &key_secondary), Some(val_secondary));
-        assert!(resolver.text(&name_b, &key_primary).is_none());
+        // A re-registration starts the name on a fresh budget.
+        registry.bump_record_version(&node);
+        assert_eq!(resolver.text_bytes(&node), 0);
+        assert_eq!(resolver.text_chunk_count(&node, &json), 0);
+        resolver.set_text(&owner, &node, &bio, &bytes(&e, b"1234"));
+        assert_eq!(resolver.text_bytes(&node), 4);
     }
 
     #[test]
-    fn ownership_changes_require_new_auth() {
+    fn service_records_map_keys_to_contracts() {
         let e = Env::default();
         e.mock_all_auths();
         let resolver_id = e.register(Resolver, ());
         let registry_id = e.register(MockRegistry, ());
         let resolver = ResolverClient::new(&e, &resolver_id);
         let registry = MockRegistryClient::new(&e, &registry_id);
-
         resolver.init(&registry_id);
+        let owner = Address::generate(&e);
+        let node = namehash(&e, 48);
+        registry.set_owner(&node, &owner);
+        let (amm, oracle) = (Symbol::new(&e, "amm"), Symbol::new(&e, "oracle"));
+        let (pool, feed) = (Address::generate(&e), Address::generate(&e));
 
-        let namehash = namehash(&e, 8);
-        let owner_initial = Address::generate(&e);
-        let owner_new = Address::generate(&e);
-        let first_addr = Address::generate(&e);
-        let second_addr = Address::generate(&e);
+        resolver.set_service(&owner, &node, &amm, &pool);
+        let (_, topics, _) = e.events().all().last().unwrap();
+        assert_eq!(topics.len(), 3);
+        resolver.set_service(&owner, &node, &oracle, &feed);
+        assert_eq!(resolver.service(&node, &amm), Some(pool.clone()));
+        assert_eq!(resolver.addr(&node), None);
+        let services = resolver.services(&node);
+        assert_eq!(
+            services.keys(),
+            soroban_sdk::vec![&e, amm.clone(), oracle.clone()]
+        );
+        assert_eq!(services.get(oracle.clone()), Some(feed));
 
-        registry.set_owner(&namehash, &owner_initial);
-        resolver.set_addr(&owner_initial, &namehash, &first_addr);
-        assert_eq!(resolver.addr(&namehash), Some(first_addr));
+        assert_eq!(
+            resolver.try_set_service(&owner, &node, &amm, &account(&e)),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        resolver.clear_service(&owner, &node, &amm);
+        assert_eq!(resolver.service(&node, &amm), None);
+        assert_eq!(
+            resolver.services(&node).keys(),
+            soroban_sdk::vec![&e, oracle]
+        );
+    }
 
-        registry.set_owner(&namehash, &owner_new);
+    #[test]
+    fn metadata_anchors_uri_and_hash() {
+        let e = Env::default();
+        e.mock_all_auths();
+        let resolver_id = e.register(Resolver, ());
+        let registry_id = e.register(MockRegistry, ());
+        let resolver = ResolverClient::new(&e, &resolver_id);
+        let registry = MockRegistryClient::new(&e, &registry_id);
+        resolver.init(&registry_id);
+        let owner = Address::generate(&e);
+        let node = namehash(&e, 49);
+        registry.set_owner(&node, &owner);
+        assert_eq!(resolver.metadata(&node), None);
 
-        let stale_owner_attempt = catch_unwind(AssertUnwindSafe(|| {
-            resolver.set_addr(&owner_initial, &namehash, &second_addr)
-        }));
-        assert!(stale_owner_attempt.is_err());
+        let document = bytes(&e, b"{\"name\":\"alice.stellar\"}");
+        let hash = e.crypto().sha256(&document).to_bytes();
+        let uri = bytes(&e, b"ipfs://bafymeta");
+        resolver.set_metadata(&owner, &node, &uri, &hash);
+        let (_, _, data) = e.events().all().last().unwrap();
+        let map = Map::<Symbol, Val>::try_from_val(&e, &data).unwrap();
+        assert_eq!(
+            BytesN::<32>::try_from_val(&e, &map.get(Symbol::new(&e, "hash")).unwrap()).unwrap(),
+            hash
+        );
+        assert_eq!(
+            resolver.metadata(&node),
+            Some(Metadata {
+                uri,
+                hash: hash.clone()
+            })
+        );
 
-        resolver.set_addr(&owner_new, &namehash, &second_addr);
-        assert_eq!(resolver.addr(&namehash), Some(second_addr));
+        assert_eq!(
+            resolver.try_set_metadata(&owner, &node, &bytes(&e, b"ftp://x"), &hash),
+            Err(Ok(ResolverError::InvalidInput.into()))
+        );
+        // A new registration starts without the previous owner's metadata.
+        registry.bump_record_version(&node);
+        assert_eq!(resolver.metadata(&node), None);
     }
 }
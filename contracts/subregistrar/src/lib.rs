@@ -0,0 +1,513 @@
+#![no_std]
+
+#[cfg(test)]
+extern crate std;
+
+use name_service_common::subnode;
+use soroban_sdk::token::TokenClient;
+use soroban_sdk::{
+    contract, contracterror, contractevent, contractimpl, contracttype, panic_with_error, Address,
+    Bytes, BytesN, Env, IntoVal, Symbol, Val, Vec,
+};
+
+const DAY_IN_LEDGERS: u32 = 17_280;
+/// Persistent entries are topped back up to ~30 days whenever they drop below ~29.
+const TTL_EXTEND_TO: u32 = 30 * DAY_IN_LEDGERS;
+const TTL_THRESHOLD: u32 = TTL_EXTEND_TO - DAY_IN_LEDGERS;
+const MAX_LABEL_LEN: u32 = 63;
+
+#[contracttype]
+#[derive(Clone)]
+enum DataKey {
+    Config,
+    Allowed(Address),
+}
+
+/// What `register` charges and who may call it. The parent's current Registry owner
+/// manages it and receives every payment.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SubregistrarConfig {
+    pub registry: Address,
+    /// Node whose children are sold, such as the namehash of `theirname.stellar`.
+    pub parent: BytesN<32>,
+    pub token: Address,
+    /// Prices indexed by label length: entry `i` prices labels of `i + 1` bytes and the last
+    /// entry every longer label. Empty means free.
+    pub prices: Vec<i128>,
+    /// Anyone may register when set; otherwise only allow-listed callers.
+    pub public: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["subname_registered"])]
+pub struct EvtSubnameRegistered {
+    #[topic]
+    pub namehash: BytesN<32>,
+    pub label: Bytes,
+    pub owner: Address,
+    pub price: i128,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["pricing_changed"])]
+pub struct EvtPricingChanged {
+    pub token: Address,
+    pub prices: Vec<i128>,
+    pub public: bool,
+}
+
+#[derive(Clone)]
+#[contractevent(topics = ["allowlist_changed"])]
+pub struct EvtAllowlistChanged {
+    #[topic]
+    pub account: Address,
+    pub allowed: bool,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum SubregistrarError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    /// The caller is not the parent's Registry owner.
+    NotOwner = 3,
+    /// A negative price.
+    InvalidPrice = 4,
+    /// Empty, longer than 63 bytes, or not lowercase letters, digits, and inner hyphens.
+    InvalidLabel = 5,
+    /// The subname already has an owner.
+    NameTaken = 6,
+    /// Registration is allow-listed and the caller is not on the list.
+    NotAllowed = 7,
+    /// The parent's owner has not made this contract its Registry subnode operator.
+    NotOperator = 8,
+}
+
+/// Sells subnames of one parent node under the parent owner's own pricing and access
+/// rules. The owner deploys it and names it the parent's Registry subnode operator.
+#[contract]
+pub struct Subregistrar;
+
+fn write_entry<V: IntoVal<Env, Val>>(env: &Env, key: &DataKey, value: &V) {
+    let storage = env.storage().persistent();
+    storage.set(key, value);
+    storage.extend_ttl(key, TTL_THRESHOLD, TTL_EXTEND_TO);
+}
+
+fn read_config(env: &Env) -> SubregistrarConfig {
+    let storage = env.storage().persistent();
+    let config = storage
+        .get(&DataKey::Config)
+        .unwrap_or_else(|| panic_with_error!(env, SubregistrarError::NotInitialized));
+    storage.extend_ttl(&DataKey::Config, TTL_THRESHOLD, TTL_EXTEND_TO);
+    config
+}
+
+/// Requires `caller` to be the parent's current owner and to have authorized the call.
+fn ensure_parent_owner(env: &Env, config: &SubregistrarConfig, caller: &Address) {
+    caller.require_auth();
+    if registry_api::owner(env, &config.registry, &config.parent).as_ref() != Some(caller) {
+        panic_with_error!(env, SubregistrarError::NotOwner);
+    }
+}
+
+fn validate_prices(env: &Env, prices: &Vec<i128>) {
+    if prices.iter().any(|price| price < 0) {
+        panic_with_error!(env, SubregistrarError::InvalidPrice);
+    }
+}
+
+/// Same canonical form the Registrar accepts for ASCII labels.
+fn validate_label(env: &Env, label: &Bytes) {
+    let len = label.len();
+    if len == 0 || len > MAX_LABEL_LEN {
+        panic_with_error!(env, SubregistrarError::InvalidLabel);
+    }
+    for (i, byte) in label.iter().enumerate() {
+        let inner = i != 0 && i as u32 != len - 1;
+        if !(byte.is_ascii_lowercase() || byte.is_ascii_digit() || (byte == b'-' && inner)) {
+            panic_with_error!(env, SubregistrarError::InvalidLabel);
+        }
+    }
+}
+
+fn price_of(config: &SubregistrarConfig, label: &Bytes) -> i128 {
+    if config.prices.is_empty() {
+        return 0;
+    }
+    let index = (label.len() - 1).min(config.prices.len() - 1);
+    config.prices.get_unchecked(index)
+}
+
+mod registry_api {
+    use super::*;
+
+    pub fn owner(env: &Env, registry: &Address, namehash: &BytesN<32>) -> Option<Address> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "get_owner"),
+            (namehash.clone(),).into_val(env),
+        )
+    }
+
+    pub fn subnode_operator(
+        env: &Env,
+        registry: &Address,
+        namehash: &BytesN<32>,
+    ) -> Option<Address> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "subnode_operator"),
+            (namehash.clone(),).into_val(env),
+        )
+    }
+
+    pub fn set_owner(
+        env: &Env,
+        registry: &Address,
+        parent: &BytesN<32>,
+        label: &Bytes,
+        owner: &Address,
+    ) -> BytesN<32> {
+        env.invoke_contract(
+            registry,
+            &Symbol::new(env, "set_owner"),
+            (
+                env.current_contract_address(),
+                parent.clone(),
+                label.clone(),
+                owner.clone(),
+            )
+                .into_val(env),
+        )
+    }
+}
+
+#[contractimpl]
+impl Subregistrar {
+    /// Binds this deployment to `parent`. Must be authorized by the parent's Registry owner,
+    /// who then calls the Registry's `set_subnode_operator(parent, Some(this))`.
+    pub fn init(
+        env: Env,
+        registry: Address,
+        parent: BytesN<32>,
+        token: Address,
+        prices: Vec<i128>,
+        public: bool,
+    ) {
+        if env.storage().persistent().has(&DataKey::Config) {
+            panic_with_error!(&env, SubregistrarError::AlreadyInitialized);
+        }
+        validate_prices(&env, &prices);
+        let config = SubregistrarConfig {
+            registry,
+            parent,
+            token,
+            prices,
+            public,
+        };
+        let owner = registry_api::owner(&env, &config.registry, &config.parent)
+            .unwrap_or_else(|| panic_with_error!(&env, SubregistrarError::NotOwner));
+        owner.require_auth();
+        write_entry(&env, &DataKey::Config, &config);
+    }
+
+    pub fn config(env: Env) -> SubregistrarConfig {
+        read_config(&env)
+    }
+
+    /// Parent owner only: replace the payment token, prices, and whether registration is
+    /// open to everyone. Emits `pricing_changed`.
+    pub fn set_pricing(env: Env, owner: Address, token: Address, prices: Vec<i128>, public: bool) {
+        let mut config = read_config(&env);
+        ensure_parent_owner(&env, &config, &owner);
+        validate_prices(&env, &prices);
+        config.token = token.clone();
+        config.prices = prices.clone();
+        config.public = public;
+        write_entry(&env, &DataKey::Config, &config);
+        EvtPricingChanged {
+            token,
+            prices,
+            public,
+        }
+        .publish(&env);
+    }
+
+    /// Parent owner only: add or remove `accounts` from the allow-list that applies while
+    /// registration is not public. Emits `allowlist_changed` per account.
+    pub fn set_allowed(env: Env, owner: Address, accounts: Vec<Address>, allowed: bool) {
+        let config = read_config(&env);
+        ensure_parent_owner(&env, &config, &owner);
+        for account in accounts.iter() {
+            let key = DataKey::Allowed(account.clone());
+            if allowed {
+                write_entry(&env, &key, &true);
+            } else {
+                env.storage().persistent().remove(&key);
+            }
+            EvtAllowlistChanged { account, allowed }.publish(&env);
+        }
+    }
+
+    pub fn is_allowed(env: Env, account: Address) -> bool {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Allowed(account))
+            .unwrap_or(false)
+    }
+
+    /// What `register` charges for `label`.
+    pub fn price(env: Env, label: Bytes) -> i128 {
+        validate_label(&env, &label);
+        price_of(&read_config(&env), &label)
+    }
+
+    /// Registers `label` under the parent to `owner`, charging `caller` the price for its
+    /// length, paid straight to the parent's current owner. Subnames have no expiry of their
+    /// own. Returns the namehash and emits `subname_registered`.
+    pub fn register(env: Env, caller: Address, label: Bytes, owner: Address) -> BytesN<32> {
+        caller.require_auth();
+        let config = read_config(&env);
+        validate_label(&env, &label);
+        if !config.public && !Self::is_allowed(env.clone(), caller.clone()) {
+            panic_with_error!(&env, SubregistrarError::NotAllowed);
+        }
+        if registry_api::subnode_operator(&env, &config.registry, &config.parent)
+            != Some(env.current_contract_address())
+        {
+            panic_with_error!(&env, SubregistrarError::NotOperator);
+        }
+        let namehash = subnode(&env, &config.parent, &label);
+        if registry_api::owner(&env, &config.registry, &namehash).is_some() {
+            panic_with_error!(&env, SubregistrarError::NameTaken);
+        }
+
+        let price = price_of(&config, &label);
+        if price > 0 {
+            let parent_owner = registry_api::owner(&env, &config.registry, &config.parent)
+                .unwrap_or_else(|| panic_with_error!(&env, SubregistrarError::NotOwner));
+            TokenClient::new(&env, &config.token).transfer(&caller, &parent_owner, &price);
+        }
+        registry_api::set_owner(&env, &config.registry, &config.parent, &label, &owner);
+        EvtSubnameRegistered {
+            namehash: namehash.clone(),
+            label,
+            owner,
+            price,
+        }
+        .publish(&env);
+        namehash
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use registry::{Registry, RegistryClient};
+    use soroban_sdk::testutils::Address as _;
+    use soroban_sdk::token::StellarAssetClient;
+    use soroban_sdk::vec;
+
+    struct Setup {
+        env: Env,
+        registry: RegistryClient<'static>,
+        subregistrar: SubregistrarClient<'static>,
+        token: Address,
+        parent_owner: Address,
+        parent: BytesN<32>,
+    }
+
+    /// `alice.stellar` on a real Registry, owned by `parent_owner`, with a subregistrar
+    /// charging 50 for one- and two-byte labels and 10 for longer ones.
+    fn setup(public: bool) -> Setup {
+        let env = Env::default();
+        env.mock_all_auths();
+        let admin = Address::generate(&env);
+        let registry_id = env.register(Registry, (&admin, &admin));
+        let registry = RegistryClient::new(&env, &registry_id);
+        let tld_owner = Address::generate(&env);
+        let parent_owner = Address::generate(&env);
+        let tld = registry.create_tld(&admin, &Bytes::from_slice(&env, b"stellar"), &tld_owner);
+        let parent = registry.set_owner(
+            &tld_owner,
+            &tld,
+            &Bytes::from_slice(&env, b"alice"),
+            &parent_owner,
+        );
+        let token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+
+        let subregistrar_id = env.register(Subregistrar, ());
+        let subregistrar = SubregistrarClient::new(&env, &subregistrar_id);
+        subregistrar.init(
+            &registry_id,
+            &parent,
+            &token,
+            &vec![&env, 50, 50, 10],
+            &public,
+        );
+        registry.set_subnode_operator(&parent, &Some(subregistrar_id));
+        Setup {
+            env,
+            registry,
+            subregistrar,
+            token,
+            parent_owner,
+            parent,
+        }
+    }
+
+    #[test]
+    fn public_registration_pays_the_parent_owner() {
+        let s = setup(true);
+        let env = &s.env;
+        let buyer = Address::generate(env);
+        StellarAssetClient::new(env, &s.token).mint(&buyer, &100);
+        let label = Bytes::from_slice(env, b"pay");
+        assert_eq!(s.subregistrar.price(&label), 10);
+        assert_eq!(s.subregistrar.price(&Bytes::from_slice(env, b"x")), 50);
+
+        let namehash = s.subregistrar.register(&buyer, &label, &buyer);
+        assert_eq!(namehash, subnode(env, &s.parent, &label));
+        assert_eq!(s.registry.owner(&namehash), buyer);
+        let token = TokenClient::new(env, &s.token);
+        assert_eq!(token.balance(&s.parent_owner), 10);
+        assert_eq!(token.balance(&buyer), 90);
+        assert_eq!(
+            s.subregistrar.try_register(&buyer, &label, &buyer),
+            Err(Ok(SubregistrarError::NameTaken.into()))
+        );
+        assert_eq!(
+            s.subregistrar
+                .try_register(&buyer, &Bytes::from_slice(env, b"-bad"), &buyer),
+            Err(Ok(SubregistrarError::InvalidLabel.into()))
+        );
+    }
+
+    #[test]
+    fn allow_list_and_operator_gate_registration() {
+        let s = setup(false);
+        let env = &s.env;
+        let member = Address::generate(env);
+        let outsider = Address::generate(env);
+        StellarAssetClient::new(env, &s.token).mint(&member, &100);
+        let label = Bytes::from_slice(env, b"team");
+        assert_eq!(
+            s.subregistrar.try_register(&outsider, &label, &outsider),
+            Err(Ok(SubregistrarError::NotAllowed.into()))
+        );
+        assert_eq!(
+            s.subregistrar
+                .try_set_allowed(&outsider, &vec![env, outsider.clone()], &true),
+            Err(Ok(SubregistrarError::NotOwner.into()))
+        );
+        s.subregistrar
+            .set_allowed(&s.parent_owner, &vec![env, member.clone()], &true);
+        s.subregistrar.register(&member, &label, &member);
+
+        // Free pricing; the operator lapses when the parent changes hands.
+        s.subregistrar
+            .set_pricing(&s.parent_owner, &s.token, &Vec::new(env), &true);
+        assert_eq!(s.subregistrar.price(&label), 0);
+        s.registry.transfer(&s.parent_owner, &s.parent, &outsider);
+        assert_eq!(
+            s.subregistrar
+                .try_register(&outsider, &Bytes::from_slice(env, b"late"), &outsider),
+            Err(Ok(SubregistrarError::NotOperator.into()))
+        );
+    }
+
+    #[test]
+    fn only_the_parent_owner_configures_and_new_owners_get_paid() {
+        let s = setup(true);
+        let env = &s.env;
+        let outsider = Address::generate(env);
+        assert_eq!(
+            s.subregistrar.try_init(
+                &s.registry.address,
+                &s.parent,
+                &s.token,
+                &Vec::new(env),
+                &true
+            ),
+            Err(Ok(SubregistrarError::AlreadyInitialized.into()))
+        );
+        assert_eq!(
+            s.subregistrar
+                .try_set_pricing(&outsider, &s.token, &Vec::new(env), &true),
+            Err(Ok(SubregistrarError::NotOwner.into()))
+        );
+        assert_eq!(
+            s.subregistrar
+                .try_set_pricing(&s.parent_owner, &s.token, &vec![env, 5, -1], &true),
+            Err(Ok(SubregistrarError::InvalidPrice.into()))
+        );
+        // A parent nobody owns cannot be bound.
+        let unowned = subnode(env, &s.parent, &Bytes::from_slice(env, b"none"));
+        let fresh = SubregistrarClient::new(env, &env.register(Subregistrar, ()));
+        assert_eq!(
+            fresh.try_init(
+                &s.registry.address,
+                &unowned,
+                &s.token,
+                &Vec::new(env),
+                &true
+            ),
+            Err(Ok(SubregistrarError::NotOwner.into()))
+        );
+
+        let longest = Bytes::from_slice(env, &[b'a'; MAX_LABEL_LEN as usize]);
+        let mut too_long = longest.clone();
+        too_long.push_back(b'a');
+        for bad in [too_long, Bytes::new(env), Bytes::from_slice(env, b"Caps")] {
+            assert_eq!(
+                s.subregistrar.try_price(&bad),
+                Err(Ok(SubregistrarError::InvalidLabel.into()))
+            );
+        }
+        assert_eq!(s.subregistrar.price(&longest), 10);
+
+        // The payer may register for someone else, and the parent's new owner is paid.
+        let buyer = Address::generate(env);
+        let friend = Address::generate(env);
+        StellarAssetClient::new(env, &s.token).mint(&buyer, &100);
+        s.registry.transfer(&s.parent_owner, &s.parent, &outsider);
+        s.registry
+            .set_subnode_operator(&s.parent, &Some(s.subregistrar.address.clone()));
+        let namehash = s.subregistrar.register(&buyer, &longest, &friend);
+        assert_eq!(s.registry.owner(&namehash), friend);
+        let token = TokenClient::new(env, &s.token);
+        assert_eq!(token.balance(&outsider), 10);
+        assert_eq!(token.balance(&s.parent_owner), 0);
+        assert_eq!(token.balance(&buyer), 90);
+    }
+
+    #[test]
+    fn removed_accounts_lose_access_to_a_private_subregistrar() {
+        let s = setup(false);
+        let env = &s.env;
+        let member = Address::generate(env);
+        let members = vec![env, member.clone()];
+        s.subregistrar.set_allowed(&s.parent_owner, &members, &true);
+        assert!(s.subregistrar.is_allowed(&member));
+        s.subregistrar
+            .set_allowed(&s.parent_owner, &members, &false);
+        assert!(!s.subregistrar.is_allowed(&member));
+        assert_eq!(
+            s.subregistrar
+                .try_register(&member, &Bytes::from_slice(env, b"gone"), &member),
+            Err(Ok(SubregistrarError::NotAllowed.into()))
+        );
+
+        // Opening registration lets anyone in without touching the list.
+        s.subregistrar
+            .set_pricing(&s.parent_owner, &s.token, &Vec::new(env), &true);
+        s.subregistrar
+            .register(&member, &Bytes::from_slice(env, b"open"), &member);
+        assert!(!s.subregistrar.is_allowed(&member));
+    }
+}
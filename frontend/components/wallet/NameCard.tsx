@@ -89,7 +89,8 @@ export function NameCard({ name, onRenewed }: NameCardProps) {
       const sourceAccount = await rpcServer.getAccount(publicKey);
 
       setRenewStatus("Building transaction...");
-      const renewOp = createRenewOperation(config.registrarId, publicKey, label);
+      const tld = name.fqdn.split(".").slice(1).join(".") || "stellar";
+      const renewOp = createRenewOperation(config.registrarId, publicKey, tld, label);
       const tx = new TransactionBuilder(sourceAccount, {
         fee: BASE_FEE,
         networkPassphrase: passphrase,
@@ -182,6 +183,7 @@ export function NameCard({ name, onRenewed }: NameCardProps) {
       setTransferStatus("Building transaction...");
       const transferOp = createTransferOperation(
         config.registryId,
+        publicKey,
         name.namehash,
         transferTo.trim()
       );
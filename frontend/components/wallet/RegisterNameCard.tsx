@@ -228,6 +228,7 @@ export function RegisterNameCard({ onRegistered, initialLabel }: RegisterNameCar
       const registerOp = createRegisterOperation(
         config.registrarId,
         publicKey,
+        "stellar",
         normalizedLabel,
         publicKey,
         secretHex,
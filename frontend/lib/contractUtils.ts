@@ -35,10 +35,15 @@ export function generateSecretBytes(): Uint8Array {
   return secret;
 }
 
+/**
+ * Mirrors the registrar's `sha256(label || owner xdr || secret || committer xdr)`. The committer
+ * must be the account that later calls `register`; it defaults to the owner.
+ */
 export async function computeCommitmentHex(
   label: string,
   ownerAccount: string,
-  secret: Uint8Array
+  secret: Uint8Array,
+  committerAccount: string = ownerAccount
 ): Promise<string> {
   if (secret.length !== 32) {
     throw new Error("Secret must be 32 bytes");
@@ -46,11 +51,15 @@ export async function computeCommitmentHex(
   const labelBytes = new TextEncoder().encode(label);
   const ownerVal = Address.fromString(ownerAccount).toScVal();
   const ownerXdr = ownerVal.toXDR();
+  const committerXdr = Address.fromString(committerAccount).toScVal().toXDR();
 
-  const preimage = new Uint8Array(labelBytes.length + ownerXdr.length + secret.length);
+  const preimage = new Uint8Array(
+    labelBytes.length + ownerXdr.length + secret.length + committerXdr.length
+  );
   preimage.set(labelBytes, 0);
   preimage.set(ownerXdr, labelBytes.length);
   preimage.set(secret, labelBytes.length + ownerXdr.length);
+  preimage.set(committerXdr, labelBytes.length + ownerXdr.length + secret.length);
 
   const webCrypto = globalThis.crypto;
   if (!webCrypto?.subtle) {
@@ -68,25 +77,33 @@ export async function computeCommitmentHex(
 
 /**
  * Creates a contract invocation operation for renewing a name through the registrar.
- * The registrar renew function expects the caller address and the plain-text label bytes.
+ * The registrar renew function expects the caller address and the plain-text TLD and label bytes.
  */
 export function createRenewOperation(
   registrarContractId: string,
   callerAccount: string,
+  tld: string,
   label: string
 ): xdr.Operation<Operation.InvokeHostFunction> {
   const contract = new Contract(registrarContractId);
   const caller = Address.fromString(callerAccount);
-  const labelBytes = new TextEncoder().encode(label);
-  return contract.call("renew", caller.toScVal(), nativeToScVal(labelBytes));
+  const encoder = new TextEncoder();
+  return contract.call(
+    "renew",
+    caller.toScVal(),
+    nativeToScVal(encoder.encode(tld)),
+    nativeToScVal(encoder.encode(label))
+  );
 }
 
 /**
  * Creates a contract invocation operation for transferring ownership of a name
- * via the registry contract.
+ * via the registry contract. `callerAccount` must be the current owner or an
+ * operator the owner approved with `set_approval_for_all`.
  */
 export function createTransferOperation(
   registryContractId: string,
+  callerAccount: string,
   namehashHex: string,
   newOwnerAccount: string
 ): xdr.Operation<Operation.InvokeHostFunction> {
@@ -96,8 +113,9 @@ export function createTransferOperation(
     throw new Error("Invalid namehash length; expected 32 bytes");
   }
   const namehashVal = nativeToScVal(new Uint8Array(namehashBytes));
+  const caller = Address.fromString(callerAccount);
   const newOwner = Address.fromString(newOwnerAccount);
-  return contract.call("transfer", namehashVal, newOwner.toScVal());
+  return contract.call("transfer", caller.toScVal(), namehashVal, newOwner.toScVal());
 }
 
 /**
@@ -123,21 +141,34 @@ export function createCommitOperation(
   );
 }
 
+/** Default registration term passed to `register` (one year). */
+export const DEFAULT_REGISTRATION_SECS = 31_536_000;
+
 /**
- * Creates a register operation for the registrar using the provided label, owner, and secret hex.
+ * Creates a register operation for the registrar using the provided TLD, label, owner, and secret hex.
  * Pass `resolverAddress` as null or undefined to skip setting a resolver during registration.
+ * `durationSecs` defaults to one year and must not exceed the registrar's `max_registration_secs`.
+ * `referrerAddress` is credited the registrar's referral share of the fee when provided.
+ * `voucherCode` is a registrar voucher code that discounts the fee when provided.
+ * `sponsorAddress` names the sponsor whose allowance pays when the caller registers for itself.
  */
 export function createRegisterOperation(
   registrarContractId: string,
   callerAccount: string,
+  tld: string,
   label: string,
   ownerAccount: string,
   secretHex: string,
-  resolverAddress?: string | null
+  resolverAddress?: string | null,
+  durationSecs: number = DEFAULT_REGISTRATION_SECS,
+  referrerAddress?: string | null,
+  voucherCode?: string | null,
+  sponsorAddress?: string | null
 ): xdr.Operation<Operation.InvokeHostFunction> {
   const contract = new Contract(registrarContractId);
   const caller = Address.fromString(callerAccount);
   const owner = Address.fromString(ownerAccount);
+  const tldBytes = new TextEncoder().encode(tld);
   const labelBytes = new TextEncoder().encode(label);
   const secretBytes = hexToBytes(secretHex);
   if (secretBytes.length !== 32) {
@@ -148,14 +179,31 @@ export function createRegisterOperation(
     resolverAddress && resolverAddress.trim().length > 0
       ? Address.fromString(resolverAddress.trim()).toScVal()
       : xdr.ScVal.scvVoid();
+  const referrerVal =
+    referrerAddress && referrerAddress.trim().length > 0
+      ? Address.fromString(referrerAddress.trim()).toScVal()
+      : xdr.ScVal.scvVoid();
+  const voucherVal =
+    voucherCode && voucherCode.trim().length > 0
+      ? nativeToScVal(new TextEncoder().encode(voucherCode.trim()))
+      : xdr.ScVal.scvVoid();
+  const sponsorVal =
+    sponsorAddress && sponsorAddress.trim().length > 0
+      ? Address.fromString(sponsorAddress.trim()).toScVal()
+      : xdr.ScVal.scvVoid();
 
   return contract.call(
     "register",
     caller.toScVal(),
+    nativeToScVal(tldBytes),
     nativeToScVal(labelBytes),
     owner.toScVal(),
     nativeToScVal(secretBytes),
-    resolverVal
+    resolverVal,
+    nativeToScVal(BigInt(durationSecs), { type: "u64" }),
+    referrerVal,
+    voucherVal,
+    sponsorVal
   );
 }
 
@@ -0,0 +1,115 @@
+//! The contracts and `name-service-common` against the reference model.
+
+use rand::Rng;
+use registrar::{Registrar, RegistrarClient, RegistrarError};
+use registry::{Registry, RegistryClient};
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::xdr::ScAddress;
+use soroban_sdk::{Address, Bytes, Env, Vec};
+
+use crate::{canonical_label, cases, commitment, namehash, registry_accepts};
+
+#[test]
+fn registrar_normalize_matches_reference() {
+    let env = Env::default();
+    let registrar = RegistrarClient::new(&env, &env.register(Registrar, ()));
+    let admin = Address::generate(&env);
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+    registrar.init(
+        &env.register(Registry, (Address::generate(&env), Address::generate(&env))),
+        &Bytes::from_slice(&env, b"stellar"),
+        &admin,
+        &token,
+    );
+    let mut rng = cases::rng();
+    for _ in 0..cases::count(2_000) {
+        let label = cases::label(&mut rng);
+        let got = registrar.try_normalize(&Bytes::from_slice(&env, &label));
+        match canonical_label(&label) {
+            Some(expected) => assert_eq!(
+                got,
+                Ok(Ok(Bytes::from_slice(&env, &expected))),
+                "label {label:?}"
+            ),
+            None => assert_eq!(
+                got,
+                Err(Ok(RegistrarError::InvalidLabel.into())),
+                "label {label:?}"
+            ),
+        }
+    }
+}
+
+#[test]
+fn namehash_matches_reference() {
+    let env = Env::default();
+    let registry = RegistryClient::new(
+        &env,
+        &env.register(Registry, (Address::generate(&env), Address::generate(&env))),
+    );
+    let mut rng = cases::rng();
+    for _ in 0..cases::count(1_000) {
+        let depth = rng.gen_range(0..5);
+        let labels: std::vec::Vec<std::vec::Vec<u8>> =
+            (0..depth).map(|_| cases::label(&mut rng)).collect();
+        let mut args = Vec::new(&env);
+        for label in &labels {
+            args.push_back(Bytes::from_slice(&env, label));
+        }
+        let slices: std::vec::Vec<&[u8]> = labels.iter().map(|label| &label[..]).collect();
+        let expected = namehash(&slices);
+
+        assert_eq!(
+            name_service_common::namehash(&env, &args).to_array(),
+            expected,
+            "labels {labels:?}"
+        );
+        let got = registry.try_namehash(&args);
+        if labels.iter().all(|label| registry_accepts(label)) {
+            assert_eq!(
+                got.unwrap().unwrap().to_array(),
+                expected,
+                "labels {labels:?}"
+            );
+        } else {
+            assert!(got.is_err(), "labels {labels:?}");
+        }
+    }
+}
+
+#[test]
+fn compute_commitment_matches_reference() {
+    let env = Env::default();
+    let mut rng = cases::rng();
+    for _ in 0..cases::count(500) {
+        let label = cases::label(&mut rng);
+        let secret: std::vec::Vec<u8> = (0..rng.gen_range(0..40)).map(|_| rng.gen()).collect();
+        let owner = if rng.gen() {
+            Address::generate(&env)
+        } else {
+            env.register(Registry, (Address::generate(&env), Address::generate(&env)))
+        };
+        let committer = Address::generate(&env);
+
+        let got = name_service_common::compute_commitment(
+            &env,
+            &Bytes::from_slice(&env, &label),
+            &owner,
+            &Bytes::from_slice(&env, &secret),
+            &committer,
+        );
+        let expected = commitment(
+            &label,
+            &ScAddress::from(&owner),
+            &secret,
+            &ScAddress::from(&committer),
+        );
+        assert_eq!(
+            got.to_array(),
+            expected,
+            "label {label:?}, secret {secret:?}"
+        );
+    }
+}
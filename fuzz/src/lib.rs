@@ -0,0 +1,138 @@
+//! Randomized tests of label validation, hashing, and the registration lifecycle.
+//!
+//! The functions here are deliberately naive reference implementations, written from the
+//! documented formulas with `sha2` and `stellar-xdr` rather than the Soroban host, so the
+//! tests catch drift in the contracts and `name-service-common`. Every test draws its cases
+//! from a seeded RNG. Set `SNS_FUZZ_SEED` to replay a failure and `SNS_FUZZ_CASES` to change
+//! how many cases run.
+
+use sha2::{Digest, Sha256};
+use soroban_sdk::xdr::{Limits, ScAddress, ScVal, WriteXdr};
+
+#[cfg(test)]
+mod hashing;
+#[cfg(test)]
+mod lifecycle;
+
+/// Longest label the Registry and Registrar accept, in bytes.
+pub const MAX_LABEL_LEN: usize = 63;
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// `namehash` of `labels`, given from the TLD down, folding
+/// `node = sha256(node || sha256(label))` from the zero node.
+pub fn namehash(labels: &[&[u8]]) -> [u8; 32] {
+    labels.iter().fold([0u8; 32], |node, label| {
+        let mut data = node.to_vec();
+        data.extend_from_slice(&sha256(label));
+        sha256(&data)
+    })
+}
+
+/// Whether the Registry accepts `label`: non-empty and at most 63 bytes.
+pub fn registry_accepts(label: &[u8]) -> bool {
+    !label.is_empty() && label.len() <= MAX_LABEL_LEN
+}
+
+/// Canonical form of `label` under the Registrar's default parameters (no IDN, lengths
+/// 1..=63): ASCII lowercased, then only letters, digits, and hyphens that neither lead nor
+/// trail, with no `--` at positions 2 and 3. `None` if the label has no canonical form.
+pub fn canonical_label(label: &[u8]) -> Option<Vec<u8>> {
+    if label.is_empty() || label.len() > MAX_LABEL_LEN || !label.is_ascii() {
+        return None;
+    }
+    let out = label.to_ascii_lowercase();
+    let last = out.len() - 1;
+    let ldh = out.iter().enumerate().all(|(idx, b)| match b {
+        b'a'..=b'z' | b'0'..=b'9' => true,
+        b'-' => idx != 0 && idx != last,
+        _ => false,
+    });
+    let reserved_hyphens = out.len() >= 4 && &out[2..4] == b"--";
+    (ldh && !reserved_hyphens).then_some(out)
+}
+
+/// Version 1 commitment: `sha256(label || owner xdr || secret || committer xdr)`, where
+/// an address's XDR is that of the `ScVal` holding it.
+pub fn commitment(
+    label: &[u8],
+    owner: &ScAddress,
+    secret: &[u8],
+    committer: &ScAddress,
+) -> [u8; 32] {
+    let address_xdr = |address: &ScAddress| {
+        ScVal::Address(address.clone())
+            .to_xdr(Limits::none())
+            .expect("addresses encode")
+    };
+    let mut data = label.to_vec();
+    data.extend(address_xdr(owner));
+    data.extend_from_slice(secret);
+    data.extend(address_xdr(committer));
+    sha256(&data)
+}
+
+#[cfg(test)]
+pub(crate) mod cases {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    /// Cases per test unless `SNS_FUZZ_CASES` says otherwise.
+    pub fn count(default: usize) -> usize {
+        std::env::var("SNS_FUZZ_CASES")
+            .ok()
+            .and_then(|cases| cases.parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// RNG seeded from `SNS_FUZZ_SEED` or the clock. The seed is printed so a failing run
+    /// can be replayed; `cargo test` shows it for failed tests only.
+    pub fn rng() -> StdRng {
+        let seed = std::env::var("SNS_FUZZ_SEED")
+            .ok()
+            .and_then(|seed| seed.parse().ok())
+            .unwrap_or_else(|| {
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .expect("clock after 1970")
+                    .as_nanos() as u64
+            });
+        println!("SNS_FUZZ_SEED={seed}");
+        StdRng::seed_from_u64(seed)
+    }
+
+    /// Bytes that often look like a label: mostly letters, digits, and hyphens in either
+    /// case, with some dots, underscores, `xn--` prefixes, non-ASCII bytes, and lengths on
+    /// either side of the 63-byte limit.
+    pub fn label(rng: &mut StdRng) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789-";
+        let len = match rng.gen_range(0..10) {
+            0 => rng.gen_range(60..70),
+            1 => 0,
+            _ => rng.gen_range(1..12),
+        };
+        let mut label: Vec<u8> = (0..len)
+            .map(|_| match rng.gen_range(0..40) {
+                0 => b'.',
+                1 => b'_',
+                2 => rng.gen_range(0x80..=0xff),
+                _ => ALPHABET[rng.gen_range(0..ALPHABET.len())],
+            })
+            .collect();
+        if rng.gen_range(0..10) == 0 {
+            label.splice(0..0, *b"xn--");
+        }
+        label
+    }
+
+    /// A label the Registrar accepts, lowercase letters and digits only.
+    pub fn valid_label(rng: &mut StdRng) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+        let len = rng.gen_range(1..=20);
+        (0..len)
+            .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())])
+            .collect()
+    }
+}
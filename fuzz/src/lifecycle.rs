@@ -0,0 +1,179 @@
+//! Register → renew → expire → re-register on a real Registry and Registrar, with random
+//! terms, renewal counts, and timings.
+
+use rand::Rng;
+use registrar::{Registrar, RegistrarClient};
+use registry::{Registry, RegistryClient};
+use soroban_sdk::testutils::{Address as _, Ledger as _};
+use soroban_sdk::token::StellarAssetClient;
+use soroban_sdk::{Address, Bytes, BytesN, Env};
+
+use crate::{cases, namehash};
+
+const DAY: u64 = 86_400;
+const YEAR: u64 = 365 * DAY;
+
+/// Default Registrar parameters the invariants depend on.
+const COMMIT_MIN_AGE_SECS: u64 = 10;
+const GRACE_PERIOD_SECS: u64 = 90 * DAY;
+/// Default `renew_extension_secs`, added per renewal.
+const RENEW_EXTENSION_SECS: u64 = YEAR;
+
+struct Deployment {
+    env: Env,
+    registry: RegistryClient<'static>,
+    registrar: RegistrarClient<'static>,
+    token: Address,
+    tld: Bytes,
+    admin: Address,
+}
+
+impl Deployment {
+    /// A Registry and Registrar serving `stellar`, wired as the Deployer does.
+    fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().set_timestamp(1_000_000);
+        let admin = Address::generate(&env);
+        let registry = RegistryClient::new(&env, &env.register(Registry, (&admin, &admin)));
+        let registrar = RegistrarClient::new(&env, &env.register(Registrar, ()));
+        let token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let tld = Bytes::from_slice(&env, b"stellar");
+        registrar.init(&registry.address, &tld, &admin, &token);
+        registry.create_tld(&admin, &tld, &registrar.address);
+        registry.set_controller(&registrar.address, &true);
+        Deployment {
+            env,
+            registry,
+            registrar,
+            token,
+            tld,
+            admin,
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.env.ledger().timestamp()
+    }
+
+    fn advance(&self, secs: u64) {
+        self.env.ledger().set_timestamp(self.now() + secs);
+    }
+
+    fn funded_account(&self) -> Address {
+        let account = Address::generate(&self.env);
+        StellarAssetClient::new(&self.env, &self.token)
+            .mint(&account, &1_000_000_000_000_000_000_000_000);
+        account
+    }
+
+    /// Commits, waits the minimum age, and registers `label` to `owner`.
+    fn register(&self, owner: &Address, label: &Bytes, duration_secs: u64) -> BytesN<32> {
+        let secret = Bytes::from_slice(&self.env, b"secret");
+        let commitment = self.registrar.make_commitment(
+            &self.tld,
+            label,
+            owner,
+            &secret,
+            owner,
+            &None,
+            &duration_secs,
+        );
+        self.registrar.commit(owner, &commitment, &label.len());
+        self.advance(COMMIT_MIN_AGE_SECS);
+        self.registrar.register(
+            owner,
+            &self.tld,
+            label,
+            owner,
+            &secret,
+            &None,
+            &duration_secs,
+            &None,
+            &None,
+            &None,
+        )
+    }
+}
+
+#[test]
+fn lifecycle_invariants_hold() {
+    let mut rng = cases::rng();
+    for _ in 0..cases::count(12) {
+        let d = Deployment::new();
+        let alice = d.funded_account();
+        let bob = d.funded_account();
+        let raw = cases::valid_label(&mut rng);
+        let label = Bytes::from_slice(&d.env, &raw);
+
+        // Registration: the node is the reference namehash, owned by the registrant, for
+        // exactly the requested term, and no longer available.
+        let duration = rng.gen_range(DAY..=5 * YEAR);
+        let node = d.register(&alice, &label, duration);
+        assert_eq!(
+            node.to_array(),
+            namehash(&[b"stellar", &raw]),
+            "label {raw:?}"
+        );
+        assert_eq!(d.registry.owner(&node), alice);
+        let mut expires = d.now() + duration;
+        assert_eq!(d.registry.expires(&node), expires);
+        assert!(!d.registrar.available(&d.tld, &label));
+
+        // Renewals, before expiry or during grace, extend from the later of expiry and now.
+        for _ in 0..rng.gen_range(0..4) {
+            let wait = rng.gen_range(0..expires - d.now() + GRACE_PERIOD_SECS);
+            d.advance(wait);
+            d.registrar.renew(&alice, &d.tld, &label);
+            expires = expires.max(d.now()) + RENEW_EXTENSION_SECS;
+            assert_eq!(d.registry.expires(&node), expires);
+            assert_eq!(d.registry.owner(&node), alice);
+        }
+
+        // Grace: expired but still held, so nobody else can take it.
+        d.advance(expires - d.now() + rng.gen_range(1..=GRACE_PERIOD_SECS));
+        assert!(!d.registrar.available(&d.tld, &label));
+        assert_eq!(d.registry.owner(&node), alice);
+
+        // Past grace the name is free again, and re-registering it to someone else yields
+        // the same node with a fresh owner and term.
+        d.advance(expires + GRACE_PERIOD_SECS - d.now() + rng.gen_range(1..=YEAR));
+        assert!(d.registrar.available(&d.tld, &label));
+        let duration = rng.gen_range(DAY..=5 * YEAR);
+        assert_eq!(d.register(&bob, &label, duration), node);
+        assert_eq!(d.registry.owner(&node), bob);
+        assert_eq!(d.registry.expires(&node), d.now() + duration);
+        assert!(!d.registrar.available(&d.tld, &label));
+    }
+}
+
+#[test]
+fn registry_grace_follows_registrar_params() {
+    let mut rng = cases::rng();
+    for _ in 0..cases::count(12) {
+        let d = Deployment::new();
+        let alice = d.funded_account();
+        let raw = cases::valid_label(&mut rng);
+        let label = Bytes::from_slice(&d.env, &raw);
+        let tld_node = BytesN::from_array(&d.env, &namehash(&[b"stellar"]));
+        assert_eq!(d.registry.grace_period(&tld_node), GRACE_PERIOD_SECS);
+
+        let grace = rng.gen_range(0..=GRACE_PERIOD_SECS);
+        let mut params = d.registrar.params();
+        params.grace_period_secs = grace;
+        d.registrar.set_params(&d.admin, &params);
+        assert_eq!(d.registry.grace_period(&tld_node), grace);
+
+        // The Registry stops reporting the owner exactly when the Registrar frees the name.
+        let node = d.register(&alice, &label, DAY);
+        let expires = d.registry.expires(&node);
+        d.advance(expires + grace - d.now());
+        assert!(!d.registrar.available(&d.tld, &label));
+        assert_eq!(d.registry.owner_checked(&node), Some(alice.clone()));
+        d.advance(1);
+        assert!(d.registrar.available(&d.tld, &label));
+        assert_eq!(d.registry.owner_checked(&node), None);
+    }
+}
@@ -14,6 +14,9 @@ const EVENTS_LIMIT = 50;
 const INITIAL_LEDGER_WINDOW = 2000;
 const MIN_START_LEDGER = 7;
 
+/** Last `seq` seen per contract, to spot events the stream skipped. */
+const lastSeq = new Map<string, number>();
+
 export async function startIndexer(): Promise<void> {
   const config = getConfig();
 
@@ -141,6 +144,8 @@ function normalizeEvent(raw: RpcEvent): NormalizedEvent | null {
     const timestamp = parseTimestamp(raw.ledgerClosedAt);
     const contractId = raw.contractId ? raw.contractId.toString() : "unknown";
     const serialized = serializeEvent(raw);
+    const seq =
+      typeof data.seq === "bigint" ? Number(data.seq) : typeof data.seq === "number" ? data.seq : undefined;
 
     return {
       contractId,
@@ -150,6 +155,7 @@ function normalizeEvent(raw: RpcEvent): NormalizedEvent | null {
       type: eventType,
       namehash,
       data,
+      seq,
       raw: serialized
     };
   } catch (error) {
@@ -358,6 +364,18 @@ export async function processNormalizedEvent(
 ): Promise<void> {
   const config = getConfig();
   const mutations = extractMutations(event, config.tld, config.registryId);
+  if (event.seq !== undefined) {
+    const previous = lastSeq.get(event.contractId);
+    if (previous !== undefined && event.seq > previous + 1) {
+      logger.warn(
+        { contractId: event.contractId, expected: previous + 1, seq: event.seq },
+        "missed events: sequence gap"
+      );
+    }
+    if (previous === undefined || event.seq > previous) {
+      lastSeq.set(event.contractId, event.seq);
+    }
+  }
   if (mutations.length === 0) {
     logger.debug({ eventType: event.type }, "no mutations extracted");
   }
@@ -466,6 +484,10 @@ async function applyMutation(client: PoolClient, mutation: Mutation) {
       );
       break;
     }
+    case "clearRecords": {
+      await client.query(`DELETE FROM records WHERE namehash = $1`, [mutation.namehash]);
+      break;
+    }
     case "registrarRegistration": {
       // When a name is registered via registrar, the registry contract ID should be set
       // from the config since we're tracking events from the configured registry
@@ -46,6 +46,8 @@ export interface NormalizedEvent {
   type: string;
   namehash: Buffer;
   data: Record<string, unknown>;
+  /** Registry and Registrar events number themselves per contract, starting at 1. */
+  seq?: number;
   cursor?: string;
   raw?: unknown;
 }
@@ -63,6 +65,7 @@ export type Mutation =
   | { kind: "setExpiry"; namehash: Buffer; expiresAt: number; contractId?: string }
   | { kind: "setRecord"; namehash: Buffer; key: Buffer; value: Buffer; contractId?: string }
   | { kind: "deleteRecord"; namehash: Buffer; key: Buffer; contractId?: string }
+  | { kind: "clearRecords"; namehash: Buffer; contractId?: string }
   | {
       kind: "registrarRegistration";
       namehash: Buffer;
@@ -89,6 +92,14 @@ export function extractMutations(event: NormalizedEvent, tld?: string, registryI
   }
 
   switch (type) {
+    case "tld_created": {
+      // Top-level nodes are minted by the registry admin; `fqdn` is just the label.
+      if (data.label !== undefined) {
+        const fqdn = coerceString(data.label, "label");
+        mutations.push({ kind: "ensureName", namehash, fqdn, contractId });
+      }
+      break;
+    }
     case "resolver_changed": {
       const resolver = coerceString(data.resolver, "resolver");
       mutations.push({ kind: "setResolver", namehash, resolver, contractId });
@@ -99,6 +110,13 @@ export function extractMutations(event: NormalizedEvent, tld?: string, registryI
       mutations.push({ kind: "setExpiry", namehash, expiresAt, contractId });
       break;
     }
+    case "expiry_changed": {
+      // Covers every expiry write; a cleared expiry means the name lapsed at this event.
+      const expiresAt =
+        data.new === undefined || data.new === null ? event.timestamp : coerceNumber(data.new, "new");
+      mutations.push({ kind: "setExpiry", namehash, expiresAt, contractId });
+      break;
+    }
     case "transfer": {
       const owner = coerceString(data.to ?? data.owner ?? data.new_owner, "owner");
       mutations.push({ kind: "setOwner", namehash, owner, source: "registry", contractId: isRegistryEvent ? contractId : undefined });
@@ -123,6 +141,109 @@ export function extractMutations(event: NormalizedEvent, tld?: string, registryI
       });
       break;
     }
+    case "coin_address_changed": {
+      const coinType = coerceNumber(data.coin_type ?? data.coinType, "coin_type");
+      const value = coerceBuffer(data.value, "value");
+      mutations.push({
+        kind: "setRecord",
+        namehash,
+        key: Buffer.from(`addr:${coinType}`, "utf8"),
+        value,
+        contractId
+      });
+      break;
+    }
+    case "contenthash_changed": {
+      const hash = coerceBuffer(data.hash, "hash");
+      const key = Buffer.from("contenthash", "utf8");
+      if (hash.length === 0) {
+        mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      } else {
+        mutations.push({ kind: "setRecord", namehash, key, value: hash, contractId });
+      }
+      break;
+    }
+    case "wildcard_changed": {
+      const key = Buffer.from("wildcard", "utf8");
+      if (data.addr === undefined || data.addr === null) {
+        mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      } else {
+        const value = Buffer.from(coerceString(data.addr, "addr"), "utf8");
+        mutations.push({ kind: "setRecord", namehash, key, value, contractId });
+      }
+      break;
+    }
+    case "muxed_changed": {
+      const key = Buffer.from("muxed", "utf8");
+      if (data.addr === undefined || data.addr === null) {
+        mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      } else {
+        // Stored as `<G...>` or `<G...>:<id>`; the ID is a u64, so keep it as a string.
+        const addr = coerceString(data.addr, "addr");
+        const id = data.id === undefined || data.id === null ? undefined : String(data.id);
+        const value = Buffer.from(id === undefined ? addr : `${addr}:${id}`, "utf8");
+        mutations.push({ kind: "setRecord", namehash, key, value, contractId });
+      }
+      break;
+    }
+    case "service_changed": {
+      const serviceKey = coerceString(data.service_key ?? data.serviceKey, "service_key");
+      const key = Buffer.from(`service:${serviceKey}`, "utf8");
+      if (data.contract === undefined || data.contract === null) {
+        mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      } else {
+        const value = Buffer.from(coerceString(data.contract, "contract"), "utf8");
+        mutations.push({ kind: "setRecord", namehash, key, value, contractId });
+      }
+      break;
+    }
+    case "metadata_changed": {
+      // The URI is the record; readers verify the fetched document against the event's `hash`.
+      const value = coerceBuffer(data.uri, "uri");
+      mutations.push({ kind: "setRecord", namehash, key: Buffer.from("metadata", "utf8"), value, contractId });
+      break;
+    }
+    case "pubkey_changed": {
+      const keyType = coerceNumber(data.key_type ?? data.keyType, "key_type");
+      const value = coerceBuffer(data.key, "key");
+      const key = Buffer.from(`pubkey:${keyType}`, "utf8");
+      if (value.length === 0) {
+        mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      } else {
+        mutations.push({ kind: "setRecord", namehash, key, value, contractId });
+      }
+      break;
+    }
+    case "typed_record_changed": {
+      const recordType = coerceString(data.record_type ?? data.recordType, "record_type");
+      const value = coerceBuffer(data.value, "value");
+      const key = Buffer.from(`typed:${recordType}`, "utf8");
+      if (value.length === 0) {
+        mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      } else {
+        mutations.push({ kind: "setRecord", namehash, key, value, contractId });
+      }
+      break;
+    }
+    case "record_version_changed": {
+      // A new record generation makes every previous resolver record unreachable.
+      mutations.push({ kind: "clearRecords", namehash, contractId });
+      break;
+    }
+    case "address_cleared": {
+      mutations.push({
+        kind: "deleteRecord",
+        namehash,
+        key: Buffer.from("addr", "utf8"),
+        contractId
+      });
+      break;
+    }
+    case "text_cleared": {
+      const key = coerceBuffer(data.key, "key");
+      mutations.push({ kind: "deleteRecord", namehash, key, contractId });
+      break;
+    }
     case "name_registered": {
       const owner = coerceString(data.owner, "owner");
       const expiresAt = coerceNumber(
@@ -130,10 +251,11 @@ export function extractMutations(event: NormalizedEvent, tld?: string, registryI
         "expires_at"
       );
       
-      // Extract label from event data and construct FQDN
-      if (data.label !== undefined && tld) {
+      // Extract label and TLD from event data and construct FQDN
+      const nameTld = data.tld !== undefined ? coerceString(data.tld, "tld") : tld;
+      if (data.label !== undefined && nameTld) {
         const label = coerceString(data.label, "label");
-        const fqdn = `${label}.${tld}`;
+        const fqdn = `${label}.${nameTld}`;
         mutations.push({
           kind: "ensureName",
           namehash,
@@ -160,8 +282,132 @@ export function extractMutations(event: NormalizedEvent, tld?: string, registryI
       mutations.push({ kind: "registrarRenewal", namehash, expiresAt, contractId });
       break;
     }
-    case "commit_made": {
-      // Registrar commitment events are not persisted.
+    case "name_released": {
+      // A released name is expired as of the release; it can be registered again at once.
+      mutations.push({ kind: "setExpiry", namehash, expiresAt: event.timestamp, contractId });
+      break;
+    }
+    case "expiry_warning":
+    case "released":
+    case "name_reaped": {
+      // Expiry warnings restate the stored expiry; the registrar's `name_released` carries
+      // the expiry of a release, and a reaped name had already lapsed.
+      break;
+    }
+    case "commit_made":
+    case "commit_cancelled":
+    case "commitments_swept":
+    case "commitment_consumed":
+    case "label_reserved":
+    case "label_released":
+    case "length_policy_changed":
+    case "tld_configured":
+    case "grace_period_changed":
+    case "council_changed":
+    case "action_proposed":
+    case "action_approved":
+    case "action_executed":
+    case "timelock_changed":
+    case "params_queued":
+    case "params_executed":
+    case "params_cancelled":
+    case "premium_auction_created":
+    case "premium_bid_placed":
+    case "premium_auction_settled": {
+      // Registrar commitment, policy, admin council, timelock, and premium auction events,
+      // and the Registry grace period they set, are not persisted; a premium sale arrives as
+      // `name_registered`.
+      break;
+    }
+    case "referral_accrued":
+    case "referral_claimed":
+    case "renewal_funds_deposited":
+    case "renewal_funds_withdrawn":
+    case "auto_renew_changed":
+    case "auto_renewed":
+    case "name_redeemed":
+    case "sponsor_allowance_changed": {
+      // Registrar accounting; the expiry change arrives as `name_renewed`.
+      break;
+    }
+    case "gift_registered":
+    case "gift_claimed":
+    case "gift_reclaimed":
+    case "voucher_minted":
+    case "voucher_revoked":
+    case "voucher_redeemed": {
+      // Gifts are held by the registrar until delivered as the registry's `transfer`; voucher
+      // discounts are already reflected in `name_registered.price`.
+      break;
+    }
+    case "record_verifier_changed":
+    case "record_schema_changed":
+    case "verified_record_changed":
+    case "records_frozen":
+    case "challenge_issued":
+    case "challenge_verified":
+    case "text_chunk_changed":
+    case "text_limits_changed": {
+      // Attestations are scoped to the owner they were made for, freezes and schema approvals
+      // only gate writes, and sign-in challenges are ephemeral; read them, and chunked text
+      // values, from the resolver.
+      break;
+    }
+    case "transfer_offered":
+    case "transfer_offer_cancelled": {
+      // Pending offers move nothing; an accepted one arrives as `transfer`.
+      break;
+    }
+    case "flags_changed":
+    case "lease_granted":
+    case "lease_ended":
+    case "manager_changed": {
+      // Flags, leases, and record managers only gate writes; ownership is unchanged.
+      break;
+    }
+    case "bridge_changed":
+    case "name_exported":
+    case "name_imported":
+    case "migration_opened":
+    case "migration_closed": {
+      // Bridging freezes a name in place; an import's new owner arrives as `transfer`, and a
+      // migrated expiry as `renew`.
+      break;
+    }
+    case "subnode_operator_changed":
+    case "subname_registered":
+    case "pricing_changed":
+    case "allowlist_changed": {
+      // Subregistrar sales; the subname's owner arrives as the registry's `transfer`.
+      break;
+    }
+    case "dns_claimed":
+    case "verifier_changed": {
+      // DNS claims; the new owner arrives as the registry's `transfer`.
+      break;
+    }
+    case "listed":
+    case "listing_cancelled":
+    case "offer_made":
+    case "offer_withdrawn":
+    case "sold": {
+      // Marketplace activity; the ownership change arrives as the registry's `transfer`.
+      break;
+    }
+    case "avatar_changed":
+    case "url_changed":
+    case "email_changed": {
+      // Typed profile fields live in the text store under the field name.
+      const field = type.slice(0, -"_changed".length);
+      const value = coerceBuffer(data[field], field);
+      mutations.push({ kind: "setRecord", namehash, key: Buffer.from(field, "utf8"), value, contractId });
+      break;
+    }
+    case "social_changed": {
+      const platform = coerceString(data.platform, "platform");
+      const value = coerceBuffer(data.handle, "handle");
+      const key = Buffer.from(`social.${platform}`, "utf8");
+      mutations.push({ kind: "setRecord", namehash, key, value, contractId });
       break;
     }
     case "text_changed": {
@@ -2,10 +2,12 @@
 /**
  * commitment-helper.js
  * Compute Registrar commitment as implemented on-chain:
- * sha256( label_bytes || xdr(Address(owner)) || secret_bytes )
+ * sha256( label_bytes || xdr(Address(owner)) || secret_bytes || xdr(Address(committer)) )
  *
  * Usage:
- *   node commitment-helper.js --label alice --owner G... --secret-hex <64 hex chars>
+ *   node commitment-helper.js --label alice --owner G... --secret-hex <64 hex chars> [--committer G...]
+ *
+ * The committer is the account that will call both `commit` and `register`; it defaults to the owner.
  *
  * Prints the 32-byte commitment as lowercase hex (no 0x prefix).
  */
@@ -41,6 +43,7 @@ function main() {
   const label = args.label || '';
   const owner = args.owner;
   const secretHex = args['secret-hex'];
+  const committer = args.committer || owner;
 
   if (!label && !labelHex) throw new Error('--label or --label-hex is required');
   if (!owner) throw new Error('--owner is required (G...)');
@@ -54,11 +57,10 @@ function main() {
     throw new Error('secret-hex must be 32 bytes (64 hex chars)');
   }
 
-  const scAddr = scAddressAccountFromG(owner);
   // Contract uses Address::to_xdr => ScVal(Address)
-  const scAddrXdr = xdr.ScVal.scvAddress(scAddr).toXDR(); // Buffer
+  const addressXdr = (gaddr) => xdr.ScVal.scvAddress(scAddressAccountFromG(gaddr)).toXDR(); // Buffer
 
-  const buf = Buffer.concat([labelBytes, scAddrXdr, secretBytes]);
+  const buf = Buffer.concat([labelBytes, addressXdr(owner), secretBytes, addressXdr(committer)]);
   const commitment = crypto.createHash('sha256').update(buf).digest('hex');
   process.stdout.write(commitment.toLowerCase());
 }
@@ -1,7 +1,7 @@
 #!/usr/bin/env bash
 # Automate local sandbox deployment for the registry, resolver, and registrar contracts.
 # Usage:
-#   ./scripts/deploy_local.sh [--identity alias] [--network sandbox] [--tld stellar] [--admin alias|G...]
+#   ./scripts/deploy_local.sh [--identity alias] [--network sandbox] [--tld stellar] [--admin alias|G...] [--token C...]
 
 set -euo pipefail
 
@@ -9,6 +9,7 @@ IDENTITY="${IDENTITY:-local-user}"
 NETWORK="${NETWORK:-sandbox}"
 TLD="${TLD:-stellar}"
 ADMIN_INPUT="${ADMIN:-}"
+TOKEN_ID="${TOKEN:-}"
 SKIP_BUILD=0
 
 usage() {
@@ -20,11 +21,12 @@ Options:
   --network <name>     Soroban network profile (default: sandbox)
   --tld <label>        Top-level domain to own (default: stellar)
   --admin <alias|G..>  Admin address or identity alias (default: identity address)
+  --token <C...>       Asset contract used for registrar fees (default: native XLM SAC)
   --skip-build         Skip rebuilding contract WASM artifacts
   -h, --help           Show this help and exit
 
 Environment overrides:
-  IDENTITY, NETWORK, TLD, ADMIN behave like the flags above.
+  IDENTITY, NETWORK, TLD, ADMIN, TOKEN behave like the flags above.
 EOF
 }
 
@@ -46,6 +48,10 @@ while [[ $# -gt 0 ]]; do
       ADMIN_INPUT="$2"
       shift 2
       ;;
+    --token)
+      TOKEN_ID="$2"
+      shift 2
+      ;;
     --skip-build)
       SKIP_BUILD=1
       shift
@@ -128,8 +134,14 @@ build_contracts() {
   just build-contract registrar >/dev/null
 }
 
+# Extra arguments are passed to the contract's constructor.
 deploy_contract() {
   local crate="$1"
+  shift
+  local constructor_args=()
+  if [[ $# -gt 0 ]]; then
+    constructor_args=(-- "$@")
+  fi
   local wasm="target/wasm32v1-none/release/${crate}.wasm"
   if [[ ! -f "$wasm" ]]; then
     echo "WASM artifact not found: $wasm" >&2
@@ -142,7 +154,8 @@ deploy_contract() {
   soroban contract deploy \
     --wasm "$wasm" \
     --network "$NETWORK" \
-    --source "$IDENTITY" | tee "$log" >&2
+    --source "$IDENTITY" \
+    ${constructor_args[@]+"${constructor_args[@]}"} | tee "$log" >&2
 
   local id
   id=$(grep -E '^[A-Z0-9]{56}$' "$log" | tail -n1 || true)
@@ -168,6 +181,33 @@ print_version() {
     version
 }
 
+create_tld() {
+  local registry_id="$1"
+  local admin_addr="$2"
+  local owner_id="$3"
+  local tld_hex
+  tld_hex=$(printf "%s" "$TLD" | xxd -p -c256)
+  log "Minting TLD \"$TLD\" to $owner_id..."
+  soroban contract invoke \
+    --id "$registry_id" \
+    --network "$NETWORK" \
+    --source "$IDENTITY" \
+    -- \
+    create_tld --admin "$admin_addr" --label "$tld_hex" --owner "$owner_id" >/dev/null
+}
+
+approve_controller() {
+  local registry_id="$1"
+  local controller_id="$2"
+  log "Approving registrar $controller_id as registry controller..."
+  soroban contract invoke \
+    --id "$registry_id" \
+    --network "$NETWORK" \
+    --source "$IDENTITY" \
+    -- \
+    set_controller --controller "$controller_id" --approved true >/dev/null
+}
+
 init_resolver() {
   local resolver_id="$1"
   local registry_id="$2"
@@ -184,6 +224,7 @@ init_registrar() {
   local registrar_id="$1"
   local registry_id="$2"
   local admin_addr="$3"
+  local token_id="$4"
   local tld_hex
   tld_hex=$(printf "%s" "$TLD" | xxd -p -c256)
   if [[ -z "$tld_hex" ]]; then
@@ -191,7 +232,7 @@ init_registrar() {
     exit 1
   fi
 
-  log "Initializing registrar with registry $registry_id, admin $admin_addr, TLD \"$TLD\"..."
+  log "Initializing registrar with registry $registry_id, admin $admin_addr, token $token_id, TLD \"$TLD\"..."
   soroban contract invoke \
     --id "$registrar_id" \
     --network "$NETWORK" \
@@ -200,7 +241,8 @@ init_registrar() {
     init \
       --registry "$registry_id" \
       --tld "$tld_hex" \
-      --admin "$admin_addr" >/dev/null
+      --admin "$admin_addr" \
+      --token "$token_id" >/dev/null
 }
 
 main() {
@@ -215,9 +257,13 @@ main() {
     ADMIN_ADDR=$(compute_address "$ADMIN_INPUT")
   fi
 
+  if [[ -z "$TOKEN_ID" ]]; then
+    TOKEN_ID=$(soroban contract id asset --asset native --network "$NETWORK")
+  fi
+
   build_contracts
 
-  REGISTRY_ID=$(deploy_contract registry)
+  REGISTRY_ID=$(deploy_contract registry --admin "$SIGNER_ADDR" --root_owner "$SIGNER_ADDR")
   log "  -> Registry ID: $REGISTRY_ID"
   print_version "$REGISTRY_ID" "registry"
   RESOLVER_ID=$(deploy_contract resolver)
@@ -227,7 +273,9 @@ main() {
 
   REGISTRAR_ID=$(deploy_contract registrar)
   log "  -> Registrar ID: $REGISTRAR_ID"
-  init_registrar "$REGISTRAR_ID" "$REGISTRY_ID" "$ADMIN_ADDR"
+  init_registrar "$REGISTRAR_ID" "$REGISTRY_ID" "$ADMIN_ADDR" "$TOKEN_ID"
+  create_tld "$REGISTRY_ID" "$SIGNER_ADDR" "$REGISTRAR_ID"
+  approve_controller "$REGISTRY_ID" "$REGISTRAR_ID"
   print_version "$REGISTRAR_ID" "registrar"
 
   update_envs
@@ -240,6 +288,7 @@ Deployment complete!
   REGISTRAR_ID=$REGISTRAR_ID
   Signer=$SIGNER_ADDR
   Admin=$ADMIN_ADDR
+  Token=$TOKEN_ID
   Network=$NETWORK
 EOF
 }
@@ -48,6 +48,10 @@ OWNER_ADDR="${OWNER_ADDR:-${ACCOUNT:-}}"
 # Prefer provided signer (identity alias or secret seed). Fall back to IDENTITY env,
 # and only then to ACCOUNT (address) if nothing else is provided.
 OWNER_SKEY="${OWNER_SKEY:-${IDENTITY:-${ACCOUNT:-}}}"
+# Registration term in seconds (default: one year).
+DURATION_SECS="${DURATION_SECS:-31536000}"
+# TLD to register under; must be one the Registrar issues (see its `tlds`).
+TLD="${TLD:-stellar}"
 
 # Track if --owner-addr was explicitly set via flag
 OWNER_ADDR_EXPLICIT=0
@@ -81,6 +85,8 @@ while [[ $# -gt 0 ]]; do
     --owner-skey)     OWNER_SKEY="$2"; shift 2;;
     --network-passphrase=*) NETWORK_PASSPHRASE="${1#*=}"; shift;;
     --network-passphrase) NETWORK_PASSPHRASE="$2"; shift 2;;
+    --duration-secs=*) DURATION_SECS="${1#*=}"; shift;;
+    --duration-secs)  DURATION_SECS="$2"; shift 2;;
   *) echo "Unknown flag: $1"; exit 1;;
   esac
 done
@@ -197,6 +203,7 @@ NET_FLAGS=( --network "${NETWORK}" --rpc-url "${RPC_URL}" )
 # - Bytes (label/secret) expect raw hex without 0x.
 LABEL_HEX="$(printf "%s" "${LABEL}" | xxd -p -c256)"
 LABEL_ARG="${LABEL_HEX}"
+TLD_HEX="$(printf "%s" "${TLD}" | xxd -p -c256)"
 SECRET_ARG="${SECRET_HEX}"
 
 # Compute commitment locally (matches on-chain compute_commitment)
@@ -205,7 +212,7 @@ if ! command -v node >/dev/null 2>&1; then
   echo "Error: node is required to run scripts/commitment-helper.js."
   exit 1
 fi
-COMMITMENT_HEX="$(node "${SCRIPT_DIR}/commitment-helper.js" --label-hex "${LABEL_ARG}" --owner "${OWNER_ADDR}" --secret-hex "${SECRET_ARG}")"
+COMMITMENT_HEX="$(node "${SCRIPT_DIR}/commitment-helper.js" --label-hex "${LABEL_ARG}" --owner "${OWNER_ADDR}" --secret-hex "${SECRET_ARG}" --committer "${OWNER_ADDR}")"
 if [[ -z "${COMMITMENT_HEX}" ]]; then
   echo "Error: commitment computation via commitment-helper.js failed."
   exit 1
@@ -312,9 +319,11 @@ REGISTER_ARGS=(
   -- \
   register \
   --caller "${OWNER_ADDR}"
+  --tld "${TLD_HEX}"
   --label "${LABEL_ARG}"
   --owner "${OWNER_ADDR}"
   --secret "${SECRET_ARG}"
+  --duration_secs "${DURATION_SECS}"
 )
 
 if [[ -n "${RESOLVER_ID}" ]]; then
@@ -322,6 +331,8 @@ if [[ -n "${RESOLVER_ID}" ]]; then
 else
   REGISTER_ARGS+=( --resolver=null: )
 fi
+REGISTER_ARGS+=( --referrer=null: )
+REGISTER_ARGS+=( --voucher=null: )
 
 set +e
 REGISTER_OUT="$(soroban contract invoke "${REGISTER_ARGS[@]}" 2>&1)"
@@ -361,7 +372,7 @@ AVAILABLE_RESULT="$(soroban contract invoke \
   "${NET_FLAGS[@]}" \
   --network-passphrase "${NETWORK_PASSPHRASE}" \
   -- \
-  available --label "${LABEL_HEX}" 2>&1)"
+  available --tld "${TLD_HEX}" --label "${LABEL_HEX}" 2>&1)"
 if echo "${AVAILABLE_RESULT}" | grep -q "false"; then
   echo "   ✅ Name is registered (available returned false)"
 elif echo "${AVAILABLE_RESULT}" | grep -q "true"; then
@@ -379,7 +390,7 @@ echo "   2. Check if name is available (should return false if registered):"
 echo "      soroban contract invoke --id ${REGISTRAR_ID} \\"
 echo "        ${NET_FLAGS[@]} \\"
 echo "        --network-passphrase '${NETWORK_PASSPHRASE}' \\"
-echo "        -- available --label ${LABEL_HEX}"
+echo "        -- available --tld ${TLD_HEX} --label ${LABEL_HEX}"
 echo ""
 if [[ -n "${REGISTRY_ID}" && -n "${NAMEHASH_HEX}" ]]; then
   echo "   3. Check Registry owner (requires namehash):"
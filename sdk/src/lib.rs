@@ -0,0 +1,532 @@
+//! Host-side client for the Stellar Name Service contracts.
+//!
+//! [`NameService`] wraps the Registry, Resolver, and Registrar behind typed calls such as
+//! [`NameService::resolve`], [`NameService::reverse`], and [`NameService::register_flow`], so
+//! integrators never assemble invocation arguments by hand. Talking to the network is left to
+//! a [`Transport`]: an implementation forwards each call to Soroban RPC (simulating reads,
+//! signing and submitting writes) with whatever RPC client and signer the application already
+//! uses. Arguments and results cross it as XDR [`ScVal`]s.
+
+use std::fmt;
+
+use registrar::CommitmentStatus;
+use soroban_sdk::xdr::{ScAddress, ScVal};
+use soroban_sdk::{Address, Bytes, BytesN, Env, IntoVal, TryFromVal, Val, Vec};
+
+pub use soroban_sdk::xdr;
+
+/// Longest label the Registry accepts, in bytes.
+const MAX_LABEL_LENGTH: usize = 63;
+/// Longest dotted name the Registry accepts, in bytes.
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Network access for [`NameService`].
+pub trait Transport {
+    type Error;
+
+    /// Simulates `function` on `contract` and returns its result without submitting anything.
+    fn simulate(
+        &self,
+        contract: &ScAddress,
+        function: &str,
+        args: &[ScVal],
+    ) -> Result<ScVal, Self::Error>;
+
+    /// Submits `function` on `contract` as `source`, signing whatever authorization `source`
+    /// must give, waits for the transaction to land, and returns its result.
+    fn invoke(
+        &self,
+        source: &ScAddress,
+        contract: &ScAddress,
+        function: &str,
+        args: &[ScVal],
+    ) -> Result<ScVal, Self::Error>;
+
+    /// Returns once a ledger closed at or after `timestamp` (Unix seconds).
+    fn wait_until(&self, timestamp: u64) -> Result<(), Self::Error>;
+}
+
+/// Failure of a [`NameService`] call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error<E> {
+    /// The transport failed, including when a contract call aborted.
+    Transport(E),
+    /// The name is empty, over 255 bytes, or has an empty or overlong label.
+    InvalidName,
+    /// The name has no address, or the address has no primary name.
+    NotFound,
+    /// The commitment vanished or expired before it could be revealed.
+    CommitmentLost,
+    /// A contract returned a value of an unexpected type.
+    UnexpectedValue,
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(err) => write!(f, "transport error: {err:?}"),
+            Error::InvalidName => f.write_str("invalid name"),
+            Error::NotFound => f.write_str("not found"),
+            Error::CommitmentLost => f.write_str("commitment expired before reveal"),
+            Error::UnexpectedValue => f.write_str("unexpected contract result"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+/// Addresses of one deployment, as returned by the Deployer's `deploy`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contracts {
+    pub registry: ScAddress,
+    pub resolver: ScAddress,
+    pub registrar: ScAddress,
+}
+
+/// Inputs of [`NameService::register_flow`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Registration {
+    /// Account that commits, reveals, and pays.
+    pub caller: ScAddress,
+    /// Name to register, `label.tld`.
+    pub name: String,
+    pub owner: ScAddress,
+    /// Random bytes hiding the label until the reveal; never reuse one.
+    pub secret: std::vec::Vec<u8>,
+    pub resolver: Option<ScAddress>,
+    pub duration_secs: u64,
+    pub referrer: Option<ScAddress>,
+    pub voucher: Option<std::vec::Vec<u8>>,
+    /// Account whose allowance pays when `caller` registers for itself; see the Registrar's
+    /// `sponsor_allowance`.
+    pub sponsor: Option<ScAddress>,
+}
+
+/// Typed client for one deployment.
+pub struct NameService<T> {
+    transport: T,
+    contracts: Contracts,
+}
+
+impl<T: Transport> NameService<T> {
+    pub fn new(transport: T, contracts: Contracts) -> Self {
+        Self {
+            transport,
+            contracts,
+        }
+    }
+
+    pub fn contracts(&self) -> &Contracts {
+        &self.contracts
+    }
+
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Namehash of a dotted name; see [`namehash`].
+    pub fn namehash(&self, name: &str) -> Result<[u8; 32], Error<T::Error>> {
+        namehash(name).ok_or(Error::InvalidName)
+    }
+
+    /// Address `name` resolves to, read through the Resolver's `resolve` gateway. Fails with
+    /// `NotFound` if the name has no live resolver or no address record.
+    pub fn resolve(&self, name: &str) -> Result<ScAddress, Error<T::Error>> {
+        let env = Env::default();
+        let labels = name_labels(&env, name)?;
+        let addr: Option<Address> = self.simulate(
+            &env,
+            &self.contracts.resolver,
+            "resolve",
+            (labels,).into_val(&env),
+        )?;
+        addr.map(|addr| ScAddress::from(&addr))
+            .ok_or(Error::NotFound)
+    }
+
+    /// Primary name of `address`, such as `alice.stellar`. Fails with `NotFound` if none is
+    /// set or its forward record no longer points back at `address`.
+    pub fn reverse(&self, address: &ScAddress) -> Result<String, Error<T::Error>> {
+        let env = Env::default();
+        let addr = to_address(&env, address)?;
+        let namehash: Option<BytesN<32>> = self.simulate(
+            &env,
+            &self.contracts.resolver,
+            "reverse",
+            (addr,).into_val(&env),
+        )?;
+        let namehash = namehash.ok_or(Error::NotFound)?;
+        let name: Bytes = self.simulate(
+            &env,
+            &self.contracts.registry,
+            "name_of",
+            (namehash,).into_val(&env),
+        )?;
+        let mut buf = vec![0u8; name.len() as usize];
+        name.copy_into_slice(&mut buf);
+        String::from_utf8(buf).map_err(|_| Error::UnexpectedValue)
+    }
+
+    /// Registers `registration.name` end to end: asks the Registrar for a version 2
+    /// commitment, commits it, waits out the minimum commitment age, and reveals with
+    /// `register`. Returns the new name's namehash. The label is lowercased; an
+    /// internationalized label must be given in its `xn--` form.
+    pub fn register_flow(&self, registration: &Registration) -> Result<[u8; 32], Error<T::Error>> {
+        let env = Env::default();
+        let (label, tld) = split_name(&env, &registration.name)?;
+        let caller = to_address(&env, &registration.caller)?;
+        let owner = to_address(&env, &registration.owner)?;
+        let resolver = registration
+            .resolver
+            .as_ref()
+            .map(|resolver| to_address(&env, resolver))
+            .transpose()?;
+        let referrer = registration
+            .referrer
+            .as_ref()
+            .map(|referrer| to_address(&env, referrer))
+            .transpose()?;
+        let secret = Bytes::from_slice(&env, &registration.secret);
+        let voucher = registration
+            .voucher
+            .as_ref()
+            .map(|voucher| Bytes::from_slice(&env, voucher));
+        let sponsor = registration
+            .sponsor
+            .as_ref()
+            .map(|sponsor| to_address(&env, sponsor))
+            .transpose()?;
+        let registrar = &self.contracts.registrar;
+
+        let commitment: BytesN<32> = self.simulate(
+            &env,
+            registrar,
+            "make_commitment",
+            (
+                tld.clone(),
+                label.clone(),
+                owner.clone(),
+                secret.clone(),
+                caller.clone(),
+                resolver.clone(),
+                registration.duration_secs,
+            )
+                .into_val(&env),
+        )?;
+        let () = self.invoke(
+            &env,
+            &registration.caller,
+            registrar,
+            "commit",
+            (caller.clone(), commitment.clone(), label.len()).into_val(&env),
+        )?;
+        loop {
+            let status: CommitmentStatus = self.simulate(
+                &env,
+                registrar,
+                "commitment_status",
+                (commitment.clone(),).into_val(&env),
+            )?;
+            match status {
+                CommitmentStatus::TooFresh(ready_at) => self
+                    .transport
+                    .wait_until(ready_at)
+                    .map_err(Error::Transport)?,
+                CommitmentStatus::Ready(_) => break,
+                CommitmentStatus::NotFound | CommitmentStatus::Expired => {
+                    return Err(Error::CommitmentLost)
+                }
+            }
+        }
+        let namehash: BytesN<32> = self.invoke(
+            &env,
+            &registration.caller,
+            registrar,
+            "register",
+            (
+                caller,
+                tld,
+                label,
+                owner,
+                secret,
+                resolver,
+                registration.duration_secs,
+                referrer,
+                voucher,
+                sponsor,
+            )
+                .into_val(&env),
+        )?;
+        Ok(namehash.to_array())
+    }
+
+    /// Extends the registration of `label.tld` by the Registrar's renewal period, paid by
+    /// `caller`.
+    pub fn renew(&self, caller: &ScAddress, name: &str) -> Result<(), Error<T::Error>> {
+        let env = Env::default();
+        let (label, tld) = split_name(&env, name)?;
+        let caller_address = to_address(&env, caller)?;
+        self.invoke(
+            &env,
+            caller,
+            &self.contracts.registrar,
+            "renew",
+            (caller_address, tld, label).into_val(&env),
+        )
+    }
+
+    /// Sets the text record `key` of `name` on the bundled Resolver; `caller` must own the
+    /// name or operate for its owner.
+    pub fn set_text(
+        &self,
+        caller: &ScAddress,
+        name: &str,
+        key: &str,
+        value: &str,
+    ) -> Result<(), Error<T::Error>> {
+        let env = Env::default();
+        let namehash = BytesN::from_array(&env, &self.namehash(name)?);
+        let caller_address = to_address(&env, caller)?;
+        self.invoke(
+            &env,
+            caller,
+            &self.contracts.resolver,
+            "set_text",
+            (
+                caller_address,
+                namehash,
+                Bytes::from_slice(&env, key.as_bytes()),
+                Bytes::from_slice(&env, value.as_bytes()),
+            )
+                .into_val(&env),
+        )
+    }
+
+    fn simulate<R: TryFromVal<Env, Val>>(
+        &self,
+        env: &Env,
+        contract: &ScAddress,
+        function: &str,
+        args: Vec<Val>,
+    ) -> Result<R, Error<T::Error>> {
+        let result = self
+            .transport
+            .simulate(contract, function, &to_sc_vals(env, args)?)
+            .map_err(Error::Transport)?;
+        from_sc_val(env, &result)
+    }
+
+    fn invoke<R: TryFromVal<Env, Val>>(
+        &self,
+        env: &Env,
+        source: &ScAddress,
+        contract: &ScAddress,
+        function: &str,
+        args: Vec<Val>,
+    ) -> Result<R, Error<T::Error>> {
+        let result = self
+            .transport
+            .invoke(source, contract, function, &to_sc_vals(env, args)?)
+            .map_err(Error::Transport)?;
+        from_sc_val(env, &result)
+    }
+}
+
+/// Namehash of a dotted name, computed locally as the Registry's `namehash_str` does, or
+/// `None` if the name is empty, over 255 bytes, or has an empty or overlong label.
+pub fn namehash(name: &str) -> Option<[u8; 32]> {
+    let env = Env::default();
+    let labels = name_labels::<()>(&env, name).ok()?;
+    Some(name_service_common::namehash(&env, &labels).to_array())
+}
+
+/// Labels of a dotted name from the TLD down, ASCII-lowercased and checked against the
+/// Registry's length limits.
+fn name_labels<E>(env: &Env, name: &str) -> Result<Vec<Bytes>, Error<E>> {
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(Error::InvalidName);
+    }
+    let mut labels = Vec::new(env);
+    for label in name.rsplit('.') {
+        labels.push_back(label_bytes(env, label)?);
+    }
+    Ok(labels)
+}
+
+/// `(label, tld)` of `label.tld`, split at the first dot as the Registrar's `register_str`
+/// does.
+fn split_name<E>(env: &Env, name: &str) -> Result<(Bytes, Bytes), Error<E>> {
+    if name.len() > MAX_NAME_LENGTH {
+        return Err(Error::InvalidName);
+    }
+    let (label, tld) = name.split_once('.').ok_or(Error::InvalidName)?;
+    Ok((label_bytes(env, label)?, label_bytes(env, tld)?))
+}
+
+fn label_bytes<E>(env: &Env, label: &str) -> Result<Bytes, Error<E>> {
+    if label.is_empty() || label.len() > MAX_LABEL_LENGTH {
+        return Err(Error::InvalidName);
+    }
+    Ok(Bytes::from_slice(
+        env,
+        label.to_ascii_lowercase().as_bytes(),
+    ))
+}
+
+fn to_address<E>(env: &Env, address: &ScAddress) -> Result<Address, Error<E>> {
+    Address::try_from_val(env, address).map_err(|_| Error::UnexpectedValue)
+}
+
+fn to_sc_vals<E>(env: &Env, args: Vec<Val>) -> Result<std::vec::Vec<ScVal>, Error<E>> {
+    args.iter()
+        .map(|arg| ScVal::try_from_val(env, &arg).map_err(|_| Error::UnexpectedValue))
+        .collect()
+}
+
+fn from_sc_val<R: TryFromVal<Env, Val>, E>(env: &Env, value: &ScVal) -> Result<R, Error<E>> {
+    let value = Val::try_from_val(env, value).map_err(|_| Error::UnexpectedValue)?;
+    R::try_from_val(env, &value).map_err(|_| Error::UnexpectedValue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use registrar::{Registrar, RegistrarClient};
+    use registry::{Registry, RegistryClient};
+    use resolver::{Resolver, ResolverClient};
+    use soroban_sdk::testutils::{Address as _, Ledger as _};
+    use soroban_sdk::token::StellarAssetClient;
+    use soroban_sdk::Symbol;
+    use std::convert::Infallible;
+
+    /// Runs every call directly against the contracts in `env`, with all auths mocked.
+    struct LocalTransport {
+        env: Env,
+    }
+
+    impl LocalTransport {
+        fn call(&self, contract: &ScAddress, function: &str, args: &[ScVal]) -> ScVal {
+            let env = &self.env;
+            let mut vals = Vec::new(env);
+            for arg in args {
+                vals.push_back(Val::try_from_val(env, arg).unwrap());
+            }
+            let contract = Address::try_from_val(env, contract).unwrap();
+            let result: Val = env.invoke_contract(&contract, &Symbol::new(env, function), vals);
+            ScVal::try_from_val(env, &result).unwrap()
+        }
+    }
+
+    impl Transport for LocalTransport {
+        type Error = Infallible;
+
+        fn simulate(
+            &self,
+            contract: &ScAddress,
+            function: &str,
+            args: &[ScVal],
+        ) -> Result<ScVal, Infallible> {
+            Ok(self.call(contract, function, args))
+        }
+
+        fn invoke(
+            &self,
+            _source: &ScAddress,
+            contract: &ScAddress,
+            function: &str,
+            args: &[ScVal],
+        ) -> Result<ScVal, Infallible> {
+            Ok(self.call(contract, function, args))
+        }
+
+        fn wait_until(&self, timestamp: u64) -> Result<(), Infallible> {
+            if self.env.ledger().timestamp() < timestamp {
+                self.env.ledger().set_timestamp(timestamp);
+            }
+            Ok(())
+        }
+    }
+
+    /// A wired Registry, Resolver, and Registrar serving `stellar`, as the Deployer sets up.
+    fn deployment(env: &Env) -> (Address, Address, Address, Address) {
+        env.mock_all_auths();
+        let admin = Address::generate(env);
+        let registry = env.register(Registry, (&admin, &admin));
+        let resolver = env.register(Resolver, ());
+        let registrar = env.register(Registrar, ());
+        let token = env
+            .register_stellar_asset_contract_v2(admin.clone())
+            .address();
+        let tld = Bytes::from_slice(env, b"stellar");
+        let registry_client = RegistryClient::new(env, &registry);
+        ResolverClient::new(env, &resolver).init(&registry);
+        RegistrarClient::new(env, &registrar).init(&registry, &tld, &admin, &token);
+        registry_client.create_tld(&admin, &tld, &registrar);
+        registry_client.set_controller(&registrar, &true);
+        (registry, resolver, registrar, token)
+    }
+
+    #[test]
+    fn register_flow_then_resolve_and_reverse() {
+        let env = Env::default();
+        env.ledger().set_timestamp(1_000);
+        let (registry, resolver, registrar, token) = deployment(&env);
+        let alice = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&alice, &1_000_000_000_000);
+        let sdk = NameService::new(
+            LocalTransport { env: env.clone() },
+            Contracts {
+                registry: ScAddress::from(&registry),
+                resolver: ScAddress::from(&resolver),
+                registrar: ScAddress::from(&registrar),
+            },
+        );
+
+        let namehash = sdk
+            .register_flow(&Registration {
+                caller: ScAddress::from(&alice),
+                name: "Alice.stellar".into(),
+                owner: ScAddress::from(&alice),
+                secret: b"secret".to_vec(),
+                resolver: Some(ScAddress::from(&resolver)),
+                duration_secs: 365 * 24 * 60 * 60,
+                referrer: None,
+                voucher: None,
+                sponsor: None,
+            })
+            .unwrap();
+        assert_eq!(sdk.namehash("alice.stellar").unwrap(), namehash);
+        assert!(env.ledger().timestamp() > 1_000);
+        let node = BytesN::from_array(&env, &namehash);
+        assert_eq!(RegistryClient::new(&env, &registry).owner(&node), alice);
+
+        assert_eq!(sdk.resolve("alice.stellar"), Err(Error::NotFound));
+        let resolver_client = ResolverClient::new(&env, &resolver);
+        resolver_client.set_addr(&alice, &node, &alice);
+        resolver_client.set_reverse(&alice, &alice, &node);
+        assert_eq!(
+            sdk.resolve("ALICE.stellar").unwrap(),
+            ScAddress::from(&alice)
+        );
+        assert_eq!(
+            sdk.reverse(&ScAddress::from(&alice)).unwrap(),
+            "alice.stellar"
+        );
+        assert_eq!(
+            sdk.reverse(&ScAddress::from(&Address::generate(&env))),
+            Err(Error::NotFound)
+        );
+        let alice_sc = ScAddress::from(&alice);
+        sdk.set_text(&alice_sc, "alice.stellar", "url", "https://alice.example")
+            .unwrap();
+        assert_eq!(
+            resolver_client.text(&node, &Bytes::from_slice(&env, b"url")),
+            Some(Bytes::from_slice(&env, b"https://alice.example"))
+        );
+        let registry_client = RegistryClient::new(&env, &registry);
+        let expires = registry_client.expires(&node);
+        sdk.renew(&alice_sc, "alice.stellar").unwrap();
+        assert!(registry_client.expires(&node) > expires);
+        assert_eq!(sdk.namehash("alice..stellar"), Err(Error::InvalidName));
+    }
+}